@@ -14,7 +14,7 @@
 //! To add a new target system, the following blocks need to exist:
 //!
 //! - `SHARED_LIB_DIRS`: This should contain directories relative to `ONEAPI_ROOT` environment variable that contain the
-//!     shared libraries. `main()` will check if any of these directories are not on the `PATH` environment variable and crash if not.
+//!   shared libraries. `main()` will check if any of these directories are not on the `PATH` environment variable and crash if not.
 //! - `LINK_DIRS`: The directory where `.lib` files are. `main()` will output a `cargo:rustc-link-search` for each of these.
 //!
 //! # Supported systems
@@ -25,6 +25,14 @@
 //! - [x] Linux 64 bit
 //! - [x] MacOS 64 bit
 //!
+//! # Other BLAS libraries
+//!
+//! - `openblas`: dynamically link to OpenBLAS. Set `OPENBLAS_LIB_DIR` if it isn't installed in a
+//!   directory the linker searches by default.
+//! - `accelerate`: link to Apple's Accelerate framework (macOS only).
+//!
+//! Only one BLAS library can be selected at a time.
+//!
 //! This script also creates a "nightly" feature if the crate is compiled on a nightly branch
 use rustc_version::{version_meta, Channel};
 
@@ -34,6 +42,8 @@ pub const DYNAMIC: bool = cfg!(feature = "mkl-dynamic-seq") || cfg!(feature = "m
 pub const SEQUENTIAL: bool = cfg!(feature = "mkl-static-seq") || cfg!(feature = "mkl-dynamic-seq");
 pub const THREADED: bool = cfg!(feature = "mkl-static-iomp") || cfg!(feature = "mkl-dynamic-iomp");
 pub const MKL: bool = (STATIC || DYNAMIC) && (SEQUENTIAL || THREADED);
+pub const OPENBLAS: bool = cfg!(feature = "openblas");
+pub const ACCELERATE: bool = cfg!(feature = "accelerate");
 
 pub const LINK_TYPE: &str = if STATIC { "static" } else { "dylib" };
 pub const LIB_POSTFIX: &str = if cfg!(windows) && DYNAMIC { "_dll" } else { "" };
@@ -123,11 +133,42 @@ pub enum BuildError {
     OneAPINotADir(std::path::PathBuf),
     PathNotFound(std::env::VarError),
     AddSharedLibDirToPath(String),
+    MultipleBlasLibraries,
+    UnsupportedTargetOs(String),
 }
 
 fn main() -> Result<(), BuildError> {
     println!("cargo:rerun-if-changed=build.rs");
 
+    let blas_features = [
+        cfg!(feature = "mkl-static-iomp"),
+        cfg!(feature = "mkl-static-seq"),
+        cfg!(feature = "mkl-dynamic-iomp"),
+        cfg!(feature = "mkl-dynamic-seq"),
+        OPENBLAS,
+        ACCELERATE,
+    ];
+    if blas_features.iter().filter(|&&f| f).count() > 1 {
+        return Err(BuildError::MultipleBlasLibraries);
+    }
+
+    if OPENBLAS {
+        println!("cargo:rerun-if-env-changed=OPENBLAS_LIB_DIR");
+        if let Ok(lib_dir) = std::env::var("OPENBLAS_LIB_DIR") {
+            println!("cargo:rustc-link-search={lib_dir}");
+        }
+        println!("cargo:rustc-link-lib=dylib=openblas");
+    }
+
+    if ACCELERATE {
+        // NOTE: `cfg!(target_os)` is the os of the build script, not of the crate
+        let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+        if target_os != "macos" {
+            return Err(BuildError::UnsupportedTargetOs(target_os));
+        }
+        println!("cargo:rustc-link-lib=framework=Accelerate");
+    }
+
     #[cfg(any(
         feature = "mkl-static-iomp",
         feature = "mkl-static-seq",
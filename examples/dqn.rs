@@ -1,4 +1,4 @@
-//! Implements Deep Q Learning on random data.
+//! Implements Deep Q Learning on random transitions sampled from a replay buffer.
 
 use dfdx::prelude::*;
 use rand::{rngs::StdRng, Rng, SeedableRng};
@@ -17,11 +17,17 @@ type QNetwork = (
 fn main() {
     let mut rng = StdRng::seed_from_u64(0);
 
-    let state: Tensor2D<64, STATE_SIZE> = Tensor2D::randn(&mut rng);
-    let action: [usize; 64] = [(); 64].map(|_| rng.gen_range(0..ACTION_SIZE));
-    let reward: Tensor1D<64> = Tensor1D::randn(&mut rng);
-    let done: Tensor1D<64> = Tensor1D::zeros();
-    let next_state: Tensor2D<64, STATE_SIZE> = Tensor2D::randn(&mut rng);
+    // fill a replay buffer with random transitions
+    let mut buffer: ReplayBuffer<Tensor1D<STATE_SIZE>> = ReplayBuffer::new(1024);
+    for _ in 0..256 {
+        buffer.push(Transition {
+            state: Tensor1D::randn(&mut rng),
+            action: rng.gen_range(0..ACTION_SIZE),
+            reward: rng.gen_range(-1.0..1.0),
+            next_state: Tensor1D::randn(&mut rng),
+            done: rng.gen_bool(0.1),
+        });
+    }
 
     // initiliaze model - all weights are 0s
     let mut q_net: QNetwork = Default::default();
@@ -32,22 +38,24 @@ fn main() {
     let mut sgd = Sgd::new(SgdConfig {
         lr: 1e-1,
         momentum: Some(Momentum::Nesterov(0.9)),
+        grad_clip: None,
     });
 
     // run through training data
     for _i_epoch in 0..15 {
         let start = Instant::now();
+        let batch: ReplayBatch<Tensor2D<64, STATE_SIZE>, 64> = buffer.sample(&mut rng);
 
         // targ_q = R + discount * max(Q(S'))
         // curr_q = Q(S)[A]
         // loss = mse(curr_q, targ_q)
-        let next_q_values: Tensor2D<64, ACTION_SIZE> = target_q_net.forward(next_state.clone());
+        let next_q_values: Tensor2D<64, ACTION_SIZE> = target_q_net.forward(batch.next_states);
         let max_next_q: Tensor1D<64> = next_q_values.max_axis::<-1>();
-        let target_q = 0.99 * mul(max_next_q, &(1.0 - done.clone())) + &reward;
+        let target_q = 0.99 * mul(max_next_q, &(1.0 - batch.dones)) + &batch.rewards;
 
         // forward through model, computing gradients
-        let q_values = q_net.forward(state.trace());
-        let action_qs: Tensor1D<64, OwnedTape> = q_values.select(&action);
+        let q_values = q_net.forward(batch.states.trace());
+        let action_qs: Tensor1D<64, OwnedTape> = q_values.select(&batch.actions);
 
         let loss = mse_loss(action_qs, &target_q);
         let loss_v = *loss.data();
@@ -1,7 +1,8 @@
 use dfdx::prelude::*;
 use indicatif::ProgressBar;
-use mnist::*;
+use mnist::{Mnist, MnistBuilder};
 use rand::prelude::{SeedableRng, StdRng};
+use std::sync::Arc;
 use std::time::Instant;
 
 struct MnistDataset {
@@ -17,25 +18,21 @@ impl MnistDataset {
             lbl: mnist.trn_lbl.iter().map(|&v| v as usize).collect(),
         }
     }
+}
+
+impl Dataset for MnistDataset {
+    type Input = Tensor1D<784>;
+    type Label = usize;
 
     fn len(&self) -> usize {
         self.lbl.len()
     }
 
-    pub fn get_batch<const B: usize>(
-        &self,
-        idxs: [usize; B],
-    ) -> (Tensor2D<B, 784>, Tensor2D<B, 10>) {
-        let mut img = Tensor2D::zeros();
-        let mut lbl = Tensor2D::zeros();
-        let img_data = img.mut_data();
-        let lbl_data = lbl.mut_data();
-        for (batch_i, &img_idx) in idxs.iter().enumerate() {
-            let start = 784 * img_idx;
-            img_data[batch_i].copy_from_slice(&self.img[start..start + 784]);
-            lbl_data[batch_i][self.lbl[img_idx]] = 1.0;
-        }
-        (img, lbl)
+    fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+        let mut img = Tensor1D::zeros();
+        img.mut_data()
+            .copy_from_slice(&self.img[784 * i..784 * (i + 1)]);
+        (img, self.lbl[i])
     }
 }
 
@@ -62,19 +59,21 @@ fn main() {
     model.reset_params(&mut rng);
     let mut opt: Adam<Mlp> = Default::default();
 
-    let dataset = MnistDataset::train(&mnist_path);
+    let dataset = Arc::new(MnistDataset::train(&mnist_path));
     println!("Found {:?} training images", dataset.len());
 
     for i_epoch in 0..10 {
         let mut total_epoch_loss = 0.0;
         let mut num_batches = 0;
+        let mut accuracy = Accuracy::default();
         let start = Instant::now();
         let bar = ProgressBar::new(dataset.len() as u64);
-        for (img, lbl) in SubsetIterator::<BATCH_SIZE>::shuffled(dataset.len(), &mut rng)
-            .map(|i| dataset.get_batch(i))
-        {
+        let loader = DataLoader::<_, BATCH_SIZE>::shuffled(dataset.clone(), &mut rng);
+        for (img, lbl) in loader.prefetch(2, 8) {
+            let targ = one_hot_encode(&lbl);
             let logits = model.forward(img.traced());
-            let loss = cross_entropy_with_logits_loss(logits, &lbl);
+            accuracy.update(&logits, &targ);
+            let loss = cross_entropy_with_logits_loss(logits, &targ);
 
             total_epoch_loss += loss.data();
             num_batches += 1;
@@ -87,10 +86,11 @@ fn main() {
         bar.finish_and_clear();
 
         println!(
-            "Epoch {i_epoch} in {:?} ({:.3} batches/s): avg sample loss {:.3}",
+            "Epoch {i_epoch} in {:?} ({:.3} batches/s): avg sample loss {:.3}, accuracy {:.3}",
             dur,
             num_batches as f32 / dur.as_secs_f32(),
             BATCH_SIZE as f32 * total_epoch_loss / num_batches as f32,
+            accuracy.compute(),
         );
     }
 
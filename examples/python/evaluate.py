@@ -0,0 +1,17 @@
+"""Evaluates the classifier saved by `cargo run --bin train` on random points."""
+import sys
+
+import numpy as np
+from dfdx_example import CircleClassifier
+
+model = CircleClassifier.load(sys.argv[1] if len(sys.argv) > 1 else "circle.npz")
+print(f"sample shape: {model.sample_shape}, output shape: {model.output_shape}")
+
+points = np.random.uniform(-1.0, 1.0, size=(10_000, 2)).astype(np.float32)
+labels = (points**2).sum(axis=1) < 0.36
+
+# any number of points can be passed at once, they are run through the model 256 at a time
+logits = model(points)
+assert logits.shape == (10_000, 1)
+accuracy = ((logits[:, 0] > 0.0) == labels).mean()
+print(f"accuracy: {accuracy:.3f}")
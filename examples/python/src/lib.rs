@@ -0,0 +1,27 @@
+//! Python bindings for a classifier that is trained in rust, and evaluated on numpy arrays in python.
+//!
+//! Train the model & save it to `circle.npz`, then build the extension module with
+//! [maturin](https://www.maturin.rs) and run the python script:
+//! ```sh
+//! cd examples/python
+//! cargo run --release --bin train
+//! pip install maturin numpy
+//! maturin develop --release
+//! python evaluate.py circle.npz
+//! ```
+
+use dfdx::prelude::*;
+use pyo3::prelude::*;
+
+/// A 2 layer network that predicts whether a point is inside of a circle.
+pub type Mlp = ((Linear<2, 16>, ReLU), (Linear<16, 16>, ReLU), Linear<16, 1>);
+
+dfdx::python_model!(
+    /// Predicts the logit of whether a 2d point is inside of a circle with radius `0.6`.
+    pub struct CircleClassifier(Mlp), sample = Tensor1D<2>, batch = 256
+);
+
+#[pymodule]
+fn dfdx_example(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<CircleClassifier>()
+}
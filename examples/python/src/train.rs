@@ -0,0 +1,37 @@
+//! Trains [dfdx_example::Mlp] on random points and saves it to `circle.npz`.
+
+use dfdx::prelude::*;
+use dfdx_example::Mlp;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const BATCH_SIZE: usize = 64;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut mlp: Mlp = Default::default();
+    mlp.reset_params(&mut rng);
+    let mut opt: Adam<Mlp> = Default::default();
+
+    for i in 0..2000 {
+        let mut x: Tensor2D<BATCH_SIZE, 2> = Tensor2D::zeros();
+        let mut y: Tensor2D<BATCH_SIZE, 1> = Tensor2D::zeros();
+        for (x, y) in x.mut_data().iter_mut().zip(y.mut_data().iter_mut()) {
+            *x = [rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)];
+            y[0] = if x[0] * x[0] + x[1] * x[1] < 0.36 {
+                1.0
+            } else {
+                0.0
+            };
+        }
+
+        let logits = mlp.forward(x.trace());
+        let loss = binary_cross_entropy_with_logits_loss(logits, &y);
+        if i % 200 == 0 {
+            println!("step {i}: loss={:.3}", loss.data());
+        }
+        opt.update(&mut mlp, loss.backward())
+            .expect("unused params");
+    }
+
+    mlp.save("circle.npz").expect("failed to save model");
+}
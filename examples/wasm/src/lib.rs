@@ -0,0 +1,90 @@
+//! Trains a small classifier and runs it in the browser.
+//!
+//! Build with [wasm-pack](https://rustwasm.github.io/wasm-pack/) and serve the directory with
+//! any static file server:
+//! ```sh
+//! cd examples/wasm
+//! wasm-pack build --target web
+//! python3 -m http.server
+//! ```
+//! Then open http://localhost:8000 in the browser.
+
+use dfdx::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use wasm_bindgen::prelude::*;
+
+// a 2 layer network that predicts whether a point is inside of a circle
+type Mlp = (
+    (Linear<2, 16>, ReLU),
+    (Linear<16, 16>, ReLU),
+    Linear<16, 1>,
+);
+
+const BATCH_SIZE: usize = 64;
+
+#[wasm_bindgen]
+pub struct Classifier {
+    mlp: Mlp,
+    opt: Adam<Mlp>,
+    rng: StdRng,
+}
+
+#[wasm_bindgen]
+impl Classifier {
+    /// Creates a randomly initialized model. There is no `thread_rng()` in the browser without
+    /// the `wasm` feature of dfdx, so the rng is seeded from javascript.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u64) -> Classifier {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut mlp: Mlp = Default::default();
+        mlp.reset_params(&mut rng);
+        Classifier {
+            mlp,
+            opt: Default::default(),
+            rng,
+        }
+    }
+
+    /// Loads parameters that were saved with `SaveToNpz::save()`, e.g. from a `fetch()` response.
+    pub fn load(&mut self, npz: &[u8]) -> Result<(), JsError> {
+        self.mlp
+            .load_from_bytes(npz)
+            .map_err(|e| JsError::new(&format!("{e:?}")))
+    }
+
+    /// Runs `steps` training steps on random points, and returns the last loss.
+    pub fn train(&mut self, steps: usize) -> f32 {
+        let mut loss_v = 0.0;
+        for _ in 0..steps {
+            let mut x: Tensor2D<BATCH_SIZE, 2> = Tensor2D::zeros();
+            let mut y: Tensor2D<BATCH_SIZE, 1> = Tensor2D::zeros();
+            for (x, y) in x.mut_data().iter_mut().zip(y.mut_data().iter_mut()) {
+                *x = [self.rng.gen_range(-1.0..1.0), self.rng.gen_range(-1.0..1.0)];
+                y[0] = inside_circle(x[0], x[1]);
+            }
+
+            let logits = self.mlp.forward(x.trace());
+            let loss = binary_cross_entropy_with_logits_loss(logits, &y);
+            loss_v = *loss.data();
+            self.opt
+                .update(&mut self.mlp, loss.backward())
+                .expect("unused params");
+        }
+        loss_v
+    }
+
+    /// The probability that `(x, y)` is inside of the circle.
+    pub fn predict(&self, x: f32, y: f32) -> f32 {
+        let logit = self.mlp.forward(Tensor1D::new([x, y]));
+        logit.sigmoid().data()[0]
+    }
+}
+
+/// The label of the point `(x, y)`: `1.0` if it is inside a circle with radius `0.6`.
+fn inside_circle(x: f32, y: f32) -> f32 {
+    if x * x + y * y < 0.36 {
+        1.0
+    } else {
+        0.0
+    }
+}
@@ -4,7 +4,7 @@
 pub trait CountElements: Clone {
     type Dtype: Clone + Default;
     const NUM_ELEMENTS: usize;
-    const NUM_BYTES: usize = Self::NUM_ELEMENTS * std::mem::size_of::<Self::Dtype>();
+    const NUM_BYTES: usize = Self::NUM_ELEMENTS * core::mem::size_of::<Self::Dtype>();
 
     fn ref_first_elem(&self) -> &Self::Dtype;
     fn mut_first_elem(&mut self) -> &mut Self::Dtype;
@@ -105,9 +105,38 @@ pub trait HasArrayType {
         + CountElements<Dtype = Self::Dtype>
         + ZeroElements
         + HasAxis<0>
-        + HasAxis<-1>;
+        + HasAxis<-1>
+        + NumpyArray;
 }
 
+/// The [crate::numpy] traits that every [HasArrayType::Array] implements, so it can be saved
+/// and loaded. Without the `std` feature there is no [crate::numpy], and this has no requirements.
+#[cfg(feature = "std")]
+pub trait NumpyArray:
+    crate::numpy::NumpyDtype
+    + crate::numpy::NumpyShape
+    + crate::numpy::ReadNumbers
+    + crate::numpy::WriteNumbers
+{
+}
+
+#[cfg(feature = "std")]
+impl<T> NumpyArray for T where
+    T: crate::numpy::NumpyDtype
+        + crate::numpy::NumpyShape
+        + crate::numpy::ReadNumbers
+        + crate::numpy::WriteNumbers
+{
+}
+
+/// The [crate::numpy] traits that every [HasArrayType::Array] implements, so it can be saved
+/// and loaded. Without the `std` feature there is no [crate::numpy], and this has no requirements.
+#[cfg(not(feature = "std"))]
+pub trait NumpyArray {}
+
+#[cfg(not(feature = "std"))]
+impl<T> NumpyArray for T {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
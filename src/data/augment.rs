@@ -0,0 +1,437 @@
+//! Random transformations of image tensors with shape (C, H, W) for data augmentation, and
+//! [Augmented] to apply them to the inputs of a [Dataset].
+
+use crate::prelude::*;
+use rand::prelude::{Rng, SeedableRng, StdRng};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A (random) transformation of a sample, e.g. to augment images during training.
+///
+/// Tuples of transforms apply each of them in order, like tuples of [Module]s.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// let augment = (
+///     RandomCrop::<28, 28> { padding: 2 },
+///     RandomHorizontalFlip { p: 0.5 },
+///     Normalize { mean: [0.5], std: [0.25] },
+/// );
+/// let img: Tensor3D<1, 28, 28> = TensorCreator::zeros();
+/// let img = augment.apply(img, &mut StdRng::seed_from_u64(0));
+/// assert_eq!(img.data()[0][0][0], -2.0);
+/// ```
+pub trait Transform<T> {
+    type Output;
+
+    fn apply<R: Rng>(&self, x: T, rng: &mut R) -> Self::Output;
+}
+
+macro_rules! tuple_impls {
+    ([$($name:ident),+] [$($idx:tt),+], $last:ident, [$($rev_tail:ident),+]) => {
+        impl<
+            Input,
+            $last:
+            $(Transform::<$rev_tail ::Output>, $rev_tail: )+
+            Transform<Input>
+        > Transform<Input> for ($($name,)+) {
+            type Output = $last ::Output;
+
+            /// Applies each transform of the tuple in order.
+            fn apply<R: Rng>(&self, x: Input, rng: &mut R) -> Self::Output {
+                $(let x = self.$idx.apply(x, rng);)+
+                x
+            }
+        }
+    };
+}
+
+tuple_impls!([A, B] [0, 1], B, [A]);
+tuple_impls!([A, B, C] [0, 1, 2], C, [B, A]);
+tuple_impls!([A, B, C, D] [0, 1, 2, 3], D, [C, B, A]);
+tuple_impls!([A, B, C, D, E] [0, 1, 2, 3, 4], E, [D, C, B, A]);
+tuple_impls!([A, B, C, D, E, F] [0, 1, 2, 3, 4, 5], F, [E, D, C, B, A]);
+
+/// Crops a random (OH, OW) region of the image, after padding it with `padding` zeros on every
+/// side. With `padding` & the output size of the input size, this randomly shifts the image.
+///
+/// Panics if the padded image is smaller than (OH, OW).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomCrop<const OH: usize, const OW: usize> {
+    pub padding: usize,
+}
+
+impl<const C: usize, const H: usize, const W: usize, const OH: usize, const OW: usize>
+    Transform<Tensor3D<C, H, W>> for RandomCrop<OH, OW>
+{
+    type Output = Tensor3D<C, OH, OW>;
+
+    fn apply<R: Rng>(&self, x: Tensor3D<C, H, W>, rng: &mut R) -> Self::Output {
+        let p = self.padding;
+        assert!(
+            H + 2 * p >= OH && W + 2 * p >= OW,
+            "can't crop ({OH}, {OW}) from ({H}, {W}) with padding {p}"
+        );
+        // top left corner of the crop in the padded image
+        let y0 = rng.gen_range(0..=H + 2 * p - OH);
+        let x0 = rng.gen_range(0..=W + 2 * p - OW);
+
+        let mut out: Self::Output = TensorCreator::zeros();
+        for (src, dst) in x.data().iter().zip(out.mut_data().iter_mut()) {
+            for (oy, row) in dst.iter_mut().enumerate() {
+                let Some(y) = (y0 + oy).checked_sub(p).filter(|&y| y < H) else {
+                    continue;
+                };
+                for (ox, v) in row.iter_mut().enumerate() {
+                    if let Some(x) = (x0 + ox).checked_sub(p).filter(|&x| x < W) {
+                        *v = src[y][x];
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Mirrors the image horizontally with probability `p`.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomHorizontalFlip {
+    pub p: f32,
+}
+
+impl Default for RandomHorizontalFlip {
+    fn default() -> Self {
+        Self { p: 0.5 }
+    }
+}
+
+impl<const C: usize, const H: usize, const W: usize> Transform<Tensor3D<C, H, W>>
+    for RandomHorizontalFlip
+{
+    type Output = Tensor3D<C, H, W>;
+
+    fn apply<R: Rng>(&self, mut x: Tensor3D<C, H, W>, rng: &mut R) -> Self::Output {
+        if rng.gen::<f32>() < self.p {
+            x.mut_data()
+                .iter_mut()
+                .flat_map(|c| c.iter_mut())
+                .for_each(|row| row.reverse());
+        }
+        x
+    }
+}
+
+/// Randomly changes the brightness, contrast, and saturation of an image with pixels in
+/// `[0.0, 1.0]`. Each is scaled by a random factor in `[1 - v, 1 + v]`, where `v` is the field
+/// with its name, and the result is clamped to `[0.0, 1.0]`. Saturation only applies to rgb
+/// images.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColorJitter {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+}
+
+impl<const C: usize, const H: usize, const W: usize> Transform<Tensor3D<C, H, W>> for ColorJitter {
+    type Output = Tensor3D<C, H, W>;
+
+    fn apply<R: Rng>(&self, mut x: Tensor3D<C, H, W>, rng: &mut R) -> Self::Output {
+        let mut factor = |v: f32| rng.gen_range(1.0 - v..=1.0 + v).max(0.0);
+        let brightness = factor(self.brightness);
+        let contrast = factor(self.contrast);
+        let saturation = factor(self.saturation);
+
+        let data = x.mut_data();
+        data.iter_mut()
+            .flat_map(|c| c.iter_mut().flat_map(|row| row.iter_mut()))
+            .for_each(|v| *v = (*v * brightness).clamp(0.0, 1.0));
+
+        let mean = gray(data).iter().flatten().sum::<f32>() / (H * W) as f32;
+        data.iter_mut()
+            .flat_map(|c| c.iter_mut().flat_map(|row| row.iter_mut()))
+            .for_each(|v| *v = ((*v - mean) * contrast + mean).clamp(0.0, 1.0));
+
+        if C == 3 {
+            let gray = gray(data);
+            for c in data.iter_mut() {
+                for (row, g) in c.iter_mut().zip(gray.iter()) {
+                    for (v, g) in row.iter_mut().zip(g.iter()) {
+                        *v = ((*v - g) * saturation + g).clamp(0.0, 1.0);
+                    }
+                }
+            }
+        }
+        x
+    }
+}
+
+/// The grayscale version of an image, with the same weights as `image::DynamicImage::to_luma8()`
+/// for rgb images.
+fn gray<const C: usize, const H: usize, const W: usize>(
+    data: &[[[f32; W]; H]; C],
+) -> Vec<[f32; W]> {
+    let weights: &[f32] = if C == 3 {
+        &[0.2126, 0.7152, 0.0722]
+    } else {
+        &[1.0 / C as f32; C]
+    };
+    let mut out = vec![[0.0; W]; H];
+    for (c, &w) in data.iter().zip(weights.iter()) {
+        for (row, g) in c.iter().zip(out.iter_mut()) {
+            for (v, g) in row.iter().zip(g.iter_mut()) {
+                *g += w * v;
+            }
+        }
+    }
+    out
+}
+
+/// Normalizes every channel with `(x - mean) / std`.
+#[derive(Debug, Clone, Copy)]
+pub struct Normalize<const C: usize> {
+    pub mean: [f32; C],
+    pub std: [f32; C],
+}
+
+impl Normalize<3> {
+    /// The per channel mean & std of ImageNet, which most pretrained vision models expect.
+    pub fn imagenet() -> Self {
+        Self {
+            mean: [0.485, 0.456, 0.406],
+            std: [0.229, 0.224, 0.225],
+        }
+    }
+}
+
+impl<const C: usize, const H: usize, const W: usize> Transform<Tensor3D<C, H, W>> for Normalize<C> {
+    type Output = Tensor3D<C, H, W>;
+
+    fn apply<R: Rng>(&self, mut x: Tensor3D<C, H, W>, _: &mut R) -> Self::Output {
+        for (c, channel) in x.mut_data().iter_mut().enumerate() {
+            channel
+                .iter_mut()
+                .flat_map(|row| row.iter_mut())
+                .for_each(|v| *v = (*v - self.mean[c]) / self.std[c]);
+        }
+        x
+    }
+}
+
+/// Sets a random `size` x `size` square of the image to 0 with probability `p`. The center of
+/// the square is anywhere in the image, so it can be partially outside of it.
+#[derive(Debug, Clone, Copy)]
+pub struct Cutout {
+    pub size: usize,
+    pub p: f32,
+}
+
+impl<const C: usize, const H: usize, const W: usize> Transform<Tensor3D<C, H, W>> for Cutout {
+    type Output = Tensor3D<C, H, W>;
+
+    fn apply<R: Rng>(&self, mut x: Tensor3D<C, H, W>, rng: &mut R) -> Self::Output {
+        if self.size == 0 || rng.gen::<f32>() >= self.p {
+            return x;
+        }
+        let (cy, cx) = (rng.gen_range(0..H), rng.gen_range(0..W));
+        let ys = cy.saturating_sub(self.size / 2)..(cy + self.size.div_ceil(2)).min(H);
+        let xs = cx.saturating_sub(self.size / 2)..(cx + self.size.div_ceil(2)).min(W);
+        for channel in x.mut_data().iter_mut() {
+            for row in channel[ys.clone()].iter_mut() {
+                row[xs.clone()].fill(0.0);
+            }
+        }
+        x
+    }
+}
+
+/// A [Dataset] whose inputs are transformed with a [Transform], e.g. to augment the images of a
+/// dataset during training.
+///
+/// The randomness of the transform only depends on `seed`, the epoch, and the index of the
+/// sample, so the augmentation is reproducible even with [DataLoader::prefetch()]. Call
+/// [Augmented::set_epoch()] before every epoch to get different augmentations in each of them.
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// # use std::sync::Arc;
+/// let augment = (RandomCrop::<32, 32> { padding: 4 }, RandomHorizontalFlip::default());
+/// let train = Arc::new(Augmented::new(Cifar10::new("./datasets", Split::Train)?, augment, 0));
+/// for epoch in 0..10 {
+///     train.set_epoch(epoch);
+///     for (img, lbl) in DataLoader::<_, 64>::shuffled(train.clone(), &mut rng).prefetch(4, 2) {
+///         ...
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Augmented<D, T> {
+    pub dataset: D,
+    pub transform: T,
+    seed: u64,
+    epoch: AtomicU64,
+}
+
+impl<D, T> Augmented<D, T> {
+    pub fn new(dataset: D, transform: T, seed: u64) -> Self {
+        Self {
+            dataset,
+            transform,
+            seed,
+            epoch: AtomicU64::new(0),
+        }
+    }
+
+    /// Sets the epoch, which changes the randomness of the transform.
+    pub fn set_epoch(&self, epoch: u64) {
+        self.epoch.store(epoch, Ordering::Relaxed);
+    }
+}
+
+impl<D: Dataset, T: Transform<D::Input>> Dataset for Augmented<D, T> {
+    type Input = T::Output;
+    type Label = D::Label;
+
+    fn len(&self) -> usize {
+        self.dataset.len()
+    }
+
+    fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        let seed = self.seed
+            ^ epoch.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (i as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (x, y) = self.dataset.get(i);
+        (self.transform.apply(x, &mut rng), y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0)
+    }
+
+    #[test]
+    fn test_random_crop() {
+        let x = Tensor3D::new([[[1.0, 2.0], [3.0, 4.0]]]);
+        let y: Tensor3D<1, 1, 1> = RandomCrop::<1, 1> { padding: 0 }.apply(x.clone(), &mut rng());
+        assert!(x.data()[0]
+            .iter()
+            .flatten()
+            .any(|v| *v == y.data()[0][0][0]));
+
+        let mut shifted = false;
+        let mut rng = rng();
+        for _ in 0..20 {
+            let y = RandomCrop::<2, 2> { padding: 1 }.apply(x.clone(), &mut rng);
+            let sum: f32 = y.data()[0].iter().flatten().sum();
+            shifted |= sum != 10.0;
+            assert!(y.data()[0]
+                .iter()
+                .flatten()
+                .all(|v| *v == 0.0 || x.data()[0].iter().flatten().any(|u| u == v)));
+        }
+        assert!(shifted);
+
+        let y = RandomCrop::<4, 4> { padding: 1 }.apply(x, &mut StdRng::seed_from_u64(1));
+        assert_eq!(
+            y.data()[0],
+            [
+                [0.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 2.0, 0.0],
+                [0.0, 3.0, 4.0, 0.0],
+                [0.0, 0.0, 0.0, 0.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_horizontal_flip() {
+        let x = Tensor3D::new([[[1.0, 2.0, 3.0]], [[4.0, 5.0, 6.0]]]);
+        let y = RandomHorizontalFlip { p: 1.0 }.apply(x.clone(), &mut rng());
+        assert_eq!(y.data(), &[[[3.0, 2.0, 1.0]], [[6.0, 5.0, 4.0]]]);
+        let y = RandomHorizontalFlip { p: 0.0 }.apply(x.clone(), &mut rng());
+        assert_eq!(y.data(), x.data());
+    }
+
+    #[test]
+    fn test_color_jitter() {
+        let x = Tensor3D::new([[[0.2, 0.4]], [[0.6, 0.8]], [[0.1, 0.9]]]);
+        let y = ColorJitter::default().apply(x.clone(), &mut rng());
+        for (a, b) in y
+            .data()
+            .iter()
+            .flatten()
+            .flatten()
+            .zip(x.data().iter().flatten().flatten())
+        {
+            assert!((a - b).abs() < 1e-6);
+        }
+
+        let jitter = ColorJitter {
+            brightness: 0.5,
+            contrast: 0.5,
+            saturation: 0.5,
+        };
+        let y = jitter.apply(x.clone(), &mut rng());
+        assert_ne!(y.data(), x.data());
+        assert!(y
+            .data()
+            .iter()
+            .flatten()
+            .flatten()
+            .all(|v| (0.0..=1.0).contains(v)));
+    }
+
+    #[test]
+    fn test_normalize_and_cutout() {
+        let x: Tensor3D<2, 4, 4> = TensorCreator::ones();
+        let norm = Normalize {
+            mean: [0.5, 1.0],
+            std: [0.5, 2.0],
+        };
+        let y = (norm, Cutout { size: 2, p: 1.0 }).apply(x, &mut rng());
+        let count = |c: usize, v: f32| y.data()[c].iter().flatten().filter(|&&u| u == v).count();
+        assert_eq!(count(1, 0.0), 16);
+        let zeros = count(0, 0.0);
+        assert!((1..=4).contains(&zeros));
+        assert_eq!(count(0, 1.0), 16 - zeros);
+    }
+
+    struct Ones;
+
+    impl Dataset for Ones {
+        type Input = Tensor3D<1, 4, 4>;
+        type Label = usize;
+        fn len(&self) -> usize {
+            8
+        }
+        fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+            (TensorCreator::ones(), i)
+        }
+    }
+
+    #[test]
+    fn test_augmented_is_reproducible() {
+        let transform = (
+            RandomCrop::<3, 3> { padding: 1 },
+            Cutout { size: 2, p: 0.5 },
+        );
+        let a = Augmented::new(Ones, transform, 0);
+        let b = Augmented::new(Ones, transform, 0);
+        let samples = |d: &Augmented<Ones, (RandomCrop<3, 3>, Cutout)>| -> Vec<[[[f32; 3]; 3]; 1]> {
+            (0..d.len()).map(|i| *d.get(i).0.data()).collect()
+        };
+        assert_eq!(samples(&a), samples(&b));
+
+        b.set_epoch(1);
+        assert_ne!(samples(&a), samples(&b));
+        a.set_epoch(1);
+        assert_eq!(samples(&a), samples(&b));
+    }
+}
@@ -0,0 +1,355 @@
+//! [Dataset]s of MNIST, FashionMNIST, and CIFAR-10, which are downloaded the first time they are
+//! loaded and cached in a directory afterwards.
+
+use crate::prelude::*;
+use flate2::read::GzDecoder;
+use std::error::Error;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const MNIST_URL: &str = "https://ossci-datasets.s3.amazonaws.com/mnist/";
+const FASHION_MNIST_URL: &str = "http://fashion-mnist.s3-website.eu-central-1.amazonaws.com/";
+const CIFAR10_URL: &str = "https://www.cs.toronto.edu/~kriz/cifar-10-binary.tar.gz";
+
+/// The names of the classes of [Mnist::fashion()], in the order of their labels.
+pub const FASHION_MNIST_CLASSES: [&str; 10] = [
+    "T-shirt/top",
+    "Trouser",
+    "Pullover",
+    "Dress",
+    "Coat",
+    "Sandal",
+    "Shirt",
+    "Sneaker",
+    "Bag",
+    "Ankle boot",
+];
+
+/// The names of the classes of [Cifar10], in the order of their labels.
+pub const CIFAR10_CLASSES: [&str; 10] = [
+    "airplane",
+    "automobile",
+    "bird",
+    "cat",
+    "deer",
+    "dog",
+    "frog",
+    "horse",
+    "ship",
+    "truck",
+];
+
+/// Which part of a dataset to load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    Train,
+    Test,
+}
+
+/// The MNIST dataset of handwritten digits, or the FashionMNIST dataset of clothes, which have
+/// the same format. Inputs are grayscale images with pixels in `[0.0, 1.0]`, and labels are the
+/// classes `0..10`.
+///
+/// The files are stored in the same layout as `torchvision`, so existing downloads are reused.
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// let train = Mnist::new("./datasets", Split::Train)?;
+/// assert_eq!(train.len(), 60_000);
+/// for (img, lbl) in DataLoader::<_, 32>::in_order(&train) {
+///     let img: Tensor4D<32, 1, 28, 28> = img;
+///     let lbl: Tensor2D<32, 10> = one_hot_encode(&lbl);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mnist {
+    images: Vec<u8>,
+    labels: Vec<u8>,
+}
+
+impl Mnist {
+    /// Loads MNIST from `root/MNIST/raw`, and downloads it first if it's not there yet.
+    pub fn new<P: AsRef<Path>>(root: P, split: Split) -> Result<Self, DatasetError> {
+        Self::load(&root.as_ref().join("MNIST").join("raw"), MNIST_URL, split)
+    }
+
+    /// Loads FashionMNIST from `root/FashionMNIST/raw`, and downloads it first if it's not
+    /// there yet. See [FASHION_MNIST_CLASSES] for the names of the labels.
+    pub fn fashion<P: AsRef<Path>>(root: P, split: Split) -> Result<Self, DatasetError> {
+        let dir = root.as_ref().join("FashionMNIST").join("raw");
+        Self::load(&dir, FASHION_MNIST_URL, split)
+    }
+
+    fn load(dir: &Path, url: &str, split: Split) -> Result<Self, DatasetError> {
+        let prefix = match split {
+            Split::Train => "train",
+            Split::Test => "t10k",
+        };
+        let images = fetch_gz(dir, url, &format!("{prefix}-images-idx3-ubyte"))?;
+        let labels = fetch_gz(dir, url, &format!("{prefix}-labels-idx1-ubyte"))?;
+        let images = read_idx(&images, 3, &[28, 28])?;
+        let labels = read_idx(&labels, 1, &[])?;
+        if images.len() != labels.len() * 28 * 28 {
+            return Err(invalid("the number of images and labels is different"));
+        }
+        Ok(Self { images, labels })
+    }
+}
+
+impl Dataset for Mnist {
+    type Input = Tensor3D<1, 28, 28>;
+    type Label = usize;
+
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+        let mut img: Self::Input = TensorCreator::zeros();
+        let pixels = &self.images[i * 28 * 28..(i + 1) * 28 * 28];
+        let data = img.mut_data().as_flattened_mut().as_flattened_mut();
+        for (x, &p) in data.iter_mut().zip(pixels.iter()) {
+            *x = p as f32 / 255.0;
+        }
+        (img, self.labels[i] as usize)
+    }
+}
+
+/// The CIFAR-10 dataset of small images of 10 classes. Inputs are rgb images with pixels in
+/// `[0.0, 1.0]`, and labels are the classes `0..10`. See [CIFAR10_CLASSES] for their names.
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// let test = Cifar10::new("./datasets", Split::Test)?;
+/// let (img, lbl): (Tensor3D<3, 32, 32>, usize) = test.get(0);
+/// println!("{}", CIFAR10_CLASSES[lbl]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cifar10 {
+    images: Vec<u8>,
+    labels: Vec<u8>,
+}
+
+impl Cifar10 {
+    /// Loads CIFAR-10 from `root/cifar-10-batches-bin`, and downloads it first if it's not
+    /// there yet.
+    pub fn new<P: AsRef<Path>>(root: P, split: Split) -> Result<Self, DatasetError> {
+        let root = root.as_ref();
+        let dir = root.join("cifar-10-batches-bin");
+        let files = match split {
+            Split::Train => (1..=5).map(|i| format!("data_batch_{i}.bin")).collect(),
+            Split::Test => vec!["test_batch.bin".to_string()],
+        };
+        if files.iter().any(|f| !dir.join(f).exists()) {
+            std::fs::create_dir_all(root)?;
+            tar::Archive::new(GzDecoder::new(download(CIFAR10_URL)?)).unpack(root)?;
+        }
+
+        let mut images = Vec::new();
+        let mut labels = Vec::new();
+        for f in files {
+            let data = std::fs::read(dir.join(&f))?;
+            if data.len() % (1 + 3 * 32 * 32) != 0 {
+                return Err(invalid(&format!("{f} is not a multiple of 3073 bytes")));
+            }
+            for record in data.chunks_exact(1 + 3 * 32 * 32) {
+                labels.push(record[0]);
+                images.extend_from_slice(&record[1..]);
+            }
+        }
+        Ok(Self { images, labels })
+    }
+}
+
+impl Dataset for Cifar10 {
+    type Input = Tensor3D<3, 32, 32>;
+    type Label = usize;
+
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+        let mut img: Self::Input = TensorCreator::zeros();
+        // NOTE: the images are stored channel first, like tensors
+        let pixels = &self.images[i * 3 * 32 * 32..(i + 1) * 3 * 32 * 32];
+        let data = img.mut_data().as_flattened_mut().as_flattened_mut();
+        for (x, &p) in data.iter_mut().zip(pixels.iter()) {
+            *x = p as f32 / 255.0;
+        }
+        (img, self.labels[i] as usize)
+    }
+}
+
+/// Returns the path of `dir/name`. If it doesn't exist yet, `{url}{name}.gz` is downloaded and
+/// decompressed to it first.
+fn fetch_gz(dir: &Path, url: &str, name: &str) -> Result<PathBuf, DatasetError> {
+    let path = dir.join(name);
+    if !path.exists() {
+        let mut data = Vec::new();
+        GzDecoder::new(download(&format!("{url}{name}.gz"))?).read_to_end(&mut data)?;
+        // NOTE: this only writes the file once the download is complete, so it's never cached
+        // partially
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&path, data)?;
+    }
+    Ok(path)
+}
+
+fn download(url: &str) -> Result<impl Read, DatasetError> {
+    let response = ureq::get(url).call()?;
+    Ok(response.into_body().into_reader())
+}
+
+/// Reads the data of the idx file at `path`, which must have `ndims` dimensions, and `dims` as
+/// all but the first.
+fn read_idx(path: &Path, ndims: u8, dims: &[u32]) -> Result<Vec<u8>, DatasetError> {
+    let data = std::fs::read(path)?;
+    let name = path.display();
+    let header_len = 4 + 4 * ndims as usize;
+    if data.len() < header_len || data[..4] != [0, 0, 0x08, ndims] {
+        return Err(invalid(&format!("{name} is not an idx file of u8s")));
+    }
+    let shape: Vec<u32> = data[4..header_len]
+        .chunks_exact(4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    if shape[1..] != *dims {
+        return Err(invalid(&format!("{name} has shape {shape:?}")));
+    }
+    let len = shape.iter().map(|&d| d as usize).product::<usize>();
+    if data.len() != header_len + len {
+        return Err(invalid(&format!("{name} has the wrong length")));
+    }
+    Ok(data[header_len..].to_vec())
+}
+
+fn invalid(msg: &str) -> DatasetError {
+    DatasetError::InvalidFormat(msg.into())
+}
+
+/// Error that can happen while downloading or loading a dataset.
+#[derive(Debug)]
+pub enum DatasetError {
+    /// Something went wrong with reading or writing the files.
+    Io(std::io::Error),
+
+    /// Something went wrong with downloading the dataset.
+    Download(ureq::Error),
+
+    /// A file of the dataset is not in the expected format.
+    InvalidFormat(String),
+}
+
+impl std::fmt::Display for DatasetError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DatasetError::Io(err) => write!(fmt, "{}", err),
+            DatasetError::Download(err) => write!(fmt, "failed to download dataset: {}", err),
+            DatasetError::InvalidFormat(msg) => write!(fmt, "invalid format: {}", msg),
+        }
+    }
+}
+
+impl Error for DatasetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DatasetError::Io(err) => Some(err),
+            DatasetError::Download(err) => Some(err),
+            DatasetError::InvalidFormat(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DatasetError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ureq::Error> for DatasetError {
+    fn from(e: ureq::Error) -> Self {
+        Self::Download(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn idx(dims: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 0x08, dims.len() as u8];
+        for d in dims {
+            bytes.extend_from_slice(&d.to_be_bytes());
+        }
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_load_cached_mnist() {
+        let root = tempfile::tempdir().expect("");
+        let dir = root.path().join("FashionMNIST").join("raw");
+        fs::create_dir_all(&dir).expect("");
+        let pixels: Vec<u8> = (0..2 * 28 * 28).map(|i| (i % 256) as u8).collect();
+        fs::write(
+            dir.join("t10k-images-idx3-ubyte"),
+            idx(&[2, 28, 28], &pixels),
+        )
+        .expect("");
+        fs::write(dir.join("t10k-labels-idx1-ubyte"), idx(&[2], &[7, 3])).expect("");
+
+        let mnist = Mnist::fashion(root.path(), Split::Test).expect("");
+        assert_eq!(mnist.len(), 2);
+        let (img, lbl) = mnist.get(1);
+        assert_eq!(lbl, 3);
+        assert_eq!(img.data()[0][0][0], (784 % 256) as f32 / 255.0);
+        assert_eq!(img.data()[0][27][27], ((2 * 784 - 1) % 256) as f32 / 255.0);
+    }
+
+    #[test]
+    fn test_invalid_mnist() {
+        let root = tempfile::tempdir().expect("");
+        let dir = root.path().join("MNIST").join("raw");
+        fs::create_dir_all(&dir).expect("");
+        fs::write(
+            dir.join("train-images-idx3-ubyte"),
+            idx(&[1, 28, 28], &[0; 784]),
+        )
+        .expect("");
+        fs::write(dir.join("train-labels-idx1-ubyte"), idx(&[2], &[0, 1])).expect("");
+        let r = Mnist::new(root.path(), Split::Train);
+        assert!(matches!(r, Err(DatasetError::InvalidFormat(_))));
+
+        fs::write(
+            dir.join("train-images-idx3-ubyte"),
+            idx(&[1, 28, 27], &[0; 756]),
+        )
+        .expect("");
+        let r = Mnist::new(root.path(), Split::Train);
+        assert!(matches!(r, Err(DatasetError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn test_load_cached_cifar10() {
+        let root = tempfile::tempdir().expect("");
+        let dir = root.path().join("cifar-10-batches-bin");
+        fs::create_dir_all(&dir).expect("");
+        let mut data = Vec::new();
+        for label in [9, 4] {
+            data.push(label);
+            data.extend((0..3 * 32 * 32).map(|i| (i / 1024) as u8 * 100));
+        }
+        fs::write(dir.join("test_batch.bin"), data).expect("");
+
+        let cifar = Cifar10::new(root.path(), Split::Test).expect("");
+        assert_eq!(cifar.len(), 2);
+        let (img, lbl) = cifar.get(1);
+        assert_eq!(lbl, 4);
+        assert_eq!(img.data()[0], [[0.0; 32]; 32]);
+        assert_eq!(img.data()[2], [[200.0 / 255.0; 32]; 32]);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::prelude::*;
+use rand::prelude::{Rng, SeedableRng, StdRng};
+
+/// A stream of samples that can only be iterated, e.g. because the data doesn't fit in memory
+/// and is read from sharded files or received over the network. Unlike a [Dataset], the number
+/// of samples doesn't need to be known, and samples can't be accessed by index.
+///
+/// Every call to [IterableDataset::iter()] starts a new epoch.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use std::io::{BufRead, BufReader};
+/// /// A csv file per shard, with 2 features and a label per line.
+/// struct Shards(Vec<&'static [u8]>);
+///
+/// impl IterableDataset for Shards {
+///     type Input = Tensor1D<2>;
+///     type Label = usize;
+///     type Iter = Box<dyn Iterator<Item = (Self::Input, Self::Label)>>;
+///
+///     fn iter(&self) -> Self::Iter {
+///         let lines = self.0.clone().into_iter().flat_map(|shard| BufReader::new(shard).lines());
+///         Box::new(lines.map(|line| {
+///             let v: Vec<f32> = line.unwrap().split(',').map(|v| v.parse().unwrap()).collect();
+///             (Tensor1D::new([v[0], v[1]]), v[2] as usize)
+///         }))
+///     }
+/// }
+///
+/// let shards = Shards(vec![b"0.5,1.5,0\n2.5,0.5,1\n", b"1.0,1.0,1\n"]);
+/// let batches: Vec<(Tensor2D<2, 2>, [usize; 2])> = IterableLoader::new(shards.iter()).collect();
+/// assert_eq!(batches.len(), 1);
+/// ```
+pub trait IterableDataset {
+    /// The type of a single input, e.g. a [Tensor1D] of features.
+    type Input;
+
+    /// The type of a single label, e.g. a class index (`usize`).
+    type Label;
+
+    type Iter: Iterator<Item = (Self::Input, Self::Label)>;
+
+    /// Iterates the samples from the start.
+    fn iter(&self) -> Self::Iter;
+}
+
+/// An iterator over batches of a stream of samples (e.g. from [IterableDataset::iter()]), which
+/// collates `B` samples at a time with [Collate]. The last batch is dropped if it is not full.
+///
+/// Since the stream can't be shuffled up front, [IterableLoader::shuffle_buffer()] can be used
+/// to shuffle samples within a buffer instead.
+///
+/// Generic Arguments:
+/// - `B` - The number of samples in a batch.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let samples = (0..10).map(|i| (i as f32, i));
+/// let loader = IterableLoader::<_, 4>::new(samples).shuffle_buffer(5, 0);
+/// for (x, y) in loader {
+///     let x: Tensor1D<4> = x;
+///     let y: [usize; 4] = y;
+/// }
+/// ```
+pub struct IterableLoader<It: Iterator, const B: usize> {
+    iter: It,
+    buffer: Vec<It::Item>,
+    buffer_size: usize,
+    rng: StdRng,
+}
+
+impl<It: Iterator, const B: usize> IterableLoader<It, B> {
+    /// Iterates the samples of `iter` in order.
+    pub fn new(iter: It) -> Self {
+        Self {
+            iter,
+            buffer: Vec::new(),
+            buffer_size: 0,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    /// Keeps `buffer_size` samples in a buffer, and picks the next sample randomly from it.
+    /// Larger buffers shuffle better, but need more memory. `seed` seeds the random picks.
+    pub fn shuffle_buffer(mut self, buffer_size: usize, seed: u64) -> Self {
+        self.buffer = Vec::with_capacity(buffer_size);
+        self.buffer_size = buffer_size;
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn next_sample(&mut self) -> Option<It::Item> {
+        if self.buffer_size == 0 {
+            return self.iter.next();
+        }
+        while self.buffer.len() < self.buffer_size {
+            match self.iter.next() {
+                Some(sample) => self.buffer.push(sample),
+                None => break,
+            }
+        }
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let i = self.rng.gen_range(0..self.buffer.len());
+        Some(self.buffer.swap_remove(i))
+    }
+}
+
+impl<It, I, L, const B: usize> Iterator for IterableLoader<It, B>
+where
+    It: Iterator<Item = (I, L)>,
+    I: Collate<B>,
+    L: Collate<B>,
+{
+    type Item = (I::Batched, L::Batched);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut inputs = Vec::with_capacity(B);
+        let mut labels = Vec::with_capacity(B);
+        for _ in 0..B {
+            let (input, label) = self.next_sample()?;
+            inputs.push(input);
+            labels.push(label);
+        }
+        Some((I::collate(inputs), L::collate(labels)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Stream(usize);
+
+    impl IterableDataset for Stream {
+        type Input = Tensor1D<2>;
+        type Label = usize;
+        type Iter = std::iter::Map<std::ops::Range<usize>, fn(usize) -> (Tensor1D<2>, usize)>;
+
+        fn iter(&self) -> Self::Iter {
+            (0..self.0).map(|i| (Tensor1D::new([i as f32, -(i as f32)]), i))
+        }
+    }
+
+    #[test]
+    fn test_iterable_in_order() {
+        let stream = Stream(7);
+        for _epoch in 0..2 {
+            let batches: Vec<_> = IterableLoader::<_, 3>::new(stream.iter()).collect();
+            assert_eq!(batches.len(), 2);
+            assert_eq!(
+                batches[0].0.data(),
+                &[[0.0, -0.0], [1.0, -1.0], [2.0, -2.0]]
+            );
+            assert_eq!(batches[0].1, [0, 1, 2]);
+            assert_eq!(batches[1].1, [3, 4, 5]);
+        }
+    }
+
+    #[test]
+    fn test_iterable_shuffle_buffer() {
+        let loader = IterableLoader::<_, 4>::new(Stream(20).iter()).shuffle_buffer(8, 0);
+        let batches: Vec<[usize; 4]> = loader.map(|(_, y)| y).collect();
+        let mut seen: Vec<usize> = batches.iter().flatten().copied().collect();
+        assert_ne!(seen, (0..20).collect::<Vec<_>>());
+
+        // a sample can only be picked once the samples before it are in the buffer
+        for (i, &s) in seen.iter().enumerate() {
+            assert!(s < i + 8);
+        }
+        seen.sort_unstable();
+        assert_eq!(seen, (0..20).collect::<Vec<_>>());
+
+        // the same seed gives the same order
+        let again: Vec<[usize; 4]> = IterableLoader::<_, 4>::new(Stream(20).iter())
+            .shuffle_buffer(8, 0)
+            .map(|(_, y)| y)
+            .collect();
+        assert_eq!(again, batches);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::prelude::*;
+use rand::prelude::SliceRandom;
+use std::sync::Arc;
+
+/// A collection of samples that can be accessed by index. Each sample is an input and a label,
+/// which are combined into batches by a [DataLoader].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// struct Squares;
+///
+/// impl Dataset for Squares {
+///     type Input = Tensor1D<1>;
+///     type Label = f32;
+///
+///     fn len(&self) -> usize {
+///         10
+///     }
+///
+///     fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+///         (Tensor1D::new([i as f32]), (i * i) as f32)
+///     }
+/// }
+/// ```
+pub trait Dataset {
+    /// The type of a single input, e.g. a [Tensor1D] of features.
+    type Input;
+
+    /// The type of a single label, e.g. a class index (`usize`).
+    type Label;
+
+    /// The number of samples.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the sample at index `i`, which is in `0..self.len()`.
+    fn get(&self, i: usize) -> (Self::Input, Self::Label);
+}
+
+impl<D: Dataset + ?Sized> Dataset for &D {
+    type Input = D::Input;
+    type Label = D::Label;
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+    fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+        (**self).get(i)
+    }
+}
+
+impl<D: Dataset + ?Sized> Dataset for Arc<D> {
+    type Input = D::Input;
+    type Label = D::Label;
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+    fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+        (**self).get(i)
+    }
+}
+
+/// Something that can be combined with `B - 1` others of the same type into a batch.
+///
+/// Tensors gain a leading batch dimension, e.g. `Tensor1D<M>` becomes `Tensor2D<B, M>`, `f32`s
+/// become a `Tensor1D<B>`, and `usize`s (e.g. class labels) become a `[usize; B]` that can be
+/// passed to [one_hot_encode()].
+pub trait Collate<const B: usize>: Sized {
+    type Batched;
+
+    /// Combines `items` into a batch. `items` has exactly `B` elements.
+    fn collate(items: Vec<Self>) -> Self::Batched;
+}
+
+impl<const B: usize> Collate<B> for usize {
+    type Batched = [usize; B];
+    fn collate(items: Vec<Self>) -> Self::Batched {
+        items.try_into().unwrap()
+    }
+}
+
+impl<const B: usize> Collate<B> for f32 {
+    type Batched = Tensor1D<B>;
+    fn collate(items: Vec<Self>) -> Self::Batched {
+        Tensor1D::new(items.try_into().unwrap())
+    }
+}
+
+macro_rules! collate_impl {
+    ($typename:ident, [$($Vs:tt),*], $batched:ident) => {
+impl<const B: usize, $(const $Vs: usize, )*> Collate<B> for $typename<$($Vs, )* NoneTape> {
+    type Batched = $batched<B, $($Vs, )* NoneTape>;
+    fn collate(items: Vec<Self>) -> Self::Batched {
+        let mut batch: Self::Batched = TensorCreator::zeros();
+        for (b, item) in batch.mut_data().iter_mut().zip(items.iter()) {
+            b.clone_from(item.data());
+        }
+        batch
+    }
+}
+    };
+}
+
+collate_impl!(Tensor0D, [], Tensor1D);
+collate_impl!(Tensor1D, [M], Tensor2D);
+collate_impl!(Tensor2D, [M, N], Tensor3D);
+collate_impl!(Tensor3D, [M, N, O], Tensor4D);
+
+/// The opposite of [Collate]: splits a batch of `B` items back into the items, e.g. the
+/// outputs of a model for a batch into the output for each sample.
+pub trait Uncollate<const B: usize> {
+    type Item;
+
+    /// Splits the batch into its `B` items.
+    fn uncollate(self) -> Vec<Self::Item>;
+}
+
+impl<const B: usize> Uncollate<B> for [usize; B] {
+    type Item = usize;
+    fn uncollate(self) -> Vec<Self::Item> {
+        self.to_vec()
+    }
+}
+
+macro_rules! uncollate_impl {
+    ($batched:ident, [$($Vs:tt),*], $typename:ident) => {
+impl<const B: usize, $(const $Vs: usize, )*> Uncollate<B> for $batched<B, $($Vs, )* NoneTape> {
+    type Item = $typename<$($Vs, )* NoneTape>;
+    fn uncollate(self) -> Vec<Self::Item> {
+        let items = self.data().iter().map(|data| {
+            let mut item: Self::Item = TensorCreator::zeros();
+            item.mut_data().clone_from(data);
+            item
+        });
+        items.collect()
+    }
+}
+    };
+}
+
+uncollate_impl!(Tensor1D, [], Tensor0D);
+uncollate_impl!(Tensor2D, [M], Tensor1D);
+uncollate_impl!(Tensor3D, [M, N], Tensor2D);
+uncollate_impl!(Tensor4D, [M, N, O], Tensor3D);
+
+/// What a [DataLoader] does with the last samples, if there are less than `B` of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastBatch {
+    /// The last samples are not used, like [SubsetIterator].
+    Drop,
+
+    /// The last batch is filled up with samples from the start of the epoch, so every sample is
+    /// used at least once.
+    Pad,
+}
+
+/// An iterator over batches of a [Dataset], which collates `B` samples at a time with [Collate].
+/// The dataset can be borrowed, or shared with an [Arc] to load batches on other threads with
+/// [DataLoader::prefetch()].
+///
+/// Generic Arguments:
+/// - `B` - The number of samples in a batch.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// # struct Squares;
+/// # impl Dataset for Squares {
+/// #     type Input = Tensor1D<1>;
+/// #     type Label = f32;
+/// #     fn len(&self) -> usize { 10 }
+/// #     fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+/// #         (Tensor1D::new([i as f32]), (i * i) as f32)
+/// #     }
+/// # }
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let loader = DataLoader::<_, 4>::shuffled(&Squares, &mut rng);
+/// assert_eq!(loader.len(), 2);
+/// for (x, y) in loader {
+///     let x: Tensor2D<4, 1> = x;
+///     let y: Tensor1D<4> = y;
+/// }
+///
+/// let loader = DataLoader::<_, 4>::in_order(&Squares).last_batch(LastBatch::Pad);
+/// assert_eq!(loader.len(), 3);
+/// ```
+pub struct DataLoader<D, const B: usize> {
+    pub(super) dataset: D,
+    pub(super) indices: Vec<usize>,
+    pub(super) i: usize,
+    last: LastBatch,
+}
+
+impl<D: Dataset, const B: usize> DataLoader<D, B> {
+    /// Iterates the dataset in order, dropping the last batch if it is not full.
+    pub fn in_order(dataset: D) -> Self {
+        Self {
+            indices: (0..dataset.len()).collect(),
+            dataset,
+            i: 0,
+            last: LastBatch::Drop,
+        }
+    }
+
+    /// Iterates the dataset in a random order, dropping the last batch if it is not full.
+    pub fn shuffled<R: rand::Rng>(dataset: D, rng: &mut R) -> Self {
+        let mut loader = Self::in_order(dataset);
+        loader.indices.shuffle(rng);
+        loader
+    }
+
+    /// Like [DataLoader::shuffled()] with [crate::rng::seeded_rng()], so the order is
+    /// reproducible with [crate::set_seed()].
+    pub fn shuffled_seeded(dataset: D) -> Self {
+        Self::shuffled(dataset, &mut crate::rng::seeded_rng())
+    }
+
+    /// Sets what happens with the last batch if it is not full.
+    pub fn last_batch(mut self, last: LastBatch) -> Self {
+        self.last = last;
+        self
+    }
+
+    /// Loads the samples that have not been returned as a batch, e.g. the last samples that
+    /// were dropped with [LastBatch::Drop]. They can be used with [crate::nn::DynBatch]:
+    ///
+    /// ```rust
+    /// # use dfdx::prelude::*;
+    /// # struct Squares;
+    /// # impl Dataset for Squares {
+    /// #     type Input = Tensor1D<1>;
+    /// #     type Label = f32;
+    /// #     fn len(&self) -> usize { 10 }
+    /// #     fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+    /// #         (Tensor1D::new([i as f32]), (i * i) as f32)
+    /// #     }
+    /// # }
+    /// let mut loader = DataLoader::<_, 4>::in_order(&Squares);
+    /// for (x, y) in loader.by_ref() {
+    ///     let x: Tensor2D<4, 1> = x;
+    /// }
+    /// let (x, y) = loader.remainder();
+    /// let x: DynBatch<Tensor1D<1>> = DynBatch::new(x);
+    /// assert_eq!(x.len(), 2);
+    /// ```
+    pub fn remainder(&self) -> (Vec<D::Input>, Vec<D::Label>) {
+        let start = self.i.min(self.indices.len());
+        self.indices[start..]
+            .iter()
+            .map(|&i| self.dataset.get(i))
+            .unzip()
+    }
+}
+
+impl<D, const B: usize> Iterator for DataLoader<D, B>
+where
+    D: Dataset,
+    D::Input: Collate<B>,
+    D::Label: Collate<B>,
+{
+    type Item = (
+        <D::Input as Collate<B>>::Batched,
+        <D::Label as Collate<B>>::Batched,
+    );
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.indices.len();
+        let full = self.i + B <= n;
+        let padded = self.last == LastBatch::Pad && self.i < n;
+        if !full && !padded {
+            return None;
+        }
+
+        let batch = load_batch(&self.dataset, &self.indices, self.i);
+        self.i += B;
+        Some(batch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.indices.len().saturating_sub(self.i);
+        let batches = match self.last {
+            LastBatch::Drop => remaining / B,
+            LastBatch::Pad => remaining.div_ceil(B),
+        };
+        (batches, Some(batches))
+    }
+}
+
+impl<D, const B: usize> ExactSizeIterator for DataLoader<D, B>
+where
+    D: Dataset,
+    D::Input: Collate<B>,
+    D::Label: Collate<B>,
+{
+}
+
+/// Loads and collates the samples `indices[start..start + B]`, wrapping around to the start of
+/// `indices` if there are less than `B` left.
+pub(super) fn load_batch<D, const B: usize>(
+    dataset: &D,
+    indices: &[usize],
+    start: usize,
+) -> (
+    <D::Input as Collate<B>>::Batched,
+    <D::Label as Collate<B>>::Batched,
+)
+where
+    D: Dataset,
+    D::Input: Collate<B>,
+    D::Label: Collate<B>,
+{
+    let mut inputs = Vec::with_capacity(B);
+    let mut labels = Vec::with_capacity(B);
+    for j in start..start + B {
+        let (input, label) = dataset.get(indices[j % indices.len()]);
+        inputs.push(input);
+        labels.push(label);
+    }
+    (Collate::collate(inputs), Collate::collate(labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::{SeedableRng, StdRng};
+
+    struct Range(usize);
+
+    impl Dataset for Range {
+        type Input = Tensor1D<2>;
+        type Label = usize;
+
+        fn len(&self) -> usize {
+            self.0
+        }
+
+        fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+            (Tensor1D::new([i as f32, -(i as f32)]), i)
+        }
+    }
+
+    #[test]
+    fn test_in_order_drops_last() {
+        let batches: Vec<_> = DataLoader::<_, 3>::in_order(&Range(7)).collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            batches[0].0.data(),
+            &[[0.0, -0.0], [1.0, -1.0], [2.0, -2.0]]
+        );
+        assert_eq!(batches[0].1, [0, 1, 2]);
+        assert_eq!(batches[1].1, [3, 4, 5]);
+    }
+
+    #[test]
+    fn test_remainder() {
+        let mut loader = DataLoader::<_, 3>::in_order(&Range(7));
+        assert_eq!(loader.remainder().1, [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(loader.by_ref().count(), 2);
+        let (inputs, labels) = loader.remainder();
+        assert_eq!(labels, [6]);
+        assert_eq!(inputs[0].data(), &[6.0, -6.0]);
+
+        let mut loader = DataLoader::<_, 3>::in_order(&Range(7)).last_batch(LastBatch::Pad);
+        assert_eq!(loader.by_ref().count(), 3);
+        assert!(loader.remainder().1.is_empty());
+    }
+
+    #[test]
+    fn test_pad_last() {
+        let loader = DataLoader::<_, 3>::in_order(&Range(7)).last_batch(LastBatch::Pad);
+        assert_eq!(loader.len(), 3);
+        let labels: Vec<[usize; 3]> = loader.map(|(_, y)| y).collect();
+        assert_eq!(labels, [[0, 1, 2], [3, 4, 5], [6, 0, 1]]);
+
+        // datasets smaller than a batch are repeated
+        let labels: Vec<[usize; 5]> = DataLoader::<_, 5>::in_order(&Range(2))
+            .last_batch(LastBatch::Pad)
+            .map(|(_, y)| y)
+            .collect();
+        assert_eq!(labels, [[0, 1, 0, 1, 0]]);
+
+        let mut loader = DataLoader::<_, 2>::in_order(&Range(0)).last_batch(LastBatch::Pad);
+        assert!(loader.next().is_none());
+    }
+
+    #[test]
+    fn test_shuffled_uses_all() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut seen: Vec<usize> = DataLoader::<_, 4>::shuffled(&Range(20), &mut rng)
+            .flat_map(|(x, y)| {
+                for (row, &i) in x.data().iter().zip(y.iter()) {
+                    assert_eq!(row, &[i as f32, -(i as f32)]);
+                }
+                y
+            })
+            .collect();
+        assert_ne!(seen, (0..20).collect::<Vec<_>>());
+        seen.sort_unstable();
+        assert_eq!(seen, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_collate_tensors() {
+        let t = <f32 as Collate<2>>::collate(vec![1.0, 2.0]);
+        assert_eq!(t.data(), &[1.0, 2.0]);
+
+        let items = vec![Tensor2D::new([[1.0, 2.0]]), Tensor2D::new([[3.0, 4.0]])];
+        let t = <Tensor2D<1, 2> as Collate<2>>::collate(items);
+        assert_eq!(t.data(), &[[[1.0, 2.0]], [[3.0, 4.0]]]);
+    }
+}
@@ -1,4 +1,58 @@
 //! A collection of data utility classes such as [one_hot_encode()] and [SubsetIterator].
+//!
+//! Datasets implement [Dataset], and are iterated in (shuffled) batches with a [DataLoader].
+//! [DataLoader::prefetch()] loads the next batches on worker threads during training.
+//! Imbalanced datasets can be sampled with a [WeightedSampler] or [DataLoader::stratified()].
+//! Data that can only be streamed implements [IterableDataset] instead, and is batched with an
+//! [IterableLoader].
+//!
+//! Datasets can be split with [train_test_split()] and [k_fold()] into [Subset]s, which only
+//! hold the indices of their samples.
+//!
+//! Images can be augmented with [Transform]s like [RandomCrop] and [RandomHorizontalFlip], which
+//! are applied to the inputs of a dataset with [Augmented].
+//!
+//! Transitions of a reinforcement learning agent can be stored in a [ReplayBuffer], which samples
+//! them as batches of tensors.
+//!
+//! Text can be split into tokens with a [Tokenizer] like [WhitespaceTokenizer] or [Bpe], and
+//! mapped to indices with a [Vocab]. Variable length sequences are padded into batches with
+//! [pad_sequences()] or [pad_token_ids()], and reduced while ignoring the padding with e.g.
+//! [masked_mean()].
+//!
+//! With the `datasets` feature, MNIST, FashionMNIST, and CIFAR-10 are available as `Mnist` and
+//! `Cifar10`, which download the data the first time they are used.
+//!
+//! With the `arrow` feature, columns of Arrow record batches can be read into [Tensor2D]s with
+//! `record_batch_to_tensor()` and `TabularBatches`, and the `parquet` feature adds `read_parquet()`.
+
+mod augment;
+#[cfg(feature = "datasets")]
+mod datasets;
+mod iterable;
+mod loader;
+mod prefetch;
+mod replay;
+mod sampler;
+mod sequence;
+mod split;
+#[cfg(feature = "arrow")]
+mod tabular;
+mod text;
+
+pub use augment::*;
+#[cfg(feature = "datasets")]
+pub use datasets::*;
+pub use iterable::*;
+pub use loader::*;
+pub use prefetch::*;
+pub use replay::*;
+pub use sampler::*;
+pub use sequence::*;
+pub use split::*;
+#[cfg(feature = "arrow")]
+pub use tabular::*;
+pub use text::*;
 
 use crate::prelude::*;
 use rand::prelude::SliceRandom;
@@ -0,0 +1,229 @@
+use super::loader::load_batch;
+use crate::prelude::*;
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+
+/// A batch that can be moved to another thread. Tensors themselves can't be sent between
+/// threads, so their data is sent instead, and turned back into a tensor on the receiving thread.
+pub trait SendBatch: Sized {
+    type Data: Send + 'static;
+    fn into_data(self) -> Self::Data;
+    fn from_data(data: Self::Data) -> Self;
+}
+
+impl<const B: usize> SendBatch for [usize; B] {
+    type Data = Self;
+    fn into_data(self) -> Self::Data {
+        self
+    }
+    fn from_data(data: Self::Data) -> Self {
+        data
+    }
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )*> SendBatch for $typename<$($Vs, )* NoneTape> {
+    type Data = Box<<Self as HasArrayType>::Array>;
+    fn into_data(self) -> Self::Data {
+        let mut data: Self::Data = Cpu::zeros();
+        data.as_mut().clone_from(self.data());
+        data
+    }
+    fn from_data(data: Self::Data) -> Self {
+        Self::new_boxed(data)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor0D, []);
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+impl<D, const B: usize> DataLoader<D, B>
+where
+    D: Dataset + Clone + Send + 'static,
+    D::Input: Collate<B>,
+    D::Label: Collate<B>,
+    <D::Input as Collate<B>>::Batched: SendBatch,
+    <D::Label as Collate<B>>::Batched: SendBatch,
+{
+    /// Loads the batches on `num_workers` threads, so the next batches are loaded while the
+    /// current one is used for training. Each worker loads up to `capacity` batches ahead, and
+    /// the batches are returned in the same order as without prefetching.
+    ///
+    /// The dataset is cloned for every worker, so it should be an [Arc] (or a `&'static` reference).
+    ///
+    /// Example:
+    /// ```rust
+    /// # use dfdx::prelude::*;
+    /// # use std::sync::Arc;
+    /// # struct Squares;
+    /// # impl Dataset for Squares {
+    /// #     type Input = Tensor1D<1>;
+    /// #     type Label = f32;
+    /// #     fn len(&self) -> usize { 10 }
+    /// #     fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+    /// #         (Tensor1D::new([i as f32]), (i * i) as f32)
+    /// #     }
+    /// # }
+    /// let dataset = Arc::new(Squares);
+    /// for (x, y) in DataLoader::<_, 2>::in_order(dataset).prefetch(2, 4) {
+    ///     let x: Tensor2D<2, 1> = x;
+    ///     let y: Tensor1D<2> = y;
+    /// }
+    /// ```
+    pub fn prefetch(
+        self,
+        num_workers: usize,
+        capacity: usize,
+    ) -> Prefetch<<D::Input as Collate<B>>::Batched, <D::Label as Collate<B>>::Batched> {
+        assert!(num_workers > 0, "prefetch needs at least 1 worker");
+        let len = self.len();
+        let start = self.i;
+        let indices = Arc::new(self.indices);
+
+        let mut receivers = Vec::with_capacity(num_workers);
+        let mut workers = Vec::with_capacity(num_workers);
+        for w in 0..num_workers {
+            // NOTE: every worker has its own channel, so batches can be received in order
+            let (tx, rx) = mpsc::sync_channel(capacity);
+            let dataset = self.dataset.clone();
+            let indices = indices.clone();
+            workers.push(Some(std::thread::spawn(move || {
+                for k in (w..len).step_by(num_workers) {
+                    let (x, y) = load_batch::<_, B>(&dataset, &indices, start + k * B);
+                    if tx.send((x.into_data(), y.into_data())).is_err() {
+                        // the iterator was dropped
+                        break;
+                    }
+                }
+            })));
+            receivers.push(rx);
+        }
+
+        Prefetch {
+            receivers,
+            workers,
+            next: 0,
+            len,
+        }
+    }
+}
+
+/// An iterator over batches that are loaded on worker threads. See [DataLoader::prefetch()].
+///
+/// If a worker panics, the panic is resumed on the thread that iterates. Dropping the iterator
+/// stops the workers.
+pub struct Prefetch<I: SendBatch, L: SendBatch> {
+    receivers: Vec<mpsc::Receiver<(I::Data, L::Data)>>,
+    workers: Vec<Option<JoinHandle<()>>>,
+    next: usize,
+    len: usize,
+}
+
+impl<I: SendBatch, L: SendBatch> Iterator for Prefetch<I, L> {
+    type Item = (I, L);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.len {
+            return None;
+        }
+        let w = self.next % self.receivers.len();
+        match self.receivers[w].recv() {
+            Ok((x, y)) => {
+                self.next += 1;
+                Some((I::from_data(x), L::from_data(y)))
+            }
+            Err(_) => {
+                // the worker stopped before sending all of its batches, so it panicked
+                let worker = self.workers[w].take().unwrap();
+                match worker.join() {
+                    Err(panic) => std::panic::resume_unwind(panic),
+                    Ok(()) => unreachable!(),
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len.saturating_sub(self.next);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<I: SendBatch, L: SendBatch> ExactSizeIterator for Prefetch<I, L> {}
+
+impl<I: SendBatch, L: SendBatch> Drop for Prefetch<I, L> {
+    fn drop(&mut self) {
+        // workers stop once they can't send anymore
+        self.receivers.clear();
+        for worker in self.workers.iter_mut().filter_map(Option::take) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::{SeedableRng, StdRng};
+
+    struct Range(usize);
+
+    impl Dataset for Range {
+        type Input = Tensor1D<2>;
+        type Label = usize;
+
+        fn len(&self) -> usize {
+            self.0
+        }
+
+        fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+            assert!(i != 13, "failed to load sample 13");
+            (Tensor1D::new([i as f32, -(i as f32)]), i)
+        }
+    }
+
+    #[test]
+    fn test_prefetch_keeps_order() {
+        let dataset = Arc::new(Range(11));
+        for (num_workers, capacity) in [(1, 0), (2, 1), (3, 4), (8, 2)] {
+            let loader = DataLoader::<_, 3>::in_order(dataset.clone()).last_batch(LastBatch::Pad);
+            let expected: Vec<_> = loader.map(|(x, y)| (*x.data(), y)).collect();
+
+            let loader = DataLoader::<_, 3>::in_order(dataset.clone()).last_batch(LastBatch::Pad);
+            let prefetched = loader.prefetch(num_workers, capacity);
+            assert_eq!(prefetched.len(), 4);
+            let found: Vec<_> = prefetched.map(|(x, y)| (*x.data(), y)).collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_prefetch_shuffled() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let loader = DataLoader::<_, 4>::shuffled(Arc::new(Range(12)), &mut rng);
+        let mut seen: Vec<usize> = loader.prefetch(2, 2).flat_map(|(_, y)| y).collect();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_prefetch_drop_early() {
+        let loader = DataLoader::<_, 2>::in_order(Arc::new(Range(12)));
+        let mut prefetched = loader.prefetch(2, 1);
+        assert_eq!(prefetched.next().map(|(_, y)| y), Some([0, 1]));
+        drop(prefetched);
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to load sample 13")]
+    fn test_prefetch_worker_panic() {
+        let loader = DataLoader::<_, 2>::in_order(Arc::new(Range(20)));
+        for _ in loader.prefetch(3, 1) {}
+    }
+}
@@ -0,0 +1,214 @@
+use crate::prelude::*;
+use rand::Rng;
+
+/// A single step of an agent in an environment: taking `action` in `state` gave `reward`
+/// and led to `next_state`. `done` is true if the episode ended with this step.
+#[derive(Debug, Clone)]
+pub struct Transition<S> {
+    pub state: S,
+    pub action: usize,
+    pub reward: f32,
+    pub next_state: S,
+    pub done: bool,
+}
+
+/// `B` [Transition]s sampled from a [ReplayBuffer], collated into batches with [Collate].
+/// `dones` is `1.0` where the episode ended and `0.0` otherwise.
+#[derive(Debug, Clone)]
+pub struct ReplayBatch<S, const B: usize> {
+    pub states: S,
+    pub actions: [usize; B],
+    pub rewards: Tensor1D<B>,
+    pub next_states: S,
+    pub dones: Tensor1D<B>,
+}
+
+/// A ring buffer of the last `capacity` [Transition]s, for experience replay in e.g. DQN.
+/// Once the buffer is full, new transitions overwrite the oldest ones.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let mut buffer: ReplayBuffer<Tensor1D<4>> = ReplayBuffer::new(1000);
+/// for _ in 0..100 {
+///     buffer.push(Transition {
+///         state: Tensor1D::randn(&mut rng),
+///         action: rng.gen_range(0..2),
+///         reward: rng.gen(),
+///         next_state: Tensor1D::randn(&mut rng),
+///         done: false,
+///     });
+/// }
+/// let batch: ReplayBatch<Tensor2D<32, 4>, 32> = buffer.sample(&mut rng);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplayBuffer<S> {
+    transitions: Vec<Transition<S>>,
+    capacity: usize,
+    next: usize,
+}
+
+impl<S> ReplayBuffer<S> {
+    /// Creates an empty buffer that holds up to `capacity` transitions.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "replay buffer capacity must be at least 1");
+        Self {
+            transitions: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+        }
+    }
+
+    /// The maximum number of transitions the buffer holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of transitions in the buffer.
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// Returns true if the buffer holds `capacity` transitions.
+    pub fn is_full(&self) -> bool {
+        self.transitions.len() == self.capacity
+    }
+
+    /// Adds a transition, replacing the oldest one if the buffer is full.
+    pub fn push(&mut self, transition: Transition<S>) {
+        if self.is_full() {
+            self.transitions[self.next] = transition;
+        } else {
+            self.transitions.push(transition);
+        }
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    /// Removes all transitions.
+    pub fn clear(&mut self) {
+        self.transitions.clear();
+        self.next = 0;
+    }
+
+    /// Iterates the transitions from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &Transition<S>> {
+        let (newest, oldest) = if self.is_full() {
+            self.transitions.split_at(self.next)
+        } else {
+            self.transitions.split_at(self.transitions.len())
+        };
+        oldest.iter().chain(newest.iter())
+    }
+}
+
+impl<S: Clone> ReplayBuffer<S> {
+    /// Samples `B` transitions uniformly at random (with replacement), and collates them
+    /// into a [ReplayBatch].
+    ///
+    /// Panics if the buffer is empty.
+    pub fn sample<R: Rng, const B: usize>(
+        &self,
+        rng: &mut R,
+    ) -> ReplayBatch<<S as Collate<B>>::Batched, B>
+    where
+        S: Collate<B>,
+    {
+        assert!(!self.is_empty(), "can't sample from an empty replay buffer");
+        let mut states = Vec::with_capacity(B);
+        let mut next_states = Vec::with_capacity(B);
+        let mut actions = [0; B];
+        let mut rewards: Tensor1D<B> = TensorCreator::zeros();
+        let mut dones: Tensor1D<B> = TensorCreator::zeros();
+        for (b, action) in actions.iter_mut().enumerate() {
+            let t = &self.transitions[rng.gen_range(0..self.len())];
+            states.push(t.state.clone());
+            next_states.push(t.next_state.clone());
+            *action = t.action;
+            rewards.mut_data()[b] = t.reward;
+            dones.mut_data()[b] = if t.done { 1.0 } else { 0.0 };
+        }
+        ReplayBatch {
+            states: <S as Collate<B>>::collate(states),
+            actions,
+            rewards,
+            next_states: <S as Collate<B>>::collate(next_states),
+            dones,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::{SeedableRng, StdRng};
+
+    fn transition(i: usize) -> Transition<Tensor1D<2>> {
+        Transition {
+            state: Tensor1D::new([i as f32, 0.0]),
+            action: i,
+            reward: i as f32 * 10.0,
+            next_state: Tensor1D::new([i as f32 + 1.0, 0.0]),
+            done: i % 2 == 1,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_overwrites_oldest() {
+        let mut buffer = ReplayBuffer::new(3);
+        assert!(buffer.is_empty());
+        for i in 0..2 {
+            buffer.push(transition(i));
+        }
+        assert_eq!(buffer.len(), 2);
+        assert!(!buffer.is_full());
+        let actions: Vec<usize> = buffer.iter().map(|t| t.action).collect();
+        assert_eq!(actions, [0, 1]);
+
+        for i in 2..5 {
+            buffer.push(transition(i));
+        }
+        assert_eq!(buffer.len(), 3);
+        assert!(buffer.is_full());
+        let actions: Vec<usize> = buffer.iter().map(|t| t.action).collect();
+        assert_eq!(actions, [2, 3, 4]);
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        buffer.push(transition(5));
+        let actions: Vec<usize> = buffer.iter().map(|t| t.action).collect();
+        assert_eq!(actions, [5]);
+    }
+
+    #[test]
+    fn test_sample_collates_transitions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut buffer = ReplayBuffer::new(8);
+        for i in 0..20 {
+            buffer.push(transition(i));
+        }
+
+        let batch: ReplayBatch<Tensor2D<16, 2>, 16> = buffer.sample(&mut rng);
+        for b in 0..16 {
+            let i = batch.actions[b];
+            assert!((12..20).contains(&i));
+            assert_eq!(batch.states.data()[b], [i as f32, 0.0]);
+            assert_eq!(batch.next_states.data()[b], [i as f32 + 1.0, 0.0]);
+            assert_eq!(batch.rewards.data()[b], i as f32 * 10.0);
+            assert_eq!(batch.dones.data()[b], (i % 2) as f32);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "empty replay buffer")]
+    fn test_sample_empty() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let buffer: ReplayBuffer<Tensor1D<2>> = ReplayBuffer::new(4);
+        let _: ReplayBatch<Tensor2D<2, 2>, 2> = buffer.sample(&mut rng);
+    }
+}
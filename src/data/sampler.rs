@@ -0,0 +1,223 @@
+use crate::prelude::*;
+use rand::distributions::{Distribution, WeightedError, WeightedIndex};
+use rand::prelude::{Rng, SliceRandom};
+
+/// Draws sample indices with probability proportional to per-sample weights, with replacement.
+/// Use it with [DataLoader::weighted()], e.g. to oversample rare classes of an imbalanced dataset.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let sampler = WeightedSampler::new(&[0.0, 1.0, 3.0], 100).unwrap();
+/// let indices = sampler.sample(&mut rng);
+/// assert_eq!(indices.len(), 100);
+/// assert!(!indices.contains(&0));
+///
+/// // every class is drawn equally often on average
+/// let sampler = WeightedSampler::balanced(&[0, 0, 0, 1], 100);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WeightedSampler {
+    dist: WeightedIndex<f32>,
+    len: usize,
+    num_samples: usize,
+}
+
+impl WeightedSampler {
+    /// Draws `num_samples` indices per epoch, where index `i` has probability
+    /// `weights[i] / weights.sum()`.
+    ///
+    /// Returns an error if `weights` is empty, contains a negative weight, or sums to 0.
+    pub fn new(weights: &[f32], num_samples: usize) -> Result<Self, WeightedError> {
+        Ok(Self {
+            dist: WeightedIndex::new(weights)?,
+            len: weights.len(),
+            num_samples,
+        })
+    }
+
+    /// Weights every sample by the inverse frequency of its class in `labels`, so each class
+    /// is drawn equally often.
+    ///
+    /// Panics if `labels` is empty.
+    pub fn balanced(labels: &[usize], num_samples: usize) -> Self {
+        let counts = class_counts(labels);
+        let weights: Vec<f32> = labels.iter().map(|&l| 1.0 / counts[l] as f32).collect();
+        Self::new(&weights, num_samples).expect("labels is empty")
+    }
+
+    /// The number of weights, which should be the length of the dataset.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of indices drawn per epoch.
+    pub fn num_samples(&self) -> usize {
+        self.num_samples
+    }
+
+    /// Draws [WeightedSampler::num_samples()] indices.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Vec<usize> {
+        (0..self.num_samples)
+            .map(|_| self.dist.sample(rng))
+            .collect()
+    }
+}
+
+impl<D: Dataset, const B: usize> DataLoader<D, B> {
+    /// Iterates the indices drawn by `sampler`, so samples can be used multiple times or not at
+    /// all in an epoch.
+    ///
+    /// Panics if `sampler` doesn't have a weight for every sample of `dataset`.
+    pub fn weighted<R: Rng>(dataset: D, sampler: &WeightedSampler, rng: &mut R) -> Self {
+        assert_eq!(
+            sampler.len(),
+            dataset.len(),
+            "sampler needs a weight for every sample"
+        );
+        let mut loader = Self::in_order(dataset);
+        loader.indices = sampler.sample(rng);
+        loader
+    }
+
+    /// Iterates the dataset in a random order where every batch has (about) the same class
+    /// proportions as the whole dataset. `labels[i]` is the class of sample `i`.
+    ///
+    /// Panics if `labels` doesn't have a label for every sample of `dataset`.
+    pub fn stratified<R: Rng>(dataset: D, labels: &[usize], rng: &mut R) -> Self {
+        assert_eq!(
+            labels.len(),
+            dataset.len(),
+            "stratified needs a label for every sample"
+        );
+        let mut loader = Self::in_order(dataset);
+        loader.indices = stratify(labels, rng);
+        loader
+    }
+}
+
+fn class_counts(labels: &[usize]) -> Vec<usize> {
+    let num_classes = labels.iter().max().map_or(0, |&l| l + 1);
+    let mut counts = vec![0; num_classes];
+    for &l in labels {
+        counts[l] += 1;
+    }
+    counts
+}
+
+/// Shuffles the indices of every class, and spreads them evenly over the epoch: the `j`th of
+/// the `n` samples of a class is placed at `(j + u) / n` with `u` uniform in `[0, 1)`.
+fn stratify<R: Rng>(labels: &[usize], rng: &mut R) -> Vec<usize> {
+    let counts = class_counts(labels);
+    let mut classes: Vec<Vec<usize>> = counts.iter().map(|&n| Vec::with_capacity(n)).collect();
+    for (i, &l) in labels.iter().enumerate() {
+        classes[l].push(i);
+    }
+
+    let mut keyed = Vec::with_capacity(labels.len());
+    for class in classes.iter_mut() {
+        class.shuffle(rng);
+        let n = class.len() as f64;
+        for (j, &i) in class.iter().enumerate() {
+            keyed.push(((j as f64 + rng.gen::<f64>()) / n, i));
+        }
+    }
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    keyed.into_iter().map(|(_, i)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::{SeedableRng, StdRng};
+
+    struct Labels(Vec<usize>);
+
+    impl Dataset for Labels {
+        type Input = f32;
+        type Label = usize;
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+            (i as f32, self.0[i])
+        }
+    }
+
+    fn imbalanced() -> Vec<usize> {
+        let mut labels = vec![0; 60];
+        labels.extend([1; 30]);
+        labels.extend([2; 10]);
+        labels
+    }
+
+    #[test]
+    fn test_weighted_sampler() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(WeightedSampler::new(&[], 10).is_err());
+        assert!(WeightedSampler::new(&[0.0, 0.0], 10).is_err());
+        assert!(WeightedSampler::new(&[1.0, -1.0], 10).is_err());
+
+        let sampler = WeightedSampler::new(&[1.0, 0.0, 3.0], 1000).unwrap();
+        let indices = sampler.sample(&mut rng);
+        assert_eq!(indices.len(), 1000);
+        let counts = class_counts(&indices);
+        assert_eq!(counts[1], 0);
+        assert!((200..300).contains(&counts[0]), "{counts:?}");
+    }
+
+    #[test]
+    fn test_weighted_balanced_loader() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let labels = imbalanced();
+        let sampler = WeightedSampler::balanced(&labels, 3000);
+        let loader = DataLoader::<_, 10>::weighted(Labels(labels.clone()), &sampler, &mut rng);
+        assert_eq!(loader.len(), 300);
+
+        let drawn: Vec<usize> = loader
+            .flat_map(|(x, y)| {
+                for (&i, &l) in x.data().iter().zip(y.iter()) {
+                    assert_eq!(labels[i as usize], l);
+                }
+                y
+            })
+            .collect();
+        for count in class_counts(&drawn) {
+            assert!((900..1100).contains(&count), "{count}");
+        }
+    }
+
+    #[test]
+    fn test_stratified_batches() {
+        let labels = imbalanced();
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let loader = DataLoader::<_, 10>::stratified(Labels(labels.clone()), &labels, &mut rng);
+            let batches: Vec<[usize; 10]> = loader.map(|(_, y)| y).collect();
+            assert_eq!(batches.len(), 10);
+            for batch in batches.iter() {
+                let counts = class_counts(batch);
+                assert!((5..=7).contains(&counts[0]), "{batch:?}");
+                assert!((2..=4).contains(&counts[1]), "{batch:?}");
+            }
+            let mut seen: Vec<_> = batches.iter().flat_map(|b| b.iter().copied()).collect();
+            seen.sort_unstable();
+            assert_eq!(seen, labels);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "label for every sample")]
+    fn test_stratified_wrong_len() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let _ = DataLoader::<_, 2>::stratified(Labels(vec![0, 1, 1]), &[0, 1], &mut rng);
+    }
+}
@@ -0,0 +1,265 @@
+//! Padding variable length sequences into fixed size batches, and reductions over the sequence
+//! dimension that ignore the padding.
+
+use crate::prelude::*;
+
+/// A batch of `B` sequences of `D` dimensional elements, padded or truncated to length `L`.
+///
+/// `mask` is `1.0` for the elements of the sequences and `0.0` for the padding, and can be
+/// passed to [masked_sum()], [masked_mean()], and [masked_max()].
+#[derive(Debug, Clone)]
+pub struct PaddedBatch<const B: usize, const L: usize, const D: usize> {
+    pub data: Tensor3D<B, L, D>,
+    pub lengths: [usize; B],
+    pub mask: Tensor2D<B, L>,
+}
+
+/// Pads each of the `B` sequences with `pad_value` up to length `L`. Sequences longer than `L`
+/// are truncated.
+///
+/// Panics if there are not exactly `B` sequences.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let seqs = [vec![[1.0, 2.0]], vec![[3.0, 4.0], [5.0, 6.0], [7.0, 8.0]]];
+/// let batch: PaddedBatch<2, 2, 2> = pad_sequences(&seqs, 0.0);
+/// assert_eq!(batch.data.data(), &[[[1.0, 2.0], [0.0, 0.0]], [[3.0, 4.0], [5.0, 6.0]]]);
+/// assert_eq!(batch.lengths, [1, 2]);
+/// assert_eq!(batch.mask.data(), &[[1.0, 0.0], [1.0, 1.0]]);
+/// ```
+pub fn pad_sequences<S, const B: usize, const L: usize, const D: usize>(
+    sequences: &[S],
+    pad_value: f32,
+) -> PaddedBatch<B, L, D>
+where
+    S: AsRef<[[f32; D]]>,
+{
+    assert_eq!(sequences.len(), B, "expected {B} sequences");
+    let mut data: Tensor3D<B, L, D> = TensorCreator::zeros();
+    let mut lengths = [0; B];
+    for ((b, seq), len) in data
+        .mut_data()
+        .iter_mut()
+        .zip(sequences)
+        .zip(lengths.iter_mut())
+    {
+        let seq = seq.as_ref();
+        *len = seq.len().min(L);
+        for (i, x) in b.iter_mut().enumerate() {
+            *x = seq.get(i).copied().unwrap_or([pad_value; D]);
+        }
+    }
+    PaddedBatch {
+        data,
+        mask: lengths_to_mask(&lengths),
+        lengths,
+    }
+}
+
+/// Pads each of the `B` sequences of token ids with `pad_id` up to length `L`, and returns the
+/// padded ids and the lengths. Sequences longer than `L` are truncated.
+///
+/// The ids can be passed to [Select1] to look up embeddings. See [Vocab] for mapping tokens to ids.
+///
+/// Panics if there are not exactly `B` sequences.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let (ids, lengths) = pad_token_ids::<_, 2, 3>(&[vec![5, 6], vec![7, 8, 9, 10]], 0);
+/// assert_eq!(ids, [[5, 6, 0], [7, 8, 9]]);
+/// assert_eq!(lengths, [2, 3]);
+/// ```
+pub fn pad_token_ids<S, const B: usize, const L: usize>(
+    sequences: &[S],
+    pad_id: usize,
+) -> ([[usize; L]; B], [usize; B])
+where
+    S: AsRef<[usize]>,
+{
+    assert_eq!(sequences.len(), B, "expected {B} sequences");
+    let mut ids = [[pad_id; L]; B];
+    let mut lengths = [0; B];
+    for ((b, seq), len) in ids.iter_mut().zip(sequences).zip(lengths.iter_mut()) {
+        let seq = seq.as_ref();
+        *len = seq.len().min(L);
+        b[..*len].copy_from_slice(&seq[..*len]);
+    }
+    (ids, lengths)
+}
+
+/// Creates a mask that is `1.0` for the first `lengths[b]` elements of sequence `b`, and `0.0`
+/// for the rest.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let mask: Tensor2D<2, 3> = lengths_to_mask(&[1, 3]);
+/// assert_eq!(mask.data(), &[[1.0, 0.0, 0.0], [1.0, 1.0, 1.0]]);
+/// ```
+pub fn lengths_to_mask<const B: usize, const L: usize>(lengths: &[usize; B]) -> Tensor2D<B, L> {
+    let mut mask: Tensor2D<B, L> = TensorCreator::zeros();
+    for (m, &len) in mask.mut_data().iter_mut().zip(lengths.iter()) {
+        for x in m.iter_mut().take(len) {
+            *x = 1.0;
+        }
+    }
+    mask
+}
+
+/// Splits the padded sequences of `t` back into sequences of their `lengths`, e.g. to get the
+/// outputs of a model for a [PaddedBatch].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor3D<2, 2, 1> = Tensor3D::new([[[1.0], [2.0]], [[3.0], [4.0]]]);
+/// assert_eq!(unpad_sequences(&t, &[1, 2]), [vec![[1.0]], vec![[3.0], [4.0]]]);
+/// ```
+pub fn unpad_sequences<const B: usize, const L: usize, const D: usize, H>(
+    t: &Tensor3D<B, L, D, H>,
+    lengths: &[usize; B],
+) -> Vec<Vec<[f32; D]>> {
+    t.data()
+        .iter()
+        .zip(lengths.iter())
+        .map(|(seq, &len)| seq[..len.min(L)].to_vec())
+        .collect()
+}
+
+/// Sums `t` over the sequence dimension, ignoring the elements where `mask` is `0.0`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor3D<1, 3, 1> = Tensor3D::new([[[1.0], [2.0], [100.0]]]);
+/// let mask: Tensor2D<1, 3> = lengths_to_mask(&[2]);
+/// assert_eq!(masked_sum(t, &mask).data(), &[[3.0]]);
+/// ```
+pub fn masked_sum<const B: usize, const L: usize, const D: usize, H: Tape>(
+    t: Tensor3D<B, L, D, H>,
+    mask: &Tensor2D<B, L>,
+) -> Tensor2D<B, D, H> {
+    let mask: Tensor3D<B, L, D> = mask.clone().broadcast1();
+    mul(t, &mask).sum_axis::<1>()
+}
+
+/// Averages `t` over the sequence dimension, ignoring the elements where `mask` is `0.0`.
+/// Sequences without any elements have a mean of `0.0`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor3D<2, 3, 1> = Tensor3D::new([[[1.0], [2.0], [100.0]], [[4.0], [5.0], [6.0]]]);
+/// let mask: Tensor2D<2, 3> = lengths_to_mask(&[2, 0]);
+/// assert_eq!(masked_mean(t, &mask).data(), &[[1.5], [0.0]]);
+/// ```
+pub fn masked_mean<const B: usize, const L: usize, const D: usize, H: Tape>(
+    t: Tensor3D<B, L, D, H>,
+    mask: &Tensor2D<B, L>,
+) -> Tensor2D<B, D, H> {
+    let mut counts: Tensor1D<B> = mask.clone().sum_axis::<-1>();
+    for c in counts.mut_data().iter_mut() {
+        *c = c.max(1.0);
+    }
+    let counts: Tensor2D<B, D> = counts.broadcast1();
+    div(masked_sum(t, mask), &counts)
+}
+
+/// Takes the maximum of `t` over the sequence dimension, ignoring the elements where `mask`
+/// is `0.0`. Sequences without any elements have a maximum of `-inf`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t: Tensor3D<1, 3, 1> = Tensor3D::new([[[1.0], [2.0], [100.0]]]);
+/// let mask: Tensor2D<1, 3> = lengths_to_mask(&[2]);
+/// assert_eq!(masked_max(t, &mask).data(), &[[2.0]]);
+/// ```
+pub fn masked_max<const B: usize, const L: usize, const D: usize, H: Tape>(
+    t: Tensor3D<B, L, D, H>,
+    mask: &Tensor2D<B, L>,
+) -> Tensor2D<B, D, H> {
+    let mut padding: Tensor2D<B, L> = TensorCreator::zeros();
+    padding
+        .mut_data()
+        .iter_mut()
+        .flatten()
+        .zip(mask.data().iter().flatten())
+        .for_each(|(p, m)| *p = if *m == 0.0 { f32::NEG_INFINITY } else { 0.0 });
+    let padding: Tensor3D<B, L, D> = padding.broadcast1();
+    t.value_mask(&padding, f32::NEG_INFINITY).max_axis::<1>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_pad_sequences() {
+        let seqs: Vec<Vec<[f32; 1]>> = vec![vec![], vec![[1.0], [2.0], [3.0]], vec![[4.0]]];
+        let batch: PaddedBatch<3, 2, 1> = pad_sequences(&seqs, -1.0);
+        assert_eq!(
+            batch.data.data(),
+            &[[[-1.0], [-1.0]], [[1.0], [2.0]], [[4.0], [-1.0]]]
+        );
+        assert_eq!(batch.lengths, [0, 2, 1]);
+        assert_eq!(batch.mask.data(), &[[0.0, 0.0], [1.0, 1.0], [1.0, 0.0]]);
+        assert_eq!(
+            unpad_sequences(&batch.data, &batch.lengths),
+            [vec![], vec![[1.0], [2.0]], vec![[4.0]]]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 sequences")]
+    fn test_pad_wrong_batch_size() {
+        let _ = pad_token_ids::<_, 2, 3>(&[vec![1]], 0);
+    }
+
+    #[test]
+    fn test_masked_sum_mean_backward() {
+        let t: Tensor3D<2, 3, 2> = Tensor3D::new([
+            [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]],
+            [[-1.0, -2.0], [-3.0, -4.0], [-5.0, -6.0]],
+        ]);
+        let mask: Tensor2D<2, 3> = lengths_to_mask(&[2, 3]);
+
+        let r = masked_sum(t.trace(), &mask);
+        assert_eq!(r.data(), &[[4.0, 6.0], [-9.0, -12.0]]);
+        let gradients = r.sum().backward();
+        assert_eq!(
+            gradients.ref_gradient(&t),
+            &[
+                [[1.0, 1.0], [1.0, 1.0], [0.0, 0.0]],
+                [[1.0, 1.0], [1.0, 1.0], [1.0, 1.0]]
+            ]
+        );
+
+        let r = masked_mean(t.trace(), &mask);
+        assert_eq!(r.data(), &[[2.0, 3.0], [-3.0, -4.0]]);
+        let gradients = r.sum().backward();
+        assert_close(
+            gradients.ref_gradient(&t),
+            &[
+                [[0.5, 0.5], [0.5, 0.5], [0.0, 0.0]],
+                [[1.0 / 3.0; 2], [1.0 / 3.0; 2], [1.0 / 3.0; 2]],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_masked_max_backward() {
+        let t: Tensor3D<2, 3, 1> = Tensor3D::new([[[1.0], [3.0], [9.0]], [[2.0], [0.0], [1.0]]]);
+        let mask: Tensor2D<2, 3> = lengths_to_mask(&[2, 3]);
+        let r = masked_max(t.trace(), &mask);
+        assert_eq!(r.data(), &[[3.0], [2.0]]);
+        let gradients = r.sum().backward();
+        assert_eq!(
+            gradients.ref_gradient(&t),
+            &[[[0.0], [1.0], [0.0]], [[1.0], [0.0], [0.0]]]
+        );
+    }
+}
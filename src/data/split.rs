@@ -0,0 +1,236 @@
+use crate::prelude::*;
+use rand::prelude::{Rng, SliceRandom};
+
+/// A view of the samples `indices` of `dataset`. The samples are not copied, so the dataset
+/// is usually borrowed or an [Arc](std::sync::Arc). Created by [train_test_split()],
+/// [train_val_test_split()], and [k_fold()].
+#[derive(Debug, Clone)]
+pub struct Subset<D> {
+    pub dataset: D,
+    pub indices: Vec<usize>,
+}
+
+impl<D: Dataset> Dataset for Subset<D> {
+    type Input = D::Input;
+    type Label = D::Label;
+
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+        self.dataset.get(self.indices[i])
+    }
+}
+
+/// Randomly splits `dataset` into a train and a test set, where the test set has
+/// `test_fraction` of the samples (rounded).
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// # struct Squares;
+/// # impl Dataset for Squares {
+/// #     type Input = Tensor1D<1>;
+/// #     type Label = f32;
+/// #     fn len(&self) -> usize { 10 }
+/// #     fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+/// #         (Tensor1D::new([i as f32]), (i * i) as f32)
+/// #     }
+/// # }
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let (train, test) = train_test_split(&Squares, 0.2, &mut rng);
+/// assert_eq!(train.len(), 8);
+/// assert_eq!(test.len(), 2);
+/// for (x, y) in DataLoader::<_, 4>::shuffled(&train, &mut rng) {
+///     // ...
+/// }
+/// ```
+pub fn train_test_split<D: Dataset + Clone, R: Rng>(
+    dataset: D,
+    test_fraction: f32,
+    rng: &mut R,
+) -> (Subset<D>, Subset<D>) {
+    let (train, _, test) = train_val_test_split(dataset, 0.0, test_fraction, rng);
+    (train, test)
+}
+
+/// Randomly splits `dataset` into a train, a validation, and a test set, where the validation
+/// and test sets have `val_fraction` and `test_fraction` of the samples (rounded).
+///
+/// Panics if the fractions are negative or sum to more than 1.
+pub fn train_val_test_split<D: Dataset + Clone, R: Rng>(
+    dataset: D,
+    val_fraction: f32,
+    test_fraction: f32,
+    rng: &mut R,
+) -> (Subset<D>, Subset<D>, Subset<D>) {
+    assert!(
+        val_fraction >= 0.0 && test_fraction >= 0.0 && val_fraction + test_fraction <= 1.0,
+        "invalid split fractions {val_fraction} and {test_fraction}"
+    );
+    let len = dataset.len();
+    let num_val = (len as f32 * val_fraction).round() as usize;
+    let num_test = ((len as f32 * test_fraction).round() as usize).min(len - num_val);
+
+    let mut indices: Vec<usize> = (0..len).collect();
+    indices.shuffle(rng);
+    let test = indices.split_off(len - num_test);
+    let val = indices.split_off(len - num_test - num_val);
+    (
+        Subset {
+            dataset: dataset.clone(),
+            indices,
+        },
+        Subset {
+            dataset: dataset.clone(),
+            indices: val,
+        },
+        Subset {
+            dataset,
+            indices: test,
+        },
+    )
+}
+
+/// Randomly splits `dataset` into `k` folds of (almost) the same size, and returns a
+/// `(train, validation)` pair for each fold, where the fold is the validation set and the other
+/// folds are the train set. Every sample is in exactly one validation set.
+///
+/// Panics if `k` is less than 2 or more than the number of samples.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::prelude::*;
+/// # struct Squares;
+/// # impl Dataset for Squares {
+/// #     type Input = Tensor1D<1>;
+/// #     type Label = f32;
+/// #     fn len(&self) -> usize { 10 }
+/// #     fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+/// #         (Tensor1D::new([i as f32]), (i * i) as f32)
+/// #     }
+/// # }
+/// let mut rng = StdRng::seed_from_u64(0);
+/// for (train, val) in k_fold(&Squares, 5, &mut rng) {
+///     assert_eq!(train.len(), 8);
+///     assert_eq!(val.len(), 2);
+/// }
+/// ```
+pub fn k_fold<D: Dataset + Clone, R: Rng>(
+    dataset: D,
+    k: usize,
+    rng: &mut R,
+) -> Vec<(Subset<D>, Subset<D>)> {
+    let len = dataset.len();
+    assert!(
+        (2..=len).contains(&k),
+        "k must be in 2..={len} for {len} samples, but is {k}"
+    );
+    let mut indices: Vec<usize> = (0..len).collect();
+    indices.shuffle(rng);
+
+    let mut folds = Vec::with_capacity(k);
+    let mut start = 0;
+    for i in 0..k {
+        // the first `len % k` folds have one more sample
+        let end = start + len / k + usize::from(i < len % k);
+        let mut train = indices[..start].to_vec();
+        train.extend_from_slice(&indices[end..]);
+        let train = Subset {
+            dataset: dataset.clone(),
+            indices: train,
+        };
+        let val = Subset {
+            dataset: dataset.clone(),
+            indices: indices[start..end].to_vec(),
+        };
+        folds.push((train, val));
+        start = end;
+    }
+    folds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::{SeedableRng, StdRng};
+
+    struct Range(usize);
+
+    impl Dataset for Range {
+        type Input = f32;
+        type Label = usize;
+
+        fn len(&self) -> usize {
+            self.0
+        }
+
+        fn get(&self, i: usize) -> (Self::Input, Self::Label) {
+            (i as f32, i)
+        }
+    }
+
+    fn labels<D: Dataset<Label = usize>>(d: &D) -> Vec<usize> {
+        (0..d.len()).map(|i| d.get(i).1).collect()
+    }
+
+    #[test]
+    fn test_train_val_test_split() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (train, val, test) = train_val_test_split(&Range(10), 0.25, 0.3, &mut rng);
+        assert_eq!((train.len(), val.len(), test.len()), (4, 3, 3));
+
+        let mut all = labels(&train);
+        all.extend(labels(&val));
+        all.extend(labels(&test));
+        assert_ne!(all, (0..10).collect::<Vec<_>>());
+        all.sort_unstable();
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+
+        // the same seed gives the same split
+        let (a, b) = train_test_split(&Range(10), 0.5, &mut StdRng::seed_from_u64(1));
+        let (c, d) = train_test_split(&Range(10), 0.5, &mut StdRng::seed_from_u64(1));
+        assert_eq!(a.indices, c.indices);
+        assert_eq!(b.indices, d.indices);
+
+        let (train, test) = train_test_split(&Range(3), 1.0, &mut rng);
+        assert!(train.is_empty());
+        assert_eq!(test.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid split fractions")]
+    fn test_split_too_large() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let _ = train_val_test_split(&Range(10), 0.6, 0.6, &mut rng);
+    }
+
+    #[test]
+    fn test_k_fold() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let folds = k_fold(&Range(11), 3, &mut rng);
+        assert_eq!(folds.len(), 3);
+
+        let mut validated = Vec::new();
+        for (train, val) in folds.iter() {
+            assert!([3, 4].contains(&val.len()));
+            let mut all = labels(train);
+            all.extend(labels(val));
+            all.sort_unstable();
+            assert_eq!(all, (0..11).collect::<Vec<_>>());
+            validated.extend(labels(val));
+        }
+        validated.sort_unstable();
+        assert_eq!(validated, (0..11).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "k must be in 2..=3")]
+    fn test_k_fold_too_many_folds() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let _ = k_fold(&Range(3), 4, &mut rng);
+    }
+}
@@ -0,0 +1,336 @@
+//! Reading columns of [Apache Arrow](https://arrow.apache.org/) record batches and Parquet files
+//! into [Tensor2D]s of features.
+//!
+//! Each column is cast to `f32` as a whole with `arrow_cast`, and then copied into the tensor, so
+//! any numeric (or numeric string) column can be read without a loop over the rows.
+
+use crate::prelude::*;
+use arrow_array::{cast::AsArray, types::Float32Type, RecordBatch};
+use arrow_cast::{cast_with_options, CastOptions};
+use arrow_schema::{ArrowError, DataType};
+use std::error::Error;
+
+#[cfg(feature = "parquet")]
+use parquet::{
+    arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder},
+    arrow::ProjectionMask,
+    errors::ParquetError,
+};
+
+/// Reads `columns` of a [RecordBatch] with exactly `B` rows into a [Tensor2D] with shape (B, N).
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use std::sync::Arc;
+/// use arrow_array::{Float64Array, Int32Array, RecordBatch};
+/// let batch = RecordBatch::try_from_iter([
+///     ("age", Arc::new(Int32Array::from(vec![31, 45])) as _),
+///     ("income", Arc::new(Float64Array::from(vec![1.5, 2.5])) as _),
+/// ])
+/// .unwrap();
+/// let x: Tensor2D<2, 2> = record_batch_to_tensor(&batch, &["income", "age"]).unwrap();
+/// assert_eq!(x.data(), &[[1.5, 31.0], [2.5, 45.0]]);
+/// ```
+pub fn record_batch_to_tensor<const B: usize, const N: usize>(
+    batch: &RecordBatch,
+    columns: &[&str; N],
+) -> Result<Tensor2D<B, N>, TabularError> {
+    if batch.num_rows() != B {
+        return Err(TabularError::RowMismatch {
+            expected: B,
+            found: batch.num_rows(),
+        });
+    }
+    let mut t = Tensor2D::zeros();
+    copy_columns(batch, columns, 0, t.mut_data())?;
+    Ok(t)
+}
+
+/// Copies the rows `offset..offset + out.len()` of `columns` into `out`.
+fn copy_columns<S: AsRef<str>, const N: usize>(
+    batch: &RecordBatch,
+    columns: &[S; N],
+    offset: usize,
+    out: &mut [[f32; N]],
+) -> Result<(), TabularError> {
+    // NOTE: unsafe casts fail on values that don't fit instead of turning them into nulls
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    for (j, name) in columns.iter().enumerate() {
+        let name = name.as_ref();
+        let col = batch
+            .column_by_name(name)
+            .ok_or_else(|| TabularError::MissingColumn(name.into()))?
+            .slice(offset, out.len());
+        if col.null_count() > 0 {
+            return Err(TabularError::NullValues(name.into()));
+        }
+        let col = cast_with_options(&col, &DataType::Float32, &options)?;
+        let values = col.as_primitive::<Float32Type>().values();
+        for (row, &v) in out.iter_mut().zip(values.iter()) {
+            row[j] = v;
+        }
+    }
+    Ok(())
+}
+
+/// An iterator that regroups the rows of a stream of [RecordBatch]es into [Tensor2D]s with shape
+/// (B, N), regardless of how many rows each record batch has. Like [SubsetIterator], the last
+/// rows are dropped if there are less than `B` of them.
+///
+/// This works with any iterator of record batches, e.g. the readers of `arrow-ipc` and `arrow-csv`,
+/// or [read_parquet()].
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// let reader = arrow_ipc::reader::FileReader::try_new(std::fs::File::open("data.arrow")?, None)?;
+/// for x in TabularBatches::<_, 32, 4>::new(reader, ["a", "b", "c", "d"]) {
+///     let x: Tensor2D<32, 4> = x?;
+/// }
+/// ```
+pub struct TabularBatches<I, const B: usize, const N: usize> {
+    batches: I,
+    columns: [String; N],
+    current: Option<RecordBatch>,
+    offset: usize,
+}
+
+impl<I, const B: usize, const N: usize> TabularBatches<I, B, N> {
+    pub fn new<S: Into<String>>(batches: I, columns: [S; N]) -> Self {
+        Self {
+            batches,
+            columns: columns.map(Into::into),
+            current: None,
+            offset: 0,
+        }
+    }
+}
+
+impl<I, const B: usize, const N: usize> Iterator for TabularBatches<I, B, N>
+where
+    I: Iterator<Item = Result<RecordBatch, ArrowError>>,
+{
+    type Item = Result<Tensor2D<B, N>, TabularError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut t = Tensor2D::zeros();
+        let mut filled = 0;
+        while filled < B {
+            let batch = match &self.current {
+                Some(batch) if self.offset < batch.num_rows() => batch,
+                _ => {
+                    match self.batches.next()? {
+                        Ok(batch) => self.current = Some(batch),
+                        Err(e) => return Some(Err(e.into())),
+                    }
+                    self.offset = 0;
+                    continue;
+                }
+            };
+            let n = (B - filled).min(batch.num_rows() - self.offset);
+            let out = &mut t.mut_data()[filled..filled + n];
+            if let Err(e) = copy_columns(batch, &self.columns, self.offset, out) {
+                return Some(Err(e));
+            }
+            self.offset += n;
+            filled += n;
+        }
+        Some(Ok(t))
+    }
+}
+
+/// Reads `columns` of the Parquet file at `path` in batches of `B` rows. Only the column chunks
+/// of `columns` are read from the file.
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// for x in read_parquet::<_, 64, 3>("train.parquet", ["x0", "x1", "x2"])? {
+///     let x: Tensor2D<64, 3> = x?;
+/// }
+/// ```
+#[cfg(feature = "parquet")]
+pub fn read_parquet<P: AsRef<std::path::Path>, const B: usize, const N: usize>(
+    path: P,
+    columns: [&str; N],
+) -> Result<TabularBatches<ParquetRecordBatchReader, B, N>, TabularError> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(path)?)?;
+    let mask = ProjectionMask::columns(builder.parquet_schema(), columns);
+    let reader = builder.with_projection(mask).with_batch_size(B).build()?;
+    Ok(TabularBatches::new(reader, columns))
+}
+
+/// Error that can happen while reading tabular data into tensors.
+#[derive(Debug)]
+pub enum TabularError {
+    /// Something went wrong with reading the file.
+    Io(std::io::Error),
+
+    /// Something went wrong with reading or casting the arrow data.
+    Arrow(ArrowError),
+
+    /// Something went wrong with reading the Parquet file.
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetError),
+
+    /// The data doesn't have a column with this name.
+    MissingColumn(String),
+
+    /// The column contains null values, which can't be stored in a tensor.
+    NullValues(String),
+
+    /// The record batch has a different number of rows than the tensor.
+    RowMismatch { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for TabularError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TabularError::Io(err) => write!(fmt, "{}", err),
+            TabularError::Arrow(err) => write!(fmt, "{}", err),
+            #[cfg(feature = "parquet")]
+            TabularError::Parquet(err) => write!(fmt, "{}", err),
+            TabularError::MissingColumn(name) => write!(fmt, "missing column {}", name),
+            TabularError::NullValues(name) => write!(fmt, "column {} contains nulls", name),
+            TabularError::RowMismatch { expected, found } => {
+                write!(fmt, "expected {} rows, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for TabularError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TabularError::Io(err) => Some(err),
+            TabularError::Arrow(err) => Some(err),
+            #[cfg(feature = "parquet")]
+            TabularError::Parquet(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TabularError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ArrowError> for TabularError {
+    fn from(e: ArrowError) -> Self {
+        Self::Arrow(e)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<ParquetError> for TabularError {
+    fn from(e: ParquetError) -> Self {
+        Self::Parquet(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{ArrayRef, Float32Array, Float64Array, Int64Array, StringArray};
+    use std::sync::Arc;
+
+    fn batch(a: Vec<i64>, b: Vec<f64>) -> Result<RecordBatch, ArrowError> {
+        RecordBatch::try_from_iter([
+            ("a", Arc::new(Int64Array::from(a)) as ArrayRef),
+            ("b", Arc::new(Float64Array::from(b)) as ArrayRef),
+        ])
+    }
+
+    #[test]
+    fn test_record_batch_to_tensor() {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "f",
+                Arc::new(Float32Array::from(vec![0.5, 1.5])) as ArrayRef,
+            ),
+            (
+                "s",
+                Arc::new(StringArray::from(vec!["1", "-2.5"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let t: Tensor2D<2, 3> = record_batch_to_tensor(&batch, &["s", "f", "s"]).expect("");
+        assert_eq!(t.data(), &[[1.0, 0.5, 1.0], [-2.5, 1.5, -2.5]]);
+
+        let r: Result<Tensor2D<3, 1>, _> = record_batch_to_tensor(&batch, &["f"]);
+        assert!(matches!(
+            r,
+            Err(TabularError::RowMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_tabular_batches_regroups_rows() {
+        let batches = vec![
+            batch(vec![1, 2, 3], vec![0.1, 0.2, 0.3]),
+            batch(vec![4], vec![0.4]),
+            batch(vec![5, 6, 7], vec![0.5, 0.6, 0.7]),
+        ];
+        let tensors: Vec<Tensor2D<2, 2>> = TabularBatches::new(batches.into_iter(), ["b", "a"])
+            .collect::<Result<_, _>>()
+            .expect("");
+        assert_eq!(tensors.len(), 3);
+        assert_eq!(tensors[0].data(), &[[0.1, 1.0], [0.2, 2.0]]);
+        assert_eq!(tensors[1].data(), &[[0.3, 3.0], [0.4, 4.0]]);
+        assert_eq!(tensors[2].data(), &[[0.5, 5.0], [0.6, 6.0]]);
+    }
+
+    #[test]
+    fn test_tabular_errors() {
+        let batches = vec![batch(vec![1, 2], vec![0.1, 0.2])];
+        let mut it = TabularBatches::<_, 2, 1>::new(batches.into_iter(), ["c"]);
+        assert!(matches!(it.next(), Some(Err(TabularError::MissingColumn(c))) if c == "c"));
+
+        let batch = RecordBatch::try_from_iter([(
+            "a",
+            Arc::new(Int64Array::from(vec![Some(1), None])) as ArrayRef,
+        )])
+        .unwrap();
+        let r: Result<Tensor2D<2, 1>, _> = record_batch_to_tensor(&batch, &["a"]);
+        assert!(matches!(r, Err(TabularError::NullValues(_))));
+
+        let batch = RecordBatch::try_from_iter([(
+            "a",
+            Arc::new(StringArray::from(vec!["1", "x"])) as ArrayRef,
+        )])
+        .unwrap();
+        let r: Result<Tensor2D<2, 1>, _> = record_batch_to_tensor(&batch, &["a"]);
+        assert!(matches!(r, Err(TabularError::Arrow(_))));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_read_parquet() {
+        use parquet::arrow::ArrowWriter;
+        use tempfile::NamedTempFile;
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        let batch = batch((0..5).collect(), (0..5).map(|i| i as f64 * 0.5).collect()).unwrap();
+        let mut writer =
+            ArrowWriter::try_new(file.reopen().unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let tensors: Vec<Tensor2D<2, 2>> = read_parquet(file.path(), ["a", "b"])
+            .expect("")
+            .collect::<Result<_, _>>()
+            .expect("");
+        assert_eq!(tensors.len(), 2);
+        assert_eq!(tensors[0].data(), &[[0.0, 0.0], [1.0, 0.5]]);
+        assert_eq!(tensors[1].data(), &[[2.0, 1.0], [3.0, 1.5]]);
+    }
+}
@@ -0,0 +1,344 @@
+//! Simple text preprocessing: splitting text into tokens with a [Tokenizer], and mapping tokens to
+//! indices with a [Vocab].
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Splits text into tokens, and joins tokens back into text.
+pub trait Tokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String>;
+    fn detokenize<S: AsRef<str>>(&self, tokens: &[S]) -> String;
+}
+
+/// Splits text at whitespace.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let tokenizer = WhitespaceTokenizer { lowercase: true };
+/// assert_eq!(tokenizer.tokenize("Hello  world\n"), ["hello", "world"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhitespaceTokenizer {
+    pub lowercase: bool,
+}
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split_whitespace()
+            .map(|w| match self.lowercase {
+                true => w.to_lowercase(),
+                false => w.to_string(),
+            })
+            .collect()
+    }
+
+    fn detokenize<S: AsRef<str>>(&self, tokens: &[S]) -> String {
+        let tokens: Vec<&str> = tokens.iter().map(AsRef::as_ref).collect();
+        tokens.join(" ")
+    }
+}
+
+/// Byte pair encoding, which splits words into subwords that were frequent in the training
+/// corpus. Words are split at whitespace first, and the last subword of each word ends with
+/// [Bpe::END_OF_WORD], so the text can be restored with [Tokenizer::detokenize()].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let bpe = Bpe::train("low lower lowest slow slower", 10);
+/// let tokens = bpe.tokenize("lowly");
+/// assert_eq!(tokens, ["low", "l", "y</w>"]);
+/// assert_eq!(bpe.detokenize(&tokens), "lowly");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Bpe {
+    /// The merges in the order they were learned, which is also the order they are applied in.
+    pub merges: Vec<(String, String)>,
+    ranks: HashMap<(String, String), usize>,
+}
+
+impl Bpe {
+    /// The suffix of the last subword of every word.
+    pub const END_OF_WORD: &'static str = "</w>";
+
+    /// Creates a tokenizer that applies `merges` in order.
+    pub fn new(merges: Vec<(String, String)>) -> Self {
+        let ranks = merges
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, m)| (m, i))
+            .collect();
+        Self { merges, ranks }
+    }
+
+    /// Learns up to `num_merges` merges from `corpus`, by repeatedly merging the most frequent
+    /// pair of adjacent subwords. Ties are broken alphabetically, so training is deterministic.
+    pub fn train(corpus: &str, num_merges: usize) -> Self {
+        let mut words: BTreeMap<Vec<String>, usize> = BTreeMap::new();
+        for word in corpus.split_whitespace() {
+            *words.entry(split_chars(word)).or_default() += 1;
+        }
+
+        let mut merges = Vec::with_capacity(num_merges);
+        while merges.len() < num_merges {
+            let mut pairs: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+            for (symbols, &count) in words.iter() {
+                for pair in symbols.windows(2) {
+                    *pairs.entry((&pair[0], &pair[1])).or_default() += count;
+                }
+            }
+            // NOTE: max_by_key returns the last maximum, so reverse to get the first one
+            let best = pairs.into_iter().rev().max_by_key(|&(_, count)| count);
+            let Some(((a, b), _)) = best else {
+                break;
+            };
+            let pair = (a.to_string(), b.to_string());
+            words = words
+                .into_iter()
+                .map(|(symbols, count)| (merge(symbols, &pair), count))
+                .collect();
+            merges.push(pair);
+        }
+        Self::new(merges)
+    }
+
+    /// Splits a single word into subwords.
+    fn tokenize_word(&self, word: &str) -> Vec<String> {
+        let mut symbols = split_chars(word);
+        loop {
+            let best = symbols
+                .windows(2)
+                .filter_map(|p| self.ranks.get(&(p[0].clone(), p[1].clone())))
+                .min();
+            match best {
+                Some(&rank) => symbols = merge(symbols, &self.merges[rank]),
+                None => return symbols,
+            }
+        }
+    }
+}
+
+impl Tokenizer for Bpe {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split_whitespace()
+            .flat_map(|w| self.tokenize_word(w))
+            .collect()
+    }
+
+    fn detokenize<S: AsRef<str>>(&self, tokens: &[S]) -> String {
+        let text: String = tokens.iter().map(AsRef::as_ref).collect();
+        text.replace(Self::END_OF_WORD, " ").trim_end().to_string()
+    }
+}
+
+/// The characters of `word`, with [Bpe::END_OF_WORD] appended to the last one.
+fn split_chars(word: &str) -> Vec<String> {
+    let mut symbols: Vec<String> = word.chars().map(String::from).collect();
+    if let Some(last) = symbols.last_mut() {
+        last.push_str(Bpe::END_OF_WORD);
+    }
+    symbols
+}
+
+/// Merges every occurrence of `pair` in `symbols`.
+fn merge(symbols: Vec<String>, pair: &(String, String)) -> Vec<String> {
+    let mut out = Vec::with_capacity(symbols.len());
+    let mut symbols = symbols.into_iter().peekable();
+    while let Some(s) = symbols.next() {
+        if s == pair.0 && symbols.peek() == Some(&pair.1) {
+            out.push(s + &symbols.next().unwrap());
+        } else {
+            out.push(s);
+        }
+    }
+    out
+}
+
+/// A mapping between tokens and indices, e.g. for the rows of an embedding matrix.
+///
+/// Index [Vocab::PAD] is the padding token `<pad>`, and [Vocab::UNK] is `<unk>`, which unknown
+/// tokens are mapped to.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let tokenizer = WhitespaceTokenizer::default();
+/// let vocab = Vocab::build(tokenizer.tokenize("a b b c c c"), 1, None);
+/// assert_eq!(vocab.len(), 5);
+///
+/// let ids: [usize; 4] = vocab.encode_padded(&tokenizer.tokenize("c a d"));
+/// assert_eq!(ids, [2, 4, Vocab::UNK, Vocab::PAD]);
+/// assert_eq!(vocab.decode(&ids), ["c", "a", "<unk>"]);
+///
+/// // the indices can select the rows of an embedding matrix
+/// let embedding: Tensor2D<5, 8> = TensorCreator::zeros();
+/// let x: Tensor2D<4, 8> = embedding.select(&ids);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vocab {
+    tokens: Vec<String>,
+    ids: HashMap<String, usize>,
+}
+
+impl Vocab {
+    pub const PAD: usize = 0;
+    pub const UNK: usize = 1;
+
+    /// Creates a vocabulary of `<pad>`, `<unk>`, and then `tokens` in order. Duplicates are
+    /// skipped.
+    pub fn new<I: IntoIterator<Item = S>, S: Into<String>>(tokens: I) -> Self {
+        let mut vocab = Self {
+            tokens: Vec::new(),
+            ids: HashMap::new(),
+        };
+        for token in ["<pad>".to_string(), "<unk>".to_string()]
+            .into_iter()
+            .chain(tokens.into_iter().map(Into::into))
+        {
+            if !vocab.ids.contains_key(&token) {
+                vocab.ids.insert(token.clone(), vocab.tokens.len());
+                vocab.tokens.push(token);
+            }
+        }
+        vocab
+    }
+
+    /// Builds a vocabulary of the tokens that occur at least `min_freq` times in `tokens`, sorted
+    /// by how often they occur. With `max_size`, only the most frequent tokens are kept, so that
+    /// the vocabulary (including `<pad>` and `<unk>`) has at most `max_size` tokens.
+    pub fn build<I: IntoIterator<Item = S>, S: AsRef<str>>(
+        tokens: I,
+        min_freq: usize,
+        max_size: Option<usize>,
+    ) -> Self {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for token in tokens {
+            *counts.entry(token.as_ref().to_string()).or_default() += 1;
+        }
+        let mut counts: Vec<(String, usize)> =
+            counts.into_iter().filter(|&(_, c)| c >= min_freq).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        if let Some(max_size) = max_size {
+            counts.truncate(max_size.saturating_sub(2));
+        }
+        Self::new(counts.into_iter().map(|(t, _)| t))
+    }
+
+    /// The number of tokens, including `<pad>` and `<unk>`.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// The index of `token`, or [Vocab::UNK] if it's not in the vocabulary.
+    pub fn id(&self, token: &str) -> usize {
+        self.ids.get(token).copied().unwrap_or(Self::UNK)
+    }
+
+    /// The token at index `id`, if there is one.
+    pub fn token(&self, id: usize) -> Option<&str> {
+        self.tokens.get(id).map(String::as_str)
+    }
+
+    /// Maps every token to its index.
+    pub fn encode<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<usize> {
+        tokens.iter().map(|t| self.id(t.as_ref())).collect()
+    }
+
+    /// Maps the first `S` tokens to their indices, and pads the rest with [Vocab::PAD].
+    pub fn encode_padded<const S: usize, T: AsRef<str>>(&self, tokens: &[T]) -> [usize; S] {
+        let mut ids = [Self::PAD; S];
+        for (id, t) in ids.iter_mut().zip(tokens.iter()) {
+            *id = self.id(t.as_ref());
+        }
+        ids
+    }
+
+    /// Maps indices back to tokens, skipping padding. Indices outside of the vocabulary are
+    /// decoded as `<unk>`.
+    pub fn decode(&self, ids: &[usize]) -> Vec<&str> {
+        ids.iter()
+            .filter(|&&id| id != Self::PAD)
+            .map(|&id| self.token(id).unwrap_or(&self.tokens[Self::UNK]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitespace_tokenizer() {
+        let tokenizer = WhitespaceTokenizer::default();
+        let tokens = tokenizer.tokenize(" The cat\tsat.\n");
+        assert_eq!(tokens, ["The", "cat", "sat."]);
+        assert_eq!(tokenizer.detokenize(&tokens), "The cat sat.");
+    }
+
+    #[test]
+    fn test_vocab_build() {
+        let tokens = "b a c b c c d".split(' ');
+        let vocab = Vocab::build(tokens.clone(), 1, None);
+        assert_eq!(vocab.len(), 6);
+        assert_eq!(
+            (0..6).map(|i| vocab.token(i).unwrap()).collect::<Vec<_>>(),
+            ["<pad>", "<unk>", "c", "b", "a", "d"]
+        );
+
+        let vocab = Vocab::build(tokens.clone(), 2, None);
+        assert_eq!(vocab.len(), 4);
+        assert_eq!(vocab.id("a"), Vocab::UNK);
+
+        let vocab = Vocab::build(tokens, 1, Some(3));
+        assert_eq!(vocab.len(), 3);
+        assert_eq!(vocab.id("c"), 2);
+        assert_eq!(vocab.id("b"), Vocab::UNK);
+    }
+
+    #[test]
+    fn test_vocab_encode_decode() {
+        let vocab = Vocab::new(["hello", "world", "hello"]);
+        assert_eq!(vocab.len(), 4);
+        assert_eq!(vocab.encode(&["world", "hello", "?"]), [3, 2, Vocab::UNK]);
+
+        let ids: [usize; 2] = vocab.encode_padded(&["world", "hello", "world"]);
+        assert_eq!(ids, [3, 2]);
+        let ids: [usize; 4] = vocab.encode_padded(&["world"]);
+        assert_eq!(ids, [3, 0, 0, 0]);
+
+        assert_eq!(vocab.decode(&[2, 0, 3, 9]), ["hello", "world", "<unk>"]);
+    }
+
+    #[test]
+    fn test_bpe_train() {
+        let bpe = Bpe::train("aaab aab ab", 10);
+        assert_eq!(
+            bpe.merges[..2],
+            [
+                ("a".to_string(), "a".to_string()),
+                ("a".to_string(), "b</w>".to_string())
+            ]
+        );
+        // stops when every word is a single subword
+        let bpe = Bpe::train("ab ab", 10);
+        assert_eq!(bpe.merges, [("a".to_string(), "b</w>".to_string())]);
+    }
+
+    #[test]
+    fn test_bpe_roundtrip() {
+        let corpus = "the quick brown fox jumps over the lazy dog the end";
+        let bpe = Bpe::train(corpus, 20);
+        let tokens = bpe.tokenize("the fox  is quick");
+        assert_eq!(&tokens[..2], ["the</w>", "fox</w>"]);
+        assert_eq!(bpe.detokenize(&tokens), "the fox is quick");
+
+        // the merges can be stored and loaded again
+        let loaded = Bpe::new(bpe.merges.clone());
+        assert_eq!(loaded.tokenize("the fox  is quick"), tokens);
+    }
+}
@@ -1,6 +1,7 @@
 use super::Cpu;
 use crate::arrays::CountElements;
-use std::alloc::{alloc_zeroed, Layout};
+use alloc::alloc::{alloc_zeroed, Layout};
+use alloc::boxed::Box;
 
 /// Allocate an Nd array on the heap.
 pub trait AllocateZeros {
@@ -9,12 +10,19 @@ pub trait AllocateZeros {
 }
 
 impl AllocateZeros for Cpu {
-    /// Allocates using [alloc_zeroed].
+    /// Allocates using [alloc_zeroed], or reuses an array from the active [super::Arena].
     fn zeros<T: CountElements>() -> Box<T> {
         // TODO is this function safe for any T?
         // TODO move to using safe code once we can allocate an array directly on the heap.
         let layout = Layout::new::<T>();
         debug_assert_eq!(layout.size(), T::NUM_BYTES);
+        crate::profile::count_allocation();
+        if let Some(ptr) = super::arena::take(layout) {
+            unsafe {
+                core::ptr::write_bytes(ptr.as_ptr(), 0, layout.size());
+                return Box::from_raw(ptr.as_ptr() as *mut T);
+            }
+        }
         unsafe {
             let ptr = alloc_zeroed(layout) as *mut T;
             Box::from_raw(ptr)
@@ -0,0 +1,265 @@
+//! Reusing the memory of intermediate arrays between operations and training steps.
+
+use std::alloc::{dealloc, Layout};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+/// The cached arrays of one size.
+#[derive(Debug, Default)]
+struct Blocks {
+    free: Vec<NonNull<u8>>,
+    /// How many arrays of this size had to be allocated because none were cached. At most this
+    /// many arrays are cached, so arrays that weren't allocated through the arena (e.g. by
+    /// [crate::tensor::TensorCreator::new()]) can't make it grow without bound.
+    num_allocated: usize,
+}
+
+type Pool = HashMap<Layout, Blocks>;
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Pool>> = const { RefCell::new(None) };
+}
+
+/// A cache of freed arrays that [crate::devices::AllocateZeros::zeros()] allocates from
+/// inside of [Arena::scope()], instead of asking the system allocator for new memory.
+///
+/// Every operation allocates a new array for its result, and [backward()](crate::tensor_ops::backward)
+/// allocates one for every gradient. Inside a scope, the temporary buffers of the forward pass
+/// and the arrays of dropped [crate::gradients::Gradients] are kept in the arena, and reused for
+/// the next arrays of the same size. Since a training step allocates the same arrays every time,
+/// after the first step most of them come from the arena.
+///
+/// The cached arrays are freed all at once with [Arena::clear()] or when the arena is dropped.
+/// Arrays that are still in use are never freed by the arena, so tensors and gradients can
+/// safely outlive the scope.
+///
+/// Only allocations on the thread that entered the scope use the arena.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # let mut rng = rand::thread_rng();
+/// let mut model: Linear<4, 2> = Default::default();
+/// let mut opt: Sgd<Linear<4, 2>> = Default::default();
+/// let mut arena = Arena::default();
+/// for _ in 0..10 {
+///     arena.scope(|| {
+///         let x: Tensor2D<8, 4> = Tensor2D::randn(&mut rng);
+///         let loss = model.forward(x.trace()).square().mean();
+///         opt.update(&mut model, loss.backward()).unwrap();
+///     });
+/// }
+/// assert!(arena.num_cached() > 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct Arena {
+    pool: Pool,
+}
+
+impl Arena {
+    /// Runs `f` with allocations served from this arena. Scopes can be nested, in which case
+    /// the innermost arena is used.
+    pub fn scope<R, F: FnOnce() -> R>(&mut self, f: F) -> R {
+        /// Gives the blocks back to the arena, even if `f` panics.
+        struct Exit<'a> {
+            arena: &'a mut Arena,
+            outer: Option<Pool>,
+        }
+
+        impl Drop for Exit<'_> {
+            fn drop(&mut self) {
+                let pool = ACTIVE.with(|active| active.replace(self.outer.take()));
+                self.arena.pool = pool.unwrap_or_default();
+            }
+        }
+
+        let pool = std::mem::take(&mut self.pool);
+        let outer = ACTIVE.with(|active| active.replace(Some(pool)));
+        let _exit = Exit { arena: self, outer };
+        f()
+    }
+
+    /// The number of arrays that are cached for reuse.
+    pub fn num_cached(&self) -> usize {
+        self.pool.values().map(|blocks| blocks.free.len()).sum()
+    }
+
+    /// The total size of the cached arrays in bytes.
+    pub fn cached_bytes(&self) -> usize {
+        self.pool
+            .iter()
+            .map(|(layout, blocks)| layout.size() * blocks.free.len())
+            .sum()
+    }
+
+    /// Frees all the cached arrays.
+    pub fn clear(&mut self) {
+        for (layout, blocks) in self.pool.drain() {
+            for ptr in blocks.free {
+                // SAFETY: blocks are only cached by `recycle()`, which got them from a `Box`
+                // with this layout
+                unsafe { dealloc(ptr.as_ptr(), layout) };
+            }
+        }
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Takes a cached block with `layout` from the active arena, if there is one.
+pub(super) fn take(layout: Layout) -> Option<NonNull<u8>> {
+    ACTIVE.with(|active| {
+        let mut active = active.borrow_mut();
+        let blocks = active.as_mut()?.entry(layout).or_default();
+        let ptr = blocks.free.pop();
+        if ptr.is_none() {
+            blocks.num_allocated += 1;
+        }
+        ptr
+    })
+}
+
+/// Drops `b` and gives its memory to the active arena. Without an active arena, this is the
+/// same as [drop()].
+pub(crate) fn recycle<T: ?Sized>(b: Box<T>) {
+    let layout = Layout::for_value(b.as_ref());
+    if layout.size() == 0 || !ACTIVE.with(|active| active.borrow().is_some()) {
+        return;
+    }
+    let ptr = Box::into_raw(b);
+    // SAFETY: `ptr` came from a box, so it is valid to drop, and its memory is not used afterwards
+    unsafe { std::ptr::drop_in_place(ptr) };
+    let ptr = NonNull::new(ptr as *mut u8).unwrap();
+    ACTIVE.with(|active| {
+        match active
+            .borrow_mut()
+            .as_mut()
+            .and_then(|p| p.get_mut(&layout))
+        {
+            Some(blocks) if blocks.free.len() < blocks.num_allocated => blocks.free.push(ptr),
+            // SAFETY: `ptr` was allocated by a box with `layout`
+            _ => unsafe { dealloc(ptr.as_ptr(), layout) },
+        }
+    });
+}
+
+/// Moves the data of `b` into an [Rc], and gives the memory of `b` to the active arena.
+pub(crate) fn into_rc<T>(b: Box<T>) -> Rc<T> {
+    if !ACTIVE.with(|active| active.borrow().is_some()) {
+        return b.into();
+    }
+    let mut rc = Rc::<T>::new_uninit();
+    // SAFETY: `rc` was just created so it is unique, and is initialized by copying all of `b`,
+    // which isn't used afterwards because it is only deallocated (without dropping) by `recycle()`
+    unsafe {
+        let dst = Rc::get_mut(&mut rc).unwrap().as_mut_ptr();
+        std::ptr::copy_nonoverlapping(b.as_ref() as *const T, dst, 1);
+        recycle(Box::from_raw(
+            Box::into_raw(b) as *mut std::mem::ManuallyDrop<T>
+        ));
+        rc.assume_init()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use rand::prelude::{SeedableRng, StdRng};
+
+    #[test]
+    fn test_reuses_recycled_arrays() {
+        let mut arena = Arena::default();
+        arena.scope(|| {
+            let mut a: Box<[f32; 8]> = Cpu::zeros();
+            a[3] = 1.0;
+            let ptr = a.as_ptr();
+            recycle(a);
+
+            let b: Box<[f32; 8]> = Cpu::zeros();
+            assert_eq!(b.as_ptr(), ptr);
+            assert_eq!(b.as_ref(), &[0.0; 8]);
+
+            // a different size is allocated separately
+            let c: Box<[f32; 4]> = Cpu::zeros();
+            recycle(c);
+            recycle(b);
+        });
+        assert_eq!(arena.num_cached(), 2);
+        assert_eq!(arena.cached_bytes(), 48);
+
+        // outside of a scope the arena isn't used
+        let a: Box<[f32; 8]> = Cpu::zeros();
+        recycle(a);
+        assert_eq!(arena.num_cached(), 2);
+
+        arena.clear();
+        assert_eq!(arena.num_cached(), 0);
+    }
+
+    #[test]
+    fn test_only_caches_allocated_arrays() {
+        let mut arena = Arena::default();
+        arena.scope(|| {
+            let a: Box<[f32; 2]> = Cpu::zeros();
+            recycle(a);
+            recycle(Box::new([1.0f32; 2]));
+            recycle(Box::new([1.0f32; 3]));
+        });
+        assert_eq!(arena.num_cached(), 1);
+    }
+
+    #[test]
+    fn test_nested_scopes() {
+        let mut outer = Arena::default();
+        let mut inner = Arena::default();
+        outer.scope(|| {
+            inner.scope(|| recycle::<[f32; 3]>(Cpu::zeros()));
+            recycle::<[f32; 2]>(Cpu::zeros());
+        });
+        assert_eq!(outer.cached_bytes(), 8);
+        assert_eq!(inner.cached_bytes(), 12);
+    }
+
+    #[test]
+    fn test_training_steps_reuse_gradients() {
+        type Model = (Linear<5, 8>, ReLU, Linear<8, 3>);
+
+        fn step(model: &mut Model, opt: &mut Sgd<Model>, x: Tensor2D<4, 5>) -> f32 {
+            let loss = model.forward(x.traced()).square().mean();
+            let loss_value = *loss.data();
+            opt.update(model, loss.backward()).unwrap();
+            loss_value
+        }
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor2D<4, 5> = TensorCreator::randn(&mut rng);
+        let mut expected_model = model.clone();
+        let mut opt: Sgd<Model> = Default::default();
+        let mut expected_opt: Sgd<Model> = Default::default();
+
+        let mut arena = Arena::default();
+        let mut num_cached = Vec::new();
+        for _ in 0..6 {
+            let loss = arena.scope(|| step(&mut model, &mut opt, x.clone()));
+            assert_eq!(
+                loss,
+                step(&mut expected_model, &mut expected_opt, x.clone())
+            );
+            assert_eq!(model.2.weight.data(), expected_model.2.weight.data());
+            num_cached.push(arena.num_cached());
+        }
+
+        // after a few steps, the arena has the arrays that a step needs, so it doesn't grow
+        assert!(num_cached[3] > 0);
+        assert_eq!(num_cached[3..], [num_cached[3]; 3]);
+    }
+}
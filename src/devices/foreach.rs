@@ -10,6 +10,10 @@ use crate::arrays::CountElements;
 /// - [ForEachElement::foreach_mrr()], which takes 1 mut array and 2 ref arrays
 /// - [ForEachElement::foreach_mmm()], which takes 3 mut arrays
 ///
+/// `f` is called on the elements in order on the calling thread, even with the `rayon` feature,
+/// since it may have state (e.g. the rng of [crate::nn::Dropout]). Elementwise operations that
+/// should run in parallel use [super::SimdElementwise] instead.
+///
 /// Examples:
 /// ```rust
 /// # use dfdx::prelude::*;
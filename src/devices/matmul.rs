@@ -1,3 +1,4 @@
+use super::parallel::batches;
 use super::Cpu;
 
 #[cfg(feature = "cblas")]
@@ -6,7 +7,7 @@ use cblas_sys::{
     CblasRowMajor as RowMajor, CblasTrans as Tr,
 };
 
-pub trait Transpose {
+pub trait Transpose: Send + Sync {
     type T: Transpose<T = Self>;
 }
 
@@ -116,30 +117,22 @@ where
 {
     /// Broadcast `b` `BATCH` times.
     fn mm(a: &[[[f32; K]; M]; BATCH], b: &[[f32; N]; K], c: &mut [[[f32; N]; M]; BATCH]) {
-        for i in 0..BATCH {
-            Self::mm(&a[i], b, &mut c[i]);
-        }
+        batches(c, a, |_, c, a| Self::mm(a, b, c));
     }
 
     /// Broadcast `b` `BATCH` times.
     fn mm_at(a: &[[[f32; M]; K]; BATCH], b: &[[f32; N]; K], c: &mut [[[f32; N]; M]; BATCH]) {
-        for i in 0..BATCH {
-            Self::mm_at(&a[i], b, &mut c[i]);
-        }
+        batches(c, a, |_, c, a| Self::mm_at(a, b, c));
     }
 
     /// Broadcast `b` `BATCH` times.
     fn mm_bt(a: &[[[f32; K]; M]; BATCH], b: &[[f32; K]; N], c: &mut [[[f32; N]; M]; BATCH]) {
-        for i in 0..BATCH {
-            Self::mm_bt(&a[i], b, &mut c[i]);
-        }
+        batches(c, a, |_, c, a| Self::mm_bt(a, b, c));
     }
 
     /// Broadcast `b` `BATCH` times.
     fn mm_atct(a: &[[[f32; M]; K]; BATCH], b: &[[f32; N]; K], c: &mut [[[f32; M]; N]; BATCH]) {
-        for i in 0..BATCH {
-            Self::mm_atct(&a[i], b, &mut c[i]);
-        }
+        batches(c, a, |_, c, a| Self::mm_atct(a, b, c));
     }
 }
 
@@ -189,30 +182,22 @@ where
 {
     /// Batched matmul
     fn mm(a: &[A; BATCH], b: &[B; BATCH], c: &mut [C; BATCH]) {
-        for i in 0..BATCH {
-            Self::mm(&a[i], &b[i], &mut c[i]);
-        }
+        batches(c, a, |i, c, a| Self::mm(a, &b[i], c));
     }
 
     /// Batched matmul
     fn mm_at(a: &[A::T; BATCH], b: &[B; BATCH], c: &mut [C; BATCH]) {
-        for i in 0..BATCH {
-            Self::mm_at(&a[i], &b[i], &mut c[i]);
-        }
+        batches(c, a, |i, c, a| Self::mm_at(a, &b[i], c));
     }
 
     /// Batched matmul
     fn mm_bt(a: &[A; BATCH], b: &[B::T; BATCH], c: &mut [C; BATCH]) {
-        for i in 0..BATCH {
-            Self::mm_bt(&a[i], &b[i], &mut c[i]);
-        }
+        batches(c, a, |i, c, a| Self::mm_bt(a, &b[i], c));
     }
 
     /// Batched matmul
     fn mm_atct(a: &[A::T; BATCH], b: &[B; BATCH], c: &mut [C::T; BATCH]) {
-        for i in 0..BATCH {
-            Self::mm_atct(&a[i], &b[i], &mut c[i]);
-        }
+        batches(c, a, |i, c, a| Self::mm_atct(a, &b[i], c));
     }
 }
 
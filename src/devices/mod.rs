@@ -1,31 +1,58 @@
 //! Provides implementations for modifying Nd arrays on the [Cpu].
 
 mod allocate;
+#[cfg(feature = "std")]
+pub(crate) mod arena;
 mod broadcast;
 mod fill;
 mod foreach;
 mod matmul;
+pub(crate) mod parallel;
 mod reduce_all;
 mod reduce_axis;
 mod select;
+mod simd;
 
 pub use allocate::*;
+#[cfg(feature = "std")]
+pub use arena::Arena;
 pub use broadcast::*;
 pub use fill::*;
 pub use foreach::*;
 pub use matmul::*;
+pub use parallel::PARALLEL_THRESHOLD;
 pub use reduce_all::*;
 pub use reduce_axis::*;
 pub use select::*;
+pub use simd::*;
+pub(crate) use simd::{flat, flat_mut};
 
-use std::ops::*;
+/// Without `std` there is no [Arena], so arrays are always allocated and freed directly.
+#[cfg(not(feature = "std"))]
+pub(crate) mod arena {
+    use alloc::{boxed::Box, rc::Rc};
+    use core::{alloc::Layout, ptr::NonNull};
+
+    pub(super) fn take(_: Layout) -> Option<NonNull<u8>> {
+        None
+    }
+
+    pub(crate) fn recycle<T: ?Sized>(_: Box<T>) {}
+
+    pub(crate) fn into_rc<T>(b: Box<T>) -> Rc<T> {
+        b.into()
+    }
+}
+
+use alloc::boxed::Box;
+use core::ops::*;
 
 /// The CPU device
 pub struct Cpu;
 
 /// Represents something that can act on `T`.
 pub trait Device<T: crate::arrays::CountElements>:
-    FillElements<T> + ReduceAllElements<T> + AllocateZeros + ForEachElement<T>
+    FillElements<T> + ReduceAllElements<T> + AllocateZeros + ForEachElement<T> + SimdElementwise<T>
 {
     /// Allocate a new `T` and then store `f` applied to `t` in the new `T`. Uses [ForEachElement::foreach_mr].
     fn map<F: FnMut(&T::Dtype) -> T::Dtype>(t: &T, mut f: F) -> Box<T> {
@@ -34,29 +61,29 @@ pub trait Device<T: crate::arrays::CountElements>:
         out
     }
 
-    /// Computes `lhs += rhs`, using [ForEachElement::foreach_mr].
-    fn add(lhs: &mut T, rhs: &T)
-    where
-        T::Dtype: for<'r> AddAssign<&'r T::Dtype> + Copy,
-    {
-        Self::foreach_mr(lhs, rhs, &mut |l, r| l.add_assign(r))
+    /// Computes `lhs += rhs`, using [SimdElementwise::add_assign].
+    fn add(lhs: &mut T, rhs: &T) {
+        Self::add_assign(lhs, rhs)
     }
 
-    /// Computes `lhs -= rhs` using [ForEachElement::foreach_mr]
-    fn sub(lhs: &mut T, rhs: &T)
-    where
-        T::Dtype: for<'r> SubAssign<&'r T::Dtype> + Copy,
-    {
-        Self::foreach_mr(lhs, rhs, &mut |l, r| l.sub_assign(r))
+    /// Computes `lhs -= rhs` using [SimdElementwise::sub_assign]
+    fn sub(lhs: &mut T, rhs: &T) {
+        Self::sub_assign(lhs, rhs)
+    }
+
+    /// Computes `out += lhs * rhs` using [SimdElementwise::addmul_assign].
+    fn addmul(out: &mut T, lhs: &T, rhs: &T) {
+        Self::addmul_assign(out, lhs, rhs)
     }
 
-    /// Computes `out += lhs * rhs` using [ForEachElement::foreach_mrr].
-    fn addmul(out: &mut T, lhs: &T, rhs: &T)
+    /// Computes the euclidean norm of all elements `sqrt(sum(t^2))`, using [ReduceAllElements::reduce_all].
+    fn l2_norm(t: &T) -> T::Dtype
     where
-        T::Dtype: AddAssign,
-        for<'r> &'r T::Dtype: Mul<Output = T::Dtype>,
+        T::Dtype: num_traits::Float,
     {
-        Self::foreach_mrr(out, lhs, rhs, &mut |o, l, r| o.add_assign(l * r))
+        use num_traits::Float;
+        let squared = Self::map(t, |x| x.powi(2));
+        Self::reduce_all(squared.as_ref(), &mut |a, b| a + b).sqrt()
     }
 }
 
@@ -0,0 +1,127 @@
+//! Splitting large operations across the [rayon] thread pool, with the `rayon` feature.
+//! Without it, everything runs on the calling thread.
+
+/// With the `rayon` feature, [crate::devices::SimdElementwise] kernels and
+/// [crate::devices::ReduceAllElements] over arrays with at least this many elements are split
+/// into parts that are processed in parallel. The same goes for batched matrix multiplications
+/// and convolutions with at least this many output elements.
+///
+/// [crate::devices::ForEachElement] (and so ops like [crate::tensor_ops::map()]) always runs on
+/// the calling thread, because its closures may have state.
+///
+/// The operations run on rayon's global thread pool, which can be configured with
+/// `rayon::ThreadPoolBuilder`.
+pub const PARALLEL_THRESHOLD: usize = 1 << 15;
+
+/// Number of elements per chunk. A multiple of the simd lanes, so only the last chunk needs
+/// a scalar remainder.
+#[cfg(feature = "rayon")]
+const CHUNK_SIZE: usize = 1 << 13;
+
+/// Returns true if an operation with `num_elements` outputs should run in parallel.
+#[cfg(feature = "rayon")]
+pub(crate) fn is_parallel(num_elements: usize) -> bool {
+    num_elements >= PARALLEL_THRESHOLD
+}
+
+/// Calls `f` on chunks of `a`.
+pub(super) fn chunks_m<F>(a: &mut [f32], f: F)
+where
+    F: Fn(&mut [f32]) + Sync,
+{
+    #[cfg(feature = "rayon")]
+    if is_parallel(a.len()) {
+        use rayon::prelude::*;
+        a.par_chunks_mut(CHUNK_SIZE).for_each(&f);
+        return;
+    }
+    f(a)
+}
+
+/// Calls `f` on corresponding chunks of `a` and `b`.
+pub(super) fn chunks_mr<F>(a: &mut [f32], b: &[f32], f: F)
+where
+    F: Fn(&mut [f32], &[f32]) + Sync,
+{
+    #[cfg(feature = "rayon")]
+    if is_parallel(a.len()) {
+        use rayon::prelude::*;
+        a.par_chunks_mut(CHUNK_SIZE)
+            .zip(b.par_chunks(CHUNK_SIZE))
+            .for_each(|(a, b)| f(a, b));
+        return;
+    }
+    f(a, b)
+}
+
+/// Calls `f` on corresponding chunks of `a`, `b`, and `c`.
+pub(super) fn chunks_mrr<F>(a: &mut [f32], b: &[f32], c: &[f32], f: F)
+where
+    F: Fn(&mut [f32], &[f32], &[f32]) + Sync,
+{
+    #[cfg(feature = "rayon")]
+    if is_parallel(a.len()) {
+        use rayon::prelude::*;
+        a.par_chunks_mut(CHUNK_SIZE)
+            .zip(b.par_chunks(CHUNK_SIZE))
+            .zip(c.par_chunks(CHUNK_SIZE))
+            .for_each(|((a, b), c)| f(a, b, c));
+        return;
+    }
+    f(a, b, c)
+}
+
+/// Calls `f(i, &mut out[i], &a[i])` for every `i`, in parallel if `out` has enough elements
+/// in total (assuming `O` is an array of f32s).
+pub(crate) fn batches<O, A, F>(out: &mut [O], a: &[A], f: F)
+where
+    O: Send,
+    A: Sync,
+    F: Fn(usize, &mut O, &A) + Sync,
+{
+    #[cfg(feature = "rayon")]
+    if out.len() > 1 && is_parallel(core::mem::size_of_val(out) / core::mem::size_of::<f32>()) {
+        use rayon::prelude::*;
+        out.par_iter_mut()
+            .zip(a.par_iter())
+            .enumerate()
+            .for_each(|(i, (o, a))| f(i, o, a));
+        return;
+    }
+    for (i, (o, a)) in out.iter_mut().zip(a.iter()).enumerate() {
+        f(i, o, a);
+    }
+}
+
+#[cfg(all(test, feature = "rayon"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_cover_all_elements() {
+        let n = PARALLEL_THRESHOLD + CHUNK_SIZE / 2 + 3;
+        let mut a = vec![1.0; n];
+        let b: Vec<f32> = (0..n).map(|i| i as f32).collect();
+        chunks_mr(&mut a, &b, |a, b| {
+            assert!(a.len() <= CHUNK_SIZE);
+            a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+        });
+        for (i, a) in a.iter().enumerate() {
+            assert_eq!(*a, i as f32 + 1.0);
+        }
+    }
+
+    #[test]
+    fn test_batches() {
+        let mut out = vec![[0.0f32; 1024]; 64];
+        let a: Vec<[f32; 1024]> = (0..64).map(|i| [i as f32; 1024]).collect();
+        batches(&mut out, &a, |i, o, a| {
+            for (o, a) in o.iter_mut().zip(a) {
+                *o = a * i as f32;
+            }
+        });
+        for (i, o) in out.iter().enumerate() {
+            assert_eq!(o, &[(i * i) as f32; 1024]);
+        }
+    }
+}
@@ -1,22 +1,40 @@
 use super::Cpu;
 use crate::arrays::CountElements;
 
-/// Reduce an entire Nd array to 1 value
+/// Reduce an entire Nd array to 1 value. `f` has to be associative, since the order in which
+/// values are combined is not specified.
+///
+/// With the `rayon` feature, arrays with at least [super::PARALLEL_THRESHOLD] elements are
+/// reduced in parallel along their first axis.
 pub trait ReduceAllElements<T: CountElements> {
-    fn reduce_all<F: FnMut(T::Dtype, T::Dtype) -> T::Dtype>(inp: &T, f: &mut F) -> T::Dtype;
+    fn reduce_all<F: Fn(T::Dtype, T::Dtype) -> T::Dtype + Sync>(inp: &T, f: &mut F) -> T::Dtype;
 }
 
 impl ReduceAllElements<f32> for Cpu {
-    fn reduce_all<F: FnMut(f32, f32) -> f32>(inp: &f32, _f: &mut F) -> f32 {
+    fn reduce_all<F: Fn(f32, f32) -> f32 + Sync>(inp: &f32, _f: &mut F) -> f32 {
         *inp
     }
 }
 
-impl<T: CountElements, const M: usize> ReduceAllElements<[T; M]> for Cpu
+impl<T: CountElements + Sync, const M: usize> ReduceAllElements<[T; M]> for Cpu
 where
     Self: ReduceAllElements<T>,
+    T::Dtype: Send,
 {
-    fn reduce_all<F: FnMut(T::Dtype, T::Dtype) -> T::Dtype>(inp: &[T; M], f: &mut F) -> T::Dtype {
+    fn reduce_all<F: Fn(T::Dtype, T::Dtype) -> T::Dtype + Sync>(
+        inp: &[T; M],
+        f: &mut F,
+    ) -> T::Dtype {
+        #[cfg(feature = "rayon")]
+        if M > 1 && super::parallel::is_parallel(M * T::NUM_ELEMENTS) {
+            use rayon::prelude::*;
+            let f = &*f;
+            return inp
+                .par_iter()
+                .map(|inp_i| Self::reduce_all(inp_i, &mut &*f))
+                .reduce_with(f)
+                .unwrap();
+        }
         let mut result = None;
         for inp_i in inp.iter() {
             let partial = Self::reduce_all(inp_i, f);
@@ -50,6 +68,14 @@ mod tests {
         assert_eq!(Cpu::reduce_all(&t, &mut |a, b| a + b), 16.0);
     }
 
+    #[test]
+    fn test_reduce_large() {
+        let mut t = Box::new([[1.0; 1024]; 64]);
+        t[63][1023] = 2.0;
+        assert_eq!(Cpu::reduce_all(t.as_ref(), &mut |a, b| a + b), 65537.0);
+        assert_eq!(Cpu::reduce_all(t.as_ref(), &mut f32::max), 2.0);
+    }
+
     #[test]
     fn test_reduce_3d() {
         let t = [[[1.0, 2.0], [2.0, 3.0]], [[1.0, 0.5], [0.5, 1.0 / 3.0]]];
@@ -12,6 +12,7 @@
 
 use super::{AllocateZeros, Cpu, ForEachBroadcast1};
 use crate::arrays::CountElements;
+use alloc::boxed::Box;
 
 /// Reduce the `I`th axis of `T`. For example given T of shape (M, N, O),
 /// you can reduce:
@@ -0,0 +1,366 @@
+use super::parallel::{chunks_m, chunks_mr, chunks_mrr};
+use super::Cpu;
+use crate::arrays::CountElements;
+
+/// Elementwise kernels over all the elements of `f32` arrays, which are used by the hot
+/// elementwise tensor operations like [crate::tensor_ops::add()], [crate::tensor_ops::relu()],
+/// and [crate::tensor_ops::exp()].
+///
+/// On x86_64 cpus that support AVX2 (detected at runtime), 8 elements are processed at a time
+/// with AVX2 instructions. Otherwise, and for the remaining elements, a scalar loop is used.
+/// Both give exactly the same results, except for [SimdElementwise::exp_assign()] which is
+/// within a few ulps of [f32::exp()].
+///
+/// With the `rayon` feature, arrays with at least [crate::devices::PARALLEL_THRESHOLD] elements
+/// are processed in parallel.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let mut a = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+/// let b = [[1.0; 3]; 2];
+/// Cpu::sub_assign(&mut a, &b);
+/// assert_eq!(a, [[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]]);
+/// ```
+pub trait SimdElementwise<T> {
+    /// `a += b`
+    fn add_assign(a: &mut T, b: &T);
+
+    /// `a -= b`
+    fn sub_assign(a: &mut T, b: &T);
+
+    /// `a *= b`
+    fn mul_assign(a: &mut T, b: &T);
+
+    /// `out += lhs * rhs`
+    fn addmul_assign(out: &mut T, lhs: &T, rhs: &T);
+
+    /// `a = max(a, 0)`
+    fn relu_assign(a: &mut T);
+
+    /// `grad += (fx > 0) * result_grad`, the backward pass of relu given its output `fx`.
+    fn relu_backward(grad: &mut T, fx: &T, result_grad: &T);
+
+    /// `a = exp(a)`
+    fn exp_assign(a: &mut T);
+}
+
+/// Calls the avx2 version of a kernel if the cpu supports it, and the scalar version otherwise.
+/// Without `std` the cpu can't be checked at runtime, so avx2 is only used if it is enabled at
+/// compile time (e.g. with `-C target-feature=+avx2`).
+macro_rules! dispatch {
+    ($kernel:ident($($arg:expr),*)) => {{
+        #[cfg(all(target_arch = "x86_64", feature = "std"))]
+        if std::is_x86_feature_detected!("avx2") {
+            // SAFETY: avx2 is supported
+            return unsafe { avx2::$kernel($($arg),*) };
+        }
+        #[cfg(all(target_arch = "x86_64", not(feature = "std"), target_feature = "avx2"))]
+        // SAFETY: avx2 is enabled at compile time
+        return unsafe { avx2::$kernel($($arg),*) };
+        #[allow(unreachable_code)]
+        scalar::$kernel($($arg),*)
+    }};
+}
+
+impl<T: CountElements<Dtype = f32>> SimdElementwise<T> for Cpu {
+    fn add_assign(a: &mut T, b: &T) {
+        chunks_mr(flat_mut(a), flat(b), |a, b| dispatch!(add_assign(a, b)))
+    }
+
+    fn sub_assign(a: &mut T, b: &T) {
+        chunks_mr(flat_mut(a), flat(b), |a, b| dispatch!(sub_assign(a, b)))
+    }
+
+    fn mul_assign(a: &mut T, b: &T) {
+        chunks_mr(flat_mut(a), flat(b), |a, b| dispatch!(mul_assign(a, b)))
+    }
+
+    fn addmul_assign(out: &mut T, lhs: &T, rhs: &T) {
+        chunks_mrr(flat_mut(out), flat(lhs), flat(rhs), |o, l, r| {
+            dispatch!(addmul_assign(o, l, r))
+        })
+    }
+
+    fn relu_assign(a: &mut T) {
+        chunks_m(flat_mut(a), |a| dispatch!(relu_assign(a)))
+    }
+
+    fn relu_backward(grad: &mut T, fx: &T, result_grad: &T) {
+        chunks_mrr(flat_mut(grad), flat(fx), flat(result_grad), |g, fx, r| {
+            dispatch!(relu_backward(g, fx, r))
+        })
+    }
+
+    fn exp_assign(a: &mut T) {
+        chunks_m(flat_mut(a), |a| dispatch!(exp_assign(a)))
+    }
+}
+
+/// Views the elements of a nested f32 array as a slice.
+pub(crate) fn flat<T: CountElements<Dtype = f32>>(t: &T) -> &[f32] {
+    // SAFETY: nested arrays of f32 are contiguous, so `t` is `T::NUM_ELEMENTS` f32s.
+    unsafe { core::slice::from_raw_parts(t as *const T as *const f32, T::NUM_ELEMENTS) }
+}
+
+/// Views the elements of a nested f32 array as a mutable slice.
+pub(crate) fn flat_mut<T: CountElements<Dtype = f32>>(t: &mut T) -> &mut [f32] {
+    // SAFETY: see `flat()`
+    unsafe { core::slice::from_raw_parts_mut(t as *mut T as *mut f32, T::NUM_ELEMENTS) }
+}
+
+mod scalar {
+    #[cfg(not(feature = "std"))]
+    use num_traits::Float;
+
+    pub fn add_assign(a: &mut [f32], b: &[f32]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+    }
+
+    pub fn sub_assign(a: &mut [f32], b: &[f32]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a -= b);
+    }
+
+    pub fn mul_assign(a: &mut [f32], b: &[f32]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a *= b);
+    }
+
+    pub fn addmul_assign(out: &mut [f32], lhs: &[f32], rhs: &[f32]) {
+        for (o, (l, r)) in out.iter_mut().zip(lhs.iter().zip(rhs)) {
+            *o += l * r;
+        }
+    }
+
+    pub fn relu_assign(a: &mut [f32]) {
+        a.iter_mut().for_each(|a| *a = a.max(0.0));
+    }
+
+    pub fn relu_backward(grad: &mut [f32], fx: &[f32], result_grad: &[f32]) {
+        for (g, (fx, r)) in grad.iter_mut().zip(fx.iter().zip(result_grad)) {
+            *g += if fx > &0.0 { 1.0 } else { 0.0 } * r;
+        }
+    }
+
+    pub fn exp_assign(a: &mut [f32]) {
+        a.iter_mut().for_each(|a| *a = a.exp());
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", any(feature = "std", target_feature = "avx2")))]
+mod avx2 {
+    use super::scalar;
+    use core::arch::x86_64::*;
+
+    const LANES: usize = 8;
+
+    macro_rules! binary_assign {
+        ($name:ident, $op:ident) => {
+            #[target_feature(enable = "avx2")]
+            pub unsafe fn $name(a: &mut [f32], b: &[f32]) {
+                assert_eq!(a.len(), b.len());
+                let n = a.len() / LANES * LANES;
+                for i in (0..n).step_by(LANES) {
+                    let pa = a.as_mut_ptr().add(i);
+                    let vb = _mm256_loadu_ps(b.as_ptr().add(i));
+                    _mm256_storeu_ps(pa, $op(_mm256_loadu_ps(pa), vb));
+                }
+                scalar::$name(&mut a[n..], &b[n..]);
+            }
+        };
+    }
+
+    binary_assign!(add_assign, _mm256_add_ps);
+    binary_assign!(sub_assign, _mm256_sub_ps);
+    binary_assign!(mul_assign, _mm256_mul_ps);
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn addmul_assign(out: &mut [f32], lhs: &[f32], rhs: &[f32]) {
+        assert!(out.len() == lhs.len() && out.len() == rhs.len());
+        let n = out.len() / LANES * LANES;
+        for i in (0..n).step_by(LANES) {
+            let po = out.as_mut_ptr().add(i);
+            let vl = _mm256_loadu_ps(lhs.as_ptr().add(i));
+            let vr = _mm256_loadu_ps(rhs.as_ptr().add(i));
+            // NOTE: not fused, so the results are the same as the scalar version
+            let vo = _mm256_add_ps(_mm256_loadu_ps(po), _mm256_mul_ps(vl, vr));
+            _mm256_storeu_ps(po, vo);
+        }
+        scalar::addmul_assign(&mut out[n..], &lhs[n..], &rhs[n..]);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn relu_assign(a: &mut [f32]) {
+        let n = a.len() / LANES * LANES;
+        let zero = _mm256_setzero_ps();
+        for i in (0..n).step_by(LANES) {
+            let pa = a.as_mut_ptr().add(i);
+            // NOTE: returns the second operand for NaNs, like `f32::max`
+            _mm256_storeu_ps(pa, _mm256_max_ps(_mm256_loadu_ps(pa), zero));
+        }
+        scalar::relu_assign(&mut a[n..]);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn relu_backward(grad: &mut [f32], fx: &[f32], result_grad: &[f32]) {
+        assert!(grad.len() == fx.len() && grad.len() == result_grad.len());
+        let n = grad.len() / LANES * LANES;
+        let zero = _mm256_setzero_ps();
+        let one = _mm256_set1_ps(1.0);
+        for i in (0..n).step_by(LANES) {
+            let pg = grad.as_mut_ptr().add(i);
+            let vfx = _mm256_loadu_ps(fx.as_ptr().add(i));
+            let vr = _mm256_loadu_ps(result_grad.as_ptr().add(i));
+            let df = _mm256_and_ps(_mm256_cmp_ps::<_CMP_GT_OQ>(vfx, zero), one);
+            let vg = _mm256_add_ps(_mm256_loadu_ps(pg), _mm256_mul_ps(df, vr));
+            _mm256_storeu_ps(pg, vg);
+        }
+        scalar::relu_backward(&mut grad[n..], &fx[n..], &result_grad[n..]);
+    }
+
+    /// Inputs outside of this range (and NaNs) are computed with [f32::exp()], so that
+    /// `2^n` in [exp()] is a normal float.
+    const EXP_LO: f32 = -87.0;
+    const EXP_HI: f32 = 88.0;
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn exp_assign(a: &mut [f32]) {
+        let n = a.len() / LANES * LANES;
+        let lo = _mm256_set1_ps(EXP_LO);
+        let hi = _mm256_set1_ps(EXP_HI);
+        for i in (0..n).step_by(LANES) {
+            let pa = a.as_mut_ptr().add(i);
+            let x = _mm256_loadu_ps(pa);
+            let in_range = _mm256_and_ps(
+                _mm256_cmp_ps::<_CMP_GE_OQ>(x, lo),
+                _mm256_cmp_ps::<_CMP_LE_OQ>(x, hi),
+            );
+            if _mm256_movemask_ps(in_range) == 0xff {
+                _mm256_storeu_ps(pa, exp(x));
+            } else {
+                scalar::exp_assign(&mut a[i..i + LANES]);
+            }
+        }
+        scalar::exp_assign(&mut a[n..]);
+    }
+
+    /// `exp(x) = 2^n * exp(r)` where `n = round(x / ln(2))`, and `exp(r)` is approximated with
+    /// a polynomial (from the Cephes library).
+    #[target_feature(enable = "avx2")]
+    unsafe fn exp(x: __m256) -> __m256 {
+        let n = _mm256_floor_ps(_mm256_add_ps(
+            _mm256_mul_ps(x, _mm256_set1_ps(core::f32::consts::LOG2_E)),
+            _mm256_set1_ps(0.5),
+        ));
+
+        // r = x - n * ln(2), with ln(2) split into two parts for precision
+        let r = _mm256_sub_ps(x, _mm256_mul_ps(n, _mm256_set1_ps(0.693_359_4)));
+        let r = _mm256_sub_ps(r, _mm256_mul_ps(n, _mm256_set1_ps(-2.121_944_4e-4)));
+
+        let mut y = _mm256_set1_ps(1.987_569_1e-4);
+        for c in [
+            1.398_199_9e-3,
+            8.333_452e-3,
+            4.166_579_6e-2,
+            0.166_666_65,
+            0.5,
+        ] {
+            y = _mm256_add_ps(_mm256_mul_ps(y, r), _mm256_set1_ps(c));
+        }
+        let y = _mm256_add_ps(_mm256_mul_ps(y, _mm256_mul_ps(r, r)), r);
+        let y = _mm256_add_ps(y, _mm256_set1_ps(1.0));
+
+        // 2^n, by putting n + 127 into the exponent bits
+        let n = _mm256_add_epi32(_mm256_cvtps_epi32(n), _mm256_set1_epi32(127));
+        let pow2n = _mm256_castsi256_ps(_mm256_slli_epi32::<23>(n));
+        _mm256_mul_ps(y, pow2n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, Rng};
+
+    /// 27 elements, so both the vectorized loop and the scalar remainder are used.
+    fn random() -> [[f32; 9]; 3] {
+        let mut rng = thread_rng();
+        [[0.0; 9]; 3].map(|a| a.map(|_| rng.gen_range(-100.0..100.0)))
+    }
+
+    #[test]
+    fn test_binary_kernels_match_scalar() {
+        let (a, b, c) = (random(), random(), random());
+
+        let mut expected = a;
+        scalar::add_assign(flat_mut(&mut expected), flat(&b));
+        let mut found = a;
+        Cpu::add_assign(&mut found, &b);
+        assert_eq!(found, expected);
+
+        let mut expected = a;
+        scalar::sub_assign(flat_mut(&mut expected), flat(&b));
+        let mut found = a;
+        Cpu::sub_assign(&mut found, &b);
+        assert_eq!(found, expected);
+
+        let mut expected = a;
+        scalar::mul_assign(flat_mut(&mut expected), flat(&b));
+        let mut found = a;
+        Cpu::mul_assign(&mut found, &b);
+        assert_eq!(found, expected);
+
+        let mut expected = a;
+        scalar::addmul_assign(flat_mut(&mut expected), flat(&b), flat(&c));
+        let mut found = a;
+        Cpu::addmul_assign(&mut found, &b, &c);
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_relu_kernels() {
+        let mut a = [
+            -1.0,
+            0.0,
+            2.0,
+            f32::NAN,
+            -0.5,
+            3.0,
+            f32::INFINITY,
+            -2.0,
+            4.0,
+        ];
+        Cpu::relu_assign(&mut a);
+        assert_eq!(a, [0.0, 0.0, 2.0, 0.0, 0.0, 3.0, f32::INFINITY, 0.0, 4.0]);
+
+        let mut g = [1.0; 9];
+        Cpu::relu_backward(&mut g, &a, &[2.0; 9]);
+        assert_eq!(g, [1.0, 1.0, 3.0, 1.0, 1.0, 3.0, 3.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_exp_close_to_std() {
+        let mut a = random().map(|a| a.map(|x| x / 10.0));
+        a[0][0] = -100.0;
+        a[0][1] = 88.5;
+        a[0][2] = f32::NAN;
+        a[1][0] = f32::NEG_INFINITY;
+        a[1][1] = f32::INFINITY;
+        let mut found = a;
+        Cpu::exp_assign(&mut found);
+        for (x, y) in flat(&a).iter().zip(flat(&found)) {
+            let expected = x.exp();
+            if expected.is_nan() {
+                assert!(y.is_nan());
+            } else {
+                assert!(
+                    *y == expected || (y - expected).abs() <= expected * 4.0 * f32::EPSILON,
+                    "exp({x}) = {expected}, found {y}"
+                );
+            }
+        }
+
+        let mut a = [0.0, 1.0, -1.0, 2.0, 0.5, -0.5, 10.0, -10.0];
+        Cpu::exp_assign(&mut a);
+        assert_eq!(a[0], 1.0);
+    }
+}
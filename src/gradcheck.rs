@@ -0,0 +1,334 @@
+//! Checking the gradients of operations against finite differences with [gradcheck()].
+
+use crate::devices::{flat, flat_mut};
+use crate::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+use std::vec::Vec;
+
+/// One element of an input where the gradient from the tape doesn't match the finite difference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradMismatch {
+    /// Which of the inputs the element is in.
+    pub input: usize,
+
+    /// The index of the element in the flattened input.
+    pub index: usize,
+
+    /// The gradient that was computed by the tape.
+    pub analytic: f32,
+
+    /// The gradient that was computed with central finite differences.
+    pub numeric: f32,
+}
+
+/// The error of [gradcheck()], which contains every mismatched element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradcheckError {
+    pub mismatches: Vec<GradMismatch>,
+}
+
+impl std::fmt::Display for GradcheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} gradients don't match:", self.mismatches.len())?;
+        for m in self.mismatches.iter() {
+            write!(
+                f,
+                "\n  input {} element {}: tape {}, finite difference {}",
+                m.input, m.index, m.analytic, m.numeric
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GradcheckError {}
+
+/// The inputs of [gradcheck()]: a single tensor without a tape, or a tuple of them.
+pub trait GradcheckInputs: Clone {
+    /// The number of tensors.
+    const NUM_TENSORS: usize;
+
+    /// The elements of the `i`th tensor.
+    fn values_mut(&mut self, i: usize) -> &mut [f32];
+
+    /// The gradient of the `i`th tensor in `gradients`, or zeros if it has none.
+    fn gradient(&self, i: usize, gradients: &Gradients) -> Vec<f32>;
+}
+
+fn gradient_of<T: Tensor<Dtype = f32>>(t: &T, gradients: &Gradients) -> Vec<f32> {
+    match gradients.get(t) {
+        Some(g) => flat(g).to_vec(),
+        None => std::vec![0.0; T::Array::NUM_ELEMENTS],
+    }
+}
+
+impl<T: Tensor<Dtype = f32, Tape = NoneTape> + Clone> GradcheckInputs for T {
+    const NUM_TENSORS: usize = 1;
+    fn values_mut(&mut self, _: usize) -> &mut [f32] {
+        flat_mut(self.mut_data())
+    }
+    fn gradient(&self, _: usize, gradients: &Gradients) -> Vec<f32> {
+        gradient_of(self, gradients)
+    }
+}
+
+macro_rules! tuple_impl {
+    ($num:expr, [$($name:ident),+], [$($idx:tt),+]) => {
+impl<$($name: Tensor<Dtype = f32, Tape = NoneTape> + Clone),+> GradcheckInputs for ($($name,)+) {
+    const NUM_TENSORS: usize = $num;
+    fn values_mut(&mut self, i: usize) -> &mut [f32] {
+        match i {
+            $($idx => flat_mut(self.$idx.mut_data()),)+
+            _ => panic!("input {i} out of bounds"),
+        }
+    }
+    fn gradient(&self, i: usize, gradients: &Gradients) -> Vec<f32> {
+        match i {
+            $($idx => gradient_of(&self.$idx, gradients),)+
+            _ => panic!("input {i} out of bounds"),
+        }
+    }
+}
+    };
+}
+
+tuple_impl!(2, [A, B], [0, 1]);
+tuple_impl!(3, [A, B, C], [0, 1, 2]);
+tuple_impl!(4, [A, B, C, D], [0, 1, 2, 3]);
+
+/// Compares the gradients that the tape computes for `f(inputs)` against central finite
+/// differences `(f(x + eps) - f(x - eps)) / (2 * eps)`, for every element of every input.
+///
+/// `f` should trace one of the inputs, and pass the rest by reference (the same way as
+/// [add()] or [matmul()] take their right hand side). If `f` returns a non scalar tensor, the
+/// gradients of a random weighted sum of its elements are compared, so every output is checked.
+///
+/// An element matches if `|analytic - numeric| <= tol * (1 + |numeric|)`. Since the forward pass
+/// is computed in f32, `eps` around `1e-3` and `tol` around `1e-2` are reasonable. Ops that aren't
+/// differentiable everywhere (e.g. [relu()] or [abs()]) may mismatch for inputs within `eps` of
+/// a kink.
+///
+/// Returns all mismatched elements as a [GradcheckError].
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let x: Tensor2D<2, 3> = Tensor2D::new([[0.1, -0.2, 0.3], [0.4, 0.5, -0.6]]);
+/// let w: Tensor2D<3, 4> = Tensor2D::ones();
+/// gradcheck(|(x, w)| matmul(x.trace(), w).tanh(), &(x, w), 1e-3, 1e-2).unwrap();
+///
+/// // a custom op with the wrong derivative
+/// let wrong = |x: &Tensor1D<3>| map(x.trace(), |x| x * x, |x| *x);
+/// let err = gradcheck(wrong, &Tensor1D::new([1.0, 2.0, 3.0]), 1e-3, 1e-2).unwrap_err();
+/// assert_eq!(err.mismatches.len(), 3);
+/// ```
+pub fn gradcheck<I, O, F>(mut f: F, inputs: &I, eps: f32, tol: f32) -> Result<(), GradcheckError>
+where
+    I: GradcheckInputs,
+    O: Tensor<Dtype = f32, Tape = OwnedTape>,
+    F: FnMut(&I) -> O,
+{
+    let weights: O::NoTape = TensorCreator::randn(&mut StdRng::seed_from_u64(0));
+    let gradients = sum(mul(f(inputs), &weights)).backward();
+
+    // the weighted sum of the outputs, computed in f64 to not lose the small differences
+    let mut eval = |x: &I| -> f64 {
+        let y = f(x);
+        let y = flat(y.data()).iter();
+        y.zip(flat(weights.data()).iter())
+            .map(|(y, w)| *y as f64 * *w as f64)
+            .sum()
+    };
+
+    let mut mismatches = Vec::new();
+    let mut x = inputs.clone();
+    for input in 0..I::NUM_TENSORS {
+        let analytic = inputs.gradient(input, &gradients);
+        for (index, analytic) in analytic.into_iter().enumerate() {
+            let v = x.values_mut(input)[index];
+            x.values_mut(input)[index] = v + eps;
+            let plus = eval(&x);
+            x.values_mut(input)[index] = v - eps;
+            let minus = eval(&x);
+            x.values_mut(input)[index] = v;
+
+            let numeric = ((plus - minus) / (2.0 * eps as f64)) as f32;
+            if (analytic - numeric).abs() > tol * (1.0 + numeric.abs()) {
+                mismatches.push(GradMismatch {
+                    input,
+                    index,
+                    analytic,
+                    numeric,
+                });
+            }
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(GradcheckError { mismatches })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f32 = 1e-3;
+    const TOL: f32 = 1e-2;
+
+    #[test]
+    fn test_gradcheck_detects_wrong_gradient() {
+        let x = Tensor1D::new([1.0, -2.0, 0.5]);
+        assert!(gradcheck(|x| map(x.trace(), |x| x * x, |x| 2.0 * x), &x, EPS, TOL).is_ok());
+
+        let err = gradcheck(|x| map(x.trace(), |x| x * x, |x| x + 1.0), &x, EPS, TOL).unwrap_err();
+        let indices: Vec<usize> = err.mismatches.iter().map(|m| m.index).collect();
+        assert_eq!(indices, [1, 2]);
+        // the gradients are scaled by the random weight of the output
+        let m = err.mismatches[1];
+        assert!((m.analytic / m.numeric - 1.5).abs() < 1e-2);
+        assert!(err.to_string().starts_with("2 gradients don't match"));
+    }
+
+    #[test]
+    fn test_gradcheck_unused_input_has_zero_gradient() {
+        let x: Tensor1D<2> = Tensor1D::new([1.0, 2.0]);
+        let y: Tensor1D<2> = Tensor1D::new([3.0, 4.0]);
+        assert!(gradcheck(|(x, _)| x.trace().exp(), &(x, y), EPS, TOL).is_ok());
+    }
+
+    /// Runs [gradcheck()] on many ops, and reports the ones that fail by name.
+    #[test]
+    fn test_gradcheck_ops() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let a: Tensor2D<3, 4> = TensorCreator::randn(&mut rng);
+        let b: Tensor2D<3, 4> = TensorCreator::randn(&mut rng);
+        let w: Tensor2D<4, 2> = TensorCreator::randn(&mut rng);
+        let v: Tensor1D<4> = TensorCreator::randn(&mut rng);
+        let pos = a.clone().abs() + 0.5;
+
+        let results: Vec<(&str, Result<(), GradcheckError>)> = std::vec![
+            ("exp", gradcheck(|a| a.trace().exp(), &a, EPS, TOL)),
+            ("ln", gradcheck(|p| p.trace().ln(), &pos, EPS, TOL)),
+            ("sqrt", gradcheck(|p| p.trace().sqrt(), &pos, EPS, TOL)),
+            ("square", gradcheck(|a| a.trace().square(), &a, EPS, TOL)),
+            ("tanh", gradcheck(|a| a.trace().tanh(), &a, EPS, TOL)),
+            ("sigmoid", gradcheck(|a| a.trace().sigmoid(), &a, EPS, TOL)),
+            ("sin", gradcheck(|a| a.trace().sin(), &a, EPS, TOL)),
+            ("cos", gradcheck(|a| a.trace().cos(), &a, EPS, TOL)),
+            ("negate", gradcheck(|a| -a.trace(), &a, EPS, TOL)),
+            (
+                "add",
+                gradcheck(
+                    |(a, b)| add(a.trace(), b),
+                    &(a.clone(), b.clone()),
+                    EPS,
+                    TOL
+                )
+            ),
+            (
+                "sub",
+                gradcheck(
+                    |(a, b)| sub(a.trace(), b),
+                    &(a.clone(), b.clone()),
+                    EPS,
+                    TOL
+                )
+            ),
+            (
+                "mul",
+                gradcheck(
+                    |(a, b)| mul(a.trace(), b),
+                    &(a.clone(), b.clone()),
+                    EPS,
+                    TOL
+                )
+            ),
+            (
+                "div",
+                gradcheck(
+                    |(a, p)| div(a.trace(), p),
+                    &(a.clone(), pos.clone()),
+                    EPS,
+                    TOL
+                )
+            ),
+            (
+                "matmul",
+                gradcheck(
+                    |(a, w)| matmul(a.trace(), w),
+                    &(a.clone(), w.clone()),
+                    EPS,
+                    TOL
+                )
+            ),
+            ("softmax", gradcheck(|a| a.trace().softmax(), &a, EPS, TOL)),
+            (
+                "log_softmax",
+                gradcheck(|a| a.trace().log_softmax(), &a, EPS, TOL)
+            ),
+            (
+                "logsumexp",
+                gradcheck(|a| a.trace().logsumexp(), &a, EPS, TOL)
+            ),
+            ("sum", gradcheck(|a| a.trace().sum(), &a, EPS, TOL)),
+            ("mean", gradcheck(|a| a.trace().mean(), &a, EPS, TOL)),
+            (
+                "sum_axis",
+                gradcheck(|a| a.trace().sum_axis::<0>(), &a, EPS, TOL)
+            ),
+            (
+                "mean_axis",
+                gradcheck(|a| a.trace().mean_axis::<-1>(), &a, EPS, TOL)
+            ),
+            (
+                "normalize_axis",
+                gradcheck(|a| a.trace().normalize_axis::<-1>(1e-5), &a, EPS, TOL)
+            ),
+            (
+                "broadcast",
+                gradcheck(
+                    |v| -> Tensor2D<3, 4, OwnedTape> { v.trace().broadcast1() },
+                    &v,
+                    EPS,
+                    TOL
+                )
+            ),
+            (
+                "select",
+                gradcheck(
+                    |a| -> Tensor1D<3, OwnedTape> { a.trace().select(&[0, 3, 1]) },
+                    &a,
+                    EPS,
+                    TOL
+                )
+            ),
+            (
+                "slice",
+                gradcheck(
+                    |a| -> Tensor2D<2, 3, OwnedTape> { a.trace().i((1.., ..3)) },
+                    &a,
+                    EPS,
+                    TOL
+                )
+            ),
+            (
+                "mse_loss",
+                gradcheck(
+                    |(a, b)| mse_loss(a.trace(), b),
+                    &(a.clone(), b.clone()),
+                    EPS,
+                    TOL
+                )
+            ),
+        ];
+
+        let failed: Vec<String> = results
+            .into_iter()
+            .filter_map(|(name, r)| r.err().map(|e| std::format!("{name}: {e}")))
+            .collect();
+        assert!(failed.is_empty(), "{}", failed.join("\n"));
+    }
+}
@@ -1,6 +1,10 @@
 //! Implementations of [GradientTape] and generic Nd array containers via [Gradients].
 
 use crate::prelude::*;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(feature = "std")]
 use std::collections::HashMap;
 
 /// Records gradient computations to execute later.
@@ -12,7 +16,7 @@ use std::collections::HashMap;
 /// The reason for this design, which forces users to specify gradient computations, as opposed to having
 /// a fixed set of *kinds* of computations are these:
 /// 1. Different tensor sizes. The tensors size information would have to be stored inside the operation somehow.
-///     Instead, the operation themselves must query with a sized tensor, so sizes are still known at compile time instead of dynamically.
+///    Instead, the operation themselves must query with a sized tensor, so sizes are still known at compile time instead of dynamically.
 /// 2. Slightly different operations. It'd have to support broadcasting operations, etc which can get needlessly complex.
 /// 3. Optimizations are harder. With operations having control over everything, they can be optimized by hand separately.
 ///
@@ -29,18 +33,19 @@ use std::collections::HashMap;
 /// This is implementing the chain rule, which is normally defined as `gradient(t) += deriv * gradient(result)` with
 /// the following optimizations:
 /// 1. instead of allocating new data for the derivative (which is just -1 everywhere), we can reuse the `t` tensor since the negate
-///     function owns it.
+///    function owns it.
 /// 2. We can combine computing the derivative and multiplying by the `gradient(result)` by just setting `t` to `-gradient(result)`
 ///
 /// This would not be possible if these chain rule operations were inside of GradientTape!
 #[derive(Default)]
 #[allow(clippy::type_complexity)]
 pub struct GradientTape {
-    operations: Vec<Box<dyn FnOnce(&mut Gradients)>>,
+    /// Each operation with the name of the op that added it, if it is profiled.
+    operations: Vec<(Option<&'static str>, Box<dyn FnOnce(&mut Gradients)>)>,
 }
 
-impl std::fmt::Debug for GradientTape {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for GradientTape {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("GradientTape")
             .field("num_operations", &self.operations.len())
             .finish()
@@ -59,7 +64,8 @@ impl GradientTape {
     ///
     /// See src/tensor_ops for implementation examples.
     pub(crate) fn add_backward_op<F: 'static + FnOnce(&mut Gradients)>(&mut self, operation: F) {
-        self.operations.push(Box::new(operation));
+        let name = crate::profile::current_op();
+        self.operations.push((name, Box::new(operation)));
     }
 
     /// Compute the [Gradients]! This just runs all the operations on a new [Gradients] struct.
@@ -67,7 +73,8 @@ impl GradientTape {
     /// Note that this method takes ownership of self, so it can't be called twice!
     pub fn execute(mut self) -> Gradients {
         let mut gradients: Gradients = Default::default();
-        for operation in self.operations.drain(..).rev() {
+        for (name, operation) in self.operations.drain(..).rev() {
+            let _timer = name.and_then(crate::profile::backward_op);
             (operation)(&mut gradients);
         }
         gradients
@@ -113,12 +120,13 @@ impl Tape for NoneTape {
 /// This structure is similar to a HashMap, where all the methods require a key
 /// implementing [UniqueId] and [HasArrayType].
 ///
-/// Under the hood, it actually is a HashMap, and stores values as Box<dyn Any>. The
+/// Under the hood, it actually is a HashMap (a BTreeMap without `std`), and stores values as
+/// Box<dyn Any>. The
 /// important part of key's implementing [HasArrayType] is that the associated type
 /// of that trait is used to downcast the box to the expected value.
 #[derive(Debug, Default)]
 pub struct Gradients {
-    gradient_by_id: HashMap<UniqueId, Box<dyn std::any::Any>>,
+    gradient_by_id: HashMap<UniqueId, Box<dyn core::any::Any>>,
 }
 
 impl Gradients {
@@ -195,6 +203,20 @@ impl Gradients {
             .map(|e| e.1.downcast().unwrap())
     }
 
+    /// Inserts `data` as the data associated with `t.id()`, replacing any existing data.
+    ///
+    /// Example usage:
+    /// ```
+    /// # use dfdx::prelude::*;
+    /// let t = Tensor1D::new([1.0, 2.0, 3.0]);
+    /// let mut gradients: Gradients = Default::default();
+    /// gradients.insert(&t, Box::new([-4.0, 5.0, -6.0]));
+    /// assert_eq!(gradients.ref_gradient(&t), &[-4.0, 5.0, -6.0]);
+    /// ```
+    pub fn insert<T: HasUniqueId + HasArrayType>(&mut self, t: &T, data: Box<T::Array>) {
+        self.gradient_by_id.insert(*t.id(), data);
+    }
+
     /// Returns a mutable reference to the data associated with `t`.
     ///
     /// If no data is associated with `t`, then [AllocateZeros::zeros] is called
@@ -222,6 +244,24 @@ impl Gradients {
             .unwrap()
     }
 
+    /// Returns a reference to the data associated with `t`, or `None` if there is no
+    /// data associated with `t`.
+    ///
+    /// Example usage:
+    /// ```
+    /// # use dfdx::prelude::*;
+    /// let t = Tensor1D::new([1.0, 2.0, 3.0]);
+    /// let mut gradients: Gradients = Default::default();
+    /// assert!(gradients.get(&t).is_none());
+    /// gradients.mut_gradient(&t);
+    /// assert_eq!(gradients.get(&t), Some(&[0.0, 0.0, 0.0]));
+    /// ```
+    pub fn get<T: HasUniqueId + HasArrayType>(&self, t: &T) -> Option<&T::Array> {
+        self.gradient_by_id
+            .get(t.id())
+            .map(|g| g.as_ref().downcast_ref().unwrap())
+    }
+
     /// Returns a reference to the data associated with `t`.
     ///
     /// # Panics
@@ -247,6 +287,15 @@ impl Gradients {
     }
 }
 
+impl Drop for Gradients {
+    /// Gives the arrays to the active [Arena], if there is one.
+    fn drop(&mut self) {
+        for gradient in core::mem::take(&mut self.gradient_by_id).into_values() {
+            crate::devices::arena::recycle(gradient);
+        }
+    }
+}
+
 /// Represents something that can return a gradient for a given key.
 ///
 /// This is very similar to what [Gradients] does, however the intention
@@ -261,9 +310,13 @@ pub trait GradientProvider {
     /// Retrieves the data associated with `p` if there is any.
     /// This can modify `self`, for instance if velocities are calculated
     /// based on the associated data!
+    ///
+    /// The current value of the parameter is available through [HasArrayData::data()],
+    /// which is needed for things like weight decay. This isn't called for parameters whose
+    /// [Tensor::requires_grad()] is `false` (see [Freeze]).
     fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
     where
-        P: HasUniqueId + HasArrayType<Dtype = f32> + HasDevice;
+        P: Tensor<Dtype = f32>;
 }
 
 /// Represents something that can be updated with [GradientProvider].
@@ -277,6 +330,84 @@ pub trait CanUpdateWithGradients {
     fn update<G: GradientProvider>(&mut self, grads: &mut G, unused: &mut UnusedTensors);
 }
 
+/// Something that is called with every parameter of a [VisitParams], see [VisitParams::visit_params()].
+pub trait ParamVisitor {
+    /// Called with the parameter `p` named `name`.
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P);
+}
+
+/// Something that is called with every parameter of a [VisitParams], see [VisitParams::visit_params_mut()].
+pub trait ParamVisitorMut {
+    /// Called with the parameter `p` named `name`.
+    fn visit_mut<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &mut P);
+}
+
+/// Visits every parameter tensor of a module along with its name, including parameters
+/// that are frozen (see [Freeze]).
+///
+/// The names are the same as the file names used by [crate::nn::SaveToNpz], without the `.npy`
+/// extension, e.g. `0.weight` and `0.bias` for the first [Linear] of a tuple.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// struct Names(Vec<String>);
+///
+/// impl ParamVisitor for Names {
+///     fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, _: &P) {
+///         self.0.push(name.into());
+///     }
+/// }
+///
+/// let model: (Linear<5, 10>, ReLU, Linear<10, 2>) = Default::default();
+/// let mut names = Names(Vec::new());
+/// model.visit_params("", &mut names);
+/// assert_eq!(names.0, ["0.weight", "0.bias", "2.weight", "2.bias"]);
+/// ```
+pub trait VisitParams {
+    /// Calls [ParamVisitor::visit()] with every parameter, with names prefixed by `pre`.
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V);
+
+    /// Calls [ParamVisitorMut::visit_mut()] with every parameter, with names prefixed by `pre`.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V);
+}
+
+/// Freezes & unfreezes all the parameters of a [VisitParams]. Optimizers skip
+/// frozen parameters, which is useful for fine tuning only part of a pretrained model.
+///
+/// Frozen parameters are not reported as unused by [CanUpdateWithGradients::update()]. Their gradients
+/// are still computed by [crate::tensor_ops::backward()], they are just ignored.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let mut model: (Linear<5, 10>, Linear<10, 2>) = Default::default();
+/// model.0.freeze(); // only model.1 will be updated by optimizers
+/// assert!(!model.0.weight.requires_grad());
+/// assert!(model.1.weight.requires_grad());
+/// ```
+pub trait Freeze: VisitParams {
+    /// Sets [Tensor::requires_grad()] of all parameters to `false`.
+    fn freeze(&mut self) {
+        self.visit_params_mut("", &mut SetRequiresGrad(false));
+    }
+
+    /// Sets [Tensor::requires_grad()] of all parameters to `true`.
+    fn unfreeze(&mut self) {
+        self.visit_params_mut("", &mut SetRequiresGrad(true));
+    }
+}
+
+impl<M: VisitParams> Freeze for M {}
+
+struct SetRequiresGrad(bool);
+
+impl ParamVisitorMut for SetRequiresGrad {
+    fn visit_mut<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &mut P) {
+        p.set_requires_grad(self.0);
+    }
+}
+
 /// Holds [UniqueId] of tensors that were missing gradients during
 /// [CanUpdateWithGradients::update()], and therefore are unused
 #[derive(Debug, Default)]
@@ -339,4 +470,52 @@ mod tests {
         let g = tape.execute();
         assert_eq!(g.ref_gradient(&t1), &[1.0; 5]);
     }
+
+    struct Names(Vec<(String, bool)>);
+
+    impl ParamVisitor for Names {
+        fn visit<P: crate::tensor::Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+            self.0.push((name.into(), p.requires_grad()));
+        }
+    }
+
+    #[test]
+    fn test_visit_params_includes_frozen() {
+        let mut model: (Linear<2, 3>, Linear<3, 1>) = Default::default();
+        model.1.freeze();
+        let mut names = Names(Vec::new());
+        model.visit_params("", &mut names);
+        assert_eq!(
+            names.0,
+            [
+                ("0.weight".into(), true),
+                ("0.bias".into(), true),
+                ("1.weight".into(), false),
+                ("1.bias".into(), false),
+            ]
+        );
+    }
+
+    struct CountVisited(usize);
+
+    impl GradientProvider for CountVisited {
+        fn gradient<P>(&mut self, _: &P) -> Option<Box<P::Array>>
+        where
+            P: crate::tensor::Tensor<Dtype = f32>,
+        {
+            self.0 += 1;
+            None
+        }
+    }
+
+    #[test]
+    fn test_update_skips_frozen() {
+        let mut model: (Linear<2, 3>, Linear<3, 1>) = Default::default();
+        model.0.freeze();
+        let mut visited = CountVisited(0);
+        let mut unused = Default::default();
+        model.update(&mut visited, &mut unused);
+        assert_eq!(visited.0, 2);
+        assert_eq!(unused.len(), 2);
+    }
 }
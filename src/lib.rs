@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(incomplete_features)]
 #![cfg_attr(feature = "nightly", feature(generic_const_exprs))]
 
@@ -87,34 +88,81 @@
 //! // Use stochastic gradient descent (Sgd), with a learning rate of 1e-2, and 0.9 momentum.
 //! let mut opt = Sgd::new(SgdConfig {
 //!     lr: 1e-2,
-//!     momentum: Some(Momentum::Classic(0.9))
+//!     momentum: Some(Momentum::Classic(0.9)),
+//!     grad_clip: None,
 //! });
 //!
 //! // pass the gradients & the model into the optimizer's update method
 //! opt.update(&mut model, gradients);
 //! ```
 
+extern crate alloc;
+
 pub mod arrays;
+#[cfg(feature = "std")]
 pub mod data;
 pub mod devices;
+#[cfg(feature = "std")]
+pub mod gradcheck;
 pub mod gradients;
 pub mod losses;
+pub mod metrics;
 pub mod nn;
+#[cfg(feature = "std")]
 pub mod numpy;
+#[cfg(feature = "std")]
 pub mod optim;
+#[cfg(feature = "std")]
+pub mod profile;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod rng;
 pub mod tensor;
 pub mod tensor_ops;
 pub mod unique_id;
 
+#[cfg(feature = "std")]
+pub use rng::set_seed;
+
+/// Without `std` there is no [Profiler](profile::Profiler), so nothing is measured.
+#[cfg(not(feature = "std"))]
+pub(crate) mod profile {
+    pub(crate) struct OpTimer;
+
+    pub(crate) fn op(_: &'static str) -> Option<OpTimer> {
+        None
+    }
+
+    pub(crate) fn backward_op(_: &'static str) -> Option<OpTimer> {
+        None
+    }
+
+    pub(crate) fn current_op() -> Option<&'static str> {
+        None
+    }
+
+    pub(crate) fn count_allocation() {}
+}
+
 /// Contains all public exports.
 pub mod prelude {
     pub use crate::arrays::*;
+    #[cfg(feature = "std")]
     pub use crate::data::*;
     pub use crate::devices::*;
+    #[cfg(feature = "std")]
+    pub use crate::gradcheck::*;
     pub use crate::gradients::*;
     pub use crate::losses::*;
+    pub use crate::metrics::*;
     pub use crate::nn::*;
+    #[cfg(feature = "std")]
     pub use crate::optim::*;
+    #[cfg(feature = "std")]
+    pub use crate::profile::*;
+    #[cfg(feature = "std")]
+    pub use crate::rng::*;
     pub use crate::tensor::*;
     pub use crate::tensor_ops::*;
     pub use crate::unique_id::*;
@@ -152,16 +200,17 @@ pub const BLAS_LIB: &str = "mkl-dynamic-iomp";
 /// Some resources:
 /// 1. [Effects of Flush-To-Zero mode](https://developer.arm.com/documentation/dui0473/c/neon-and-vfp-programming/the-effects-of-using-flush-to-zero-mode?lang=en)
 /// 2. [When to use Flush-To-Zero mode](https://developer.arm.com/documentation/dui0473/c/neon-and-vfp-programming/when-to-use-flush-to-zero-mode?lang=en)
+#[allow(deprecated)]
 pub fn flush_denormals_to_zero() {
     #[cfg(all(target_arch = "x86", target_feature = "sse"))]
     {
-        use std::arch::x86::{_MM_FLUSH_ZERO_ON, _MM_SET_FLUSH_ZERO_MODE};
+        use core::arch::x86::{_MM_FLUSH_ZERO_ON, _MM_SET_FLUSH_ZERO_MODE};
         unsafe { _MM_SET_FLUSH_ZERO_MODE(_MM_FLUSH_ZERO_ON) }
     }
 
     #[cfg(all(target_arch = "x86_64", target_feature = "sse"))]
     {
-        use std::arch::x86_64::{_MM_FLUSH_ZERO_ON, _MM_SET_FLUSH_ZERO_MODE};
+        use core::arch::x86_64::{_MM_FLUSH_ZERO_ON, _MM_SET_FLUSH_ZERO_MODE};
         unsafe { _MM_SET_FLUSH_ZERO_MODE(_MM_FLUSH_ZERO_ON) }
     }
 }
@@ -171,20 +220,26 @@ pub fn flush_denormals_to_zero() {
 /// Some resources:
 /// 1. [Effects of Flush-To-Zero mode](https://developer.arm.com/documentation/dui0473/c/neon-and-vfp-programming/the-effects-of-using-flush-to-zero-mode?lang=en)
 /// 2. [When to use Flush-To-Zero mode](https://developer.arm.com/documentation/dui0473/c/neon-and-vfp-programming/when-to-use-flush-to-zero-mode?lang=en)
+#[allow(deprecated)]
 pub fn keep_denormals() {
     #[cfg(all(target_arch = "x86", target_feature = "sse"))]
     {
-        use std::arch::x86::{_MM_FLUSH_ZERO_OFF, _MM_SET_FLUSH_ZERO_MODE};
+        use core::arch::x86::{_MM_FLUSH_ZERO_OFF, _MM_SET_FLUSH_ZERO_MODE};
         unsafe { _MM_SET_FLUSH_ZERO_MODE(_MM_FLUSH_ZERO_OFF) }
     }
 
     #[cfg(all(target_arch = "x86_64", target_feature = "sse"))]
     {
-        use std::arch::x86_64::{_MM_FLUSH_ZERO_OFF, _MM_SET_FLUSH_ZERO_MODE};
+        use core::arch::x86_64::{_MM_FLUSH_ZERO_OFF, _MM_SET_FLUSH_ZERO_MODE};
         unsafe { _MM_SET_FLUSH_ZERO_MODE(_MM_FLUSH_ZERO_OFF) }
     }
 }
 
+/// Used to assert things about const generics
+pub struct Assert<const C: bool>;
+pub trait ConstTrue {}
+impl ConstTrue for Assert<true> {}
+
 #[cfg(test)]
 pub(crate) mod tests {
     const TOLERANCE: f32 = 1e-6;
@@ -217,8 +272,3 @@ pub(crate) mod tests {
         a.assert_close(b, TOLERANCE);
     }
 }
-
-/// Used to assert things about const generics
-pub struct Assert<const C: bool>;
-pub trait ConstTrue {}
-impl ConstTrue for Assert<true> {}
@@ -1,6 +1,8 @@
 //! Standard loss functions such as [mse_loss()], [cross_entropy_with_logits_loss()], and more.
 
 use crate::prelude::*;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 /// [Mean Squared Error](https://en.wikipedia.org/wiki/Mean_squared_error).
 /// This computes `(&targ - pred).square().mean()`.
@@ -184,9 +186,357 @@ pub fn binary_cross_entropy_with_logits_loss<T: Tensor<Dtype = f32>>(
     ))
 }
 
+/// [Quantile loss](https://en.wikipedia.org/wiki/Quantile_regression) (a.k.a. pinball loss)
+/// for predicting multiple quantiles at once. The last axis of `pred` must have size `Q`, and
+/// `pred[.., i]` is the prediction for `quantiles[i]`.
+///
+/// For each element this computes `max(q * (targ - pred), (q - 1) * (targ - pred))`, and then
+/// takes the mean over all elements.
+///
+/// # Arguments
+///
+/// - `pred`: Predictions where the last axis holds the predicted quantiles.
+/// - `targ`: The observed values, repeated along the last axis.
+/// - `quantiles`: The quantile for each index of the last axis. Each should be between 0 and 1.
+///
+/// The size of the last axis of `pred` has to be `Q`, which is checked at compile time:
+/// ```compile_fail
+/// # use dfdx::prelude::*;
+/// let pred: Tensor2D<2, 3> = Tensor2D::zeros();
+/// let loss = quantile_loss(pred, &Tensor2D::zeros(), &[0.1, 0.9]);
+/// ```
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// // 2 samples, predicting the 10%, 50%, and 90% quantiles for each
+/// let pred = Tensor2D::new([[0.5, 1.0, 1.5], [-1.0, 0.0, 1.0]]);
+/// let targ = Tensor2D::new([[1.2; 3], [0.3; 3]]);
+/// let loss = quantile_loss(pred.traced(), &targ, &[0.1, 0.5, 0.9]);
+/// ```
+pub fn quantile_loss<T: Tensor<Dtype = f32>, const Q: usize>(
+    pred: T,
+    targ: &T::NoTape,
+    quantiles: &[f32; Q],
+) -> Tensor0D<T::Tape> {
+    let () = LastAxisIs::<T::Array, Q>::CHECK;
+    pinball_loss(pred, targ, quantiles)
+}
+
+/// Fails to compile when [LastAxisIs::CHECK] is used and the last axis of `A` doesn't have size `Q`.
+struct LastAxisIs<A, const Q: usize>(core::marker::PhantomData<A>);
+
+impl<A: HasAxis<-1>, const Q: usize> LastAxisIs<A, Q> {
+    const CHECK: () = assert!(
+        A::SIZE == Q,
+        "the last axis must have one element per quantile"
+    );
+}
+
+fn pinball_loss<T: Tensor<Dtype = f32>, const Q: usize>(
+    pred: T,
+    targ: &T::NoTape,
+    quantiles: &[f32; Q],
+) -> Tensor0D<T::Tape> {
+    // fills the last axis of each with the quantiles
+    let mut i = 0;
+    let mut under_weight = T::NoTape::zeros();
+    let mut over_weight = T::NoTape::zeros();
+    T::Device::foreach_mm(
+        under_weight.mut_data(),
+        over_weight.mut_data(),
+        &mut |u, o| {
+            *u = -quantiles[i % Q];
+            *o = 1.0 - quantiles[i % Q];
+            i += 1;
+        },
+    );
+
+    // with `d = pred - targ`, pinball loss is `max(-q * d, (1 - q) * d)`
+    let (diff, tape) = sub(pred, targ).split_tape();
+    let (over, tape) = mul(diff.duplicate().put_tape(tape), &over_weight).split_tape();
+    let under = mul(diff.put_tape(tape), &under_weight);
+    mean(maximum(under, &over))
+}
+
+/// [Poisson negative log likelihood](https://en.wikipedia.org/wiki/Poisson_regression) loss
+/// for count data, where the model predicts the log of the poisson rate.
+///
+/// This computes `(log_rates.exp() - targ * log_rates).mean()`.
+///
+/// See [poisson_nll_loss()] for when the model predicts the rate directly.
+///
+/// # Inputs
+/// - `log_rates` - the log of the expected counts. **NOT** the output of [exp()].
+/// - `targ` - observed counts.
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let log_rates = Tensor1D::new([-1.0, 0.5, 2.0]);
+/// let counts = Tensor1D::new([0.0, 2.0, 7.0]);
+/// let loss = poisson_nll_with_log_rates_loss(log_rates.traced(), &counts);
+/// ```
+pub fn poisson_nll_with_log_rates_loss<T: Tensor<Dtype = f32>>(
+    log_rates: T,
+    targ: &T::NoTape,
+) -> Tensor0D<T::Tape> {
+    mean(binary_map::binary_map(
+        log_rates,
+        targ,
+        |x, y| x.exp() - y * x,
+        |x, y| x.exp() - y,
+        |x, _| -x,
+    ))
+}
+
+/// [Poisson negative log likelihood](https://en.wikipedia.org/wiki/Poisson_regression) loss
+/// for count data, where the model predicts the poisson rate directly.
+///
+/// This computes `(rates - targ * (rates + eps).ln()).mean()`.
+///
+/// See [poisson_nll_with_log_rates_loss()] for a more numerically stable version that
+/// operates on log rates.
+///
+/// # Inputs
+/// - `rates` - the expected counts, which must be non-negative.
+/// - `targ` - observed counts.
+/// - `eps` - added to `rates` before taking the log to avoid `ln(0)`. `1e-8` is a common value.
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let rates = Tensor1D::new([0.5, 1.5, 8.0]);
+/// let counts = Tensor1D::new([0.0, 2.0, 7.0]);
+/// let loss = poisson_nll_loss(rates.traced(), &counts, 1e-8);
+/// ```
+pub fn poisson_nll_loss<T: Tensor<Dtype = f32>>(
+    rates: T,
+    targ: &T::NoTape,
+    eps: T::Dtype,
+) -> Tensor0D<T::Tape> {
+    mean(binary_map::binary_map(
+        rates,
+        targ,
+        move |x, y| x - y * (x + eps).ln(),
+        move |x, y| 1.0 - y / (x + eps),
+        move |x, _| -(x + eps).ln(),
+    ))
+}
+
+/// Soft [Dice loss](https://arxiv.org/abs/1606.04797) for segmentation.
+/// This computes `1 - (2 * (probs * targ).sum() + smooth) / (probs.sum() + targ.sum() + smooth)`
+/// over all elements.
+///
+/// # Arguments
+///
+/// - `probs`: Per-pixel probabilities, e.g. the output of [sigmoid()] or [softmax()].
+/// - `targ`: Target masks with values between 0 and 1.
+/// - `smooth`: Added to the numerator & denominator to avoid dividing by 0 on empty masks. `1.0` is a common value.
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let probs = Tensor2D::new([[0.9, 0.1], [0.8, 0.3]]);
+/// let targ = Tensor2D::new([[1.0, 0.0], [1.0, 0.0]]);
+/// let loss = dice_loss(probs.traced(), &targ, 1.0);
+/// ```
+pub fn dice_loss<T: Tensor<Dtype = f32>>(
+    probs: T,
+    targ: &T::NoTape,
+    smooth: T::Dtype,
+) -> Tensor0D<T::Tape> {
+    let targ_sum = sum(targ.duplicate()).data() + smooth;
+    let (probs, tape) = probs.split_tape();
+    let (intersection, tape) = sum(mul(probs.duplicate().put_tape(tape), targ)).split_tape();
+    let (denom, tape) = add_scalar(sum(probs.put_tape(tape)), targ_sum).split_tape();
+    let numer = add_scalar(mul_scalar(intersection.put_tape(tape), 2.0), smooth);
+    add_scalar(negate(div(numer, &denom)), 1.0)
+}
+
+/// Soft IoU (a.k.a. [Jaccard](https://en.wikipedia.org/wiki/Jaccard_index)) loss for segmentation.
+/// This computes `1 - (intersection + smooth) / (union + smooth)` over all elements, where
+/// `intersection = (probs * targ).sum()` and `union = probs.sum() + targ.sum() - intersection`.
+///
+/// # Arguments
+///
+/// - `probs`: Per-pixel probabilities, e.g. the output of [sigmoid()] or [softmax()].
+/// - `targ`: Target masks with values between 0 and 1.
+/// - `smooth`: Added to the numerator & denominator to avoid dividing by 0 on empty masks. `1.0` is a common value.
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let probs = Tensor2D::new([[0.9, 0.1], [0.8, 0.3]]);
+/// let targ = Tensor2D::new([[1.0, 0.0], [1.0, 0.0]]);
+/// let loss = iou_loss(probs.traced(), &targ, 1.0);
+/// ```
+pub fn iou_loss<T: Tensor<Dtype = f32>>(
+    probs: T,
+    targ: &T::NoTape,
+    smooth: T::Dtype,
+) -> Tensor0D<T::Tape> {
+    let targ_sum = sum(targ.duplicate()).data() + smooth;
+    let (probs, tape) = probs.split_tape();
+    let (intersection, tape) = sum(mul(probs.duplicate().put_tape(tape), targ)).split_tape();
+    let union = sub(
+        add_scalar(sum(probs.put_tape(tape)), targ_sum),
+        &intersection,
+    );
+    let (union, tape) = union.split_tape();
+    let numer = add_scalar(intersection.put_tape(tape), smooth);
+    add_scalar(negate(div(numer, &union)), 1.0)
+}
+
+/// [Triplet Margin Loss](https://pytorch.org/docs/stable/generated/torch.nn.TripletMarginLoss.html)
+/// for metric learning. Pulls `anchor` towards `positive` and pushes it away from `negative`.
+///
+/// This computes `max(d(anchor, positive) - d(anchor, negative) + margin, 0).mean()`, where
+/// `d(x, y)` is the `p`-norm of `x - y` along the last axis.
+///
+/// # Arguments
+///
+/// - `anchor`: The embeddings to compute the loss for.
+/// - `positive`: Embeddings that should be close to `anchor`.
+/// - `negative`: Embeddings that should be at least `margin` further from `anchor` than `positive`.
+///   See [hardest_negatives()] and [semi_hard_negatives()] for mining these from a batch.
+/// - `margin`: The desired gap between the positive and negative distances.
+/// - `p`: The degree of the norm. `2.0` is euclidean distance.
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let anchor = Tensor2D::new([[0.0, 1.0], [1.0, 0.0]]);
+/// let positive = Tensor2D::new([[0.0, 0.5], [0.5, 0.0]]);
+/// let negative = Tensor2D::new([[0.5, 0.5], [0.0, 0.5]]);
+/// let loss = triplet_margin_loss(anchor.traced(), &positive, &negative, 1.0, 2.0);
+/// ```
+pub fn triplet_margin_loss<T: Reduce1<-1>>(
+    anchor: T,
+    positive: &T::NoTape,
+    negative: &T::NoTape,
+    margin: f32,
+    p: f32,
+) -> Tensor0D<T::Tape> {
+    let (anchor, tape) = anchor.split_tape();
+    let d_pos = pairwise_distance(anchor.duplicate().put_tape(tape), positive, p);
+    let (d_pos, tape) = d_pos.split_tape();
+    let (d_neg, tape) = pairwise_distance(anchor.put_tape(tape), negative, p).split_tape();
+    mean(relu(add_scalar(sub(d_pos.put_tape(tape), &d_neg), margin)))
+}
+
+/// Added to differences before taking norms, so the gradient of the norm is defined at 0.
+const DISTANCE_EPS: f32 = 1e-6;
+
+/// Computes `(|x1 - x2 + eps| ^ p).sum(-1) ^ (1 / p)`.
+fn pairwise_distance<T: Reduce1<-1>>(x1: T, x2: &T::NoTape, p: f32) -> T::Reduced {
+    let diff = add_scalar(sub(x1, x2), DISTANCE_EPS);
+    let diff = map(
+        diff,
+        move |x| x.abs().powf(p),
+        move |x| p * x.signum() * x.abs().powf(p - 1.0),
+    );
+    map(
+        sum_axis::<T, -1>(diff),
+        move |x| x.powf(p.recip()),
+        move |x| x.powf(p.recip() - 1.0) / p,
+    )
+}
+
+/// The same distance as [pairwise_distance()], but for plain arrays.
+fn lp_distance<const N: usize>(x1: &[f32; N], x2: &[f32; N], p: f32) -> f32 {
+    x1.iter()
+        .zip(x2.iter())
+        .map(|(a, b)| (a - b + DISTANCE_EPS).abs().powf(p))
+        .sum::<f32>()
+        .powf(p.recip())
+}
+
+/// In-batch hard negative mining for [triplet_margin_loss()]. For every `anchor[i]`,
+/// finds the index `j` of the closest `candidates[j]` where `labels[j] != labels[i]`.
+///
+/// If every candidate has the same label as `anchor[i]`, then `i` is used.
+///
+/// The returned indices can be passed to [Select1::select()] along axis `0` to build the negatives.
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let anchor = Tensor2D::new([[0.0, 0.0], [1.0, 1.0], [5.0, 5.0]]);
+/// let positive = Tensor2D::new([[0.0, 0.1], [1.0, 1.1], [5.0, 5.1]]);
+/// let idx = hardest_negatives(&anchor, &positive, &[0, 1, 2], 2.0);
+/// assert_eq!(idx, [1, 0, 1]);
+/// let negative: Tensor2D<3, 2> = Select1::<_, 0>::select(positive.clone(), &idx);
+/// let loss = triplet_margin_loss(anchor.traced(), &positive, &negative, 1.0, 2.0);
+/// ```
+pub fn hardest_negatives<const B: usize, const N: usize, H>(
+    anchor: &Tensor2D<B, N, H>,
+    candidates: &Tensor2D<B, N>,
+    labels: &[usize; B],
+    p: f32,
+) -> [usize; B] {
+    let mut indices = [0; B];
+    for (i, a) in anchor.data().iter().enumerate() {
+        indices[i] =
+            closest_negative(a, candidates.data(), labels, i, p, f32::NEG_INFINITY).unwrap_or(i);
+    }
+    indices
+}
+
+/// In-batch semi-hard negative mining for [triplet_margin_loss()], as described in
+/// [FaceNet](https://arxiv.org/abs/1503.03832). For every `anchor[i]`, finds the index `j`
+/// of the closest `candidates[j]` where `labels[j] != labels[i]`, and which is further
+/// from `anchor[i]` than `positive[i]` is.
+///
+/// If there is no such candidate, the hardest negative is used instead (see [hardest_negatives()]).
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let anchor = Tensor2D::new([[0.0, 0.0], [1.0, 1.0], [5.0, 5.0]]);
+/// let positive = Tensor2D::new([[0.0, 2.0], [1.0, 1.1], [5.0, 5.1]]);
+/// let idx = semi_hard_negatives(&anchor, &positive, &positive, &[0, 1, 2], 2.0);
+/// assert_eq!(idx, [2, 0, 1]);
+/// ```
+pub fn semi_hard_negatives<const B: usize, const N: usize, H>(
+    anchor: &Tensor2D<B, N, H>,
+    positive: &Tensor2D<B, N>,
+    candidates: &Tensor2D<B, N>,
+    labels: &[usize; B],
+    p: f32,
+) -> [usize; B] {
+    let mut indices = [0; B];
+    for (i, (a, pos)) in anchor.data().iter().zip(positive.data().iter()).enumerate() {
+        let d_pos = lp_distance(a, pos, p);
+        indices[i] = closest_negative(a, candidates.data(), labels, i, p, d_pos)
+            .or_else(|| closest_negative(a, candidates.data(), labels, i, p, f32::NEG_INFINITY))
+            .unwrap_or(i);
+    }
+    indices
+}
+
+/// Index of the closest candidate with a different label than `labels[i]`
+/// whose distance from `a` is strictly more than `min_dist`.
+fn closest_negative<const B: usize, const N: usize>(
+    a: &[f32; N],
+    candidates: &[[f32; N]; B],
+    labels: &[usize; B],
+    i: usize,
+    p: f32,
+    min_dist: f32,
+) -> Option<usize> {
+    (0..B)
+        .filter(|&j| labels[j] != labels[i])
+        .map(|j| (j, lp_distance(a, &candidates[j], p)))
+        .filter(|&(_, d)| d > min_dist)
+        .min_by(|x, y| x.1.total_cmp(&y.1))
+        .map(|(j, _)| j)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::assert_close;
 
     #[test]
     fn test_mse() {
@@ -408,4 +758,142 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_quantile_loss() {
+        let pred = Tensor2D::new([[0.5, 1.0, 1.5], [-1.0, 0.0, 1.0]]);
+        let targ = Tensor2D::new([[1.2; 3], [0.3; 3]]);
+        let loss = quantile_loss(pred.trace(), &targ, &[0.1, 0.5, 0.9]);
+        assert!((loss.data() - 0.09166667).abs() < 1e-6);
+        let g = loss.backward();
+        assert_close(
+            g.ref_gradient(&pred),
+            &[[-0.016666668, -0.083333336, 0.016666668]; 2],
+        );
+    }
+
+    #[test]
+    fn test_poisson_nll_with_log_rates() {
+        let x = Tensor1D::new([-1.0, 0.5, 2.0, 0.1]);
+        let y = Tensor1D::new([0.0, 2.0, 7.0, 1.0]);
+        let loss = poisson_nll_with_log_rates_loss(x.trace(), &y);
+        assert!((loss.data() + 1.147293).abs() < 1e-6);
+        let g = loss.backward();
+        assert_close(
+            g.ref_gradient(&x),
+            &[0.09196986, -0.08781968, 0.09726402, 0.02629273],
+        );
+        assert_close(g.ref_gradient(&y), &[0.25, -0.125, -0.5, -0.025]);
+    }
+
+    #[test]
+    fn test_poisson_nll() {
+        let x = Tensor1D::new([0.5, 1.5, 8.0, 1.0]);
+        let y = Tensor1D::new([0.0, 2.0, 7.0, 1.0]);
+        let loss = poisson_nll_loss(x.trace(), &y, 1e-8);
+        assert!((loss.data() + 1.0917553).abs() < 1e-6);
+        let g = loss.backward();
+        assert_close(g.ref_gradient(&x), &[0.25, -0.083333336, 0.03125, 0.0]);
+        assert_close(
+            g.ref_gradient(&y),
+            &[0.1732868, -0.10136628, -0.5198604, 0.0],
+        );
+    }
+
+    #[test]
+    fn test_dice_loss() {
+        let probs = Tensor2D::new([[0.9, 0.2, 0.4], [0.1, 0.7, 0.6]]);
+        let targ = Tensor2D::new([[1.0, 0.0, 1.0], [0.0, 1.0, 0.0]]);
+        let loss = dice_loss(probs.trace(), &targ, 1.0);
+        assert!((loss.data() - 0.2753623).abs() < 1e-6);
+        let g = loss.backward();
+        assert_close(
+            g.ref_gradient(&probs),
+            &[
+                [-0.18483512, 0.10501995, -0.18483512],
+                [0.10501995, -0.18483512, 0.10501995],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_iou_loss() {
+        let probs = Tensor2D::new([[0.9, 0.2, 0.4], [0.1, 0.7, 0.6]]);
+        let targ = Tensor2D::new([[1.0, 0.0, 1.0], [0.0, 1.0, 0.0]]);
+        let loss = iou_loss(probs.trace(), &targ, 1.0);
+        assert!((loss.data() - 0.3877551).abs() < 1e-6);
+        let g = loss.backward();
+        assert_close(
+            g.ref_gradient(&probs),
+            &[
+                [-0.20408164, 0.12494794, -0.20408164],
+                [0.12494794, -0.20408164, 0.12494794],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_dice_and_iou_perfect_prediction() {
+        let targ = Tensor1D::new([1.0, 0.0, 1.0, 1.0]);
+        assert!(dice_loss(targ.clone(), &targ, 1.0).data().abs() < 1e-6);
+        assert!(iou_loss(targ.clone(), &targ, 1.0).data().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_triplet_margin_loss() {
+        let anchor = Tensor2D::new([[0.5, -1.0, 0.25], [1.5, 0.3, -0.7]]);
+        let positive = Tensor2D::new([[0.1, -0.8, 0.9], [1.0, 0.2, -0.1]]);
+        let negative = Tensor2D::new([[0.6, -0.9, 0.3], [-0.5, 1.2, 0.4]]);
+        let loss = triplet_margin_loss(anchor.trace(), &positive, &negative, 1.0, 2.0);
+        assert!((loss.data() - 0.8194939).abs() < 1e-6);
+        let g = loss.backward();
+        assert_close(
+            g.ref_gradient(&anchor),
+            &[[0.5868242, 0.2065894, -0.24525525], [0.0; 3]],
+        );
+        assert_close(
+            g.ref_gradient(&positive),
+            &[[-0.2534905, 0.12674431, 0.41192043], [0.0; 3]],
+        );
+        assert_close(
+            g.ref_gradient(&negative),
+            &[[-0.3333337, -0.3333337, -0.16666518], [0.0; 3]],
+        );
+    }
+
+    #[test]
+    fn test_triplet_margin_loss_satisfied() {
+        let anchor = Tensor1D::new([1.0, 2.0]);
+        let positive = Tensor1D::new([1.0, 2.5]);
+        let negative = Tensor1D::new([-3.0, 2.0]);
+        let loss = triplet_margin_loss(anchor.trace(), &positive, &negative, 1.0, 1.0);
+        assert_eq!(loss.data(), &0.0);
+        let g = loss.backward();
+        assert_eq!(g.ref_gradient(&anchor), &[0.0; 2]);
+    }
+
+    #[test]
+    fn test_hardest_negatives() {
+        let anchor = Tensor2D::new([[0.0, 0.0], [1.0, 1.0], [5.0, 5.0], [0.1, 0.0]]);
+        let labels = [0, 1, 2, 0];
+        assert_eq!(
+            hardest_negatives(&anchor, &anchor, &labels, 2.0),
+            [1, 3, 1, 1]
+        );
+        assert_eq!(
+            hardest_negatives(&anchor, &anchor, &[0; 4], 2.0),
+            [0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_semi_hard_negatives() {
+        let anchor = Tensor2D::new([[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]]);
+        let positive = Tensor2D::new([[0.0, 2.0], [1.0, 1.1], [2.0, 9.0]]);
+        let labels = [0, 1, 2];
+        assert_eq!(
+            semi_hard_negatives(&anchor, &positive, &positive, &labels, 2.0),
+            [2, 0, 1]
+        );
+    }
 }
@@ -0,0 +1,450 @@
+//! Streaming evaluation metrics such as [Accuracy] and [ConfusionMatrix], which are updated with
+//! one batch of predictions & targets at a time with [UpdateMetric::update()], and report their
+//! final value with [Metric::compute()].
+//!
+//! Targets have the same shape as the predictions, just like in [crate::losses]. Classification
+//! metrics take the argmax of the last axis of both, so targets are one hot vectors (see
+//! `one_hot_encode()`) or probabilities, and predictions are logits or probabilities.
+//!
+//! Example:
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let mut accuracy = Accuracy::default();
+//! let mut mse = MeanSquaredError::default();
+//! for _ in 0..2 {
+//!     let logits: Tensor2D<2, 3> = Tensor2D::new([[0.1, 0.5, 0.2], [2.0, 0.0, 1.0]]);
+//!     let targets: Tensor2D<2, 3> = Tensor2D::new([[0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+//!     accuracy.update(&logits, &targets);
+//!     mse.update(&logits, &targets);
+//! }
+//! assert_eq!(accuracy.compute(), 0.5);
+//! ```
+
+use crate::devices::flat;
+use crate::prelude::*;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// A metric that is accumulated over many batches, see [UpdateMetric].
+pub trait Metric {
+    /// The value of the metric, usually `f32`.
+    type Output;
+
+    /// The value of the metric over all batches since the last [Metric::reset()].
+    fn compute(&self) -> Self::Output;
+
+    /// Forgets all batches, e.g. at the start of an epoch.
+    fn reset(&mut self);
+}
+
+/// Adds a batch of predictions & targets of type `T` to a [Metric].
+pub trait UpdateMetric<T: Tensor<Dtype = f32>>: Metric {
+    /// Adds `pred` and `targ` to the metric. Only the values are used, so `pred` can have a tape.
+    fn update(&mut self, pred: &T, targ: &T::NoTape);
+}
+
+/// The index of the largest value of `row`, where the first index wins ties.
+fn argmax(row: &[f32]) -> usize {
+    let mut best = 0;
+    for (i, v) in row.iter().enumerate() {
+        if *v > row[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+/// Divides two counts, and returns `0.0` if there is nothing to divide.
+fn ratio(num: usize, den: usize) -> f32 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f32 / den as f32
+    }
+}
+
+/// The fraction of predictions where the argmax of the last axis equals the argmax of the target.
+///
+/// Every vector along the last axis is one sample, so a [Tensor2D] of shape `(B, C)` adds `B`
+/// samples, and a [Tensor3D] of shape `(B, S, C)` adds `B * S` samples.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Accuracy {
+    pub correct: usize,
+    pub total: usize,
+}
+
+impl Metric for Accuracy {
+    type Output = f32;
+    fn compute(&self) -> f32 {
+        ratio(self.correct, self.total)
+    }
+    fn reset(&mut self) {
+        *self = Default::default();
+    }
+}
+
+impl<T: Tensor<Dtype = f32>> UpdateMetric<T> for Accuracy
+where
+    T::Array: HasAxis<-1>,
+{
+    fn update(&mut self, pred: &T, targ: &T::NoTape) {
+        let n = <T::Array as HasAxis<-1>>::SIZE;
+        let pred = flat(pred.data()).chunks_exact(n);
+        let targ = flat(targ.data()).chunks_exact(n);
+        for (p, t) in pred.zip(targ) {
+            self.correct += (argmax(p) == argmax(t)) as usize;
+            self.total += 1;
+        }
+    }
+}
+
+/// The fraction of elements where the prediction and target are on the same side of a
+/// threshold, for binary or multi label classification.
+///
+/// Predictions are positive if they are `>= threshold`, which is `0.5` for probabilities
+/// by default (use `0.0` for logits). Targets are positive if they are `>= 0.5`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinaryAccuracy {
+    pub threshold: f32,
+    pub correct: usize,
+    pub total: usize,
+}
+
+impl BinaryAccuracy {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            correct: 0,
+            total: 0,
+        }
+    }
+}
+
+impl Default for BinaryAccuracy {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl Metric for BinaryAccuracy {
+    type Output = f32;
+    fn compute(&self) -> f32 {
+        ratio(self.correct, self.total)
+    }
+    fn reset(&mut self) {
+        self.correct = 0;
+        self.total = 0;
+    }
+}
+
+impl<T: Tensor<Dtype = f32>> UpdateMetric<T> for BinaryAccuracy {
+    fn update(&mut self, pred: &T, targ: &T::NoTape) {
+        for (p, t) in flat(pred.data()).iter().zip(flat(targ.data()).iter()) {
+            self.correct += ((*p >= self.threshold) == (*t >= 0.5)) as usize;
+            self.total += 1;
+        }
+    }
+}
+
+/// Counts how often each of the `C` classes is predicted for each target class, with the same
+/// argmax as [Accuracy]. [Metric::compute()] returns the counts, where `counts[t][p]` is the
+/// number of samples of class `t` that were predicted as class `p`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let mut cm = ConfusionMatrix::<2>::default();
+/// let probs = Tensor2D::new([[0.9, 0.1], [0.2, 0.8], [0.6, 0.4]]);
+/// let targets = Tensor2D::new([[1.0, 0.0], [0.0, 1.0], [0.0, 1.0]]);
+/// cm.update(&probs, &targets);
+/// assert_eq!(cm.compute(), [[1, 0], [1, 1]]);
+/// assert_eq!(cm.precision(0), 0.5);
+/// assert_eq!(cm.recall(1), 0.5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfusionMatrix<const C: usize> {
+    pub counts: [[usize; C]; C],
+}
+
+impl<const C: usize> Default for ConfusionMatrix<C> {
+    fn default() -> Self {
+        Self {
+            counts: [[0; C]; C],
+        }
+    }
+}
+
+impl<const C: usize> ConfusionMatrix<C> {
+    /// The fraction of samples predicted as `class` that are of `class`.
+    pub fn precision(&self, class: usize) -> f32 {
+        let predicted = self.counts.iter().map(|row| row[class]).sum();
+        ratio(self.counts[class][class], predicted)
+    }
+
+    /// The fraction of samples of `class` that are predicted as `class`.
+    pub fn recall(&self, class: usize) -> f32 {
+        let actual = self.counts[class].iter().sum();
+        ratio(self.counts[class][class], actual)
+    }
+
+    /// The harmonic mean of [ConfusionMatrix::precision()] and [ConfusionMatrix::recall()].
+    pub fn f1(&self, class: usize) -> f32 {
+        let p = self.precision(class);
+        let r = self.recall(class);
+        if p + r == 0.0 {
+            0.0
+        } else {
+            2.0 * p * r / (p + r)
+        }
+    }
+
+    /// The mean of [ConfusionMatrix::f1()] over all classes, which weighs rare classes as much
+    /// as common ones.
+    pub fn macro_f1(&self) -> f32 {
+        (0..C).map(|c| self.f1(c)).sum::<f32>() / C as f32
+    }
+
+    /// The fraction of all samples that are predicted correctly, same as [Accuracy].
+    pub fn accuracy(&self) -> f32 {
+        let correct = (0..C).map(|c| self.counts[c][c]).sum();
+        let total = self.counts.iter().flatten().sum();
+        ratio(correct, total)
+    }
+
+    fn add(&mut self, pred: &[f32], targ: &[f32]) {
+        let pred = pred.chunks_exact(C);
+        let targ = targ.chunks_exact(C);
+        for (p, t) in pred.zip(targ) {
+            self.counts[argmax(t)][argmax(p)] += 1;
+        }
+    }
+}
+
+impl<const C: usize> Metric for ConfusionMatrix<C> {
+    type Output = [[usize; C]; C];
+    fn compute(&self) -> Self::Output {
+        self.counts
+    }
+    fn reset(&mut self) {
+        *self = Default::default();
+    }
+}
+
+impl<const C: usize, H: Tape> UpdateMetric<Tensor1D<C, H>> for ConfusionMatrix<C> {
+    fn update(&mut self, pred: &Tensor1D<C, H>, targ: &Tensor1D<C>) {
+        self.add(pred.data(), targ.data());
+    }
+}
+
+impl<const B: usize, const C: usize, H: Tape> UpdateMetric<Tensor2D<B, C, H>>
+    for ConfusionMatrix<C>
+{
+    fn update(&mut self, pred: &Tensor2D<B, C, H>, targ: &Tensor2D<B, C>) {
+        self.add(flat(pred.data()), flat(targ.data()));
+    }
+}
+
+impl<const B: usize, const S: usize, const C: usize, H: Tape> UpdateMetric<Tensor3D<B, S, C, H>>
+    for ConfusionMatrix<C>
+{
+    fn update(&mut self, pred: &Tensor3D<B, S, C, H>, targ: &Tensor3D<B, S, C>) {
+        self.add(flat(pred.data()), flat(targ.data()));
+    }
+}
+
+/// The mean of `(pred - targ)^2` over all elements of all batches, same as [mse_loss()].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MeanSquaredError {
+    pub sum: f64,
+    pub total: usize,
+}
+
+impl Metric for MeanSquaredError {
+    type Output = f32;
+    fn compute(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.sum / self.total as f64) as f32
+        }
+    }
+    fn reset(&mut self) {
+        *self = Default::default();
+    }
+}
+
+impl<T: Tensor<Dtype = f32>> UpdateMetric<T> for MeanSquaredError {
+    fn update(&mut self, pred: &T, targ: &T::NoTape) {
+        for (p, t) in flat(pred.data()).iter().zip(flat(targ.data()).iter()) {
+            self.sum += ((p - t) as f64).powi(2);
+            self.total += 1;
+        }
+    }
+}
+
+/// The mean of `|pred - targ|` over all elements of all batches, same as [mae_loss()].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MeanAbsoluteError {
+    pub sum: f64,
+    pub total: usize,
+}
+
+impl Metric for MeanAbsoluteError {
+    type Output = f32;
+    fn compute(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.sum / self.total as f64) as f32
+        }
+    }
+    fn reset(&mut self) {
+        *self = Default::default();
+    }
+}
+
+impl<T: Tensor<Dtype = f32>> UpdateMetric<T> for MeanAbsoluteError {
+    fn update(&mut self, pred: &T, targ: &T::NoTape) {
+        for (p, t) in flat(pred.data()).iter().zip(flat(targ.data()).iter()) {
+            self.sum += ((p - t) as f64).abs();
+            self.total += 1;
+        }
+    }
+}
+
+/// The weighted mean of values that aren't predictions, like the loss of each batch.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let mut loss = RunningMean::default();
+/// loss.add(1.0, 3);
+/// loss.add(3.0, 1);
+/// assert_eq!(loss.compute(), 1.5);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RunningMean {
+    pub sum: f64,
+    pub weight: usize,
+}
+
+impl RunningMean {
+    /// Adds a `value` that is the mean of `weight` samples, e.g. the loss of a batch and the
+    /// batch size.
+    pub fn add(&mut self, value: f32, weight: usize) {
+        self.sum += value as f64 * weight as f64;
+        self.weight += weight;
+    }
+}
+
+impl Metric for RunningMean {
+    type Output = f32;
+    fn compute(&self) -> f32 {
+        if self.weight == 0 {
+            0.0
+        } else {
+            (self.sum / self.weight as f64) as f32
+        }
+    }
+    fn reset(&mut self) {
+        *self = Default::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accuracy_over_batches() {
+        let mut acc = Accuracy::default();
+        assert_eq!(acc.compute(), 0.0);
+
+        let pred = Tensor2D::new([[0.1, 0.7, 0.2], [0.5, 0.2, 0.3]]);
+        let targ = Tensor2D::new([[0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        acc.update(&pred.trace(), &targ);
+        acc.update(
+            &Tensor1D::new([3.0, 2.0, 1.0]),
+            &Tensor1D::new([1.0, 0.0, 0.0]),
+        );
+        assert_eq!(
+            acc,
+            Accuracy {
+                correct: 2,
+                total: 3
+            }
+        );
+        assert!((acc.compute() - 2.0 / 3.0).abs() < 1e-6);
+
+        acc.reset();
+        assert_eq!(acc.total, 0);
+    }
+
+    #[test]
+    fn test_accuracy_3d() {
+        let mut acc = Accuracy::default();
+        let pred: Tensor3D<2, 2, 2> =
+            Tensor3D::new([[[1.0, 0.0], [1.0, 0.0]], [[0.0, 1.0], [1.0, 0.0]]]);
+        let targ = Tensor3D::new([[[1.0, 0.0], [0.0, 1.0]], [[0.0, 1.0], [1.0, 0.0]]]);
+        acc.update(&pred, &targ);
+        assert_eq!(acc.compute(), 0.75);
+    }
+
+    #[test]
+    fn test_binary_accuracy() {
+        let pred = Tensor1D::new([-1.0, 0.5, 2.0, 0.0]);
+        let targ = Tensor1D::new([0.0, 0.0, 1.0, 1.0]);
+
+        let mut probs = BinaryAccuracy::default();
+        probs.update(&pred, &targ);
+        assert_eq!(probs.compute(), 0.5);
+
+        let mut logits = BinaryAccuracy::new(0.0);
+        logits.update(&pred, &targ);
+        assert_eq!(logits.compute(), 0.75);
+    }
+
+    #[test]
+    fn test_confusion_matrix() {
+        let mut cm = ConfusionMatrix::<3>::default();
+        let pred = Tensor2D::new([
+            [0.8, 0.1, 0.1],
+            [0.1, 0.8, 0.1],
+            [0.1, 0.8, 0.1],
+            [0.1, 0.1, 0.8],
+        ]);
+        let targ = Tensor2D::new([
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ]);
+        cm.update(&pred, &targ);
+        assert_eq!(cm.compute(), [[1, 1, 0], [0, 1, 1], [0, 0, 0]]);
+        assert_eq!(cm.precision(1), 0.5);
+        assert_eq!(cm.recall(0), 0.5);
+        assert_eq!(cm.f1(0), 2.0 / 3.0);
+        assert_eq!(cm.f1(2), 0.0);
+        assert!((cm.macro_f1() - (2.0 / 3.0 + 0.5) / 3.0).abs() < 1e-6);
+        assert_eq!(cm.accuracy(), 0.5);
+    }
+
+    #[test]
+    fn test_regression_metrics_match_losses() {
+        let mut rng = rand::thread_rng();
+        let mut mse = MeanSquaredError::default();
+        let mut mae = MeanAbsoluteError::default();
+        let mut mse_losses = RunningMean::default();
+        let mut mae_losses = RunningMean::default();
+        for _ in 0..3 {
+            let pred: Tensor2D<4, 5> = Tensor2D::randn(&mut rng);
+            let targ: Tensor2D<4, 5> = Tensor2D::randn(&mut rng);
+            mse.update(&pred, &targ);
+            mae.update(&pred, &targ);
+            mse_losses.add(*mse_loss(pred.clone(), &targ).data(), 20);
+            mae_losses.add(*mae_loss(pred, &targ).data(), 20);
+        }
+        assert!((mse.compute() - mse_losses.compute()).abs() < 1e-5);
+        assert!((mae.compute() - mae_losses.compute()).abs() < 1e-5);
+    }
+}
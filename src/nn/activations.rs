@@ -5,6 +5,7 @@ macro_rules! activation_impls {
     ($struct_name:ident, $func_name:ident, #[$docstring:meta]) => {
         #[$docstring]
         #[derive(Default, Debug, Clone, Copy)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $struct_name;
 
         impl CanUpdateWithGradients for $struct_name {
@@ -12,13 +13,25 @@ macro_rules! activation_impls {
             fn update<G: GradientProvider>(&mut self, _: &mut G, _: &mut UnusedTensors) {}
         }
 
+        impl VisitParams for $struct_name {
+            /// Does nothing.
+            fn visit_params<V: ParamVisitor>(&self, _: &str, _: &mut V) {}
+
+            /// Does nothing.
+            fn visit_params_mut<V: ParamVisitorMut>(&mut self, _: &str, _: &mut V) {}
+        }
+
         impl ResetParams for $struct_name {
             /// Does nothing.
             fn reset_params<R: Rng>(&mut self, _: &mut R) {}
         }
 
+        #[cfg(feature = "std")]
         impl SaveToNpz for $struct_name {}
+        #[cfg(feature = "std")]
         impl LoadFromNpz for $struct_name {}
+        #[cfg(feature = "std")]
+        impl<T: Tensor<Dtype = f32>> Summarize<T> for $struct_name where T: TensorShapes {}
 
         impl<T: Tensor<Dtype = f32>> Module<T> for $struct_name {
             type Output = T;
@@ -40,8 +53,46 @@ activation_impls!(Square, square, #[doc="Unit struct that impls [Module] as call
 activation_impls!(Sqrt, sqrt, #[doc="Unit struct that impls [Module] as calling [sqrt()] on `input`."]);
 activation_impls!(Abs, abs, #[doc="Unit struct that impls [Module] as calling [abs()] on `input`."]);
 
+#[cfg(feature = "std")]
+macro_rules! onnx_unary_impls {
+    ($($struct_name:ident => $op_type:literal),+) => {
+        $(impl ExportToOnnx for $struct_name {
+            #[doc=concat!("Adds a `", $op_type, "` node.")]
+            fn export_onnx(&self, _: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+                graph.add_unary($op_type, x)
+            }
+        })+
+    };
+}
+
+#[cfg(feature = "std")]
+onnx_unary_impls!(
+    ReLU => "Relu",
+    Sin => "Sin",
+    Cos => "Cos",
+    Ln => "Log",
+    Exp => "Exp",
+    Sigmoid => "Sigmoid",
+    Tanh => "Tanh",
+    Sqrt => "Sqrt",
+    Abs => "Abs"
+);
+
+#[cfg(feature = "std")]
+impl ExportToOnnx for Square {
+    /// Adds a `Mul` node that multiplies `x` by itself.
+    fn export_onnx(&self, _: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let y = graph.add_node("Mul", vec![x.name.clone(), x.name], vec![]);
+        OnnxValueInfo {
+            name: y,
+            shape: x.shape,
+        }
+    }
+}
+
 /// Unit struct that impls [Module] as calling [softmax()] on `input`."
 #[derive(Default, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Softmax;
 
 impl CanUpdateWithGradients for Softmax {
@@ -49,14 +100,48 @@ impl CanUpdateWithGradients for Softmax {
     fn update<G: GradientProvider>(&mut self, _: &mut G, _: &mut UnusedTensors) {}
 }
 
+impl VisitParams for Softmax {
+    /// Does nothing.
+    fn visit_params<V: ParamVisitor>(&self, _: &str, _: &mut V) {}
+
+    /// Does nothing.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, _: &str, _: &mut V) {}
+}
+
 impl ResetParams for Softmax {
     /// Does nothing.
     fn reset_params<R: Rng>(&mut self, _: &mut R) {}
 }
 
+#[cfg(feature = "std")]
 impl SaveToNpz for Softmax {}
+#[cfg(feature = "std")]
 impl LoadFromNpz for Softmax {}
 
+#[cfg(feature = "std")]
+impl<T> Summarize<T> for Softmax
+where
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
+
+#[cfg(feature = "std")]
+impl ExportToOnnx for Softmax {
+    /// Adds a `Softmax` node over the last axis.
+    fn export_onnx(&self, _: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let y = graph.add_node(
+            "Softmax",
+            vec![x.name],
+            vec![OnnxAttribute::int("axis", -1)],
+        );
+        OnnxValueInfo {
+            name: y,
+            shape: x.shape,
+        }
+    }
+}
+
 impl<T: Reduce1<-1>> Module<T> for Softmax {
     type Output = T;
     fn forward(&self, input: T) -> Self::Output {
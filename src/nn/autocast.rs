@@ -0,0 +1,129 @@
+use crate::prelude::*;
+
+/// Runs `M` in half precision for mixed precision training: the input and the output of `M` are
+/// rounded to `f16` with [to_f16()], and so are the gradients in the backward pass. Wrap the layers
+/// that are safe to run in half precision with this, like [Linear] and convolutions, and keep
+/// everything else (e.g. softmax, normalization & losses) in `f32`.
+///
+/// Use [crate::optim::MasterWeights] so that the parameters of `M` are in half precision too, and
+/// [crate::optim::LossScaler] so that small gradients don't underflow.
+///
+/// **Note** Tensors are always stored as `f32`, so this emulates the precision of `f16`,
+/// but not its memory use or speed.
+///
+/// # Examples
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let module: Autocast<Linear<2, 1>> = Default::default();
+/// let x = Tensor1D::new([1.0 + 1e-4, 2.0]);
+/// let y = module.forward(x.trace());
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Autocast<M>(pub M);
+
+impl<M: CanUpdateWithGradients> CanUpdateWithGradients for Autocast<M> {
+    /// Pass through to `M`'s [CanUpdateWithGradients].
+    fn update<G: GradientProvider>(&mut self, grads: &mut G, unused: &mut UnusedTensors) {
+        self.0.update(grads, unused);
+    }
+}
+
+impl<M: VisitParams> VisitParams for Autocast<M> {
+    /// Pass through to `M`'s [VisitParams].
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        self.0.visit_params(pre, visitor);
+    }
+
+    /// Pass through to `M`'s [VisitParams].
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        self.0.visit_params_mut(pre, visitor);
+    }
+}
+
+impl<M: ResetParams> ResetParams for Autocast<M> {
+    /// Pass through to `M`'s [ResetParams].
+    fn reset_params<R: rand::Rng>(&mut self, rng: &mut R) {
+        self.0.reset_params(rng);
+    }
+}
+
+impl<T, M> Module<T> for Autocast<M>
+where
+    T: Tensor<Dtype = f32>,
+    M: Module<T>,
+    M::Output: Tensor<Dtype = f32>,
+{
+    type Output = M::Output;
+
+    /// Calls forward on `M` with `x` rounded to `f16`, and rounds the result to `f16`.
+    fn forward(&self, x: T) -> Self::Output {
+        to_f16(self.0.forward(to_f16(x)))
+    }
+}
+
+impl<T, M> Summarize<T> for Autocast<M>
+where
+    T: Tensor<Dtype = f32>,
+    M: Summarize<T>,
+    M::Output: Tensor<Dtype = f32>,
+{
+    /// Adds a row for the autocast, followed by the rows of `M` with the same prefix.
+    fn summarize(&self, pre: &str, depth: usize, layers: &mut Vec<LayerSummary>) {
+        layers.push(LayerSummary::new::<T, _>(self, pre, depth, "Autocast"));
+        self.0.summarize(pre, depth + 1, layers);
+    }
+}
+
+impl<M: SaveToNpz> SaveToNpz for Autocast<M> {
+    /// Pass through to `M`'s [SaveToNpz].
+    fn write<W>(
+        &self,
+        filename_prefix: &str,
+        w: &mut zip::ZipWriter<W>,
+    ) -> zip::result::ZipResult<()>
+    where
+        W: std::io::Write + std::io::Seek,
+    {
+        self.0.write(filename_prefix, w)
+    }
+}
+
+impl<M: LoadFromNpz> LoadFromNpz for Autocast<M> {
+    /// Pass through to `M`'s [LoadFromNpz].
+    fn read<R>(&mut self, filename_prefix: &str, r: &mut zip::ZipArchive<R>) -> Result<(), NpzError>
+    where
+        R: std::io::Read + std::io::Seek,
+    {
+        self.0.read(filename_prefix, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{prelude::StdRng, SeedableRng};
+
+    #[test]
+    fn test_autocast_rounds_input_and_output() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Autocast<Linear<3, 2>> = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+
+        let y = model.forward(x.clone());
+        let expected = model.0.forward(x.clone().to_f16()).to_f16();
+        assert_eq!(y.data(), expected.data());
+        assert_ne!(y.data(), model.0.forward(x).data());
+    }
+
+    #[test]
+    fn test_autocast_gradients() {
+        let model: Autocast<Linear<1, 1>> = Default::default();
+        let x = Tensor1D::new([1.0]);
+        let gradients = (model.forward(x.trace()) * 1e-9).sum().backward();
+        assert_eq!(gradients.ref_gradient(&model.0.bias), &[0.0]);
+        let gradients = (model.forward(x.trace()) * 1e-3).sum().backward();
+        assert_eq!(gradients.ref_gradient(&model.0.bias), &[0.0010004044]);
+    }
+}
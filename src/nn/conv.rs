@@ -1,7 +1,12 @@
 use crate::prelude::*;
+use alloc::format;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 use rand::Rng;
 use rand_distr::Uniform;
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
 use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 /// **Requires Nightly** Performs 2d convolutions on 3d and 4d images.
@@ -24,6 +29,7 @@ use zip::{result::ZipResult, ZipArchive, ZipWriter};
 /// let _: Tensor4D<2, 33, 13, 12> = m.forward(Tensor4D::<2, 16, 15, 14>::zeros());
 /// ```
 #[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Conv2D<
     const IN_CHAN: usize,
     const OUT_CHAN: usize,
@@ -49,6 +55,27 @@ impl<
     }
 }
 
+impl<
+        const IN_CHAN: usize,
+        const OUT_CHAN: usize,
+        const KERNEL_SIZE: usize,
+        const STRIDE: usize,
+        const PADDING: usize,
+    > VisitParams for Conv2D<IN_CHAN, OUT_CHAN, KERNEL_SIZE, STRIDE, PADDING>
+{
+    /// Visits [Self::weight] and [Self::bias].
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        visitor.visit(&format!("{pre}weight"), &self.weight);
+        visitor.visit(&format!("{pre}bias"), &self.bias);
+    }
+
+    /// Visits [Self::weight] and [Self::bias].
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        visitor.visit_mut(&format!("{pre}weight"), &mut self.weight);
+        visitor.visit_mut(&format!("{pre}bias"), &mut self.bias);
+    }
+}
+
 impl<
         const IN_CHAN: usize,
         const OUT_CHAN: usize,
@@ -66,6 +93,7 @@ impl<
     }
 }
 
+#[cfg(feature = "std")]
 impl<
         const IN_CHAN: usize,
         const OUT_CHAN: usize,
@@ -86,6 +114,7 @@ impl<
     }
 }
 
+#[cfg(feature = "std")]
 impl<
         const IN_CHAN: usize,
         const OUT_CHAN: usize,
@@ -106,6 +135,63 @@ impl<
     }
 }
 
+#[cfg(feature = "std")]
+impl<
+        const IN_CHAN: usize,
+        const OUT_CHAN: usize,
+        const KERNEL_SIZE: usize,
+        const STRIDE: usize,
+        const PADDING: usize,
+        T,
+    > Summarize<T> for Conv2D<IN_CHAN, OUT_CHAN, KERNEL_SIZE, STRIDE, PADDING>
+where
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
+
+#[cfg(feature = "std")]
+impl<
+        const IN_CHAN: usize,
+        const OUT_CHAN: usize,
+        const KERNEL_SIZE: usize,
+        const STRIDE: usize,
+        const PADDING: usize,
+    > ExportToOnnx for Conv2D<IN_CHAN, OUT_CHAN, KERNEL_SIZE, STRIDE, PADDING>
+{
+    /// Adds a `Conv` node with [Self::weight] and [Self::bias], which are named `{pre}weight` and `{pre}bias`.
+    /// Since `Conv` requires a batch dimension, 3d inputs are unsqueezed before and squeezed after.
+    fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let weight = graph.add_initializer(format!("{pre}weight"), self.weight.data());
+        let bias = graph.add_initializer(format!("{pre}bias"), self.bias.data());
+        let unbatched = x.shape.len() == 3;
+
+        let mut y = x.name;
+        if unbatched {
+            let axes = graph.add_i64_initializer(format!("{pre}unsqueeze_axes"), &[0]);
+            y = graph.add_node("Unsqueeze", vec![y, axes], vec![]);
+        }
+        let attributes = vec![
+            OnnxAttribute::ints("kernel_shape", vec![KERNEL_SIZE as i64; 2]),
+            OnnxAttribute::ints("strides", vec![STRIDE as i64; 2]),
+            OnnxAttribute::ints("pads", vec![PADDING as i64; 4]),
+        ];
+        y = graph.add_node("Conv", vec![y, weight, bias], attributes);
+        if unbatched {
+            let axes = graph.add_i64_initializer(format!("{pre}squeeze_axes"), &[0]);
+            y = graph.add_node("Squeeze", vec![y, axes], vec![]);
+        }
+
+        let mut shape = x.shape;
+        let n = shape.len();
+        shape[n - 3] = OUT_CHAN;
+        for dim in shape[n - 2..].iter_mut() {
+            *dim = (*dim + 2 * PADDING - KERNEL_SIZE) / STRIDE + 1;
+        }
+        OnnxValueInfo { name: y, shape }
+    }
+}
+
 impl<
         TAPE: 'static + Tape,
         const IN_CHAN: usize,
@@ -183,6 +269,48 @@ mod tests {
     use std::fs::File;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn test_export_onnx() {
+        let model: Conv2D<2, 4, 3, 2, 1> = Default::default();
+        let onnx = model.to_onnx::<Tensor3D<2, 10, 9>>();
+        let ops: Vec<&str> = onnx
+            .graph
+            .nodes
+            .iter()
+            .map(|n| n.op_type.as_str())
+            .collect();
+        assert_eq!(ops, ["Unsqueeze", "Conv", "Squeeze"]);
+        assert_eq!(&onnx.graph.nodes[1].inputs[1..], &["weight", "bias"]);
+        assert_eq!(
+            onnx.graph.nodes[1].attributes[2],
+            OnnxAttribute::ints("pads", vec![1; 4])
+        );
+        assert_eq!(&onnx.graph.outputs[0].shape, &[4, 5, 5]);
+        assert_eq!(&onnx.graph.initializers[0].dims, &[4, 2, 3, 3]);
+
+        let onnx = model.to_onnx::<Tensor4D<3, 2, 10, 9>>();
+        let ops: Vec<&str> = onnx
+            .graph
+            .nodes
+            .iter()
+            .map(|n| n.op_type.as_str())
+            .collect();
+        assert_eq!(ops, ["Conv"]);
+        assert_eq!(&onnx.graph.outputs[0].shape, &[3, 4, 5, 5]);
+    }
+
+    #[test]
+    fn test_onnx_roundtrip() {
+        let mut rng = thread_rng();
+        let mut model: (Conv2D<2, 4, 3, 2, 1>, ReLU, FlattenImage) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor4D<3, 2, 7, 6> = TensorCreator::randn(&mut rng);
+
+        let onnx = OnnxInference::from_model(&model.to_onnx::<Tensor4D<3, 2, 7, 6>>()).expect("");
+        let y: Tensor2D<3, 48> = onnx.infer(&x).expect("");
+        crate::tests::assert_close(y.data(), model.forward(x).data());
+    }
+
     #[test]
     fn test_forward_3d_sizes() {
         type Img = Tensor3D<3, 10, 10>;
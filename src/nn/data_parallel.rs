@@ -0,0 +1,355 @@
+use crate::devices::{flat, flat_mut};
+use crate::prelude::*;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Computes the gradients of a loss on multiple threads, each with its own replica of the model.
+///
+/// Tensors can't be shared between threads, so every thread keeps a replica of the model, which
+/// is created with [Default] and gets a copy of the parameters of the model (by name, see
+/// [VisitParams]) before every [DataParallel::backward()]. A batch is split into shards by the caller, the replicas compute
+/// the loss and gradients of the shards in parallel, and the gradients are averaged. The
+/// gradients can then be passed to any [Optimizer].
+///
+/// The shards are moved to the threads, so they have to be [Send], e.g. arrays instead of tensors.
+///
+/// The [seeded_rng()] of each thread gets a different seed drawn from the [seeded_rng()] of the thread
+/// calling [DataParallel::new()], so e.g. [Dropout] differs between replicas and is reproducible with [crate::set_seed()].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = (Linear<5, 16>, ReLU, Linear<16, 2>);
+/// let mut model: Model = Default::default();
+/// let mut opt: Sgd<Model> = Default::default();
+/// let mut dp = DataParallel::new(2, |m: &Model, (x, y): ([[f32; 5]; 4], [[f32; 2]; 4])| {
+///     mse_loss(m.forward(Tensor2D::new(x).traced()), &Tensor2D::new(y))
+/// });
+///
+/// // a batch of 8 samples, split into 2 shards of 4
+/// let shards = vec![([[0.0; 5]; 4], [[1.0; 2]; 4]); 2];
+/// let (loss, gradients) = dp.backward(&model, shards);
+/// opt.update(&mut model, gradients).expect("unused params");
+/// ```
+pub struct DataParallel<M, I> {
+    workers: Vec<Worker<I>>,
+    marker: PhantomData<*const M>,
+}
+
+struct Job<I> {
+    params: Arc<StateDict>,
+    shards: Vec<I>,
+}
+
+/// The sum of the losses and of the gradients of every parameter, keyed by name.
+struct JobResult {
+    loss: f32,
+    gradients: BTreeMap<String, Vec<f32>>,
+}
+
+struct Worker<I> {
+    jobs: Option<Sender<Job<I>>>,
+    results: Receiver<JobResult>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<M, I> DataParallel<M, I>
+where
+    M: Default + VisitParams + 'static,
+    I: Send + 'static,
+{
+    /// Starts `num_replicas` threads that compute the loss of a shard with `loss_fn`.
+    ///
+    /// **Panics** if `num_replicas` is 0.
+    pub fn new<F>(num_replicas: usize, loss_fn: F) -> Self
+    where
+        F: Fn(&M, I) -> Tensor0D<OwnedTape> + Send + Sync + 'static,
+    {
+        assert!(num_replicas > 0, "DataParallel needs at least 1 replica");
+        let loss_fn = Arc::new(loss_fn);
+        let workers = (0..num_replicas)
+            .map(|_| {
+                let (jobs, job_receiver) = channel::<Job<I>>();
+                let (result_sender, results) = channel();
+                let loss_fn = loss_fn.clone();
+                // drawn here so the seed of each replica only depends on the seed of this thread
+                let seed = seeded_rng().gen();
+                let handle = std::thread::spawn(move || {
+                    crate::rng::reseed_thread(seed);
+                    let mut replica = M::default();
+                    for job in job_receiver {
+                        let result = run_job(&mut replica, job, loss_fn.as_ref());
+                        if result_sender.send(result).is_err() {
+                            break;
+                        }
+                    }
+                });
+                Worker {
+                    jobs: Some(jobs),
+                    results,
+                    handle: Some(handle),
+                }
+            })
+            .collect();
+        Self {
+            workers,
+            marker: PhantomData,
+        }
+    }
+
+    /// The number of threads.
+    pub fn num_replicas(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Computes the loss of every shard with the parameters of `model`, and returns the average
+    /// loss and the average gradients of `model`'s parameters. The shards are distributed evenly
+    /// across the replicas. Frozen parameters don't get gradients.
+    ///
+    /// **Panics** if `shards` is empty, or if a replica panicked.
+    pub fn backward(&mut self, model: &M, shards: Vec<I>) -> (f32, Gradients) {
+        let num_shards = shards.len();
+        assert!(
+            num_shards > 0,
+            "DataParallel::backward() needs at least 1 shard"
+        );
+
+        let params = Arc::new(model.state_dict());
+
+        // the first `num_shards % num_replicas` replicas get one more shard
+        let num_replicas = self.workers.len();
+        let mut shards = shards.into_iter();
+        let mut num_jobs = 0;
+        for (i, worker) in self.workers.iter().enumerate() {
+            let n = num_shards / num_replicas + usize::from(i < num_shards % num_replicas);
+            if n == 0 {
+                break;
+            }
+            let job = Job {
+                params: params.clone(),
+                shards: shards.by_ref().take(n).collect(),
+            };
+            let sent = worker.jobs.as_ref().unwrap().send(job);
+            sent.expect("a DataParallel replica panicked");
+            num_jobs += 1;
+        }
+
+        let results: Vec<JobResult> = self.workers[..num_jobs]
+            .iter()
+            .map(|w| w.results.recv().expect("a DataParallel replica panicked"))
+            .collect();
+        let loss = results.iter().map(|r| r.loss).sum::<f32>() / num_shards as f32;
+
+        let mut average = AverageGradients {
+            replicas: results.into_iter().map(|r| r.gradients).collect(),
+            scale: 1.0 / num_shards as f32,
+            gradients: Default::default(),
+        };
+        model.visit_params("", &mut average);
+        (loss, average.gradients)
+    }
+}
+
+impl<M, I> Drop for DataParallel<M, I> {
+    fn drop(&mut self) {
+        for worker in self.workers.iter_mut() {
+            // closing the channel stops the thread
+            drop(worker.jobs.take());
+            if let Some(handle) = worker.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl<M, I> std::fmt::Debug for DataParallel<M, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataParallel")
+            .field("num_replicas", &self.workers.len())
+            .finish()
+    }
+}
+
+/// Loads the parameters of `job` into `replica`, and sums the losses & gradients of its shards.
+fn run_job<M, I, F>(replica: &mut M, job: Job<I>, loss_fn: &F) -> JobResult
+where
+    M: VisitParams,
+    F: Fn(&M, I) -> Tensor0D<OwnedTape>,
+{
+    // NOTE: the replica has the same type as the model, so every name matches
+    replica
+        .load_state_dict(&job.params, true)
+        .expect("replica has the same parameters as the model");
+
+    let mut result = JobResult {
+        loss: 0.0,
+        gradients: BTreeMap::new(),
+    };
+    for shard in job.shards {
+        let loss = loss_fn(replica, shard);
+        result.loss += *loss.data();
+        let mut sum = SumGradients {
+            gradients: loss.backward(),
+            sums: &mut result.gradients,
+        };
+        replica.visit_params("", &mut sum);
+    }
+    result
+}
+
+/// Adds the gradient of each parameter to the sum with the same name.
+struct SumGradients<'a> {
+    gradients: Gradients,
+    sums: &'a mut BTreeMap<String, Vec<f32>>,
+}
+
+impl ParamVisitor for SumGradients<'_> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+        if let Some(g) = self.gradients.get(p) {
+            let g = flat(g);
+            match self.sums.get_mut(name) {
+                Some(sum) => sum.iter_mut().zip(g).for_each(|(s, g)| *s += g),
+                None => {
+                    self.sums.insert(name.into(), g.to_vec());
+                }
+            }
+        }
+    }
+}
+
+/// Inserts the sum of each trainable parameter's gradients across the replicas times `scale`
+/// into `gradients`.
+struct AverageGradients {
+    replicas: Vec<BTreeMap<String, Vec<f32>>>,
+    scale: f32,
+    gradients: Gradients,
+}
+
+impl ParamVisitor for AverageGradients {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+        if !p.requires_grad() {
+            return;
+        }
+        let mut average: Option<Box<P::Array>> = None;
+        for g in self.replicas.iter().filter_map(|r| r.get(name)) {
+            let average = flat_mut(average.get_or_insert_with(P::Device::zeros).as_mut());
+            average
+                .iter_mut()
+                .zip(g)
+                .for_each(|(a, g)| *a += g * self.scale);
+        }
+        if let Some(average) = average {
+            self.gradients.insert(p, average);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::StdRng, SeedableRng};
+
+    type Model = (Linear<3, 4>, ReLU, Linear<4, 2>);
+    type Shard = ([[f32; 3]; 2], [[f32; 2]; 2]);
+
+    fn loss_fn(m: &Model, (x, y): Shard) -> Tensor0D<OwnedTape> {
+        mse_loss(m.forward(Tensor2D::new(x).traced()), &Tensor2D::new(y))
+    }
+
+    #[test]
+    fn test_data_parallel_matches_single_thread() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let shards: Vec<Shard> = (0..5)
+            .map(|_| {
+                let x: Tensor2D<2, 3> = TensorCreator::randn(&mut rng);
+                let y: Tensor2D<2, 2> = TensorCreator::randn(&mut rng);
+                (*x.data(), *y.data())
+            })
+            .collect();
+
+        let mut dp = DataParallel::new(3, loss_fn);
+        assert_eq!(dp.num_replicas(), 3);
+        let (loss, gradients) = dp.backward(&model, shards.clone());
+
+        let mut expected_loss = 0.0;
+        let mut expected: Vec<Gradients> = Vec::new();
+        for shard in shards {
+            let l = loss_fn(&model, shard);
+            expected_loss += *l.data() / 5.0;
+            expected.push(l.backward());
+        }
+        assert!((loss - expected_loss).abs() < 1e-6);
+
+        let mut w = [[0.0; 3]; 4];
+        for g in expected.iter() {
+            for (w, g) in w.iter_mut().zip(g.ref_gradient(&model.0.weight)) {
+                for (w, g) in w.iter_mut().zip(g) {
+                    *w += g / 5.0;
+                }
+            }
+        }
+        assert_close(gradients.ref_gradient(&model.0.weight), &w);
+
+        // the replicas use the new parameters
+        let mut opt: Sgd<Model> = Default::default();
+        opt.update(&mut model, gradients).expect("");
+        let shard = ([[1.0; 3]; 2], [[0.0; 2]; 2]);
+        let (loss, _) = dp.backward(&model, vec![shard]);
+        assert_eq!(loss, *loss_fn(&model, shard).data());
+    }
+
+    #[test]
+    fn test_data_parallel_replicas_have_different_seeds() {
+        type Model = (Linear<1, 16>, DropoutOneIn<2>);
+        let run = || {
+            set_seed(1);
+            let model: Model = Default::default();
+            let mut dp = DataParallel::new(2, |m: &Model, x: [f32; 1]| {
+                m.forward(Tensor1D::new(x).traced()).sum()
+            });
+            let (_, gradients) = dp.backward(&model, vec![[1.0]; 2]);
+            *gradients.ref_gradient(&model.0.bias)
+        };
+        let g = run();
+        assert_eq!(g, run());
+        // each element is `0.0` or `2.0` in each replica, so `1.0` means the masks differ
+        assert!(g.contains(&1.0));
+    }
+
+    #[test]
+    fn test_data_parallel_unused_params() {
+        let mut model: (Linear<3, 2>, Linear<3, 2>) = Default::default();
+        let mut dp = DataParallel::new(2, |m: &(Linear<3, 2>, Linear<3, 2>), x: [f32; 3]| {
+            m.0.forward(Tensor1D::new(x).traced()).sum()
+        });
+        let (_, gradients) = dp.backward(&model, vec![[1.0; 3]; 2]);
+        let mut opt: Sgd<(Linear<3, 2>, Linear<3, 2>)> = Default::default();
+        opt.update(&mut model, gradients).expect_err("");
+    }
+
+    #[test]
+    fn test_data_parallel_frozen_params() {
+        type Model = (Linear<3, 3>, Linear<3, 2>);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut StdRng::seed_from_u64(0));
+        model.0.freeze();
+        let before = model.clone();
+        let mut dp = DataParallel::new(2, |m: &Model, x: [f32; 3]| {
+            m.forward(Tensor1D::new(x).traced()).sum()
+        });
+        let (loss, gradients) = dp.backward(&model, vec![[1.0; 3]; 2]);
+        assert!(gradients.get(&model.0.weight).is_none());
+        assert_eq!(loss, *before.forward(Tensor1D::new([1.0; 3])).sum().data());
+        let mut opt: Sgd<Model> = Default::default();
+        opt.update(&mut model, gradients).expect("");
+        assert_eq!(model.0.weight.data(), before.0.weight.data());
+        assert!(model.1.weight.data() != before.1.weight.data());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::prelude::*;
+use core::{cell::RefCell, ops::DerefMut};
 use rand::{prelude::StdRng, Rng, SeedableRng};
-use std::{cell::RefCell, ops::DerefMut};
 
 /// A [Module<Tensor>] that calls [dropout()] in [Module::forward()] with probability `1.0 / N`.
 /// Note that [dropout()] does not do anything for tensors with [NoneTape].
@@ -14,20 +14,32 @@ use std::{cell::RefCell, ops::DerefMut};
 /// let dropout: DropoutOneIn<2> = Default::default();
 /// let t: Tensor2D<2, 5> = Tensor2D::ones();
 /// let r = dropout.forward(t.trace());
-/// assert_eq!(r.data(), &[[2.0, 2.0, 2.0, 2.0, 2.0], [0.0, 2.0, 2.0, 2.0, 0.0]]);
+/// assert_eq!(r.data(), &[[2.0, 2.0, 2.0, 0.0, 0.0], [2.0, 2.0, 0.0, 0.0, 2.0]]);
 /// ```
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DropoutOneIn<const N: usize> {
+    #[cfg_attr(feature = "serde", serde(skip, default = "new_rng"))]
     rng: RefCell<StdRng>,
 }
 
+/// A new [StdRng] seeded from [seeded_rng()], so every call has a different seed that is
+/// reproducible with [crate::set_seed()].
+#[cfg(feature = "std")]
+fn new_rng() -> RefCell<StdRng> {
+    RefCell::new(StdRng::seed_from_u64(seeded_rng().gen()))
+}
+
+/// A new [StdRng] seeded from the [UniqueId] constructor, so every call has a different seed.
+#[cfg(not(feature = "std"))]
+fn new_rng() -> RefCell<StdRng> {
+    RefCell::new(StdRng::seed_from_u64(unique_id().as_u64()))
+}
+
 impl<const N: usize> Default for DropoutOneIn<N> {
-    /// Seeds [StdRng] with a new seed every time this is called. The seed comes from the [UniqueId] constructor.
+    /// Seeds [StdRng] with a new seed every time this is called. The seed comes from [seeded_rng()].
     fn default() -> Self {
-        let seed = unique_id().as_u64();
-        Self {
-            rng: RefCell::new(StdRng::seed_from_u64(seed)),
-        }
+        Self { rng: new_rng() }
     }
 }
 
@@ -36,14 +48,40 @@ impl<const N: usize> CanUpdateWithGradients for DropoutOneIn<N> {
     fn update<G: GradientProvider>(&mut self, _: &mut G, _: &mut UnusedTensors) {}
 }
 
+impl<const N: usize> VisitParams for DropoutOneIn<N> {
+    /// Does nothing.
+    fn visit_params<V: ParamVisitor>(&self, _: &str, _: &mut V) {}
+
+    /// Does nothing.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, _: &str, _: &mut V) {}
+}
+
 impl<const N: usize> ResetParams for DropoutOneIn<N> {
     /// Does nothing.
     fn reset_params<R: Rng>(&mut self, _: &mut R) {}
 }
 
+#[cfg(feature = "std")]
 impl<const N: usize> SaveToNpz for DropoutOneIn<N> {}
+#[cfg(feature = "std")]
 impl<const N: usize> LoadFromNpz for DropoutOneIn<N> {}
 
+#[cfg(feature = "std")]
+impl<const N: usize, T> Summarize<T> for DropoutOneIn<N>
+where
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> ExportToOnnx for DropoutOneIn<N> {
+    /// Does nothing, since dropout is the identity at inference.
+    fn export_onnx(&self, _: &str, _: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        x
+    }
+}
+
 impl<const N: usize, T: Tensor<Dtype = f32>> Module<T> for DropoutOneIn<N> {
     type Output = T;
 
@@ -85,8 +123,10 @@ impl<const N: usize, T: Tensor<Dtype = f32>> Module<T> for DropoutOneIn<N> {
 /// );
 /// ```
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dropout {
     pub p: f32,
+    #[cfg_attr(feature = "serde", serde(skip, default = "new_rng"))]
     rng: RefCell<StdRng>,
 }
 
@@ -101,11 +141,7 @@ impl Dropout {
 
     /// Constructs [Dropout] with `p` and a different seed every call.
     pub fn p(p: f32) -> Self {
-        let seed = unique_id().as_u64();
-        Self {
-            p,
-            rng: RefCell::new(StdRng::seed_from_u64(seed)),
-        }
+        Self { p, rng: new_rng() }
     }
 }
 
@@ -121,14 +157,40 @@ impl CanUpdateWithGradients for Dropout {
     fn update<G: GradientProvider>(&mut self, _: &mut G, _: &mut UnusedTensors) {}
 }
 
+impl VisitParams for Dropout {
+    /// Does nothing.
+    fn visit_params<V: ParamVisitor>(&self, _: &str, _: &mut V) {}
+
+    /// Does nothing.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, _: &str, _: &mut V) {}
+}
+
 impl ResetParams for Dropout {
     /// Does nothing.
     fn reset_params<R: rand::Rng>(&mut self, _: &mut R) {}
 }
 
+#[cfg(feature = "std")]
 impl SaveToNpz for Dropout {}
+#[cfg(feature = "std")]
 impl LoadFromNpz for Dropout {}
 
+#[cfg(feature = "std")]
+impl<T> Summarize<T> for Dropout
+where
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
+
+#[cfg(feature = "std")]
+impl ExportToOnnx for Dropout {
+    /// Does nothing, since dropout is the identity at inference.
+    fn export_onnx(&self, _: &str, _: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        x
+    }
+}
+
 impl<T: Tensor<Dtype = f32>> Module<T> for Dropout {
     type Output = T;
 
@@ -0,0 +1,408 @@
+use crate::gradients::Tape;
+use crate::prelude::*;
+
+/// A batch of samples whose size is only known at runtime. Each sample is a tensor with a
+/// shape that is known at compile time, and all samples share the same [Tape].
+///
+/// Ops are applied to each sample with [DynBatch::map()] and [DynBatch::zip_map()], and
+/// [DynBatch::sum()] & [DynBatch::mean()] reduce over the batch dimension. Every module of
+/// [crate::nn] implements [Module] for [DynBatch], so a model can be run on a batch of any size,
+/// and losses are computed per sample and then averaged:
+///
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let model: (Linear<3, 4>, ReLU, Linear<4, 2>) = Default::default();
+/// let x: DynBatch<Tensor1D<3>> = DynBatch::new(vec![Tensor1D::zeros(); 5]);
+/// let y: DynBatch<Tensor1D<2>, OwnedTape> = model.forward(x.traced());
+/// assert_eq!(y.len(), 5);
+///
+/// let targets: Vec<Tensor1D<2>> = vec![Tensor1D::ones(); 5];
+/// let loss: Tensor0D<OwnedTape> = y.zip_map(&targets, mse_loss).mean();
+/// let gradients = loss.backward();
+/// ```
+///
+/// Since each sample goes through the model on its own, this is slower than a batch with a
+/// compile time size. See [forward_dynamic()] for running a model on a runtime number of samples
+/// without computing gradients.
+#[derive(Debug, Clone)]
+pub struct DynBatch<T, Tape = NoneTape> {
+    samples: Vec<T>,
+    tape: Tape,
+}
+
+impl<T> DynBatch<T, NoneTape> {
+    /// Creates a batch from `samples`.
+    pub fn new(samples: Vec<T>) -> Self {
+        Self {
+            samples,
+            tape: NoneTape,
+        }
+    }
+
+    /// Returns the samples of the batch.
+    pub fn into_samples(self) -> Vec<T> {
+        self.samples
+    }
+}
+
+impl<T: Tensor<Tape = NoneTape, NoTape = T>> DynBatch<T, NoneTape> {
+    /// Clones `self` and returns a copy with [OwnedTape] as the [Tape]. The samples keep their
+    /// ids, so their gradients can be looked up with the original samples.
+    pub fn trace(&self) -> DynBatch<T, OwnedTape> {
+        DynBatch::new(self.samples.iter().map(Tensor::duplicate).collect()).traced()
+    }
+
+    /// Takes ownership of `self` and inserts [OwnedTape] as the [Tape].
+    pub fn traced(self) -> DynBatch<T, OwnedTape> {
+        self.put_tape(Default::default())
+    }
+}
+
+impl<T, H> DynBatch<T, H> {
+    /// The number of samples in the batch.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Whether there are no samples in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// The samples of the batch, without the tape.
+    pub fn samples(&self) -> &[T] {
+        &self.samples
+    }
+
+    /// Removes the tape from the batch.
+    pub fn split_tape(self) -> (DynBatch<T, NoneTape>, H) {
+        (DynBatch::new(self.samples), self.tape)
+    }
+
+    /// Replaces the tape of the batch with `tape`.
+    pub fn put_tape<H2: Tape>(self, tape: H2) -> DynBatch<T, H2> {
+        DynBatch {
+            samples: self.samples,
+            tape,
+        }
+    }
+}
+
+impl<T: Tensor<Tape = NoneTape, NoTape = T>, H: Tape> DynBatch<T, H> {
+    /// Calls `f` on each sample with the tape of the batch, e.g. `x.map(relu)`.
+    pub fn map<U, F>(self, mut f: F) -> DynBatch<U::NoTape, H>
+    where
+        T: PutTape<H>,
+        U: Tensor<Tape = H>,
+        F: FnMut(<T as PutTape<H>>::Output) -> U,
+    {
+        let mut tape = self.tape;
+        let mut samples = Vec::with_capacity(self.samples.len());
+        for x in self.samples {
+            let (y, t) = f(x.put_tape(tape)).split_tape();
+            samples.push(y);
+            tape = t;
+        }
+        DynBatch { samples, tape }
+    }
+
+    /// Calls `f` on each sample with the tape of the batch and the matching item of `rhs`. This
+    /// is how losses are computed, e.g. `y.zip_map(&targets, mse_loss).mean()`.
+    ///
+    /// Panics if `rhs` does not have an item for each sample.
+    pub fn zip_map<R, U, F>(self, rhs: &[R], mut f: F) -> DynBatch<U::NoTape, H>
+    where
+        T: PutTape<H>,
+        U: Tensor<Tape = H>,
+        F: FnMut(<T as PutTape<H>>::Output, &R) -> U,
+    {
+        assert_eq!(self.len(), rhs.len(), "batch sizes don't match");
+        let mut rhs = rhs.iter();
+        self.map(|x| f(x, rhs.next().unwrap()))
+    }
+
+    /// Sums the samples of the batch, i.e. over the batch dimension.
+    pub fn sum(self) -> <T as PutTape<H>>::Output
+    where
+        T: 'static + PutTape<H> + TensorCreator,
+    {
+        let _op = crate::profile::op("sum");
+        let mut data: Box<T::Array> = T::Device::zeros();
+        for x in self.samples.iter() {
+            T::Device::add(data.as_mut(), x.data());
+        }
+        let result = T::new_boxed(data);
+        let phantom_result = result.phantom();
+        let mut tape = self.tape;
+        let samples = self.samples;
+        tape.add_backward_op(move |grads| {
+            let result_grad = grads.remove(&phantom_result).unwrap();
+            for x in samples.iter() {
+                T::Device::add(grads.mut_gradient(x), result_grad.as_ref());
+            }
+        });
+        result.put_tape(tape)
+    }
+
+    /// Averages the samples of the batch, i.e. over the batch dimension.
+    ///
+    /// Panics if the batch is empty.
+    pub fn mean(self) -> <T as PutTape<H>>::Output
+    where
+        T: 'static + PutTape<H> + TensorCreator,
+        <T as PutTape<H>>::Output: Tensor<Dtype = f32>,
+    {
+        assert!(!self.is_empty(), "can't average an empty batch");
+        let n = self.len() as f32;
+        div_scalar(self.sum(), n)
+    }
+}
+
+/// Implements [Module] for a [DynBatch] of `$tensor`s by calling the module on each sample.
+macro_rules! dyn_batch_module {
+    ([$($params:tt)*], $module:ty, $tensor:ident, [$($Vs:tt),*]) => {
+        impl<$($params)* H: Tape> Module<DynBatch<$tensor<$($Vs, )* NoneTape>, H>> for $module
+        where
+            Self: Module<$tensor<$($Vs, )* H>>,
+            <Self as Module<$tensor<$($Vs, )* H>>>::Output: Tensor<Tape = H>,
+        {
+            type Output =
+                DynBatch<<<Self as Module<$tensor<$($Vs, )* H>>>::Output as Tensor>::NoTape, H>;
+
+            /// Calls [Module::forward()] on each sample.
+            fn forward(&self, input: DynBatch<$tensor<$($Vs, )* NoneTape>, H>) -> Self::Output {
+                input.map(|x| self.forward(x))
+            }
+
+            /// Calls [Module::forward_mut()] on each sample.
+            fn forward_mut(&mut self, input: DynBatch<$tensor<$($Vs, )* NoneTape>, H>) -> Self::Output {
+                input.map(|x| self.forward_mut(x))
+            }
+        }
+    };
+}
+
+/// Calls [dyn_batch_module] for samples of every rank.
+macro_rules! dyn_batch_module_all_ranks {
+    ([$($params:tt)*], $module:ty) => {
+        dyn_batch_module!([$($params)*], $module, Tensor0D, []);
+        dyn_batch_module!([$($params)* const M: usize,], $module, Tensor1D, [M]);
+        dyn_batch_module!([$($params)* const M: usize, const N: usize,], $module, Tensor2D, [M, N]);
+        dyn_batch_module!(
+            [$($params)* const M: usize, const N: usize, const O: usize,],
+            $module,
+            Tensor3D, [M, N, O]
+        );
+        dyn_batch_module!(
+            [$($params)* const M: usize, const N: usize, const O: usize, const P: usize,],
+            $module,
+            Tensor4D, [M, N, O, P]
+        );
+    };
+}
+
+dyn_batch_module!([const I: usize, const O: usize,], Linear<I, O>, Tensor1D, [I]);
+dyn_batch_module!([const I: usize, const O: usize, const S: usize,], Linear<I, O>, Tensor2D, [S, I]);
+dyn_batch_module!([const M: usize,], LayerNorm1D<M>, Tensor1D, [M]);
+dyn_batch_module!([const M: usize, const S: usize,], LayerNorm1D<M>, Tensor2D, [S, M]);
+dyn_batch_module_all_ranks!([], ReLU);
+dyn_batch_module_all_ranks!([], Sin);
+dyn_batch_module_all_ranks!([], Cos);
+dyn_batch_module_all_ranks!([], Ln);
+dyn_batch_module_all_ranks!([], Exp);
+dyn_batch_module_all_ranks!([], Sigmoid);
+dyn_batch_module_all_ranks!([], Tanh);
+dyn_batch_module_all_ranks!([], Square);
+dyn_batch_module_all_ranks!([], Sqrt);
+dyn_batch_module_all_ranks!([], Abs);
+dyn_batch_module_all_ranks!([], Softmax);
+dyn_batch_module_all_ranks!([], Dropout);
+dyn_batch_module_all_ranks!([const I: usize,], DropoutOneIn<I>);
+dyn_batch_module_all_ranks!([F,], Residual<F>);
+dyn_batch_module_all_ranks!([F, R,], GeneralizedResidual<F, R>);
+
+#[cfg(feature = "nightly")]
+dyn_batch_module!([const M: usize, const N: usize, const O: usize,], FlattenImage, Tensor3D, [M, N, O]);
+#[cfg(feature = "nightly")]
+dyn_batch_module!(
+    [const I: usize, const O: usize, const K: usize, const S: usize, const P: usize,
+     const HEIGHT: usize, const WIDTH: usize,],
+    Conv2D<I, O, K, S, P>,
+    Tensor3D, [I, HEIGHT, WIDTH]
+);
+#[cfg(feature = "nightly")]
+dyn_batch_module!(
+    [const M: usize, const K: usize, const V: usize, const HEADS: usize, const S: usize,],
+    MultiHeadAttention<M, M, K, V, HEADS>,
+    Tensor2D, [S, M]
+);
+
+/// Runs `module` on any number of `samples`, `B` samples at a time, and returns the output for
+/// each sample. The last batch is filled up with copies of the last sample, and their outputs
+/// are discarded.
+///
+/// Tensors have a batch size that is fixed at compile time, so this is how a model can be used
+/// when the number of samples is only known at runtime, e.g. for the last samples of a dataset
+/// or the requests of a server. Samples have to be independent of the other samples in their
+/// batch, which is the case for all modules of [crate::nn].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let model: Linear<3, 2> = Default::default();
+/// let samples: Vec<Tensor1D<3>> = vec![Tensor1D::zeros(); 5];
+/// let outputs: Vec<Tensor1D<2>> = forward_dynamic::<4, _, _>(&model, samples);
+/// assert_eq!(outputs.len(), 5);
+/// ```
+pub fn forward_dynamic<const B: usize, M, S>(
+    module: &M,
+    samples: Vec<S>,
+) -> Vec<<M::Output as Uncollate<B>>::Item>
+where
+    S: Collate<B> + Clone,
+    M: Module<S::Batched>,
+    M::Output: Uncollate<B>,
+{
+    let num_samples = samples.len();
+    let mut outputs = Vec::with_capacity(num_samples);
+    let mut samples = samples.into_iter().peekable();
+    while samples.peek().is_some() {
+        let mut batch: Vec<S> = samples.by_ref().take(B).collect();
+        let last = batch.last().unwrap().clone();
+        batch.resize(B, last);
+        outputs.extend(module.forward(S::collate(batch)).uncollate());
+    }
+    outputs.truncate(num_samples);
+    outputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::StdRng, SeedableRng};
+
+    #[test]
+    fn test_forward_dynamic() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: (Linear<3, 4>, ReLU, Linear<4, 2>) = Default::default();
+        model.reset_params(&mut rng);
+
+        for num_samples in [0, 1, 3, 4, 7] {
+            let samples: Vec<Tensor1D<3>> = (0..num_samples)
+                .map(|_| TensorCreator::randn(&mut rng))
+                .collect();
+            let outputs = forward_dynamic::<3, _, _>(&model, samples.clone());
+            assert_eq!(outputs.len(), num_samples);
+            for (x, y) in samples.into_iter().zip(outputs.iter()) {
+                assert_close(y.data(), model.forward(x).data());
+            }
+        }
+    }
+
+    #[test]
+    fn test_dyn_batch_matches_const_batch() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: (Linear<3, 4>, ReLU, Linear<4, 2>) = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor2D<3, 3> = TensorCreator::randn(&mut rng);
+        let y: Tensor2D<3, 2> = TensorCreator::randn(&mut rng);
+
+        let loss = mse_loss(model.forward(x.trace()), &y);
+        let loss_value = *loss.data();
+        let gradients = loss.backward();
+
+        let dyn_x = DynBatch::new(Uncollate::<3>::uncollate(x.clone()));
+        let targets = Uncollate::<3>::uncollate(y);
+        let dyn_loss = model
+            .forward(dyn_x.trace())
+            .zip_map(&targets, mse_loss)
+            .mean();
+        assert!((dyn_loss.data() - loss_value).abs() < 1e-6);
+        let dyn_gradients = dyn_loss.backward();
+
+        assert_close(
+            dyn_gradients.ref_gradient(&model.0.weight),
+            gradients.ref_gradient(&model.0.weight),
+        );
+        assert_close(
+            dyn_gradients.ref_gradient(&model.2.bias),
+            gradients.ref_gradient(&model.2.bias),
+        );
+        for (i, sample) in dyn_x.samples().iter().enumerate() {
+            assert_close(
+                dyn_gradients.ref_gradient(sample),
+                &gradients.ref_gradient(&x)[i],
+            );
+        }
+    }
+
+    #[test]
+    fn test_dyn_batch_sum_and_mean() {
+        let x = DynBatch::new(vec![
+            Tensor1D::new([1.0, 2.0]),
+            Tensor1D::new([3.0, 4.0]),
+            Tensor1D::new([5.0, 6.0]),
+        ]);
+        assert_eq!(x.clone().sum().data(), &[9.0, 12.0]);
+
+        let r = x.trace().map(square).mean();
+        assert_close(r.data(), &[35.0 / 3.0, 56.0 / 3.0]);
+        let gradients = r.sum().backward();
+        assert_close(gradients.ref_gradient(&x.samples()[2]), &[10.0 / 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_dyn_batch_nested_modules() {
+        let mut rng = StdRng::seed_from_u64(1);
+        type Model = (
+            Repeated<Residual<(Linear<2, 2>, Tanh)>, 2>,
+            GeneralizedResidual<Linear<2, 3>, Linear<2, 3>>,
+            LayerNorm1D<3>,
+            Softmax,
+        );
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+
+        let samples: Vec<Tensor1D<2>> = (0..5).map(|_| TensorCreator::randn(&mut rng)).collect();
+        let y = model.forward(DynBatch::new(samples.clone()));
+        assert_eq!(y.len(), 5);
+        for (x, y) in samples.into_iter().zip(y.samples()) {
+            assert_close(y.data(), model.forward(x).data());
+        }
+    }
+
+    #[test]
+    fn test_train_partial_batch() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut model: (Linear<2, 4>, ReLU, Linear<4, 1>) = Default::default();
+        model.reset_params(&mut rng);
+        let mut opt: Sgd<_> = Sgd::new(SgdConfig {
+            lr: 0.1,
+            ..Default::default()
+        });
+
+        let x = DynBatch::new(vec![Tensor1D::new([1.0, -1.0]), Tensor1D::new([0.5, 2.0])]);
+        let targets = vec![Tensor1D::new([1.0]), Tensor1D::new([-1.0])];
+        let mut losses = Vec::new();
+        for _ in 0..10 {
+            let loss = model
+                .forward_mut(x.trace())
+                .zip_map(&targets, mse_loss)
+                .mean();
+            losses.push(*loss.data());
+            opt.update(&mut model, loss.backward())
+                .expect("unused params");
+        }
+        assert!(losses[9] < losses[0]);
+    }
+
+    #[test]
+    fn test_uncollate() {
+        let x: Tensor3D<2, 1, 2> = Tensor3D::new([[[1.0, 2.0]], [[3.0, 4.0]]]);
+        let items = Uncollate::<2>::uncollate(x);
+        assert_eq!(items[0].data(), &[[1.0, 2.0]]);
+        assert_eq!(items[1].data(), &[[3.0, 4.0]]);
+        assert_eq!(Uncollate::<2>::uncollate([5, 6]), [5, 6]);
+    }
+}
@@ -9,6 +9,7 @@ use crate::prelude::*;
 /// let _: Tensor2D<8, {3 * 5 * 7}> = FlattenImage.forward(Tensor4D::<8, 3, 5, 7>::zeros());
 /// ```
 #[derive(Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FlattenImage;
 
 impl ResetParams for FlattenImage {
@@ -21,9 +22,50 @@ impl CanUpdateWithGradients for FlattenImage {
     fn update<G: GradientProvider>(&mut self, _: &mut G, _: &mut UnusedTensors) {}
 }
 
+impl VisitParams for FlattenImage {
+    /// Does nothing.
+    fn visit_params<V: ParamVisitor>(&self, _: &str, _: &mut V) {}
+
+    /// Does nothing.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, _: &str, _: &mut V) {}
+}
+
+#[cfg(feature = "std")]
 impl SaveToNpz for FlattenImage {}
+#[cfg(feature = "std")]
 impl LoadFromNpz for FlattenImage {}
 
+#[cfg(feature = "std")]
+impl<T> Summarize<T> for FlattenImage
+where
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
+
+#[cfg(feature = "std")]
+impl ExportToOnnx for FlattenImage {
+    /// Adds a `Reshape` node for 3d inputs, and a `Flatten` node that keeps the batch dimension for 4d inputs.
+    fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let numel: usize = x.shape.iter().product();
+        if x.shape.len() == 3 {
+            let shape = graph.add_i64_initializer(format!("{pre}shape"), &[-1]);
+            let y = graph.add_node("Reshape", vec![x.name, shape], vec![]);
+            OnnxValueInfo {
+                name: y,
+                shape: vec![numel],
+            }
+        } else {
+            let batch = x.shape[0];
+            let y = graph.add_node("Flatten", vec![x.name], vec![OnnxAttribute::int("axis", 1)]);
+            OnnxValueInfo {
+                name: y,
+                shape: vec![batch, numel / batch],
+            }
+        }
+    }
+}
+
 impl<const M: usize, const N: usize, const O: usize, H: Tape> Module<Tensor3D<M, N, O, H>>
     for FlattenImage
 where
@@ -55,4 +97,15 @@ mod tests {
         let _: Tensor1D<{ 15 * 10 * 5 }> = FlattenImage.forward(Tensor3D::<15, 10, 5>::zeros());
         let _: Tensor2D<5, 24> = FlattenImage.forward(Tensor4D::<5, 4, 3, 2>::zeros());
     }
+
+    #[test]
+    fn test_export_onnx() {
+        let onnx = FlattenImage.to_onnx::<Tensor3D<4, 3, 2>>();
+        assert_eq!(onnx.graph.nodes[0].op_type, "Reshape");
+        assert_eq!(&onnx.graph.outputs[0].shape, &[24]);
+
+        let onnx = FlattenImage.to_onnx::<Tensor4D<5, 4, 3, 2>>();
+        assert_eq!(onnx.graph.nodes[0].op_type, "Flatten");
+        assert_eq!(&onnx.graph.outputs[0].shape, &[5, 24]);
+    }
 }
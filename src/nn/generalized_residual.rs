@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use alloc::format;
 
 /// A residual connection `R` around `F`: `F(x) + R(x)`,
 /// as introduced in [Deep Residual Learning for Image Recognition](https://arxiv.org/abs/1512.03385).
@@ -16,6 +17,7 @@ use crate::prelude::*;
 /// assert_eq!(y.data(), &[4.0, 1.0, 0.0, 2.0, 6.0]);
 /// ```
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GeneralizedResidual<F, R>(F, R);
 
 impl<F: CanUpdateWithGradients, R: CanUpdateWithGradients> CanUpdateWithGradients
@@ -28,6 +30,21 @@ impl<F: CanUpdateWithGradients, R: CanUpdateWithGradients> CanUpdateWithGradient
     }
 }
 
+impl<F: VisitParams, R: VisitParams> VisitParams for GeneralizedResidual<F, R> {
+    /// Visits `F` with the prefix `{pre}_main` and `R` with the prefix `{pre}_residual`.
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        self.0.visit_params(&format!("{}_main", pre), visitor);
+        self.1.visit_params(&format!("{}_residual", pre), visitor);
+    }
+
+    /// Visits `F` with the prefix `{pre}_main` and `R` with the prefix `{pre}_residual`.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        self.0.visit_params_mut(&format!("{}_main", pre), visitor);
+        self.1
+            .visit_params_mut(&format!("{}_residual", pre), visitor);
+    }
+}
+
 impl<F: ResetParams, R: ResetParams> ResetParams for GeneralizedResidual<F, R> {
     /// Pass through to `F`'s [ResetParams].
     fn reset_params<RNG: rand::Rng>(&mut self, rng: &mut RNG) {
@@ -59,6 +76,48 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+impl<F, R, T, O> Summarize<T> for GeneralizedResidual<F, R>
+where
+    T: Tensor<Dtype = f32>,
+    O: Tensor<Dtype = T::Dtype, Tape = T::Tape>,
+    F: Summarize<T, Output = O>,
+    R: Summarize<T, Output = O>,
+{
+    /// Adds a row for the residual, followed by the rows of `F` and `R` with the prefixes
+    /// `{pre}_main` and `{pre}_residual` like in [SaveToNpz].
+    fn summarize(&self, pre: &str, depth: usize, layers: &mut Vec<LayerSummary>) {
+        layers.push(LayerSummary::new::<T, _>(
+            self,
+            pre,
+            depth,
+            "GeneralizedResidual",
+        ));
+        self.0
+            .summarize(&format!("{}_main", pre), depth + 1, layers);
+        self.1
+            .summarize(&format!("{}_residual", pre), depth + 1, layers);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F: ExportToOnnx, R: ExportToOnnx> ExportToOnnx for GeneralizedResidual<F, R> {
+    /// Exports `F` and `R` and then adds an `Add` node for `F(x) + R(x)`. The parameters
+    /// are named the same as in [SaveToNpz].
+    fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let f_x = self
+            .0
+            .export_onnx(&format!("{}_main", pre), graph, x.clone());
+        let r_x = self.1.export_onnx(&format!("{}_residual", pre), graph, x);
+        let y = graph.add_node("Add", vec![f_x.name, r_x.name], vec![]);
+        OnnxValueInfo {
+            name: y,
+            shape: f_x.shape,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 impl<F: SaveToNpz, R: SaveToNpz> SaveToNpz for GeneralizedResidual<F, R> {
     /// Pass through to `F`/`R`'s [SaveToNpz].
     fn write<W>(
@@ -75,6 +134,7 @@ impl<F: SaveToNpz, R: SaveToNpz> SaveToNpz for GeneralizedResidual<F, R> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<F: LoadFromNpz, R: LoadFromNpz> LoadFromNpz for GeneralizedResidual<F, R> {
     /// Pass through to `F`/`R`'s [LoadFromNpz].
     fn read<READ>(
@@ -261,6 +321,7 @@ mod tests {
         let sgd_config = SgdConfig {
             lr: 1.0,
             momentum: None,
+            grad_clip: None,
         };
         Sgd::new(sgd_config)
             .update(&mut model, gradients)
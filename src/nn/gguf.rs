@@ -0,0 +1,557 @@
+use super::{HasStateDict, StateTensor};
+use crate::numpy::f32_to_f16;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// The alignment of the tensor data in a `.gguf` file, which is the default of the format.
+const ALIGNMENT: usize = 32;
+
+/// The number of values in a block of the quantized [GgmlType]s.
+const BLOCK_SIZE: usize = 32;
+
+/// Something that can be saved to a [.gguf](https://github.com/ggerganov/ggml/blob/master/docs/gguf.md)
+/// file, which llama.cpp-compatible runtimes use for inference.
+///
+/// This is implemented for everything that implements [HasStateDict], and the tensors are named
+/// like the entries of [HasStateDict::state_dict()], e.g. `0.weight`. Runtimes expect the tensors and
+/// metadata of an architecture to have specific names, so use [SaveToGguf::save_gguf_with()] to
+/// rename them, and [GgufOptions::metadata] for the hyperparameters.
+///
+/// Only 2d tensors are stored as [GgufOptions::quantization], and the block quantized types are
+/// only used if the rows are a multiple of 32 long. All other tensors (like biases & norms) are
+/// stored as `F32`.
+pub trait SaveToGguf: HasStateDict {
+    /// Save this object into the `.gguf` file at `path`.
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let model: (Linear<64, 64>, ReLU, Linear<64, 8>) = Default::default();
+    /// let mut options = GgufOptions::new("mlp");
+    /// options.quantization = GgmlType::Q8_0;
+    /// model.save_gguf("model.gguf", &options)?;
+    /// ```
+    fn save_gguf<P: AsRef<Path>>(&self, path: P, options: &GgufOptions) -> Result<(), GgufError> {
+        self.save_gguf_with(path, options, |key| key.to_string())
+    }
+
+    /// Save this object into the `.gguf` file at `path`, renaming every tensor with `rename`.
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let model: TransformerDecoder<64, 64, 128, 2, 4> = Default::default();
+    /// let mut options = GgufOptions::new("llama");
+    /// options.metadata.insert("llama.block_count".into(), GgufValue::U32(2));
+    /// model.save_gguf_with("model.gguf", &options, |key| {
+    ///     format!("blk.{key}").replace("attn.w_q", "attn_q").replace("attn.w_k", "attn_k")
+    /// })?;
+    /// ```
+    fn save_gguf_with<P, F>(
+        &self,
+        path: P,
+        options: &GgufOptions,
+        rename: F,
+    ) -> Result<(), GgufError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&str) -> String,
+    {
+        let mut f = BufWriter::new(File::create(path)?);
+        self.write_gguf(&mut f, options, rename)?;
+        f.flush()?;
+        Ok(())
+    }
+
+    /// Writes this object in the `.gguf` format to `w`. See [SaveToGguf::save_gguf_with()].
+    fn write_gguf<W, F>(
+        &self,
+        w: &mut W,
+        options: &GgufOptions,
+        mut rename: F,
+    ) -> Result<(), GgufError>
+    where
+        W: Write,
+        F: FnMut(&str) -> String,
+    {
+        let mut tensors = BTreeMap::new();
+        for (name, tensor) in self.state_dict() {
+            let name = rename(&name);
+            if tensors.contains_key(&name) {
+                return Err(GgufError::DuplicateName(name));
+            }
+            tensors.insert(name, tensor);
+        }
+
+        let mut header = Vec::new();
+        header.extend(b"GGUF");
+        header.extend(3u32.to_le_bytes());
+        header.extend((tensors.len() as u64).to_le_bytes());
+        header.extend((options.metadata.len() as u64).to_le_bytes());
+        for (key, value) in options.metadata.iter() {
+            write_string(&mut header, key);
+            header.extend(value.type_id().to_le_bytes());
+            value.write(&mut header, key)?;
+        }
+
+        let mut data = Vec::new();
+        for (name, tensor) in tensors.iter() {
+            let ty = options.quantization.for_tensor(tensor);
+            write_string(&mut header, name);
+            header.extend((tensor.shape.len() as u32).to_le_bytes());
+            // NOTE: ggml lists the dimensions from the innermost to the outermost
+            for &dim in tensor.shape.iter().rev() {
+                header.extend((dim as u64).to_le_bytes());
+            }
+            header.extend(ty.id().to_le_bytes());
+            header.extend((data.len() as u64).to_le_bytes());
+            ty.write(&mut data, &tensor.data);
+            data.resize(data.len().next_multiple_of(ALIGNMENT), 0);
+        }
+        header.resize(header.len().next_multiple_of(ALIGNMENT), 0);
+
+        w.write_all(&header)?;
+        w.write_all(&data)?;
+        Ok(())
+    }
+}
+
+impl<T: HasStateDict> SaveToGguf for T {}
+
+/// Options for [SaveToGguf].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GgufOptions {
+    /// The key value pairs of the file, e.g. `general.architecture`.
+    pub metadata: BTreeMap<String, GgufValue>,
+
+    /// The type of the 2d tensors, see [SaveToGguf].
+    pub quantization: GgmlType,
+}
+
+impl GgufOptions {
+    /// Options with `general.architecture` set to `architecture` (which all `.gguf` files need),
+    /// and no quantization.
+    pub fn new(architecture: &str) -> Self {
+        let mut metadata = BTreeMap::new();
+        metadata.insert(
+            "general.architecture".to_string(),
+            GgufValue::String(architecture.to_string()),
+        );
+        Self {
+            metadata,
+            quantization: GgmlType::F32,
+        }
+    }
+}
+
+/// The types that tensors can be stored as in a `.gguf` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum GgmlType {
+    F32,
+    F16,
+
+    /// Blocks of 32 values stored as 4 bit integers, with an `f16` scale per block.
+    Q4_0,
+
+    /// Blocks of 32 values stored as 8 bit integers, with an `f16` scale per block.
+    Q8_0,
+}
+
+impl GgmlType {
+    /// The id of the type in ggml.
+    fn id(&self) -> u32 {
+        match self {
+            GgmlType::F32 => 0,
+            GgmlType::F16 => 1,
+            GgmlType::Q4_0 => 2,
+            GgmlType::Q8_0 => 8,
+        }
+    }
+
+    /// The type that `tensor` is stored as when `self` is the requested quantization.
+    fn for_tensor(&self, tensor: &StateTensor) -> Self {
+        match (self, tensor.shape.as_slice()) {
+            (_, &[_, cols]) if cols % BLOCK_SIZE == 0 => *self,
+            (GgmlType::F16, &[_, _]) => *self,
+            _ => GgmlType::F32,
+        }
+    }
+
+    /// Writes `values` as this type to `w`.
+    fn write(&self, w: &mut Vec<u8>, values: &[f32]) {
+        match self {
+            GgmlType::F32 => values.iter().for_each(|v| w.extend(v.to_le_bytes())),
+            GgmlType::F16 => values
+                .iter()
+                .for_each(|&v| w.extend(f32_to_f16(v).to_le_bytes())),
+            GgmlType::Q4_0 => {
+                for block in values.chunks_exact(BLOCK_SIZE) {
+                    // NOTE: the value with the largest magnitude becomes -8, so its sign is kept
+                    let max = block
+                        .iter()
+                        .fold(0.0f32, |m, &v| if v.abs() > m.abs() { v } else { m });
+                    let d = max / -8.0;
+                    let id = if d != 0.0 { 1.0 / d } else { 0.0 };
+                    w.extend(f32_to_f16(d).to_le_bytes());
+                    let q = |v: f32| ((v * id + 8.5) as u8).min(15);
+                    for j in 0..BLOCK_SIZE / 2 {
+                        w.push(q(block[j]) | (q(block[j + BLOCK_SIZE / 2]) << 4));
+                    }
+                }
+            }
+            GgmlType::Q8_0 => {
+                for block in values.chunks_exact(BLOCK_SIZE) {
+                    let amax = block.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+                    let d = amax / 127.0;
+                    let id = if d != 0.0 { 1.0 / d } else { 0.0 };
+                    w.extend(f32_to_f16(d).to_le_bytes());
+                    w.extend(block.iter().map(|v| (v * id).round() as i8 as u8));
+                }
+            }
+        }
+    }
+}
+
+/// A metadata value of a `.gguf` file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GgufValue {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    Bool(bool),
+    String(String),
+
+    /// An array of values, which must all have the same type.
+    Array(Vec<GgufValue>),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+}
+
+impl GgufValue {
+    /// The id of the type of the value in the `.gguf` format.
+    fn type_id(&self) -> u32 {
+        match self {
+            GgufValue::U8(_) => 0,
+            GgufValue::I8(_) => 1,
+            GgufValue::U16(_) => 2,
+            GgufValue::I16(_) => 3,
+            GgufValue::U32(_) => 4,
+            GgufValue::I32(_) => 5,
+            GgufValue::F32(_) => 6,
+            GgufValue::Bool(_) => 7,
+            GgufValue::String(_) => 8,
+            GgufValue::Array(_) => 9,
+            GgufValue::U64(_) => 10,
+            GgufValue::I64(_) => 11,
+            GgufValue::F64(_) => 12,
+        }
+    }
+
+    /// Writes the value (without its type) to `w`. `key` is only used for errors.
+    fn write(&self, w: &mut Vec<u8>, key: &str) -> Result<(), GgufError> {
+        match self {
+            GgufValue::U8(v) => w.extend(v.to_le_bytes()),
+            GgufValue::I8(v) => w.extend(v.to_le_bytes()),
+            GgufValue::U16(v) => w.extend(v.to_le_bytes()),
+            GgufValue::I16(v) => w.extend(v.to_le_bytes()),
+            GgufValue::U32(v) => w.extend(v.to_le_bytes()),
+            GgufValue::I32(v) => w.extend(v.to_le_bytes()),
+            GgufValue::F32(v) => w.extend(v.to_le_bytes()),
+            GgufValue::Bool(v) => w.push(*v as u8),
+            GgufValue::String(v) => write_string(w, v),
+            GgufValue::Array(values) => {
+                // NOTE: the type of an empty array doesn't matter
+                let type_id = values.first().map_or(0, GgufValue::type_id);
+                if values.iter().any(|v| v.type_id() != type_id) {
+                    return Err(GgufError::MixedArray(key.to_string()));
+                }
+                w.extend(type_id.to_le_bytes());
+                w.extend((values.len() as u64).to_le_bytes());
+                for v in values.iter() {
+                    v.write(w, key)?;
+                }
+            }
+            GgufValue::U64(v) => w.extend(v.to_le_bytes()),
+            GgufValue::I64(v) => w.extend(v.to_le_bytes()),
+            GgufValue::F64(v) => w.extend(v.to_le_bytes()),
+        }
+        Ok(())
+    }
+}
+
+macro_rules! value_from {
+    ($($ty:ty => $variant:ident),*) => {
+        $(
+impl From<$ty> for GgufValue {
+    fn from(v: $ty) -> Self {
+        GgufValue::$variant(v.into())
+    }
+}
+        )*
+    };
+}
+
+value_from!(
+    u8 => U8, i8 => I8, u16 => U16, i16 => I16, u32 => U32, i32 => I32, f32 => F32,
+    bool => Bool, String => String, &str => String, u64 => U64, i64 => I64, f64 => F64
+);
+
+fn write_string(w: &mut Vec<u8>, s: &str) {
+    w.extend((s.len() as u64).to_le_bytes());
+    w.extend(s.as_bytes());
+}
+
+/// Error that can happen while saving a `.gguf` file.
+#[derive(Debug)]
+pub enum GgufError {
+    /// Something went wrong with writing the file.
+    Io(std::io::Error),
+
+    /// Two tensors have the same name after renaming.
+    DuplicateName(String),
+
+    /// The values of the [GgufValue::Array] at this key don't all have the same type.
+    MixedArray(String),
+}
+
+impl std::fmt::Display for GgufError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GgufError::Io(err) => write!(fmt, "{}", err),
+            GgufError::DuplicateName(name) => write!(fmt, "duplicate tensor name {}", name),
+            GgufError::MixedArray(key) => {
+                write!(fmt, "array {} has values of different types", key)
+            }
+        }
+    }
+}
+
+impl Error for GgufError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GgufError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for GgufError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numpy::f16_to_f32;
+    use crate::prelude::*;
+    use rand::thread_rng;
+
+    /// A minimal reader for the files written by [SaveToGguf], which returns the metadata as
+    /// raw bytes, and the tensors as `(name, dims, type, data)`.
+    struct Gguf {
+        metadata: Vec<(String, u32, Vec<u8>)>,
+        tensors: Vec<(String, Vec<u64>, u32, Vec<u8>)>,
+    }
+
+    fn parse(bytes: &[u8]) -> Gguf {
+        let u64_at = |pos: &mut usize| {
+            *pos += 8;
+            u64::from_le_bytes(bytes[*pos - 8..*pos].try_into().unwrap())
+        };
+        let u32_at = |pos: &mut usize| {
+            *pos += 4;
+            u32::from_le_bytes(bytes[*pos - 4..*pos].try_into().unwrap())
+        };
+        let string_at = |pos: &mut usize| {
+            let len = u64_at(pos) as usize;
+            *pos += len;
+            String::from_utf8(bytes[*pos - len..*pos].to_vec()).unwrap()
+        };
+
+        assert_eq!(&bytes[..4], b"GGUF");
+        let mut pos = 4;
+        assert_eq!(u32_at(&mut pos), 3);
+        let num_tensors = u64_at(&mut pos);
+        let num_kvs = u64_at(&mut pos);
+
+        let mut metadata = Vec::new();
+        for _ in 0..num_kvs {
+            let key = string_at(&mut pos);
+            let ty = u32_at(&mut pos);
+            let start = pos;
+            match ty {
+                4 => pos += 4,
+                8 => {
+                    string_at(&mut pos);
+                }
+                9 => {
+                    assert_eq!(u32_at(&mut pos), 8);
+                    for _ in 0..u64_at(&mut pos) {
+                        string_at(&mut pos);
+                    }
+                }
+                _ => unimplemented!(),
+            }
+            metadata.push((key, ty, bytes[start..pos].to_vec()));
+        }
+
+        let mut infos = Vec::new();
+        for _ in 0..num_tensors {
+            let name = string_at(&mut pos);
+            let dims: Vec<u64> = (0..u32_at(&mut pos)).map(|_| u64_at(&mut pos)).collect();
+            let ty = u32_at(&mut pos);
+            let offset = u64_at(&mut pos) as usize;
+            assert_eq!(offset % ALIGNMENT, 0);
+            infos.push((name, dims, ty, offset));
+        }
+        let start = pos.next_multiple_of(ALIGNMENT);
+        assert!(bytes[pos..start].iter().all(|&b| b == 0));
+
+        let tensors = infos
+            .iter()
+            .enumerate()
+            .map(|(i, (name, dims, ty, offset))| {
+                let end = infos.get(i + 1).map_or(bytes.len() - start, |t| t.3);
+                let data = bytes[start + offset..start + end].to_vec();
+                (name.clone(), dims.clone(), *ty, data)
+            })
+            .collect();
+        Gguf { metadata, tensors }
+    }
+
+    fn dequantize(ty: u32, data: &[u8], numel: usize) -> Vec<f32> {
+        let f16 = |b: &[u8]| f16_to_f32(u16::from_le_bytes([b[0], b[1]]));
+        let mut values = Vec::new();
+        match ty {
+            0 => values.extend(
+                data.chunks_exact(4)
+                    .map(|b| f32::from_le_bytes(b.try_into().unwrap())),
+            ),
+            2 => {
+                for block in data.chunks_exact(18) {
+                    let d = f16(block);
+                    let qs = &block[2..];
+                    values.extend(qs.iter().map(|q| ((q & 0xf) as f32 - 8.0) * d));
+                    values.extend(qs.iter().map(|q| ((q >> 4) as f32 - 8.0) * d));
+                }
+            }
+            8 => {
+                for block in data.chunks_exact(34) {
+                    let d = f16(block);
+                    values.extend(block[2..].iter().map(|&q| q as i8 as f32 * d));
+                }
+            }
+            _ => unimplemented!(),
+        }
+        values.truncate(numel);
+        values
+    }
+
+    #[test]
+    fn test_gguf_format() {
+        let mut model: (Linear<3, 2>, ReLU) = Default::default();
+        model.0.weight = Tensor2D::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        model.0.bias = Tensor1D::new([-1.0, 0.5]);
+        let mut options = GgufOptions::new("mlp");
+        options.metadata.insert(
+            "tokenizer.ggml.tokens".into(),
+            GgufValue::Array(vec![GgufValue::String("a".into())]),
+        );
+        options
+            .metadata
+            .insert("mlp.block_count".into(), 1u32.into());
+
+        let mut bytes = Vec::new();
+        model
+            .write_gguf(&mut bytes, &options, |key| format!("blk.{key}"))
+            .expect("");
+        let gguf = parse(&bytes);
+
+        let keys: Vec<&str> = gguf.metadata.iter().map(|m| m.0.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "general.architecture",
+                "mlp.block_count",
+                "tokenizer.ggml.tokens"
+            ]
+        );
+        assert_eq!(gguf.metadata[0].2[8..], *b"mlp");
+        assert_eq!(gguf.metadata[1].2, 1u32.to_le_bytes());
+
+        assert_eq!(gguf.tensors.len(), 2);
+        let (name, dims, ty, data) = &gguf.tensors[0];
+        assert_eq!(
+            (name.as_str(), dims.as_slice(), *ty),
+            ("blk.0.bias", &[2][..], 0)
+        );
+        assert_eq!(dequantize(0, data, 2), [-1.0, 0.5]);
+        let (name, dims, ty, data) = &gguf.tensors[1];
+        assert_eq!(
+            (name.as_str(), dims.as_slice(), *ty),
+            ("blk.0.weight", &[3, 2][..], 0)
+        );
+        assert_eq!(dequantize(0, data, 6), [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn test_gguf_quantization() {
+        let mut model: (Linear<64, 2>, Linear<2, 3>) = Default::default();
+        model.reset_params(&mut thread_rng());
+        let weight = model.0.weight.data().concat();
+
+        for (quantization, id, tolerance) in [
+            (GgmlType::Q8_0, 8, 0.2 / 127.0),
+            (GgmlType::Q4_0, 2, 0.2 / 8.0),
+            (GgmlType::F32, 0, 0.0),
+        ] {
+            let mut options = GgufOptions::new("mlp");
+            options.quantization = quantization;
+            let mut bytes = Vec::new();
+            model
+                .write_gguf(&mut bytes, &options, |key| key.to_string())
+                .expect("");
+            let gguf = parse(&bytes);
+
+            // only the 64 wide weight is quantized
+            let types: Vec<u32> = gguf.tensors.iter().map(|t| t.2).collect();
+            assert_eq!(types, [0, id, 0, 0]);
+
+            let (_, dims, ty, data) = &gguf.tensors[1];
+            assert_eq!(dims, &[64, 2]);
+            let values = dequantize(*ty, data, 128);
+            for (a, b) in values.iter().zip(weight.iter()) {
+                assert!((a - b).abs() <= tolerance, "{a} vs {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_gguf_errors() {
+        let model: (Linear<3, 2>, Linear<2, 2>) = Default::default();
+        let options = GgufOptions::new("mlp");
+        let r = model.write_gguf(&mut Vec::new(), &options, |key| key[2..].to_string());
+        assert!(matches!(r, Err(GgufError::DuplicateName(name)) if name == "bias"));
+
+        let mut options = GgufOptions::new("mlp");
+        options.metadata.insert(
+            "mixed".into(),
+            GgufValue::Array(vec![1u32.into(), "a".into()]),
+        );
+        let r = model.write_gguf(&mut Vec::new(), &options, |key| key.to_string());
+        assert!(matches!(r, Err(GgufError::MixedArray(key)) if key == "mixed"));
+    }
+}
@@ -1,6 +1,9 @@
 use crate::prelude::*;
+use alloc::format;
 use rand::prelude::Rng;
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
 use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 macro_rules! tuple_impls {
@@ -11,12 +14,25 @@ macro_rules! tuple_impls {
             }
         }
 
+        impl<$($name: VisitParams),+> VisitParams for ($($name,)+) {
+            /// Visits each part of the tuple with the prefix `{pre}{idx}.`. See [VisitParams].
+            fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+                $(self.$idx.visit_params(&format!("{}{}.", pre, $idx), visitor);)+
+            }
+
+            /// Visits each part of the tuple with the prefix `{pre}{idx}.`. See [VisitParams].
+            fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+                $(self.$idx.visit_params_mut(&format!("{}{}.", pre, $idx), visitor);)+
+            }
+        }
+
         impl<$($name: ResetParams),+> ResetParams for ($($name,)+) {
             fn reset_params<R: Rng>(&mut self, rng: &mut R) {
                 $(self.$idx.reset_params(rng));+
             }
         }
 
+        #[cfg(feature = "std")]
         impl<$($name: SaveToNpz),+> SaveToNpz for ($($name,)+) {
             /// Calls `SaveToNpz::write(self.<idx>, ...)` on each part of the tuple. See [SaveToNpz].
             ///
@@ -29,6 +45,7 @@ macro_rules! tuple_impls {
             }
         }
 
+        #[cfg(feature = "std")]
         impl<$($name: LoadFromNpz),+> LoadFromNpz for ($($name,)+) {
             /// Calls `LoadFromNpz::read(self.<idx>, ...)` on each part of the tuple. See [LoadFromNpz].
             ///
@@ -41,6 +58,30 @@ macro_rules! tuple_impls {
             }
         }
 
+        #[cfg(feature = "std")]
+        impl<$($name: ExportToOnnx),+> ExportToOnnx for ($($name,)+) {
+            /// Exports each part of the tuple in order, with the prefix `{base}{idx}.` like [SaveToNpz].
+            fn export_onnx(&self, base: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+                $(let x = self.$idx.export_onnx(&format!("{}{}.", base, $idx), graph, x);)+
+                x
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl<
+            Input: Tensor,
+            $last:
+            $(Summarize::<$rev_tail ::Output>, $rev_tail: )+
+            Summarize<Input>
+        > Summarize<Input> for ($($name,)+) {
+            /// Adds a row for the whole tuple, followed by the rows of each part of the tuple
+            /// with the prefix `{base}{idx}.` like [SaveToNpz].
+            fn summarize(&self, base: &str, depth: usize, layers: &mut Vec<LayerSummary>) {
+                layers.push(LayerSummary::new::<Input, _>(self, base, depth, "Tuple"));
+                $(self.$idx.summarize(&format!("{}{}.", base, $idx), depth + 1, layers);)+
+            }
+        }
+
         /*This macro expands like this for a 4-tuple:
 
         impl<
@@ -67,7 +108,7 @@ macro_rules! tuple_impls {
         }
         */
         impl<
-            Input: Tensor,
+            Input,
             $last:
             $(Module::<$rev_tail ::Output>, $rev_tail: )+
             Module<Input>
@@ -132,6 +173,7 @@ mod tests {
         let mut sgd = Sgd::new(SgdConfig {
             lr: 1.0,
             momentum: None,
+            grad_clip: None,
         });
         sgd.update(&mut model, gradients).expect("");
 
@@ -228,27 +270,27 @@ mod tests {
     #[test]
     fn test_set_to_1() {
         assert_eq!(
-            SetTo1::<0, 5>::default().forward(Tensor1D::zeros()).data(),
+            SetTo1::<0, 5>.forward(Tensor1D::zeros()).data(),
             &[1.0, 0.0, 0.0, 0.0, 0.0]
         );
 
         assert_eq!(
-            SetTo1::<1, 5>::default().forward(Tensor1D::zeros()).data(),
+            SetTo1::<1, 5>.forward(Tensor1D::zeros()).data(),
             &[0.0, 1.0, 0.0, 0.0, 0.0]
         );
 
         assert_eq!(
-            SetTo1::<2, 5>::default().forward(Tensor1D::zeros()).data(),
+            SetTo1::<2, 5>.forward(Tensor1D::zeros()).data(),
             &[0.0, 0.0, 1.0, 0.0, 0.0]
         );
 
         assert_eq!(
-            SetTo1::<3, 5>::default().forward(Tensor1D::zeros()).data(),
+            SetTo1::<3, 5>.forward(Tensor1D::zeros()).data(),
             &[0.0, 0.0, 0.0, 1.0, 0.0]
         );
 
         assert_eq!(
-            SetTo1::<4, 5>::default().forward(Tensor1D::zeros()).data(),
+            SetTo1::<4, 5>.forward(Tensor1D::zeros()).data(),
             &[0.0, 0.0, 0.0, 0.0, 1.0]
         );
     }
@@ -0,0 +1,300 @@
+use super::safetensors::{read_tensors, Elements};
+use super::SafetensorsError;
+use crate::gradients::VisitParams;
+use rust_hdf5::{H5File, Hdf5Error};
+use std::error::Error;
+use std::path::Path;
+
+/// Something that can be loaded from the weights of a Keras model, saved as a `.h5` file with
+/// `model.save("model.h5")` or `model.save_weights("model.h5")`.
+///
+/// This is implemented for everything that implements [VisitParams]. Keras stores the weights
+/// of every layer in a group named after the layer, so each layer that should be loaded is
+/// mapped to the prefix that [VisitParams] uses for the module, e.g. `("dense_1", "2.")`.
+///
+/// The weights are converted to the layouts of dfdx:
+/// - `kernel` of `Dense` layers is transposed from (in, out) to `weight` with (out, in).
+/// - `kernel` of `Conv2D` layers is permuted from (kh, kw, in, out) to `weight` with (out, in, kh, kw).
+/// - `bias`, and `gamma` & `beta` of normalization layers keep their names.
+/// - `moving_mean` & `moving_variance` of `BatchNormalization` become `running_mean` &
+///   `running_var`, as in PyTorch.
+///
+/// Only the `.h5` format of Keras 2 (and `tf.keras`) is supported, since the `.keras` &
+/// `.weights.h5` formats of Keras 3 don't store the names of the weights.
+pub trait LoadFromKeras: VisitParams {
+    /// Loads the weights of `layers` from the Keras weights file at `path`. Each pair is the name
+    /// of a layer in the Keras model and the prefix of the module it is loaded into.
+    ///
+    /// Layers of the Keras model that aren't in `layers` (e.g. ones without weights) are ignored.
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let mut model: (Linear<5, 10>, ReLU, Linear<10, 5>) = Default::default();
+    /// model.load_keras("model.h5", &[("dense", "0."), ("dense_1", "2.")])?;
+    /// ```
+    fn load_keras<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        layers: &[(&str, &str)],
+    ) -> Result<(), KerasError> {
+        let file = H5File::open(path)?;
+        let names = file.dataset_names();
+
+        // full models store the weights under `model_weights`, `save_weights()` at the root
+        let root = if names.iter().any(|n| n.starts_with("model_weights/")) {
+            "model_weights/"
+        } else {
+            ""
+        };
+
+        let mut tensors = Vec::new();
+        for &(layer, pre) in layers {
+            let group = format!("{}{}/", root, layer);
+            let mut found = false;
+            for name in names.iter().filter(|n| n.starts_with(&group)) {
+                found = true;
+                let ds = file.dataset(name)?;
+                let weight = name.rsplit('/').next().unwrap().trim_end_matches(":0");
+                let (weight, shape, data) = convert(weight, ds.shape(), ds.read_numeric_as()?)
+                    .ok_or_else(|| KerasError::UnsupportedWeight(name.clone()))?;
+                let bytes: Vec<u8> = data.iter().flat_map(|x| x.to_le_bytes()).collect();
+                tensors.push((format!("{}{}", pre, weight), shape, bytes));
+            }
+            if !found {
+                return Err(KerasError::MissingLayer(layer.into()));
+            }
+        }
+
+        let tensors = tensors
+            .iter()
+            .map(|(name, shape, data)| (name.clone(), shape.clone(), Elements::F32(data)))
+            .collect();
+        read_tensors(self, tensors)?;
+        Ok(())
+    }
+}
+
+impl<T: VisitParams> LoadFromKeras for T {}
+
+/// Converts the Keras weight `name` to the name, shape, and data of the matching dfdx parameter.
+fn convert(
+    name: &str,
+    shape: Vec<usize>,
+    data: Vec<f32>,
+) -> Option<(&'static str, Vec<usize>, Vec<f32>)> {
+    match (name, shape.as_slice()) {
+        ("kernel", &[i, o]) => Some(("weight", vec![o, i], permute(&data, &shape, [1, 0]))),
+        ("kernel", &[kh, kw, i, o]) => Some((
+            "weight",
+            vec![o, i, kh, kw],
+            permute(&data, &shape, [3, 2, 0, 1]),
+        )),
+        ("bias", _) => Some(("bias", shape, data)),
+        ("gamma", _) => Some(("gamma", shape, data)),
+        ("beta", _) => Some(("beta", shape, data)),
+        ("moving_mean", _) => Some(("running_mean", shape, data)),
+        ("moving_variance", _) => Some(("running_var", shape, data)),
+        _ => None,
+    }
+}
+
+/// Permutes the axes of the row major `data` with `shape`, so that axis `i` of the result is
+/// axis `axes[i]` of `data`.
+fn permute<const N: usize>(data: &[f32], shape: &[usize], axes: [usize; N]) -> Vec<f32> {
+    let mut strides = [1; N];
+    for i in (0..N - 1).rev() {
+        strides[i] = strides[i + 1] * shape[i + 1];
+    }
+    let new_shape = axes.map(|a| shape[a]);
+
+    let mut out = Vec::with_capacity(data.len());
+    let mut idx = [0; N];
+    for _ in 0..data.len() {
+        out.push(data[(0..N).map(|i| idx[i] * strides[axes[i]]).sum::<usize>()]);
+        for i in (0..N).rev() {
+            idx[i] += 1;
+            if idx[i] < new_shape[i] {
+                break;
+            }
+            idx[i] = 0;
+        }
+    }
+    out
+}
+
+/// Error that can happen while loading the weights of a Keras model.
+#[derive(Debug)]
+pub enum KerasError {
+    /// Something went wrong with reading the `.h5` file.
+    Hdf5(Hdf5Error),
+
+    /// The file doesn't contain any weights for a layer.
+    MissingLayer(String),
+
+    /// The file contains a weight that can't be converted to a dfdx parameter.
+    UnsupportedWeight(String),
+
+    /// The shape of a weight in the file is different from the shape of the parameter.
+    ShapeMismatch { expected: String, found: String },
+
+    /// The layers don't contain a weight for one of the parameters.
+    MissingTensor,
+}
+
+impl std::fmt::Display for KerasError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KerasError::Hdf5(err) => write!(fmt, "{}", err),
+            KerasError::MissingLayer(layer) => write!(fmt, "missing layer {}", layer),
+            KerasError::UnsupportedWeight(name) => write!(fmt, "unsupported weight {}", name),
+            KerasError::ShapeMismatch { expected, found } => {
+                write!(
+                    fmt,
+                    "shape mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            KerasError::MissingTensor => write!(fmt, "missing tensor"),
+        }
+    }
+}
+
+impl Error for KerasError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            KerasError::Hdf5(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Hdf5Error> for KerasError {
+    fn from(e: Hdf5Error) -> Self {
+        Self::Hdf5(e)
+    }
+}
+
+impl From<SafetensorsError> for KerasError {
+    fn from(e: SafetensorsError) -> Self {
+        match e {
+            SafetensorsError::ShapeMismatch { expected, found } => {
+                Self::ShapeMismatch { expected, found }
+            }
+            SafetensorsError::MissingTensor => Self::MissingTensor,
+            // NOTE: the tensors are already in memory as f32, so this can't happen
+            e => Self::Hdf5(Hdf5Error::Io(std::io::Error::other(e.to_string()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use tempfile::NamedTempFile;
+
+    type Weight<'a> = (&'a str, &'a [usize], &'a [f32]);
+
+    /// Writes `weights` to a new group at `path`, like Keras does for a layer.
+    fn write_layer(file: &H5File, path: &str, weights: &[Weight]) {
+        let mut parts = path.split('/');
+        let mut g = file.create_group(parts.next().unwrap()).expect("");
+        for part in parts {
+            g = g.create_group(part).expect("");
+        }
+        for &(name, shape, data) in weights {
+            let ds = g.new_dataset::<f32>().shape(shape).create(name).expect("");
+            ds.write_raw(data).expect("");
+        }
+    }
+
+    #[test]
+    fn test_load_keras_dense() {
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        {
+            let f = H5File::create(file.path()).expect("");
+            let kernel = [1., 2., 3., 4., 5., 6.];
+            write_layer(
+                &f,
+                "dense/dense",
+                &[
+                    ("kernel:0", &[3, 2], &kernel),
+                    ("bias:0", &[2], &[-1., -2.]),
+                ],
+            );
+            write_layer(
+                &f,
+                "dense_1/dense_1",
+                &[("kernel:0", &[2, 1], &[7., 8.]), ("bias:0", &[1], &[9.])],
+            );
+        }
+
+        let mut model: (Linear<3, 2>, ReLU, Linear<2, 1>) = Default::default();
+        model
+            .load_keras(file.path(), &[("dense", "0."), ("dense_1", "2.")])
+            .expect("");
+        assert_eq!(model.0.weight.data(), &[[1., 3., 5.], [2., 4., 6.]]);
+        assert_eq!(model.0.bias.data(), &[-1., -2.]);
+        assert_eq!(model.2.weight.data(), &[[7., 8.]]);
+        assert_eq!(model.2.bias.data(), &[9.]);
+    }
+
+    #[test]
+    fn test_load_keras_model_weights() {
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        {
+            let f = H5File::create(file.path()).expect("");
+            write_layer(
+                &f,
+                "model_weights/ln/ln",
+                &[("gamma:0", &[2], &[1., 2.]), ("beta:0", &[2], &[3., 4.])],
+            );
+        }
+
+        let mut ln: LayerNorm1D<2> = Default::default();
+        ln.load_keras(file.path(), &[("ln", "")]).expect("");
+        assert_eq!(ln.gamma.data(), &[1., 2.]);
+        assert_eq!(ln.beta.data(), &[3., 4.]);
+    }
+
+    #[test]
+    fn test_load_keras_errors() {
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        {
+            let f = H5File::create(file.path()).expect("");
+            write_layer(
+                &f,
+                "dense/dense",
+                &[
+                    ("kernel:0", &[3, 2], &[0.0; 6]),
+                    ("bias:0", &[2], &[0.0; 2]),
+                ],
+            );
+            write_layer(&f, "emb/emb", &[("embeddings:0", &[2], &[0.0; 2])]);
+        }
+
+        let mut model: Linear<3, 2> = Default::default();
+        let r = model.load_keras(file.path(), &[("dense_1", "")]);
+        assert!(matches!(r, Err(KerasError::MissingLayer(l)) if l == "dense_1"));
+
+        let r = model.load_keras(file.path(), &[("emb", "")]);
+        assert!(matches!(r, Err(KerasError::UnsupportedWeight(_))));
+
+        let mut model: Linear<2, 3> = Default::default();
+        let r = model.load_keras(file.path(), &[("dense", "")]);
+        assert!(matches!(r, Err(KerasError::ShapeMismatch { .. })));
+    }
+
+    #[test]
+    fn test_permute_conv_kernel() {
+        let data: Vec<f32> = (0..24).map(|x| x as f32).collect();
+        let (name, shape, out) = convert("kernel", vec![2, 2, 3, 2], data.clone()).unwrap();
+        assert_eq!(name, "weight");
+        assert_eq!(shape, [2, 3, 2, 2]);
+        for (o, i, y, x) in [(0, 0, 0, 0), (1, 2, 0, 1), (0, 1, 1, 0), (1, 0, 1, 1)] {
+            let src = ((y * 2 + x) * 3 + i) * 2 + o;
+            assert_eq!(out[((o * 3 + i) * 2 + y) * 2 + x], data[src]);
+        }
+    }
+}
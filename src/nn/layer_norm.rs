@@ -1,5 +1,8 @@
 use crate::prelude::*;
+use alloc::format;
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
 use zip::{result::ZipResult, ZipArchive};
 
 /// Implements layer normalization as described in [Layer Normalization](https://arxiv.org/abs/1607.06450).
@@ -20,6 +23,7 @@ use zip::{result::ZipResult, ZipArchive};
 /// let _: Tensor1D<5> = model.forward(x);
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerNorm1D<const M: usize> {
     pub gamma: Tensor1D<M, NoneTape>,
     pub beta: Tensor1D<M, NoneTape>,
@@ -53,6 +57,47 @@ impl<const M: usize> CanUpdateWithGradients for LayerNorm1D<M> {
     }
 }
 
+impl<const M: usize> VisitParams for LayerNorm1D<M> {
+    /// Visits [Self::gamma] and [Self::beta].
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        visitor.visit(&format!("{pre}gamma"), &self.gamma);
+        visitor.visit(&format!("{pre}beta"), &self.beta);
+    }
+
+    /// Visits [Self::gamma] and [Self::beta].
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        visitor.visit_mut(&format!("{pre}gamma"), &mut self.gamma);
+        visitor.visit_mut(&format!("{pre}beta"), &mut self.beta);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, T> Summarize<T> for LayerNorm1D<M>
+where
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize> ExportToOnnx for LayerNorm1D<M> {
+    /// Adds a `LayerNormalization` node over the last axis, with [Self::gamma] and [Self::beta]
+    /// as the scale and bias. These are named `{pre}gamma` and `{pre}beta`.
+    fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let gamma = graph.add_initializer(format!("{pre}gamma"), self.gamma.data());
+        let beta = graph.add_initializer(format!("{pre}beta"), self.beta.data());
+        let attributes = vec![
+            OnnxAttribute::int("axis", -1),
+            OnnxAttribute::float("epsilon", self.epsilon),
+        ];
+        let y = graph.add_node("LayerNormalization", vec![x.name, gamma, beta], attributes);
+        OnnxValueInfo {
+            name: y,
+            shape: x.shape,
+        }
+    }
+}
+
 impl<H: Tape, const M: usize> Module<Tensor1D<M, H>> for LayerNorm1D<M> {
     type Output = Tensor1D<M, H>;
 
@@ -101,6 +146,7 @@ impl<H: Tape, const B: usize, const S: usize, const M: usize> Module<Tensor3D<B,
     }
 }
 
+#[cfg(feature = "std")]
 impl<const M: usize> SaveToNpz for LayerNorm1D<M> {
     /// Saves [Self::gamma] to `{pre}gamma.npy` and [Self::beta] to `{pre}beta.npy`
     /// using [npz_fwrite()].
@@ -111,6 +157,7 @@ impl<const M: usize> SaveToNpz for LayerNorm1D<M> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<const M: usize> LoadFromNpz for LayerNorm1D<M> {
     /// Reads [Self::gamma] from `{p}gamma.npy` and [Self::beta] from `{p}beta.npy`
     /// using [npz_fread()].
@@ -1,7 +1,12 @@
 use crate::prelude::*;
+use alloc::format;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 use rand::Rng;
 use rand_distr::Uniform;
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
 use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 /// A linear transformation of the form `weight * x + bias`, where `weight` is a matrix, `x` is a vector or matrix,
@@ -23,6 +28,7 @@ use zip::{result::ZipResult, ZipArchive, ZipWriter};
 /// assert_eq!(y.data(), &[0.0; 2]);
 /// ```
 #[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Linear<const I: usize, const O: usize> {
     /// Transposed weight matrix, shape (O, I)
     pub weight: Tensor2D<O, I, NoneTape>,
@@ -38,6 +44,20 @@ impl<const I: usize, const O: usize> CanUpdateWithGradients for Linear<I, O> {
     }
 }
 
+impl<const I: usize, const O: usize> VisitParams for Linear<I, O> {
+    /// Visits [Self::weight] and [Self::bias].
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        visitor.visit(&format!("{pre}weight"), &self.weight);
+        visitor.visit(&format!("{pre}bias"), &self.bias);
+    }
+
+    /// Visits [Self::weight] and [Self::bias].
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        visitor.visit_mut(&format!("{pre}weight"), &mut self.weight);
+        visitor.visit_mut(&format!("{pre}bias"), &mut self.bias);
+    }
+}
+
 impl<const I: usize, const O: usize> ResetParams for Linear<I, O> {
     /// Initializes [Self::weight] and [Self::bias] from a [Uniform] distribution
     /// between [-1 / sqrt(I), 1 / sqrt(I)].
@@ -51,6 +71,7 @@ impl<const I: usize, const O: usize> ResetParams for Linear<I, O> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<const I: usize, const O: usize> SaveToNpz for Linear<I, O> {
     /// Saves [Self::weight] to `{pre}weight.npy` and [Self::bias] to `{pre}bias.npy`
     /// using [npz_fwrite()].
@@ -64,6 +85,7 @@ impl<const I: usize, const O: usize> SaveToNpz for Linear<I, O> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<const I: usize, const O: usize> LoadFromNpz for Linear<I, O> {
     /// Reads [Self::weight] from `{pre}weight.npy` and [Self::bias] from `{pre}bias.npy`
     /// using [npz_fread()].
@@ -77,6 +99,35 @@ impl<const I: usize, const O: usize> LoadFromNpz for Linear<I, O> {
     }
 }
 
+#[cfg(feature = "std")]
+impl<const I: usize, const O: usize, T> Summarize<T> for Linear<I, O>
+where
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
+
+#[cfg(feature = "std")]
+impl<const I: usize, const O: usize> ExportToOnnx for Linear<I, O> {
+    /// Adds a `MatMul` node with [Self::weight] transposed to shape `(I, O)`, followed by
+    /// an `Add` node with [Self::bias]. These are named `{pre}weight` and `{pre}bias`.
+    fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let mut weight_t: Box<[[f32; O]; I]> = Cpu::zeros();
+        for (i, row) in self.weight.data().iter().enumerate() {
+            for (j, w) in row.iter().enumerate() {
+                weight_t[j][i] = *w;
+            }
+        }
+        let weight = graph.add_initializer(format!("{pre}weight"), weight_t.as_ref());
+        let bias = graph.add_initializer(format!("{pre}bias"), self.bias.data());
+        let y = graph.add_node("MatMul", vec![x.name, weight], vec![]);
+        let y = graph.add_node("Add", vec![y, bias], vec![]);
+        let mut shape = x.shape;
+        *shape.last_mut().unwrap() = O;
+        OnnxValueInfo { name: y, shape }
+    }
+}
+
 impl<const I: usize, const O: usize, H: Tape> Module<Tensor1D<I, H>> for Linear<I, O> {
     type Output = Tensor1D<O, H>;
 
@@ -286,6 +337,64 @@ mod tests {
         assert_eq!(loaded_model.bias.data(), saved_model.bias.data());
     }
 
+    #[test]
+    fn test_save_load_linear_bytes() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut saved_model: Linear<5, 3> = Default::default();
+        saved_model.reset_params(&mut rng);
+        let bytes = saved_model.save_to_bytes().expect("");
+
+        let mut loaded_model: Linear<5, 3> = Default::default();
+        loaded_model.load_from_bytes(&bytes).expect("");
+        assert_eq!(loaded_model.weight.data(), saved_model.weight.data());
+        assert_eq!(loaded_model.bias.data(), saved_model.bias.data());
+    }
+
+    #[test]
+    fn test_save_load_linear_f16() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut saved_model: Linear<50, 30> = Default::default();
+        saved_model.reset_params(&mut rng);
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        let file_f16 = NamedTempFile::new().expect("failed to create tempfile");
+        saved_model.save(file.path()).expect("");
+        saved_model.save_f16(file_f16.path()).expect("");
+        let size = |f: &NamedTempFile| f.as_file().metadata().expect("").len();
+        assert!(size(&file_f16) * 10 < size(&file) * 6);
+
+        let mut loaded_model: Linear<50, 30> = Default::default();
+        loaded_model.load(file_f16.path()).expect("");
+        for (l, s) in loaded_model
+            .weight
+            .data()
+            .iter()
+            .zip(saved_model.weight.data())
+        {
+            for (a, b) in l.iter().zip(s.iter()) {
+                assert!((a - b).abs() <= b.abs() * 1e-3, "{a} vs {b}");
+            }
+        }
+        for (a, b) in loaded_model.bias.data().iter().zip(saved_model.bias.data()) {
+            assert!((a - b).abs() <= b.abs() * 1e-3, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_save_f16_nested_file_names() {
+        let model: (Linear<2, 3>, ReLU, (Linear<3, 1>, Tanh)) = Default::default();
+        let names = |bytes: Vec<u8>| {
+            let zip = ZipArchive::new(std::io::Cursor::new(bytes)).expect("");
+            let mut names: Vec<String> = zip.file_names().map(String::from).collect();
+            names.sort_unstable();
+            names
+        };
+        let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        model.write_f16("", &mut zip).expect("");
+        let f16 = zip.finish().expect("").into_inner();
+        assert_eq!(names(f16), names(model.save_to_bytes().expect("")));
+    }
+
     #[test]
     fn test_linear_missing_gradients() {
         let mut model: Linear<5, 3> = Default::default();
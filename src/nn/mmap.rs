@@ -0,0 +1,308 @@
+use super::safetensors::{parse_file, read_tensors, Elements, TensorInfo};
+use super::{npz_fread, NpzError, SafetensorsError};
+use crate::prelude::*;
+use memmap2::Mmap;
+use std::error::Error;
+use std::{fs::File, io::Cursor, path::Path};
+use zip::{result::ZipError, ZipArchive};
+
+/// A `.npz` or `.safetensors` file that is memory mapped instead of read into memory.
+///
+/// Opening the file only parses the index of the tensors. The data of a tensor is read from the
+/// file the first time it is accessed, either for a single tensor with [MmapWeights::tensor()], or
+/// for all the parameters of a module with [LoadFromMmap::load_mmap()]. Either way the data is
+/// copied straight into the tensors, so even multi-hundred-MB files never have a second copy on
+/// the heap.
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// let weights = unsafe { MmapWeights::open("model.safetensors")? };
+/// let mut model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+/// model.load_mmap(&weights)?;
+/// let bias: Tensor1D<5> = weights.tensor("1.bias")?;
+/// ```
+pub struct MmapWeights {
+    mmap: Mmap,
+    format: Format,
+    names: Vec<String>,
+}
+
+enum Format {
+    Npz,
+
+    /// The tensors of the file, and the offset of the data section.
+    Safetensors(Vec<TensorInfo>, usize),
+}
+
+impl MmapWeights {
+    /// Memory maps the `.npz` or `.safetensors` file at `path`. The format is detected from the
+    /// contents of the file.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified (by this or any other process) while the [MmapWeights]
+    /// exists, see [memmap2::Mmap::map()].
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self, MmapError> {
+        let mmap = Mmap::map(&File::open(path)?)?;
+
+        if mmap.starts_with(b"PK\x03\x04") {
+            let zip = ZipArchive::new(Cursor::new(&mmap[..]))?;
+            let names = zip
+                .file_names()
+                .map(|f| f.trim_end_matches(".npy").to_string())
+                .collect();
+            return Ok(Self {
+                mmap,
+                format: Format::Npz,
+                names,
+            });
+        }
+
+        let (infos, start) = parse_file(&mmap)?;
+        let names = infos.iter().map(|info| info.name.clone()).collect();
+        Ok(Self {
+            mmap,
+            format: Format::Safetensors(infos, start),
+            names,
+        })
+    }
+
+    /// The names of all the tensors in the file, e.g. `0.weight`.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Reads the tensor called `name` (without the `.npy`) from the file. Only this tensor's data
+    /// is read.
+    pub fn tensor<T: TensorCreator + VisitParams>(&self, name: &str) -> Result<T, MmapError> {
+        match &self.format {
+            Format::Npz => {
+                let mut zip = ZipArchive::new(Cursor::new(&self.mmap[..]))?;
+                let mut data: Box<T::Array> = Cpu::zeros();
+                npz_fread(&mut zip, format!("{name}.npy"), data.as_mut())?;
+                Ok(T::new_boxed(data))
+            }
+            Format::Safetensors(infos, start) => {
+                let info = infos
+                    .iter()
+                    .find(|info| info.name == name)
+                    .ok_or(SafetensorsError::MissingTensor)?;
+                // NOTE: a tensor's only parameter is itself, which is named ""
+                let tensors = vec![(
+                    String::new(),
+                    info.shape.clone(),
+                    self.elements(info, *start),
+                )];
+                let mut t = T::zeros();
+                read_tensors(&mut t, tensors)?;
+                Ok(t)
+            }
+        }
+    }
+
+    /// The data of the `.safetensors` tensor `info`, whose data section starts at `start`.
+    fn elements(&self, info: &TensorInfo, start: usize) -> Elements<'_> {
+        let [begin, end] = info.data_offsets;
+        let data = &self.mmap[start + begin..start + end];
+        // NOTE: the dtypes were checked in `open()`
+        match info.dtype.as_str() {
+            "F32" => Elements::F32(data),
+            _ => Elements::F16(data),
+        }
+    }
+}
+
+/// Something that can be loaded from [MmapWeights].
+///
+/// This is implemented for everything that implements [VisitParams], and the tensors are named
+/// the same way as with [super::LoadFromNpz::load()] and [super::LoadFromSafetensors::load_safetensors()].
+pub trait LoadFromMmap: VisitParams {
+    /// Loads all the parameters from `weights`, copying each one directly from the file into
+    /// its tensor.
+    fn load_mmap(&mut self, weights: &MmapWeights) -> Result<(), MmapError> {
+        match &weights.format {
+            Format::Npz => {
+                let zip = ZipArchive::new(Cursor::new(&weights.mmap[..]))?;
+                let mut visitor = ReadNpz(zip, Ok(()));
+                self.visit_params_mut("", &mut visitor);
+                Ok(visitor.1?)
+            }
+            Format::Safetensors(infos, start) => {
+                let tensors = infos
+                    .iter()
+                    .map(|info| {
+                        let data = weights.elements(info, *start);
+                        (info.name.clone(), info.shape.clone(), data)
+                    })
+                    .collect();
+                Ok(read_tensors(self, tensors)?)
+            }
+        }
+    }
+}
+
+impl<T: VisitParams> LoadFromMmap for T {}
+
+/// Reads every parameter from the `.npy` file with its name, and stops at the first error.
+struct ReadNpz<'a>(ZipArchive<Cursor<&'a [u8]>>, Result<(), NpzError>);
+
+impl ParamVisitorMut for ReadNpz<'_> {
+    fn visit_mut<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &mut P) {
+        if self.1.is_ok() {
+            self.1 = npz_fread(&mut self.0, format!("{name}.npy"), p.mut_data());
+        }
+    }
+}
+
+/// Error that can happen while loading from [MmapWeights].
+#[derive(Debug)]
+pub enum MmapError {
+    /// Something went wrong with opening or mapping the file.
+    Io(std::io::Error),
+
+    /// Something went wrong while loading from a `.npz` file.
+    Npz(NpzError),
+
+    /// Something went wrong while loading from a `.safetensors` file.
+    Safetensors(SafetensorsError),
+}
+
+impl std::fmt::Display for MmapError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MmapError::Io(err) => write!(fmt, "{}", err),
+            MmapError::Npz(err) => write!(fmt, "{}", err),
+            MmapError::Safetensors(err) => write!(fmt, "{}", err),
+        }
+    }
+}
+
+impl Error for MmapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MmapError::Io(err) => Some(err),
+            MmapError::Npz(err) => Some(err),
+            MmapError::Safetensors(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for MmapError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<NpzError> for MmapError {
+    fn from(e: NpzError) -> Self {
+        Self::Npz(e)
+    }
+}
+
+impl From<ZipError> for MmapError {
+    fn from(e: ZipError) -> Self {
+        Self::Npz(NpzError::Zip(e))
+    }
+}
+
+impl From<SafetensorsError> for MmapError {
+    fn from(e: SafetensorsError) -> Self {
+        Self::Safetensors(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn::{SaveToNpz, SaveToSafetensors};
+    use rand::thread_rng;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    type Model = (Linear<3, 4>, ReLU, LayerNorm1D<4>);
+
+    fn model() -> Model {
+        let mut model: Model = Default::default();
+        model.reset_params(&mut thread_rng());
+        model.2.gamma = TensorCreator::randn(&mut thread_rng());
+        model
+    }
+
+    fn assert_same(a: &Model, b: &Model) {
+        assert_eq!(a.0.weight.data(), b.0.weight.data());
+        assert_eq!(a.0.bias.data(), b.0.bias.data());
+        assert_eq!(a.2.gamma.data(), b.2.gamma.data());
+        assert_eq!(a.2.beta.data(), b.2.beta.data());
+    }
+
+    #[test]
+    fn test_load_mmap_safetensors() {
+        let saved = model();
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        saved.save_safetensors(file.path()).expect("");
+
+        let weights = unsafe { MmapWeights::open(file.path()) }.expect("");
+        assert_eq!(weights.names(), ["0.weight", "0.bias", "2.gamma", "2.beta"]);
+        let mut loaded: Model = Default::default();
+        loaded.load_mmap(&weights).expect("");
+        assert_same(&saved, &loaded);
+
+        let weight: Tensor2D<4, 3> = weights.tensor("0.weight").expect("");
+        assert_eq!(weight.data(), saved.0.weight.data());
+
+        saved.save_safetensors_f16(file.path()).expect("");
+        let weights = unsafe { MmapWeights::open(file.path()) }.expect("");
+        let gamma: Tensor1D<4> = weights.tensor("2.gamma").expect("");
+        for (a, b) in gamma.data().iter().zip(saved.2.gamma.data()) {
+            assert!((a - b).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_load_mmap_npz() {
+        let saved = model();
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        saved.save(file.path()).expect("");
+
+        let weights = unsafe { MmapWeights::open(file.path()) }.expect("");
+        assert_eq!(weights.names().len(), 4);
+        let mut loaded: Model = Default::default();
+        loaded.load_mmap(&weights).expect("");
+        assert_same(&saved, &loaded);
+
+        let beta: Tensor1D<4> = weights.tensor("2.beta").expect("");
+        assert_eq!(beta.data(), saved.2.beta.data());
+    }
+
+    #[test]
+    fn test_mmap_errors() {
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        model().save_safetensors(file.path()).expect("");
+        let weights = unsafe { MmapWeights::open(file.path()) }.expect("");
+
+        let mut wrong: (Linear<3, 4>, Linear<4, 4>) = Default::default();
+        assert!(matches!(
+            wrong.load_mmap(&weights),
+            Err(MmapError::Safetensors(SafetensorsError::MissingTensor))
+        ));
+        assert!(matches!(
+            weights.tensor::<Tensor1D<4>>("1.bias"),
+            Err(MmapError::Safetensors(SafetensorsError::MissingTensor))
+        ));
+        assert!(matches!(
+            weights.tensor::<Tensor1D<3>>("0.bias"),
+            Err(MmapError::Safetensors(
+                SafetensorsError::ShapeMismatch { .. }
+            ))
+        ));
+
+        let mut file = NamedTempFile::new().expect("failed to create tempfile");
+        file.write_all(&100u64.to_le_bytes()).expect("");
+        file.write_all(b"{}").expect("");
+        assert!(matches!(
+            unsafe { MmapWeights::open(file.path()) },
+            Err(MmapError::Safetensors(SafetensorsError::InvalidHeader(_)))
+        ));
+    }
+}
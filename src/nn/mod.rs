@@ -60,6 +60,41 @@
 //! );
 //! ```
 //!
+//! # Summaries
+//!
+//! [Summarize::summary()] lists the layers of a model with their output shapes and number of
+//! parameters for a given input type, and how many parameters are trainable vs. frozen:
+//!
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let model: (Linear<5, 3>, ReLU, Linear<3, 2>) = Default::default();
+//! println!("{}", Summarize::<Tensor2D<16, 5>>::summary(&model));
+//! ```
+//!
+//! # Runtime batch sizes
+//!
+//! The batch size of a tensor is fixed at compile time. For a batch whose size is only known at
+//! runtime, e.g. the last samples of an epoch from [crate::data::DataLoader::remainder()], use a
+//! [DynBatch]. All modules implement [Module] for it, and losses are computed per sample and
+//! averaged with [DynBatch::mean()], so it can be trained like a normal batch:
+//!
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let model: (Linear<5, 3>, ReLU, Linear<3, 2>) = Default::default();
+//! let x: DynBatch<Tensor1D<5>> = DynBatch::new(vec![Tensor1D::zeros(); 7]);
+//! let targets: Vec<Tensor1D<2>> = vec![Tensor1D::zeros(); 7];
+//! let loss = model.forward(x.traced()).zip_map(&targets, mse_loss).mean();
+//! let gradients = loss.backward();
+//! ```
+//!
+//! To only run a model on a runtime number of samples, [forward_dynamic()] is faster: it pads the
+//! samples into batches of a fixed size.
+//!
+//! # Multiple threads
+//!
+//! [DataParallel] computes the gradients of a batch on multiple threads, with a replica of the
+//! model on each thread.
+//!
 //! # Saving and Loading
 //!
 //! Call [SaveToNpz::save()] and [LoadFromNpz::load()] traits. All modules provided here implement it,
@@ -74,30 +109,114 @@
 //! state_dict = {k: torch.from_numpy(v) for k, v in np.load("dfdx-model.npz").items()}
 //! mlp.load_state_dict(state_dict)
 //! ```
+//!
+//! The parameters of a module can also be accessed by name with [HasStateDict::state_dict()], and
+//! [HasStateDict::load_state_dict()] can load them into a module with a different structure, e.g.
+//! a pretrained backbone into a bigger model, reporting which keys were missing or unexpected.
+//!
+//! Modules can also be saved to & loaded from [.safetensors](https://github.com/huggingface/safetensors) files
+//! with [SaveToSafetensors::save_safetensors()] and [LoadFromSafetensors::load_safetensors()].
+//!
+//! To halve the size of large models, [SaveToNpz::save_f16()] and [SaveToSafetensors::save_safetensors_f16()]
+//! store the weights as half precision. Loading converts them back to `f32` automatically.
+//!
+//! With the `mmap` feature, large `.npz` and `.safetensors` files can be memory mapped with
+//! `MmapWeights` instead of being read into memory first, and loaded with `LoadFromMmap::load_mmap()`.
+//!
+//! With the `serde` feature, tensors and all the modules implement `serde::Serialize` and
+//! `serde::Deserialize`, so models can be embedded in configs or sent over the wire with
+//! any serde format. Tensors are serialized as nested sequences of their data.
+//!
+//! Pretrained weights can be loaded from PyTorch checkpoints with [LoadFromTorch::load_torch()],
+//! and [LoadFromTorch::load_torch_with()] can rename the keys of the state dict to match the module.
+//!
+//! With the `keras` feature, the weights of Keras models saved as `.h5` files can be loaded with
+//! `LoadFromKeras::load_keras()`, by mapping the names of the Keras layers to modules.
+//!
+//! # Exporting to GGUF
+//!
+//! Models can be exported to `.gguf` files for llama.cpp-compatible runtimes with
+//! [SaveToGguf::save_gguf()], optionally quantizing the weights with [GgufOptions::quantization].
+//!
+//! # Exporting to ONNX
+//!
+//! For inference in other runtimes, modules can be exported to [ONNX](https://onnx.ai/) with
+//! [ExportToOnnx::save_onnx()], e.g. `model.save_onnx::<Tensor2D<1, 5>>("model.onnx")`, where the
+//! generic is the input type. See [ExportToOnnx] for which modules are supported.
+//!
+//! ONNX models from other frameworks can be run with [OnnxInference], e.g.
+//! `OnnxInference::load("model.onnx")?.infer::<_, Tensor2D<1, 10>>(&x)?`.
 
 mod activations;
+#[cfg(feature = "std")]
+mod autocast;
+#[cfg(feature = "std")]
+mod data_parallel;
 mod dropout;
+#[cfg(feature = "std")]
+mod dynamic_batch;
 mod generalized_residual;
+#[cfg(feature = "std")]
+mod gguf;
 mod impl_module_for_tuples;
+#[cfg(feature = "keras")]
+mod keras;
 mod layer_norm;
 mod linear;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod module;
+#[cfg(feature = "std")]
 mod npz;
+#[cfg(feature = "std")]
+mod onnx;
 mod repeated;
 mod residual;
+#[cfg(feature = "std")]
+mod safetensors;
+#[cfg(feature = "serde")]
+mod serde_array;
 mod split_into;
+#[cfg(feature = "std")]
+mod state_dict;
+#[cfg(feature = "std")]
+mod summary;
+#[cfg(feature = "std")]
+mod torch;
 
 pub use activations::*;
+#[cfg(feature = "std")]
+pub use autocast::*;
+#[cfg(feature = "std")]
+pub use data_parallel::*;
 pub use dropout::*;
+#[cfg(feature = "std")]
+pub use dynamic_batch::*;
 pub use generalized_residual::*;
-pub use impl_module_for_tuples::*;
+#[cfg(feature = "std")]
+pub use gguf::*;
+#[cfg(feature = "keras")]
+pub use keras::*;
 pub use layer_norm::*;
 pub use linear::*;
+#[cfg(feature = "mmap")]
+pub use mmap::*;
 pub use module::*;
+#[cfg(feature = "std")]
 pub use npz::*;
+#[cfg(feature = "std")]
+pub use onnx::*;
 pub use repeated::*;
 pub use residual::*;
+#[cfg(feature = "std")]
+pub use safetensors::*;
 pub use split_into::*;
+#[cfg(feature = "std")]
+pub use state_dict::*;
+#[cfg(feature = "std")]
+pub use summary::*;
+#[cfg(feature = "std")]
+pub use torch::*;
 
 #[cfg(feature = "nightly")]
 mod transformer;
@@ -126,9 +245,7 @@ mod tests {
     impl GradientProvider for SimpleGradients {
         fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
         where
-            P: crate::prelude::HasUniqueId
-                + crate::prelude::HasArrayType<Dtype = f32>
-                + crate::prelude::HasDevice,
+            P: crate::prelude::Tensor<Dtype = f32>,
         {
             self.0.remove(p)
         }
@@ -95,4 +95,11 @@ pub trait ResetParams {
     /// }
     /// ```
     fn reset_params<R: rand::Rng>(&mut self, rng: &mut R);
+
+    /// Calls [ResetParams::reset_params()] with [crate::rng::seeded_rng()], so the parameters
+    /// are reproducible with [crate::set_seed()].
+    #[cfg(feature = "std")]
+    fn reset_params_seeded(&mut self) {
+        self.reset_params(&mut crate::rng::seeded_rng());
+    }
 }
@@ -1,7 +1,14 @@
-use crate::numpy::{self, NpyError, NumpyDtype, NumpyShape, ReadNumbers, WriteNumbers};
+use crate::arrays::CountElements;
+use crate::devices::flat;
+use crate::gradients::{ParamVisitor, VisitParams};
+use crate::numpy::{
+    self, f32_to_f16, write_raw_header, Endian, NpyError, NumpyDtype, NumpyShape, ReadNumbers,
+    WriteNumbers,
+};
+use crate::tensor::Tensor;
 use std::error::Error;
 use std::{
-    io::{BufReader, BufWriter, Read, Seek, Write},
+    io::{BufReader, BufWriter, Cursor, Read, Seek, Write},
     path::Path,
 };
 use zip::{
@@ -30,6 +37,14 @@ pub trait SaveToNpz {
         Ok(())
     }
 
+    /// Saves this object in the `.npz` format into a new [Vec], for when there is no file system
+    /// (e.g. on `wasm32-unknown-unknown`). Load it with [LoadFromNpz::load_from_bytes()].
+    fn save_to_bytes(&self) -> ZipResult<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        self.write("", &mut zip)?;
+        Ok(zip.finish()?.into_inner())
+    }
+
     /// Write this object into [ZipWriter] `w` with a base filename of `filename_prefix`.
     ///
     /// Example:
@@ -51,6 +66,55 @@ pub trait SaveToNpz {
     {
         Ok(())
     }
+
+    /// Save this object into the `.npz` file at `path` with all the `f32` arrays stored as
+    /// half precision (`f2`), which halves the size of the file. [LoadFromNpz] converts
+    /// them back to `f32`.
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+    /// model.save_f16("tst.npz")?;
+    /// ```
+    fn save_f16<P: AsRef<Path>>(&self, path: P) -> ZipResult<()>
+    where
+        Self: VisitParams,
+    {
+        let f = std::fs::File::create(path)?;
+        let f = BufWriter::new(f);
+        let mut zip = ZipWriter::new(f);
+        self.write_f16("", &mut zip)?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Write this object into [ZipWriter] `w` like [SaveToNpz::write()], but with all the `f32`
+    /// arrays stored as half precision (`f2`). The arrays are the parameters from [VisitParams],
+    /// which have the same names as the files of [SaveToNpz::write()].
+    fn write_f16<W>(&self, filename_prefix: &str, w: &mut ZipWriter<W>) -> ZipResult<()>
+    where
+        W: Write + Seek,
+        Self: VisitParams,
+    {
+        let mut writer = F16Writer { w, result: Ok(()) };
+        self.visit_params(filename_prefix, &mut writer);
+        writer.result
+    }
+}
+
+/// Writes every parameter with [npz_fwrite_f16()], and stops at the first error.
+struct F16Writer<'a, W: Write + Seek> {
+    w: &'a mut ZipWriter<W>,
+    result: ZipResult<()>,
+}
+
+impl<W: Write + Seek> ParamVisitor for F16Writer<'_, W> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+        if self.result.is_ok() {
+            self.result = npz_fwrite_f16(self.w, format!("{name}.npy"), p.data());
+        }
+    }
 }
 
 /// Something that can be loaded from a `.npz` file (which is a `zip` file).
@@ -73,6 +137,20 @@ pub trait LoadFromNpz {
         Ok(())
     }
 
+    /// Loads data in the `.npz` format from `bytes`, for when there is no file system (e.g. a
+    /// file that was embedded with [include_bytes!] or downloaded in a browser).
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let mut model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+    /// model.load_from_bytes(include_bytes!("model.npz"))?;
+    /// ```
+    fn load_from_bytes(&mut self, bytes: &[u8]) -> Result<(), NpzError> {
+        let mut zip = ZipArchive::new(Cursor::new(bytes))?;
+        self.read("", &mut zip)
+    }
+
     /// Reads this object from a [ZipArchive]. `r` with a base filename of `filename_prefix`.
     ///
     /// Example:
@@ -157,6 +235,21 @@ pub fn npz_fwrite<W: Write + Seek, T: NumpyDtype + NumpyShape + WriteNumbers>(
     Ok(())
 }
 
+/// Writes the `f32` array `data` to a new file in a zip archive named `filename`, as half
+/// precision (`f2`) numbers.
+pub fn npz_fwrite_f16<W, T>(w: &mut zip::ZipWriter<W>, filename: String, data: &T) -> ZipResult<()>
+where
+    W: Write + Seek,
+    T: NumpyShape + CountElements<Dtype = f32>,
+{
+    w.start_file(filename, Default::default())?;
+    write_raw_header(w, Endian::Little, "f2", T::shape())?;
+    for &x in flat(data) {
+        w.write_all(&f32_to_f16(x).to_le_bytes())?;
+    }
+    Ok(())
+}
+
 /// Reads `data` from a file already in a zip archive named `filename`.
 ///
 /// Example:
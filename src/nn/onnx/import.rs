@@ -0,0 +1,431 @@
+use super::ops::{build_op, Op};
+use super::{OnnxModel, OnnxTensor, OnnxValueInfo};
+use crate::numpy::{f16_to_f32, Endian, NumpyShape, ReadNumbers, WriteNumbers};
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::error::Error;
+use std::{fs::File, io::Read, path::Path};
+
+/// An ONNX graph that can be run for inference. Each node is built into an
+/// operation when the model is loaded, and [OnnxInference::run()] executes them in order.
+///
+/// Values are [OnnxArray]s of `f32`, so integer tensors (e.g. the shape input of `Reshape`)
+/// are converted to `f32` as well.
+///
+/// Supported operators:
+/// - Elementwise: `Add`, `Sub`, `Mul`, `Div`, `Pow`, `Relu`, `LeakyRelu`, `Elu`, `Sigmoid`, `Tanh`,
+///   `Softplus`, `Exp`, `Log`, `Sin`, `Cos`, `Sqrt`, `Abs`, `Neg`, `Clip`, `Cast`, `Identity`, `Dropout`
+/// - Linear algebra: `MatMul`, `Gemm`
+/// - Normalization: `Softmax`, `LogSoftmax`, `LayerNormalization`, `BatchNormalization`
+/// - Convolutions: `Conv` (1d & 2d, including groups), `MaxPool`, `AveragePool`,
+///   `GlobalAveragePool`, `GlobalMaxPool`
+/// - Shapes: `Flatten`, `Reshape`, `Transpose`, `Squeeze`, `Unsqueeze`, `Concat`, `Shape`, `Gather`, `Constant`
+///
+/// # Example
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// let model = OnnxInference::load("model.onnx")?;
+/// let y: Tensor2D<1, 2> = model.infer(&Tensor2D::<1, 5>::zeros())?;
+/// ```
+pub struct OnnxInference {
+    inputs: Vec<OnnxValueInfo>,
+    outputs: Vec<String>,
+    initializers: HashMap<String, OnnxArray>,
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    op: Box<dyn Op>,
+    inputs: Vec<String>,
+    output: String,
+}
+
+impl std::fmt::Debug for OnnxInference {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("OnnxInference")
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .field("num_nodes", &self.nodes.len())
+            .finish()
+    }
+}
+
+impl OnnxInference {
+    /// Loads the `.onnx` file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, OnnxError> {
+        let mut f = File::open(path)?;
+        Self::read(&mut f)
+    }
+
+    /// Reads a model in the `.onnx` format from `r`.
+    pub fn read<R: Read>(r: &mut R) -> Result<Self, OnnxError> {
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf)?;
+        Self::from_model(&OnnxModel::decode(&buf)?)
+    }
+
+    /// Builds the operations for each node of `model`. Returns [OnnxError::UnsupportedOp] if
+    /// any of the nodes can't be run.
+    pub fn from_model(model: &OnnxModel) -> Result<Self, OnnxError> {
+        let graph = &model.graph;
+        let mut initializers = HashMap::new();
+        for tensor in graph.initializers.iter() {
+            initializers.insert(tensor.name.clone(), OnnxArray::from_tensor(tensor)?);
+        }
+
+        // NOTE: older models also list the initializers as graph inputs
+        let inputs = graph
+            .inputs
+            .iter()
+            .filter(|info| !initializers.contains_key(&info.name))
+            .cloned()
+            .collect();
+
+        let mut nodes = Vec::with_capacity(graph.nodes.len());
+        for node in graph.nodes.iter() {
+            let output = node.outputs.first().ok_or_else(|| {
+                OnnxError::Invalid(format!("node `{}` has no outputs", node.name))
+            })?;
+            nodes.push(Node {
+                op: build_op(node, model.opset_version)?,
+                inputs: node.inputs.clone(),
+                output: output.clone(),
+            });
+        }
+
+        Ok(Self {
+            inputs,
+            outputs: graph.outputs.iter().map(|o| o.name.clone()).collect(),
+            initializers,
+            nodes,
+        })
+    }
+
+    /// The inputs of the graph that must be passed to [OnnxInference::run()], in order.
+    pub fn inputs(&self) -> &[OnnxValueInfo] {
+        &self.inputs
+    }
+
+    /// Runs the graph on `inputs`, which must be in the same order as [OnnxInference::inputs()],
+    /// and returns the values of the graph's outputs.
+    pub fn run(&self, inputs: Vec<OnnxArray>) -> Result<Vec<OnnxArray>, OnnxError> {
+        if inputs.len() != self.inputs.len() {
+            return Err(OnnxError::Invalid(format!(
+                "expected {} inputs, found {}",
+                self.inputs.len(),
+                inputs.len()
+            )));
+        }
+
+        let mut values: HashMap<&str, OnnxArray> = HashMap::new();
+        for (info, x) in self.inputs.iter().zip(inputs) {
+            let fixed = info.shape.len() == x.shape.len()
+                && info
+                    .shape
+                    .iter()
+                    .zip(x.shape.iter())
+                    .all(|(&e, &f)| e == 0 || e == f);
+            if !fixed {
+                return Err(OnnxError::ShapeMismatch {
+                    expected: info.shape.clone(),
+                    found: x.shape,
+                });
+            }
+            values.insert(&info.name, x);
+        }
+
+        for node in self.nodes.iter() {
+            let y = {
+                let mut inputs = Vec::with_capacity(node.inputs.len());
+                for name in node.inputs.iter() {
+                    // NOTE: optional inputs that aren't given have an empty name
+                    if name.is_empty() {
+                        inputs.push(None);
+                        continue;
+                    }
+                    let x = values
+                        .get(name.as_str())
+                        .or_else(|| self.initializers.get(name))
+                        .ok_or_else(|| OnnxError::Invalid(format!("missing value `{name}`")))?;
+                    inputs.push(Some(x));
+                }
+                node.op.run(&inputs)?
+            };
+            values.insert(&node.output, y);
+        }
+
+        let mut outputs = Vec::with_capacity(self.outputs.len());
+        for name in self.outputs.iter() {
+            let y = values
+                .remove(name.as_str())
+                .or_else(|| self.initializers.get(name).cloned())
+                .ok_or_else(|| OnnxError::Invalid(format!("missing output `{name}`")))?;
+            outputs.push(y);
+        }
+        Ok(outputs)
+    }
+
+    /// Runs the graph on the tensor `x`, and returns the first output as a tensor of type `O`.
+    /// The graph must have a single input.
+    pub fn infer<I, O>(&self, x: &I) -> Result<O, OnnxError>
+    where
+        I: HasArrayData<Dtype = f32>,
+        O: TensorCreator + HasArrayType<Dtype = f32>,
+    {
+        let y = self.run(vec![OnnxArray::from_array(x.data())])?;
+        let mut data: Box<O::Array> = O::Device::zeros();
+        y[0].copy_to(data.as_mut())?;
+        Ok(O::new_boxed(data))
+    }
+}
+
+/// A dynamically shaped array of `f32` that is the value of an edge in [OnnxInference].
+/// The data is stored in row major order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnnxArray {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+impl OnnxArray {
+    /// Creates an array with `shape`. Panics if `data` doesn't have the number of elements in `shape`.
+    pub fn new(shape: Vec<usize>, data: Vec<f32>) -> Self {
+        assert_eq!(shape.iter().product::<usize>(), data.len());
+        Self { shape, data }
+    }
+
+    /// Creates an array with the shape & data of the nd array `data`.
+    pub fn from_array<A: NumpyShape + WriteNumbers>(data: &A) -> Self {
+        let mut bytes = Vec::new();
+        data.write_numbers(&mut bytes, Endian::Little)
+            .expect("writing to a Vec can't fail");
+        let data = bytes
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        Self::new(A::shape(), data)
+    }
+
+    /// Copies the data into the nd array `out`, which must have the same shape.
+    pub fn copy_to<A: NumpyShape + ReadNumbers>(&self, out: &mut A) -> Result<(), OnnxError> {
+        if A::shape() != self.shape {
+            return Err(OnnxError::ShapeMismatch {
+                expected: A::shape(),
+                found: self.shape.clone(),
+            });
+        }
+        let bytes: Vec<u8> = self.data.iter().flat_map(|v| v.to_le_bytes()).collect();
+        out.read_numbers(&mut bytes.as_slice(), Endian::Little)?;
+        Ok(())
+    }
+
+    /// Converts the data of `tensor` to `f32`.
+    pub(super) fn from_tensor(tensor: &OnnxTensor) -> Result<Self, OnnxError> {
+        let raw = &tensor.raw_data;
+        let data: Vec<f32> = match tensor.data_type {
+            1 => raw
+                .chunks(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+            2 | 9 => raw.iter().map(|&b| b as f32).collect(),
+            3 => raw.iter().map(|&b| b as i8 as f32).collect(),
+            6 => raw
+                .chunks(4)
+                .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32)
+                .collect(),
+            7 => raw
+                .chunks(8)
+                .map(|b| i64::from_le_bytes(b.try_into().unwrap()) as f32)
+                .collect(),
+            10 => raw
+                .chunks(2)
+                .map(|b| f16_to_f32(u16::from_le_bytes([b[0], b[1]])))
+                .collect(),
+            11 => raw
+                .chunks(8)
+                .map(|b| f64::from_le_bytes(b.try_into().unwrap()) as f32)
+                .collect(),
+            dtype => {
+                return Err(OnnxError::UnsupportedDtype {
+                    name: tensor.name.clone(),
+                    dtype,
+                })
+            }
+        };
+        let shape: Vec<usize> = tensor.dims.iter().map(|&d| d as usize).collect();
+        if shape.iter().product::<usize>() != data.len() {
+            return Err(OnnxError::Invalid(format!(
+                "tensor `{}` has {} elements, but its shape is {:?}",
+                tensor.name,
+                data.len(),
+                shape
+            )));
+        }
+        Ok(Self { shape, data })
+    }
+}
+
+/// Error that can happen while loading or running an ONNX model.
+#[derive(Debug)]
+pub enum OnnxError {
+    /// Something went wrong with reading the file.
+    Io(std::io::Error),
+
+    /// The file is not a valid ONNX protobuf message.
+    Decode(String),
+
+    /// An attribute has a type that is not supported (e.g. subgraphs).
+    UnsupportedAttribute(String),
+
+    /// The graph contains an operator, or a configuration of an operator, that is not supported.
+    UnsupportedOp(String),
+
+    /// A tensor in the graph has a data type that can't be converted to `f32`.
+    UnsupportedDtype { name: String, dtype: i32 },
+
+    /// The graph is not valid, e.g. a node uses a value that doesn't exist or shapes are incompatible.
+    Invalid(String),
+
+    /// An input or output has a different shape than expected.
+    ShapeMismatch {
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+}
+
+impl std::fmt::Display for OnnxError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OnnxError::Io(err) => write!(fmt, "{}", err),
+            OnnxError::Decode(msg) => write!(fmt, "failed to decode model: {}", msg),
+            OnnxError::UnsupportedAttribute(name) => {
+                write!(fmt, "attribute {} has an unsupported type", name)
+            }
+            OnnxError::UnsupportedOp(msg) => write!(fmt, "unsupported operator: {}", msg),
+            OnnxError::UnsupportedDtype { name, dtype } => {
+                write!(fmt, "tensor {} has unsupported data type {}", name, dtype)
+            }
+            OnnxError::Invalid(msg) => write!(fmt, "invalid graph: {}", msg),
+            OnnxError::ShapeMismatch { expected, found } => {
+                write!(
+                    fmt,
+                    "shape mismatch: expected {:?}, found {:?}",
+                    expected, found
+                )
+            }
+        }
+    }
+}
+
+impl Error for OnnxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            OnnxError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for OnnxError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::StdRng, SeedableRng};
+
+    fn roundtrip<M, I, O>(model: &M, x: I) -> O
+    where
+        M: ExportToOnnx + Module<I>,
+        I: HasArrayData<Dtype = f32>,
+        O: TensorCreator + HasArrayType<Dtype = f32>,
+    {
+        let mut buf = Vec::new();
+        model.write_onnx::<I>(&mut buf).expect("");
+        let onnx = OnnxInference::read(&mut buf.as_slice()).expect("");
+        onnx.infer(&x).expect("")
+    }
+
+    #[test]
+    fn test_mlp_roundtrip() {
+        type Model = (
+            Linear<5, 8>,
+            ReLU,
+            Linear<8, 8>,
+            Tanh,
+            Linear<8, 3>,
+            Softmax,
+        );
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let x: Tensor2D<4, 5> = Tensor2D::randn(&mut rng);
+        let y: Tensor2D<4, 3> = roundtrip(&model, x.clone());
+        assert_close(y.data(), model.forward(x).data());
+    }
+
+    #[test]
+    fn test_residual_layer_norm_roundtrip() {
+        type Model = (
+            Residual<(Linear<4, 4>, Sigmoid)>,
+            LayerNorm1D<4>,
+            GeneralizedResidual<(Linear<4, 3>, Square), (Linear<4, 3>, Abs)>,
+            Dropout,
+            (Sin, Cos, Exp, Sqrt, Ln),
+        );
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        model.1.gamma = Tensor1D::randn(&mut rng);
+        model.1.beta = Tensor1D::randn(&mut rng);
+        let x: Tensor1D<4> = Tensor1D::randn(&mut rng);
+        let y: Tensor1D<3> = roundtrip(&model, x.clone());
+        assert_close(y.data(), model.forward(x).data());
+    }
+
+    #[test]
+    fn test_run_inputs() {
+        let model: Linear<2, 3> = Default::default();
+        let onnx = OnnxInference::from_model(&model.to_onnx::<Tensor1D<2>>()).expect("");
+        assert_eq!(onnx.inputs().len(), 1);
+        assert_eq!(&onnx.inputs()[0].shape, &[2]);
+
+        let y = onnx
+            .run(vec![OnnxArray::new(vec![2], vec![1.0, 2.0])])
+            .expect("");
+        assert_eq!(y, [OnnxArray::new(vec![3], vec![0.0; 3])]);
+
+        assert!(matches!(
+            onnx.run(vec![OnnxArray::new(vec![3], vec![0.0; 3])]),
+            Err(OnnxError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(onnx.run(vec![]), Err(OnnxError::Invalid(_))));
+        assert!(matches!(
+            onnx.infer::<_, Tensor1D<2>>(&Tensor1D::<2>::zeros()),
+            Err(OnnxError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_op() {
+        let mut model = ReLU.to_onnx::<Tensor1D<2>>();
+        model.graph.nodes[0].op_type = "NonMaxSuppression".into();
+        assert!(matches!(
+            OnnxInference::from_model(&model),
+            Err(OnnxError::UnsupportedOp(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let buf = Linear::<2, 2>::default().to_onnx::<Tensor1D<2>>().encode();
+        assert!(matches!(
+            OnnxInference::read(&mut &buf[..buf.len() - 1]),
+            Err(OnnxError::Decode(_))
+        ));
+    }
+}
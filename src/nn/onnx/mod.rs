@@ -0,0 +1,301 @@
+mod import;
+mod ops;
+mod proto;
+
+pub use import::*;
+pub use proto::*;
+
+use crate::arrays::HasArrayType;
+use crate::numpy::{NumpyShape, WriteNumbers};
+use crate::prelude::Module;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Something that can be exported as an [ONNX](https://onnx.ai/) graph for inference in other runtimes.
+///
+/// Since modules are statically typed, there is nothing to record at runtime - instead each module
+/// appends the nodes it would run in [Module::forward()] to an [OnnxGraph]. Parameters are stored in
+/// the graph as initializers, named the same way as in [crate::nn::SaveToNpz].
+///
+/// This is implemented for [crate::nn::Linear], the activations (including [crate::nn::Softmax]),
+/// [crate::nn::LayerNorm1D], the dropout layers (which are no-ops at inference), tuples,
+/// [crate::nn::Repeated], [crate::nn::Residual] and [crate::nn::GeneralizedResidual].
+/// With the `nightly` feature, `Conv2D` and `FlattenImage` are supported as well.
+///
+/// # Example
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// let model: (Linear<5, 10>, ReLU, Linear<10, 2>, Softmax) = Default::default();
+/// model.save_onnx::<Tensor2D<8, 5>>("model.onnx")?;
+/// ```
+///
+/// # Implementing
+/// Modules add the nodes for their forward pass with [OnnxGraph::add_node()], and their parameters
+/// with [OnnxGraph::add_initializer()], using `pre` as the prefix of the parameter names:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// struct AddOne;
+///
+/// impl ExportToOnnx for AddOne {
+///     fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+///         let one = graph.add_initializer(format!("{pre}one"), &1.0f32);
+///         let y = graph.add_node("Add", vec![x.name, one], vec![]);
+///         OnnxValueInfo { name: y, shape: x.shape }
+///     }
+/// }
+/// ```
+pub trait ExportToOnnx {
+    /// Appends the nodes that compute `Module::forward(input)` to `graph`, and returns the output value.
+    /// Parameters are added as initializers, and their names are prefixed with `pre`.
+    fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, input: OnnxValueInfo) -> OnnxValueInfo;
+
+    /// Builds an ONNX model of `Module::<I>::forward()`. The graph has a single input named `input`,
+    /// and a single output named `output`.
+    fn to_onnx<I>(&self) -> OnnxModel
+    where
+        Self: Module<I>,
+        I: HasArrayType<Dtype = f32>,
+    {
+        let mut graph = OnnxGraph {
+            name: "dfdx".into(),
+            ..Default::default()
+        };
+        let input = OnnxValueInfo {
+            name: "input".into(),
+            shape: I::Array::shape(),
+        };
+        graph.inputs.push(input.clone());
+        let mut output = self.export_onnx("", &mut graph, input);
+
+        match graph.nodes.last_mut() {
+            Some(node) if node.outputs[0] == output.name => node.outputs[0] = "output".into(),
+            // the output is an input or initializer, so it needs to be copied into `output`
+            _ => graph.nodes.push(OnnxNode {
+                name: "output".into(),
+                op_type: "Identity".into(),
+                inputs: vec![output.name],
+                outputs: vec!["output".into()],
+                attributes: vec![],
+            }),
+        }
+        output.name = "output".into();
+        graph.outputs.push(output);
+
+        OnnxModel {
+            ir_version: ONNX_IR_VERSION,
+            opset_version: ONNX_OPSET_VERSION,
+            producer_name: "dfdx".into(),
+            producer_version: env!("CARGO_PKG_VERSION").into(),
+            graph,
+        }
+    }
+
+    /// Writes the ONNX model of `Module::<I>::forward()` to `w`. See [ExportToOnnx::to_onnx()].
+    fn write_onnx<I>(&self, w: &mut impl Write) -> std::io::Result<()>
+    where
+        Self: Module<I>,
+        I: HasArrayType<Dtype = f32>,
+    {
+        w.write_all(&self.to_onnx::<I>().encode())
+    }
+
+    /// Saves the ONNX model of `Module::<I>::forward()` into the `.onnx` file at `path`. See [ExportToOnnx::to_onnx()].
+    fn save_onnx<I>(&self, path: impl AsRef<Path>) -> std::io::Result<()>
+    where
+        Self: Module<I>,
+        I: HasArrayType<Dtype = f32>,
+    {
+        let mut f = BufWriter::new(File::create(path)?);
+        self.write_onnx::<I>(&mut f)?;
+        f.flush()
+    }
+}
+
+impl OnnxGraph {
+    /// Adds a node that runs `op_type` on `inputs`, and returns the name of its output.
+    pub fn add_node(
+        &mut self,
+        op_type: &str,
+        inputs: Vec<String>,
+        attributes: Vec<OnnxAttribute>,
+    ) -> String {
+        let name = format!("{op_type}_{}", self.nodes.len());
+        self.nodes.push(OnnxNode {
+            name: name.clone(),
+            op_type: op_type.into(),
+            inputs,
+            outputs: vec![name.clone()],
+            attributes,
+        });
+        name
+    }
+
+    /// Adds a float initializer named `name` with the contents of `data`, and returns its name.
+    pub fn add_initializer<A: NumpyShape + WriteNumbers>(
+        &mut self,
+        name: String,
+        data: &A,
+    ) -> String {
+        self.initializers
+            .push(OnnxTensor::from_array(name.clone(), data));
+        name
+    }
+
+    /// Adds a 1d int64 initializer named `name`, and returns its name. These are used
+    /// for inputs like the shape of `Reshape`.
+    pub fn add_i64_initializer(&mut self, name: String, data: &[i64]) -> String {
+        self.initializers
+            .push(OnnxTensor::from_i64s(name.clone(), data));
+        name
+    }
+
+    /// Adds a node for a unary `op_type` that doesn't change the shape of `x`.
+    pub(crate) fn add_unary(&mut self, op_type: &str, x: OnnxValueInfo) -> OnnxValueInfo {
+        OnnxValueInfo {
+            name: self.add_node(op_type, vec![x.name], vec![]),
+            shape: x.shape,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn op_types(model: &OnnxModel) -> Vec<&str> {
+        model
+            .graph
+            .nodes
+            .iter()
+            .map(|n| n.op_type.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_export_mlp() {
+        let mut model: (Linear<3, 2>, ReLU, Linear<2, 4>, Softmax) = Default::default();
+        model.0.weight = Tensor2D::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let onnx = model.to_onnx::<Tensor2D<8, 3>>();
+
+        assert_eq!(onnx.ir_version, ONNX_IR_VERSION);
+        assert_eq!(
+            op_types(&onnx),
+            ["MatMul", "Add", "Relu", "MatMul", "Add", "Softmax"]
+        );
+
+        let graph = &onnx.graph;
+        assert_eq!(graph.inputs[0].name, "input");
+        assert_eq!(&graph.inputs[0].shape, &[8, 3]);
+        assert_eq!(graph.outputs[0].name, "output");
+        assert_eq!(&graph.outputs[0].shape, &[8, 4]);
+        assert_eq!(&graph.nodes[0].inputs, &["input", "0.weight"]);
+        assert_eq!(&graph.nodes[1].inputs, &["MatMul_0", "0.bias"]);
+        assert_eq!(&graph.nodes[5].outputs, &["output"]);
+        assert_eq!(graph.nodes[5].attributes, [OnnxAttribute::int("axis", -1)]);
+
+        let names: Vec<&str> = graph.initializers.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["0.weight", "0.bias", "2.weight", "2.bias"]);
+
+        // the weight is transposed so that it can be right multiplied
+        let w = &graph.initializers[0];
+        assert_eq!(&w.dims, &[3, 2]);
+        assert_eq!(w.data_type, ONNX_FLOAT);
+        let data: Vec<f32> = w
+            .raw_data
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(data, [1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn test_export_residual_layer_norm() {
+        type Model = (
+            Residual<(Linear<4, 4>, Tanh)>,
+            LayerNorm1D<4>,
+            Dropout,
+            Square,
+        );
+        let model: Model = Default::default();
+        let onnx = model.to_onnx::<Tensor1D<4>>();
+        assert_eq!(
+            op_types(&onnx),
+            ["MatMul", "Add", "Tanh", "Add", "LayerNormalization", "Mul"]
+        );
+
+        let nodes = &onnx.graph.nodes;
+        assert_eq!(&nodes[3].inputs, &["Tanh_2", "input"]);
+        assert_eq!(&nodes[4].inputs, &["Add_3", "1.gamma", "1.beta"]);
+        assert_eq!(
+            nodes[4].attributes,
+            [
+                OnnxAttribute::int("axis", -1),
+                OnnxAttribute::float("epsilon", 1e-5)
+            ]
+        );
+        assert_eq!(
+            &nodes[5].inputs,
+            &["LayerNormalization_4", "LayerNormalization_4"]
+        );
+        assert_eq!(&onnx.graph.outputs[0].shape, &[4]);
+    }
+
+    #[test]
+    fn test_export_identity() {
+        let onnx = Dropout::default().to_onnx::<Tensor1D<3>>();
+        assert_eq!(op_types(&onnx), ["Identity"]);
+        assert_eq!(&onnx.graph.nodes[0].inputs, &["input"]);
+        assert_eq!(&onnx.graph.nodes[0].outputs, &["output"]);
+    }
+
+    #[test]
+    fn test_export_repeated_and_generalized_residual() {
+        type Model = (
+            Repeated<(Linear<3, 3>, Sigmoid), 2>,
+            GeneralizedResidual<Linear<3, 2>, Linear<3, 2>>,
+        );
+        let model: Model = Default::default();
+        let onnx = model.to_onnx::<Tensor3D<2, 5, 3>>();
+        assert_eq!(
+            op_types(&onnx),
+            [
+                "MatMul", "Add", "Sigmoid", "MatMul", "Add", "Sigmoid", "MatMul", "Add", "MatMul",
+                "Add", "Add"
+            ]
+        );
+        let names: Vec<&str> = onnx
+            .graph
+            .initializers
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "0.0.0.weight",
+                "0.0.0.bias",
+                "0.1.0.weight",
+                "0.1.0.bias",
+                "1._mainweight",
+                "1._mainbias",
+                "1._residualweight",
+                "1._residualbias"
+            ]
+        );
+        assert_eq!(&onnx.graph.outputs[0].shape, &[2, 5, 2]);
+    }
+
+    #[test]
+    fn test_write_onnx() {
+        let model: (Linear<2, 2>, Sin) = Default::default();
+        let mut buf = Vec::new();
+        model.write_onnx::<Tensor1D<2>>(&mut buf).expect("");
+        assert_eq!(buf, model.to_onnx::<Tensor1D<2>>().encode());
+        // ir_version = 8, then producer_name = "dfdx"
+        assert_eq!(&buf[..8], &[0x08, 8, 0x12, 4, b'd', b'f', b'd', b'x']);
+    }
+}
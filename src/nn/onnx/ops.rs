@@ -0,0 +1,1388 @@
+use super::{AttributeValue, OnnxArray, OnnxError, OnnxNode};
+
+/// A node of an [super::OnnxInference] graph. Optional inputs that aren't given are `None`.
+pub(super) trait Op {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError>;
+}
+
+/// Builds the operation for `node`, using the semantics of version `opset` of the default operator set.
+pub(super) fn build_op(node: &OnnxNode, opset: i64) -> Result<Box<dyn Op>, OnnxError> {
+    let attrs = Attributes(node);
+    let op: Box<dyn Op> = match node.op_type.as_str() {
+        "Identity" | "Dropout" => unary(|x| x),
+        "Relu" => unary(|x| x.max(0.0)),
+        "Sigmoid" => unary(|x| 1.0 / (1.0 + (-x).exp())),
+        "Tanh" => unary(f32::tanh),
+        "Exp" => unary(f32::exp),
+        "Log" => unary(f32::ln),
+        "Sin" => unary(f32::sin),
+        "Cos" => unary(f32::cos),
+        "Sqrt" => unary(f32::sqrt),
+        "Abs" => unary(f32::abs),
+        "Neg" => unary(|x| -x),
+        "Softplus" => unary(|x| x.exp().ln_1p()),
+        "LeakyRelu" => {
+            let alpha = attrs.float("alpha", 0.01)?;
+            unary(move |x| if x < 0.0 { alpha * x } else { x })
+        }
+        "Elu" => {
+            let alpha = attrs.float("alpha", 1.0)?;
+            unary(move |x| if x < 0.0 { alpha * x.exp_m1() } else { x })
+        }
+        "Cast" => match attrs.int("to", 1)? {
+            // integer & bool types
+            2..=7 | 9 | 12 | 13 => unary(f32::trunc),
+            _ => unary(|x| x),
+        },
+        "Clip" if opset < 11 => {
+            let min = attrs.float("min", f32::NEG_INFINITY)?;
+            let max = attrs.float("max", f32::INFINITY)?;
+            unary(move |x| x.clamp(min, max))
+        }
+        "Clip" => Box::new(Clip),
+        "Add" => binary(|a, b| a + b),
+        "Sub" => binary(|a, b| a - b),
+        "Mul" => binary(|a, b| a * b),
+        "Div" => binary(|a, b| a / b),
+        "Pow" => binary(f32::powf),
+        "MatMul" => Box::new(MatMul),
+        "Gemm" => Box::new(Gemm {
+            alpha: attrs.float("alpha", 1.0)?,
+            beta: attrs.float("beta", 1.0)?,
+            trans_a: attrs.int("transA", 0)? != 0,
+            trans_b: attrs.int("transB", 0)? != 0,
+        }),
+        "Softmax" | "LogSoftmax" => Box::new(Softmax {
+            axis: attrs.int("axis", if opset < 13 { 1 } else { -1 })?,
+            coerce_2d: opset < 13,
+            log: node.op_type == "LogSoftmax",
+        }),
+        "LayerNormalization" => Box::new(LayerNorm {
+            axis: attrs.int("axis", -1)?,
+            epsilon: attrs.float("epsilon", 1e-5)?,
+        }),
+        "BatchNormalization" => Box::new(BatchNorm {
+            epsilon: attrs.float("epsilon", 1e-5)?,
+        }),
+        "Conv" => Box::new(Conv {
+            window: Window::new(&attrs, attrs.ints("kernel_shape")?)?,
+            group: match attrs.int("group", 1)? {
+                g if g > 0 => g as usize,
+                _ => return Err(attrs.invalid_value("group")),
+            },
+        }),
+        "MaxPool" | "AveragePool" => {
+            let kernel = attrs.ints("kernel_shape")?;
+            if kernel.is_none() {
+                return Err(OnnxError::Invalid(format!(
+                    "{} requires kernel_shape",
+                    node.op_type
+                )));
+            }
+            Box::new(Pool {
+                window: Window::new(&attrs, kernel)?,
+                max: node.op_type == "MaxPool",
+                count_include_pad: attrs.int("count_include_pad", 0)? != 0,
+            })
+        }
+        "GlobalAveragePool" => Box::new(GlobalPool { max: false }),
+        "GlobalMaxPool" => Box::new(GlobalPool { max: true }),
+        "Flatten" => Box::new(Flatten {
+            axis: attrs.int("axis", 1)?,
+        }),
+        "Reshape" => Box::new(Reshape {
+            allow_zero: attrs.int("allowzero", 0)? != 0,
+        }),
+        "Transpose" => Box::new(Transpose {
+            perm: attrs.ints("perm")?,
+        }),
+        "Squeeze" => Box::new(Squeeze {
+            axes: attrs.ints("axes")?,
+        }),
+        "Unsqueeze" => Box::new(Unsqueeze {
+            axes: attrs.ints("axes")?,
+        }),
+        "Concat" => Box::new(Concat {
+            axis: attrs.int("axis", 0)?,
+        }),
+        "Shape" => Box::new(Shape {
+            start: attrs.int("start", 0)?,
+            end: attrs.ints("end")?.map(|e| e[0]),
+        }),
+        "Gather" => Box::new(Gather {
+            axis: attrs.int("axis", 0)?,
+        }),
+        "Constant" => Box::new(Constant(attrs.constant()?)),
+        op => return Err(OnnxError::UnsupportedOp(op.into())),
+    };
+    Ok(op)
+}
+
+/// Reads the attributes of a node.
+struct Attributes<'a>(&'a OnnxNode);
+
+impl<'a> Attributes<'a> {
+    fn get(&self, name: &str) -> Option<&'a AttributeValue> {
+        self.0
+            .attributes
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| &a.value)
+    }
+
+    fn invalid(&self, name: &str) -> OnnxError {
+        OnnxError::Invalid(format!(
+            "attribute `{name}` of {} has the wrong type",
+            self.0.op_type
+        ))
+    }
+
+    fn invalid_value(&self, name: &str) -> OnnxError {
+        OnnxError::Invalid(format!(
+            "attribute `{name}` of {} has an invalid value",
+            self.0.op_type
+        ))
+    }
+
+    fn int(&self, name: &str, default: i64) -> Result<i64, OnnxError> {
+        match self.get(name) {
+            None => Ok(default),
+            Some(AttributeValue::Int(i)) => Ok(*i),
+            Some(_) => Err(self.invalid(name)),
+        }
+    }
+
+    fn float(&self, name: &str, default: f32) -> Result<f32, OnnxError> {
+        match self.get(name) {
+            None => Ok(default),
+            Some(AttributeValue::Float(f)) => Ok(*f),
+            Some(_) => Err(self.invalid(name)),
+        }
+    }
+
+    fn string(&self, name: &str, default: &str) -> Result<String, OnnxError> {
+        match self.get(name) {
+            None => Ok(default.into()),
+            Some(AttributeValue::String(s)) => Ok(s.clone()),
+            Some(_) => Err(self.invalid(name)),
+        }
+    }
+
+    /// Returns an ints attribute, accepting a single int as well.
+    fn ints(&self, name: &str) -> Result<Option<Vec<i64>>, OnnxError> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(AttributeValue::Ints(i)) => Ok(Some(i.clone())),
+            Some(AttributeValue::Int(i)) => Ok(Some(vec![*i])),
+            Some(_) => Err(self.invalid(name)),
+        }
+    }
+
+    /// The value of a `Constant` node.
+    fn constant(&self) -> Result<OnnxArray, OnnxError> {
+        let attr = self
+            .0
+            .attributes
+            .first()
+            .ok_or_else(|| self.invalid("value"))?;
+        match (attr.name.as_str(), &attr.value) {
+            ("value", AttributeValue::Tensor(t)) => OnnxArray::from_tensor(t),
+            ("value_float", AttributeValue::Float(f)) => Ok(OnnxArray::new(vec![], vec![*f])),
+            ("value_int", AttributeValue::Int(i)) => Ok(OnnxArray::new(vec![], vec![*i as f32])),
+            ("value_floats", AttributeValue::Floats(fs)) => {
+                Ok(OnnxArray::new(vec![fs.len()], fs.clone()))
+            }
+            ("value_ints", AttributeValue::Ints(is)) => Ok(OnnxArray::new(
+                vec![is.len()],
+                is.iter().map(|&i| i as f32).collect(),
+            )),
+            (name, _) => Err(OnnxError::UnsupportedOp(format!(
+                "Constant with attribute {name}"
+            ))),
+        }
+    }
+}
+
+fn input<'a>(inputs: &[Option<&'a OnnxArray>], i: usize) -> Result<&'a OnnxArray, OnnxError> {
+    inputs
+        .get(i)
+        .copied()
+        .flatten()
+        .ok_or_else(|| OnnxError::Invalid(format!("missing input {i}")))
+}
+
+fn optional_input<'a>(inputs: &[Option<&'a OnnxArray>], i: usize) -> Option<&'a OnnxArray> {
+    inputs.get(i).copied().flatten()
+}
+
+/// Converts a possibly negative `axis` into an index into a shape of length `rank`.
+fn normalize_axis(axis: i64, rank: usize) -> Result<usize, OnnxError> {
+    let a = if axis < 0 { axis + rank as i64 } else { axis };
+    if a < 0 || a >= rank as i64 {
+        return Err(OnnxError::Invalid(format!(
+            "axis {axis} is out of range for rank {rank}"
+        )));
+    }
+    Ok(a as usize)
+}
+
+fn numel(shape: &[usize]) -> usize {
+    shape.iter().product()
+}
+
+/// Converts an int tensor (that is stored as `f32`) to `i64`s.
+fn to_ints(x: &OnnxArray) -> Vec<i64> {
+    x.data.iter().map(|&v| v as i64).collect()
+}
+
+/// The strides of `shape` when broadcast to `out`, with `0` for broadcasted dimensions.
+fn broadcast_strides(shape: &[usize], out: &[usize]) -> Vec<usize> {
+    let mut strides = vec![0; out.len()];
+    let mut stride = 1;
+    for (i, &d) in shape.iter().enumerate().rev() {
+        let j = out.len() - shape.len() + i;
+        if d != 1 {
+            strides[j] = stride;
+        }
+        stride *= d;
+    }
+    strides
+}
+
+/// The multidirectional (numpy style) broadcast of two shapes.
+fn broadcast_shapes(a: &[usize], b: &[usize]) -> Result<Vec<usize>, OnnxError> {
+    let rank = a.len().max(b.len());
+    let mut out = vec![0; rank];
+    for (i, o) in out.iter_mut().enumerate() {
+        let da = if i + a.len() >= rank {
+            a[i + a.len() - rank]
+        } else {
+            1
+        };
+        let db = if i + b.len() >= rank {
+            b[i + b.len() - rank]
+        } else {
+            1
+        };
+        *o = match (da, db) {
+            (x, y) if x == y => x,
+            (1, y) => y,
+            (x, 1) => x,
+            _ => {
+                return Err(OnnxError::Invalid(format!(
+                    "can't broadcast shapes {a:?} and {b:?}"
+                )))
+            }
+        };
+    }
+    Ok(out)
+}
+
+/// Calls `f(i, j)` for each element of `out` in row major order, where `i` & `j`
+/// are the flat indices into arrays with strides `sa` & `sb`.
+fn for_each_broadcast<F: FnMut(usize, usize)>(out: &[usize], sa: &[usize], sb: &[usize], mut f: F) {
+    let mut idx = vec![0; out.len()];
+    let (mut i, mut j) = (0, 0);
+    for _ in 0..numel(out) {
+        f(i, j);
+        for d in (0..out.len()).rev() {
+            idx[d] += 1;
+            i += sa[d];
+            j += sb[d];
+            if idx[d] < out[d] {
+                break;
+            }
+            i -= sa[d] * out[d];
+            j -= sb[d] * out[d];
+            idx[d] = 0;
+        }
+    }
+}
+
+struct Unary<F>(F);
+
+fn unary<F: Fn(f32) -> f32 + 'static>(f: F) -> Box<dyn Op> {
+    Box::new(Unary(f))
+}
+
+impl<F: Fn(f32) -> f32> Op for Unary<F> {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        Ok(OnnxArray {
+            shape: x.shape.clone(),
+            data: x.data.iter().map(|&v| (self.0)(v)).collect(),
+        })
+    }
+}
+
+struct Binary<F>(F);
+
+fn binary<F: Fn(f32, f32) -> f32 + 'static>(f: F) -> Box<dyn Op> {
+    Box::new(Binary(f))
+}
+
+impl<F: Fn(f32, f32) -> f32> Op for Binary<F> {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let (a, b) = (input(inputs, 0)?, input(inputs, 1)?);
+        if a.shape == b.shape {
+            let data = a.data.iter().zip(b.data.iter());
+            return Ok(OnnxArray {
+                shape: a.shape.clone(),
+                data: data.map(|(&x, &y)| (self.0)(x, y)).collect(),
+            });
+        }
+        let shape = broadcast_shapes(&a.shape, &b.shape)?;
+        let sa = broadcast_strides(&a.shape, &shape);
+        let sb = broadcast_strides(&b.shape, &shape);
+        let mut data = Vec::with_capacity(numel(&shape));
+        for_each_broadcast(&shape, &sa, &sb, |i, j| {
+            data.push((self.0)(a.data[i], b.data[j]))
+        });
+        Ok(OnnxArray { shape, data })
+    }
+}
+
+/// `Clip` from opset 11, where `min` and `max` are optional inputs.
+struct Clip;
+
+impl Op for Clip {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let min = optional_input(inputs, 1).map_or(f32::NEG_INFINITY, |m| m.data[0]);
+        let max = optional_input(inputs, 2).map_or(f32::INFINITY, |m| m.data[0]);
+        Ok(OnnxArray {
+            shape: x.shape.clone(),
+            data: x.data.iter().map(|v| v.clamp(min, max)).collect(),
+        })
+    }
+}
+
+/// Computes `a * b` where `a` is `(m, k)` and `b` is `(k, n)`, either of which can
+/// be stored transposed. The result is added to `out`.
+#[allow(clippy::too_many_arguments)]
+fn matmul_into(
+    a: &[f32],
+    b: &[f32],
+    out: &mut [f32],
+    (m, k, n): (usize, usize, usize),
+    trans_a: bool,
+    trans_b: bool,
+    alpha: f32,
+) {
+    for i in 0..m {
+        for p in 0..k {
+            let a_ip = if trans_a { a[p * m + i] } else { a[i * k + p] } * alpha;
+            for j in 0..n {
+                let b_pj = if trans_b { b[j * k + p] } else { b[p * n + j] };
+                out[i * n + j] += a_ip * b_pj;
+            }
+        }
+    }
+}
+
+/// Batched matrix multiplication with numpy semantics.
+struct MatMul;
+
+impl Op for MatMul {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let (a, b) = (input(inputs, 0)?, input(inputs, 1)?);
+        if a.shape.is_empty() || b.shape.is_empty() {
+            return Err(OnnxError::Invalid("MatMul inputs can't be scalars".into()));
+        }
+        // 1d inputs are treated as a row or column vector
+        let mut sa = a.shape.clone();
+        let mut sb = b.shape.clone();
+        if a.shape.len() == 1 {
+            sa.insert(0, 1);
+        }
+        if b.shape.len() == 1 {
+            sb.push(1);
+        }
+        let (m, k) = (sa[sa.len() - 2], sa[sa.len() - 1]);
+        let (k2, n) = (sb[sb.len() - 2], sb[sb.len() - 1]);
+        if k != k2 {
+            return Err(OnnxError::Invalid(format!(
+                "can't MatMul shapes {:?} and {:?}",
+                a.shape, b.shape
+            )));
+        }
+
+        let (batch_a, batch_b) = (&sa[..sa.len() - 2], &sb[..sb.len() - 2]);
+        let batch = broadcast_shapes(batch_a, batch_b)?;
+        let stride_a = broadcast_strides(batch_a, &batch);
+        let stride_b = broadcast_strides(batch_b, &batch);
+        let mut data = vec![0.0; numel(&batch) * m * n];
+        let mut out = data.chunks_mut(m * n);
+        for_each_broadcast(&batch, &stride_a, &stride_b, |i, j| {
+            let a = &a.data[i * m * k..(i + 1) * m * k];
+            let b = &b.data[j * k * n..(j + 1) * k * n];
+            matmul_into(a, b, out.next().unwrap(), (m, k, n), false, false, 1.0);
+        });
+
+        let mut shape = batch;
+        if a.shape.len() > 1 {
+            shape.push(m);
+        }
+        if b.shape.len() > 1 {
+            shape.push(n);
+        }
+        Ok(OnnxArray { shape, data })
+    }
+}
+
+/// `alpha * A * B + beta * C` for 2d `A` and `B`.
+struct Gemm {
+    alpha: f32,
+    beta: f32,
+    trans_a: bool,
+    trans_b: bool,
+}
+
+impl Op for Gemm {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let (a, b) = (input(inputs, 0)?, input(inputs, 1)?);
+        if a.shape.len() != 2 || b.shape.len() != 2 {
+            return Err(OnnxError::Invalid("Gemm inputs must be 2d".into()));
+        }
+        let (m, k) = match self.trans_a {
+            false => (a.shape[0], a.shape[1]),
+            true => (a.shape[1], a.shape[0]),
+        };
+        let (k2, n) = match self.trans_b {
+            false => (b.shape[0], b.shape[1]),
+            true => (b.shape[1], b.shape[0]),
+        };
+        if k != k2 {
+            return Err(OnnxError::Invalid(format!(
+                "can't Gemm shapes {:?} and {:?}",
+                a.shape, b.shape
+            )));
+        }
+
+        let shape = vec![m, n];
+        let mut data = vec![0.0; m * n];
+        if let Some(c) = optional_input(inputs, 2) {
+            if broadcast_shapes(&c.shape, &shape)? != shape {
+                return Err(OnnxError::Invalid(format!(
+                    "can't broadcast Gemm bias {:?} to {shape:?}",
+                    c.shape
+                )));
+            }
+            let sc = broadcast_strides(&c.shape, &shape);
+            let mut out = data.iter_mut();
+            for_each_broadcast(&shape, &sc, &sc, |i, _| {
+                *out.next().unwrap() = self.beta * c.data[i]
+            });
+        }
+        let (trans_a, trans_b) = (self.trans_a, self.trans_b);
+        matmul_into(
+            &a.data,
+            &b.data,
+            &mut data,
+            (m, k, n),
+            trans_a,
+            trans_b,
+            self.alpha,
+        );
+        Ok(OnnxArray { shape, data })
+    }
+}
+
+/// `Softmax` & `LogSoftmax`. Before opset 13, the input is coerced to 2d at `axis`,
+/// so the softmax is over all of the dimensions from `axis` onwards.
+struct Softmax {
+    axis: i64,
+    coerce_2d: bool,
+    log: bool,
+}
+
+impl Op for Softmax {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let axis = normalize_axis(self.axis, x.shape.len())?;
+        let outer = numel(&x.shape[..axis]);
+        let (len, inner) = match self.coerce_2d {
+            true => (numel(&x.shape[axis..]), 1),
+            false => (x.shape[axis], numel(&x.shape[axis + 1..])),
+        };
+        let mut data = x.data.clone();
+        for o in 0..outer {
+            for i in 0..inner {
+                let idx = |j: usize| (o * len + j) * inner + i;
+                let max = (0..len)
+                    .map(|j| data[idx(j)])
+                    .fold(f32::NEG_INFINITY, f32::max);
+                let sum: f32 = (0..len).map(|j| (data[idx(j)] - max).exp()).sum();
+                for j in 0..len {
+                    let v = &mut data[idx(j)];
+                    *v = match self.log {
+                        true => *v - max - sum.ln(),
+                        false => (*v - max).exp() / sum,
+                    };
+                }
+            }
+        }
+        Ok(OnnxArray {
+            shape: x.shape.clone(),
+            data,
+        })
+    }
+}
+
+/// Normalizes over all the dimensions from `axis` onwards.
+struct LayerNorm {
+    axis: i64,
+    epsilon: f32,
+}
+
+impl Op for LayerNorm {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let scale = input(inputs, 1)?;
+        let bias = optional_input(inputs, 2);
+        let axis = normalize_axis(self.axis, x.shape.len())?;
+        let n = numel(&x.shape[axis..]);
+        if scale.data.len() != n || bias.is_some_and(|b| b.data.len() != n) {
+            return Err(OnnxError::Invalid(
+                "LayerNormalization scale & bias must match the normalized shape".into(),
+            ));
+        }
+        let mut data = Vec::with_capacity(x.data.len());
+        for row in x.data.chunks(n) {
+            let mean = row.iter().sum::<f32>() / n as f32;
+            let var = row.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n as f32;
+            let std = (var + self.epsilon).sqrt();
+            for (j, v) in row.iter().enumerate() {
+                let b = bias.map_or(0.0, |b| b.data[j]);
+                data.push((v - mean) / std * scale.data[j] + b);
+            }
+        }
+        Ok(OnnxArray {
+            shape: x.shape.clone(),
+            data,
+        })
+    }
+}
+
+/// Inference mode batch norm, using the running mean & variance. Channels are dimension 1.
+struct BatchNorm {
+    epsilon: f32,
+}
+
+impl Op for BatchNorm {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let params = [1, 2, 3, 4].map(|i| input(inputs, i));
+        let [scale, bias, mean, var] = params;
+        let (scale, bias, mean, var) = (scale?, bias?, mean?, var?);
+        if x.shape.len() < 2 {
+            return Err(OnnxError::Invalid(
+                "BatchNormalization input must be at least 2d".into(),
+            ));
+        }
+        let c = x.shape[1];
+        if [scale, bias, mean, var].iter().any(|p| p.data.len() != c) {
+            return Err(OnnxError::Invalid(
+                "BatchNormalization parameters must have one value per channel".into(),
+            ));
+        }
+        let inner = numel(&x.shape[2..]);
+        let mut data = x.data.clone();
+        for (i, v) in data.iter_mut().enumerate() {
+            let ch = (i / inner) % c;
+            let std = (var.data[ch] + self.epsilon).sqrt();
+            *v = (*v - mean.data[ch]) / std * scale.data[ch] + bias.data[ch];
+        }
+        Ok(OnnxArray {
+            shape: x.shape.clone(),
+            data,
+        })
+    }
+}
+
+/// The sliding window of `Conv` & pooling over 1d or 2d inputs. 1d windows are stored as 2d windows with height 1.
+struct Window {
+    kernel: Option<[usize; 2]>,
+    strides: [usize; 2],
+    dilations: [usize; 2],
+    /// `[top, left, bottom, right]`
+    pads: [usize; 4],
+    auto_pad: String,
+    ceil_mode: bool,
+    spatial_dims: Option<usize>,
+}
+
+impl Window {
+    fn new(attrs: &Attributes, kernel: Option<Vec<i64>>) -> Result<Self, OnnxError> {
+        let spatial_dims = kernel.as_ref().map(|k| k.len());
+        if let Some(d) = spatial_dims {
+            if d != 1 && d != 2 {
+                return Err(OnnxError::UnsupportedOp(format!(
+                    "{} with {d} spatial dimensions",
+                    attrs.0.op_type
+                )));
+            }
+        }
+        // kernel sizes, strides & dilations have one positive value per spatial dimension
+        let to_2d = |name: &str, v: Vec<i64>| -> Result<[usize; 2], OnnxError> {
+            let v: Option<Vec<usize>> = v
+                .into_iter()
+                .map(|a| usize::try_from(a).ok().filter(|&a| a > 0))
+                .collect();
+            match (v.as_deref(), spatial_dims) {
+                (Some(&[a]), None | Some(1)) => Ok([1, a]),
+                (Some(&[a, b]), None | Some(2)) => Ok([a, b]),
+                _ => Err(attrs.invalid_value(name)),
+            }
+        };
+        let pads: Option<Vec<usize>> = match attrs.ints("pads")? {
+            None => Some(vec![0; 4]),
+            Some(p) => p.into_iter().map(|a| usize::try_from(a).ok()).collect(),
+        };
+        let pads = match (pads.as_deref(), spatial_dims) {
+            (Some(&[b, e]), None | Some(1)) => [0, b, 0, e],
+            (Some(&[t, l, b, r]), None | Some(2)) => [t, l, b, r],
+            _ => return Err(attrs.invalid_value("pads")),
+        };
+        let ints_2d = |name: &str| match attrs.ints(name)? {
+            None => Ok([1, 1]),
+            Some(v) => to_2d(name, v),
+        };
+        Ok(Self {
+            kernel: kernel.map(|k| to_2d("kernel_shape", k)).transpose()?,
+            strides: ints_2d("strides")?,
+            dilations: ints_2d("dilations")?,
+            pads,
+            auto_pad: attrs.string("auto_pad", "NOTSET")?,
+            ceil_mode: attrs.int("ceil_mode", 0)? != 0,
+            spatial_dims,
+        })
+    }
+
+    /// Returns the `(n, c, [h, w])` of `x`, where 1d inputs have `h = 1`.
+    fn input_dims(&self, x: &OnnxArray) -> Result<(usize, usize, [usize; 2]), OnnxError> {
+        match (x.shape.len(), self.spatial_dims) {
+            (3, None | Some(1)) => Ok((x.shape[0], x.shape[1], [1, x.shape[2]])),
+            (4, None | Some(2)) => Ok((x.shape[0], x.shape[1], [x.shape[2], x.shape[3]])),
+            _ => Err(OnnxError::Invalid(format!(
+                "expected a 1d or 2d image, found shape {:?}",
+                x.shape
+            ))),
+        }
+    }
+
+    /// Returns the `[top, left]` padding and the output size for an input of size `input` and a kernel of size `kernel`.
+    fn output_size(
+        &self,
+        input: [usize; 2],
+        kernel: [usize; 2],
+    ) -> Result<([usize; 2], [usize; 2]), OnnxError> {
+        let mut begin = [0; 2];
+        let mut out = [0; 2];
+        for d in 0..2 {
+            let (i, s) = (input[d], self.strides[d]);
+            if kernel[d] == 0 {
+                return Err(OnnxError::Invalid(format!("empty kernel {kernel:?}")));
+            }
+            let k = (kernel[d] - 1) * self.dilations[d] + 1;
+            match self.auto_pad.as_str() {
+                "SAME_UPPER" | "SAME_LOWER" => {
+                    out[d] = i.div_ceil(s);
+                    let total = ((out[d] - 1) * s + k).saturating_sub(i);
+                    begin[d] = match self.auto_pad.as_str() {
+                        "SAME_UPPER" => total / 2,
+                        _ => total - total / 2,
+                    };
+                }
+                "VALID" | "NOTSET" => {
+                    let (pb, pe) = match self.auto_pad.as_str() {
+                        "VALID" => (0, 0),
+                        _ => (self.pads[d], self.pads[d + 2]),
+                    };
+                    if i + pb + pe < k {
+                        return Err(OnnxError::Invalid(format!(
+                            "kernel {kernel:?} is larger than the padded input {input:?}"
+                        )));
+                    }
+                    let span = i + pb + pe - k;
+                    out[d] = match self.ceil_mode {
+                        false => span / s + 1,
+                        true => {
+                            // the last window must start inside the input or left padding
+                            let o = span.div_ceil(s) + 1;
+                            if (o - 1) * s >= i + pb {
+                                o - 1
+                            } else {
+                                o
+                            }
+                        }
+                    };
+                    begin[d] = pb;
+                }
+                p => return Err(OnnxError::UnsupportedOp(format!("auto_pad {p}"))),
+            }
+        }
+        Ok((begin, out))
+    }
+
+    /// The output shape for input `x`, which is 3d for 1d inputs.
+    fn output_shape(&self, x: &OnnxArray, c: usize, out: [usize; 2]) -> Vec<usize> {
+        match x.shape.len() {
+            3 => vec![x.shape[0], c, out[1]],
+            _ => vec![x.shape[0], c, out[0], out[1]],
+        }
+    }
+}
+
+struct Conv {
+    window: Window,
+    group: usize,
+}
+
+impl Op for Conv {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let w = input(inputs, 1)?;
+        let bias = optional_input(inputs, 2);
+        let (batch, chan, [h, wd]) = self.window.input_dims(x)?;
+        let kernel = match w.shape.len() {
+            3 => [1, w.shape[2]],
+            4 => [w.shape[2], w.shape[3]],
+            _ => return Err(OnnxError::Invalid("Conv weight must be 3d or 4d".into())),
+        };
+        let (m, cg, g) = (w.shape[0], w.shape[1], self.group);
+        if g == 0 || chan != cg * g || m % g != 0 || self.window.kernel.is_some_and(|k| k != kernel)
+        {
+            return Err(OnnxError::Invalid(format!(
+                "Conv weight {:?} doesn't match input {:?}",
+                w.shape, x.shape
+            )));
+        }
+        let mg = m / g;
+        let ([pt, pl], [oh, ow]) = self.window.output_size([h, wd], kernel)?;
+        let ([sh, sw], [dh, dw]) = (self.window.strides, self.window.dilations);
+
+        let mut data = vec![0.0; batch * m * oh * ow];
+        for (idx, out) in data.iter_mut().enumerate() {
+            let (ox, oy) = (idx % ow, (idx / ow) % oh);
+            let (oc, b) = ((idx / (ow * oh)) % m, idx / (ow * oh * m));
+            let mut sum = bias.map_or(0.0, |b| b.data[oc]);
+            for c in 0..cg {
+                let ic = (oc / mg) * cg + c;
+                for ky in 0..kernel[0] {
+                    let iy = (oy * sh + ky * dh) as isize - pt as isize;
+                    if iy < 0 || iy >= h as isize {
+                        continue;
+                    }
+                    for kx in 0..kernel[1] {
+                        let ix = (ox * sw + kx * dw) as isize - pl as isize;
+                        if ix < 0 || ix >= wd as isize {
+                            continue;
+                        }
+                        let xi = ((b * chan + ic) * h + iy as usize) * wd + ix as usize;
+                        let wi = ((oc * cg + c) * kernel[0] + ky) * kernel[1] + kx;
+                        sum += x.data[xi] * w.data[wi];
+                    }
+                }
+            }
+            *out = sum;
+        }
+        Ok(OnnxArray {
+            shape: self.window.output_shape(x, m, [oh, ow]),
+            data,
+        })
+    }
+}
+
+/// `MaxPool` & `AveragePool`.
+struct Pool {
+    window: Window,
+    max: bool,
+    count_include_pad: bool,
+}
+
+impl Op for Pool {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let (batch, chan, [h, w]) = self.window.input_dims(x)?;
+        let kernel = self.window.kernel.unwrap();
+        let ([pt, pl], [oh, ow]) = self.window.output_size([h, w], kernel)?;
+        let ([sh, sw], [dh, dw]) = (self.window.strides, self.window.dilations);
+        let padded = [
+            h + self.window.pads[0] + self.window.pads[2],
+            w + self.window.pads[1] + self.window.pads[3],
+        ];
+
+        let mut data = vec![0.0; batch * chan * oh * ow];
+        for (idx, out) in data.iter_mut().enumerate() {
+            let (ox, oy, bc) = (idx % ow, (idx / ow) % oh, idx / (ow * oh));
+            let mut acc = if self.max { f32::NEG_INFINITY } else { 0.0 };
+            let mut count = 0;
+            for ky in 0..kernel[0] {
+                let py = oy * sh + ky * dh;
+                for kx in 0..kernel[1] {
+                    let px = ox * sw + kx * dw;
+                    let (iy, ix) = (py as isize - pt as isize, px as isize - pl as isize);
+                    if iy < 0 || iy >= h as isize || ix < 0 || ix >= w as isize {
+                        // padding counts towards the average only if it's explicit padding
+                        if self.count_include_pad && py < padded[0] && px < padded[1] {
+                            count += 1;
+                        }
+                        continue;
+                    }
+                    let v = x.data[(bc * h + iy as usize) * w + ix as usize];
+                    acc = if self.max { acc.max(v) } else { acc + v };
+                    count += 1;
+                }
+            }
+            *out = if self.max {
+                acc
+            } else {
+                acc / count.max(1) as f32
+            };
+        }
+        Ok(OnnxArray {
+            shape: self.window.output_shape(x, chan, [oh, ow]),
+            data,
+        })
+    }
+}
+
+/// Pools over all the spatial dimensions (2 onwards), keeping them as size 1.
+struct GlobalPool {
+    max: bool,
+}
+
+impl Op for GlobalPool {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        if x.shape.len() < 3 {
+            return Err(OnnxError::Invalid(
+                "global pooling input must be at least 3d".into(),
+            ));
+        }
+        let n = numel(&x.shape[2..]);
+        let data = x
+            .data
+            .chunks(n)
+            .map(|c| match self.max {
+                true => c.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                false => c.iter().sum::<f32>() / n as f32,
+            })
+            .collect();
+        let mut shape = x.shape.clone();
+        shape[2..].fill(1);
+        Ok(OnnxArray { shape, data })
+    }
+}
+
+struct Flatten {
+    axis: i64,
+}
+
+impl Op for Flatten {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        // NOTE: axis can be equal to the rank here
+        let axis = normalize_axis(self.axis, x.shape.len() + 1)?;
+        let axis = if self.axis < 0 { axis - 1 } else { axis };
+        Ok(OnnxArray {
+            shape: vec![numel(&x.shape[..axis]), numel(&x.shape[axis..])],
+            data: x.data.clone(),
+        })
+    }
+}
+
+struct Reshape {
+    allow_zero: bool,
+}
+
+impl Op for Reshape {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let target = to_ints(input(inputs, 1)?);
+        let mut shape = Vec::with_capacity(target.len());
+        let mut infer = None;
+        for (i, &d) in target.iter().enumerate() {
+            match d {
+                -1 => {
+                    infer = Some(i);
+                    shape.push(1);
+                }
+                0 if !self.allow_zero => shape.push(*x.shape.get(i).ok_or_else(|| {
+                    OnnxError::Invalid(format!("can't copy dimension {i} of {:?}", x.shape))
+                })?),
+                d if d >= 0 => shape.push(d as usize),
+                _ => {
+                    return Err(OnnxError::Invalid(format!(
+                        "invalid Reshape shape {target:?}"
+                    )))
+                }
+            }
+        }
+        let known = numel(&shape);
+        if let Some(i) = infer {
+            if known == 0 {
+                return Err(OnnxError::Invalid(format!(
+                    "invalid Reshape shape {target:?}"
+                )));
+            }
+            shape[i] = x.data.len() / known;
+        }
+        if numel(&shape) != x.data.len() {
+            return Err(OnnxError::Invalid(format!(
+                "can't reshape {:?} to {target:?}",
+                x.shape
+            )));
+        }
+        Ok(OnnxArray {
+            shape,
+            data: x.data.clone(),
+        })
+    }
+}
+
+struct Transpose {
+    perm: Option<Vec<i64>>,
+}
+
+impl Op for Transpose {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let rank = x.shape.len();
+        let perm: Vec<usize> = match &self.perm {
+            Some(p) => p
+                .iter()
+                .map(|&a| normalize_axis(a, rank))
+                .collect::<Result<_, _>>()?,
+            None => (0..rank).rev().collect(),
+        };
+        if perm.len() != rank || (0..rank).any(|a| !perm.contains(&a)) {
+            return Err(OnnxError::Invalid(format!(
+                "invalid Transpose perm {perm:?}"
+            )));
+        }
+        let shape: Vec<usize> = perm.iter().map(|&p| x.shape[p]).collect();
+        let in_strides = broadcast_strides(&x.shape, &x.shape);
+        let strides: Vec<usize> = perm.iter().map(|&p| in_strides[p]).collect();
+        let mut data = Vec::with_capacity(x.data.len());
+        for_each_broadcast(&shape, &strides, &strides, |i, _| data.push(x.data[i]));
+        Ok(OnnxArray { shape, data })
+    }
+}
+
+/// The axes of `Squeeze` & `Unsqueeze`, which are an input from opset 13 and an attribute before that.
+fn axes(attr: &Option<Vec<i64>>, inputs: &[Option<&OnnxArray>]) -> Option<Vec<i64>> {
+    optional_input(inputs, 1)
+        .map(to_ints)
+        .or_else(|| attr.clone())
+}
+
+struct Squeeze {
+    axes: Option<Vec<i64>>,
+}
+
+impl Op for Squeeze {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let rank = x.shape.len();
+        let axes: Vec<usize> = match axes(&self.axes, inputs) {
+            Some(a) => a
+                .iter()
+                .map(|&a| normalize_axis(a, rank))
+                .collect::<Result<_, _>>()?,
+            None => (0..rank).filter(|&i| x.shape[i] == 1).collect(),
+        };
+        if axes.iter().any(|&a| x.shape[a] != 1) {
+            return Err(OnnxError::Invalid(format!(
+                "can't squeeze axes {axes:?} of {:?}",
+                x.shape
+            )));
+        }
+        let shape = (0..rank)
+            .filter(|i| !axes.contains(i))
+            .map(|i| x.shape[i])
+            .collect();
+        Ok(OnnxArray {
+            shape,
+            data: x.data.clone(),
+        })
+    }
+}
+
+struct Unsqueeze {
+    axes: Option<Vec<i64>>,
+}
+
+impl Op for Unsqueeze {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let axes = axes(&self.axes, inputs)
+            .ok_or_else(|| OnnxError::Invalid("Unsqueeze requires axes".into()))?;
+        let rank = x.shape.len() + axes.len();
+        let mut axes: Vec<usize> = axes
+            .iter()
+            .map(|&a| normalize_axis(a, rank))
+            .collect::<Result<_, _>>()?;
+        axes.sort_unstable();
+        let mut shape = x.shape.clone();
+        for a in axes {
+            shape.insert(a, 1);
+        }
+        Ok(OnnxArray {
+            shape,
+            data: x.data.clone(),
+        })
+    }
+}
+
+struct Concat {
+    axis: i64,
+}
+
+impl Op for Concat {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let xs: Vec<&OnnxArray> = inputs.iter().flatten().copied().collect();
+        let first = xs
+            .first()
+            .ok_or_else(|| OnnxError::Invalid("Concat requires inputs".into()))?;
+        let axis = normalize_axis(self.axis, first.shape.len())?;
+        let mut shape = first.shape.clone();
+        shape[axis] = 0;
+        for x in xs.iter() {
+            let same = x.shape.len() == shape.len()
+                && (0..shape.len()).all(|d| d == axis || x.shape[d] == shape[d]);
+            if !same {
+                return Err(OnnxError::Invalid(format!(
+                    "can't concat {:?} and {:?}",
+                    first.shape, x.shape
+                )));
+            }
+            shape[axis] += x.shape[axis];
+        }
+        let outer = numel(&shape[..axis]);
+        let mut data = Vec::with_capacity(numel(&shape));
+        for o in 0..outer {
+            for x in xs.iter() {
+                let n = numel(&x.shape[axis..]);
+                data.extend_from_slice(&x.data[o * n..(o + 1) * n]);
+            }
+        }
+        Ok(OnnxArray { shape, data })
+    }
+}
+
+struct Shape {
+    start: i64,
+    end: Option<i64>,
+}
+
+impl Op for Shape {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let rank = x.shape.len() as i64;
+        let clamp = |v: i64| (if v < 0 { v + rank } else { v }).clamp(0, rank) as usize;
+        let (start, end) = (clamp(self.start), clamp(self.end.unwrap_or(rank)));
+        let dims: Vec<f32> = x.shape[start..end.max(start)]
+            .iter()
+            .map(|&d| d as f32)
+            .collect();
+        Ok(OnnxArray::new(vec![dims.len()], dims))
+    }
+}
+
+struct Gather {
+    axis: i64,
+}
+
+impl Op for Gather {
+    fn run(&self, inputs: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        let x = input(inputs, 0)?;
+        let indices = input(inputs, 1)?;
+        let axis = normalize_axis(self.axis, x.shape.len())?;
+        let len = x.shape[axis];
+        let inner = numel(&x.shape[axis + 1..]);
+        let mut shape = x.shape[..axis].to_vec();
+        shape.extend_from_slice(&indices.shape);
+        shape.extend_from_slice(&x.shape[axis + 1..]);
+
+        let mut data = Vec::with_capacity(numel(&shape));
+        for o in 0..numel(&x.shape[..axis]) {
+            for &i in to_ints(indices).iter() {
+                let i = if i < 0 { i + len as i64 } else { i };
+                if i < 0 || i >= len as i64 {
+                    return Err(OnnxError::Invalid(format!(
+                        "Gather index {i} is out of range"
+                    )));
+                }
+                let start = (o * len + i as usize) * inner;
+                data.extend_from_slice(&x.data[start..start + inner]);
+            }
+        }
+        Ok(OnnxArray { shape, data })
+    }
+}
+
+struct Constant(OnnxArray);
+
+impl Op for Constant {
+    fn run(&self, _: &[Option<&OnnxArray>]) -> Result<OnnxArray, OnnxError> {
+        Ok(self.0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn::OnnxAttribute;
+    use crate::tests::assert_close;
+
+    fn try_run_op(
+        op_type: &str,
+        attributes: Vec<OnnxAttribute>,
+        inputs: &[&OnnxArray],
+    ) -> Result<OnnxArray, OnnxError> {
+        let node = OnnxNode {
+            name: "test".into(),
+            op_type: op_type.into(),
+            inputs: vec![],
+            outputs: vec![],
+            attributes,
+        };
+        let inputs: Vec<Option<&OnnxArray>> = inputs.iter().map(|&x| Some(x)).collect();
+        build_op(&node, 17)?.run(&inputs)
+    }
+
+    fn run_op(op_type: &str, attributes: Vec<OnnxAttribute>, inputs: &[&OnnxArray]) -> OnnxArray {
+        try_run_op(op_type, attributes, inputs).expect("")
+    }
+
+    fn arange(shape: Vec<usize>) -> OnnxArray {
+        let n = numel(&shape);
+        OnnxArray::new(shape, (0..n).map(|v| v as f32).collect())
+    }
+
+    #[test]
+    fn test_broadcast_add() {
+        let a = arange(vec![2, 1, 3]);
+        let b = OnnxArray::new(vec![2, 1], vec![10.0, 20.0]);
+        let y = run_op("Add", vec![], &[&a, &b]);
+        assert_eq!(&y.shape, &[2, 2, 3]);
+        #[rustfmt::skip]
+        assert_eq!(
+            y.data,
+            [10.0, 11.0, 12.0, 20.0, 21.0, 22.0, 13.0, 14.0, 15.0, 23.0, 24.0, 25.0]
+        );
+    }
+
+    #[test]
+    fn test_matmul_batched() {
+        let a = arange(vec![2, 2, 3]);
+        let b = arange(vec![3, 2]);
+        let y = run_op("MatMul", vec![], &[&a, &b]);
+        assert_eq!(&y.shape, &[2, 2, 2]);
+        assert_eq!(y.data, [10.0, 13.0, 28.0, 40.0, 46.0, 67.0, 64.0, 94.0]);
+
+        let v = OnnxArray::new(vec![3], vec![1.0, 0.0, -1.0]);
+        let y = run_op("MatMul", vec![], &[&a, &v]);
+        assert_eq!(&y.shape, &[2, 2]);
+        assert_eq!(y.data, [-2.0, -2.0, -2.0, -2.0]);
+    }
+
+    #[test]
+    fn test_gemm() {
+        // a is stored transposed, a^T = [[0, 3], [1, 4], [2, 5]]
+        let a = arange(vec![2, 3]);
+        let b = arange(vec![2, 2]);
+        let c = OnnxArray::new(vec![2], vec![1.0, -1.0]);
+        let attrs = vec![
+            OnnxAttribute::int("transA", 1),
+            OnnxAttribute::float("alpha", 2.0),
+            OnnxAttribute::float("beta", 0.5),
+        ];
+        let y = run_op("Gemm", attrs, &[&a, &b, &c]);
+        assert_eq!(&y.shape, &[3, 2]);
+        // a^T * b = [[6, 9], [8, 13], [10, 17]]
+        assert_eq!(y.data, [12.5, 17.5, 16.5, 25.5, 20.5, 33.5]);
+    }
+
+    #[test]
+    fn test_softmax_axis() {
+        let x = OnnxArray::new(vec![2, 2], vec![0.0, 1.0, 2.0, 4.0]);
+        let y = run_op("Softmax", vec![OnnxAttribute::int("axis", 0)], &[&x]);
+        assert_close(&[y.data[0], y.data[2]], &[0.11920292, 0.880797]);
+        assert_close(&[y.data[1], y.data[3]], &[0.047425874, 0.95257413]);
+    }
+
+    #[test]
+    fn test_conv_2d() {
+        let x = arange(vec![1, 1, 3, 3]);
+        let w = OnnxArray::new(
+            vec![2, 1, 2, 2],
+            vec![1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0],
+        );
+        let b = OnnxArray::new(vec![2], vec![0.0, 100.0]);
+        let y = run_op("Conv", vec![], &[&x, &w, &b]);
+        assert_eq!(&y.shape, &[1, 2, 2, 2]);
+        assert_eq!(y.data, [4.0, 6.0, 10.0, 12.0, 104.0, 106.0, 110.0, 112.0]);
+
+        let attrs = vec![
+            OnnxAttribute::ints("pads", vec![1, 1, 1, 1]),
+            OnnxAttribute::ints("strides", vec![2, 2]),
+        ];
+        let y = run_op("Conv", attrs, &[&x, &w]);
+        assert_eq!(&y.shape, &[1, 2, 2, 2]);
+        assert_eq!(y.data, [0.0, 2.0, 6.0, 12.0, 0.0, 1.0, 3.0, 12.0]);
+    }
+
+    #[test]
+    fn test_conv_groups_1d() {
+        let x = arange(vec![1, 2, 4]);
+        let w = OnnxArray::new(vec![2, 1, 2], vec![1.0, 1.0, 1.0, -1.0]);
+        let y = run_op("Conv", vec![OnnxAttribute::int("group", 2)], &[&x, &w]);
+        assert_eq!(&y.shape, &[1, 2, 3]);
+        assert_eq!(y.data, [1.0, 3.0, 5.0, -1.0, -1.0, -1.0]);
+    }
+
+    #[test]
+    fn test_pools() {
+        let x = arange(vec![1, 1, 4, 4]);
+        let attrs = vec![
+            OnnxAttribute::ints("kernel_shape", vec![2, 2]),
+            OnnxAttribute::ints("strides", vec![2, 2]),
+        ];
+        let y = run_op("MaxPool", attrs.clone(), &[&x]);
+        assert_eq!(&y.shape, &[1, 1, 2, 2]);
+        assert_eq!(y.data, [5.0, 7.0, 13.0, 15.0]);
+
+        let y = run_op("AveragePool", attrs, &[&x]);
+        assert_eq!(y.data, [2.5, 4.5, 10.5, 12.5]);
+
+        let attrs = vec![
+            OnnxAttribute::ints("kernel_shape", vec![3, 3]),
+            OnnxAttribute::ints("pads", vec![1, 1, 1, 1]),
+            OnnxAttribute::ints("strides", vec![3, 3]),
+        ];
+        let y = run_op("AveragePool", attrs, &[&x]);
+        assert_eq!(&y.shape, &[1, 1, 2, 2]);
+        assert_eq!(y.data, [2.5, 4.5, 10.5, 12.5]);
+
+        let y = run_op("GlobalAveragePool", vec![], &[&x]);
+        assert_eq!(&y.shape, &[1, 1, 1, 1]);
+        assert_eq!(y.data, [7.5]);
+    }
+
+    #[test]
+    fn test_batch_norm() {
+        let x = arange(vec![1, 2, 2]);
+        let scale = OnnxArray::new(vec![2], vec![1.0, 2.0]);
+        let bias = OnnxArray::new(vec![2], vec![0.0, 1.0]);
+        let mean = OnnxArray::new(vec![2], vec![1.0, 2.0]);
+        let var = OnnxArray::new(vec![2], vec![4.0, 1.0]);
+        let attrs = vec![OnnxAttribute::float("epsilon", 0.0)];
+        let y = run_op(
+            "BatchNormalization",
+            attrs,
+            &[&x, &scale, &bias, &mean, &var],
+        );
+        assert_eq!(y.data, [-0.5, 0.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_shape_ops() {
+        let x = arange(vec![2, 3, 1]);
+        let y = run_op("Transpose", vec![], &[&x]);
+        assert_eq!(&y.shape, &[1, 3, 2]);
+        assert_eq!(y.data, [0.0, 3.0, 1.0, 4.0, 2.0, 5.0]);
+
+        let y = run_op("Squeeze", vec![], &[&x]);
+        assert_eq!(&y.shape, &[2, 3]);
+
+        let axes = OnnxArray::new(vec![2], vec![0.0, -1.0]);
+        let y = run_op("Unsqueeze", vec![], &[&x, &axes]);
+        assert_eq!(&y.shape, &[1, 2, 3, 1, 1]);
+
+        let y = run_op("Flatten", vec![OnnxAttribute::int("axis", -1)], &[&x]);
+        assert_eq!(&y.shape, &[6, 1]);
+
+        let target = OnnxArray::new(vec![2], vec![0.0, -1.0]);
+        let y = run_op("Reshape", vec![], &[&x, &target]);
+        assert_eq!(&y.shape, &[2, 3]);
+
+        let y = run_op("Concat", vec![OnnxAttribute::int("axis", 1)], &[&x, &x]);
+        assert_eq!(&y.shape, &[2, 6, 1]);
+        assert_eq!(
+            y.data,
+            [0.0, 1.0, 2.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 3.0, 4.0, 5.0]
+        );
+
+        // the pattern pytorch uses for `x.view(x.size(0), -1)`
+        let shape = run_op("Shape", vec![], &[&x]);
+        assert_eq!(shape.data, [2.0, 3.0, 1.0]);
+        let index = OnnxArray::new(vec![], vec![0.0]);
+        let batch = run_op("Gather", vec![], &[&shape, &index]);
+        assert_eq!(batch, OnnxArray::new(vec![], vec![2.0]));
+    }
+
+    #[test]
+    fn test_invalid_windows() {
+        let x = arange(vec![1, 1, 4, 4]);
+        let w = arange(vec![1, 1, 2, 2]);
+        let invalid = [
+            OnnxAttribute::ints("strides", vec![]),
+            OnnxAttribute::ints("strides", vec![0, 1]),
+            OnnxAttribute::ints("strides", vec![1, 1, 1]),
+            OnnxAttribute::ints("dilations", vec![-1, 1]),
+            OnnxAttribute::ints("kernel_shape", vec![2, -2]),
+            OnnxAttribute::ints("pads", vec![0, -1, 0, 0]),
+            OnnxAttribute::ints("pads", vec![0, 0, 0]),
+            OnnxAttribute::int("group", -1),
+        ];
+        for attr in invalid {
+            assert!(matches!(
+                try_run_op("Conv", vec![attr.clone()], &[&x, &w]),
+                Err(OnnxError::Invalid(_))
+            ));
+            if attr.name != "group" {
+                // the first attribute with a name is used, so this doesn't override `attr`
+                let kernel = OnnxAttribute::ints("kernel_shape", vec![2, 2]);
+                assert!(matches!(
+                    try_run_op("MaxPool", vec![attr, kernel], &[&x]),
+                    Err(OnnxError::Invalid(_))
+                ));
+            }
+        }
+
+        let empty_kernel = OnnxArray::new(vec![1, 1, 0, 2], vec![]);
+        assert!(matches!(
+            try_run_op("Conv", vec![], &[&x, &empty_kernel]),
+            Err(OnnxError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_transpose() {
+        let x = arange(vec![2, 3]);
+        for perm in [vec![0, 0], vec![1], vec![0, 2]] {
+            assert!(matches!(
+                try_run_op("Transpose", vec![OnnxAttribute::ints("perm", perm)], &[&x]),
+                Err(OnnxError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let node = OnnxNode {
+            name: "test".into(),
+            op_type: "Conv".into(),
+            inputs: vec![],
+            outputs: vec![],
+            attributes: vec![OnnxAttribute::ints("kernel_shape", vec![1, 1, 1])],
+        };
+        assert!(matches!(
+            build_op(&node, 17),
+            Err(OnnxError::UnsupportedOp(_))
+        ));
+    }
+}
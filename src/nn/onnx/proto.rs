@@ -0,0 +1,709 @@
+//! The subset of the [ONNX protobuf messages](https://github.com/onnx/onnx/blob/main/onnx/onnx.proto)
+//! needed to describe inference graphs, along with a minimal protobuf encoder & decoder for them.
+
+use super::OnnxError;
+use crate::numpy::{Endian, NumpyShape, WriteNumbers};
+
+/// The ONNX IR version that models are exported with.
+pub const ONNX_IR_VERSION: i64 = 8;
+
+/// The version of the default ONNX operator set that models are exported with.
+pub const ONNX_OPSET_VERSION: i64 = 17;
+
+/// `TensorProto.DataType.FLOAT`
+pub const ONNX_FLOAT: i32 = 1;
+
+/// `TensorProto.DataType.INT64`
+pub const ONNX_INT64: i32 = 7;
+
+/// An ONNX `ModelProto`. Create one with [super::ExportToOnnx::to_onnx()].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnnxModel {
+    pub ir_version: i64,
+    pub opset_version: i64,
+    pub producer_name: String,
+    pub producer_version: String,
+    pub graph: OnnxGraph,
+}
+
+/// An ONNX `GraphProto`. Nodes are stored in topological order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OnnxGraph {
+    pub name: String,
+    pub nodes: Vec<OnnxNode>,
+    pub initializers: Vec<OnnxTensor>,
+    pub inputs: Vec<OnnxValueInfo>,
+    pub outputs: Vec<OnnxValueInfo>,
+}
+
+/// An ONNX `NodeProto` from the default domain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnnxNode {
+    pub name: String,
+    pub op_type: String,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub attributes: Vec<OnnxAttribute>,
+}
+
+/// An ONNX `AttributeProto`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnnxAttribute {
+    pub name: String,
+    pub value: AttributeValue,
+}
+
+/// The value of an [OnnxAttribute].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Float(f32),
+    Int(i64),
+    String(String),
+    Tensor(OnnxTensor),
+    Floats(Vec<f32>),
+    Ints(Vec<i64>),
+}
+
+/// An ONNX `TensorProto` with its data stored as little endian `raw_data`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnnxTensor {
+    pub name: String,
+    pub dims: Vec<i64>,
+    pub data_type: i32,
+    pub raw_data: Vec<u8>,
+}
+
+/// An ONNX `ValueInfoProto` for a float tensor. When decoding, dimensions that
+/// aren't fixed (i.e. that have a `dim_param`) are `0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnnxValueInfo {
+    pub name: String,
+    pub shape: Vec<usize>,
+}
+
+impl OnnxAttribute {
+    pub fn float(name: &str, value: f32) -> Self {
+        Self {
+            name: name.into(),
+            value: AttributeValue::Float(value),
+        }
+    }
+
+    pub fn int(name: &str, value: i64) -> Self {
+        Self {
+            name: name.into(),
+            value: AttributeValue::Int(value),
+        }
+    }
+
+    pub fn ints(name: &str, value: Vec<i64>) -> Self {
+        Self {
+            name: name.into(),
+            value: AttributeValue::Ints(value),
+        }
+    }
+}
+
+impl OnnxTensor {
+    /// Creates a float tensor named `name` with the shape & data of `data`.
+    pub fn from_array<A: NumpyShape + WriteNumbers>(name: String, data: &A) -> Self {
+        let mut raw_data = Vec::new();
+        data.write_numbers(&mut raw_data, Endian::Little)
+            .expect("writing to a Vec can't fail");
+        Self {
+            name,
+            dims: A::shape().into_iter().map(|d| d as i64).collect(),
+            data_type: ONNX_FLOAT,
+            raw_data,
+        }
+    }
+
+    /// Creates a 1d int64 tensor named `name` containing `data`.
+    pub fn from_i64s(name: String, data: &[i64]) -> Self {
+        Self {
+            name,
+            dims: vec![data.len() as i64],
+            data_type: ONNX_INT64,
+            raw_data: data.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
+}
+
+impl OnnxModel {
+    /// Encodes this model into the protobuf wire format, i.e. the contents of a `.onnx` file.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = ProtoWriter::default();
+        w.int(1, self.ir_version);
+        w.string(2, &self.producer_name);
+        w.string(3, &self.producer_version);
+        w.message(7, |w| self.graph.encode(w));
+        w.message(8, |w| w.int(2, self.opset_version));
+        w.buf
+    }
+}
+
+impl OnnxGraph {
+    fn encode(&self, w: &mut ProtoWriter) {
+        for node in self.nodes.iter() {
+            w.message(1, |w| node.encode(w));
+        }
+        w.string(2, &self.name);
+        for tensor in self.initializers.iter() {
+            w.message(5, |w| tensor.encode(w));
+        }
+        for input in self.inputs.iter() {
+            w.message(11, |w| input.encode(w));
+        }
+        for output in self.outputs.iter() {
+            w.message(12, |w| output.encode(w));
+        }
+    }
+}
+
+impl OnnxNode {
+    fn encode(&self, w: &mut ProtoWriter) {
+        for input in self.inputs.iter() {
+            w.string(1, input);
+        }
+        for output in self.outputs.iter() {
+            w.string(2, output);
+        }
+        w.string(3, &self.name);
+        w.string(4, &self.op_type);
+        for attr in self.attributes.iter() {
+            w.message(5, |w| attr.encode(w));
+        }
+    }
+}
+
+impl OnnxAttribute {
+    fn encode(&self, w: &mut ProtoWriter) {
+        w.string(1, &self.name);
+        // NOTE: the `AttributeType` enum is stored in field 20
+        match &self.value {
+            AttributeValue::Float(f) => {
+                w.float(2, *f);
+                w.int(20, 1);
+            }
+            AttributeValue::Int(i) => {
+                w.int(3, *i);
+                w.int(20, 2);
+            }
+            AttributeValue::String(s) => {
+                w.string(4, s);
+                w.int(20, 3);
+            }
+            AttributeValue::Tensor(t) => {
+                w.message(5, |w| t.encode(w));
+                w.int(20, 4);
+            }
+            AttributeValue::Floats(fs) => {
+                for f in fs.iter() {
+                    w.float(7, *f);
+                }
+                w.int(20, 6);
+            }
+            AttributeValue::Ints(is) => {
+                for i in is.iter() {
+                    w.int(8, *i);
+                }
+                w.int(20, 7);
+            }
+        }
+    }
+}
+
+impl OnnxTensor {
+    fn encode(&self, w: &mut ProtoWriter) {
+        for &d in self.dims.iter() {
+            w.int(1, d);
+        }
+        w.int(2, self.data_type as i64);
+        w.string(8, &self.name);
+        w.bytes(9, &self.raw_data);
+    }
+}
+
+impl OnnxValueInfo {
+    fn encode(&self, w: &mut ProtoWriter) {
+        w.string(1, &self.name);
+        // TypeProto { tensor_type: TypeProto.Tensor { elem_type, shape: TensorShapeProto { dim } } }
+        w.message(2, |w| {
+            w.message(1, |w| {
+                w.int(1, ONNX_FLOAT as i64);
+                w.message(2, |w| {
+                    for &d in self.shape.iter() {
+                        w.message(1, |w| w.int(1, d as i64));
+                    }
+                });
+            });
+        });
+    }
+}
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_LEN: u64 = 2;
+const WIRE_FIXED32: u64 = 5;
+
+/// Writes protobuf fields. Repeated fields are written unpacked, which is what proto2 (used by onnx) expects.
+#[derive(Default)]
+struct ProtoWriter {
+    buf: Vec<u8>,
+}
+
+impl ProtoWriter {
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+    }
+
+    fn key(&mut self, field: u64, wire_type: u64) {
+        self.varint((field << 3) | wire_type);
+    }
+
+    fn int(&mut self, field: u64, v: i64) {
+        self.key(field, WIRE_VARINT);
+        self.varint(v as u64);
+    }
+
+    fn float(&mut self, field: u64, v: f32) {
+        self.key(field, WIRE_FIXED32);
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn bytes(&mut self, field: u64, v: &[u8]) {
+        self.key(field, WIRE_LEN);
+        self.varint(v.len() as u64);
+        self.buf.extend_from_slice(v);
+    }
+
+    fn string(&mut self, field: u64, v: &str) {
+        self.bytes(field, v.as_bytes());
+    }
+
+    fn message<F: FnOnce(&mut Self)>(&mut self, field: u64, f: F) {
+        let mut inner = Self::default();
+        f(&mut inner);
+        self.bytes(field, &inner.buf);
+    }
+}
+
+impl OnnxModel {
+    /// Decodes a model from the protobuf wire format, i.e. the contents of a `.onnx` file.
+    ///
+    /// Fields that aren't needed for inference (e.g. docs & metadata) are skipped. Tensors
+    /// with data stored in external files are not supported.
+    pub fn decode(buf: &[u8]) -> Result<Self, OnnxError> {
+        let mut model = Self {
+            ir_version: 0,
+            opset_version: 0,
+            producer_name: String::new(),
+            producer_version: String::new(),
+            graph: Default::default(),
+        };
+        let mut r = ProtoReader::new(buf);
+        while let Some((field, value)) = r.next()? {
+            match field {
+                1 => model.ir_version = value.int()?,
+                2 => model.producer_name = value.string()?,
+                3 => model.producer_version = value.string()?,
+                7 => model.graph = OnnxGraph::decode(value.bytes()?)?,
+                8 => {
+                    let (mut domain, mut version) = (String::new(), 0);
+                    let mut r = ProtoReader::new(value.bytes()?);
+                    while let Some((field, value)) = r.next()? {
+                        match field {
+                            1 => domain = value.string()?,
+                            2 => version = value.int()?,
+                            _ => {}
+                        }
+                    }
+                    if domain.is_empty() || domain == "ai.onnx" {
+                        model.opset_version = model.opset_version.max(version);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(model)
+    }
+}
+
+impl OnnxGraph {
+    fn decode(buf: &[u8]) -> Result<Self, OnnxError> {
+        let mut graph = Self::default();
+        let mut r = ProtoReader::new(buf);
+        while let Some((field, value)) = r.next()? {
+            match field {
+                1 => graph.nodes.push(OnnxNode::decode(value.bytes()?)?),
+                2 => graph.name = value.string()?,
+                5 => graph.initializers.push(OnnxTensor::decode(value.bytes()?)?),
+                11 => graph.inputs.push(OnnxValueInfo::decode(value.bytes()?)?),
+                12 => graph.outputs.push(OnnxValueInfo::decode(value.bytes()?)?),
+                _ => {}
+            }
+        }
+        Ok(graph)
+    }
+}
+
+impl OnnxNode {
+    fn decode(buf: &[u8]) -> Result<Self, OnnxError> {
+        let mut node = Self {
+            name: String::new(),
+            op_type: String::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            attributes: Vec::new(),
+        };
+        let mut r = ProtoReader::new(buf);
+        while let Some((field, value)) = r.next()? {
+            match field {
+                1 => node.inputs.push(value.string()?),
+                2 => node.outputs.push(value.string()?),
+                3 => node.name = value.string()?,
+                4 => node.op_type = value.string()?,
+                5 => node.attributes.push(OnnxAttribute::decode(value.bytes()?)?),
+                _ => {}
+            }
+        }
+        Ok(node)
+    }
+}
+
+impl OnnxAttribute {
+    fn decode(buf: &[u8]) -> Result<Self, OnnxError> {
+        let mut name = String::new();
+        let mut attr_type = None;
+        let (mut f, mut i, mut s, mut t) = (None, None, None, None);
+        let (mut floats, mut ints) = (Vec::new(), Vec::new());
+        let mut r = ProtoReader::new(buf);
+        while let Some((field, value)) = r.next()? {
+            match field {
+                1 => name = value.string()?,
+                2 => f = Some(value.float()?),
+                3 => i = Some(value.int()?),
+                4 => s = Some(value.string()?),
+                5 => t = Some(OnnxTensor::decode(value.bytes()?)?),
+                7 => value.floats(&mut floats)?,
+                8 => value.ints(&mut ints)?,
+                20 => attr_type = Some(value.int()?),
+                _ => {}
+            }
+        }
+        // NOTE: very old models don't set the type, so fall back to whichever value is present
+        let value = match (attr_type, f, i, s, t) {
+            (Some(1), Some(f), ..) | (None, Some(f), ..) => AttributeValue::Float(f),
+            (Some(2), _, Some(i), ..) | (None, _, Some(i), ..) => AttributeValue::Int(i),
+            (Some(3), _, _, Some(s), _) | (None, _, _, Some(s), _) => AttributeValue::String(s),
+            (Some(4), .., Some(t)) | (None, .., Some(t)) => AttributeValue::Tensor(t),
+            (Some(6), ..) => AttributeValue::Floats(floats),
+            (Some(7), ..) => AttributeValue::Ints(ints),
+            (None, ..) if !floats.is_empty() => AttributeValue::Floats(floats),
+            (None, ..) if !ints.is_empty() => AttributeValue::Ints(ints),
+            (Some(5 | 8..), ..) => return Err(OnnxError::UnsupportedAttribute(name)),
+            _ => {
+                return Err(OnnxError::Decode(format!(
+                    "attribute `{name}` has no value"
+                )))
+            }
+        };
+        Ok(Self { name, value })
+    }
+}
+
+impl OnnxTensor {
+    fn decode(buf: &[u8]) -> Result<Self, OnnxError> {
+        let mut tensor = Self {
+            name: String::new(),
+            dims: Vec::new(),
+            data_type: 0,
+            raw_data: Vec::new(),
+        };
+        let (mut floats, mut int32s, mut int64s, mut doubles) = (vec![], vec![], vec![], vec![]);
+        let mut r = ProtoReader::new(buf);
+        while let Some((field, value)) = r.next()? {
+            match field {
+                1 => value.ints(&mut tensor.dims)?,
+                2 => tensor.data_type = value.int()? as i32,
+                4 => value.floats(&mut floats)?,
+                5 => value.ints(&mut int32s)?,
+                7 => value.ints(&mut int64s)?,
+                8 => tensor.name = value.string()?,
+                9 => tensor.raw_data = value.bytes()?.to_vec(),
+                10 => value.doubles(&mut doubles)?,
+                14 if value.int()? == 1 => {
+                    return Err(OnnxError::Decode(format!(
+                        "tensor `{}` has external data, which is not supported",
+                        tensor.name
+                    )))
+                }
+                _ => {}
+            }
+        }
+        // store the typed data fields as raw data, so there's only one place to read from
+        let raw = &mut tensor.raw_data;
+        raw.extend(floats.iter().flat_map(|v| v.to_le_bytes()));
+        raw.extend(int32s.iter().flat_map(|&v| (v as i32).to_le_bytes()));
+        raw.extend(int64s.iter().flat_map(|v| v.to_le_bytes()));
+        raw.extend(doubles.iter().flat_map(|v| v.to_le_bytes()));
+        Ok(tensor)
+    }
+}
+
+impl OnnxValueInfo {
+    fn decode(buf: &[u8]) -> Result<Self, OnnxError> {
+        let mut info = Self {
+            name: String::new(),
+            shape: Vec::new(),
+        };
+        let mut r = ProtoReader::new(buf);
+        while let Some((field, value)) = r.next()? {
+            match field {
+                1 => info.name = value.string()?,
+                2 => {
+                    let tensor_type = ProtoReader::new(value.bytes()?).find(1)?;
+                    let shape = match tensor_type {
+                        Some(t) => ProtoReader::new(t.bytes()?).find(2)?,
+                        None => None,
+                    };
+                    if let Some(shape) = shape {
+                        let mut r = ProtoReader::new(shape.bytes()?);
+                        while let Some((field, dim)) = r.next()? {
+                            if field == 1 {
+                                let dim_value = ProtoReader::new(dim.bytes()?).find(1)?;
+                                let dim_value = dim_value.map(|v| v.int()).transpose()?;
+                                info.shape.push(dim_value.unwrap_or(0) as usize);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// A single field value read by [ProtoReader].
+enum ProtoValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> ProtoValue<'a> {
+    fn int(&self) -> Result<i64, OnnxError> {
+        match self {
+            Self::Varint(v) => Ok(*v as i64),
+            _ => Err(OnnxError::Decode("expected a varint".into())),
+        }
+    }
+
+    fn float(&self) -> Result<f32, OnnxError> {
+        match self {
+            Self::Fixed32(v) => Ok(f32::from_bits(*v)),
+            _ => Err(OnnxError::Decode("expected a float".into())),
+        }
+    }
+
+    fn bytes(&self) -> Result<&'a [u8], OnnxError> {
+        match self {
+            Self::Bytes(b) => Ok(b),
+            _ => Err(OnnxError::Decode(
+                "expected a length delimited field".into(),
+            )),
+        }
+    }
+
+    fn string(&self) -> Result<String, OnnxError> {
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| OnnxError::Decode("invalid utf8 string".into()))
+    }
+
+    /// Appends a repeated int field, which may be packed or unpacked.
+    fn ints(&self, out: &mut Vec<i64>) -> Result<(), OnnxError> {
+        match self {
+            Self::Bytes(b) => {
+                let mut r = ProtoReader::new(b);
+                while r.pos < b.len() {
+                    out.push(r.varint()? as i64);
+                }
+                Ok(())
+            }
+            _ => {
+                out.push(self.int()?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Appends a repeated float field, which may be packed or unpacked.
+    fn floats(&self, out: &mut Vec<f32>) -> Result<(), OnnxError> {
+        match self {
+            Self::Bytes(b) if b.len() % 4 == 0 => {
+                out.extend(
+                    b.chunks(4)
+                        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])),
+                );
+                Ok(())
+            }
+            _ => {
+                out.push(self.float()?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Appends a repeated double field, which may be packed or unpacked.
+    fn doubles(&self, out: &mut Vec<f64>) -> Result<(), OnnxError> {
+        match self {
+            Self::Bytes(b) if b.len() % 8 == 0 => {
+                out.extend(
+                    b.chunks(8)
+                        .map(|c| f64::from_le_bytes(c.try_into().unwrap())),
+                );
+                Ok(())
+            }
+            Self::Fixed64(v) => {
+                out.push(f64::from_bits(*v));
+                Ok(())
+            }
+            _ => Err(OnnxError::Decode("expected a double".into())),
+        }
+    }
+}
+
+/// Reads the fields of a protobuf message one at a time.
+struct ProtoReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64, OnnxError> {
+        let mut v = 0;
+        for shift in (0..64).step_by(7) {
+            let b = *self
+                .buf
+                .get(self.pos)
+                .ok_or_else(|| OnnxError::Decode("unexpected end of message".into()))?;
+            self.pos += 1;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b < 0x80 {
+                return Ok(v);
+            }
+        }
+        Err(OnnxError::Decode("varint is too long".into()))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], OnnxError> {
+        if self.buf.len() - self.pos < n {
+            return Err(OnnxError::Decode("unexpected end of message".into()));
+        }
+        self.pos += n;
+        Ok(&self.buf[self.pos - n..self.pos])
+    }
+
+    /// Returns the next field number & value, or `None` at the end of the message.
+    fn next(&mut self) -> Result<Option<(u64, ProtoValue<'a>)>, OnnxError> {
+        if self.pos == self.buf.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            WIRE_VARINT => ProtoValue::Varint(self.varint()?),
+            WIRE_FIXED64 => {
+                ProtoValue::Fixed64(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+            }
+            WIRE_LEN => {
+                let len = self.varint()? as usize;
+                ProtoValue::Bytes(self.take(len)?)
+            }
+            WIRE_FIXED32 => {
+                ProtoValue::Fixed32(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+            }
+            t => return Err(OnnxError::Decode(format!("unsupported wire type {t}"))),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+
+    /// Returns the value of the last occurrence of `field`.
+    fn find(&mut self, field: u64) -> Result<Option<ProtoValue<'a>>, OnnxError> {
+        let mut found = None;
+        while let Some((f, value)) = self.next()? {
+            if f == field {
+                found = Some(value);
+            }
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varints() {
+        let mut w = ProtoWriter::default();
+        w.int(1, 150);
+        assert_eq!(&w.buf, &[0x08, 0x96, 0x01]);
+
+        let mut w = ProtoWriter::default();
+        w.int(2, -1);
+        assert_eq!(
+            &w.buf,
+            &[0x10, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_encode_node() {
+        let node = OnnxNode {
+            name: "n".into(),
+            op_type: "Softmax".into(),
+            inputs: vec!["x".into()],
+            outputs: vec!["y".into()],
+            attributes: vec![OnnxAttribute::int("axis", -1)],
+        };
+        let mut w = ProtoWriter::default();
+        node.encode(&mut w);
+        #[rustfmt::skip]
+        let expected: Vec<u8> = [
+            &[0x0a, 1, b'x'][..],
+            &[0x12, 1, b'y'],
+            &[0x1a, 1, b'n'],
+            &[0x22, 7], b"Softmax",
+            &[0x2a, 20, 0x0a, 4], b"axis",
+            &[0x18, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            &[0xa0, 0x01, 0x02],
+        ].concat();
+        assert_eq!(w.buf, expected);
+    }
+
+    #[test]
+    fn test_encode_tensor() {
+        let t = OnnxTensor::from_array("w".into(), &[[1.0f32, 2.0]]);
+        assert_eq!(&t.dims, &[1, 2]);
+        let mut w = ProtoWriter::default();
+        t.encode(&mut w);
+        #[rustfmt::skip]
+        let expected: Vec<u8> = [
+            &[0x08, 1, 0x08, 2][..],
+            &[0x10, 1],
+            &[0x42, 1, b'w'],
+            &[0x4a, 8], &1.0f32.to_le_bytes(), &2.0f32.to_le_bytes(),
+        ].concat();
+        assert_eq!(w.buf, expected);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::prelude::*;
+use alloc::format;
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
 use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 /// Repeats `T` `N` times. This requires that `T`'s input is the same as it's output.
@@ -16,7 +19,16 @@ use zip::{result::ZipResult, ZipArchive, ZipWriter};
 /// let out: Tensor1D<10> = model.forward(Tensor1D::zeros());
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Repeated<T, const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::nn::serde_array"))]
     pub modules: [T; N],
 }
 
@@ -31,7 +43,7 @@ where
     }
 }
 
-impl<T, const N: usize> std::ops::Index<usize> for Repeated<T, N> {
+impl<T, const N: usize> core::ops::Index<usize> for Repeated<T, N> {
     type Output = T;
     fn index(&self, index: usize) -> &Self::Output {
         &self.modules[index]
@@ -54,6 +66,49 @@ impl<T: CanUpdateWithGradients, const N: usize> CanUpdateWithGradients for Repea
     }
 }
 
+impl<T: VisitParams, const N: usize> VisitParams for Repeated<T, N> {
+    /// Visits the `i`th module with the prefix `{pre}{i}.`.
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        for i in 0..N {
+            self.modules[i].visit_params(&format!("{}{}.", pre, i), visitor);
+        }
+    }
+
+    /// Visits the `i`th module with the prefix `{pre}{i}.`.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        for i in 0..N {
+            self.modules[i].visit_params_mut(&format!("{}{}.", pre, i), visitor);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Input, T: Summarize<Input, Output = Input>, const N: usize> Summarize<Input>
+    for Repeated<T, N>
+{
+    /// Adds a row for the whole stack, followed by the rows of each sub module with the
+    /// prefix `{pre}{i}.` for the `i`th module.
+    fn summarize(&self, pre: &str, depth: usize, layers: &mut Vec<LayerSummary>) {
+        layers.push(LayerSummary::new::<Input, _>(self, pre, depth, "Repeated"));
+        for i in 0..N {
+            self.modules[i].summarize(&format!("{}{}.", pre, i), depth + 1, layers);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ExportToOnnx, const N: usize> ExportToOnnx for Repeated<T, N> {
+    /// Exports each sub module in order, with the prefix `{pre}{i}.` for the `i`th module.
+    fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let mut x = x;
+        for i in 0..N {
+            x = self.modules[i].export_onnx(&format!("{}{}.", pre, i), graph, x);
+        }
+        x
+    }
+}
+
+#[cfg(feature = "std")]
 impl<T: SaveToNpz, const N: usize> SaveToNpz for Repeated<T, N> {
     /// Calls `SaveToNpz::write(self.modules[i], ...)` on each sub module. See [SaveToNpz].
     ///
@@ -68,6 +123,7 @@ impl<T: SaveToNpz, const N: usize> SaveToNpz for Repeated<T, N> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<T: LoadFromNpz, const N: usize> LoadFromNpz for Repeated<T, N> {
     /// Calls `LoadFromNpz::read(self.modules[i], ...)` on each sub module. See [LoadFromNpz].
     ///
@@ -15,6 +15,7 @@ use crate::prelude::*;
 /// assert_eq!(y.data(), &[-2.0, -1.0, 0.0, 2.0, 4.0]);
 /// ```
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Residual<F>(pub F);
 
 impl<F: CanUpdateWithGradients> CanUpdateWithGradients for Residual<F> {
@@ -24,6 +25,18 @@ impl<F: CanUpdateWithGradients> CanUpdateWithGradients for Residual<F> {
     }
 }
 
+impl<F: VisitParams> VisitParams for Residual<F> {
+    /// Pass through to `F`'s [VisitParams].
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        self.0.visit_params(pre, visitor);
+    }
+
+    /// Pass through to `F`'s [VisitParams].
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        self.0.visit_params_mut(pre, visitor);
+    }
+}
+
 impl<F: ResetParams> ResetParams for Residual<F> {
     /// Pass through to `F`'s [ResetParams].
     fn reset_params<R: rand::Rng>(&mut self, rng: &mut R) {
@@ -45,6 +58,33 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+impl<T, F> Summarize<T> for Residual<F>
+where
+    T: Tensor<Dtype = f32>,
+    F: Summarize<T, Output = T>,
+{
+    /// Adds a row for the residual, followed by the rows of `F` with the same prefix.
+    fn summarize(&self, pre: &str, depth: usize, layers: &mut Vec<LayerSummary>) {
+        layers.push(LayerSummary::new::<T, _>(self, pre, depth, "Residual"));
+        self.0.summarize(pre, depth + 1, layers);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F: ExportToOnnx> ExportToOnnx for Residual<F> {
+    /// Exports `F` and then adds an `Add` node for `F(x) + x`.
+    fn export_onnx(&self, pre: &str, graph: &mut OnnxGraph, x: OnnxValueInfo) -> OnnxValueInfo {
+        let f_x = self.0.export_onnx(pre, graph, x.clone());
+        let y = graph.add_node("Add", vec![f_x.name, x.name], vec![]);
+        OnnxValueInfo {
+            name: y,
+            shape: f_x.shape,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 impl<F: SaveToNpz> SaveToNpz for Residual<F> {
     /// Pass through to `F`'s [SaveToNpz].
     fn write<W>(
@@ -60,6 +100,7 @@ impl<F: SaveToNpz> SaveToNpz for Residual<F> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<F: LoadFromNpz> LoadFromNpz for Residual<F> {
     /// Pass through to `F`'s [LoadFromNpz].
     fn read<R>(&mut self, filename_prefix: &str, r: &mut zip::ZipArchive<R>) -> Result<(), NpzError>
@@ -0,0 +1,761 @@
+use crate::devices::{flat, flat_mut};
+use crate::numpy::{f16_to_f32, f32_to_f16, NumpyShape};
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::error::Error;
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+/// The maximum length of the JSON header of a `.safetensors` file, the same limit as the
+/// reference implementation.
+pub(super) const MAX_HEADER_LEN: usize = 100_000_000;
+
+/// Something that can be saved to a [.safetensors](https://github.com/huggingface/safetensors) file.
+///
+/// This is implemented for everything that implements [VisitParams], and the tensors are named
+/// by [VisitParams], which is the same as the files in a `.npz` (without the `.npy`), e.g. `0.weight`
+/// and `0.bias`.
+pub trait SaveToSafetensors: VisitParams {
+    /// Save this object into the `.safetensors` file located at `path`.
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+    /// model.save_safetensors("model.safetensors")?;
+    /// ```
+    fn save_safetensors<P: AsRef<Path>>(&self, path: P) -> Result<(), SafetensorsError> {
+        let mut f = BufWriter::new(File::create(path)?);
+        self.write_safetensors(&mut f)
+    }
+
+    /// Writes this object in the `.safetensors` format to `w`.
+    fn write_safetensors<W: Write>(&self, w: &mut W) -> Result<(), SafetensorsError> {
+        let mut tensors = WriteTensors::new(false);
+        self.visit_params("", &mut tensors);
+        tensors.finish(w)
+    }
+
+    /// Save this object into the `.safetensors` file located at `path` with `F16` tensors, which
+    /// halves the size of the file. [LoadFromSafetensors] converts them back to `f32`.
+    fn save_safetensors_f16<P: AsRef<Path>>(&self, path: P) -> Result<(), SafetensorsError> {
+        let mut f = BufWriter::new(File::create(path)?);
+        self.write_safetensors_f16(&mut f)
+    }
+
+    /// Writes this object in the `.safetensors` format to `w` with `F16` tensors.
+    fn write_safetensors_f16<W: Write>(&self, w: &mut W) -> Result<(), SafetensorsError> {
+        let mut tensors = WriteTensors::new(true);
+        self.visit_params("", &mut tensors);
+        tensors.finish(w)
+    }
+}
+
+impl<T: VisitParams> SaveToSafetensors for T {}
+
+/// Builds the header & data section of a `.safetensors` file from the visited parameters.
+struct WriteTensors {
+    f16: bool,
+    header: String,
+    data: Vec<u8>,
+}
+
+impl WriteTensors {
+    fn new(f16: bool) -> Self {
+        Self {
+            f16,
+            header: String::from("{"),
+            data: Vec::new(),
+        }
+    }
+
+    fn finish<W: Write>(mut self, w: &mut W) -> Result<(), SafetensorsError> {
+        self.header.push('}');
+
+        // the data should start at a multiple of 8 bytes
+        while !self.header.len().is_multiple_of(8) {
+            self.header.push(' ');
+        }
+
+        w.write_all(&(self.header.len() as u64).to_le_bytes())?;
+        w.write_all(self.header.as_bytes())?;
+        w.write_all(&self.data)?;
+        Ok(())
+    }
+}
+
+impl ParamVisitor for WriteTensors {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+        let start = self.data.len();
+        let dtype = if self.f16 {
+            for &x in flat(p.data()) {
+                self.data.extend(f32_to_f16(x).to_le_bytes());
+            }
+            "F16"
+        } else {
+            for &x in flat(p.data()) {
+                self.data.extend(x.to_le_bytes());
+            }
+            "F32"
+        };
+        if self.header.len() > 1 {
+            self.header.push(',');
+        }
+        self.header.push_str(&format!(
+            "\"{name}\":{{\"dtype\":\"{dtype}\",\"shape\":{:?},\"data_offsets\":[{start},{}]}}",
+            P::Array::shape(),
+            self.data.len()
+        ));
+    }
+}
+
+/// Something that can be loaded from a [.safetensors](https://github.com/huggingface/safetensors) file.
+///
+/// This is implemented for everything that implements [VisitParams]. See [SaveToSafetensors] for how tensors are named.
+/// Only `F32` and `F16` tensors can be loaded (`F16` is converted to `f32`), and the shape of each tensor must match the shape of the parameter.
+/// Tensors in the file that are not parameters are ignored. Nothing is loaded if an error is returned.
+pub trait LoadFromSafetensors: VisitParams {
+    /// Loads data from the `.safetensors` file at `path`.
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let mut model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+    /// model.load_safetensors("model.safetensors")?;
+    /// ```
+    fn load_safetensors<P: AsRef<Path>>(&mut self, path: P) -> Result<(), SafetensorsError> {
+        let mut f = BufReader::new(File::open(path)?);
+        self.read_safetensors(&mut f)
+    }
+
+    /// Reads this object from `r`, which contains data in the `.safetensors` format.
+    fn read_safetensors<R: Read>(&mut self, r: &mut R) -> Result<(), SafetensorsError> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)?;
+        let (infos, start) = parse_file(&bytes)?;
+        let tensors = infos
+            .into_iter()
+            .map(|info| {
+                let [begin, end] = info.data_offsets;
+                let data = &bytes[start + begin..start + end];
+                // NOTE: the dtypes were checked by `parse_file()`
+                let data = match info.dtype.as_str() {
+                    "F32" => Elements::F32(data),
+                    _ => Elements::F16(data),
+                };
+                (info.name, info.shape, data)
+            })
+            .collect();
+        read_tensors(self, tensors)
+    }
+}
+
+impl<T: VisitParams> LoadFromSafetensors for T {}
+
+/// The little endian elements of a tensor that is loaded with [read_tensors()].
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Elements<'a> {
+    F32(&'a [u8]),
+    F16(&'a [u8]),
+}
+
+impl Elements<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Elements::F32(bytes) => bytes.len() / 4,
+            Elements::F16(bytes) => bytes.len() / 2,
+        }
+    }
+
+    /// Copies the elements into `dst`, which has [Elements::len()] elements.
+    fn copy_to(&self, dst: &mut [f32]) {
+        match self {
+            Elements::F32(bytes) => {
+                for (x, b) in dst.iter_mut().zip(bytes.chunks_exact(4)) {
+                    *x = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                }
+            }
+            Elements::F16(bytes) => {
+                for (x, b) in dst.iter_mut().zip(bytes.chunks_exact(2)) {
+                    *x = f16_to_f32(u16::from_le_bytes([b[0], b[1]]));
+                }
+            }
+        }
+    }
+}
+
+/// Loads `module` from a list of `(name, shape, elements)`. Every parameter must be in `tensors`
+/// with the same shape, and nothing is loaded if one isn't.
+pub(super) fn read_tensors<M: VisitParams + ?Sized>(
+    module: &mut M,
+    tensors: Vec<(String, Vec<usize>, Elements<'_>)>,
+) -> Result<(), SafetensorsError> {
+    let tensors: HashMap<String, (Vec<usize>, Elements<'_>)> = tensors
+        .into_iter()
+        .map(|(name, shape, data)| (name, (shape, data)))
+        .collect();
+    let mut check = CheckTensors(&tensors, Ok(()));
+    module.visit_params("", &mut check);
+    check.1?;
+    module.visit_params_mut("", &mut CopyTensors(&tensors));
+    Ok(())
+}
+
+/// Finds the first parameter that is missing or has a different shape.
+struct CheckTensors<'a, 'b>(
+    &'a HashMap<String, (Vec<usize>, Elements<'b>)>,
+    Result<(), SafetensorsError>,
+);
+
+impl ParamVisitor for CheckTensors<'_, '_> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, _: &P) {
+        if self.1.is_err() {
+            return;
+        }
+        let Some((shape, data)) = self.0.get(name) else {
+            self.1 = Err(SafetensorsError::MissingTensor);
+            return;
+        };
+        let expected = P::Array::shape();
+        if *shape != expected || data.len() != P::Array::NUM_ELEMENTS {
+            self.1 = Err(SafetensorsError::ShapeMismatch {
+                expected: format!("{:?}", expected),
+                found: format!("{:?}", shape),
+            });
+        }
+    }
+}
+
+/// Copies the tensors that were checked by [CheckTensors] into the parameters.
+struct CopyTensors<'a, 'b>(&'a HashMap<String, (Vec<usize>, Elements<'b>)>);
+
+impl ParamVisitorMut for CopyTensors<'_, '_> {
+    fn visit_mut<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &mut P) {
+        if let Some((_, data)) = self.0.get(name) {
+            data.copy_to(flat_mut(p.mut_data()));
+        }
+    }
+}
+
+/// Error that can happen while saving or loading a `.safetensors` file.
+#[derive(Debug)]
+pub enum SafetensorsError {
+    /// Something went wrong with reading or writing the file.
+    Io(std::io::Error),
+
+    /// The JSON header of the file is not valid.
+    InvalidHeader(String),
+
+    /// A tensor in the file has a dtype other than `F32` or `F16`.
+    DtypeMismatch { name: String, dtype: String },
+
+    /// The shape of a tensor in the file is different from the shape of the parameter.
+    ShapeMismatch { expected: String, found: String },
+
+    /// The file doesn't contain a tensor for one of the parameters.
+    MissingTensor,
+}
+
+impl std::fmt::Display for SafetensorsError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SafetensorsError::Io(err) => write!(fmt, "{}", err),
+            SafetensorsError::InvalidHeader(msg) => write!(fmt, "invalid header: {}", msg),
+            SafetensorsError::DtypeMismatch { name, dtype } => {
+                write!(
+                    fmt,
+                    "expected {} to have dtype F32 or F16, found {}",
+                    name, dtype
+                )
+            }
+            SafetensorsError::ShapeMismatch { expected, found } => {
+                write!(
+                    fmt,
+                    "shape mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            SafetensorsError::MissingTensor => write!(fmt, "missing tensor"),
+        }
+    }
+}
+
+impl Error for SafetensorsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SafetensorsError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SafetensorsError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+pub(super) struct TensorInfo {
+    pub(super) name: String,
+    pub(super) dtype: String,
+    pub(super) shape: Vec<usize>,
+    pub(super) data_offsets: [usize; 2],
+}
+
+/// Returns the number of bytes per element of `info`, after checking that the dtype is `F32` or
+/// `F16` and that the data is in bounds of a data section that is `data_len` bytes long.
+pub(super) fn element_size(info: &TensorInfo, data_len: usize) -> Result<usize, SafetensorsError> {
+    let size: usize = match info.dtype.as_str() {
+        "F32" => 4,
+        "F16" => 2,
+        _ => {
+            return Err(SafetensorsError::DtypeMismatch {
+                name: info.name.clone(),
+                dtype: info.dtype.clone(),
+            })
+        }
+    };
+    let [start, end] = info.data_offsets;
+    let num_bytes = info.shape.iter().try_fold(size, |n, &d| n.checked_mul(d));
+    if start > end || end > data_len || Some(end - start) != num_bytes {
+        return Err(SafetensorsError::InvalidHeader(format!(
+            "invalid data_offsets for {}",
+            info.name
+        )));
+    }
+    Ok(size)
+}
+
+/// Parses the header of the `.safetensors` file `bytes`, and returns its tensors and the offset of
+/// the data section. The header must be at most [MAX_HEADER_LEN] bytes, and every tensor must be
+/// in bounds of the file.
+pub(super) fn parse_file(bytes: &[u8]) -> Result<(Vec<TensorInfo>, usize), SafetensorsError> {
+    let invalid = |msg: &str| SafetensorsError::InvalidHeader(msg.into());
+    let header_len = match bytes.get(..8) {
+        Some(b) => u64::from_le_bytes(b.try_into().unwrap()),
+        None => return Err(invalid("file is too short")),
+    };
+    let header_len = usize::try_from(header_len)
+        .ok()
+        .filter(|&n| n <= MAX_HEADER_LEN)
+        .ok_or_else(|| invalid("header is too large"))?;
+    let start = header_len
+        .checked_add(8)
+        .filter(|&s| s <= bytes.len())
+        .ok_or_else(|| invalid("header is longer than the file"))?;
+    let infos = parse_header(&bytes[8..start])?;
+    for info in infos.iter() {
+        element_size(info, bytes.len() - start)?;
+    }
+    Ok((infos, start))
+}
+
+/// Parses the JSON header of a `.safetensors` file. The `__metadata__` entry is ignored.
+pub(super) fn parse_header(header: &[u8]) -> Result<Vec<TensorInfo>, SafetensorsError> {
+    let mut parser = JsonParser { buf: header, i: 0 };
+    let entries = match parser.value()? {
+        Json::Object(entries) => entries,
+        _ => return Err(SafetensorsError::InvalidHeader("expected an object".into())),
+    };
+
+    let mut infos = Vec::new();
+    for (name, value) in entries {
+        if name == "__metadata__" {
+            continue;
+        }
+        let fields = match value {
+            Json::Object(fields) => fields,
+            _ => return Err(invalid_entry(&name)),
+        };
+        let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+        let dtype = match get("dtype") {
+            Some(Json::String(dtype)) => dtype.clone(),
+            _ => return Err(invalid_entry(&name)),
+        };
+        let shape = match get("shape").and_then(Json::as_usizes) {
+            Some(shape) => shape,
+            None => return Err(invalid_entry(&name)),
+        };
+        let data_offsets = match get("data_offsets").and_then(Json::as_usizes).as_deref() {
+            Some(&[start, end]) => [start, end],
+            _ => return Err(invalid_entry(&name)),
+        };
+        infos.push(TensorInfo {
+            name,
+            dtype,
+            shape,
+            data_offsets,
+        });
+    }
+    Ok(infos)
+}
+
+fn invalid_entry(name: &str) -> SafetensorsError {
+    SafetensorsError::InvalidHeader(format!("invalid entry for {}", name))
+}
+
+/// The subset of JSON that is needed to parse `.safetensors` headers.
+enum Json {
+    Object(Vec<(String, Json)>),
+    Array(Vec<Json>),
+    String(String),
+    Number(String),
+    Literal,
+}
+
+impl Json {
+    fn as_usizes(&self) -> Option<Vec<usize>> {
+        match self {
+            Json::Array(values) => values
+                .iter()
+                .map(|v| match v {
+                    Json::Number(n) => n.parse().ok(),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    buf: &'a [u8],
+    i: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn value(&mut self) -> Result<Json, SafetensorsError> {
+        match self.peek()? {
+            b'{' => {
+                self.i += 1;
+                let mut entries = Vec::new();
+                if self.peek()? == b'}' {
+                    self.i += 1;
+                    return Ok(Json::Object(entries));
+                }
+                loop {
+                    if self.peek()? != b'"' {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    entries.push((key, self.value()?));
+                    if self.next_is(b'}')? {
+                        return Ok(Json::Object(entries));
+                    }
+                }
+            }
+            b'[' => {
+                self.i += 1;
+                let mut values = Vec::new();
+                if self.peek()? == b']' {
+                    self.i += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    if self.next_is(b']')? {
+                        return Ok(Json::Array(values));
+                    }
+                }
+            }
+            b'"' => Ok(Json::String(self.string()?)),
+            b'-' | b'0'..=b'9' => Ok(Json::Number(self.token())),
+            b't' | b'f' | b'n' => {
+                self.token();
+                Ok(Json::Literal)
+            }
+            _ => Err(self.error("unexpected character")),
+        }
+    }
+
+    /// Skips whitespace and returns the next byte without consuming it.
+    fn peek(&mut self) -> Result<u8, SafetensorsError> {
+        while self.i < self.buf.len() && self.buf[self.i].is_ascii_whitespace() {
+            self.i += 1;
+        }
+        self.buf
+            .get(self.i)
+            .copied()
+            .ok_or_else(|| self.error("unexpected end"))
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), SafetensorsError> {
+        if self.peek()? != c {
+            return Err(self.error(&format!("expected '{}'", c as char)));
+        }
+        self.i += 1;
+        Ok(())
+    }
+
+    /// Consumes either a `,` (returning `false`) or `end` (returning `true`).
+    fn next_is(&mut self, end: u8) -> Result<bool, SafetensorsError> {
+        let c = self.peek()?;
+        self.i += 1;
+        match c {
+            b',' => Ok(false),
+            c if c == end => Ok(true),
+            _ => Err(self.error(&format!("expected ',' or '{}'", end as char))),
+        }
+    }
+
+    fn token(&mut self) -> String {
+        let start = self.i;
+        while self.i < self.buf.len() && !b",}] \t\r\n".contains(&self.buf[self.i]) {
+            self.i += 1;
+        }
+        String::from_utf8_lossy(&self.buf[start..self.i]).into_owned()
+    }
+
+    fn string(&mut self) -> Result<String, SafetensorsError> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let c = *self
+                .buf
+                .get(self.i)
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.i += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let c = *self
+                        .buf
+                        .get(self.i)
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.i += 1;
+                    match c {
+                        b'n' => bytes.push(b'\n'),
+                        b't' => bytes.push(b'\t'),
+                        b'r' => bytes.push(b'\r'),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0c),
+                        b'u' => {
+                            let hex = self
+                                .buf
+                                .get(self.i..self.i + 4)
+                                .and_then(|h| std::str::from_utf8(h).ok())
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            self.i += 4;
+                            bytes.extend_from_slice(hex.to_string().as_bytes());
+                        }
+                        c => bytes.push(c),
+                    }
+                }
+                c => bytes.push(c),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid utf8"))
+    }
+
+    fn error(&self, msg: &str) -> SafetensorsError {
+        SafetensorsError::InvalidHeader(format!("{} at byte {}", msg, self.i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use rand::{prelude::StdRng, SeedableRng};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_save_load_safetensors() {
+        type Model = (Linear<5, 3>, ReLU, LayerNorm1D<3>);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut saved: Model = Default::default();
+        saved.reset_params(&mut rng);
+        saved.2.gamma.randomize(&mut rng, &rand_distr::Standard);
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        saved.save_safetensors(file.path()).expect("");
+
+        let mut loaded: Model = Default::default();
+        loaded.load_safetensors(file.path()).expect("");
+        assert_eq!(loaded.0.weight.data(), saved.0.weight.data());
+        assert_eq!(loaded.0.bias.data(), saved.0.bias.data());
+        assert_eq!(loaded.2.gamma.data(), saved.2.gamma.data());
+        assert_eq!(loaded.2.beta.data(), saved.2.beta.data());
+    }
+
+    #[test]
+    fn test_safetensors_format() {
+        let model: Linear<2, 1> = Linear {
+            weight: Tensor2D::new([[1.0, 2.0]]),
+            bias: Tensor1D::new([3.0]),
+        };
+        let mut buf = Vec::new();
+        model.write_safetensors(&mut buf).expect("");
+
+        let header_len = u64::from_le_bytes(buf[..8].try_into().unwrap()) as usize;
+        assert_eq!(header_len % 8, 0);
+        let header = std::str::from_utf8(&buf[8..8 + header_len]).unwrap();
+        assert_eq!(
+            header.trim_end(),
+            r#"{"weight":{"dtype":"F32","shape":[1, 2],"data_offsets":[0,8]},"bias":{"dtype":"F32","shape":[1],"data_offsets":[8,12]}}"#
+        );
+        let data: Vec<f32> = buf[8 + header_len..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(data, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_safetensors_f16() {
+        let mut model: Linear<2, 1> = Linear {
+            weight: Tensor2D::new([[1.0, -2.5]]),
+            bias: Tensor1D::new([1.0 / 3.0]),
+        };
+        let mut buf = Vec::new();
+        model.write_safetensors_f16(&mut buf).expect("");
+
+        let header_len = u64::from_le_bytes(buf[..8].try_into().unwrap()) as usize;
+        let header = std::str::from_utf8(&buf[8..8 + header_len]).unwrap();
+        assert_eq!(
+            header.trim_end(),
+            r#"{"weight":{"dtype":"F16","shape":[1, 2],"data_offsets":[0,4]},"bias":{"dtype":"F16","shape":[1],"data_offsets":[4,6]}}"#
+        );
+        assert_eq!(
+            &buf[8 + header_len..],
+            &[0x00, 0x3c, 0x00, 0xc1, 0x55, 0x35]
+        );
+
+        model.read_safetensors(&mut buf.as_slice()).expect("");
+        assert_eq!(model.weight.data(), &[[1.0, -2.5]]);
+        assert_eq!(model.bias.data(), &[0.33325195]);
+    }
+
+    fn safetensors_file(header: &str, data: &[f32]) -> Vec<u8> {
+        let mut buf = (header.len() as u64).to_le_bytes().to_vec();
+        buf.extend_from_slice(header.as_bytes());
+        for x in data {
+            buf.extend_from_slice(&x.to_le_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_load_safetensors_with_metadata_and_extra_tensors() {
+        let buf = safetensors_file(
+            r#"{
+                "__metadata__": {"format": "pt"},
+                "bias": {"dtype": "F32", "shape": [1], "data_offsets": [8, 12]},
+                "weight": {"dtype": "F32", "shape": [1, 2], "data_offsets": [0, 8]},
+                "other": {"dtype": "F32", "shape": [], "data_offsets": [12, 16]}
+            }"#,
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        let mut model: Linear<2, 1> = Default::default();
+        model.read_safetensors(&mut buf.as_slice()).expect("");
+        assert_eq!(model.weight.data(), &[[1.0, 2.0]]);
+        assert_eq!(model.bias.data(), &[3.0]);
+    }
+
+    #[test]
+    fn test_load_safetensors_errors() {
+        let mut model: Linear<2, 1> = Default::default();
+
+        let buf = safetensors_file(
+            r#"{"weight":{"dtype":"I32","shape":[1,2],"data_offsets":[0,4]}}"#,
+            &[0.0],
+        );
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::DtypeMismatch { .. })
+        ));
+
+        let buf = safetensors_file(
+            r#"{"weight":{"dtype":"F32","shape":[2,1],"data_offsets":[0,8]},"bias":{"dtype":"F32","shape":[1],"data_offsets":[8,12]}}"#,
+            &[1.0, 2.0, 3.0],
+        );
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::ShapeMismatch { .. })
+        ));
+
+        let buf = safetensors_file(
+            r#"{"weight":{"dtype":"F32","shape":[1,2],"data_offsets":[0,8]}}"#,
+            &[1.0, 2.0],
+        );
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::MissingTensor)
+        ));
+
+        let buf = safetensors_file(
+            r#"{"weight":{"dtype":"F32","shape":[1,2],"data_offsets":[0,12]}}"#,
+            &[1.0, 2.0],
+        );
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::InvalidHeader(_))
+        ));
+
+        let buf = safetensors_file(r#"{"weight":"#, &[]);
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::InvalidHeader(_))
+        ));
+
+        // the number of bytes of the shape overflows
+        let buf = safetensors_file(
+            r#"{"weight":{"dtype":"F32","shape":[4611686018427387904,4],"data_offsets":[0,0]}}"#,
+            &[],
+        );
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_safetensors_invalid_header_len() {
+        let mut model: Linear<2, 1> = Default::default();
+
+        let mut buf = u64::MAX.to_le_bytes().to_vec();
+        buf.extend_from_slice(b"{}");
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::InvalidHeader(_))
+        ));
+
+        let mut buf = 100_000_001u64.to_le_bytes().to_vec();
+        buf.extend_from_slice(b"{}");
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::InvalidHeader(_))
+        ));
+
+        let mut buf = 3u64.to_le_bytes().to_vec();
+        buf.extend_from_slice(b"{}");
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::InvalidHeader(_))
+        ));
+
+        assert!(matches!(
+            model.read_safetensors(&mut [0u8; 4].as_slice()),
+            Err(SafetensorsError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_safetensors_error_loads_nothing() {
+        let mut model: Linear<2, 1> = Default::default();
+        let buf = safetensors_file(
+            r#"{"weight":{"dtype":"F32","shape":[1,2],"data_offsets":[0,8]},"bias":{"dtype":"F32","shape":[2],"data_offsets":[8,16]}}"#,
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        assert!(matches!(
+            model.read_safetensors(&mut buf.as_slice()),
+            Err(SafetensorsError::ShapeMismatch { .. })
+        ));
+        assert_eq!(model.weight.data(), &[[0.0; 2]]);
+    }
+}
@@ -0,0 +1,88 @@
+//! (De)serializes `[T; N]` fields for any `N` with `#[serde(with = "crate::nn::serde_array")]`,
+//! since serde itself only supports arrays up to length 32.
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::marker::PhantomData;
+
+pub(crate) fn serialize<S: Serializer, T: Serialize, const N: usize>(
+    arr: &[T; N],
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    let mut tup = s.serialize_tuple(N)?;
+    for x in arr.iter() {
+        tup.serialize_element(x)?;
+    }
+    tup.end()
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(
+    d: D,
+) -> Result<[T; N], D::Error> {
+    d.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+    type Value = [T; N];
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "an array of length {N}")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[T; N], A::Error> {
+        let mut items = Vec::with_capacity(N);
+        while let Some(x) = seq.next_element()? {
+            items.push(x);
+        }
+        let len = items.len();
+        items
+            .try_into()
+            .map_err(|_| de::Error::invalid_length(len, &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use rand::{prelude::StdRng, SeedableRng};
+
+    type Model = (
+        Repeated<(Linear<3, 3>, ReLU), 2>,
+        LayerNorm1D<3>,
+        Dropout,
+        GeneralizedResidual<Linear<3, 2>, (Residual<Linear<3, 3>>, Linear<3, 2>)>,
+        Softmax,
+    );
+
+    #[test]
+    fn test_model_json() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        model.2.p = 0.25;
+
+        let json = serde_json::to_string(&model).expect("");
+        let loaded: Model = serde_json::from_str(&json).expect("");
+        assert_eq!(loaded.state_dict(), model.state_dict());
+        assert_eq!(loaded.1.epsilon, model.1.epsilon);
+        assert_eq!(loaded.2.p, 0.25);
+
+        let x: Tensor1D<3> = TensorCreator::randn(&mut rng);
+        assert_eq!(loaded.forward(x.clone()).data(), model.forward(x).data());
+    }
+
+    #[test]
+    fn test_repeated_json_format() {
+        let model: Repeated<Linear<1, 1>, 2> = Default::default();
+        let json = serde_json::to_string(&model).expect("");
+        assert_eq!(
+            json,
+            r#"{"modules":[{"weight":[[0.0]],"bias":[0.0]},{"weight":[[0.0]],"bias":[0.0]}]}"#
+        );
+        let json = r#"{"modules":[{"weight":[[0.0]],"bias":[0.0]}]}"#;
+        assert!(serde_json::from_str::<Repeated<Linear<1, 1>, 2>>(json).is_err());
+    }
+}
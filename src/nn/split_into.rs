@@ -17,6 +17,7 @@ use crate::prelude::*;
 /// let _: (Tensor1D<3>, Tensor1D<7>) = model.forward(Tensor1D::<5>::zeros());
 /// ```
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SplitInto<T>(pub T);
 
 impl<T: CanUpdateWithGradients> CanUpdateWithGradients for SplitInto<T> {
@@ -25,12 +26,23 @@ impl<T: CanUpdateWithGradients> CanUpdateWithGradients for SplitInto<T> {
     }
 }
 
+impl<T: VisitParams> VisitParams for SplitInto<T> {
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        self.0.visit_params(pre, visitor);
+    }
+
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        self.0.visit_params_mut(pre, visitor);
+    }
+}
+
 impl<T: ResetParams> ResetParams for SplitInto<T> {
     fn reset_params<R: rand::Rng>(&mut self, rng: &mut R) {
         self.0.reset_params(rng);
     }
 }
 
+#[cfg(feature = "std")]
 impl<T: SaveToNpz> SaveToNpz for SplitInto<T> {
     fn write<W>(&self, p: &str, w: &mut zip::ZipWriter<W>) -> zip::result::ZipResult<()>
     where
@@ -40,6 +52,7 @@ impl<T: SaveToNpz> SaveToNpz for SplitInto<T> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<T: LoadFromNpz> LoadFromNpz for SplitInto<T> {
     fn read<R>(&mut self, p: &str, r: &mut zip::ZipArchive<R>) -> Result<(), NpzError>
     where
@@ -50,7 +63,7 @@ impl<T: LoadFromNpz> LoadFromNpz for SplitInto<T> {
 }
 
 macro_rules! tuple_impls {
-    ([$($heads:ident),+] $tail:ident) => {
+    ([$($heads:ident),+] $tail:ident, [$($heads_idx:tt),+] $tail_idx:tt) => {
 impl<
     Input: Tensor,
     $($heads : Module<Input>,)+
@@ -76,14 +89,33 @@ where
         )
     }
 }
+
+#[cfg(feature = "std")]
+impl<
+    Input: Tensor,
+    $($heads : Summarize<Input>,)+
+    $tail: Summarize<Input>
+> Summarize<Input> for SplitInto<($($heads,)+ $tail)>
+where
+    $($heads::Output: Tensor<Tape = Input::Tape>,)+
+    <Self as Module<Input>>::Output: TensorShapes,
+{
+    /// Adds a row for the split, followed by the rows of each head with the prefix
+    /// `{pre}{idx}.` like [SaveToNpz].
+    fn summarize(&self, pre: &str, depth: usize, layers: &mut Vec<LayerSummary>) {
+        layers.push(LayerSummary::new::<Input, _>(self, pre, depth, "SplitInto"));
+        $(self.0.$heads_idx.summarize(&format!("{}{}.", pre, $heads_idx), depth + 1, layers);)+
+        self.0.$tail_idx.summarize(&format!("{}{}.", pre, $tail_idx), depth + 1, layers);
+    }
+}
 }
 }
 
-tuple_impls!([A] B);
-tuple_impls!([A, B] C);
-tuple_impls!([A, B, C] D);
-tuple_impls!([A, B, C, D] E);
-tuple_impls!([A, B, C, D, E] F);
+tuple_impls!([A] B, [0] 1);
+tuple_impls!([A, B] C, [0, 1] 2);
+tuple_impls!([A, B, C] D, [0, 1, 2] 3);
+tuple_impls!([A, B, C, D] E, [0, 1, 2, 3] 4);
+tuple_impls!([A, B, C, D, E] F, [0, 1, 2, 3, 4] 5);
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,314 @@
+use crate::devices::{flat, flat_mut};
+use crate::numpy::NumpyShape;
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+
+/// A map from the names of a module's parameters to their values. The names are the ones given by
+/// [VisitParams], which are the same as the files that [super::SaveToNpz] writes (without the `.npy`),
+/// e.g. `0.weight` and `0.bias`.
+pub type StateDict = BTreeMap<String, StateTensor>;
+
+/// The value of a parameter in a [StateDict].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateTensor {
+    pub shape: Vec<usize>,
+
+    /// The values in row major order.
+    pub data: Vec<f32>,
+}
+
+/// The keys that didn't match up when loading a [StateDict] with [HasStateDict::load_state_dict()].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IncompatibleKeys {
+    /// Parameters of the module that are not in the state dict.
+    pub missing: Vec<String>,
+
+    /// Entries of the state dict that are not parameters of the module.
+    pub unexpected: Vec<String>,
+}
+
+impl IncompatibleKeys {
+    /// Returns `true` if every key matched.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Something whose parameters can be accessed by name with a [StateDict].
+///
+/// This is implemented for everything that implements [VisitParams], which includes all the
+/// modules in nn. Unlike [super::LoadFromNpz::load()], a state dict can be loaded into a module
+/// with a different structure, as long as the matching keys have the same shapes.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let backbone: (Linear<5, 10>, ReLU) = Default::default();
+/// let mut model: ((Linear<5, 10>, ReLU), Linear<10, 2>) = Default::default();
+///
+/// // the backbone is the first layer of the model, so its keys need a `0.` prefix
+/// let state: StateDict = backbone
+///     .state_dict()
+///     .into_iter()
+///     .map(|(key, value)| (format!("0.{key}"), value))
+///     .collect();
+/// let keys = model.load_state_dict(&state, false).unwrap();
+/// assert_eq!(keys.missing, ["1.bias", "1.weight"]);
+/// ```
+pub trait HasStateDict: VisitParams {
+    /// Returns the values of all the parameters, keyed by name.
+    fn state_dict(&self) -> StateDict {
+        let mut state = CollectState(StateDict::new());
+        self.visit_params("", &mut state);
+        state.0
+    }
+
+    /// Loads the parameters that have an entry in `state`, and returns the keys that didn't match.
+    ///
+    /// If `strict` is `true`, every parameter must be in `state` and every entry of `state` must be
+    /// a parameter, otherwise [StateDictError::IncompatibleKeys] is returned. If `strict` is `false`,
+    /// parameters that are not in `state` keep their current values, and extra entries are ignored.
+    ///
+    /// Nothing is loaded if an error is returned.
+    fn load_state_dict(
+        &mut self,
+        state: &StateDict,
+        strict: bool,
+    ) -> Result<IncompatibleKeys, StateDictError> {
+        let mut check = CheckState {
+            state,
+            visited: BTreeSet::new(),
+            missing: Vec::new(),
+            mismatch: None,
+        };
+        self.visit_params("", &mut check);
+        if let Some(err) = check.mismatch {
+            return Err(err);
+        }
+        check.missing.sort();
+        let keys = IncompatibleKeys {
+            missing: check.missing,
+            unexpected: state
+                .keys()
+                .filter(|k| !check.visited.contains(k.as_str()))
+                .cloned()
+                .collect(),
+        };
+        if strict && !keys.is_empty() {
+            return Err(StateDictError::IncompatibleKeys(keys));
+        }
+
+        self.visit_params_mut("", &mut LoadState(state));
+        Ok(keys)
+    }
+}
+
+impl<T: VisitParams> HasStateDict for T {}
+
+/// Copies every parameter into a [StateTensor].
+struct CollectState(StateDict);
+
+impl ParamVisitor for CollectState {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+        let value = StateTensor {
+            shape: P::Array::shape(),
+            data: flat(p.data()).to_vec(),
+        };
+        self.0.insert(name.into(), value);
+    }
+}
+
+/// Finds the parameters that are not in `state`, and the first entry of `state` with the
+/// wrong shape.
+struct CheckState<'a> {
+    state: &'a StateDict,
+    visited: BTreeSet<String>,
+    missing: Vec<String>,
+    mismatch: Option<StateDictError>,
+}
+
+impl ParamVisitor for CheckState<'_> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, _: &P) {
+        self.visited.insert(name.into());
+        let Some(value) = self.state.get(name) else {
+            self.missing.push(name.into());
+            return;
+        };
+        let expected = P::Array::shape();
+        if self.mismatch.is_none()
+            && (value.shape != expected || value.data.len() != P::Array::NUM_ELEMENTS)
+        {
+            self.mismatch = Some(StateDictError::ShapeMismatch {
+                name: name.into(),
+                expected,
+                found: value.shape.clone(),
+            });
+        }
+    }
+}
+
+/// Copies the entries of a [StateDict] that was checked by [CheckState] into the parameters.
+struct LoadState<'a>(&'a StateDict);
+
+impl ParamVisitorMut for LoadState<'_> {
+    fn visit_mut<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &mut P) {
+        if let Some(value) = self.0.get(name) {
+            flat_mut(p.mut_data()).copy_from_slice(&value.data);
+        }
+    }
+}
+
+/// Error that can happen while loading a [StateDict].
+#[derive(Debug)]
+pub enum StateDictError {
+    /// The keys of the state dict don't match the parameters, and the load was strict.
+    IncompatibleKeys(IncompatibleKeys),
+
+    /// The shape of an entry is different from the shape of the parameter.
+    ShapeMismatch {
+        name: String,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+}
+
+impl std::fmt::Display for StateDictError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StateDictError::IncompatibleKeys(keys) => write!(
+                fmt,
+                "missing keys {:?}, unexpected keys {:?}",
+                keys.missing, keys.unexpected
+            ),
+            StateDictError::ShapeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                fmt,
+                "shape mismatch for {name}: expected {expected:?}, found {found:?}"
+            ),
+        }
+    }
+}
+
+impl Error for StateDictError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_state_dict() {
+        let mut model: (Linear<2, 3>, ReLU, LayerNorm1D<3>) = Default::default();
+        model.0.weight = Tensor2D::new([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+        let state = model.state_dict();
+        let keys: Vec<&str> = state.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["0.bias", "0.weight", "2.beta", "2.gamma"]);
+        assert_eq!(
+            state["0.weight"],
+            StateTensor {
+                shape: vec![3, 2],
+                data: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+            }
+        );
+        assert_eq!(state["2.gamma"].data, [1.0; 3]);
+    }
+
+    #[test]
+    fn test_state_dict_names_match_npz() {
+        type Model = (
+            Residual<Linear<2, 2>>,
+            GeneralizedResidual<LayerNorm1D<2>, Linear<2, 2>>,
+            Repeated<(Linear<2, 2>, ReLU), 2>,
+            SplitInto<(Linear<2, 1>, Linear<2, 3>)>,
+        );
+        let model: Model = Default::default();
+        let bytes = model.save_to_bytes().expect("");
+        let zip = zip::ZipArchive::new(std::io::Cursor::new(bytes)).expect("");
+        let mut files: Vec<String> = zip
+            .file_names()
+            .map(|f| f.trim_end_matches(".npy").to_string())
+            .collect();
+        files.sort();
+        let keys: Vec<String> = model.state_dict().into_keys().collect();
+        assert_eq!(keys, files);
+    }
+
+    #[test]
+    fn test_load_state_dict_strict() {
+        let mut rng = thread_rng();
+        let mut a: (Linear<2, 3>, Linear<3, 1>) = Default::default();
+        a.reset_params(&mut rng);
+        let mut b: (Linear<2, 3>, Linear<3, 1>) = Default::default();
+
+        let keys = b.load_state_dict(&a.state_dict(), true).expect("");
+        assert!(keys.is_empty());
+        assert_eq!(a.0.weight.data(), b.0.weight.data());
+        assert_eq!(a.1.bias.data(), b.1.bias.data());
+
+        let mut state = a.state_dict();
+        state.remove("1.bias");
+        state.insert("2.weight".into(), state["0.bias"].clone());
+        let before = b.state_dict();
+        match b.load_state_dict(&state, true) {
+            Err(StateDictError::IncompatibleKeys(keys)) => {
+                assert_eq!(keys.missing, ["1.bias"]);
+                assert_eq!(keys.unexpected, ["2.weight"]);
+            }
+            r => panic!("{r:?}"),
+        }
+        assert_eq!(b.state_dict(), before);
+    }
+
+    #[test]
+    fn test_load_partial_backbone() {
+        let mut rng = thread_rng();
+        let mut backbone: (Linear<4, 3>, Tanh) = Default::default();
+        backbone.reset_params(&mut rng);
+        let mut model: ((Linear<4, 3>, Tanh), Linear<3, 2>) = Default::default();
+        model.reset_params(&mut rng);
+        let head = model.1.clone();
+
+        let state: StateDict = backbone
+            .state_dict()
+            .into_iter()
+            .map(|(k, v)| (format!("0.{k}"), v))
+            .chain([(
+                "extra".to_string(),
+                StateTensor {
+                    shape: vec![],
+                    data: vec![1.0],
+                },
+            )])
+            .collect();
+        let keys = model.load_state_dict(&state, false).expect("");
+        assert_eq!(keys.missing, ["1.bias", "1.weight"]);
+        assert_eq!(keys.unexpected, ["extra"]);
+        assert_eq!(model.0 .0.weight.data(), backbone.0.weight.data());
+        assert_eq!(model.0 .0.bias.data(), backbone.0.bias.data());
+        assert_eq!(model.1.weight.data(), head.weight.data());
+        assert_eq!(model.1.bias.data(), head.bias.data());
+    }
+
+    #[test]
+    fn test_load_state_dict_shape_mismatch() {
+        let mut model: Linear<2, 3> = Default::default();
+        let mut state = model.state_dict();
+        state.get_mut("bias").unwrap().shape = vec![1, 3];
+        assert!(matches!(
+            model.load_state_dict(&state, false),
+            Err(StateDictError::ShapeMismatch { name, expected, found })
+                if name == "bias" && expected == [3] && found == [1, 3]
+        ));
+
+        let mut state = model.state_dict();
+        state.get_mut("weight").unwrap().data.pop();
+        assert!(matches!(
+            model.load_state_dict(&state, true),
+            Err(StateDictError::ShapeMismatch { .. })
+        ));
+    }
+}
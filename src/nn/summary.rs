@@ -0,0 +1,421 @@
+use crate::numpy::NumpyShape;
+use crate::prelude::*;
+use std::fmt::{Display, Formatter};
+
+/// Prints a table of the layers of a model, similar to [torchinfo](https://github.com/TylerYep/torchinfo):
+/// the output shape & number of parameters of each layer when given an input of type `Input`,
+/// and how many of the parameters are trainable vs. frozen (see [crate::gradients::Freeze]).
+///
+/// Rows are named the same way as in [crate::nn::SaveToNpz], and containers (tuples, [crate::nn::Residual],
+/// [crate::nn::GeneralizedResidual], [crate::nn::Repeated] and [crate::nn::SplitInto]) add a row for
+/// themselves followed by the rows of their sub modules. All other modules are a single row.
+///
+/// # Example
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let mut model: (Linear<5, 10>, ReLU, Residual<Linear<10, 10>>, Linear<10, 2>) = Default::default();
+/// model.0.freeze();
+/// let summary = Summarize::<Tensor2D<8, 5>>::summary(&model);
+/// assert_eq!(summary.total_params(), 60 + 110 + 22);
+/// assert_eq!(summary.frozen_params(), 60);
+/// println!("{summary}");
+/// ```
+///
+/// which prints
+/// ```text
+/// Layer                   Output Shape   Param #
+/// ==============================================
+/// Tuple                   [8, 2]         192
+///   0: Linear<5, 10>      [8, 10]        60
+///   1: ReLU               [8, 10]        0
+///   2: Residual           [8, 10]        110
+///     2: Linear<10, 10>   [8, 10]        110
+///   3: Linear<10, 2>      [8, 2]         22
+/// ==============================================
+/// Input shape: [8, 5]
+/// Total params: 192
+/// Trainable params: 132
+/// Frozen params: 60
+/// ```
+///
+/// # Implementing
+/// Modules without sub modules only need an empty impl:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// struct AddOne;
+///
+/// # impl ResetParams for AddOne {
+/// #     fn reset_params<R: rand::Rng>(&mut self, _: &mut R) {}
+/// # }
+/// # impl CanUpdateWithGradients for AddOne {
+/// #     fn update<G: GradientProvider>(&mut self, _: &mut G, _: &mut UnusedTensors) {}
+/// # }
+/// impl VisitParams for AddOne {
+///     fn visit_params<V: ParamVisitor>(&self, _: &str, _: &mut V) {}
+///     fn visit_params_mut<V: ParamVisitorMut>(&mut self, _: &str, _: &mut V) {}
+/// }
+///
+/// impl<const N: usize, H: Tape> Module<Tensor1D<N, H>> for AddOne {
+///     type Output = Tensor1D<N, H>;
+///     fn forward(&self, x: Tensor1D<N, H>) -> Self::Output {
+///         add_scalar(x, 1.0)
+///     }
+/// }
+///
+/// impl<const N: usize, H: Tape> Summarize<Tensor1D<N, H>> for AddOne {}
+/// ```
+pub trait Summarize<Input>: Module<Input, Output: TensorShapes> + VisitParams {
+    /// Appends the rows of this module to `layers`, where `pre` is the prefix of its parameters
+    /// like in [crate::nn::SaveToNpz], and `depth` is how deeply it is nested in the model.
+    fn summarize(&self, pre: &str, depth: usize, layers: &mut Vec<LayerSummary>) {
+        layers.push(LayerSummary::new::<Input, _>(
+            self,
+            pre,
+            depth,
+            &short_type_name::<Self>(),
+        ));
+    }
+
+    /// Summarizes all the layers of the model. The first row is the model itself.
+    fn summary(&self) -> Summary
+    where
+        Input: TensorShapes,
+    {
+        let mut layers = Vec::new();
+        self.summarize("", 0, &mut layers);
+        Summary {
+            input_shapes: Input::shapes(),
+            layers,
+        }
+    }
+}
+
+/// The shapes of tensors, as shown in a [Summary]. Implemented for tensors and tuples of tensors.
+pub trait TensorShapes {
+    fn shapes() -> Vec<Vec<usize>>;
+}
+
+impl<T: HasArrayType<Dtype = f32>> TensorShapes for T
+where
+    T::Array: NumpyShape,
+{
+    fn shapes() -> Vec<Vec<usize>> {
+        vec![T::Array::shape()]
+    }
+}
+
+macro_rules! tuple_shapes {
+    ([$($name:ident),+]) => {
+        impl<$($name: TensorShapes),+> TensorShapes for ($($name,)+) {
+            fn shapes() -> Vec<Vec<usize>> {
+                let mut shapes = Vec::new();
+                $(shapes.extend($name::shapes());)+
+                shapes
+            }
+        }
+    };
+}
+
+tuple_shapes!([A, B]);
+tuple_shapes!([A, B, C]);
+tuple_shapes!([A, B, C, D]);
+tuple_shapes!([A, B, C, D, E]);
+tuple_shapes!([A, B, C, D, E, F]);
+
+/// A single row of a [Summary].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerSummary {
+    /// The prefix of the parameters of this layer in [crate::nn::SaveToNpz], without the trailing `.`.
+    pub name: String,
+    /// The type of the layer, without module paths.
+    pub layer: String,
+    /// How deeply the layer is nested in the model. The model itself has depth `0`.
+    pub depth: usize,
+    /// The shapes of the output of [Module::forward()]. Modules that output a tuple have multiple.
+    pub output_shapes: Vec<Vec<usize>>,
+    /// The number of parameters with [crate::tensor::Tensor::requires_grad()], including sub modules.
+    pub trainable_params: usize,
+    /// The number of frozen parameters, including sub modules.
+    pub frozen_params: usize,
+}
+
+impl LayerSummary {
+    /// Counts the parameters of `module`, and reads its output shapes from `M::Output`.
+    /// The name of the row is `pre` without the trailing `.`.
+    pub fn new<Input, M: Summarize<Input> + ?Sized>(
+        module: &M,
+        pre: &str,
+        depth: usize,
+        layer: &str,
+    ) -> Self {
+        let mut counter = CountParams::default();
+        module.visit_params("", &mut counter);
+        Self {
+            name: pre.trim_end_matches('.').into(),
+            layer: layer.into(),
+            depth,
+            output_shapes: M::Output::shapes(),
+            trainable_params: counter.trainable,
+            frozen_params: counter.frozen,
+        }
+    }
+
+    /// The number of trainable & frozen parameters.
+    pub fn num_params(&self) -> usize {
+        self.trainable_params + self.frozen_params
+    }
+}
+
+/// The summary of a model returned by [Summarize::summary()], which is printed as a table
+/// with [Display].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    /// The shapes of the input of the model.
+    pub input_shapes: Vec<Vec<usize>>,
+    /// The rows of the table, where the first is the model itself.
+    pub layers: Vec<LayerSummary>,
+}
+
+impl Summary {
+    /// The total number of parameters in the model.
+    pub fn total_params(&self) -> usize {
+        self.layers[0].num_params()
+    }
+
+    /// The number of parameters that will be updated by optimizers.
+    pub fn trainable_params(&self) -> usize {
+        self.layers[0].trainable_params
+    }
+
+    /// The number of parameters that are frozen, see [crate::gradients::Freeze].
+    pub fn frozen_params(&self) -> usize {
+        self.layers[0].frozen_params
+    }
+}
+
+fn fmt_shapes(shapes: &[Vec<usize>]) -> String {
+    let shapes: Vec<String> = shapes.iter().map(|s| format!("{s:?}")).collect();
+    shapes.join(", ")
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<[String; 3]> = self
+            .layers
+            .iter()
+            .map(|l| {
+                let indent = "  ".repeat(l.depth);
+                let layer = if l.name.is_empty() {
+                    format!("{indent}{}", l.layer)
+                } else {
+                    format!("{indent}{}: {}", l.name, l.layer)
+                };
+                [
+                    layer,
+                    fmt_shapes(&l.output_shapes),
+                    l.num_params().to_string(),
+                ]
+            })
+            .collect();
+
+        let header = ["Layer", "Output Shape", "Param #"];
+        let mut widths = header.map(str::len);
+        for row in rows.iter() {
+            for (w, cell) in widths.iter_mut().zip(row.iter()) {
+                *w = (*w).max(cell.len());
+            }
+        }
+        let line = "=".repeat(widths[0] + widths[1] + widths[2] + 6);
+
+        writeln!(
+            f,
+            "{:w0$}   {:w1$}   {}",
+            header[0],
+            header[1],
+            header[2],
+            w0 = widths[0],
+            w1 = widths[1]
+        )?;
+        writeln!(f, "{line}")?;
+        for [layer, shapes, params] in rows.iter() {
+            writeln!(
+                f,
+                "{layer:w0$}   {shapes:w1$}   {params}",
+                w0 = widths[0],
+                w1 = widths[1]
+            )?;
+        }
+        writeln!(f, "{line}")?;
+        writeln!(f, "Input shape: {}", fmt_shapes(&self.input_shapes))?;
+        writeln!(f, "Total params: {}", self.total_params())?;
+        writeln!(f, "Trainable params: {}", self.trainable_params())?;
+        write!(f, "Frozen params: {}", self.frozen_params())
+    }
+}
+
+/// Removes the module paths from [core::any::type_name()], e.g. `dfdx::nn::linear::Linear<5, 10>`
+/// becomes `Linear<5, 10>`.
+pub(crate) fn short_type_name<T: ?Sized>() -> String {
+    let mut name = String::new();
+    let mut segment_start = 0;
+    let mut chars = core::any::type_name::<T>().chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            name.truncate(segment_start);
+        } else {
+            name.push(c);
+            if !(c.is_alphanumeric() || c == '_') {
+                segment_start = name.len();
+            }
+        }
+    }
+    name
+}
+
+#[derive(Default)]
+struct CountParams {
+    trainable: usize,
+    frozen: usize,
+}
+
+impl ParamVisitor for CountParams {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &P) {
+        if p.requires_grad() {
+            self.trainable += P::Array::NUM_ELEMENTS;
+        } else {
+            self.frozen += P::Array::NUM_ELEMENTS;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Row<'a> = (&'a str, &'a str, usize, Vec<Vec<usize>>, usize, usize);
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(short_type_name::<Linear<5, 10>>(), "Linear<5, 10>");
+        assert_eq!(
+            short_type_name::<(Linear<5, 10>, ReLU)>(),
+            "(Linear<5, 10>, ReLU)"
+        );
+        assert_eq!(
+            short_type_name::<Residual<Linear<2, 3>>>(),
+            "Residual<Linear<2, 3>>"
+        );
+    }
+
+    #[test]
+    fn test_summary_rows() {
+        type Model = (
+            Linear<5, 10>,
+            ReLU,
+            Residual<(Linear<10, 10>, Tanh)>,
+            SplitInto<(Linear<10, 2>, Linear<10, 3>)>,
+        );
+        let mut model: Model = Default::default();
+        model.2.freeze();
+        let summary = Summarize::<Tensor2D<4, 5>>::summary(&model);
+
+        let rows: Vec<Row> = summary
+            .layers
+            .iter()
+            .map(|l| {
+                let shapes = l.output_shapes.clone();
+                let (t, f) = (l.trainable_params, l.frozen_params);
+                (l.name.as_str(), l.layer.as_str(), l.depth, shapes, t, f)
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (
+                    "",
+                    "Tuple",
+                    0,
+                    vec![vec![4, 2], vec![4, 3]],
+                    60 + 22 + 33,
+                    110
+                ),
+                ("0", "Linear<5, 10>", 1, vec![vec![4, 10]], 60, 0),
+                ("1", "ReLU", 1, vec![vec![4, 10]], 0, 0),
+                ("2", "Residual", 1, vec![vec![4, 10]], 0, 110),
+                ("2", "Tuple", 2, vec![vec![4, 10]], 0, 110),
+                ("2.0", "Linear<10, 10>", 3, vec![vec![4, 10]], 0, 110),
+                ("2.1", "Tanh", 3, vec![vec![4, 10]], 0, 0),
+                ("3", "SplitInto", 1, vec![vec![4, 2], vec![4, 3]], 55, 0),
+                ("3.0", "Linear<10, 2>", 2, vec![vec![4, 2]], 22, 0),
+                ("3.1", "Linear<10, 3>", 2, vec![vec![4, 3]], 33, 0),
+            ]
+        );
+        assert_eq!(summary.input_shapes, [vec![4, 5]]);
+        assert_eq!(summary.total_params(), 225);
+        assert_eq!(summary.trainable_params(), 115);
+        assert_eq!(summary.frozen_params(), 110);
+    }
+
+    #[test]
+    fn test_summary_repeated_and_generalized_residual() {
+        type Model = Repeated<GeneralizedResidual<Linear<3, 3>, (ReLU, Linear<3, 3>)>, 2>;
+        let model: Model = Default::default();
+        let summary = Summarize::<Tensor1D<3>>::summary(&model);
+        let names: Vec<(&str, &str)> = summary
+            .layers
+            .iter()
+            .map(|l| (l.name.as_str(), l.layer.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("", "Repeated"),
+                ("0", "GeneralizedResidual"),
+                ("0._main", "Linear<3, 3>"),
+                ("0._residual", "Tuple"),
+                ("0._residual0", "ReLU"),
+                ("0._residual1", "Linear<3, 3>"),
+                ("1", "GeneralizedResidual"),
+                ("1._main", "Linear<3, 3>"),
+                ("1._residual", "Tuple"),
+                ("1._residual0", "ReLU"),
+                ("1._residual1", "Linear<3, 3>"),
+            ]
+        );
+        assert_eq!(summary.total_params(), 4 * 12);
+    }
+
+    #[test]
+    fn test_summary_display() {
+        let mut model: (Linear<5, 10>, ReLU, Residual<Linear<10, 10>>, Linear<10, 2>) =
+            Default::default();
+        model.0.freeze();
+        let summary = Summarize::<Tensor2D<8, 5>>::summary(&model);
+        assert_eq!(
+            summary.to_string(),
+            "Layer                   Output Shape   Param #
+==============================================
+Tuple                   [8, 2]         192
+  0: Linear<5, 10>      [8, 10]        60
+  1: ReLU               [8, 10]        0
+  2: Residual           [8, 10]        110
+    2: Linear<10, 10>   [8, 10]        110
+  3: Linear<10, 2>      [8, 2]         22
+==============================================
+Input shape: [8, 5]
+Total params: 192
+Trainable params: 132
+Frozen params: 60"
+        );
+    }
+
+    #[test]
+    fn test_summary_does_not_change_model() {
+        let mut model: (Linear<2, 2>, Linear<2, 2>) = Default::default();
+        model.1.freeze();
+        let _ = Summarize::<Tensor1D<2>>::summary(&model);
+        assert!(model.0.weight.requires_grad());
+        assert!(!model.1.weight.requires_grad());
+    }
+}
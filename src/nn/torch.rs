@@ -0,0 +1,665 @@
+use super::safetensors::{read_tensors, Elements};
+use super::SafetensorsError;
+use crate::gradients::VisitParams;
+use crate::numpy::f16_to_f32;
+use std::collections::HashMap;
+use std::error::Error;
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::Path,
+};
+use zip::{result::ZipError, ZipArchive};
+
+/// Something that can be loaded from a PyTorch checkpoint (`.pt`/`.pth`/`.bin` files saved with `torch.save()`).
+///
+/// This is implemented for everything that implements [VisitParams], and the entries of the state dict
+/// are matched to parameters by the names of [VisitParams], e.g. `0.weight` and `0.bias`.
+/// Nested dictionaries in the checkpoint are flattened by joining the keys with `.`, so a checkpoint
+/// saved with `torch.save({"model": model.state_dict()}, ...)` has entries like `model.0.weight`.
+///
+/// Only checkpoints in the zip format (the default since PyTorch 1.6) are supported. Tensors with dtypes
+/// `float32`, `float64`, `float16`, and `bfloat16` are converted to `f32`.
+pub trait LoadFromTorch: VisitParams {
+    /// Loads data from the PyTorch checkpoint at `path`.
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let mut model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+    /// model.load_torch("model.pt")?;
+    /// ```
+    fn load_torch<P: AsRef<Path>>(&mut self, path: P) -> Result<(), TorchError> {
+        self.load_torch_with(path, |key| key.to_string())
+    }
+
+    /// Loads data from the PyTorch checkpoint at `path`, renaming every key of the state dict
+    /// with `rename` before matching it to a parameter.
+    ///
+    /// Example:
+    /// ```ignore
+    /// # use dfdx::prelude::*;
+    /// let mut model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+    /// // the pytorch model stored the layers as `encoder.0` and `encoder.2`
+    /// model.load_torch_with("model.pt", |key| {
+    ///     key.replace("encoder.0.", "0.").replace("encoder.2.", "1.")
+    /// })?;
+    /// ```
+    fn load_torch_with<P, F>(&mut self, path: P, rename: F) -> Result<(), TorchError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&str) -> String,
+    {
+        let mut f = BufReader::new(File::open(path)?);
+        self.read_torch(&mut f, rename)
+    }
+
+    /// Reads data from `r`, which contains a PyTorch checkpoint. See [LoadFromTorch::load_torch_with()].
+    fn read_torch<R, F>(&mut self, r: &mut R, mut rename: F) -> Result<(), TorchError>
+    where
+        R: Read + Seek,
+        F: FnMut(&str) -> String,
+    {
+        let mut zip = ZipArchive::new(r)?;
+        let pkl_name = zip
+            .file_names()
+            .find(|name| name.ends_with("data.pkl"))
+            .ok_or_else(|| invalid("missing data.pkl"))?
+            .to_string();
+        let prefix = pkl_name.trim_end_matches("data.pkl");
+
+        let mut pkl = Vec::new();
+        zip.by_name(&pkl_name)?.read_to_end(&mut pkl)?;
+        let mut entries = Vec::new();
+        flatten(Unpickler::new(&pkl).load()?, "", &mut entries);
+
+        let mut storages: HashMap<String, Vec<f32>> = HashMap::new();
+        let mut tensors = Vec::new();
+        for (name, t) in entries {
+            if !storages.contains_key(&t.key) {
+                let mut bytes = Vec::new();
+                zip.by_name(&format!("{}data/{}", prefix, t.key))?
+                    .read_to_end(&mut bytes)?;
+                let storage =
+                    to_f32s(&bytes, &t.dtype).ok_or_else(|| TorchError::UnsupportedDtype {
+                        name: name.clone(),
+                        dtype: t.dtype.clone(),
+                    })?;
+                storages.insert(t.key.clone(), storage);
+            }
+            let data = gather(&storages[&t.key], &t)
+                .ok_or_else(|| invalid(&format!("{} is out of bounds of its storage", name)))?;
+            tensors.push((rename(&name), t.size, data));
+        }
+
+        let tensors = tensors
+            .iter()
+            .map(|(name, shape, data)| (name.clone(), shape.clone(), Elements::F32(data)))
+            .collect();
+        read_tensors(self, tensors)?;
+        Ok(())
+    }
+}
+
+impl<T: VisitParams> LoadFromTorch for T {}
+
+/// Error that can happen while loading a PyTorch checkpoint.
+#[derive(Debug)]
+pub enum TorchError {
+    /// Something went wrong with reading the file.
+    Io(std::io::Error),
+
+    /// Something went wrong with reading the `.zip` archive.
+    Zip(ZipError),
+
+    /// The checkpoint is not in a format that can be loaded.
+    InvalidCheckpoint(String),
+
+    /// A tensor in the checkpoint has a dtype that can't be converted to `f32`.
+    UnsupportedDtype { name: String, dtype: String },
+
+    /// The shape of a tensor in the checkpoint is different from the shape of the parameter.
+    ShapeMismatch { expected: String, found: String },
+
+    /// The checkpoint doesn't contain a tensor for one of the parameters.
+    MissingTensor,
+}
+
+impl std::fmt::Display for TorchError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TorchError::Io(err) => write!(fmt, "{}", err),
+            TorchError::Zip(err) => write!(fmt, "{}", err),
+            TorchError::InvalidCheckpoint(msg) => write!(fmt, "invalid checkpoint: {}", msg),
+            TorchError::UnsupportedDtype { name, dtype } => {
+                write!(fmt, "{} has unsupported dtype {}", name, dtype)
+            }
+            TorchError::ShapeMismatch { expected, found } => {
+                write!(
+                    fmt,
+                    "shape mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            TorchError::MissingTensor => write!(fmt, "missing tensor"),
+        }
+    }
+}
+
+impl Error for TorchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TorchError::Io(err) => Some(err),
+            TorchError::Zip(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TorchError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ZipError> for TorchError {
+    fn from(e: ZipError) -> Self {
+        Self::Zip(e)
+    }
+}
+
+impl From<SafetensorsError> for TorchError {
+    fn from(e: SafetensorsError) -> Self {
+        match e {
+            SafetensorsError::Io(e) => Self::Io(e),
+            SafetensorsError::ShapeMismatch { expected, found } => {
+                Self::ShapeMismatch { expected, found }
+            }
+            SafetensorsError::MissingTensor => Self::MissingTensor,
+            e => Self::InvalidCheckpoint(e.to_string()),
+        }
+    }
+}
+
+fn invalid(msg: &str) -> TorchError {
+    TorchError::InvalidCheckpoint(msg.into())
+}
+
+/// A tensor in the checkpoint, which is a view into a storage.
+#[derive(Debug, Clone)]
+struct TensorRef {
+    dtype: String,
+    key: String,
+    offset: usize,
+    size: Vec<usize>,
+    stride: Vec<usize>,
+}
+
+/// Converts the raw little endian bytes of a storage with type `dtype` (e.g. `FloatStorage`) to `f32`s.
+fn to_f32s(bytes: &[u8], dtype: &str) -> Option<Vec<f32>> {
+    let data = match dtype {
+        "FloatStorage" => bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        "DoubleStorage" => bytes
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32)
+            .collect(),
+        "HalfStorage" => bytes
+            .chunks_exact(2)
+            .map(|b| f16_to_f32(u16::from_le_bytes([b[0], b[1]])))
+            .collect(),
+        "BFloat16Storage" => bytes
+            .chunks_exact(2)
+            .map(|b| f32::from_bits((u16::from_le_bytes([b[0], b[1]]) as u32) << 16))
+            .collect(),
+        _ => return None,
+    };
+    Some(data)
+}
+
+/// Copies the elements of `t` out of `storage` in row major order, as little endian bytes.
+fn gather(storage: &[f32], t: &TensorRef) -> Option<Vec<u8>> {
+    let numel: usize = t.size.iter().product();
+    let mut data = Vec::with_capacity(numel * 4);
+    let mut index = vec![0; t.size.len()];
+    for _ in 0..numel {
+        let i: usize = t.offset
+            + index
+                .iter()
+                .zip(&t.stride)
+                .map(|(i, s)| i * s)
+                .sum::<usize>();
+        data.extend_from_slice(&storage.get(i)?.to_le_bytes());
+        for d in (0..index.len()).rev() {
+            index[d] += 1;
+            if index[d] < t.size[d] {
+                break;
+            }
+            index[d] = 0;
+        }
+    }
+    Some(data)
+}
+
+/// Adds all the tensors in (possibly nested) dictionaries to `entries`, with keys joined by `.`.
+fn flatten(value: Value, prefix: &str, entries: &mut Vec<(String, TensorRef)>) {
+    match value {
+        Value::Tensor(t) => entries.push((prefix.to_string(), t)),
+        Value::Dict(items) => {
+            for (key, value) in items {
+                if let Value::String(key) = key {
+                    let key = if prefix.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    flatten(value, &key, entries);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The values that can be created while unpickling a checkpoint. Anything that
+/// isn't needed to find the tensors is an [Value::Object].
+#[derive(Debug, Clone)]
+enum Value {
+    Mark,
+    None,
+    Int(i64),
+    String(String),
+    Tuple(Vec<Value>),
+    List(Vec<Value>),
+    Dict(Vec<(Value, Value)>),
+    Global(String, String),
+    Storage { dtype: String, key: String },
+    Tensor(TensorRef),
+    Object,
+}
+
+impl Value {
+    fn into_usizes(self) -> Option<Vec<usize>> {
+        match self {
+            Value::Tuple(values) => values
+                .into_iter()
+                .map(|v| match v {
+                    Value::Int(i) => usize::try_from(i).ok(),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+/// A minimal implementation of python's pickle virtual machine, which supports the opcodes that `torch.save()` uses.
+struct Unpickler<'a> {
+    buf: &'a [u8],
+    i: usize,
+    stack: Vec<Value>,
+    memo: HashMap<u32, Value>,
+}
+
+impl<'a> Unpickler<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            i: 0,
+            stack: Vec::new(),
+            memo: HashMap::new(),
+        }
+    }
+
+    fn load(mut self) -> Result<Value, TorchError> {
+        loop {
+            let op = self.take(1)?[0];
+            match op {
+                0x80 => {
+                    self.take(1)?; // PROTO
+                }
+                0x95 => {
+                    self.take(8)?; // FRAME
+                }
+                b'.' => return self.pop(),
+                b'(' => self.stack.push(Value::Mark),
+                b')' => self.stack.push(Value::Tuple(Vec::new())),
+                b']' => self.stack.push(Value::List(Vec::new())),
+                b'}' => self.stack.push(Value::Dict(Vec::new())),
+                b'N' => self.stack.push(Value::None),
+                0x88 | 0x89 => self.stack.push(Value::Object), // NEWTRUE & NEWFALSE
+                b'K' => {
+                    let v = self.take(1)?[0];
+                    self.stack.push(Value::Int(v as i64));
+                }
+                b'M' => {
+                    let v = self.take_u16()?;
+                    self.stack.push(Value::Int(v as i64));
+                }
+                b'J' => {
+                    let b = self.take(4)?;
+                    let v = i32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                    self.stack.push(Value::Int(v as i64));
+                }
+                0x8a => {
+                    let n = self.take(1)?[0] as usize;
+                    let b = self.take(n)?;
+                    if n > 8 {
+                        return Err(invalid("integer too large"));
+                    }
+                    let mut v = 0i64;
+                    for (k, &byte) in b.iter().enumerate() {
+                        v |= (byte as i64) << (8 * k);
+                    }
+                    if n > 0 && n < 8 && b[n - 1] & 0x80 != 0 {
+                        v -= 1i64 << (8 * n);
+                    }
+                    self.stack.push(Value::Int(v));
+                }
+                b'G' => {
+                    self.take(8)?; // BINFLOAT
+                    self.stack.push(Value::Object);
+                }
+                b'X' => {
+                    let n = self.take_u32()? as usize;
+                    let s = self.take_string(n)?;
+                    self.stack.push(Value::String(s));
+                }
+                0x8c | b'U' => {
+                    let n = self.take(1)?[0] as usize;
+                    let s = self.take_string(n)?;
+                    self.stack.push(Value::String(s));
+                }
+                b'B' => {
+                    let n = self.take_u32()? as usize;
+                    self.take(n)?;
+                    self.stack.push(Value::Object);
+                }
+                b'C' => {
+                    let n = self.take(1)?[0] as usize;
+                    self.take(n)?;
+                    self.stack.push(Value::Object);
+                }
+                b'c' => {
+                    let module = self.take_line()?;
+                    let name = self.take_line()?;
+                    self.stack.push(Value::Global(module, name));
+                }
+                0x93 => {
+                    let name = self.pop()?;
+                    let module = self.pop()?;
+                    match (module, name) {
+                        (Value::String(module), Value::String(name)) => {
+                            self.stack.push(Value::Global(module, name))
+                        }
+                        _ => return Err(invalid("invalid STACK_GLOBAL")),
+                    }
+                }
+                b'q' => {
+                    let k = self.take(1)?[0] as u32;
+                    self.memoize(k)?;
+                }
+                b'r' => {
+                    let k = self.take_u32()?;
+                    self.memoize(k)?;
+                }
+                0x94 => {
+                    let k = self.memo.len() as u32;
+                    self.memoize(k)?;
+                }
+                b'h' => {
+                    let k = self.take(1)?[0] as u32;
+                    self.get(k)?;
+                }
+                b'j' => {
+                    let k = self.take_u32()?;
+                    self.get(k)?;
+                }
+                b't' => {
+                    let items = self.pop_mark()?;
+                    self.stack.push(Value::Tuple(items));
+                }
+                0x85..=0x87 => {
+                    let n = (op - 0x84) as usize;
+                    if self.stack.len() < n {
+                        return Err(invalid("stack underflow"));
+                    }
+                    let items = self.stack.split_off(self.stack.len() - n);
+                    self.stack.push(Value::Tuple(items));
+                }
+                b'a' => {
+                    let v = self.pop()?;
+                    if let Some(Value::List(items)) = self.stack.last_mut() {
+                        items.push(v);
+                    }
+                }
+                b'e' => {
+                    let values = self.pop_mark()?;
+                    if let Some(Value::List(items)) = self.stack.last_mut() {
+                        items.extend(values);
+                    }
+                }
+                b's' => {
+                    let v = self.pop()?;
+                    let k = self.pop()?;
+                    if let Some(Value::Dict(items)) = self.stack.last_mut() {
+                        items.push((k, v));
+                    }
+                }
+                b'u' => {
+                    let values = self.pop_mark()?;
+                    if let Some(Value::Dict(items)) = self.stack.last_mut() {
+                        let mut values = values.into_iter();
+                        while let (Some(k), Some(v)) = (values.next(), values.next()) {
+                            items.push((k, v));
+                        }
+                    }
+                }
+                b'R' => {
+                    let args = self.pop()?;
+                    let callable = self.pop()?;
+                    let v = reduce(callable, args)?;
+                    self.stack.push(v);
+                }
+                b'b' => {
+                    // BUILD sets the state of an object, which is never needed for tensors
+                    self.pop()?;
+                }
+                0x81 => {
+                    self.pop()?;
+                    self.pop()?;
+                    self.stack.push(Value::Object);
+                }
+                b'Q' => {
+                    let pid = self.pop()?;
+                    self.stack.push(persistent_load(pid));
+                }
+                _ => return Err(invalid(&format!("unsupported pickle opcode {:#x}", op))),
+            }
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], TorchError> {
+        let buf: &'a [u8] = self.buf;
+        let bytes = buf
+            .get(self.i..self.i + n)
+            .ok_or_else(|| invalid("unexpected end of pickle"))?;
+        self.i += n;
+        Ok(bytes)
+    }
+
+    fn take_u16(&mut self) -> Result<u16, TorchError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn take_u32(&mut self) -> Result<u32, TorchError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn take_string(&mut self, n: usize) -> Result<String, TorchError> {
+        String::from_utf8(self.take(n)?.to_vec()).map_err(|_| invalid("invalid utf8"))
+    }
+
+    fn take_line(&mut self) -> Result<String, TorchError> {
+        let n = self.buf[self.i..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| invalid("unexpected end of pickle"))?;
+        let line = self.take_string(n)?;
+        self.i += 1;
+        Ok(line)
+    }
+
+    fn pop(&mut self) -> Result<Value, TorchError> {
+        self.stack.pop().ok_or_else(|| invalid("stack underflow"))
+    }
+
+    fn pop_mark(&mut self) -> Result<Vec<Value>, TorchError> {
+        let mark = self
+            .stack
+            .iter()
+            .rposition(|v| matches!(v, Value::Mark))
+            .ok_or_else(|| invalid("missing mark"))?;
+        let items = self.stack.split_off(mark + 1);
+        self.stack.pop();
+        Ok(items)
+    }
+
+    fn memoize(&mut self, k: u32) -> Result<(), TorchError> {
+        let v = self
+            .stack
+            .last()
+            .ok_or_else(|| invalid("stack underflow"))?;
+        self.memo.insert(k, v.clone());
+        Ok(())
+    }
+
+    fn get(&mut self, k: u32) -> Result<(), TorchError> {
+        let v = self.memo.get(&k).ok_or_else(|| invalid("missing memo"))?;
+        self.stack.push(v.clone());
+        Ok(())
+    }
+}
+
+/// Calls `callable` with `args`, for the functions that are used to build state dicts.
+fn reduce(callable: Value, args: Value) -> Result<Value, TorchError> {
+    let (module, name) = match callable {
+        Value::Global(module, name) => (module, name),
+        _ => return Ok(Value::Object),
+    };
+    let mut args = match args {
+        Value::Tuple(args) => args.into_iter(),
+        _ => return Err(invalid("expected a tuple of arguments")),
+    };
+    Ok(match (module.as_str(), name.as_str()) {
+        ("collections", "OrderedDict") => Value::Dict(Vec::new()),
+        ("torch._utils", "_rebuild_tensor" | "_rebuild_tensor_v2") => {
+            let (dtype, key) = match args.next() {
+                Some(Value::Storage { dtype, key }) => (dtype, key),
+                _ => return Err(invalid("expected a storage")),
+            };
+            let offset = match args.next() {
+                Some(Value::Int(offset)) if offset >= 0 => offset as usize,
+                _ => return Err(invalid("expected a storage offset")),
+            };
+            let size = args.next().and_then(Value::into_usizes);
+            let stride = args.next().and_then(Value::into_usizes);
+            match (size, stride) {
+                (Some(size), Some(stride)) if size.len() == stride.len() => {
+                    Value::Tensor(TensorRef {
+                        dtype,
+                        key,
+                        offset,
+                        size,
+                        stride,
+                    })
+                }
+                _ => return Err(invalid("expected size and stride")),
+            }
+        }
+        ("torch._utils", "_rebuild_parameter") => args.next().unwrap_or(Value::Object),
+        _ => Value::Object,
+    })
+}
+
+/// Storages are saved as `('storage', storage_type, key, location, numel)`.
+fn persistent_load(pid: Value) -> Value {
+    if let Value::Tuple(pid) = pid {
+        if let [Value::String(kind), Value::Global(_, dtype), Value::String(key), ..] =
+            pid.as_slice()
+        {
+            if kind == "storage" {
+                return Value::Storage {
+                    dtype: dtype.clone(),
+                    key: key.clone(),
+                };
+            }
+        }
+    }
+    Value::Object
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use std::io::{Cursor, Write};
+    use zip::ZipWriter;
+
+    /// The pickle that `torch.save()` writes for a state dict with:
+    /// - `fc.weight`: a `(3, 2)` tensor that is the transpose of storage `0`, which has 6 elements
+    /// - `fc.bias`: a `(3,)` tensor at offset 1 of storage `1`, which has 4 elements
+    const DATA_PKL: &[u8] = b"\x80\x02ccollections\nOrderedDict\nq\x00)Rq\x01(X\x09\x00\x00\x00fc.weightq\x02ctorch._utils\n_rebuild_tensor_v2\nq\x03((X\x07\x00\x00\x00storageq\x04ctorch\nFloatStorage\nq\x05X\x01\x00\x00\x000q\x06X\x03\x00\x00\x00cpuq\x07K\x06tq\x08QK\x00K\x03K\x02\x86q\x09K\x01K\x03\x86q\x0a\x89h\x00)Rq\x0btq\x0cRq\x0dX\x07\x00\x00\x00fc.biasq\x0eh\x03((h\x04h\x05X\x01\x00\x00\x001q\x0fh\x07K\x04tq\x10QK\x01K\x03\x85q\x11K\x01\x85q\x12\x89h\x00)Rq\x13tq\x14Rq\x15u}q\x16X\x09\x00\x00\x00_metadataq\x17h\x00)Rq\x18sb.";
+
+    fn checkpoint() -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("archive/data.pkl", Default::default())
+            .unwrap();
+        zip.write_all(DATA_PKL).unwrap();
+        zip.start_file("archive/data/0", Default::default())
+            .unwrap();
+        for x in [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0] {
+            zip.write_all(&x.to_le_bytes()).unwrap();
+        }
+        zip.start_file("archive/data/1", Default::default())
+            .unwrap();
+        for x in [0.0f32, 7.0, 8.0, 9.0] {
+            zip.write_all(&x.to_le_bytes()).unwrap();
+        }
+        zip.start_file("archive/version", Default::default())
+            .unwrap();
+        zip.write_all(b"3\n").unwrap();
+        let mut buf = zip.finish().unwrap();
+        buf.set_position(0);
+        buf
+    }
+
+    #[test]
+    fn test_load_torch_with_rename() {
+        let mut model: Linear<2, 3> = Default::default();
+        model
+            .read_torch(&mut checkpoint(), |key| key.replace("fc.", ""))
+            .expect("");
+        assert_eq!(model.weight.data(), &[[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]]);
+        assert_eq!(model.bias.data(), &[7.0, 8.0, 9.0]);
+    }
+
+    #[test]
+    fn test_load_torch_errors() {
+        let mut model: Linear<2, 3> = Default::default();
+        assert!(matches!(
+            model.read_torch(&mut checkpoint(), |key| key.to_string()),
+            Err(TorchError::MissingTensor)
+        ));
+
+        let mut model: Linear<3, 2> = Default::default();
+        assert!(matches!(
+            model.read_torch(&mut checkpoint(), |key| key.replace("fc.", "")),
+            Err(TorchError::ShapeMismatch { .. })
+        ));
+    }
+}
@@ -1,5 +1,10 @@
 use crate::prelude::*;
+use alloc::format;
 use rand::Rng;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 /// **Requires Nightly** A multi-head attention layer.
 ///
@@ -14,6 +19,7 @@ use rand::Rng;
 /// `MultiHeadAttention<8, 10, 10, 10, 2>` is an attention layer with 2 heads and 10 token, key and value dims.
 /// TODO: Doctests fail for some reason
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiHeadAttention<
     const M: usize,
     const N: usize,
@@ -49,6 +55,62 @@ impl<const M: usize, const N: usize, const K: usize, const V: usize, const H: us
     }
 }
 
+impl<const M: usize, const N: usize, const K: usize, const V: usize, const H: usize> VisitParams
+    for MultiHeadAttention<M, N, K, V, H>
+{
+    fn visit_params<P: ParamVisitor>(&self, pre: &str, visitor: &mut P) {
+        self.w_q.visit_params(&format!("{pre}w_q."), visitor);
+        self.w_k.visit_params(&format!("{pre}w_k."), visitor);
+        self.w_v.visit_params(&format!("{pre}w_v."), visitor);
+        self.w_o.visit_params(&format!("{pre}w_o."), visitor);
+    }
+
+    fn visit_params_mut<P: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut P) {
+        self.w_q.visit_params_mut(&format!("{pre}w_q."), visitor);
+        self.w_k.visit_params_mut(&format!("{pre}w_k."), visitor);
+        self.w_v.visit_params_mut(&format!("{pre}w_v."), visitor);
+        self.w_o.visit_params_mut(&format!("{pre}w_o."), visitor);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const K: usize, const V: usize, const H: usize> SaveToNpz
+    for MultiHeadAttention<M, N, K, V, H>
+{
+    /// Saves [Self::w_q], [Self::w_k], [Self::w_v], and [Self::w_o] with the prefixes
+    /// `{pre}w_q.`, `{pre}w_k.`, `{pre}w_v.`, and `{pre}w_o.`.
+    fn write<W: Write + Seek>(&self, pre: &str, w: &mut ZipWriter<W>) -> ZipResult<()> {
+        self.w_q.write(&format!("{pre}w_q."), w)?;
+        self.w_k.write(&format!("{pre}w_k."), w)?;
+        self.w_v.write(&format!("{pre}w_v."), w)?;
+        self.w_o.write(&format!("{pre}w_o."), w)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const K: usize, const V: usize, const H: usize> LoadFromNpz
+    for MultiHeadAttention<M, N, K, V, H>
+{
+    /// Reads the linear layers with the same prefixes as [SaveToNpz::write()].
+    fn read<R: Read + Seek>(&mut self, pre: &str, r: &mut ZipArchive<R>) -> Result<(), NpzError> {
+        self.w_q.read(&format!("{pre}w_q."), r)?;
+        self.w_k.read(&format!("{pre}w_k."), r)?;
+        self.w_v.read(&format!("{pre}w_v."), r)?;
+        self.w_o.read(&format!("{pre}w_o."), r)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const K: usize, const V: usize, const H: usize, T> Summarize<T>
+    for MultiHeadAttention<M, N, K, V, H>
+where
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
+
 impl<
         const M: usize,
         const K: usize,
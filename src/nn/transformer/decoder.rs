@@ -1,6 +1,11 @@
 use rand::Rng;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 use crate::prelude::*;
+use alloc::format;
 
 /// **Requires Nightly** A transformer decoder block. Different than the normal transformer block
 /// as this self attention accepts an additional sequence from the encoder.
@@ -12,6 +17,7 @@ use crate::prelude::*;
 /// - `H` The number of attention heads.
 /// TODO: Doctests
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransformerDecoderBlock<
     const M: usize,
     const N: usize,
@@ -30,6 +36,20 @@ pub struct TransformerDecoderBlock<
     ),
 }
 
+impl<const M: usize, const N: usize, const I: usize, const K: usize, const H: usize> Clone
+    for TransformerDecoderBlock<M, N, I, K, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+    Assert<{ K % H == 0 }>: ConstTrue,
+{
+    fn clone(&self) -> Self {
+        Self {
+            attn: self.attn.clone(),
+            ff: self.ff.clone(),
+        }
+    }
+}
+
 impl<const M: usize, const N: usize, const I: usize, const K: usize, const H: usize> ResetParams
     for TransformerDecoderBlock<M, N, I, K, H>
 where
@@ -54,6 +74,63 @@ where
     }
 }
 
+impl<const M: usize, const N: usize, const I: usize, const K: usize, const H: usize> VisitParams
+    for TransformerDecoderBlock<M, N, I, K, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+    Assert<{ K % H == 0 }>: ConstTrue,
+{
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        self.attn.visit_params(&format!("{pre}attn."), visitor);
+        self.ff.visit_params(&format!("{pre}ff."), visitor);
+    }
+
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        self.attn.visit_params_mut(&format!("{pre}attn."), visitor);
+        self.ff.visit_params_mut(&format!("{pre}ff."), visitor);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const I: usize, const K: usize, const H: usize> SaveToNpz
+    for TransformerDecoderBlock<M, N, I, K, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+    Assert<{ K % H == 0 }>: ConstTrue,
+{
+    /// Saves [Self::attn] with the prefix `{pre}attn.` and [Self::ff] with the prefix `{pre}ff.`.
+    fn write<W: Write + Seek>(&self, pre: &str, w: &mut ZipWriter<W>) -> ZipResult<()> {
+        self.attn.write(&format!("{pre}attn."), w)?;
+        self.ff.write(&format!("{pre}ff."), w)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const I: usize, const K: usize, const H: usize> LoadFromNpz
+    for TransformerDecoderBlock<M, N, I, K, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+    Assert<{ K % H == 0 }>: ConstTrue,
+{
+    /// Reads [Self::attn] and [Self::ff] with the same prefixes as [SaveToNpz::write()].
+    fn read<R: Read + Seek>(&mut self, pre: &str, r: &mut ZipArchive<R>) -> Result<(), NpzError> {
+        self.attn.read(&format!("{pre}attn."), r)?;
+        self.ff.read(&format!("{pre}ff."), r)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const I: usize, const K: usize, const H: usize, T> Summarize<T>
+    for TransformerDecoderBlock<M, N, I, K, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+    Assert<{ K % H == 0 }>: ConstTrue,
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+{
+}
 impl<
         const M: usize,
         const N: usize,
@@ -124,6 +201,7 @@ where
 /// - `H` The number of heads for self attention.
 /// TODO: Doctests
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransformerDecoder<
     const M: usize,
     const N: usize,
@@ -133,6 +211,7 @@ pub struct TransformerDecoder<
 > where
     Assert<{ M % H == 0 }>: ConstTrue,
 {
+    #[cfg_attr(feature = "serde", serde(with = "crate::nn::serde_array"))]
     pub blocks: [TransformerDecoderBlock<M, N, I, M, H>; L],
 }
 
@@ -149,6 +228,18 @@ where
     }
 }
 
+impl<const M: usize, const N: usize, const I: usize, const L: usize, const H: usize> Clone
+    for TransformerDecoder<M, N, I, L, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+{
+    fn clone(&self) -> Self {
+        Self {
+            blocks: self.blocks.clone(),
+        }
+    }
+}
+
 impl<const M: usize, const N: usize, const I: usize, const L: usize, const H: usize> ResetParams
     for TransformerDecoder<M, N, I, L, H>
 where
@@ -173,6 +264,79 @@ where
     }
 }
 
+impl<const M: usize, const N: usize, const I: usize, const L: usize, const H: usize> VisitParams
+    for TransformerDecoder<M, N, I, L, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+{
+    /// Visits the `i`th block with the prefix `{pre}{i}.`.
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        for (i, block) in self.blocks.iter().enumerate() {
+            block.visit_params(&format!("{pre}{i}."), visitor);
+        }
+    }
+
+    /// Visits the `i`th block with the prefix `{pre}{i}.`.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        for (i, block) in self.blocks.iter_mut().enumerate() {
+            block.visit_params_mut(&format!("{pre}{i}."), visitor);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const I: usize, const L: usize, const H: usize> SaveToNpz
+    for TransformerDecoder<M, N, I, L, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+{
+    /// Saves each block with the prefix `{pre}{i}.`, like [Repeated].
+    fn write<W: Write + Seek>(&self, pre: &str, w: &mut ZipWriter<W>) -> ZipResult<()> {
+        for (i, block) in self.blocks.iter().enumerate() {
+            block.write(&format!("{pre}{i}."), w)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const I: usize, const L: usize, const H: usize> LoadFromNpz
+    for TransformerDecoder<M, N, I, L, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+{
+    /// Reads each block with the prefix `{pre}{i}.`, like [Repeated].
+    fn read<R: Read + Seek>(&mut self, pre: &str, r: &mut ZipArchive<R>) -> Result<(), NpzError> {
+        for (i, block) in self.blocks.iter_mut().enumerate() {
+            block.read(&format!("{pre}{i}."), r)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const M: usize, const N: usize, const I: usize, const L: usize, const H: usize, T> Summarize<T>
+    for TransformerDecoder<M, N, I, L, H>
+where
+    Assert<{ M % H == 0 }>: ConstTrue,
+    Self: Module<T>,
+    <Self as Module<T>>::Output: TensorShapes,
+    TransformerDecoderBlock<M, N, I, M, H>: Summarize<T>,
+{
+    /// Adds a row for the decoder, followed by a row for each block with the prefix `{pre}{i}.`.
+    fn summarize(&self, pre: &str, depth: usize, layers: &mut Vec<LayerSummary>) {
+        layers.push(LayerSummary::new::<T, _>(
+            self,
+            pre,
+            depth,
+            "TransformerDecoder",
+        ));
+        for (i, block) in self.blocks.iter().enumerate() {
+            block.summarize(&format!("{pre}{i}."), depth + 1, layers);
+        }
+    }
+}
+
 impl<
         const M: usize,
         const N: usize,
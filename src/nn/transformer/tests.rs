@@ -1700,3 +1700,46 @@ fn test_transformer_decoder() {
         ],
     );
 }
+
+#[test]
+fn test_save_load_decoder() {
+    let mut rng = rand::thread_rng();
+    let mut saved: TransformerDecoder<32, 32, 64, 2, 4> = Default::default();
+    saved.reset_params(&mut rng);
+    let state = saved.state_dict();
+    assert_eq!(state.len(), 2 * (4 * 2 + 2 * 2 + 2 * 2));
+    assert!(state.contains_key("1.attn.w_o.weight"));
+    assert!(state.contains_key("0.ff.1.2.bias"));
+
+    let mut loaded: TransformerDecoder<32, 32, 64, 2, 4> = Default::default();
+    loaded.load_state_dict(&state, true).expect("");
+    assert_eq!(loaded.state_dict(), state);
+
+    let mut bytes = Vec::new();
+    let mut options = GgufOptions::new("decoder");
+    options.quantization = GgmlType::Q8_0;
+    saved
+        .write_gguf(&mut bytes, &options, |key| format!("blk.{key}"))
+        .expect("");
+    assert_eq!(&bytes[..4], b"GGUF");
+}
+
+#[test]
+fn test_summary_encoder_and_decoder() {
+    let encoder: TransformerEncoder<4, 8, 2, 2> = Default::default();
+    let summary = Summarize::<Tensor2D<3, 4>>::summary(&encoder);
+    let attn = 4 * (4 * 4 + 4);
+    let ff = (4 * 8 + 8) + (8 * 4 + 4);
+    assert_eq!(summary.total_params(), 2 * (attn + ff + 2 * 8));
+    assert_eq!(summary.layers[2].name, "0.0");
+    assert_eq!(summary.layers[2].layer, "Residual");
+    assert_eq!(summary.layers[3].layer, "MultiHeadAttention<4, 4, 4, 4, 2>");
+    assert_eq!(summary.layers[3].output_shapes, [vec![3, 4]]);
+
+    let decoder: TransformerDecoder<4, 4, 8, 1, 2> = Default::default();
+    let summary = Summarize::<(Tensor2D<3, 4>, Tensor2D<5, 4>)>::summary(&decoder);
+    assert_eq!(summary.input_shapes, [vec![3, 4], vec![5, 4]]);
+    assert_eq!(summary.layers.len(), 2);
+    assert_eq!(summary.layers[1].name, "0");
+    assert_eq!(summary.layers[1].output_shapes, [vec![3, 4]]);
+}
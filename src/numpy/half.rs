@@ -0,0 +1,139 @@
+//! Conversions between `f32` and IEEE 754 half precision floats (`f16`), which are stored as `u16`s.
+
+use super::Endian;
+use std::io::{Read, Result};
+
+/// Converts the bits of an `f16` to an `f32`. This is exact.
+pub(crate) fn f16_to_f32(h: u16) -> f32 {
+    let sign = if h & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = ((h >> 10) & 0x1f) as i32;
+    let frac = (h & 0x3ff) as f32;
+    sign * match exp {
+        0 => frac * 2f32.powi(-24),
+        0x1f if frac == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + frac / 1024.0) * 2f32.powi(exp - 15),
+    }
+}
+
+/// Converts an `f32` to the bits of the nearest `f16` (ties to even). Values that are
+/// too large become infinity, and values that are too small become zero.
+pub(crate) fn f32_to_f16(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let man = bits & 0x7f_ffff;
+    if exp == 0xff {
+        let nan = if man != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    // the exponent with the f16 bias, and the number of mantissa bits that are dropped
+    let e = exp - 127 + 15;
+    let (half, shift) = match e {
+        0x1f.. => return sign | 0x7c00,
+        ..=-11 => return sign,
+        -10..=0 => (0, (14 - e) as u32),
+        _ => ((e as u32) << 10, 13),
+    };
+    // NOTE: the implicit leading 1 becomes explicit for subnormals
+    let man = if e <= 0 { man | 0x80_0000 } else { man };
+    let half = half | (man >> shift);
+    let rem = man & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round_up = rem > halfway || (rem == halfway && half & 1 == 1);
+    // NOTE: rounding up can carry into the exponent, which is still correct
+    sign | (half + round_up as u32) as u16
+}
+
+/// Reads `f16`s from `r` and outputs them as `f32` bytes with the same [Endian].
+pub(super) struct F16Upcast<R> {
+    r: R,
+    endian: Endian,
+    buf: [u8; 4],
+    pos: usize,
+}
+
+impl<R: Read> F16Upcast<R> {
+    pub(super) fn new(r: R, endian: Endian) -> Self {
+        Self {
+            r,
+            endian,
+            buf: [0; 4],
+            pos: 4,
+        }
+    }
+}
+
+impl<R: Read> Read for F16Upcast<R> {
+    fn read(&mut self, out: &mut [u8]) -> Result<usize> {
+        if self.pos == 4 {
+            let mut bytes = [0; 2];
+            self.r.read_exact(&mut bytes)?;
+            let (h, f): (u16, fn(f32) -> [u8; 4]) = match self.endian {
+                Endian::Big => (u16::from_be_bytes(bytes), f32::to_be_bytes),
+                Endian::Little => (u16::from_le_bytes(bytes), f32::to_le_bytes),
+                Endian::Native => (u16::from_ne_bytes(bytes), f32::to_ne_bytes),
+            };
+            self.buf = f(f16_to_f32(h));
+            self.pos = 0;
+        }
+        let n = out.len().min(4 - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f16_to_f32() {
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x3555), 0.33325195);
+        assert_eq!(f16_to_f32(0x0001), 5.9604645e-8);
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+    }
+
+    #[test]
+    fn test_f32_to_f16() {
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(1.0 / 3.0), 0x3555);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xfc00);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f32_to_f16(1e-10), 0);
+
+        // subnormals, including rounding up to the smallest normal
+        assert_eq!(f32_to_f16(5.9604645e-8), 0x0001);
+        assert_eq!(f32_to_f16(3.0e-8), 0x0001);
+        assert_eq!(f32_to_f16(6.1035156e-5), 0x0400);
+        assert_eq!(f32_to_f16(6.103e-5), 0x0400);
+
+        // ties round to even
+        assert_eq!(f32_to_f16(1.0 + 2f32.powi(-11)), 0x3c00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 * 2f32.powi(-11)), 0x3c02);
+
+        for h in (0..0x7c00).step_by(7) {
+            assert_eq!(f32_to_f16(f16_to_f32(h)), h);
+        }
+    }
+
+    #[test]
+    fn test_upcast_reader() {
+        let bytes = [0x00, 0x3c, 0x00, 0xc0];
+        let mut out = Vec::new();
+        F16Upcast::new(&bytes[..], Endian::Little)
+            .take(8)
+            .read_to_end(&mut out)
+            .expect("");
+        assert_eq!(out[..4], 1.0f32.to_le_bytes());
+        assert_eq!(out[4..], (-2.0f32).to_le_bytes());
+    }
+}
@@ -37,7 +37,8 @@ where
 ///
 /// The overall process is:
 /// 1. Read the .npy header.
-/// 2. Make sure T's [NumpyDtype] matches the header's dtype
+/// 2. Make sure T's [NumpyDtype] matches the header's dtype. `f2` (half precision) data
+///    is also accepted for `f4` types, and is converted to `f32`.
 /// 3. Make sure T's [NumpyShape::shape()] matches the header's shape
 /// 4. Parse an [Endian] from header's "descr" field.
 /// 5. Read the data using [ReadNumbers].
@@ -48,8 +49,11 @@ where
     T: NumpyDtype + NumpyShape + ReadNumbers,
     R: Read,
 {
-    let endian = read_header::<T, R>(r)?;
-    t.read_numbers(r, endian)?;
+    let (endian, half) = read_header::<T, R>(r)?;
+    match half {
+        true => t.read_numbers(&mut half::F16Upcast::new(r, endian), endian)?,
+        false => t.read_numbers(r, endian)?,
+    }
     Ok(())
 }
 
@@ -121,29 +125,13 @@ impl From<std::string::FromUtf8Error> for NpyError {
     }
 }
 
-fn read_header<T, R>(r: &mut R) -> Result<Endian, NpyError>
+/// Returns the [Endian] of the data, and whether it is `f2` data for an `f4` type.
+fn read_header<T, R>(r: &mut R) -> Result<(Endian, bool), NpyError>
 where
     T: NumpyDtype + NumpyShape,
     R: Read,
 {
-    let mut magic = [0; 6];
-    r.read_exact(&mut magic)?;
-    if magic != MAGIC_NUMBER {
-        return Err(NpyError::InvalidMagicNumber(magic));
-    }
-
-    let mut version = [0; 2];
-    r.read_exact(&mut version)?;
-    if version != VERSION {
-        return Err(NpyError::InvalidVersion(version));
-    }
-
-    let mut header_len_bytes = [0; 2];
-    r.read_exact(&mut header_len_bytes)?;
-    let header_len = u16::from_le_bytes(header_len_bytes);
-
-    let mut header: Vec<u8> = vec![0; header_len as usize];
-    r.read_exact(&mut header)?;
+    let header = read_header_bytes(r)?;
 
     let mut i = 0;
     i = expect(&header, i, b"{'descr': '")?;
@@ -156,7 +144,12 @@ where
     };
     i += 1;
 
-    i = expect(&header, i, T::DTYPE.as_bytes())?;
+    let half = T::DTYPE == "f4" && header[i..].starts_with(b"f2");
+    if half {
+        i += 2;
+    } else {
+        i = expect(&header, i, T::DTYPE.as_bytes())?;
+    }
     i = expect(&header, i, b"', ")?;
 
     // fortran order
@@ -168,7 +161,29 @@ where
     i = expect(&header, i, shape_str.as_bytes())?;
     expect(&header, i, b"), }")?;
 
-    Ok(endian)
+    Ok((endian, half))
+}
+
+fn read_header_bytes<R: Read>(r: &mut R) -> Result<Vec<u8>, NpyError> {
+    let mut magic = [0; 6];
+    r.read_exact(&mut magic)?;
+    if magic != MAGIC_NUMBER {
+        return Err(NpyError::InvalidMagicNumber(magic));
+    }
+
+    let mut version = [0; 2];
+    r.read_exact(&mut version)?;
+    if version != VERSION {
+        return Err(NpyError::InvalidVersion(version));
+    }
+
+    let mut header_len_bytes = [0; 2];
+    r.read_exact(&mut header_len_bytes)?;
+    let header_len = u16::from_le_bytes(header_len_bytes);
+
+    let mut header: Vec<u8> = vec![0; header_len as usize];
+    r.read_exact(&mut header)?;
+    Ok(header)
 }
 
 fn expect(buf: &[u8], i: usize, chars: &[u8]) -> Result<usize, NpyError> {
@@ -222,6 +237,19 @@ impl ReadNumbers for f64 {
     }
 }
 
+impl ReadNumbers for u64 {
+    fn read_numbers<R: Read>(&mut self, r: &mut R, endian: Endian) -> std::io::Result<()> {
+        let mut bytes = [0; 8];
+        r.read_exact(&mut bytes)?;
+        *self = match endian {
+            Endian::Big => Self::from_be_bytes(bytes),
+            Endian::Little => Self::from_le_bytes(bytes),
+            Endian::Native => Self::from_ne_bytes(bytes),
+        };
+        Ok(())
+    }
+}
+
 impl<T: ReadNumbers, const M: usize> ReadNumbers for [T; M] {
     fn read_numbers<R: Read>(&mut self, r: &mut R, endian: Endian) -> std::io::Result<()> {
         for self_i in self.iter_mut() {
@@ -298,4 +326,18 @@ mod tests {
         let mut value = [[0.0f32; 2]; 3];
         assert!(load(file.path(), &mut value).is_err());
     }
+
+    #[test]
+    fn test_f16_load() {
+        let mut bytes = Vec::new();
+        write_raw_header(&mut bytes, Endian::Big, "f2", vec![2]).expect("");
+        bytes.extend_from_slice(&[0x3c, 0x00, 0xc0, 0x00]);
+
+        let mut v = [0.0f32; 2];
+        read(&mut bytes.as_slice(), &mut v).expect("");
+        assert_eq!(v, [1.0, -2.0]);
+
+        let mut v = [0.0f64; 2];
+        read(&mut bytes.as_slice(), &mut v).expect_err("");
+    }
 }
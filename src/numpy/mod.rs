@@ -1,9 +1,11 @@
 //! Provides some generic functions to load & save Nd arrays in the [.npy](https://numpy.org/devdocs/reference/generated/numpy.lib.format.html)
 //! format. See [load()] and [save()]
 
+mod half;
 mod load;
 mod save;
 
+pub(crate) use half::{f16_to_f32, f32_to_f16};
 pub use load::*;
 pub use save::*;
 
@@ -44,6 +46,10 @@ impl NumpyDtype for f64 {
     const DTYPE: &'static str = "f8";
 }
 
+impl NumpyDtype for u64 {
+    const DTYPE: &'static str = "u8";
+}
+
 impl<T: NumpyDtype, const M: usize> NumpyDtype for [T; M] {
     const DTYPE: &'static str = T::DTYPE;
 }
@@ -62,6 +68,7 @@ pub trait NumpyShape {
 
 impl NumpyShape for f32 {}
 impl NumpyShape for f64 {}
+impl NumpyShape for u64 {}
 
 impl<T: NumpyShape, const M: usize> NumpyShape for [T; M] {
     fn shape() -> Vec<usize> {
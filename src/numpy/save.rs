@@ -51,7 +51,17 @@ where
     T: NumpyDtype + NumpyShape,
     W: Write,
 {
-    let shape_str = to_shape_str(T::shape());
+    write_raw_header(w, endian, T::DTYPE, T::shape())
+}
+
+/// Writes a .npy header for data with `dtype` and `shape` that are only known at runtime.
+pub(crate) fn write_raw_header<W: Write>(
+    w: &mut W,
+    endian: Endian,
+    dtype: &str,
+    shape: Vec<usize>,
+) -> Result<()> {
+    let shape_str = to_shape_str(shape);
 
     let mut header: Vec<u8> = Vec::new();
     write!(
@@ -62,12 +72,12 @@ where
             Endian::Little => '<',
             Endian::Native => '=',
         },
-        T::DTYPE,
+        dtype,
         shape_str,
     )?;
 
     // padding
-    while (header.len() + 1) % 64 != 0 {
+    while !(header.len() + 1).is_multiple_of(64) {
         header.write_all(b"\x20")?;
     }
 
@@ -76,7 +86,7 @@ where
 
     // header length
     assert!(header.len() < u16::MAX as usize);
-    assert!(header.len() % 64 == 0);
+    assert!(header.len().is_multiple_of(64));
 
     w.write_all(MAGIC_NUMBER)?; // magic number
     w.write_all(VERSION)?; // version major & minor
@@ -112,6 +122,16 @@ impl WriteNumbers for f64 {
     }
 }
 
+impl WriteNumbers for u64 {
+    fn write_numbers<W: Write>(&self, w: &mut W, endian: Endian) -> Result<()> {
+        match endian {
+            Endian::Big => w.write_all(&self.to_be_bytes()),
+            Endian::Little => w.write_all(&self.to_le_bytes()),
+            Endian::Native => w.write_all(&self.to_ne_bytes()),
+        }
+    }
+}
+
 impl<T: WriteNumbers, const M: usize> WriteNumbers for [T; M] {
     fn write_numbers<W: Write>(&self, w: &mut W, endian: Endian) -> Result<()> {
         for self_i in self.iter() {
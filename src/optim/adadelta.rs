@@ -0,0 +1,241 @@
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// An implementation of the Adadelta optimizer from
+/// [ADADELTA: An Adaptive Learning Rate Method](https://arxiv.org/abs/1212.5701).
+///
+/// Based on [pytorch's implementation](https://pytorch.org/docs/stable/generated/torch.optim.Adadelta.html),
+/// which also scales the update by a learning rate.
+///
+/// # Example Usage
+///
+/// Constructing using default:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: Adadelta<Model> = Default::default();
+/// ```
+///
+/// Constructing using new:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: Adadelta<Model> = Adadelta::new(AdadeltaConfig {
+///     lr: 0.5,
+///     rho: 0.95,
+///     eps: 1e-6,
+///     grad_clip: None,
+/// });
+/// ```
+///
+/// See module level documentation at [crate::optim] for examples of how to actually use an optimizer.
+#[derive(Debug)]
+pub struct Adadelta<M> {
+    /// Hyperparameter configuration
+    pub cfg: AdadeltaConfig,
+
+    square_avg: Gradients,
+    delta_avg: Gradients,
+    gradients: Gradients,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [Adadelta].
+#[derive(Debug, Clone, Copy)]
+pub struct AdadeltaConfig {
+    /// Coefficient that scales the computed update. Defaults to `1.0`.
+    pub lr: f32,
+
+    /// Decay rate of the running averages of squared gradients and squared updates. Defaults to `0.9`.
+    pub rho: f32,
+
+    /// Epsilon for numerical stability. Defaults to `1e-6`.
+    pub eps: f32,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
+}
+
+impl Default for AdadeltaConfig {
+    fn default() -> Self {
+        Self {
+            lr: 1.0,
+            rho: 0.9,
+            eps: 1e-6,
+            grad_clip: None,
+        }
+    }
+}
+
+impl<M> Default for Adadelta<M> {
+    /// See [AdadeltaConfig]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<M> Adadelta<M> {
+    /// Constructs using hyperparameters from `cfg`.
+    pub fn new(cfg: AdadeltaConfig) -> Self {
+        Self {
+            cfg,
+            square_avg: Default::default(),
+            delta_avg: Default::default(),
+            gradients: Default::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M> GradientProvider for Adadelta<M> {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let mut g_t = self.gradients.remove(p)?;
+        let square_avg = self.square_avg.mut_gradient(p);
+        let delta_avg = self.delta_avg.mut_gradient(p);
+        let AdadeltaConfig { lr, rho, eps, .. } = self.cfg;
+        P::Device::foreach_mmm(g_t.as_mut(), square_avg, delta_avg, &mut |g, sa, da| {
+            *sa = *sa * rho + g.powi(2) * (1.0 - rho);
+            let delta = (*da + eps).sqrt() / (*sa + eps).sqrt() * *g;
+            *da = *da * rho + delta.powi(2) * (1.0 - rho);
+            *g = lr * delta;
+        });
+        Some(g_t)
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for Adadelta<M> {
+    fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
+        self.begin_step(module, gradients);
+        let mut unused_tensors = Default::default();
+        module.update(self, &mut unused_tensors);
+        unused_tensors.into()
+    }
+}
+
+impl<M> OptimizerStep for Adadelta<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for Adadelta<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for Adadelta<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}square_avg"),
+            module,
+            &self.square_avg,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}delta_avg"),
+            module,
+            &self.delta_avg,
+        )?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for Adadelta<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}square_avg"),
+            module,
+            &mut self.square_avg,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}delta_avg"),
+            module,
+            &mut self.delta_avg,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    fn test_matches_expected(cfg: AdadeltaConfig, expected: [[f32; 5]; 5]) {
+        let rate = Tensor1D::new([0.1, 1.0, 2.0, 10.0, 100.0]);
+        let mut t: Tensor1D<5> = Tensor1D::ones();
+        let mut opt = Adadelta::new(cfg);
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().sum().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_adadelta_default() {
+        const EXPECTED: [[f32; 5]; 5] = [
+            [0.99687654, 0.99683774, 0.99683774, 0.99683774, 0.99683774],
+            [0.9936778, 0.99359816, 0.99359816, 0.99359816, 0.99359816],
+            [0.99043053, 0.99030906, 0.99030906, 0.99030906, 0.99030906],
+            [0.9871482, 0.986984, 0.986984, 0.986984, 0.986984],
+            [0.9838387, 0.9836313, 0.9836313, 0.9836313, 0.9836313],
+        ];
+        test_matches_expected(Default::default(), EXPECTED);
+    }
+
+    #[test]
+    fn test_adadelta_custom() {
+        const CFG: AdadeltaConfig = AdadeltaConfig {
+            lr: 0.5,
+            rho: 0.5,
+            eps: 1e-2,
+            grad_clip: None,
+        };
+        const EXPECTED: [[f32; 5]; 5] = [
+            [0.99009854, 0.9294654, 0.92930037, 0.92928934, 0.92928934],
+            [0.9802457, 0.850123, 0.84973407, 0.8497081, 0.8497081],
+            [0.9704661, 0.7660623, 0.7654033, 0.7653593, 0.7653592],
+            [0.96077126, 0.67967516, 0.67870474, 0.6786398, 0.6786398],
+            [0.9511669, 0.5927247, 0.5914036, 0.5913152, 0.5913151],
+        ];
+        test_matches_expected(CFG, EXPECTED);
+    }
+
+    #[test]
+    fn test_adadelta_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: Adadelta<Model> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
@@ -0,0 +1,258 @@
+use super::state::{read_step, write_step};
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// An implementation of the Adagrad optimizer from
+/// [Adaptive Subgradient Methods for Online Learning and Stochastic Optimization](https://www.jmlr.org/papers/volume12/duchi11a/duchi11a.pdf).
+///
+/// Based on [pytorch's implementation](https://pytorch.org/docs/stable/generated/torch.optim.Adagrad.html).
+///
+/// # Example Usage
+///
+/// Constructing using default:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: Adagrad<Model> = Default::default();
+/// ```
+///
+/// Constructing using new:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: Adagrad<Model> = Adagrad::new(AdagradConfig {
+///     lr: 1e-1,
+///     lr_decay: 1e-3,
+///     initial_accumulator_value: 0.1,
+///     eps: 1e-10,
+///     grad_clip: None,
+/// });
+/// ```
+///
+/// See module level documentation at [crate::optim] for examples of how to actually use an optimizer.
+#[derive(Debug)]
+pub struct Adagrad<M> {
+    /// Hyperparameter configuration
+    pub cfg: AdagradConfig,
+
+    step: usize,
+    sum_sq: Gradients,
+    gradients: Gradients,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [Adagrad].
+#[derive(Debug, Clone, Copy)]
+pub struct AdagradConfig {
+    /// Learning rate. Defaults to `1e-2`.
+    pub lr: f32,
+
+    /// The learning rate at step `t` is `lr / (1 + (t - 1) * lr_decay)`. Defaults to `0.0`.
+    pub lr_decay: f32,
+
+    /// The starting value of the sum of squared gradients. Defaults to `0.0`.
+    pub initial_accumulator_value: f32,
+
+    /// Epsilon for numerical stability. Defaults to `1e-10`.
+    pub eps: f32,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
+}
+
+impl Default for AdagradConfig {
+    fn default() -> Self {
+        Self {
+            lr: 1e-2,
+            lr_decay: 0.0,
+            initial_accumulator_value: 0.0,
+            eps: 1e-10,
+            grad_clip: None,
+        }
+    }
+}
+
+impl<M> Default for Adagrad<M> {
+    /// See [AdagradConfig]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<M> Adagrad<M> {
+    /// Constructs using hyperparameters from `cfg`.
+    pub fn new(cfg: AdagradConfig) -> Self {
+        Self {
+            cfg,
+            step: 0,
+            sum_sq: Default::default(),
+            gradients: Default::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M> GradientProvider for Adagrad<M> {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let mut g_t = self.gradients.remove(p)?;
+
+        if self.sum_sq.get(p).is_none() {
+            let init = self.cfg.initial_accumulator_value;
+            self.sum_sq.insert(p, P::Device::filled(&mut |v| *v = init));
+        }
+        let sum_sq = self.sum_sq.mut_gradient(p);
+
+        let lr = self.cfg.lr / (1.0 + (self.step - 1) as f32 * self.cfg.lr_decay);
+        P::Device::foreach_mm(g_t.as_mut(), sum_sq, &mut |g, s| {
+            *s += g.powi(2);
+            *g *= lr / (s.sqrt() + self.cfg.eps);
+        });
+        Some(g_t)
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for Adagrad<M> {
+    fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
+        self.begin_step(module, gradients);
+        let mut unused_tensors = Default::default();
+        module.update(self, &mut unused_tensors);
+        unused_tensors.into()
+    }
+}
+
+impl<M> OptimizerStep for Adagrad<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.step = self.step.checked_add(1).unwrap();
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for Adagrad<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for Adagrad<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_step(w, format!("{filename_prefix}step.npy"), self.step)?;
+        write_param_buffer(w, &format!("{filename_prefix}sum_sq"), module, &self.sum_sq)?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for Adagrad<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        self.step = read_step(r, format!("{filename_prefix}step.npy"))?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}sum_sq"),
+            module,
+            &mut self.sum_sq,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    fn test_matches_expected(cfg: AdagradConfig, expected: [[f32; 5]; 5]) {
+        let rate = Tensor1D::new([0.1, 1.0, 2.0, 10.0, 100.0]);
+        let mut t: Tensor1D<5> = Tensor1D::ones();
+        let mut opt = Adagrad::new(cfg);
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().sum().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_adagrad_default() {
+        const EXPECTED: [[f32; 5]; 5] = [
+            [0.99; 5],
+            [0.982_964_6; 5],
+            [0.97723794; 5],
+            [0.97229034; 5],
+            [0.9678739; 5],
+        ];
+        test_matches_expected(Default::default(), EXPECTED);
+    }
+
+    #[test]
+    fn test_adagrad_lr_decay_and_initial_accumulator() {
+        const CFG: AdagradConfig = AdagradConfig {
+            lr: 1e-1,
+            lr_decay: 0.5,
+            initial_accumulator_value: 0.1,
+            eps: 1e-10,
+            grad_clip: None,
+        };
+        const EXPECTED: [[f32; 5]; 5] = [
+            [0.99368805, 0.90122706, 0.90007806, 0.9000001, 0.9],
+            [0.9895148, 0.8569005, 0.8554975, 0.8554025, 0.85540235],
+            [0.9864041, 0.83018225, 0.82867706, 0.8285752, 0.828575],
+            [0.98392814, 0.81179243, 0.8102343, 0.8101289, 0.8101288],
+            [0.9818739, 0.7981247, 0.79653513, 0.79642767, 0.7964275],
+        ];
+        test_matches_expected(CFG, EXPECTED);
+    }
+
+    #[test]
+    fn test_adagrad_initial_accumulator_for_late_params() {
+        let cfg = AdagradConfig {
+            initial_accumulator_value: 0.1,
+            ..Default::default()
+        };
+        let mut model: (Tensor1D<2>, Tensor1D<2>) = (Tensor1D::ones(), Tensor1D::ones());
+        let mut opt = Adagrad::new(cfg);
+        let gradients = model.0.trace().square().sum().backward();
+        opt.update(&mut model, gradients).expect_err("");
+        let gradients = model.1.trace().square().sum().backward();
+        opt.update(&mut model, gradients).expect_err("");
+
+        // the second param gets its first gradient at step 2, but starts from the same
+        // accumulator as a param that is updated from the first step
+        let mut t: Tensor1D<2> = Tensor1D::ones();
+        let mut opt = Adagrad::new(cfg);
+        let gradients = t.trace().square().sum().backward();
+        opt.update(&mut t, gradients).expect("");
+        assert_close(model.1.data(), t.data());
+    }
+
+    #[test]
+    fn test_adagrad_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: Adagrad<Model> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
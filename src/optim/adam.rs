@@ -1,5 +1,8 @@
+use super::state::{read_step, write_step};
 use crate::prelude::*;
+use std::io::{Read, Seek, Write};
 use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 /// An implementation of the Adam optimizer from
 /// [Adam: A Method for Stochastic Optimization](https://arxiv.org/abs/1412.6980)
@@ -21,6 +24,7 @@ use std::marker::PhantomData;
 ///     lr: 1e-2,
 ///     betas: [0.5, 0.25],
 ///     eps: 1e-6,
+///     grad_clip: None,
 /// });
 /// ```
 ///
@@ -47,6 +51,7 @@ pub struct Adam<M> {
 ///     lr: 1e-2,
 ///     betas: [0.1, 0.2],
 ///     eps: 1e-6,
+///     grad_clip: None,
 /// };
 /// ```
 #[derive(Debug, Clone, Copy)]
@@ -59,6 +64,9 @@ pub struct AdamConfig {
 
     /// Epsilon for numerical stability. Defaults to `1e-8`.
     pub eps: f32,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
 }
 
 impl Default for AdamConfig {
@@ -67,6 +75,7 @@ impl Default for AdamConfig {
             lr: 1e-3,
             betas: [0.9, 0.999],
             eps: 1e-8,
+            grad_clip: None,
         }
     }
 }
@@ -92,17 +101,23 @@ impl<M> Adam<M> {
     }
 }
 
+/// Updates the exponential moving averages of the gradient `m` and the squared gradient `v`.
+/// This is shared by all the optimizers that are variants of [Adam].
+pub(super) fn update_moments(g: f32, m: &mut f32, v: &mut f32, betas: [f32; 2]) {
+    *m = *m * betas[0] + g * (1.0 - betas[0]);
+    *v = *v * betas[1] + g.powi(2) * (1.0 - betas[1]);
+}
+
 impl<M> GradientProvider for Adam<M> {
     fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
     where
-        P: HasUniqueId + HasArrayType<Dtype = f32> + HasDevice,
+        P: Tensor<Dtype = f32>,
     {
         let mut g_t = self.gradients.remove(p)?;
         let m_t = self.moment1.mut_gradient(p);
         let v_t = self.moment2.mut_gradient(p);
         P::Device::foreach_mmm(g_t.as_mut(), m_t, v_t, &mut |g, m, v| {
-            *m = *m * self.cfg.betas[0] + *g * (1.0 - self.cfg.betas[0]);
-            *v = *v * self.cfg.betas[1] + g.powi(2) * (1.0 - self.cfg.betas[1]);
+            update_moments(*g, m, v, self.cfg.betas);
             let m_hat = *m * (1.0 - self.cfg.betas[0].powi(self.t)).recip();
             let v_hat = *v * (1.0 - self.cfg.betas[1].powi(self.t)).recip();
             *g = self.cfg.lr * m_hat / (v_hat.sqrt() + self.cfg.eps)
@@ -111,16 +126,83 @@ impl<M> GradientProvider for Adam<M> {
     }
 }
 
-impl<M: CanUpdateWithGradients> Optimizer<M> for Adam<M> {
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for Adam<M> {
     fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
-        self.t = self.t.checked_add(1).unwrap();
-        self.gradients = gradients;
+        self.begin_step(module, gradients);
         let mut unused_tensors = Default::default();
         module.update(self, &mut unused_tensors);
         unused_tensors.into()
     }
 }
 
+impl<M> OptimizerStep for Adam<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.t = self.t.checked_add(1).unwrap();
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for Adam<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for Adam<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_step(w, format!("{filename_prefix}t.npy"), self.t as usize)?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &self.moment1,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for Adam<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        self.t = read_step(r, format!("{filename_prefix}t.npy"))? as i32;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &mut self.moment1,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &mut self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,6 +240,7 @@ mod tests {
             lr: 1e-3,
             betas: [0.5, 0.25],
             eps: 1e-8,
+            grad_clip: None,
         });
         let mut t: Tensor1D<5> = Tensor1D::ones();
         let rate = Tensor1D::new([1e-4, 1e-3, 1e-2, 1e-1, 1e-0]);
@@ -195,6 +278,7 @@ mod tests {
             lr: 1e-3,
             betas: [0.9, 0.999],
             eps: 1e-8,
+            grad_clip: None,
         });
 
         let py = model.forward(x.trace());
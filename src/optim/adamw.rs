@@ -0,0 +1,312 @@
+use super::adam::update_moments;
+use super::state::{read_step, write_step};
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// An implementation of the AdamW optimizer from
+/// [Decoupled Weight Decay Regularization](https://arxiv.org/abs/1711.05101).
+///
+/// The difference from [Adam] is that weight decay is applied directly to the parameters,
+/// instead of being added to the gradient before the moments are computed.
+///
+/// # Example Usage
+///
+/// Constructing using default:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: AdamW<Model> = Default::default();
+/// ```
+///
+/// Changing using new
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: AdamW<Model> = AdamW::new(AdamWConfig {
+///     lr: 1e-2,
+///     betas: [0.5, 0.25],
+///     eps: 1e-6,
+///     weight_decay: 1e-1,
+///     grad_clip: None,
+/// });
+/// ```
+///
+/// See module level documentation at [crate::optim] for examples of how to actually use an optimizer.
+#[derive(Debug)]
+pub struct AdamW<M> {
+    /// Hyperparameter configuration
+    pub cfg: AdamWConfig,
+
+    t: i32,
+    gradients: Gradients,
+    moment1: Gradients,
+    moment2: Gradients,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [AdamW].
+///
+/// Changing all default parameters:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// AdamWConfig {
+///     lr: 1e-2,
+///     betas: [0.1, 0.2],
+///     eps: 1e-6,
+///     weight_decay: 1e-1,
+///     grad_clip: None,
+/// };
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct AdamWConfig {
+    /// Learning rate. Defaults to `1e-3`.
+    pub lr: f32,
+
+    /// Betas from Adam paper. Defaults to `[0.9, 0.999]`.
+    pub betas: [f32; 2],
+
+    /// Epsilon for numerical stability. Defaults to `1e-8`.
+    pub eps: f32,
+
+    /// Decoupled weight decay. Each update, parameters are scaled by `1 - lr * weight_decay`.
+    /// Defaults to `1e-2`.
+    pub weight_decay: f32,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
+}
+
+impl Default for AdamWConfig {
+    fn default() -> Self {
+        Self {
+            lr: 1e-3,
+            betas: [0.9, 0.999],
+            eps: 1e-8,
+            weight_decay: 1e-2,
+            grad_clip: None,
+        }
+    }
+}
+
+impl<M> Default for AdamW<M> {
+    /// See [AdamWConfig]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<M> AdamW<M> {
+    /// Constructs using hyperparameters from `cfg`.
+    pub fn new(cfg: AdamWConfig) -> Self {
+        Self {
+            cfg,
+            t: 0,
+            gradients: Default::default(),
+            moment1: Default::default(),
+            moment2: Default::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M> GradientProvider for AdamW<M> {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let mut g_t = self.gradients.remove(p)?;
+        let m_t = self.moment1.mut_gradient(p);
+        let v_t = self.moment2.mut_gradient(p);
+        P::Device::foreach_mmm(g_t.as_mut(), m_t, v_t, &mut |g, m, v| {
+            update_moments(*g, m, v, self.cfg.betas);
+            let m_hat = *m * (1.0 - self.cfg.betas[0].powi(self.t)).recip();
+            let v_hat = *v * (1.0 - self.cfg.betas[1].powi(self.t)).recip();
+            *g = self.cfg.lr * m_hat / (v_hat.sqrt() + self.cfg.eps)
+        });
+        // NOTE: decay is added after the moments are computed, so it doesn't affect them
+        P::Device::foreach_mr(g_t.as_mut(), p.data(), &mut |g, p| {
+            *g += self.cfg.lr * self.cfg.weight_decay * p;
+        });
+        Some(g_t)
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for AdamW<M> {
+    fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
+        self.begin_step(module, gradients);
+        let mut unused_tensors = Default::default();
+        module.update(self, &mut unused_tensors);
+        unused_tensors.into()
+    }
+}
+
+impl<M> OptimizerStep for AdamW<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.t = self.t.checked_add(1).unwrap();
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for AdamW<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for AdamW<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_step(w, format!("{filename_prefix}t.npy"), self.t as usize)?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &self.moment1,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for AdamW<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        self.t = read_step(r, format!("{filename_prefix}t.npy"))? as i32;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &mut self.moment1,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &mut self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::*, SeedableRng};
+
+    #[test]
+    fn test_default_adamw_params() {
+        let mut opt = AdamW::default();
+        let mut t: Tensor1D<5> = Tensor1D::ones();
+        let rate = Tensor1D::new([1e-6, 1e-5, 1e-4, 1e-3, 1e-2]);
+        let expected = [
+            [0.99999, 0.999986, 0.9997043, 0.9990144, 0.99899024],
+            [0.9999799, 0.99997205, 0.9994086, 0.9980288, 0.99798054],
+            [0.9999699, 0.999958, 0.99911296, 0.9970433, 0.9969709],
+            [0.9999598, 0.9999441, 0.9988174, 0.9960579, 0.9959613],
+            [0.9999498, 0.9999301, 0.9985218, 0.9950726, 0.9949518],
+            [0.99993974, 0.9999161, 0.9982263, 0.9940874, 0.993_942_4],
+            [0.9999297, 0.9999021, 0.99793077, 0.9931023, 0.99293315],
+            [0.9999197, 0.9998881, 0.99763536, 0.99211735, 0.991924],
+            [0.99990964, 0.999_874_2, 0.99733996, 0.9911326, 0.99091506],
+            [0.9998996, 0.99986017, 0.9970446, 0.990148, 0.98990625],
+        ];
+
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().mean().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_custom_adamw_params() {
+        let mut opt: AdamW<Tensor1D<5>> = AdamW::new(AdamWConfig {
+            lr: 1e-2,
+            betas: [0.5, 0.25],
+            eps: 1e-8,
+            weight_decay: 1.0,
+            grad_clip: None,
+        });
+        let mut t: Tensor1D<5> = Tensor1D::ones();
+        let rate = Tensor1D::new([1e-4, 1e-3, 1e-2, 1e-1, 1e-0]);
+        let expected = [
+            [0.98714286, 0.9802439, 0.9800025, 0.98, 0.98],
+            [0.97443044, 0.960_663_4, 0.96017826, 0.9601733, 0.96017325],
+            [0.9618636, 0.94125295, 0.9405209, 0.9405134, 0.9405133],
+            [0.949443, 0.92201483, 0.9210324, 0.92102236, 0.92102224],
+            [0.937169, 0.9029541, 0.9017178, 0.9017051, 0.901705],
+            [0.92504144, 0.88407516, 0.8825815, 0.88256615, 0.88256603],
+            [0.9130597, 0.86538094, 0.8636265, 0.8636085, 0.8636084],
+            [0.90122277, 0.8468729, 0.8448543, 0.84483355, 0.8448333],
+            [0.8895294, 0.8285515, 0.826265, 0.82624143, 0.8262412],
+            [0.87797827, 0.810416, 0.807858, 0.8078317, 0.8078314],
+        ];
+
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().mean().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_adamw_changes_all_params() {
+        type Model = (Linear<5, 16>, ReLU, Linear<16, 16>, ReLU, Linear<16, 10>);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let model_0 = model.clone();
+
+        let x: Tensor2D<16, 5> = Tensor2D::rand(&mut rng);
+        let y: Tensor2D<16, 10> = Tensor2D::rand(&mut rng);
+        let mut opt: AdamW<Model> = Default::default();
+
+        let py = model.forward(x.trace());
+        let loss = (py - &y).square().mean();
+        let gradients = loss.backward();
+        opt.update(&mut model, gradients).expect("");
+
+        let model_1 = model.clone();
+
+        assert!(model_0.0.weight.data() != model_1.0.weight.data());
+        assert!(model_0.0.bias.data() != model_1.0.bias.data());
+        assert!(model_0.2.weight.data() != model_1.2.weight.data());
+        assert!(model_0.2.bias.data() != model_1.2.bias.data());
+        assert!(model_0.4.weight.data() != model_1.4.weight.data());
+        assert!(model_0.4.bias.data() != model_1.4.bias.data());
+    }
+
+    #[test]
+    fn test_adamw_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: AdamW<Model> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
@@ -0,0 +1,354 @@
+use crate::devices::{flat, flat_mut};
+use crate::prelude::*;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// Sums data across multiple processes that train the same model, e.g. on different machines.
+/// Used by [all_reduce_gradients()] to average the gradients of all processes.
+///
+/// [TcpAllReduce] is a backend that only needs the standard library. Other backends (e.g. MPI)
+/// can be plugged in by implementing this trait.
+pub trait AllReduce {
+    /// The number of processes.
+    fn world_size(&self) -> usize;
+
+    /// Replaces `data` with the element-wise sum of `data` of all processes. Every process has to
+    /// call this the same number of times, with the same length of `data`.
+    fn all_reduce_sum(&mut self, data: &mut [f32]) -> io::Result<()>;
+}
+
+/// An [AllReduce] for a single process, which doesn't change the data.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SingleProcess;
+
+impl AllReduce for SingleProcess {
+    fn world_size(&self) -> usize {
+        1
+    }
+
+    fn all_reduce_sum(&mut self, _: &mut [f32]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Averages the gradients of `module`'s parameters across all processes of `backend`. Call this
+/// after [backward()] and before [Optimizer::update()] in every process.
+///
+/// A parameter that has no gradient in some processes counts as a gradient of `0.0` in those.
+/// Parameters without a gradient in any process still have no gradient afterwards.
+/// Frozen parameters (see [Freeze]) are skipped.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # fn main() -> std::io::Result<()> {
+/// type Model = (Linear<5, 16>, ReLU, Linear<16, 2>);
+/// let mut model: Model = Default::default();
+/// let mut opt: Sgd<Model> = Default::default();
+/// // e.g. `TcpAllReduce::connect("10.0.0.1:29500")?` in all but the first process
+/// let mut backend = SingleProcess;
+///
+/// let x: Tensor2D<4, 5> = Tensor2D::zeros();
+/// let mut gradients = model.forward(x.trace()).square().mean().backward();
+/// all_reduce_gradients(&model, &mut gradients, &mut backend)?;
+/// opt.update(&mut model, gradients).expect("unused params");
+/// # Ok(())
+/// # }
+/// ```
+pub fn all_reduce_gradients<M, A>(
+    module: &M,
+    gradients: &mut Gradients,
+    backend: &mut A,
+) -> io::Result<()>
+where
+    M: VisitParams,
+    A: AllReduce + ?Sized,
+{
+    // the gradients are reduced in a single buffer, followed by how many processes had a
+    // gradient for each parameter
+    let mut flatten = Flatten {
+        gradients,
+        data: Vec::new(),
+        counts: Vec::new(),
+    };
+    module.visit_params("", &mut flatten);
+    let Flatten {
+        gradients,
+        mut data,
+        counts,
+    } = flatten;
+    let num_params = counts.len();
+    data.extend(counts);
+
+    backend.all_reduce_sum(&mut data)?;
+
+    let counts = data.split_off(data.len() - num_params);
+    let mut unflatten = Unflatten {
+        gradients,
+        data: &data,
+        counts: counts.into_iter(),
+        scale: 1.0 / backend.world_size() as f32,
+    };
+    module.visit_params("", &mut unflatten);
+    Ok(())
+}
+
+/// Appends each gradient (or zeros) to `data`, and whether it exists to `counts`.
+struct Flatten<'a> {
+    gradients: &'a mut Gradients,
+    data: Vec<f32>,
+    counts: Vec<f32>,
+}
+
+impl ParamVisitor for Flatten<'_> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &P) {
+        if !p.requires_grad() {
+            return;
+        }
+        match self.gradients.get(p) {
+            Some(g) => {
+                self.data.extend_from_slice(flat(g));
+                self.counts.push(1.0);
+            }
+            None => {
+                let len = self.data.len();
+                self.data.resize(len + flat(p.data()).len(), 0.0);
+                self.counts.push(0.0);
+            }
+        }
+    }
+}
+
+/// Copies the next part of `data` times `scale` into each gradient, if any process had it.
+struct Unflatten<'a> {
+    gradients: &'a mut Gradients,
+    data: &'a [f32],
+    counts: std::vec::IntoIter<f32>,
+    scale: f32,
+}
+
+impl ParamVisitor for Unflatten<'_> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &P) {
+        if !p.requires_grad() {
+            return;
+        }
+        let (data, rest) = self.data.split_at(flat(p.data()).len());
+        self.data = rest;
+        if self.counts.next().unwrap() > 0.0 {
+            let g = flat_mut(self.gradients.mut_gradient(p));
+            g.iter_mut()
+                .zip(data)
+                .for_each(|(g, d)| *g = d * self.scale);
+        }
+    }
+}
+
+/// An [AllReduce] over TCP. The first process (rank 0) listens for the others with
+/// [TcpAllReduce::root()], and every other process connects to it with [TcpAllReduce::connect()].
+/// The first process sums the data of all processes, and sends the sum back to them.
+///
+/// Example:
+/// ```rust,no_run
+/// # use dfdx::prelude::*;
+/// # fn main() -> std::io::Result<()> {
+/// # let rank = 0;
+/// let mut backend = if rank == 0 {
+///     TcpAllReduce::root(std::net::TcpListener::bind("0.0.0.0:29500")?, 4)?
+/// } else {
+///     TcpAllReduce::connect("10.0.0.1:29500")?
+/// };
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TcpAllReduce {
+    rank: usize,
+    world_size: usize,
+
+    /// The connections to all other processes for rank 0, and to rank 0 otherwise.
+    streams: Vec<TcpStream>,
+}
+
+impl TcpAllReduce {
+    /// Creates the first process of `world_size` processes, and waits until the other
+    /// processes connected to `listener`.
+    ///
+    /// **Panics** if `world_size` is 0.
+    pub fn root(listener: TcpListener, world_size: usize) -> io::Result<Self> {
+        assert!(world_size > 0, "world_size must be at least 1");
+        let mut streams = Vec::with_capacity(world_size - 1);
+        for rank in 1..world_size {
+            let (mut stream, _) = listener.accept()?;
+            stream.set_nodelay(true)?;
+            write_u64(&mut stream, rank as u64)?;
+            write_u64(&mut stream, world_size as u64)?;
+            streams.push(stream);
+        }
+        Ok(Self {
+            rank: 0,
+            world_size,
+            streams,
+        })
+    }
+
+    /// Connects to the first process at `addr`, which assigns the rank of this process.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let rank = read_u64(&mut stream)? as usize;
+        let world_size = read_u64(&mut stream)? as usize;
+        Ok(Self {
+            rank,
+            world_size,
+            streams: vec![stream],
+        })
+    }
+
+    /// The index of this process, from `0` to `world_size - 1`.
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+}
+
+impl AllReduce for TcpAllReduce {
+    fn world_size(&self) -> usize {
+        self.world_size
+    }
+
+    fn all_reduce_sum(&mut self, data: &mut [f32]) -> io::Result<()> {
+        if self.rank != 0 {
+            let stream = &mut self.streams[0];
+            write_f32s(stream, data)?;
+            return read_f32s(stream, data);
+        }
+
+        let mut buf = vec![0.0; data.len()];
+        for stream in self.streams.iter_mut() {
+            read_f32s(stream, &mut buf)?;
+            data.iter_mut().zip(buf.iter()).for_each(|(d, b)| *d += b);
+        }
+        for stream in self.streams.iter_mut() {
+            write_f32s(stream, data)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_u64(w: &mut impl Write, v: u64) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Writes the length of `data` and its elements.
+fn write_f32s(w: &mut impl Write, data: &[f32]) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(8 + 4 * data.len());
+    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    data.iter()
+        .for_each(|x| bytes.extend_from_slice(&x.to_le_bytes()));
+    w.write_all(&bytes)
+}
+
+/// Reads as many elements as `data` has, which have to be as many as were written.
+fn read_f32s(r: &mut impl Read, data: &mut [f32]) -> io::Result<()> {
+    let len = read_u64(r)? as usize;
+    if len != data.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "expected {} values, but another process sent {len}",
+                data.len()
+            ),
+        ));
+    }
+    let mut bytes = vec![0; 4 * len];
+    r.read_exact(&mut bytes)?;
+    for (x, b) in data.iter_mut().zip(bytes.chunks_exact(4)) {
+        *x = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `f` on `world_size` threads that are connected with [TcpAllReduce].
+    fn run<T: Send, F: Fn(TcpAllReduce) -> T + Sync>(world_size: usize, f: F) -> Vec<T> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::scope(|s| {
+            let f = &f;
+            let root = s.spawn(move || f(TcpAllReduce::root(listener, world_size).unwrap()));
+            let others: Vec<_> = (1..world_size)
+                .map(|_| s.spawn(move || f(TcpAllReduce::connect(addr).unwrap())))
+                .collect();
+            let mut results = vec![root.join().unwrap()];
+            results.extend(others.into_iter().map(|h| h.join().unwrap()));
+            results
+        })
+    }
+
+    #[test]
+    fn test_tcp_all_reduce_sum() {
+        let results = run(3, |mut backend| {
+            let rank = backend.rank() as f32;
+            let mut data = [rank, 10.0 * rank];
+            backend.all_reduce_sum(&mut data).unwrap();
+            backend.all_reduce_sum(&mut data).unwrap();
+            (backend.world_size(), data)
+        });
+        assert_eq!(results, [(3, [9.0, 90.0]); 3]);
+    }
+
+    #[test]
+    fn test_all_reduce_gradients() {
+        let results = run(2, |mut backend| {
+            let rank = backend.rank() as f32;
+            let model: (Tensor1D<2>, Tensor1D<1>, Tensor1D<1>) = Default::default();
+            let mut gradients: Gradients = Default::default();
+            *gradients.mut_gradient(&model.0) = [rank + 1.0, -1.0];
+            if rank == 0.0 {
+                *gradients.mut_gradient(&model.1) = [4.0];
+            }
+            all_reduce_gradients(&model, &mut gradients, &mut backend).unwrap();
+            (
+                *gradients.ref_gradient(&model.0),
+                *gradients.ref_gradient(&model.1),
+                gradients.get(&model.2).is_none(),
+            )
+        });
+        assert_eq!(results, [([1.5, -1.0], [2.0], true); 2]);
+    }
+
+    #[test]
+    fn test_all_reduce_skips_frozen() {
+        let results = run(2, |mut backend| {
+            let rank = backend.rank() as f32;
+            let mut model: (Tensor1D<1>, Tensor1D<1>) = Default::default();
+            model.1.freeze();
+            let mut gradients: Gradients = Default::default();
+            *gradients.mut_gradient(&model.0) = [rank];
+            *gradients.mut_gradient(&model.1) = [rank];
+            all_reduce_gradients(&model, &mut gradients, &mut backend).unwrap();
+            (
+                *gradients.ref_gradient(&model.0),
+                *gradients.ref_gradient(&model.1),
+            )
+        });
+        assert_eq!(results, [([0.5], [0.0]), ([0.5], [1.0])]);
+    }
+
+    #[test]
+    fn test_all_reduce_different_lengths() {
+        let results = run(2, |mut backend| {
+            let mut data = vec![1.0; backend.rank() + 1];
+            backend.all_reduce_sum(&mut data).map_err(|e| e.kind())
+        });
+        assert_eq!(results[0], Err(io::ErrorKind::InvalidData));
+    }
+}
@@ -0,0 +1,376 @@
+use super::state::{read_step, write_step};
+use crate::nn::{LoadFromNpz, NpzError, SaveToNpz};
+use crate::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::{ChaCha12Rng, ChaCha20Rng, ChaCha8Rng};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+use zip::{
+    result::{ZipError, ZipResult},
+    ZipArchive, ZipWriter,
+};
+
+/// The version of the [Checkpoint] format that is written. Checkpoints written by newer
+/// versions can't be resumed.
+pub const CHECKPOINT_VERSION: usize = 1;
+
+/// Everything needed to restart a training run: the model's parameters, the optimizer's state,
+/// the random number generator, the epoch & step counters, and any metadata (like hyperparameters),
+/// all saved to a single `.npz` file.
+///
+/// The file contains:
+/// - `version.npy`, `epoch.npy`, and `step.npy`
+/// - the model's parameters prefixed with `model.` (see [SaveToNpz])
+/// - the optimizer's state prefixed with `optimizer.` (see [SaveStateToNpz])
+/// - `rng`, the raw state of the random number generator, if there is one
+/// - `metadata/{key}`, a utf-8 file for each metadata entry
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// # use rand_chacha::ChaCha8Rng;
+/// let mut rng = ChaCha8Rng::seed_from_u64(0);
+/// let mut model: (Linear<5, 10>, ReLU, Linear<10, 2>) = Default::default();
+/// let mut opt: Adam<_> = Default::default();
+///
+/// let mut ckpt = Checkpoint::default();
+/// ckpt.metadata.insert("lr".into(), "1e-3".into());
+/// for epoch in 0..10 {
+///     // -- snip training --
+///     ckpt.epoch = epoch + 1;
+///     ckpt.set_rng(&rng);
+///     ckpt.save("ckpt.npz", &model, &opt)?;
+/// }
+///
+/// // later, to continue training:
+/// let ckpt = Checkpoint::resume("ckpt.npz", &mut model, &mut opt)?;
+/// let mut rng: ChaCha8Rng = ckpt.rng().unwrap();
+/// for epoch in ckpt.epoch..20 {
+///     // -- snip training --
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The number of completed epochs.
+    pub epoch: usize,
+
+    /// The number of completed optimizer steps.
+    pub step: usize,
+
+    /// Arbitrary key/value pairs saved with the checkpoint, like hyperparameters or the git commit.
+    pub metadata: BTreeMap<String, String>,
+
+    rng: Option<Vec<u8>>,
+}
+
+impl Checkpoint {
+    /// Stores the state of `rng`, so it can be restored with [Checkpoint::rng()].
+    pub fn set_rng<R: RngState>(&mut self, rng: &R) {
+        self.rng = Some(rng.state());
+    }
+
+    /// Restores the random number generator stored with [Checkpoint::set_rng()]. Returns `None`
+    /// if there is no stored rng, or it was stored by a different type of rng.
+    pub fn rng<R: RngState>(&self) -> Option<R> {
+        self.rng.as_ref().and_then(|s| R::from_state(s))
+    }
+
+    /// Saves the checkpoint along with `model` and the state of `opt` into the `.npz` file at `path`.
+    pub fn save<M, O, P>(&self, path: P, model: &M, opt: &O) -> ZipResult<()>
+    where
+        M: SaveToNpz + VisitParams,
+        O: SaveStateToNpz<M>,
+        P: AsRef<Path>,
+    {
+        let f = std::fs::File::create(path)?;
+        let mut zip = ZipWriter::new(BufWriter::new(f));
+        self.write(model, opt, &mut zip)?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Writes the checkpoint along with `model` and the state of `opt` into [ZipWriter] `w`.
+    pub fn write<M, O, W>(&self, model: &M, opt: &O, w: &mut ZipWriter<W>) -> ZipResult<()>
+    where
+        M: SaveToNpz + VisitParams,
+        O: SaveStateToNpz<M>,
+        W: Write + Seek,
+    {
+        write_step(w, "version.npy".into(), CHECKPOINT_VERSION)?;
+        write_step(w, "epoch.npy".into(), self.epoch)?;
+        write_step(w, "step.npy".into(), self.step)?;
+        model.write("model.", w)?;
+        opt.write_state(model, "optimizer.", w)?;
+        if let Some(rng) = &self.rng {
+            w.start_file("rng", Default::default())?;
+            w.write_all(rng)?;
+        }
+        for (key, value) in self.metadata.iter() {
+            w.start_file(format!("metadata/{key}"), Default::default())?;
+            w.write_all(value.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Loads `model` and the state of `opt` from the `.npz` file at `path` that was saved with
+    /// [Checkpoint::save()], and returns the rest of the checkpoint.
+    pub fn resume<M, O, P>(path: P, model: &mut M, opt: &mut O) -> Result<Self, CheckpointError>
+    where
+        M: LoadFromNpz + VisitParams,
+        O: LoadStateFromNpz<M>,
+        P: AsRef<Path>,
+    {
+        let f = std::fs::File::open(path).map_err(NpzError::from)?;
+        let mut zip = ZipArchive::new(BufReader::new(f)).map_err(NpzError::from)?;
+        Self::read(model, opt, &mut zip)
+    }
+
+    /// Reads a checkpoint written with [Checkpoint::write()] from [ZipArchive] `r`, loading `model`
+    /// and the state of `opt`.
+    pub fn read<M, O, R>(
+        model: &mut M,
+        opt: &mut O,
+        r: &mut ZipArchive<R>,
+    ) -> Result<Self, CheckpointError>
+    where
+        M: LoadFromNpz + VisitParams,
+        O: LoadStateFromNpz<M>,
+        R: Read + Seek,
+    {
+        let version = read_step(r, "version.npy".into())?;
+        if version > CHECKPOINT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion(version));
+        }
+        let mut ckpt = Self {
+            epoch: read_step(r, "epoch.npy".into())?,
+            step: read_step(r, "step.npy".into())?,
+            ..Default::default()
+        };
+        model.read("model.", r)?;
+        opt.read_state(model, "optimizer.", r)?;
+
+        match r.by_name("rng") {
+            Ok(mut f) => {
+                let mut state = Vec::new();
+                f.read_to_end(&mut state).map_err(NpzError::from)?;
+                ckpt.rng = Some(state);
+            }
+            Err(ZipError::FileNotFound) => {}
+            Err(e) => return Err(NpzError::from(e).into()),
+        }
+
+        let keys: Vec<String> = r
+            .file_names()
+            .filter_map(|name| name.strip_prefix("metadata/"))
+            .map(String::from)
+            .collect();
+        for key in keys {
+            let mut value = String::new();
+            let mut f = r
+                .by_name(&format!("metadata/{key}"))
+                .map_err(NpzError::from)?;
+            f.read_to_string(&mut value)
+                .map_err(|_| CheckpointError::InvalidMetadata(key.clone()))?;
+            ckpt.metadata.insert(key, value);
+        }
+        Ok(ckpt)
+    }
+}
+
+/// A random number generator whose state can be stored in a [Checkpoint].
+///
+/// This is implemented for the generators in [rand_chacha]. [rand::rngs::StdRng] doesn't expose
+/// its state, so use [ChaCha12Rng] (which is the same algorithm) for reproducible training instead.
+pub trait RngState: Sized {
+    /// The complete state of the generator.
+    fn state(&self) -> Vec<u8>;
+
+    /// Recreates a generator from [RngState::state()], or `None` if `state` is invalid.
+    fn from_state(state: &[u8]) -> Option<Self>;
+}
+
+macro_rules! chacha_rng_state {
+    ($($Rng:ty),+) => {$(
+impl RngState for $Rng {
+    /// The seed, followed by the little endian stream & word position.
+    fn state(&self) -> Vec<u8> {
+        let mut state = self.get_seed().to_vec();
+        state.extend_from_slice(&self.get_stream().to_le_bytes());
+        state.extend_from_slice(&self.get_word_pos().to_le_bytes());
+        state
+    }
+
+    fn from_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 56 {
+            return None;
+        }
+        let mut rng = Self::from_seed(state[..32].try_into().unwrap());
+        rng.set_stream(u64::from_le_bytes(state[32..40].try_into().unwrap()));
+        rng.set_word_pos(u128::from_le_bytes(state[40..].try_into().unwrap()));
+        Some(rng)
+    }
+}
+    )+};
+}
+
+chacha_rng_state!(ChaCha8Rng, ChaCha12Rng, ChaCha20Rng);
+
+/// Error that can happen while resuming from a [Checkpoint].
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// Something went wrong while reading the model or optimizer.
+    Npz(NpzError),
+
+    /// The checkpoint was written by a newer version of the format than [CHECKPOINT_VERSION].
+    UnsupportedVersion(usize),
+
+    /// The value of a metadata entry isn't valid utf-8.
+    InvalidMetadata(String),
+}
+
+impl std::fmt::Display for CheckpointError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CheckpointError::Npz(err) => write!(fmt, "{}", err),
+            CheckpointError::UnsupportedVersion(v) => write!(
+                fmt,
+                "checkpoint version {v} is newer than the supported version {CHECKPOINT_VERSION}"
+            ),
+            CheckpointError::InvalidMetadata(key) => {
+                write!(fmt, "metadata `{key}` is not valid utf-8")
+            }
+        }
+    }
+}
+
+impl Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CheckpointError::Npz(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<NpzError> for CheckpointError {
+    fn from(e: NpzError) -> Self {
+        Self::Npz(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use tempfile::NamedTempFile;
+
+    type Model = (Linear<5, 8>, ReLU, Linear<8, 3>);
+
+    fn train_step<O: Optimizer<Model>>(model: &mut Model, opt: &mut O, rng: &mut ChaCha8Rng) {
+        let x: Tensor2D<4, 5> = Tensor2D::randn(rng);
+        let y: Tensor2D<4, 3> = Tensor2D::randn(rng);
+        let gradients = mse_loss(model.forward(x.trace()), &y).backward();
+        opt.update(model, gradients).expect("");
+    }
+
+    #[test]
+    fn test_resume_training() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let mut opt: Adam<Model> = Default::default();
+
+        let mut ckpt = Checkpoint::default();
+        ckpt.metadata.insert("lr".into(), "1e-3".into());
+        ckpt.metadata.insert("note".into(), "first run\nü".into());
+        for _ in 0..3 {
+            train_step(&mut model, &mut opt, &mut rng);
+            ckpt.step += 1;
+        }
+        ckpt.epoch = 1;
+        ckpt.set_rng(&rng);
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        ckpt.save(file.path(), &model, &opt).expect("");
+
+        let mut loaded: Model = Default::default();
+        let mut resumed: Adam<Model> = Default::default();
+        let loaded_ckpt = Checkpoint::resume(file.path(), &mut loaded, &mut resumed).expect("");
+        assert_eq!(loaded_ckpt, ckpt);
+        let mut loaded_rng: ChaCha8Rng = loaded_ckpt.rng().expect("");
+
+        for _ in 0..3 {
+            train_step(&mut model, &mut opt, &mut rng);
+            train_step(&mut loaded, &mut resumed, &mut loaded_rng);
+        }
+        assert_eq!(model.0.weight.data(), loaded.0.weight.data());
+        assert_eq!(model.2.bias.data(), loaded.2.bias.data());
+        assert_eq!(rng.gen::<u64>(), loaded_rng.gen::<u64>());
+    }
+
+    #[test]
+    fn test_checkpoint_files() {
+        let mut model: Model = Default::default();
+        let opt: Sgd<Model> = Default::default();
+        let mut ckpt = Checkpoint {
+            epoch: 2,
+            step: 7,
+            ..Default::default()
+        };
+        ckpt.metadata.insert("a".into(), "b".into());
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        ckpt.save(file.path(), &model, &opt).expect("");
+        let zip = ZipArchive::new(file.reopen().expect("")).expect("");
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            &names,
+            &[
+                "epoch.npy",
+                "metadata/a",
+                "model.0.bias.npy",
+                "model.0.weight.npy",
+                "model.2.bias.npy",
+                "model.2.weight.npy",
+                "step.npy",
+                "version.npy"
+            ]
+        );
+
+        let mut opt = opt;
+        let loaded = Checkpoint::resume(file.path(), &mut model, &mut opt).expect("");
+        assert_eq!(loaded, ckpt);
+        assert!(loaded.rng::<ChaCha8Rng>().is_none());
+    }
+
+    #[test]
+    fn test_rng_state() {
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        rng.set_stream(5);
+        let _ = rng.gen::<[u32; 7]>();
+        let mut ckpt = Checkpoint::default();
+        ckpt.set_rng(&rng);
+        let mut restored: ChaCha20Rng = ckpt.rng().expect("");
+        assert_eq!(restored.gen::<[u64; 4]>(), rng.gen::<[u64; 4]>());
+        assert!(ChaCha8Rng::from_state(&[0; 10]).is_none());
+    }
+
+    #[test]
+    fn test_newer_version() {
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        let mut zip = ZipWriter::new(file.reopen().expect(""));
+        write_step(&mut zip, "version.npy".into(), CHECKPOINT_VERSION + 1).expect("");
+        zip.finish().expect("");
+
+        let mut model: Model = Default::default();
+        let mut opt: Sgd<Model> = Default::default();
+        let result = Checkpoint::resume(file.path(), &mut model, &mut opt);
+        assert!(matches!(
+            result,
+            Err(CheckpointError::UnsupportedVersion(v)) if v == CHECKPOINT_VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::prelude::*;
+
+/// Gradient clipping that optimizers apply in [Optimizer::update()] before updating any parameters.
+///
+/// Set this on any of the optimizer configs, e.g. [SgdConfig::grad_clip] or [AdamConfig::grad_clip].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// AdamConfig {
+///     grad_clip: Some(GradClip::Norm(1.0)),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradClip {
+    /// Scales all gradients so that their global l2 norm is at most this value.
+    /// The norm is computed across the gradients of **all** parameters in the module,
+    /// like pytorch's [clip_grad_norm_](https://pytorch.org/docs/stable/generated/torch.nn.utils.clip_grad_norm_.html).
+    Norm(f32),
+
+    /// Clamps each element of all gradients to be in the range `[-value, value]`.
+    Value(f32),
+}
+
+impl GradClip {
+    /// Clips the gradients of all of `module`'s parameters that are in `gradients`.
+    pub fn clip<M: VisitParams>(&self, module: &M, gradients: &mut Gradients) {
+        match *self {
+            GradClip::Norm(max_norm) => {
+                let norm = grad_norm(module, gradients);
+                let scale = max_norm / (norm + 1e-6);
+                if scale < 1.0 {
+                    visit_gradients(module, gradients, |g| *g *= scale);
+                }
+            }
+            GradClip::Value(value) => {
+                visit_gradients(module, gradients, |g| *g = g.clamp(-value, value));
+            }
+        }
+    }
+}
+
+/// Computes the l2 norm of the gradients of all of `module`'s parameters, as if they were
+/// concatenated into a single vector. Parameters without a gradient and frozen parameters (see [Freeze])
+/// are skipped.
+pub fn grad_norm<M: VisitParams>(module: &M, gradients: &mut Gradients) -> f32 {
+    let mut sum_sq = 0.0;
+    visit_gradients(module, gradients, |g| sum_sq += *g * *g);
+    sum_sq.sqrt()
+}
+
+/// Calls `f` on every element of the gradients of `module`'s parameters.
+pub(super) fn visit_gradients<M, F>(module: &M, gradients: &mut Gradients, f: F)
+where
+    M: VisitParams,
+    F: FnMut(&mut f32),
+{
+    module.visit_params("", &mut GradientVisitor { gradients, f });
+}
+
+struct GradientVisitor<'a, F> {
+    gradients: &'a mut Gradients,
+    f: F,
+}
+
+impl<'a, F: FnMut(&mut f32)> ParamVisitor for GradientVisitor<'a, F> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &P) {
+        if !p.requires_grad() {
+            return;
+        }
+        if let Some(mut g) = self.gradients.remove(p) {
+            P::Device::foreach_m(g.as_mut(), &mut self.f);
+            self.gradients.insert(p, g);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_grad_norm_is_global() {
+        let model: (Tensor1D<2>, Tensor1D<1>) = Default::default();
+        let mut gradients: Gradients = Default::default();
+        *gradients.mut_gradient(&model.0) = [3.0, 0.0];
+        *gradients.mut_gradient(&model.1) = [4.0];
+        assert!((grad_norm(&model, &mut gradients) - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_clip_norm() {
+        let model: (Tensor1D<2>, Tensor1D<1>) = Default::default();
+        let mut gradients: Gradients = Default::default();
+        *gradients.mut_gradient(&model.0) = [3.0, 0.0];
+        *gradients.mut_gradient(&model.1) = [-4.0];
+        GradClip::Norm(1.0).clip(&model, &mut gradients);
+        assert_close(gradients.ref_gradient(&model.0), &[0.6, 0.0]);
+        assert_close(gradients.ref_gradient(&model.1), &[-0.8]);
+    }
+
+    #[test]
+    fn test_clip_norm_below_max_is_unchanged() {
+        let model: Tensor1D<2> = Default::default();
+        let mut gradients: Gradients = Default::default();
+        *gradients.mut_gradient(&model) = [3.0, -4.0];
+        GradClip::Norm(10.0).clip(&model, &mut gradients);
+        assert_eq!(gradients.ref_gradient(&model), &[3.0, -4.0]);
+    }
+
+    #[test]
+    fn test_clip_value() {
+        let model: Tensor1D<4> = Default::default();
+        let mut gradients: Gradients = Default::default();
+        *gradients.mut_gradient(&model) = [-3.0, -0.5, 0.5, 3.0];
+        GradClip::Value(1.0).clip(&model, &mut gradients);
+        assert_eq!(gradients.ref_gradient(&model), &[-1.0, -0.5, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_sgd_clips_before_step() {
+        let mut t: Tensor1D<2> = Tensor1D::zeros();
+        let mut opt: Sgd<Tensor1D<2>> = Sgd::new(SgdConfig {
+            lr: 1.0,
+            momentum: None,
+            grad_clip: Some(GradClip::Norm(1.0)),
+        });
+        let gradients = (t.trace() * &Tensor1D::new([3.0, 4.0])).sum().backward();
+        opt.update(&mut t, gradients).expect("");
+        assert_close(t.data(), &[-0.6, -0.8]);
+    }
+
+    #[test]
+    fn test_clip_keeps_unused_params_error() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: Adam<Model> = Adam::new(AdamConfig {
+            grad_clip: Some(GradClip::Value(1.0)),
+            ..Default::default()
+        });
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
@@ -0,0 +1,178 @@
+use crate::devices::flat_mut;
+use crate::prelude::*;
+
+/// Keeps a shadow copy of a model whose parameters are an average of the model's parameters
+/// over training. Call [ModelEma::update()] after every optimizer step, and use
+/// [ModelEma::module] (or [ModelEma::swap()] it in) for evaluation.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = (Linear<5, 16>, ReLU, Linear<16, 10>);
+/// let mut model: Model = Default::default();
+/// let mut ema = ModelEma::new(&model, Averaging::Exponential(0.999));
+/// // -- snip optimizer update of `model` --
+/// ema.update(&model);
+/// let y = ema.module.forward(Tensor1D::<5>::zeros());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ModelEma<M> {
+    /// The averaged model.
+    pub module: M,
+
+    /// How the parameters are averaged.
+    pub averaging: Averaging,
+
+    num_updates: usize,
+}
+
+/// How [ModelEma] averages parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Averaging {
+    /// Exponential moving average with the decay factor: `avg = decay * avg + (1 - decay) * p`.
+    Exponential(f32),
+
+    /// Equal weight average of all values seen so far, as in stochastic weight averaging
+    /// from [Averaging Weights Leads to Wider Optima and Better Generalization](https://arxiv.org/abs/1803.05407).
+    Uniform,
+}
+
+impl<M: VisitParams + Clone> ModelEma<M> {
+    /// Starts averaging from a copy of `model`.
+    pub fn new(model: &M, averaging: Averaging) -> Self {
+        Self {
+            module: model.clone(),
+            averaging,
+            num_updates: 0,
+        }
+    }
+
+    /// Moves the average towards the current parameters of `model`. All parameters are
+    /// averaged, including frozen ones.
+    pub fn update(&mut self, model: &M) {
+        self.num_updates += 1;
+        let weight = match self.averaging {
+            Averaging::Exponential(decay) => 1.0 - decay,
+            Averaging::Uniform => 1.0 / (self.num_updates + 1) as f32,
+        };
+
+        let params = model.state_dict();
+        let mut lerp = Lerp {
+            params: &params,
+            weight,
+        };
+        self.module.visit_params_mut("", &mut lerp);
+    }
+
+    /// Swaps the parameters of `model` with the averaged parameters. Call again
+    /// to swap back.
+    pub fn swap(&mut self, model: &mut M) {
+        std::mem::swap(&mut self.module, model);
+    }
+}
+
+/// Moves each parameter `weight` of the way towards the parameter with the same name in `params`.
+struct Lerp<'a> {
+    params: &'a StateDict,
+    weight: f32,
+}
+
+impl ParamVisitorMut for Lerp<'_> {
+    fn visit_mut<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &mut P) {
+        let target = &self.params[name];
+        for (avg, t) in flat_mut(p.mut_data()).iter_mut().zip(target.data.iter()) {
+            *avg += self.weight * (t - *avg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_ema_starts_as_copy() {
+        let model: (Tensor1D<2>, Tensor1D<3>) = (Tensor1D::new([1.0, 2.0]), Tensor1D::ones());
+        let ema = ModelEma::new(&model, Averaging::Exponential(0.5));
+        assert_eq!(ema.module.0.data(), model.0.data());
+        assert_eq!(ema.module.1.data(), model.1.data());
+    }
+
+    #[test]
+    fn test_exponential_average() {
+        let mut model: (Tensor1D<2>, Tensor1D<1>) = (Tensor1D::new([1.0, 2.0]), Tensor1D::ones());
+        let mut ema = ModelEma::new(&model, Averaging::Exponential(0.75));
+
+        model.0.mut_data().copy_from_slice(&[5.0, -2.0]);
+        *model.1.mut_data() = [3.0];
+        ema.update(&model);
+        assert_close(ema.module.0.data(), &[2.0, 1.0]);
+        assert_close(ema.module.1.data(), &[1.5]);
+
+        ema.update(&model);
+        assert_close(ema.module.0.data(), &[2.75, 0.25]);
+        assert_close(ema.module.1.data(), &[1.875]);
+    }
+
+    #[test]
+    fn test_uniform_average() {
+        let mut model: Tensor1D<2> = Tensor1D::new([0.0, 3.0]);
+        let mut ema = ModelEma::new(&model, Averaging::Uniform);
+        for v in [1.0, 2.0, 3.0] {
+            *model.mut_data() = [v, 3.0 + v];
+            ema.update(&model);
+        }
+        assert_close(ema.module.data(), &[1.5, 4.5]);
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut model: Tensor1D<2> = Tensor1D::new([1.0, 2.0]);
+        let mut ema = ModelEma::new(&model, Averaging::Exponential(0.0));
+        *model.mut_data() = [3.0, 4.0];
+        ema.update(&model);
+        *model.mut_data() = [5.0, 6.0];
+        ema.swap(&mut model);
+        assert_eq!(model.data(), &[3.0, 4.0]);
+        assert_eq!(ema.module.data(), &[5.0, 6.0]);
+    }
+
+    #[test]
+    fn test_ema_frozen_params() {
+        let mut model: (Linear<2, 2>, Linear<2, 1>) = Default::default();
+        model.reset_params(&mut rand::thread_rng());
+        model.0.freeze();
+        let mut ema = ModelEma::new(&model, Averaging::Exponential(0.5));
+
+        let model_0 = model.clone();
+        model.0.weight.mut_data()[0][0] += 2.0;
+        model.1.bias.mut_data()[0] += 4.0;
+        ema.update(&model);
+        assert_close(
+            &[ema.module.0.weight.data()[0][0]],
+            &[model_0.0.weight.data()[0][0] + 1.0],
+        );
+        assert_close(ema.module.0.bias.data(), model_0.0.bias.data());
+        assert_close(ema.module.1.bias.data(), &[model_0.1.bias.data()[0] + 2.0]);
+    }
+
+    #[test]
+    fn test_ema_linear() {
+        let mut rng = rand::thread_rng();
+        let mut model: Linear<3, 2> = Default::default();
+        model.reset_params(&mut rng);
+        let mut ema = ModelEma::new(&model, Averaging::Exponential(0.9));
+        let model_0 = model.clone();
+        model.reset_params(&mut rng);
+        ema.update(&model);
+        for i in 0..2 {
+            for j in 0..3 {
+                let expected = 0.9 * model_0.weight.data()[i][j] + 0.1 * model.weight.data()[i][j];
+                assert!((ema.module.weight.data()[i][j] - expected).abs() < 1e-6);
+            }
+            let expected = 0.9 * model_0.bias.data()[i] + 0.1 * model.bias.data()[i];
+            assert!((ema.module.bias.data()[i] - expected).abs() < 1e-6);
+        }
+    }
+}
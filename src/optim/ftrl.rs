@@ -0,0 +1,320 @@
+use super::state::{read_step, write_step};
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// An implementation of the FTRL-Proximal optimizer from
+/// [Ad Click Prediction: a View from the Trenches](https://research.google.com/pubs/archive/41159.pdf).
+///
+/// Based on [tensorflow's implementation](https://www.tensorflow.org/api_docs/python/tf/keras/optimizers/Ftrl),
+/// with a learning rate power of `-0.5`.
+///
+/// The [FtrlConfig::l1] regularization sets parameters to exactly `0.0` when their accumulated gradient is small,
+/// which produces sparse models. This is mostly used for wide linear models, like in recommendation systems.
+///
+/// **NOTE**: FTRL computes the parameters from its accumulators, so the initial values of the parameters
+/// are mostly ignored after the first update. It works best on parameters that are initialized to `0.0`.
+///
+/// # Example Usage
+///
+/// Constructing using default:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: Ftrl<Model> = Default::default();
+/// ```
+///
+/// Constructing using new:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: Ftrl<Model> = Ftrl::new(FtrlConfig {
+///     lr: 1e-1,
+///     l1: 1e-3,
+///     l2: 1e-2,
+///     beta: 1.0,
+///     initial_accumulator_value: 0.1,
+///     grad_clip: None,
+/// });
+/// ```
+///
+/// See module level documentation at [crate::optim] for examples of how to actually use an optimizer.
+#[derive(Debug)]
+pub struct Ftrl<M> {
+    /// Hyperparameter configuration
+    pub cfg: FtrlConfig,
+
+    step: usize,
+    gradients: Gradients,
+    linear: Gradients,
+    sum_sq: Gradients,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [Ftrl].
+#[derive(Debug, Clone, Copy)]
+pub struct FtrlConfig {
+    /// Learning rate. Defaults to `1e-3`.
+    pub lr: f32,
+
+    /// L1 regularization strength. Defaults to `0.0`.
+    pub l1: f32,
+
+    /// L2 regularization strength. Defaults to `0.0`.
+    pub l2: f32,
+
+    /// Added to the square root of the sum of squared gradients, like `beta` in the paper. Defaults to `0.0`.
+    pub beta: f32,
+
+    /// The starting value of the sum of squared gradients. Defaults to `0.1`.
+    pub initial_accumulator_value: f32,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
+}
+
+impl Default for FtrlConfig {
+    fn default() -> Self {
+        Self {
+            lr: 1e-3,
+            l1: 0.0,
+            l2: 0.0,
+            beta: 0.0,
+            initial_accumulator_value: 0.1,
+            grad_clip: None,
+        }
+    }
+}
+
+impl<M> Default for Ftrl<M> {
+    /// See [FtrlConfig]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<M> Ftrl<M> {
+    /// Constructs using hyperparameters from `cfg`.
+    pub fn new(cfg: FtrlConfig) -> Self {
+        Self {
+            cfg,
+            step: 0,
+            gradients: Default::default(),
+            linear: Default::default(),
+            sum_sq: Default::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M> GradientProvider for Ftrl<M> {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let mut g_t = self.gradients.remove(p)?;
+
+        let sum_sq = self.sum_sq.mut_gradient(p);
+        if self.step == 1 {
+            let init = self.cfg.initial_accumulator_value;
+            P::Device::fill(sum_sq, &mut |v| *v = init);
+        }
+        let linear = self.linear.mut_gradient(p);
+
+        let FtrlConfig {
+            lr, l1, l2, beta, ..
+        } = self.cfg;
+
+        // NOTE: `g_t` temporarily holds sigma, since the linear term also needs the parameter
+        P::Device::foreach_mmm(g_t.as_mut(), linear, sum_sq, &mut |g, z, n| {
+            let n_next = *n + g.powi(2);
+            let sigma = (n_next.sqrt() - n.sqrt()) / lr;
+            *z += *g;
+            *n = n_next;
+            *g = sigma;
+        });
+        P::Device::foreach_mrr(linear, g_t.as_ref(), p.data(), &mut |z, sigma, w| {
+            *z -= sigma * w;
+        });
+
+        // compute the new parameter values, and then the update that moves to them
+        P::Device::foreach_mrr(g_t.as_mut(), linear, sum_sq, &mut |g, z, n| {
+            *g = if z.abs() <= l1 {
+                0.0
+            } else {
+                -(z - z.signum() * l1) / ((beta + n.sqrt()) / lr + l2)
+            };
+        });
+        P::Device::foreach_mr(g_t.as_mut(), p.data(), &mut |g, w| *g = w - *g);
+        Some(g_t)
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for Ftrl<M> {
+    fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
+        self.begin_step(module, gradients);
+        let mut unused_tensors = Default::default();
+        module.update(self, &mut unused_tensors);
+        unused_tensors.into()
+    }
+}
+
+impl<M> OptimizerStep for Ftrl<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.step = self.step.checked_add(1).unwrap();
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for Ftrl<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for Ftrl<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_step(w, format!("{filename_prefix}step.npy"), self.step)?;
+        write_param_buffer(w, &format!("{filename_prefix}linear"), module, &self.linear)?;
+        write_param_buffer(w, &format!("{filename_prefix}sum_sq"), module, &self.sum_sq)?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for Ftrl<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        self.step = read_step(r, format!("{filename_prefix}step.npy"))?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}linear"),
+            module,
+            &mut self.linear,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}sum_sq"),
+            module,
+            &mut self.sum_sq,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::*, SeedableRng};
+
+    fn test_matches_expected(cfg: FtrlConfig, expected: [[f32; 5]; 10]) {
+        let rate = Tensor1D::new([1e-4, 1e-3, 1e-2, 1e-1, 1e-0]);
+        let mut t: Tensor1D<5> = Tensor1D::ones();
+        let mut opt = Ftrl::new(cfg);
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().mean().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_ftrl_default() {
+        #[rustfmt::skip]
+        const EXPECTED: [[f32; 5]; 10] = [
+[0.0, 0.0, -1.1920929e-07, 6.735325e-05, 0.3790419],
+            [-1.2649111e-11, -1.2649113e-09, -1.264911e-07, 6.7357134e-05, 0.37875694],
+            [-1.2649111e-11, -1.2649113e-09, -1.2649109e-07, 6.735628e-05, 0.37848303],
+            [-1.2649111e-11, -1.2649113e-09, -1.2649107e-07, 6.7355424e-05, 0.37821904],
+            [-1.2649111e-11, -1.2649113e-09, -1.2649106e-07, 6.735457e-05, 0.37796393],
+            [-1.2649111e-11, -1.2649113e-09, -1.2649105e-07, 6.7353714e-05, 0.3777169],
+            [-1.2649111e-11, -1.2649113e-09, -1.2649105e-07, 6.735286e-05, 0.37747726],
+            [-1.2649111e-11, -1.2649113e-09, -1.2649103e-07, 6.735201e-05, 0.37724435],
+            [-1.2649111e-11, -1.2649113e-09, -1.2649102e-07, 6.735115e-05, 0.37701765],
+            [-1.2649111e-11, -1.2649113e-09, -1.26491e-07, 6.73503e-05, 0.3767967],
+        ];
+        test_matches_expected(Default::default(), EXPECTED);
+    }
+
+    #[test]
+    fn test_ftrl_l1_l2() {
+        const CFG: FtrlConfig = FtrlConfig {
+            lr: 1e-1,
+            l1: 1e-3,
+            l2: 1e-2,
+            beta: 0.5,
+            initial_accumulator_value: 0.0,
+            grad_clip: None,
+        };
+        // l1 regularization sets the parameters with small gradients to exactly 0
+        #[rustfmt::skip]
+        const EXPECTED: [[f32; 5]; 10] = [
+            [0.0, 0.0, 0.0, 0.006930709, 0.39944506],
+            [0.0, 0.0, 0.0, 0.006925205, 0.3822965],
+            [0.0, 0.0, 0.0, 0.006919719, 0.36633545],
+            [0.0, 0.0, 0.0, 0.006914238, 0.351398],
+            [0.0, 0.0, 0.0, 0.0069087613, 0.3373581],
+            [0.0, 0.0, 0.0, 0.0069032894, 0.32411572],
+            [0.0, 0.0, 0.0, 0.0068978225, 0.31158957],
+            [0.0, 0.0, 0.0, 0.006892359, 0.2997122],
+            [0.0, 0.0, 0.0, 0.0068868995, 0.28842685],
+            [0.0, 0.0, 0.0, 0.0068814447, 0.27768508],
+        ];
+        test_matches_expected(CFG, EXPECTED);
+    }
+
+    #[test]
+    fn test_ftrl_changes_all_params() {
+        type Model = (Linear<5, 16>, ReLU, Linear<16, 16>, ReLU, Linear<16, 10>);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let model_0 = model.clone();
+
+        let x: Tensor2D<16, 5> = Tensor2D::rand(&mut rng);
+        let y: Tensor2D<16, 10> = Tensor2D::rand(&mut rng);
+        let mut opt: Ftrl<Model> = Default::default();
+
+        let py = model.forward(x.trace());
+        let loss = (py - &y).square().mean();
+        let gradients = loss.backward();
+        opt.update(&mut model, gradients).expect("");
+
+        let model_1 = model.clone();
+
+        assert!(model_0.0.weight.data() != model_1.0.weight.data());
+        assert!(model_0.0.bias.data() != model_1.0.bias.data());
+        assert!(model_0.2.weight.data() != model_1.2.weight.data());
+        assert!(model_0.2.bias.data() != model_1.2.bias.data());
+        assert!(model_0.4.weight.data() != model_1.4.weight.data());
+        assert!(model_0.4.bias.data() != model_1.4.bias.data());
+    }
+
+    #[test]
+    fn test_ftrl_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: Ftrl<Model> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
@@ -0,0 +1,184 @@
+use super::clip::visit_gradients;
+use crate::prelude::*;
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use std::marker::PhantomData;
+
+/// Adds annealed gaussian noise to the gradients before every update of the wrapped optimizer, as described in
+/// [Adding Gradient Noise Improves Learning for Very Deep Networks](https://arxiv.org/abs/1511.06807).
+///
+/// At step `t` (starting at 1), noise is sampled from `N(0, eta / (1 + t)^gamma)`, so the noise
+/// decreases as training goes on.
+///
+/// # Example Usage
+///
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # use rand::{prelude::StdRng, SeedableRng};
+/// type Model = (Linear<5, 16>, ReLU, Linear<16, 2>);
+/// let mut opt: GradNoise<Model, Sgd<Model>> = GradNoise::new(
+///     Default::default(),
+///     GradNoiseConfig { eta: 0.3, gamma: 0.55 },
+///     StdRng::seed_from_u64(0),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct GradNoise<M, O> {
+    /// The wrapped optimizer that does the actual update.
+    pub opt: O,
+
+    /// Hyperparameter configuration
+    pub cfg: GradNoiseConfig,
+
+    t: i32,
+    rng: StdRng,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [GradNoise].
+#[derive(Debug, Clone, Copy)]
+pub struct GradNoiseConfig {
+    /// Variance of the noise at step 0. Defaults to `0.01`.
+    pub eta: f32,
+
+    /// How quickly the variance decays. Defaults to `0.55`.
+    pub gamma: f32,
+}
+
+impl Default for GradNoiseConfig {
+    fn default() -> Self {
+        Self {
+            eta: 0.01,
+            gamma: 0.55,
+        }
+    }
+}
+
+impl<M, O: Default> Default for GradNoise<M, O> {
+    /// See [GradNoiseConfig]. The noise is sampled with a [StdRng] that is seeded from
+    /// [seeded_rng()], so it is reproducible with [crate::set_seed()].
+    fn default() -> Self {
+        Self::new(
+            Default::default(),
+            Default::default(),
+            StdRng::seed_from_u64(seeded_rng().gen()),
+        )
+    }
+}
+
+impl<M, O> GradNoise<M, O> {
+    /// Wraps `opt` using hyperparameters from `cfg`, sampling noise with `rng`.
+    pub fn new(opt: O, cfg: GradNoiseConfig, rng: StdRng) -> Self {
+        Self {
+            opt,
+            cfg,
+            t: 0,
+            rng,
+            marker: PhantomData,
+        }
+    }
+
+    /// The standard deviation of the noise at step `t`.
+    fn std_dev(&self, t: i32) -> f32 {
+        (self.cfg.eta / (1.0 + t as f32).powf(self.cfg.gamma)).sqrt()
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams, O: Optimizer<M>> Optimizer<M> for GradNoise<M, O> {
+    fn update(
+        &mut self,
+        module: &mut M,
+        mut gradients: Gradients,
+    ) -> Result<(), UnusedParamsError> {
+        self.t = self.t.checked_add(1).unwrap();
+        let std_dev = self.std_dev(self.t);
+        let rng = &mut self.rng;
+        visit_gradients(module, &mut gradients, |g| {
+            *g += std_dev * rng.sample::<f32, _>(StandardNormal);
+        });
+        self.opt.update(module, gradients)
+    }
+}
+
+impl<M, O: HasLearningRate> HasLearningRate for GradNoise<M, O> {
+    fn lr(&self) -> f32 {
+        self.opt.lr()
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.opt.set_lr(lr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_grad_noise_std_dev() {
+        let opt: GradNoise<Tensor0D, Sgd<Tensor0D>> = Default::default();
+        assert_close(&[opt.std_dev(1)], &[0.082_645_03]);
+        assert_close(&[opt.std_dev(10)], &[0.051_715_06]);
+        assert!(opt.std_dev(100) < opt.std_dev(10));
+    }
+
+    #[test]
+    fn test_grad_noise_zero_eta_matches_wrapped() {
+        let rate = Tensor1D::new([0.1, 1.0, 2.0, 10.0, 100.0]);
+        let mut t0: Tensor1D<5> = Tensor1D::ones();
+        let mut t1: Tensor1D<5> = Tensor1D::ones();
+        let mut adam: Adam<Tensor1D<5>> = Default::default();
+        let mut noisy: GradNoise<Tensor1D<5>, Adam<Tensor1D<5>>> = GradNoise::new(
+            Default::default(),
+            GradNoiseConfig {
+                eta: 0.0,
+                gamma: 0.55,
+            },
+            StdRng::seed_from_u64(0),
+        );
+        for _ in 0..3 {
+            let g = (t0.trace() * &rate).square().sum().backward();
+            adam.update(&mut t0, g).expect("");
+            let g = (t1.trace() * &rate).square().sum().backward();
+            noisy.update(&mut t1, g).expect("");
+        }
+        assert_eq!(t0.data(), t1.data());
+    }
+
+    #[test]
+    fn test_grad_noise_changes_update() {
+        let mut t: Tensor1D<1000> = Tensor1D::zeros();
+        let mut opt: GradNoise<Tensor1D<1000>, Sgd<Tensor1D<1000>>> = GradNoise::new(
+            Sgd::new(SgdConfig {
+                lr: 1.0,
+                momentum: None,
+                grad_clip: None,
+            }),
+            GradNoiseConfig {
+                eta: 3.0,
+                gamma: 0.0,
+            },
+            StdRng::seed_from_u64(0),
+        );
+        // the gradient is 0, so the update is only noise with variance 3
+        let mut gradients: Gradients = Default::default();
+        *gradients.mut_gradient(&t) = [0.0; 1000];
+        opt.update(&mut t, gradients).expect("");
+        let mean = t.data().iter().sum::<f32>() / 1000.0;
+        let var = t.data().iter().map(|x| (x - mean).powi(2)).sum::<f32>() / 1000.0;
+        assert!(mean.abs() < 0.2, "{mean}");
+        assert!((var - 3.0).abs() < 0.5, "{var}");
+    }
+
+    #[test]
+    fn test_grad_noise_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: GradNoise<Model, Sgd<Model>> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
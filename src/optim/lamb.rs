@@ -0,0 +1,315 @@
+use super::adam::update_moments;
+use super::state::{read_step, write_step};
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// An implementation of the LAMB optimizer from
+/// [Large Batch Optimization for Deep Learning: Training BERT in 76 minutes](https://arxiv.org/abs/1904.00962).
+///
+/// LAMB computes an [AdamW] style update for each parameter tensor, and then scales
+/// it by the trust ratio `||param|| / ||update||`, so each layer takes steps relative to
+/// the size of its weights.
+///
+/// # Example Usage
+///
+/// Constructing using default:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: Lamb<Model> = Default::default();
+/// ```
+///
+/// Changing using new
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: Lamb<Model> = Lamb::new(LambConfig {
+///     lr: 1e-2,
+///     betas: [0.5, 0.25],
+///     eps: 1e-6,
+///     weight_decay: 1e-2,
+///     grad_clip: None,
+/// });
+/// ```
+///
+/// See module level documentation at [crate::optim] for examples of how to actually use an optimizer.
+#[derive(Debug)]
+pub struct Lamb<M> {
+    /// Hyperparameter configuration
+    pub cfg: LambConfig,
+
+    t: i32,
+    gradients: Gradients,
+    moment1: Gradients,
+    moment2: Gradients,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [Lamb].
+#[derive(Debug, Clone, Copy)]
+pub struct LambConfig {
+    /// Learning rate. Defaults to `1e-3`.
+    pub lr: f32,
+
+    /// Betas from Adam paper. Defaults to `[0.9, 0.999]`.
+    pub betas: [f32; 2],
+
+    /// Epsilon for numerical stability. Defaults to `1e-6`.
+    pub eps: f32,
+
+    /// Decoupled weight decay, added to the update before the trust ratio is computed.
+    /// Defaults to `0.0`.
+    pub weight_decay: f32,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
+}
+
+impl Default for LambConfig {
+    fn default() -> Self {
+        Self {
+            lr: 1e-3,
+            betas: [0.9, 0.999],
+            eps: 1e-6,
+            weight_decay: 0.0,
+            grad_clip: None,
+        }
+    }
+}
+
+impl<M> Default for Lamb<M> {
+    /// See [LambConfig]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<M> Lamb<M> {
+    /// Constructs using hyperparameters from `cfg`.
+    pub fn new(cfg: LambConfig) -> Self {
+        Self {
+            cfg,
+            t: 0,
+            gradients: Default::default(),
+            moment1: Default::default(),
+            moment2: Default::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M> GradientProvider for Lamb<M> {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let mut g_t = self.gradients.remove(p)?;
+        let m_t = self.moment1.mut_gradient(p);
+        let v_t = self.moment2.mut_gradient(p);
+        P::Device::foreach_mmm(g_t.as_mut(), m_t, v_t, &mut |g, m, v| {
+            update_moments(*g, m, v, self.cfg.betas);
+            let m_hat = *m * (1.0 - self.cfg.betas[0].powi(self.t)).recip();
+            let v_hat = *v * (1.0 - self.cfg.betas[1].powi(self.t)).recip();
+            *g = m_hat / (v_hat.sqrt() + self.cfg.eps)
+        });
+        P::Device::foreach_mr(g_t.as_mut(), p.data(), &mut |g, p| {
+            *g += self.cfg.weight_decay * p;
+        });
+
+        let weight_norm = P::Device::l2_norm(p.data());
+        let update_norm = P::Device::l2_norm(g_t.as_ref());
+        let trust_ratio = if weight_norm > 0.0 && update_norm > 0.0 {
+            weight_norm / update_norm
+        } else {
+            1.0
+        };
+        P::Device::foreach_m(g_t.as_mut(), &mut |g| *g *= self.cfg.lr * trust_ratio);
+        Some(g_t)
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for Lamb<M> {
+    fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
+        self.begin_step(module, gradients);
+        let mut unused_tensors = Default::default();
+        module.update(self, &mut unused_tensors);
+        unused_tensors.into()
+    }
+}
+
+impl<M> OptimizerStep for Lamb<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.t = self.t.checked_add(1).unwrap();
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for Lamb<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for Lamb<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_step(w, format!("{filename_prefix}t.npy"), self.t as usize)?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &self.moment1,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for Lamb<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        self.t = read_step(r, format!("{filename_prefix}t.npy"))? as i32;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &mut self.moment1,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &mut self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::*, SeedableRng};
+
+    #[test]
+    fn test_default_lamb_params() {
+        let mut opt = Lamb::default();
+        let mut t: Tensor1D<5> = Tensor1D::ones();
+        let rate = Tensor1D::new([0.1, 1.0, 2.0, 10.0, 100.0]);
+        let expected = [
+            [0.999; 5],
+            [0.9980011, 0.998001, 0.998001, 0.998001, 0.998001],
+            [0.99700314, 0.99700296, 0.99700296, 0.99700296, 0.99700296],
+            [0.9960061, 0.99600595, 0.99600595, 0.99600595, 0.99600595],
+            [0.9950102, 0.99500996, 0.99500996, 0.99500996, 0.99500996],
+        ];
+
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().sum().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_custom_lamb_params() {
+        let mut opt = Lamb::new(LambConfig {
+            lr: 1e-2,
+            betas: [0.5, 0.25],
+            eps: 1e-6,
+            weight_decay: 0.1,
+            grad_clip: None,
+        });
+        let mut t: Tensor1D<5> = Tensor1D::new([1.0, 2.0, -1.0, 0.5, 3.0]);
+        let rate = Tensor1D::new([0.1, 1.0, 2.0, 10.0, 100.0]);
+        let expected = [
+            [0.983346, 1.9818312, -0.9833452, 0.48410225, 2.980317],
+            [0.96683735, 1.9638392, -0.9668358, 0.4683112, 2.9608324],
+            [0.95047206, 1.9460248, -0.9504698, 0.45261797, 2.9415474],
+            [0.9342494, 1.9283844, -0.9342463, 0.4370246, 2.9224575],
+            [0.9181688, 1.910913, -0.918165, 0.4215372, 2.9035552],
+        ];
+
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().sum().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_lamb_update_scales_with_weight_norm() {
+        type Model = (Tensor1D<3>, Tensor1D<3>);
+        let mut model: Model = (Tensor1D::new([1.0; 3]), Tensor1D::new([10.0; 3]));
+        let mut gradients: Gradients = Default::default();
+        *gradients.mut_gradient(&model.0) = [1.0; 3];
+        *gradients.mut_gradient(&model.1) = [1.0; 3];
+
+        let mut opt: Lamb<Model> = Default::default();
+        opt.update(&mut model, gradients).expect("");
+
+        // same gradients, but the second tensor takes a 10x bigger step because its norm is 10x bigger
+        assert_close(model.0.data(), &[0.999; 3]);
+        assert_close(model.1.data(), &[9.99; 3]);
+    }
+
+    #[test]
+    fn test_lamb_changes_all_params() {
+        type Model = (Linear<5, 16>, ReLU, Linear<16, 16>, ReLU, Linear<16, 10>);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let model_0 = model.clone();
+
+        let x: Tensor2D<16, 5> = Tensor2D::rand(&mut rng);
+        let y: Tensor2D<16, 10> = Tensor2D::rand(&mut rng);
+        let mut opt: Lamb<Model> = Default::default();
+
+        let py = model.forward(x.trace());
+        let loss = (py - &y).square().mean();
+        let gradients = loss.backward();
+        opt.update(&mut model, gradients).expect("");
+
+        let model_1 = model.clone();
+
+        assert!(model_0.0.weight.data() != model_1.0.weight.data());
+        assert!(model_0.0.bias.data() != model_1.0.bias.data());
+        assert!(model_0.2.weight.data() != model_1.2.weight.data());
+        assert!(model_0.2.bias.data() != model_1.2.bias.data());
+        assert!(model_0.4.weight.data() != model_1.4.weight.data());
+        assert!(model_0.4.bias.data() != model_1.4.bias.data());
+    }
+
+    #[test]
+    fn test_lamb_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: Lamb<Model> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
@@ -0,0 +1,220 @@
+use super::clip::visit_gradients;
+use crate::prelude::*;
+use std::marker::PhantomData;
+
+/// Dynamic loss scaling, which multiplies the loss by a large factor before the backward pass,
+/// and divides the gradients by it again before the wrapped optimizer updates the parameters.
+/// Small gradients that would otherwise underflow to `0.0` in reduced precision are kept this way.
+///
+/// If any gradient is `inf` or `nan` after a backward pass, the update is skipped and the scale
+/// is decreased by [LossScalerConfig::backoff_factor]. After [LossScalerConfig::growth_interval]
+/// updates without overflow, the scale is increased by [LossScalerConfig::growth_factor].
+/// This is the same algorithm as pytorch's [GradScaler](https://pytorch.org/docs/stable/amp.html#gradient-scaling).
+///
+/// Use it with [MasterWeights] and [Autocast] for mixed precision training, see [MasterWeights].
+/// Skipping updates with non-finite gradients is also useful on its own in full precision.
+///
+/// # Example Usage
+///
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = (Linear<5, 16>, ReLU, Linear<16, 2>);
+/// let mut model: Model = Default::default();
+/// let mut opt: LossScaler<Model, Adam<Model>> = Default::default();
+/// let x: Tensor2D<4, 5> = Tensor2D::zeros();
+/// let loss = model.forward(x.trace()).square().mean();
+/// let gradients = opt.scale_loss(loss).backward();
+/// opt.update(&mut model, gradients).expect("unused params");
+/// assert!(!opt.skipped_last_update());
+/// ```
+#[derive(Debug)]
+pub struct LossScaler<M, O> {
+    /// The wrapped optimizer that does the actual update.
+    pub opt: O,
+
+    /// Hyperparameter configuration
+    pub cfg: LossScalerConfig,
+
+    scale: f32,
+    num_good_updates: usize,
+    skipped_last_update: bool,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [LossScaler].
+#[derive(Debug, Clone, Copy)]
+pub struct LossScalerConfig {
+    /// The scale before the first update. Defaults to `65536.0`.
+    pub init_scale: f32,
+
+    /// What the scale is multiplied with after [LossScalerConfig::growth_interval] updates
+    /// without overflow. Defaults to `2.0`.
+    pub growth_factor: f32,
+
+    /// What the scale is multiplied with when an update is skipped. Defaults to `0.5`.
+    pub backoff_factor: f32,
+
+    /// How many updates without overflow increase the scale. Defaults to `2000`.
+    pub growth_interval: usize,
+}
+
+impl Default for LossScalerConfig {
+    fn default() -> Self {
+        Self {
+            init_scale: 65536.0,
+            growth_factor: 2.0,
+            backoff_factor: 0.5,
+            growth_interval: 2000,
+        }
+    }
+}
+
+impl<M, O: Default> Default for LossScaler<M, O> {
+    /// See [LossScalerConfig]
+    fn default() -> Self {
+        Self::new(Default::default(), Default::default())
+    }
+}
+
+impl<M, O> LossScaler<M, O> {
+    /// Wraps `opt` using hyperparameters from `cfg`.
+    pub fn new(opt: O, cfg: LossScalerConfig) -> Self {
+        Self {
+            opt,
+            scale: cfg.init_scale,
+            cfg,
+            num_good_updates: 0,
+            skipped_last_update: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// The current scale.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Multiplies `loss` by the current scale. Call [Tensor0D::backward()] on the result.
+    pub fn scale_loss<H: Tape>(&self, loss: Tensor0D<H>) -> Tensor0D<H> {
+        loss * self.scale
+    }
+
+    /// Whether the last call to [Optimizer::update()] skipped the update because of
+    /// non-finite gradients.
+    pub fn skipped_last_update(&self) -> bool {
+        self.skipped_last_update
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams, O: Optimizer<M>> Optimizer<M> for LossScaler<M, O> {
+    /// Unscales the gradients and updates `module` with the wrapped optimizer, unless any
+    /// gradient is not finite. In that case the parameters are not changed, and `Ok(())` is returned.
+    fn update(
+        &mut self,
+        module: &mut M,
+        mut gradients: Gradients,
+    ) -> Result<(), UnusedParamsError> {
+        let inv_scale = 1.0 / self.scale;
+        let mut finite = true;
+        visit_gradients(module, &mut gradients, |g| {
+            *g *= inv_scale;
+            finite &= g.is_finite();
+        });
+
+        self.skipped_last_update = !finite;
+        if !finite {
+            self.scale *= self.cfg.backoff_factor;
+            self.num_good_updates = 0;
+            return Ok(());
+        }
+
+        self.num_good_updates += 1;
+        if self.num_good_updates == self.cfg.growth_interval {
+            self.scale *= self.cfg.growth_factor;
+            self.num_good_updates = 0;
+        }
+        self.opt.update(module, gradients)
+    }
+}
+
+impl<M, O: HasLearningRate> HasLearningRate for LossScaler<M, O> {
+    fn lr(&self) -> f32 {
+        self.opt.lr()
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.opt.set_lr(lr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    fn sgd<M>() -> Sgd<M> {
+        Sgd::new(SgdConfig {
+            lr: 1.0,
+            momentum: None,
+            grad_clip: None,
+        })
+    }
+
+    #[test]
+    fn test_loss_scaler_matches_unscaled() {
+        let rate = Tensor1D::new([0.1, 1.0, 2.0]);
+        let mut t0: Tensor1D<3> = Tensor1D::ones();
+        let mut t1: Tensor1D<3> = Tensor1D::ones();
+        let mut opt0: Sgd<Tensor1D<3>> = sgd();
+        let mut opt1: LossScaler<Tensor1D<3>, _> = LossScaler::new(sgd(), Default::default());
+        for _ in 0..3 {
+            let g = (t0.trace() * &rate).square().sum().backward();
+            opt0.update(&mut t0, g).expect("");
+            let loss = (t1.trace() * &rate).square().sum();
+            let g = opt1.scale_loss(loss).backward();
+            opt1.update(&mut t1, g).expect("");
+            assert!(!opt1.skipped_last_update());
+        }
+        assert_close(t0.data(), t1.data());
+    }
+
+    #[test]
+    fn test_loss_scaler_skips_overflow() {
+        let mut t: Tensor1D<2> = Tensor1D::ones();
+        let mut opt: LossScaler<Tensor1D<2>, _> = LossScaler::new(
+            sgd(),
+            LossScalerConfig {
+                init_scale: 4.0,
+                growth_interval: 2,
+                ..Default::default()
+            },
+        );
+
+        let mut gradients: Gradients = Default::default();
+        *gradients.mut_gradient(&t) = [f32::INFINITY, 1.0];
+        opt.update(&mut t, gradients).expect("");
+        assert!(opt.skipped_last_update());
+        assert_eq!(t.data(), &[1.0, 1.0]);
+        assert_eq!(opt.scale(), 2.0);
+
+        for _ in 0..2 {
+            let mut gradients: Gradients = Default::default();
+            *gradients.mut_gradient(&t) = [2.0, -2.0];
+            opt.update(&mut t, gradients).expect("");
+            assert!(!opt.skipped_last_update());
+        }
+        assert_eq!(t.data(), &[-1.0, 3.0]);
+        assert_eq!(opt.scale(), 4.0);
+    }
+
+    #[test]
+    fn test_loss_scaler_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: LossScaler<Model, Sgd<Model>> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
@@ -0,0 +1,418 @@
+/// Something that has a learning rate that can be read & modified, such as all the optimizers in [crate::optim].
+///
+/// This is what [Scheduler]s use to change the learning rate of an optimizer while training.
+pub trait HasLearningRate {
+    /// The current learning rate
+    fn lr(&self) -> f32;
+
+    /// Sets the learning rate to `lr`
+    fn set_lr(&mut self, lr: f32);
+}
+
+/// A learning rate schedule, which changes the learning rate of an optimizer
+/// every time [Scheduler::step()] is called (usually once per epoch or once per batch).
+///
+/// Implementations:
+/// - [StepLR]
+/// - [ExponentialLR]
+/// - [CosineAnnealingLR]
+/// - [LinearDecayLR]
+/// - [OneCycleLR]
+/// - [LinearWarmup], which adds warmup to any other [Scheduler]
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Linear<5, 2>;
+/// let mut opt: Sgd<Model> = Sgd::new(SgdConfig { lr: 1e-1, momentum: None, grad_clip: None });
+/// let mut sched = StepLR::new(1e-1, 2, 0.5);
+/// for _epoch in 0..4 {
+///     // -- snip training loop --
+///     sched.step(&mut opt);
+/// }
+/// assert_eq!(opt.cfg.lr, 0.025);
+/// ```
+pub trait Scheduler {
+    /// The learning rate for the current step.
+    fn lr(&self) -> f32;
+
+    /// Advances the schedule by one step without modifying any optimizer.
+    fn advance(&mut self);
+
+    /// Advances the schedule by one step, and then sets the learning rate of `opt`
+    /// to [Scheduler::lr()].
+    fn step<O: HasLearningRate>(&mut self, opt: &mut O) {
+        self.advance();
+        opt.set_lr(self.lr());
+    }
+}
+
+/// Decays the learning rate by `gamma` every `step_size` steps.
+///
+/// `lr = base_lr * gamma ^ (t / step_size)`
+///
+/// Based on [pytorch's StepLR](https://pytorch.org/docs/stable/generated/torch.optim.lr_scheduler.StepLR.html)
+#[derive(Debug, Clone, Copy)]
+pub struct StepLR {
+    /// The initial learning rate.
+    pub base_lr: f32,
+
+    /// Number of steps between each decay.
+    pub step_size: usize,
+
+    /// Multiplicative factor of decay.
+    pub gamma: f32,
+
+    t: usize,
+}
+
+impl StepLR {
+    /// Constructs a [StepLR] starting at `base_lr`.
+    pub fn new(base_lr: f32, step_size: usize, gamma: f32) -> Self {
+        assert!(step_size > 0);
+        Self {
+            base_lr,
+            step_size,
+            gamma,
+            t: 0,
+        }
+    }
+}
+
+impl Scheduler for StepLR {
+    fn lr(&self) -> f32 {
+        self.base_lr * self.gamma.powi((self.t / self.step_size) as i32)
+    }
+
+    fn advance(&mut self) {
+        self.t += 1;
+    }
+}
+
+/// Decays the learning rate by `gamma` every step.
+///
+/// `lr = base_lr * gamma ^ t`
+///
+/// Based on [pytorch's ExponentialLR](https://pytorch.org/docs/stable/generated/torch.optim.lr_scheduler.ExponentialLR.html)
+#[derive(Debug, Clone, Copy)]
+pub struct ExponentialLR {
+    /// The initial learning rate.
+    pub base_lr: f32,
+
+    /// Multiplicative factor of decay.
+    pub gamma: f32,
+
+    t: usize,
+}
+
+impl ExponentialLR {
+    /// Constructs an [ExponentialLR] starting at `base_lr`.
+    pub fn new(base_lr: f32, gamma: f32) -> Self {
+        Self {
+            base_lr,
+            gamma,
+            t: 0,
+        }
+    }
+}
+
+impl Scheduler for ExponentialLR {
+    fn lr(&self) -> f32 {
+        self.base_lr * self.gamma.powi(self.t as i32)
+    }
+
+    fn advance(&mut self) {
+        self.t += 1;
+    }
+}
+
+/// Anneals the learning rate from `base_lr` to `min_lr` over `t_max` steps following
+/// half a period of a cosine. After `t_max` steps the learning rate stays at `min_lr`.
+///
+/// `lr = min_lr + (base_lr - min_lr) * (1 + cos(pi * t / t_max)) / 2`
+///
+/// Based on [pytorch's CosineAnnealingLR](https://pytorch.org/docs/stable/generated/torch.optim.lr_scheduler.CosineAnnealingLR.html),
+/// without warm restarts.
+#[derive(Debug, Clone, Copy)]
+pub struct CosineAnnealingLR {
+    /// The initial learning rate.
+    pub base_lr: f32,
+
+    /// The minimum learning rate.
+    pub min_lr: f32,
+
+    /// Number of steps to anneal over.
+    pub t_max: usize,
+
+    t: usize,
+}
+
+impl CosineAnnealingLR {
+    /// Constructs a [CosineAnnealingLR] starting at `base_lr`.
+    pub fn new(base_lr: f32, min_lr: f32, t_max: usize) -> Self {
+        assert!(t_max > 0);
+        Self {
+            base_lr,
+            min_lr,
+            t_max,
+            t: 0,
+        }
+    }
+}
+
+impl Scheduler for CosineAnnealingLR {
+    fn lr(&self) -> f32 {
+        let progress = self.t.min(self.t_max) as f32 / self.t_max as f32;
+        let scale = 0.5 * (1.0 + (std::f32::consts::PI * progress).cos());
+        self.min_lr + (self.base_lr - self.min_lr) * scale
+    }
+
+    fn advance(&mut self) {
+        self.t += 1;
+    }
+}
+
+/// Linearly increases the learning rate from `0` to the starting learning rate of `inner` over
+/// `warmup_steps` steps, and then follows the `inner` schedule.
+///
+/// Use this to add warmup to any other [Scheduler]:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let sched = LinearWarmup::new(CosineAnnealingLR::new(1e-3, 0.0, 1000), 100);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct LinearWarmup<S> {
+    /// The schedule to follow after warmup.
+    pub inner: S,
+
+    /// Number of warmup steps.
+    pub warmup_steps: usize,
+
+    t: usize,
+}
+
+impl<S: Scheduler> LinearWarmup<S> {
+    /// Constructs a [LinearWarmup] that warms up to `inner`'s starting learning rate.
+    pub fn new(inner: S, warmup_steps: usize) -> Self {
+        Self {
+            inner,
+            warmup_steps,
+            t: 0,
+        }
+    }
+}
+
+impl<S: Scheduler> Scheduler for LinearWarmup<S> {
+    fn lr(&self) -> f32 {
+        if self.t < self.warmup_steps {
+            self.inner.lr() * self.t as f32 / self.warmup_steps as f32
+        } else {
+            self.inner.lr()
+        }
+    }
+
+    fn advance(&mut self) {
+        if self.t >= self.warmup_steps {
+            self.inner.advance();
+        }
+        self.t += 1;
+    }
+}
+
+/// Linearly decays the learning rate from `base_lr` to `0` over `total_steps` steps.
+/// Combine with [LinearWarmup] for the common linear warmup then linear decay schedule.
+///
+/// `lr = base_lr * (1 - t / total_steps)`
+#[derive(Debug, Clone, Copy)]
+pub struct LinearDecayLR {
+    /// The initial learning rate.
+    pub base_lr: f32,
+
+    /// Number of steps to decay over.
+    pub total_steps: usize,
+
+    t: usize,
+}
+
+impl LinearDecayLR {
+    /// Constructs a [LinearDecayLR] starting at `base_lr`.
+    pub fn new(base_lr: f32, total_steps: usize) -> Self {
+        assert!(total_steps > 0);
+        Self {
+            base_lr,
+            total_steps,
+            t: 0,
+        }
+    }
+}
+
+impl Scheduler for LinearDecayLR {
+    fn lr(&self) -> f32 {
+        let progress = self.t.min(self.total_steps) as f32 / self.total_steps as f32;
+        self.base_lr * (1.0 - progress)
+    }
+
+    fn advance(&mut self) {
+        self.t += 1;
+    }
+}
+
+/// The 1cycle policy from [Super-Convergence](https://arxiv.org/abs/1708.07120). The learning rate
+/// is annealed from `max_lr / div_factor` up to `max_lr` over the first `pct_start` fraction of `total_steps`,
+/// and then annealed down to `max_lr / (div_factor * final_div_factor)` over the rest of the steps.
+///
+/// Both phases use cosine annealing. Based on [pytorch's OneCycleLR](https://pytorch.org/docs/stable/generated/torch.optim.lr_scheduler.OneCycleLR.html),
+/// without momentum cycling.
+///
+/// Changing the defaults:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let mut sched = OneCycleLR::new(1e-2, 1000);
+/// sched.pct_start = 0.25;
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct OneCycleLR {
+    /// The peak learning rate.
+    pub max_lr: f32,
+
+    /// Total number of steps in the cycle.
+    pub total_steps: usize,
+
+    /// Fraction of `total_steps` spent increasing the learning rate. Defaults to `0.3`.
+    pub pct_start: f32,
+
+    /// The initial learning rate is `max_lr / div_factor`. Defaults to `25.0`.
+    pub div_factor: f32,
+
+    /// The final learning rate is `max_lr / (div_factor * final_div_factor)`. Defaults to `1e4`.
+    pub final_div_factor: f32,
+
+    t: usize,
+}
+
+impl OneCycleLR {
+    /// Constructs a [OneCycleLR] with a peak of `max_lr` that lasts `total_steps` steps.
+    pub fn new(max_lr: f32, total_steps: usize) -> Self {
+        assert!(total_steps > 1);
+        Self {
+            max_lr,
+            total_steps,
+            pct_start: 0.3,
+            div_factor: 25.0,
+            final_div_factor: 1e4,
+            t: 0,
+        }
+    }
+}
+
+impl Scheduler for OneCycleLR {
+    fn lr(&self) -> f32 {
+        let initial_lr = self.max_lr / self.div_factor;
+        let min_lr = initial_lr / self.final_div_factor;
+        let peak_step = (self.pct_start * self.total_steps as f32 - 1.0).max(1.0);
+        let last_step = (self.total_steps - 1) as f32;
+        let t = (self.t as f32).min(last_step);
+        let (start, end, pct) = if t <= peak_step {
+            (initial_lr, self.max_lr, t / peak_step)
+        } else {
+            (
+                self.max_lr,
+                min_lr,
+                (t - peak_step) / (last_step - peak_step),
+            )
+        };
+        end + (start - end) * 0.5 * (1.0 + (std::f32::consts::PI * pct).cos())
+    }
+
+    fn advance(&mut self) {
+        self.t += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::tests::assert_close;
+
+    fn collect_lrs<S: Scheduler, const N: usize>(mut sched: S) -> [f32; N] {
+        let mut lrs = [0.0; N];
+        for lr in lrs.iter_mut() {
+            *lr = sched.lr();
+            sched.advance();
+        }
+        lrs
+    }
+
+    #[test]
+    fn test_step_lr() {
+        let lrs: [f32; 7] = collect_lrs(StepLR::new(1.0, 3, 0.1));
+        assert_close(&lrs, &[1.0, 1.0, 1.0, 0.1, 0.1, 0.1, 0.01]);
+    }
+
+    #[test]
+    fn test_exponential_lr() {
+        let lrs: [f32; 5] = collect_lrs(ExponentialLR::new(1.0, 0.5));
+        assert_close(&lrs, &[1.0, 0.5, 0.25, 0.125, 0.0625]);
+    }
+
+    #[test]
+    fn test_cosine_annealing_lr() {
+        let lrs: [f32; 6] = collect_lrs(CosineAnnealingLR::new(1.0, 0.0, 4));
+        assert_close(&lrs, &[1.0, 0.8535534, 0.5, 0.14644661, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_scheduler_sets_optimizer_lr() {
+        type Model = Tensor1D<5>;
+        let mut opt: Adam<Model> = Default::default();
+        let mut sched = ExponentialLR::new(1e-3, 0.5);
+        sched.step(&mut opt);
+        assert_eq!(opt.lr(), 5e-4);
+        assert_eq!(opt.cfg.lr, 5e-4);
+        sched.step(&mut opt);
+        assert_eq!(opt.cfg.lr, 2.5e-4);
+    }
+
+    #[test]
+    fn test_scheduled_sgd_updates_with_new_lr() {
+        let mut t: Tensor1D<3> = Tensor1D::zeros();
+        let mut opt: Sgd<Tensor1D<3>> = Sgd::new(SgdConfig {
+            lr: 1.0,
+            momentum: None,
+            grad_clip: None,
+        });
+        let mut sched = StepLR::new(1.0, 1, 0.5);
+        for _ in 0..3 {
+            let gradients = t.trace().sum().backward();
+            opt.update(&mut t, gradients).expect("");
+            sched.step(&mut opt);
+        }
+        // steps of size 1.0, 0.5, 0.25
+        assert_close(t.data(), &[-1.75; 3]);
+    }
+
+    #[test]
+    fn test_linear_warmup() {
+        let lrs: [f32; 7] = collect_lrs(LinearWarmup::new(ExponentialLR::new(1.0, 0.5), 4));
+        assert_close(&lrs, &[0.0, 0.25, 0.5, 0.75, 1.0, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn test_linear_warmup_then_decay() {
+        let lrs: [f32; 8] = collect_lrs(LinearWarmup::new(LinearDecayLR::new(1.0, 4), 2));
+        assert_close(&lrs, &[0.0, 0.5, 1.0, 0.75, 0.5, 0.25, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_one_cycle_lr() {
+        let sched = OneCycleLR {
+            pct_start: 0.5,
+            div_factor: 10.0,
+            final_div_factor: 10.0,
+            ..OneCycleLR::new(1.0, 6)
+        };
+        let lrs: [f32; 7] = collect_lrs(sched);
+        assert_close(&lrs, &[0.1, 0.55, 1.0, 0.7525, 0.2575, 0.01, 0.01]);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::devices::{flat, flat_mut};
+use crate::prelude::*;
+use crate::tensor_ops::round_to_f16;
+
+/// Keeps an `f32` copy (the "master weights") of a model whose parameters are rounded to half
+/// precision, for mixed precision training as described in
+/// [Mixed Precision Training](https://arxiv.org/abs/1710.03740).
+///
+/// The wrapped optimizer updates the master weights, and the parameters of the model are set to the
+/// master weights rounded to `f16` after every update. Small updates that would be lost when
+/// adding them to a half precision parameter add up in the master weights this way.
+///
+/// Together with [Autocast] for the layers that run in half precision, and [LossScaler] so that
+/// small gradients don't underflow in the backward pass, this is automatic mixed precision training:
+///
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = (Autocast<Linear<5, 16>>, ReLU, Autocast<Linear<16, 2>>);
+/// let mut model: Model = Default::default();
+/// model.reset_params_seeded();
+/// let mut opt: LossScaler<Model, MasterWeights<Model, Adam<Model>>> =
+///     LossScaler::new(MasterWeights::new(Default::default(), &mut model), Default::default());
+///
+/// let x: Tensor2D<4, 5> = Tensor2D::zeros();
+/// let loss = model.forward(x.trace()).square().mean();
+/// let gradients = opt.scale_loss(loss).backward();
+/// opt.update(&mut model, gradients).expect("unused params");
+/// ```
+///
+/// **Note** Tensors are always stored as `f32`, so this emulates the precision of `f16`,
+/// but not its memory use.
+#[derive(Debug)]
+pub struct MasterWeights<M, O> {
+    /// The wrapped optimizer, which updates the master weights.
+    pub opt: O,
+
+    master: M,
+}
+
+impl<M: Clone + VisitParams, O> MasterWeights<M, O> {
+    /// Wraps `opt`, and copies the parameters of `module` into the master weights. The parameters
+    /// of `module` are rounded to `f16`.
+    pub fn new(opt: O, module: &mut M) -> Self {
+        let master = module.clone();
+        module.visit_params_mut("", &mut RoundParams);
+        Self { opt, master }
+    }
+}
+
+impl<M, O> MasterWeights<M, O> {
+    /// The `f32` master weights, e.g. to save the model without rounding errors.
+    pub fn master(&self) -> &M {
+        &self.master
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams, O: Optimizer<M>> Optimizer<M>
+    for MasterWeights<M, O>
+{
+    /// Updates the master weights with the gradients of `module`'s parameters, and then copies
+    /// the master weights rounded to `f16` into `module`.
+    fn update(
+        &mut self,
+        module: &mut M,
+        mut gradients: Gradients,
+    ) -> Result<(), UnusedParamsError> {
+        // `module` & the master weights have the same type, so their parameters are visited
+        // in the same order
+        let mut take = TakeGradients {
+            gradients: &mut gradients,
+            taken: Vec::new(),
+        };
+        module.visit_params("", &mut take);
+        let mut put = PutGradients {
+            gradients: Default::default(),
+            taken: take.taken.into_iter(),
+        };
+        self.master.visit_params("", &mut put);
+
+        let result = self.opt.update(&mut self.master, put.gradients);
+
+        let mut values = CollectValues(Vec::new());
+        self.master.visit_params("", &mut values);
+        module.visit_params_mut("", &mut CopyRounded(values.0.into_iter()));
+        result
+    }
+}
+
+impl<M, O: HasLearningRate> HasLearningRate for MasterWeights<M, O> {
+    fn lr(&self) -> f32 {
+        self.opt.lr()
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.opt.set_lr(lr)
+    }
+}
+
+/// Rounds every parameter to `f16`.
+struct RoundParams;
+
+impl ParamVisitorMut for RoundParams {
+    fn visit_mut<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &mut P) {
+        P::Device::foreach_m(p.mut_data(), &mut |x| *x = round_to_f16(*x));
+    }
+}
+
+/// Removes the gradient of every parameter, in the order they are visited.
+struct TakeGradients<'a> {
+    gradients: &'a mut Gradients,
+    taken: Vec<Option<Vec<f32>>>,
+}
+
+impl ParamVisitor for TakeGradients<'_> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &P) {
+        let g = self.gradients.remove(p);
+        self.taken.push(g.map(|g| flat(g.as_ref()).to_vec()));
+    }
+}
+
+/// Inserts the gradients taken by [TakeGradients] for the corresponding parameters.
+struct PutGradients {
+    gradients: Gradients,
+    taken: std::vec::IntoIter<Option<Vec<f32>>>,
+}
+
+impl ParamVisitor for PutGradients {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &P) {
+        if let Some(g) = self.taken.next().unwrap() {
+            flat_mut(self.gradients.mut_gradient(p)).copy_from_slice(&g);
+        }
+    }
+}
+
+/// Collects the values of every parameter, in the order they are visited.
+struct CollectValues(Vec<Vec<f32>>);
+
+impl ParamVisitor for CollectValues {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &P) {
+        self.0.push(flat(p.data()).to_vec());
+    }
+}
+
+/// Copies the values collected by [CollectValues] rounded to `f16` into the corresponding parameters.
+struct CopyRounded(std::vec::IntoIter<Vec<f32>>);
+
+impl ParamVisitorMut for CopyRounded {
+    fn visit_mut<P: Tensor<Dtype = f32>>(&mut self, _: &str, p: &mut P) {
+        let values = self.0.next().unwrap();
+        for (p, v) in flat_mut(p.mut_data()).iter_mut().zip(values) {
+            *p = round_to_f16(v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_master_weights_keep_small_updates() {
+        let mut t: Tensor1D<2> = Tensor1D::new([1.0, 0.1]);
+        let sgd = Sgd::new(SgdConfig {
+            lr: 1e-4,
+            momentum: None,
+            grad_clip: None,
+        });
+        let mut opt = MasterWeights::new(sgd, &mut t);
+        assert_eq!(t.data(), &[1.0, 0.099975586]);
+
+        for _ in 0..10 {
+            let gradients = t.trace().sum().backward();
+            opt.update(&mut t, gradients).expect("");
+        }
+        assert_close(opt.master().data(), &[0.999, 0.099]);
+        assert_eq!(t.data(), &[0.99902344, 0.09899902]);
+
+        // without master weights, the updates are lost or rounded to a multiple of the f16 spacing
+        let mut t: Tensor1D<2> = Tensor1D::new([1.0, 0.1]).to_f16();
+        let mut sgd: Sgd<Tensor1D<2>> = Sgd::new(SgdConfig {
+            lr: 1e-4,
+            momentum: None,
+            grad_clip: None,
+        });
+        for _ in 0..10 {
+            let gradients = t.trace().sum().backward();
+            sgd.update(&mut t, gradients).expect("");
+            t = t.to_f16();
+        }
+        assert_eq!(t.data(), &[1.0, 0.09875488]);
+    }
+
+    #[test]
+    fn test_master_weights_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: MasterWeights<Model, Sgd<Model>> =
+            MasterWeights::new(Default::default(), &mut model);
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+
+    #[test]
+    fn test_loss_scaler_prevents_underflow() {
+        type Model = Autocast<Linear<1, 1>>;
+        let x = Tensor1D::new([1.0]);
+        let train = |scale: f32| {
+            let mut model: Model = Default::default();
+            let mut opt: LossScaler<Model, MasterWeights<Model, Sgd<Model>>> = LossScaler::new(
+                MasterWeights::new(Default::default(), &mut model),
+                LossScalerConfig {
+                    init_scale: scale,
+                    ..Default::default()
+                },
+            );
+            let loss = model.forward(x.trace()).sum() * 1e-8;
+            let gradients = opt.scale_loss(loss).backward();
+            opt.update(&mut model, gradients).expect("");
+            *opt.opt.master().0.bias.data()
+        };
+        // without scaling the gradient of `1e-8` is `0.0` in half precision
+        assert_eq!(train(1.0), [0.0]);
+        assert_close(&train(65536.0), &[-1e-10]);
+    }
+}
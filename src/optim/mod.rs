@@ -1,4 +1,4 @@
-//! Optimizers such as [Sgd], [Adam], and [RMSprop] that can optimize neural networks.
+//! Optimizers such as [Sgd], [Adam], [AdamW], [RMSprop], and [Adagrad] that can optimize neural networks.
 //!
 //! # Initializing
 //!
@@ -6,7 +6,14 @@
 //! all the relevant parameters through the corresponding config object:
 //! - [Sgd::new()] with [SgdConfig]
 //! - [Adam::new()] with [AdamConfig]
+//! - [AdamW::new()] with [AdamWConfig]
 //! - [RMSprop::new()] with [RMSpropConfig]
+//! - [Adagrad::new()] with [AdagradConfig]
+//! - [Adadelta::new()] with [AdadeltaConfig]
+//! - [RAdam::new()] with [RAdamConfig]
+//! - [NAdam::new()] with [NAdamConfig]
+//! - [Lamb::new()] with [LambConfig]
+//! - [Ftrl::new()] with [FtrlConfig]
 //!
 //! # Updating network parameters
 //!
@@ -25,13 +32,83 @@
 //! let gradients: Gradients = loss.backward();
 //! opt.update(&mut model, gradients);
 //! ```
+//!
+//! Each optimizer config also has a `grad_clip` field, which can be used to clip gradients
+//! with [GradClip] before the parameters are updated. Annealed gaussian noise can be added to the
+//! gradients of any optimizer by wrapping it with [GradNoise]. Wrapping it with [LossScaler] adds
+//! dynamic loss scaling, which skips updates with `inf` or `nan` gradients. Together with
+//! [MasterWeights] and [Autocast] this is used for mixed precision training.
+//!
+//! Parameters can be excluded from updates with [Freeze::freeze()], for example to only fine tune the
+//! head of a pretrained model.
+//!
+//! Different learning rates & weight decay for parts of a model can be used by wrapping
+//! any optimizer with [ParamGroups].
+//!
+//! Optimizers that need to compute gradients more than once per update, like [Sam], are given
+//! a closure that computes the gradients instead, see [Sam::step()]. All [Optimizer]s can be
+//! used the same way with [ClosureOptimizer::step()].
+//!
+//! An average of a model's parameters over training can be kept with [ModelEma].
+//!
+//! To train the same model in multiple processes (e.g. on different machines), average the
+//! gradients of all processes with [all_reduce_gradients()] before each update. How the
+//! processes communicate is defined by an [AllReduce] backend, such as [TcpAllReduce].
+//!
+//! # Saving & loading
+//!
+//! The internal state of optimizers can be saved with [SaveStateToNpz] and loaded with [LoadStateFromNpz],
+//! which allows resuming training exactly. A [Checkpoint] saves the model, the optimizer state, the
+//! random number generator, epoch & step counters, and metadata together in a single file.
+//!
+//! # Changing the learning rate
+//!
+//! All optimizers implement [HasLearningRate], so the learning rate can be changed during
+//! training by a [Scheduler] such as [StepLR], [ExponentialLR], [CosineAnnealingLR], or [OneCycleLR].
+//! Warmup can be added to any [Scheduler] with [LinearWarmup].
 
+mod adadelta;
+mod adagrad;
 mod adam;
+mod adamw;
+mod all_reduce;
+mod checkpoint;
+mod clip;
+mod ema;
+mod ftrl;
+mod grad_noise;
+mod lamb;
+mod loss_scaler;
+mod lr_scheduler;
+mod master_weights;
+mod nadam;
 mod optimizer;
+mod param_groups;
+mod radam;
 mod rmsprop;
+mod sam;
 mod sgd;
+mod state;
 
+pub use adadelta::*;
+pub use adagrad::*;
 pub use adam::*;
+pub use adamw::*;
+pub use all_reduce::*;
+pub use checkpoint::*;
+pub use clip::*;
+pub use ema::*;
+pub use ftrl::*;
+pub use grad_noise::*;
+pub use lamb::*;
+pub use loss_scaler::*;
+pub use lr_scheduler::*;
+pub use master_weights::*;
+pub use nadam::*;
 pub use optimizer::*;
+pub use param_groups::*;
+pub use radam::*;
 pub use rmsprop::*;
+pub use sam::*;
 pub use sgd::*;
+pub use state::*;
@@ -0,0 +1,327 @@
+use super::adam::update_moments;
+use super::state::{read_step, write_step};
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// An implementation of Adam with Nesterov momentum from
+/// [Incorporating Nesterov Momentum into Adam](https://openreview.net/forum?id=OM0jvwB8jIp57ZJjtNEZ).
+///
+/// This is [Adam] where the first moment estimate looks ahead one step, using
+/// the momentum decay schedule `mu_t = beta1 * (1 - 0.5 * 0.96^(t * momentum_decay))`.
+///
+/// # Example Usage
+///
+/// Constructing using default:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: NAdam<Model> = Default::default();
+/// ```
+///
+/// Changing using new
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: NAdam<Model> = NAdam::new(NAdamConfig {
+///     lr: 1e-2,
+///     betas: [0.5, 0.25],
+///     eps: 1e-6,
+///     momentum_decay: 4e-3,
+///     grad_clip: None,
+/// });
+/// ```
+///
+/// See module level documentation at [crate::optim] for examples of how to actually use an optimizer.
+#[derive(Debug)]
+pub struct NAdam<M> {
+    /// Hyperparameter configuration
+    pub cfg: NAdamConfig,
+
+    t: i32,
+    gradients: Gradients,
+    moment1: Gradients,
+    moment2: Gradients,
+    mu_product: f32,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [NAdam].
+///
+/// Changing all default parameters:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// NAdamConfig {
+///     lr: 1e-2,
+///     betas: [0.1, 0.2],
+///     eps: 1e-6,
+///     momentum_decay: 4e-3,
+///     grad_clip: None,
+/// };
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct NAdamConfig {
+    /// Learning rate. Defaults to `2e-3`.
+    pub lr: f32,
+
+    /// Betas from Adam paper. Defaults to `[0.9, 0.999]`.
+    pub betas: [f32; 2],
+
+    /// Epsilon for numerical stability. Defaults to `1e-8`.
+    pub eps: f32,
+
+    /// Decay of the momentum schedule. Defaults to `4e-3`.
+    pub momentum_decay: f32,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
+}
+
+impl Default for NAdamConfig {
+    fn default() -> Self {
+        Self {
+            lr: 2e-3,
+            betas: [0.9, 0.999],
+            eps: 1e-8,
+            momentum_decay: 4e-3,
+            grad_clip: None,
+        }
+    }
+}
+
+impl<M> Default for NAdam<M> {
+    /// See [NAdamConfig]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<M> NAdam<M> {
+    /// Constructs using hyperparameters from `cfg`.
+    pub fn new(cfg: NAdamConfig) -> Self {
+        Self {
+            cfg,
+            t: 0,
+            gradients: Default::default(),
+            moment1: Default::default(),
+            moment2: Default::default(),
+            mu_product: 1.0,
+            marker: PhantomData,
+        }
+    }
+
+    /// The momentum coefficient at step `t`.
+    fn mu(&self, t: i32) -> f32 {
+        self.cfg.betas[0] * (1.0 - 0.5 * 0.96f32.powf(t as f32 * self.cfg.momentum_decay))
+    }
+}
+
+impl<M> GradientProvider for NAdam<M> {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let mu_t = self.mu(self.t);
+        let mu_next = self.mu(self.t + 1);
+        let grad_scale = self.cfg.lr * (1.0 - mu_t) / (1.0 - self.mu_product);
+        let moment_scale = self.cfg.lr * mu_next / (1.0 - self.mu_product * mu_next);
+        let bias_correction2 = (1.0 - self.cfg.betas[1].powi(self.t)).recip();
+
+        let mut g_t = self.gradients.remove(p)?;
+        let m_t = self.moment1.mut_gradient(p);
+        let v_t = self.moment2.mut_gradient(p);
+        P::Device::foreach_mmm(g_t.as_mut(), m_t, v_t, &mut |g, m, v| {
+            update_moments(*g, m, v, self.cfg.betas);
+            let denom = (*v * bias_correction2).sqrt() + self.cfg.eps;
+            *g = (grad_scale * *g + moment_scale * *m) / denom;
+        });
+        Some(g_t)
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for NAdam<M> {
+    fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
+        self.begin_step(module, gradients);
+        let mut unused_tensors = Default::default();
+        module.update(self, &mut unused_tensors);
+        unused_tensors.into()
+    }
+}
+
+impl<M> OptimizerStep for NAdam<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.t = self.t.checked_add(1).unwrap();
+        self.mu_product *= self.mu(self.t);
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for NAdam<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for NAdam<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_step(w, format!("{filename_prefix}t.npy"), self.t as usize)?;
+        npz_fwrite(
+            w,
+            format!("{filename_prefix}mu_product.npy"),
+            &self.mu_product,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &self.moment1,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for NAdam<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        self.t = read_step(r, format!("{filename_prefix}t.npy"))? as i32;
+        npz_fread(
+            r,
+            format!("{filename_prefix}mu_product.npy"),
+            &mut self.mu_product,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &mut self.moment1,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &mut self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::*, SeedableRng};
+
+    fn test_matches_expected(mut opt: NAdam<Tensor1D<5>>, expected: [[f32; 5]; 10]) {
+        let mut t: Tensor1D<5> = Tensor1D::ones();
+        let rate = Tensor1D::new([1e-4, 1e-3, 1e-2, 1e-1, 1e-0]);
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().mean().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_default_nadam_params() {
+        #[rustfmt::skip]
+        const EXPECTED: [[f32; 5]; 10] = [
+            [0.9993963, 0.99793863, 0.9978876, 0.9978871, 0.9978871],
+            [0.9989487, 0.9964109, 0.9963221, 0.9963212, 0.9963212],
+            [0.9985306, 0.9949843, 0.9948602, 0.9948589, 0.9948589],
+            [0.99811333, 0.9935607, 0.9934013, 0.9933997, 0.9933997],
+            [0.99768764, 0.9921086, 0.99191326, 0.9919113, 0.9919113],
+            [0.9972506, 0.9906182, 0.99038595, 0.9903836, 0.9903836],
+            [0.9968018, 0.9890879, 0.9888177, 0.98881495, 0.98881495],
+            [0.9963416, 0.98751915, 0.9872101, 0.98720694, 0.98720694],
+            [0.9958708, 0.9859149, 0.985566, 0.9855625, 0.9855625],
+            [0.9953905, 0.98427826, 0.9838888, 0.9838849, 0.9838849],
+        ];
+        test_matches_expected(Default::default(), EXPECTED);
+    }
+
+    #[test]
+    fn test_custom_nadam_params() {
+        let opt = NAdam::new(NAdamConfig {
+            lr: 1e-2,
+            betas: [0.5, 0.25],
+            eps: 1e-8,
+            momentum_decay: 1e-2,
+            grad_clip: None,
+        });
+        #[rustfmt::skip]
+        const EXPECTED: [[f32; 5]; 10] = [
+            [0.99676156, 0.9889419, 0.9886683, 0.9886655, 0.98866546],
+            [0.99393785, 0.9792947, 0.9787807, 0.9787754, 0.9787753],
+            [0.9911451, 0.96973765, 0.96898323, 0.9689754, 0.96897537],
+            [0.9883405, 0.96012247, 0.95912385, 0.95911354, 0.9591135],
+            [0.98552597, 0.95045507, 0.94920844, 0.94919556, 0.9491955],
+            [0.9827077, 0.94075656, 0.9392586, 0.93924314, 0.939243],
+            [0.97989, 0.93104213, 0.9292898, 0.92927176, 0.9292716],
+            [0.97707534, 0.9213206, 0.9193111, 0.9192904, 0.91929024],
+            [0.97426516, 0.91159683, 0.9093274, 0.909304, 0.9093038],
+            [0.97146004, 0.90187323, 0.8993411, 0.89931506, 0.89931476],
+        ];
+        test_matches_expected(opt, EXPECTED);
+    }
+
+    #[test]
+    fn test_nadam_changes_all_params() {
+        type Model = (Linear<5, 16>, ReLU, Linear<16, 16>, ReLU, Linear<16, 10>);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let model_0 = model.clone();
+
+        let x: Tensor2D<16, 5> = Tensor2D::rand(&mut rng);
+        let y: Tensor2D<16, 10> = Tensor2D::rand(&mut rng);
+        let mut opt: NAdam<Model> = Default::default();
+
+        let py = model.forward(x.trace());
+        let loss = (py - &y).square().mean();
+        let gradients = loss.backward();
+        opt.update(&mut model, gradients).expect("");
+
+        let model_1 = model.clone();
+
+        assert!(model_0.0.weight.data() != model_1.0.weight.data());
+        assert!(model_0.0.bias.data() != model_1.0.bias.data());
+        assert!(model_0.2.weight.data() != model_1.2.weight.data());
+        assert!(model_0.2.bias.data() != model_1.2.bias.data());
+        assert!(model_0.4.weight.data() != model_1.4.weight.data());
+        assert!(model_0.4.bias.data() != model_1.4.bias.data());
+    }
+
+    #[test]
+    fn test_nadam_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: NAdam<Model> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
@@ -1,4 +1,6 @@
-use crate::prelude::{CanUpdateWithGradients, Gradients, UnusedTensors};
+use crate::prelude::{
+    CanUpdateWithGradients, GradientProvider, Gradients, UnusedTensors, VisitParams,
+};
 
 /// All optimizers must implement the update function, which takes an object
 /// that implements [CanUpdateWithGradients], and calls [CanUpdateWithGradients::update].
@@ -6,15 +8,15 @@ use crate::prelude::{CanUpdateWithGradients, Gradients, UnusedTensors};
 /// # Notes
 ///
 /// 1. [CanUpdateWithGradients] requires an object that implements [crate::gradients::GradientProvider].
-/// A common implementation involves implementing both [Optimizer] and [crate::gradients::GradientProvider]
-/// on one struct, and passing self to [CanUpdateWithGradients::update]. See [super::Sgd] for an example
-/// of implementing this trait.
+///    A common implementation involves implementing both [Optimizer] and [crate::gradients::GradientProvider]
+///    on one struct, and passing self to [CanUpdateWithGradients::update]. See [super::Sgd] for an example
+///    of implementing this trait.
 ///
 /// 2. Update takes ownership of [Gradients], so update cannot be called
-/// with the same gradients object.
+///    with the same gradients object.
 ///
 /// 3. Optimizer itself is generic over M, not the update method. This means a single optimizer object
-/// can only work on objects of type `M`. This also requires you to specify the model up front for the optimizer.
+///    can only work on objects of type `M`. This also requires you to specify the model up front for the optimizer.
 pub trait Optimizer<M: CanUpdateWithGradients> {
     /// Updates all of `module`'s parameters using `gradients`.
     ///
@@ -23,6 +25,55 @@ pub trait Optimizer<M: CanUpdateWithGradients> {
     fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError>;
 }
 
+/// An optimizer that is given a closure that computes gradients, instead of the gradients themselves.
+/// This is the same interface as [super::Sam::step()], which computes gradients multiple times per update.
+///
+/// Every [Optimizer] implements this by calling the closure once, and then calling [Optimizer::update()].
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Linear<5, 2>;
+/// let mut model: Model = Default::default();
+/// let mut opt: Sgd<Model> = Default::default();
+/// let x: Tensor1D<5> = Tensor1D::zeros();
+/// let y: Tensor1D<2> = Tensor1D::ones();
+/// opt.step(&mut model, |m| mse_loss(m.forward(x.trace()), &y).backward())
+///     .expect("unused params");
+/// ```
+pub trait ClosureOptimizer<M: CanUpdateWithGradients> {
+    /// Updates all of `module`'s parameters using the gradients returned by `closure`.
+    /// `closure` should run the forward & backward pass on the module it is given.
+    fn step<F: FnMut(&M) -> Gradients>(
+        &mut self,
+        module: &mut M,
+        closure: F,
+    ) -> Result<(), UnusedParamsError>;
+}
+
+impl<M: CanUpdateWithGradients, O: Optimizer<M>> ClosureOptimizer<M> for O {
+    fn step<F: FnMut(&M) -> Gradients>(
+        &mut self,
+        module: &mut M,
+        mut closure: F,
+    ) -> Result<(), UnusedParamsError> {
+        let gradients = closure(module);
+        self.update(module, gradients)
+    }
+}
+
+/// An optimizer whose [Optimizer::update()] is split into two parts:
+/// 1. [OptimizerStep::begin_step()], which is called once per update, and stores the gradients,
+///    clips them, and advances any step counters.
+/// 2. [GradientProvider::gradient()], which is called once for every parameter.
+///
+/// All optimizers in [crate::optim] implement this, which is what lets wrappers like [super::ParamGroups]
+/// change how individual parameters are updated.
+pub trait OptimizerStep: GradientProvider {
+    /// Prepares for updating the parameters of `module` with `gradients`.
+    fn begin_step<M: VisitParams>(&mut self, module: &M, gradients: Gradients);
+}
+
 /// An error indicating that a parameter was not used in gradient
 /// computation, and was therefore not present in [Gradients]
 /// while a [CanUpdateWithGradients] was trying to update it.
@@ -0,0 +1,315 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// Wraps any optimizer so that different groups of parameters can use different
+/// learning rates & weight decay, e.g. a smaller learning rate for a pretrained backbone,
+/// or no weight decay for biases.
+///
+/// Groups are made up of all the parameters whose name (see [VisitParams]) starts with a prefix, and are
+/// added with [ParamGroups::add_group()]. Since groups refer to names instead of specific tensors, they
+/// still apply after the model is cloned or loaded from a file.
+/// Parameters that are not in any group are updated by the wrapped optimizer as usual.
+///
+/// For parameters in a group:
+/// 1. The wrapped optimizer computes the update using the group's learning rate, which is the learning
+///    rate of the wrapped optimizer times [ParamGroupConfig::lr_scale]. Since it is relative, a
+///    [Scheduler] that changes the learning rate changes the learning rate of every group too.
+/// 2. Decoupled weight decay is applied as `p -= group_lr * weight_decay * p`, in addition to any weight decay
+///    the wrapped optimizer does itself.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = (Linear<5, 16>, ReLU, Linear<16, 10>);
+/// let mut model: Model = Default::default();
+/// let mut opt: ParamGroups<Model, Adam<Model>> = ParamGroups::new(Default::default());
+/// // use a 10x smaller learning rate for the first layer
+/// opt.add_group("0", ParamGroupConfig { lr_scale: 0.1, weight_decay: 0.0 });
+/// // and use weight decay on the weight of the last layer
+/// opt.add_group("2.weight", ParamGroupConfig { lr_scale: 1.0, weight_decay: 1e-2 });
+/// ```
+///
+/// Any optimizer that implements [OptimizerStep] and [HasLearningRate] can be wrapped.
+#[derive(Debug)]
+pub struct ParamGroups<M, O> {
+    /// The wrapped optimizer.
+    pub opt: O,
+
+    /// The config of each group, in the order they were added.
+    pub groups: Vec<ParamGroupConfig>,
+
+    /// The name prefix of each group in [Self::groups].
+    prefixes: Vec<String>,
+
+    /// The group of each parameter, collected in [OptimizerStep::begin_step()].
+    group_by_id: HashMap<UniqueId, usize>,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of a group of parameters in [ParamGroups].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamGroupConfig {
+    /// Multiplier of the learning rate of the wrapped optimizer for the group. Defaults to `1.0`.
+    pub lr_scale: f32,
+
+    /// Decoupled weight decay of the group. Defaults to `0.0`.
+    pub weight_decay: f32,
+}
+
+impl Default for ParamGroupConfig {
+    fn default() -> Self {
+        Self {
+            lr_scale: 1.0,
+            weight_decay: 0.0,
+        }
+    }
+}
+
+impl<M, O> ParamGroups<M, O> {
+    /// Wraps `opt` with no parameter groups.
+    pub fn new(opt: O) -> Self {
+        Self {
+            opt,
+            groups: Vec::new(),
+            prefixes: Vec::new(),
+            group_by_id: HashMap::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Adds all parameters named `prefix` or `{prefix}.*` as a new group with settings `cfg`,
+    /// e.g. `"0"` for all parameters of the first module of a tuple. `""` matches all parameters.
+    ///
+    /// If a parameter is also in a previous group, this group is used.
+    pub fn add_group(&mut self, prefix: &str, cfg: ParamGroupConfig) {
+        self.groups.push(cfg);
+        self.prefixes.push(prefix.trim_end_matches('.').into());
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams, O: OptimizerStep + HasLearningRate> Optimizer<M>
+    for ParamGroups<M, O>
+{
+    fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
+        self.begin_step(module, gradients);
+        let mut unused_tensors = Default::default();
+        module.update(self, &mut unused_tensors);
+        unused_tensors.into()
+    }
+}
+
+impl<M, O: OptimizerStep + HasLearningRate> OptimizerStep for ParamGroups<M, O> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.group_by_id.clear();
+        module.visit_params(
+            "",
+            &mut CollectIds {
+                prefixes: &self.prefixes,
+                group_by_id: &mut self.group_by_id,
+            },
+        );
+        self.opt.begin_step(module, gradients);
+    }
+}
+
+impl<M, O: OptimizerStep + HasLearningRate> GradientProvider for ParamGroups<M, O> {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let cfg = match self.group_by_id.get(p.id()) {
+            Some(&group) => self.groups[group],
+            None => return self.opt.gradient(p),
+        };
+
+        let opt_lr = self.opt.lr();
+        let lr = opt_lr * cfg.lr_scale;
+        self.opt.set_lr(lr);
+        let g_t = self.opt.gradient(p);
+        self.opt.set_lr(opt_lr);
+
+        let mut g_t = g_t?;
+        if cfg.weight_decay != 0.0 {
+            P::Device::foreach_mr(g_t.as_mut(), p.data(), &mut |g, p| {
+                *g += lr * cfg.weight_decay * p;
+            });
+        }
+        Some(g_t)
+    }
+}
+
+impl<M, O: HasLearningRate> HasLearningRate for ParamGroups<M, O> {
+    /// The learning rate of the wrapped optimizer.
+    fn lr(&self) -> f32 {
+        self.opt.lr()
+    }
+
+    /// Sets the learning rate of the wrapped optimizer. Group learning rates are relative to it,
+    /// so they change by the same factor.
+    fn set_lr(&mut self, lr: f32) {
+        self.opt.set_lr(lr)
+    }
+}
+
+/// Records the last group whose prefix matches the name of each parameter.
+struct CollectIds<'a> {
+    prefixes: &'a [String],
+    group_by_id: &'a mut HashMap<UniqueId, usize>,
+}
+
+impl<'a> ParamVisitor for CollectIds<'a> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+        let group = self.prefixes.iter().rposition(|pre| {
+            pre.is_empty()
+                || name
+                    .strip_prefix(pre.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        });
+        if let Some(group) = group {
+            self.group_by_id.insert(*p.id(), group);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    type Model = (Tensor1D<2>, Tensor1D<2>);
+
+    fn grads(model: &Model) -> Gradients {
+        let mut gradients: Gradients = Default::default();
+        *gradients.mut_gradient(&model.0) = [1.0, -1.0];
+        *gradients.mut_gradient(&model.1) = [1.0, -1.0];
+        gradients
+    }
+
+    #[test]
+    fn test_no_groups_matches_wrapped() {
+        let mut model: Model = (Tensor1D::new([1.0, 2.0]), Tensor1D::new([3.0, 4.0]));
+        let mut opt: ParamGroups<Model, Sgd<Model>> = ParamGroups::new(Default::default());
+        let gradients = grads(&model);
+        opt.update(&mut model, gradients).expect("");
+        assert_close(model.0.data(), &[0.99, 2.01]);
+        assert_close(model.1.data(), &[2.99, 4.01]);
+    }
+
+    #[test]
+    fn test_group_lr() {
+        let mut model: Model = (Tensor1D::new([1.0, 2.0]), Tensor1D::new([3.0, 4.0]));
+        let mut opt: ParamGroups<Model, Sgd<Model>> = ParamGroups::new(Default::default());
+        let cfg = ParamGroupConfig {
+            lr_scale: 10.0,
+            weight_decay: 0.0,
+        };
+        opt.add_group("1", cfg);
+        let gradients = grads(&model);
+        opt.update(&mut model, gradients).expect("");
+        assert_close(model.0.data(), &[0.99, 2.01]);
+        assert_close(model.1.data(), &[2.9, 4.1]);
+    }
+
+    #[test]
+    fn test_group_weight_decay() {
+        let mut model: Model = (Tensor1D::new([1.0, 2.0]), Tensor1D::new([3.0, 4.0]));
+        let mut opt: ParamGroups<Model, Adam<Model>> = ParamGroups::new(Default::default());
+        let cfg = ParamGroupConfig {
+            lr_scale: 1.0,
+            weight_decay: 0.5,
+        };
+        opt.add_group("0", cfg);
+        let gradients = grads(&model);
+        opt.update(&mut model, gradients).expect("");
+        // adam's first step is `lr * sign(g)`, then decay is `lr * wd * p`
+        assert_close(model.0.data(), &[0.9985, 2.0]);
+        assert_close(model.1.data(), &[2.999, 4.001]);
+    }
+
+    #[test]
+    fn test_later_group_overrides() {
+        let mut model: Model = (Tensor1D::new([1.0, 2.0]), Tensor1D::new([3.0, 4.0]));
+        let mut opt: ParamGroups<Model, Sgd<Model>> = ParamGroups::new(Default::default());
+        let slow = ParamGroupConfig {
+            lr_scale: 0.0,
+            weight_decay: 0.0,
+        };
+        let fast = ParamGroupConfig {
+            lr_scale: 100.0,
+            weight_decay: 0.0,
+        };
+        opt.add_group("", slow);
+        opt.add_group("1", fast);
+        let gradients = grads(&model);
+        opt.update(&mut model, gradients).expect("");
+        assert_close(model.0.data(), &[1.0, 2.0]);
+        assert_close(model.1.data(), &[2.0, 5.0]);
+    }
+
+    #[test]
+    fn test_groups_apply_to_clones() {
+        let model: Model = (Tensor1D::new([1.0, 2.0]), Tensor1D::new([3.0, 4.0]));
+        let mut opt: ParamGroups<Model, Sgd<Model>> = ParamGroups::new(Default::default());
+        let cfg = ParamGroupConfig {
+            lr_scale: 10.0,
+            weight_decay: 0.0,
+        };
+        opt.add_group("1", cfg);
+        let mut model = model.clone();
+        let gradients = grads(&model);
+        opt.update(&mut model, gradients).expect("");
+        assert_close(model.0.data(), &[0.99, 2.01]);
+        assert_close(model.1.data(), &[2.9, 4.1]);
+    }
+
+    #[test]
+    fn test_group_prefix_matches_whole_names() {
+        type Model = (Linear<1, 1>, Linear<1, 1>);
+        let model: Model = Default::default();
+        let mut opt: ParamGroups<Model, Sgd<Model>> = ParamGroups::new(Default::default());
+        opt.add_group("1.weight", Default::default());
+        opt.add_group("0.", Default::default());
+        opt.add_group("0.b", Default::default());
+        opt.begin_step(&model, Default::default());
+        assert_eq!(opt.group_by_id.get(model.0.weight.id()), Some(&1));
+        assert_eq!(opt.group_by_id.get(model.0.bias.id()), Some(&1));
+        assert_eq!(opt.group_by_id.get(model.1.weight.id()), Some(&0));
+        assert_eq!(opt.group_by_id.get(model.1.bias.id()), None);
+    }
+
+    #[test]
+    fn test_scheduler_changes_group_lr() {
+        let mut model: Model = (Tensor1D::new([1.0, 2.0]), Tensor1D::new([3.0, 4.0]));
+        let mut opt: ParamGroups<Model, Sgd<Model>> = ParamGroups::new(Sgd::new(SgdConfig {
+            lr: 1.0,
+            momentum: None,
+            grad_clip: None,
+        }));
+        let cfg = ParamGroupConfig {
+            lr_scale: 0.5,
+            weight_decay: 0.0,
+        };
+        opt.add_group("1", cfg);
+
+        let mut sched = StepLR::new(1.0, 1, 0.5);
+        sched.step(&mut opt);
+        assert_eq!(opt.lr(), 0.5);
+        let gradients = grads(&model);
+        opt.update(&mut model, gradients).expect("");
+        assert_close(model.0.data(), &[0.5, 2.5]);
+        assert_close(model.1.data(), &[2.75, 4.25]);
+    }
+
+    #[test]
+    fn test_param_groups_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: ParamGroups<Model, Sgd<Model>> = ParamGroups::new(Default::default());
+        opt.add_group("0", Default::default());
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
@@ -0,0 +1,315 @@
+use super::adam::update_moments;
+use super::state::{read_step, write_step};
+use crate::prelude::*;
+use std::io::{Read, Seek, Write};
+use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
+
+/// An implementation of Rectified Adam from
+/// [On the Variance of the Adaptive Learning Rate and Beyond](https://arxiv.org/abs/1908.03265).
+///
+/// This is [Adam] with a warmup of the adaptive learning rate: for the first few steps, while the variance of the
+/// second moment estimate is too large, the update is just momentum SGD. After that, the adaptive update
+/// is scaled by a rectification term.
+///
+/// # Example Usage
+///
+/// Constructing using default:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: RAdam<Model> = Default::default();
+/// ```
+///
+/// Changing using new
+/// ```rust
+/// # use dfdx::prelude::*;
+/// # type Model = Tensor0D;
+/// let mut opt: RAdam<Model> = RAdam::new(RAdamConfig {
+///     lr: 1e-2,
+///     betas: [0.5, 0.25],
+///     eps: 1e-6,
+///     grad_clip: None,
+/// });
+/// ```
+///
+/// See module level documentation at [crate::optim] for examples of how to actually use an optimizer.
+#[derive(Debug)]
+pub struct RAdam<M> {
+    /// Hyperparameter configuration
+    pub cfg: RAdamConfig,
+
+    t: i32,
+    gradients: Gradients,
+    moment1: Gradients,
+    moment2: Gradients,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [RAdam].
+///
+/// Changing all default parameters:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// RAdamConfig {
+///     lr: 1e-2,
+///     betas: [0.1, 0.2],
+///     eps: 1e-6,
+///     grad_clip: None,
+/// };
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RAdamConfig {
+    /// Learning rate. Defaults to `1e-3`.
+    pub lr: f32,
+
+    /// Betas from Adam paper. Defaults to `[0.9, 0.999]`.
+    pub betas: [f32; 2],
+
+    /// Epsilon for numerical stability. Defaults to `1e-8`.
+    pub eps: f32,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
+}
+
+impl Default for RAdamConfig {
+    fn default() -> Self {
+        Self {
+            lr: 1e-3,
+            betas: [0.9, 0.999],
+            eps: 1e-8,
+            grad_clip: None,
+        }
+    }
+}
+
+impl<M> Default for RAdam<M> {
+    /// See [RAdamConfig]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<M> RAdam<M> {
+    /// Constructs using hyperparameters from `cfg`.
+    pub fn new(cfg: RAdamConfig) -> Self {
+        Self {
+            cfg,
+            t: 0,
+            gradients: Default::default(),
+            moment1: Default::default(),
+            moment2: Default::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M> GradientProvider for RAdam<M> {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let [beta1, beta2] = self.cfg.betas;
+        let beta2_t = beta2.powi(self.t);
+        let rho_inf = 2.0 / (1.0 - beta2) - 1.0;
+        let rho_t = rho_inf - 2.0 * self.t as f32 * beta2_t / (1.0 - beta2_t);
+        let bias_correction1 = (1.0 - beta1.powi(self.t)).recip();
+
+        let mut g_t = self.gradients.remove(p)?;
+        let m_t = self.moment1.mut_gradient(p);
+        let v_t = self.moment2.mut_gradient(p);
+        if rho_t > 5.0 {
+            let rect = ((rho_t - 4.0) * (rho_t - 2.0) * rho_inf
+                / ((rho_inf - 4.0) * (rho_inf - 2.0) * rho_t))
+                .sqrt();
+            let bias_correction2 = (1.0 - beta2_t).sqrt();
+            P::Device::foreach_mmm(g_t.as_mut(), m_t, v_t, &mut |g, m, v| {
+                update_moments(*g, m, v, self.cfg.betas);
+                let m_hat = *m * bias_correction1;
+                let adaptive_lr = bias_correction2 / (v.sqrt() + self.cfg.eps);
+                *g = self.cfg.lr * m_hat * adaptive_lr * rect;
+            });
+        } else {
+            P::Device::foreach_mmm(g_t.as_mut(), m_t, v_t, &mut |g, m, v| {
+                update_moments(*g, m, v, self.cfg.betas);
+                *g = self.cfg.lr * *m * bias_correction1;
+            });
+        }
+        Some(g_t)
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for RAdam<M> {
+    fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
+        self.begin_step(module, gradients);
+        let mut unused_tensors = Default::default();
+        module.update(self, &mut unused_tensors);
+        unused_tensors.into()
+    }
+}
+
+impl<M> OptimizerStep for RAdam<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.t = self.t.checked_add(1).unwrap();
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for RAdam<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for RAdam<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_step(w, format!("{filename_prefix}t.npy"), self.t as usize)?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &self.moment1,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for RAdam<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        self.t = read_step(r, format!("{filename_prefix}t.npy"))? as i32;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment1"),
+            module,
+            &mut self.moment1,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}moment2"),
+            module,
+            &mut self.moment2,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::*, SeedableRng};
+
+    fn test_matches_expected(mut opt: RAdam<Tensor1D<5>>, expected: [[f32; 5]; 10]) {
+        let mut t: Tensor1D<5> = Tensor1D::ones();
+        let rate = Tensor1D::new([1e-4, 1e-3, 1e-2, 1e-1, 1e-0]);
+        for e in expected.iter() {
+            let gradients = (t.trace() * &rate).square().mean().backward();
+            opt.update(&mut t, gradients).expect("");
+            assert_close(t.data(), e);
+        }
+    }
+
+    #[test]
+    fn test_default_radam_params() {
+        #[rustfmt::skip]
+        const EXPECTED: [[f32; 5]; 10] = [
+            [1.0, 1.0, 0.99999994, 0.999996, 0.9996],
+            [1.0, 1.0, 0.9999999, 0.999992, 0.9992001],
+            [1.0, 1.0, 0.9999998, 0.999988, 0.9988003],
+            [1.0, 1.0, 0.99999976, 0.999984, 0.9984005],
+            [1.0, 1.0, 0.9999997, 0.99998003, 0.99800086],
+            [0.9999992, 0.9999805, 0.99997395, 0.9999542, 0.99797505],
+            [0.99999815, 0.9999553, 0.9999413, 0.9999215, 0.9979423],
+            [0.99999684, 0.999925, 0.99990267, 0.99988276, 0.9979036],
+            [0.99999523, 0.99989015, 0.9998587, 0.99983865, 0.9978595],
+            [0.9999933, 0.999851, 0.9998098, 0.99978966, 0.9978105],
+        ];
+        test_matches_expected(Default::default(), EXPECTED);
+    }
+
+    #[test]
+    fn test_custom_radam_params() {
+        let opt = RAdam::new(RAdamConfig {
+            lr: 1e-2,
+            betas: [0.5, 0.9],
+            eps: 1e-8,
+            grad_clip: None,
+        });
+        #[rustfmt::skip]
+        const EXPECTED: [[f32; 5]; 10] = [
+            [1.0, 1.0, 0.9999996, 0.99996, 0.996],
+            [1.0, 1.0, 0.99999917, 0.99992, 0.99201065],
+            [1.0, 1.0, 0.99999875, 0.99988, 0.9880335],
+            [1.0, 1.0, 0.99999833, 0.99984, 0.98406965],
+            [1.0, 1.0, 0.9999979, 0.9998, 0.98012006],
+            [0.9994515, 0.99753815, 0.9974471, 0.9972484, 0.97758156],
+            [0.99871176, 0.99435, 0.9941498, 0.9939502, 0.97430086],
+            [0.99780285, 0.99056125, 0.99023694, 0.99003625, 0.9704065],
+            [0.99674153, 0.9862585, 0.9857984, 0.98559654, 0.9659869],
+            [0.99554175, 0.9815078, 0.9809023, 0.9806991, 0.9611094],
+        ];
+        test_matches_expected(opt, EXPECTED);
+    }
+
+    #[test]
+    fn test_radam_changes_all_params() {
+        type Model = (Linear<5, 16>, ReLU, Linear<16, 16>, ReLU, Linear<16, 10>);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        let model_0 = model.clone();
+
+        let x: Tensor2D<16, 5> = Tensor2D::rand(&mut rng);
+        let y: Tensor2D<16, 10> = Tensor2D::rand(&mut rng);
+        let mut opt: RAdam<Model> = Default::default();
+
+        let py = model.forward(x.trace());
+        let loss = (py - &y).square().mean();
+        let gradients = loss.backward();
+        opt.update(&mut model, gradients).expect("");
+
+        let model_1 = model.clone();
+
+        assert!(model_0.0.weight.data() != model_1.0.weight.data());
+        assert!(model_0.0.bias.data() != model_1.0.bias.data());
+        assert!(model_0.2.weight.data() != model_1.2.weight.data());
+        assert!(model_0.2.bias.data() != model_1.2.bias.data());
+        assert!(model_0.4.weight.data() != model_1.4.weight.data());
+        assert!(model_0.4.bias.data() != model_1.4.bias.data());
+    }
+
+    #[test]
+    fn test_radam_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: RAdam<Model> = Default::default();
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        let g = y.mean().backward();
+        opt.update(&mut model, g).expect_err("");
+    }
+}
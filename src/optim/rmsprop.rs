@@ -1,5 +1,8 @@
+use super::state::{read_step, write_step};
 use crate::prelude::*;
+use std::io::{Read, Seek, Write};
 use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 /// RMSprop As described in [Hinton, 2012](http://www.cs.toronto.edu/%7Etijmen/csc321/slides/lecture_slides_lec6.pdf).
 ///
@@ -31,6 +34,7 @@ use std::marker::PhantomData;
 ///     eps: 1e-8,
 ///     momentum: Some(0.5),
 ///     centered: false,
+///     grad_clip: None,
 /// });
 /// ```
 ///
@@ -67,6 +71,9 @@ pub struct RMSpropConfig {
     /// Whether the avg should be centered by the grad's avg value.
     /// Defaults to `false`.
     pub centered: bool,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
 }
 
 impl Default for RMSpropConfig {
@@ -77,6 +84,7 @@ impl Default for RMSpropConfig {
             eps: 1e-8,
             momentum: None,
             centered: false,
+            grad_clip: None,
         }
     }
 }
@@ -106,12 +114,12 @@ impl<M> RMSprop<M> {
 impl<M> GradientProvider for RMSprop<M> {
     fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
     where
-        P: HasUniqueId + HasArrayType<Dtype = f32> + HasDevice,
+        P: Tensor<Dtype = f32>,
     {
         let mut g_t = self.gradients.remove(p)?;
 
         let square_avg = self.square_avg.mut_gradient(p);
-        if self.step == 0 {
+        if self.step == 1 {
             P::Device::fill(square_avg, &mut |v| *v = 1.0);
         }
 
@@ -154,16 +162,95 @@ impl<M> GradientProvider for RMSprop<M> {
     }
 }
 
-impl<M: CanUpdateWithGradients> Optimizer<M> for RMSprop<M> {
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for RMSprop<M> {
     fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
-        self.gradients = gradients;
+        self.begin_step(module, gradients);
         let mut unused_tensors = Default::default();
         module.update(self, &mut unused_tensors);
-        self.step += 1;
         unused_tensors.into()
     }
 }
 
+impl<M> OptimizerStep for RMSprop<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.step += 1;
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for RMSprop<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for RMSprop<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_step(w, format!("{filename_prefix}step.npy"), self.step)?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}momentums"),
+            module,
+            &self.momentums,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}square_avg"),
+            module,
+            &self.square_avg,
+        )?;
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}grad_avg"),
+            module,
+            &self.grad_avg,
+        )?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for RMSprop<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        self.step = read_step(r, format!("{filename_prefix}step.npy"))?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}momentums"),
+            module,
+            &mut self.momentums,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}square_avg"),
+            module,
+            &mut self.square_avg,
+        )?;
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}grad_avg"),
+            module,
+            &mut self.grad_avg,
+        )?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,6 +274,7 @@ mod tests {
             eps: 1e-8,
             momentum: None,
             centered: false,
+            grad_clip: None,
         };
         const EXPECTED: [[f32; 5]; 5] = [
             [0.9997892, 0.98245883, 0.9703907, 0.9683808, 0.96837723],
@@ -206,6 +294,7 @@ mod tests {
             eps: 1e-8,
             momentum: Some(0.9),
             centered: false,
+            grad_clip: None,
         };
         const EXPECTED: [[f32; 5]; 5] = [
             [0.9997892, 0.98245883, 0.9703907, 0.9683808, 0.96837723],
@@ -225,6 +314,7 @@ mod tests {
             eps: 1e-8,
             momentum: None,
             centered: false,
+            grad_clip: None,
         };
         const EXPECTED: [[f32; 5]; 5] = [
             [0.99971724, 0.9873509, 0.9859671, 0.985858, 0.98585784],
@@ -244,6 +334,7 @@ mod tests {
             eps: 1e-2,
             momentum: None,
             centered: false,
+            grad_clip: None,
         };
         const EXPECTED: [[f32; 5]; 5] = [
             [0.9997904, 0.98252594, 0.97041094, 0.9683808, 0.96837723],
@@ -263,6 +354,7 @@ mod tests {
             eps: 1e-8,
             momentum: None,
             centered: true,
+            grad_clip: None,
         };
         const EXPECTED: [[f32; 5]; 5] = [
             [0.9997892, 0.98218256, 0.96900064, 0.9666708, 0.9666667],
@@ -0,0 +1,199 @@
+use crate::prelude::*;
+use std::marker::PhantomData;
+
+/// Sharpness-Aware Minimization as described in
+/// [Sharpness-Aware Minimization for Efficiently Improving Generalization](https://arxiv.org/abs/2010.01412).
+///
+/// Wraps another optimizer, and each [Sam::step()]:
+/// 1. Computes gradients `g` at the current parameters `w`
+/// 2. Moves the parameters to the nearby worst case `w + rho * g / ||g||`, where `||g||` is the norm across all parameters
+/// 3. Computes gradients again at the perturbed parameters
+/// 4. Restores the parameters to `w`, and updates them with the wrapped optimizer using the second gradients
+///
+/// Since it needs to compute gradients twice, this does not implement [Optimizer], and so not [ClosureOptimizer]
+/// either. Instead [Sam::step()] takes the same closure as [ClosureOptimizer::step()].
+///
+/// # Example Usage
+///
+/// ```rust
+/// # use dfdx::prelude::*;
+/// type Model = (Linear<5, 16>, ReLU, Linear<16, 2>);
+/// let mut model: Model = Default::default();
+/// let mut opt: Sam<Model, Sgd<Model>> = Sam::new(Default::default(), SamConfig { rho: 0.05 });
+/// let x: Tensor2D<4, 5> = Tensor2D::zeros();
+/// let y: Tensor2D<4, 2> = Tensor2D::zeros();
+/// opt.step(&mut model, |m| mse_loss(m.forward(x.trace()), &y).backward())
+///     .expect("unused params");
+/// ```
+#[derive(Debug)]
+pub struct Sam<M, O> {
+    /// The wrapped optimizer that does the actual update.
+    pub opt: O,
+
+    /// Hyperparameter configuration
+    pub cfg: SamConfig,
+
+    marker: PhantomData<*const M>,
+}
+
+/// Configuration of hyperparameters for [Sam].
+#[derive(Debug, Clone, Copy)]
+pub struct SamConfig {
+    /// Radius of the neighborhood to search for the worst case parameters. Defaults to `0.05`.
+    pub rho: f32,
+}
+
+impl Default for SamConfig {
+    fn default() -> Self {
+        Self { rho: 0.05 }
+    }
+}
+
+impl<M, O: Default> Default for Sam<M, O> {
+    /// See [SamConfig]
+    fn default() -> Self {
+        Self::new(Default::default(), Default::default())
+    }
+}
+
+impl<M, O> Sam<M, O> {
+    /// Wraps `opt` using hyperparameters from `cfg`.
+    pub fn new(opt: O, cfg: SamConfig) -> Self {
+        Self {
+            opt,
+            cfg,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M: CanUpdateWithGradients + VisitParams, O: Optimizer<M>> Sam<M, O> {
+    /// Updates all of `module`'s parameters, calling `closure` twice to compute gradients.
+    /// `closure` should run the forward & backward pass on the module it is given.
+    pub fn step<F: FnMut(&M) -> Gradients>(
+        &mut self,
+        module: &mut M,
+        mut closure: F,
+    ) -> Result<(), UnusedParamsError> {
+        let mut gradients = closure(module);
+        let norm = grad_norm(module, &mut gradients);
+
+        let mut ascend = Perturb {
+            gradients,
+            scale: self.cfg.rho / (norm + 1e-12),
+            applied: Default::default(),
+        };
+        let mut unused = Default::default();
+        module.update(&mut ascend, &mut unused);
+
+        let gradients = closure(module);
+
+        let mut restore = Restore(ascend.applied);
+        let mut unused = Default::default();
+        module.update(&mut restore, &mut unused);
+
+        self.opt.update(module, gradients)
+    }
+}
+
+impl<M, O: HasLearningRate> HasLearningRate for Sam<M, O> {
+    fn lr(&self) -> f32 {
+        self.opt.lr()
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.opt.set_lr(lr)
+    }
+}
+
+/// Adds `scale * g` to every parameter, and remembers what was added.
+struct Perturb {
+    gradients: Gradients,
+    scale: f32,
+    applied: Gradients,
+}
+
+impl GradientProvider for Perturb {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        let mut e_w = self.gradients.remove(p)?;
+        P::Device::foreach_m(e_w.as_mut(), &mut |e| *e *= self.scale);
+        let mut neg_e_w = e_w.clone();
+        P::Device::foreach_m(neg_e_w.as_mut(), &mut |e| *e = -*e);
+        self.applied.insert(p, e_w);
+        Some(neg_e_w)
+    }
+}
+
+/// Undoes [Perturb].
+struct Restore(Gradients);
+
+impl GradientProvider for Restore {
+    fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
+    where
+        P: Tensor<Dtype = f32>,
+    {
+        self.0.remove(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+
+    #[test]
+    fn test_sam_uses_perturbed_gradients() {
+        // loss = sum(w^3), so grad = 3w^2
+        let mut w: Tensor1D<2> = Tensor1D::new([1.0, 2.0]);
+        let mut opt: Sam<Tensor1D<2>, Sgd<Tensor1D<2>>> = Sam::new(
+            Sgd::new(SgdConfig {
+                lr: 0.1,
+                momentum: None,
+                grad_clip: None,
+            }),
+            SamConfig { rho: 0.5 },
+        );
+        opt.step(&mut w, |w| {
+            let (x, tape) = w.trace().split_tape();
+            (x.duplicate().put_tape(tape).square() * &x)
+                .sum()
+                .backward()
+        })
+        .expect("");
+        // g = [3, 12], ||g|| = sqrt(153), w_adv = w + 0.5 * g / ||g||, w = w - 0.1 * 3 * w_adv^2
+        assert_close(w.data(), &[0.62282755, 0.14732626]);
+    }
+
+    #[test]
+    fn test_sam_rho_zero_matches_wrapped() {
+        let rate = Tensor1D::new([0.1, 1.0, 2.0, 10.0, 100.0]);
+        let mut t0: Tensor1D<5> = Tensor1D::ones();
+        let mut t1: Tensor1D<5> = Tensor1D::ones();
+        let mut adam: Adam<Tensor1D<5>> = Default::default();
+        let mut sam: Sam<Tensor1D<5>, Adam<Tensor1D<5>>> =
+            Sam::new(Default::default(), SamConfig { rho: 0.0 });
+        for _ in 0..3 {
+            adam.step(&mut t0, |t| (t.trace() * &rate).square().sum().backward())
+                .expect("");
+            sam.step(&mut t1, |t| (t.trace() * &rate).square().sum().backward())
+                .expect("");
+        }
+        assert_eq!(t0.data(), t1.data());
+    }
+
+    #[test]
+    fn test_sam_unused_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut model: Model = Default::default();
+        let mut opt: Sam<Model, Sgd<Model>> = Default::default();
+        opt.step(&mut model, |m| {
+            m.1.forward(Tensor2D::<8, 16>::zeros().trace())
+                .mean()
+                .backward()
+        })
+        .expect_err("");
+    }
+}
@@ -1,5 +1,7 @@
 use crate::prelude::*;
+use std::io::{Read, Seek, Write};
 use std::marker::PhantomData;
+use zip::{result::ZipResult, ZipArchive, ZipWriter};
 
 /// Implementation of Stochastic Gradient Descent. Based on [pytorch's implementation](https://pytorch.org/docs/stable/generated/torch.optim.SGD.html)
 ///
@@ -22,6 +24,7 @@ use std::marker::PhantomData;
 /// let mut opt: Sgd<Model> = Sgd::new(SgdConfig {
 ///     lr: 1e-3,
 ///     momentum: Some(Momentum::Classic(0.5)),
+///     grad_clip: None,
 /// });
 /// ```
 ///
@@ -44,7 +47,8 @@ pub struct Sgd<M> {
 /// # use dfdx::prelude::*;
 /// SgdConfig {
 ///     lr: 1e-1,
-///     momentum: None
+///     momentum: None,
+///     grad_clip: None,
 /// };
 /// ```
 ///
@@ -53,7 +57,8 @@ pub struct Sgd<M> {
 /// # use dfdx::prelude::*;
 /// SgdConfig {
 ///     lr: 1e-2,
-///     momentum: Some(Momentum::Classic(0.5))
+///     momentum: Some(Momentum::Classic(0.5)),
+///     grad_clip: None,
 /// };
 /// ```
 ///
@@ -62,7 +67,8 @@ pub struct Sgd<M> {
 /// # use dfdx::prelude::*;
 /// SgdConfig {
 ///     lr: 1e-3,
-///     momentum: Some(Momentum::Nesterov(0.25))
+///     momentum: Some(Momentum::Nesterov(0.25)),
+///     grad_clip: None,
 /// };
 /// ```
 #[derive(Debug, Clone, Copy)]
@@ -72,6 +78,9 @@ pub struct SgdConfig {
 
     /// Optional momentum. Defaults to `None`.
     pub momentum: Option<Momentum>,
+
+    /// Optional gradient clipping applied before each update. Defaults to `None`.
+    pub grad_clip: Option<GradClip>,
 }
 
 impl Default for SgdConfig {
@@ -79,6 +88,7 @@ impl Default for SgdConfig {
         Self {
             lr: 1e-2,
             momentum: None,
+            grad_clip: None,
         }
     }
 }
@@ -87,9 +97,13 @@ impl Default for SgdConfig {
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Momentum {
     /// Momentum that is applied to the velocity of a parameter directly.
+    ///
+    /// `v = g + u * v`, and the update is `lr * v`.
     Classic(f32),
 
     /// Momentum that is applied to both velocity and gradients. See [Sgd] nesterov paper for more.
+    ///
+    /// Uses the lookahead formulation `v = g + u * v`, and the update is `lr * (g + u * v)`.
     Nesterov(f32),
 }
 
@@ -115,7 +129,7 @@ impl<M> Sgd<M> {
 impl<M> GradientProvider for Sgd<M> {
     fn gradient<P>(&mut self, p: &P) -> Option<Box<P::Array>>
     where
-        P: HasUniqueId + HasArrayType<Dtype = f32> + HasDevice,
+        P: Tensor<Dtype = f32>,
     {
         let mut g_t = self.gradients.remove(p)?;
         match self.cfg.momentum {
@@ -139,15 +153,68 @@ impl<M> GradientProvider for Sgd<M> {
     }
 }
 
-impl<M: CanUpdateWithGradients> Optimizer<M> for Sgd<M> {
+impl<M: CanUpdateWithGradients + VisitParams> Optimizer<M> for Sgd<M> {
     fn update(&mut self, module: &mut M, gradients: Gradients) -> Result<(), UnusedParamsError> {
-        self.gradients = gradients;
+        self.begin_step(module, gradients);
         let mut unused_tensors = Default::default();
         module.update(self, &mut unused_tensors);
         unused_tensors.into()
     }
 }
 
+impl<M> OptimizerStep for Sgd<M> {
+    fn begin_step<N: VisitParams>(&mut self, module: &N, gradients: Gradients) {
+        self.gradients = gradients;
+        if let Some(clip) = self.cfg.grad_clip {
+            clip.clip(module, &mut self.gradients);
+        }
+    }
+}
+
+impl<M> HasLearningRate for Sgd<M> {
+    fn lr(&self) -> f32 {
+        self.cfg.lr
+    }
+
+    fn set_lr(&mut self, lr: f32) {
+        self.cfg.lr = lr;
+    }
+}
+
+impl<M: VisitParams> SaveStateToNpz<M> for Sgd<M> {
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()> {
+        write_param_buffer(
+            w,
+            &format!("{filename_prefix}velocity"),
+            module,
+            &self.velocity,
+        )?;
+        Ok(())
+    }
+}
+
+impl<M: VisitParams> LoadStateFromNpz<M> for Sgd<M> {
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError> {
+        read_param_buffer(
+            r,
+            &format!("{filename_prefix}velocity"),
+            module,
+            &mut self.velocity,
+        )?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,6 +225,7 @@ mod tests {
         let mut sgd = Sgd::new(SgdConfig {
             lr: 1.0,
             momentum: None,
+            grad_clip: None,
         });
 
         let mut pred: Tensor1D<5> = Tensor1D::zeros();
@@ -197,6 +265,7 @@ mod tests {
         let mut sgd = Sgd::new(SgdConfig {
             lr: 1e-2,
             momentum: Some(Momentum::Classic(0.5)),
+            grad_clip: None,
         });
 
         let mut t: Tensor1D<5> = Tensor1D::ones();
@@ -221,6 +290,7 @@ mod tests {
         let mut sgd = Sgd::new(SgdConfig {
             lr: 1e-2,
             momentum: Some(Momentum::Nesterov(0.5)),
+            grad_clip: None,
         });
 
         let mut t: Tensor1D<5> = Tensor1D::ones();
@@ -276,4 +346,31 @@ mod tests {
         let g = y.mean().backward();
         opt.update(&mut model, g).expect_err("");
     }
+
+    #[test]
+    fn test_sgd_frozen_params() {
+        type Model = (Linear<5, 16>, Linear<16, 10>);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        model.0.freeze();
+        let model_0 = model.clone();
+
+        let mut opt: Sgd<Model> = Default::default();
+        let x: Tensor2D<8, 5> = Tensor2D::randn(&mut rng);
+        let g = model.forward(x.trace()).mean().backward();
+        opt.update(&mut model, g).expect("");
+        assert_eq!(model.0.weight.data(), model_0.0.weight.data());
+        assert_eq!(model.0.bias.data(), model_0.0.bias.data());
+        assert!(model.1.weight.data() != model_0.1.weight.data());
+
+        // frozen params are not unused
+        let y = model.1.forward(Tensor2D::<8, 16>::zeros().trace());
+        opt.update(&mut model, y.mean().backward()).expect("");
+
+        model.0.unfreeze();
+        let g = model.forward(x.trace()).mean().backward();
+        opt.update(&mut model, g).expect("");
+        assert!(model.0.weight.data() != model_0.0.weight.data());
+    }
 }
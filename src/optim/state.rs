@@ -0,0 +1,349 @@
+use crate::nn::{npz_fread, npz_fwrite, NpzError};
+use crate::prelude::*;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+use zip::{
+    result::{ZipError, ZipResult},
+    ZipArchive, ZipWriter,
+};
+
+/// An optimizer whose internal state (momentums, step counts, etc) can be saved to a `.npz` file,
+/// so that training can be resumed exactly with [LoadStateFromNpz].
+///
+/// Since the state is per parameter, the module being optimized is needed to save the state.
+/// The state of each parameter is saved under the name that [VisitParams] gives the parameter,
+/// e.g. the first moment of `0.weight` with [Adam] is saved as `moment1.0.weight.npy`, so the state can
+/// be loaded for any module with parameters of the same names and shapes.
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// let mut model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+/// let mut opt: Adam<_> = Default::default();
+/// // -- snip training --
+/// model.save("model.npz")?;
+/// opt.save_state(&model, "opt.npz")?;
+/// ```
+pub trait SaveStateToNpz<M: VisitParams> {
+    /// Saves the state of the optimizer for `module` into the `.npz` file at `path`.
+    fn save_state<P: AsRef<Path>>(&self, module: &M, path: P) -> ZipResult<()> {
+        let f = std::fs::File::create(path)?;
+        let f = BufWriter::new(f);
+        let mut zip = ZipWriter::new(f);
+        self.write_state(module, "", &mut zip)?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Writes the state of the optimizer for `module` into [ZipWriter] `w` with a base filename of `filename_prefix`.
+    fn write_state<W: Write + Seek>(
+        &self,
+        module: &M,
+        filename_prefix: &str,
+        w: &mut ZipWriter<W>,
+    ) -> ZipResult<()>;
+}
+
+/// An optimizer whose internal state can be loaded from a `.npz` file that was
+/// saved with [SaveStateToNpz].
+///
+/// Example:
+/// ```ignore
+/// # use dfdx::prelude::*;
+/// let mut model: (Linear<5, 10>, Linear<10, 5>) = Default::default();
+/// let mut opt: Adam<_> = Default::default();
+/// model.load("model.npz")?;
+/// opt.load_state(&model, "opt.npz")?;
+/// ```
+pub trait LoadStateFromNpz<M: VisitParams> {
+    /// Loads the state of the optimizer for `module` from the `.npz` file at `path`.
+    fn load_state<P: AsRef<Path>>(&mut self, module: &M, path: P) -> Result<(), NpzError> {
+        let f = std::fs::File::open(path)?;
+        let f = BufReader::new(f);
+        let mut zip = ZipArchive::new(f)?;
+        self.read_state(module, "", &mut zip)?;
+        Ok(())
+    }
+
+    /// Reads the state of the optimizer for `module` from [ZipArchive] `r` with a base filename of `filename_prefix`.
+    fn read_state<R: Read + Seek>(
+        &mut self,
+        module: &M,
+        filename_prefix: &str,
+        r: &mut ZipArchive<R>,
+    ) -> Result<(), NpzError>;
+}
+
+/// Writes the data in `buffer` for each of `module`'s parameters into a file named
+/// `{filename}.{name}.npy`, where `name` is the name of the parameter from [VisitParams].
+/// Parameters without data in `buffer` are skipped.
+pub fn write_param_buffer<M: VisitParams + ?Sized, W: Write + Seek>(
+    w: &mut ZipWriter<W>,
+    filename: &str,
+    module: &M,
+    buffer: &Gradients,
+) -> ZipResult<()> {
+    let mut writer = BufferWriter {
+        w,
+        filename,
+        buffer,
+        result: Ok(()),
+    };
+    module.visit_params("", &mut writer);
+    writer.result
+}
+
+/// Reads the data for each of `module`'s parameters into `buffer` from files written by
+/// [write_param_buffer()]. Parameters that don't have a file will have no data in `buffer`.
+pub fn read_param_buffer<M: VisitParams + ?Sized, R: Read + Seek>(
+    r: &mut ZipArchive<R>,
+    filename: &str,
+    module: &M,
+    buffer: &mut Gradients,
+) -> Result<(), NpzError> {
+    let mut reader = BufferReader {
+        r,
+        filename,
+        buffer,
+        result: Ok(()),
+    };
+    module.visit_params("", &mut reader);
+    reader.result
+}
+
+/// Writes a step counter to `filename`.
+pub(super) fn write_step<W: Write + Seek>(
+    w: &mut ZipWriter<W>,
+    filename: String,
+    step: usize,
+) -> ZipResult<()> {
+    npz_fwrite(w, filename, &(step as u64))
+}
+
+/// Reads a step counter written with [write_step()].
+pub(super) fn read_step<R: Read + Seek>(
+    r: &mut ZipArchive<R>,
+    filename: String,
+) -> Result<usize, NpzError> {
+    let mut step = 0u64;
+    npz_fread(r, filename, &mut step)?;
+    Ok(step as usize)
+}
+
+/// The file of the buffer `filename` for the parameter `name`.
+fn buffer_file(filename: &str, name: &str) -> String {
+    if name.is_empty() {
+        format!("{filename}.npy")
+    } else {
+        format!("{filename}.{name}.npy")
+    }
+}
+
+struct BufferWriter<'a, W: Write + Seek> {
+    w: &'a mut ZipWriter<W>,
+    filename: &'a str,
+    buffer: &'a Gradients,
+    result: ZipResult<()>,
+}
+
+impl<'a, W: Write + Seek> ParamVisitor for BufferWriter<'a, W> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+        if let (Ok(()), Some(data)) = (&self.result, self.buffer.get(p)) {
+            self.result = npz_fwrite(self.w, buffer_file(self.filename, name), data);
+        }
+    }
+}
+
+struct BufferReader<'a, R: Read + Seek> {
+    r: &'a mut ZipArchive<R>,
+    filename: &'a str,
+    buffer: &'a mut Gradients,
+    result: Result<(), NpzError>,
+}
+
+impl<'a, R: Read + Seek> ParamVisitor for BufferReader<'a, R> {
+    fn visit<P: Tensor<Dtype = f32>>(&mut self, name: &str, p: &P) {
+        if self.result.is_err() {
+            return;
+        }
+        let mut data: Box<P::Array> = P::Device::zeros();
+        match npz_fread(self.r, buffer_file(self.filename, name), data.as_mut()) {
+            Ok(()) => self.buffer.insert(p, data),
+            Err(NpzError::Zip(ZipError::FileNotFound)) => {
+                self.buffer.remove(p);
+            }
+            Err(e) => self.result = Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{prelude::StdRng, SeedableRng};
+    use tempfile::NamedTempFile;
+
+    type Model = (Linear<5, 8>, ReLU, Linear<8, 3>);
+
+    fn train_step<O: Optimizer<Model>>(model: &mut Model, opt: &mut O, rng: &mut StdRng) {
+        let x: Tensor2D<4, 5> = Tensor2D::randn(rng);
+        let y: Tensor2D<4, 3> = Tensor2D::randn(rng);
+        let gradients = mse_loss(model.forward(x.trace()), &y).backward();
+        opt.update(model, gradients).expect("");
+    }
+
+    fn test_resume<O>(mut opt: O, mut resumed: O)
+    where
+        O: Optimizer<Model> + SaveStateToNpz<Model> + LoadStateFromNpz<Model>,
+    {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        model.reset_params(&mut rng);
+        for _ in 0..3 {
+            train_step(&mut model, &mut opt, &mut rng);
+        }
+
+        let model_file = NamedTempFile::new().expect("failed to create tempfile");
+        let opt_file = NamedTempFile::new().expect("failed to create tempfile");
+        model.save(model_file.path()).expect("");
+        opt.save_state(&model, opt_file.path()).expect("");
+
+        let mut loaded: Model = Default::default();
+        loaded.load(model_file.path()).expect("");
+        resumed.load_state(&loaded, opt_file.path()).expect("");
+
+        let mut rng_a = StdRng::seed_from_u64(1);
+        let mut rng_b = StdRng::seed_from_u64(1);
+        for _ in 0..3 {
+            train_step(&mut model, &mut opt, &mut rng_a);
+            train_step(&mut loaded, &mut resumed, &mut rng_b);
+        }
+        assert_eq!(model.0.weight.data(), loaded.0.weight.data());
+        assert_eq!(model.0.bias.data(), loaded.0.bias.data());
+        assert_eq!(model.2.weight.data(), loaded.2.weight.data());
+        assert_eq!(model.2.bias.data(), loaded.2.bias.data());
+    }
+
+    #[test]
+    fn test_sgd_resume() {
+        let cfg = SgdConfig {
+            lr: 1e-2,
+            momentum: Some(Momentum::Nesterov(0.9)),
+            grad_clip: None,
+        };
+        test_resume::<Sgd<Model>>(Sgd::new(cfg), Sgd::new(cfg));
+    }
+
+    #[test]
+    fn test_adam_resume() {
+        test_resume::<Adam<Model>>(Default::default(), Default::default());
+    }
+
+    #[test]
+    fn test_adamw_resume() {
+        test_resume::<AdamW<Model>>(Default::default(), Default::default());
+    }
+
+    #[test]
+    fn test_rmsprop_resume() {
+        let cfg = RMSpropConfig {
+            momentum: Some(0.9),
+            centered: true,
+            ..Default::default()
+        };
+        test_resume::<RMSprop<Model>>(RMSprop::new(cfg), RMSprop::new(cfg));
+    }
+
+    #[test]
+    fn test_adagrad_resume() {
+        test_resume::<Adagrad<Model>>(Default::default(), Default::default());
+    }
+
+    #[test]
+    fn test_adadelta_resume() {
+        test_resume::<Adadelta<Model>>(Default::default(), Default::default());
+    }
+
+    #[test]
+    fn test_radam_resume() {
+        test_resume::<RAdam<Model>>(Default::default(), Default::default());
+    }
+
+    #[test]
+    fn test_nadam_resume() {
+        test_resume::<NAdam<Model>>(Default::default(), Default::default());
+    }
+
+    #[test]
+    fn test_lamb_resume() {
+        test_resume::<Lamb<Model>>(Default::default(), Default::default());
+    }
+
+    #[test]
+    fn test_ftrl_resume() {
+        test_resume::<Ftrl<Model>>(Default::default(), Default::default());
+    }
+
+    #[test]
+    fn test_buffer_files() {
+        let model: Model = Default::default();
+        let mut buffer: Gradients = Default::default();
+        *buffer.mut_gradient(&model.0.bias) = [1.0; 8];
+        *buffer.mut_gradient(&model.2.weight) = [[2.0; 8]; 3];
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        let mut zip = ZipWriter::new(file.reopen().expect(""));
+        write_param_buffer(&mut zip, "buf", &model, &buffer).expect("");
+        zip.finish().expect("");
+
+        let zip = ZipArchive::new(file.reopen().expect("")).expect("");
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(&names, &["buf.0.bias.npy", "buf.2.weight.npy"]);
+    }
+
+    #[test]
+    fn test_step_is_an_integer() {
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        let mut zip = ZipWriter::new(file.reopen().expect(""));
+        write_step(&mut zip, "step.npy".into(), (1 << 53) + 1).expect("");
+        zip.finish().expect("");
+
+        let mut zip = ZipArchive::new(file.reopen().expect("")).expect("");
+        assert_eq!(
+            read_step(&mut zip, "step.npy".into()).expect(""),
+            (1 << 53) + 1
+        );
+        let mut f64_step = 0.0f64;
+        assert!(npz_fread(&mut zip, "step.npy".into(), &mut f64_step).is_err());
+    }
+
+    #[test]
+    fn test_state_files_are_named_by_param() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: Model = Default::default();
+        let mut opt: Adam<Model> = Default::default();
+        train_step(&mut model, &mut opt, &mut rng);
+
+        let file = NamedTempFile::new().expect("failed to create tempfile");
+        opt.save_state(&model, file.path()).expect("");
+
+        let zip = ZipArchive::new(file.reopen().expect("")).expect("");
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            &names,
+            &[
+                "moment1.0.bias.npy",
+                "moment1.0.weight.npy",
+                "moment1.2.bias.npy",
+                "moment1.2.weight.npy",
+                "moment2.0.bias.npy",
+                "moment2.0.weight.npy",
+                "moment2.2.bias.npy",
+                "moment2.2.weight.npy",
+                "t.npy",
+            ]
+        );
+    }
+}
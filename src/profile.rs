@@ -0,0 +1,281 @@
+//! Measuring how much time & memory each tensor operation takes with [Profiler].
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+type Stats = HashMap<&'static str, OpStats>;
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Stats>> = const { RefCell::new(None) };
+    /// The outermost operation that is currently running, which ops called inside it count towards.
+    static CURRENT: Cell<Option<&'static str>> = const { Cell::new(None) };
+    static NUM_ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The measurements of all calls to one operation, see [Profiler].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OpStats {
+    /// The name of the operation, e.g. `"matmul"`.
+    pub name: &'static str,
+
+    /// How many times the operation was called.
+    pub calls: usize,
+
+    /// The total wall time of the forward operations.
+    pub forward_time: Duration,
+
+    /// The total wall time of the backward operations.
+    pub backward_time: Duration,
+
+    /// How many arrays the forward operations allocated.
+    pub forward_allocations: usize,
+
+    /// How many arrays the backward operations allocated (including gradients).
+    pub backward_allocations: usize,
+}
+
+impl OpStats {
+    /// The total wall time of forward & backward operations.
+    pub fn total_time(&self) -> Duration {
+        self.forward_time + self.backward_time
+    }
+}
+
+/// Records the wall time and number of array allocations of every tensor operation that runs
+/// inside of [Profiler::scope()], and of its backward operation when the gradients are computed.
+///
+/// Measurements are grouped by the name of the operation (e.g. `"matmul"`, `"relu"`), and can be
+/// printed as a table with [std::fmt::Display]. Operations that call other operations (e.g.
+/// [crate::tensor_ops::softmax()]) are measured as a whole.
+///
+/// Only operations on the thread that entered the scope are measured. Outside of a scope
+/// nothing is measured, so there is no overhead when profiling isn't used.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let model: (Linear<4, 8>, ReLU, Linear<8, 2>) = Default::default();
+/// let mut profiler = Profiler::default();
+/// let _gradients = profiler.scope(|| {
+///     let x: Tensor2D<16, 4> = Tensor2D::zeros();
+///     model.forward(x.trace()).square().mean().backward()
+/// });
+/// let stats = profiler.stats();
+/// assert_eq!(stats.iter().find(|s| s.name == "matmul_transpose").unwrap().calls, 2);
+/// println!("{profiler}");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Profiler {
+    stats: Stats,
+}
+
+impl Profiler {
+    /// Runs `f` and adds the measurements of its operations to this profiler. Scopes can be
+    /// nested, in which case the innermost profiler records the operations.
+    pub fn scope<R, F: FnOnce() -> R>(&mut self, f: F) -> R {
+        /// Gives the measurements back to the profiler, even if `f` panics.
+        struct Exit<'a> {
+            profiler: &'a mut Profiler,
+            outer: Option<Stats>,
+        }
+
+        impl Drop for Exit<'_> {
+            fn drop(&mut self) {
+                let stats = ACTIVE.with(|active| active.replace(self.outer.take()));
+                self.profiler.stats = stats.unwrap_or_default();
+            }
+        }
+
+        let stats = std::mem::take(&mut self.stats);
+        let outer = ACTIVE.with(|active| active.replace(Some(stats)));
+        let _exit = Exit {
+            profiler: self,
+            outer,
+        };
+        f()
+    }
+
+    /// The measurements of each operation, sorted by [OpStats::total_time()] from most to
+    /// least time.
+    pub fn stats(&self) -> Vec<OpStats> {
+        let mut stats: Vec<OpStats> = self.stats.values().copied().collect();
+        stats.sort_by(|a, b| b.total_time().cmp(&a.total_time()).then(a.name.cmp(b.name)));
+        stats
+    }
+
+    /// Removes all measurements.
+    pub fn clear(&mut self) {
+        self.stats.clear();
+    }
+}
+
+impl std::fmt::Display for Profiler {
+    /// A table of [Profiler::stats()], with the share of the total time of each operation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stats = self.stats();
+        let total: Duration = stats.iter().map(OpStats::total_time).sum();
+        let width = stats.iter().map(|s| s.name.len()).max().unwrap_or(0).max(2);
+        writeln!(
+            f,
+            "{:<width$} {:>8} {:>12} {:>12} {:>7} {:>10} {:>10}",
+            "op", "calls", "forward", "backward", "time", "fwd allocs", "bwd allocs"
+        )?;
+        for s in stats.iter() {
+            let share = if total.is_zero() {
+                0.0
+            } else {
+                100.0 * s.total_time().as_secs_f64() / total.as_secs_f64()
+            };
+            writeln!(
+                f,
+                "{:<width$} {:>8} {:>12} {:>12} {:>6.1}% {:>10} {:>10}",
+                s.name,
+                s.calls,
+                format!("{:.3?}", s.forward_time),
+                format!("{:.3?}", s.backward_time),
+                share,
+                s.forward_allocations,
+                s.backward_allocations,
+            )?;
+        }
+        write!(
+            f,
+            "{:<width$} {:>8} {:>12}",
+            "total",
+            "",
+            format!("{total:.3?}")
+        )
+    }
+}
+
+/// Measures one call of an operation or its backward operation until dropped.
+pub(crate) struct OpTimer {
+    name: &'static str,
+    backward: bool,
+    start: Instant,
+    num_allocations: usize,
+}
+
+impl OpTimer {
+    fn start(name: &'static str, backward: bool) -> Option<Self> {
+        if CURRENT.with(Cell::get).is_some() || !ACTIVE.with(|a| a.borrow().is_some()) {
+            return None;
+        }
+        CURRENT.with(|c| c.set(Some(name)));
+        Some(Self {
+            name,
+            backward,
+            start: Instant::now(),
+            num_allocations: NUM_ALLOCATIONS.with(Cell::get),
+        })
+    }
+}
+
+impl Drop for OpTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        let num_allocations = NUM_ALLOCATIONS.with(Cell::get) - self.num_allocations;
+        CURRENT.with(|c| c.set(None));
+        ACTIVE.with(|active| {
+            if let Some(stats) = active.borrow_mut().as_mut() {
+                let s = stats.entry(self.name).or_insert(OpStats {
+                    name: self.name,
+                    ..Default::default()
+                });
+                if self.backward {
+                    s.backward_time += elapsed;
+                    s.backward_allocations += num_allocations;
+                } else {
+                    s.calls += 1;
+                    s.forward_time += elapsed;
+                    s.forward_allocations += num_allocations;
+                }
+            }
+        });
+    }
+}
+
+/// Starts measuring a call of the operation `name`, if a [Profiler] is active and no other
+/// operation is running. Keep the result alive until the operation is done.
+pub(crate) fn op(name: &'static str) -> Option<OpTimer> {
+    OpTimer::start(name, false)
+}
+
+/// Starts measuring the backward operation of `name`, see [op()].
+pub(crate) fn backward_op(name: &'static str) -> Option<OpTimer> {
+    OpTimer::start(name, true)
+}
+
+/// The operation that is running, if a [Profiler] is active. Backward operations that are added
+/// to the tape are measured under this name.
+pub(crate) fn current_op() -> Option<&'static str> {
+    CURRENT.with(Cell::get)
+}
+
+/// Counts an allocation of an array for the running operation.
+pub(crate) fn count_allocation() {
+    if CURRENT.with(Cell::get).is_some() {
+        NUM_ALLOCATIONS.with(|n| n.set(n.get() + 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn get(profiler: &Profiler, name: &str) -> OpStats {
+        *profiler.stats().iter().find(|s| s.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_profiler_records_forward_and_backward() {
+        let mut profiler = Profiler::default();
+        let t: Tensor1D<4> = Tensor1D::new([1.0, -2.0, 3.0, -4.0]);
+        profiler.scope(|| {
+            let _ = t.trace().relu().square().sum().backward();
+            let _ = t.clone().relu();
+        });
+
+        let relu = get(&profiler, "relu");
+        assert_eq!(relu.calls, 2);
+        assert_eq!(relu.forward_allocations, 2);
+        // the gradient of the input is allocated by the backward op
+        assert_eq!(relu.backward_allocations, 1);
+
+        let sum = get(&profiler, "sum");
+        assert_eq!(sum.calls, 1);
+        assert!(sum.backward_time > Duration::ZERO);
+        assert_eq!(profiler.stats().len(), 3);
+        assert!(profiler.to_string().contains("square"));
+
+        profiler.clear();
+        assert!(profiler.stats().is_empty());
+    }
+
+    #[test]
+    fn test_nested_ops_count_towards_outer() {
+        let mut profiler = Profiler::default();
+        profiler.scope(|| {
+            let _ = Tensor1D::new([1.0, 2.0, 3.0]).trace().softmax().backward();
+        });
+        let stats = profiler.stats();
+        let names: Vec<&str> = stats.iter().map(|s| s.name).collect();
+        assert_eq!(names, ["softmax"]);
+        assert!(stats[0].backward_time > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_nothing_recorded_outside_scope() {
+        let mut profiler = Profiler::default();
+        let t: Tensor1D<3> = Tensor1D::zeros();
+        let y = t.trace().exp();
+        profiler.scope(|| {
+            let _ = t.clone().sin();
+        });
+        let _ = y.sum().backward();
+        let names: Vec<&str> = profiler.stats().iter().map(|s| s.name).collect();
+        assert_eq!(names, ["sin"]);
+    }
+}
@@ -0,0 +1,347 @@
+//! Python bindings for trained models with [PyO3](https://pyo3.rs), so they can be evaluated
+//! on numpy arrays from existing Python pipelines without exporting them to ONNX first.
+//!
+//! Models are statically typed, so a Python class has to be declared for each model type with
+//! [python_model!] in a crate that is built as a Python extension module, e.g. with
+//! [maturin](https://www.maturin.rs). The class can load the parameters of the model from a
+//! `.npz` or `.safetensors` file, and is called with a numpy array of any number of samples:
+//!
+//! ```ignore
+//! use dfdx::prelude::*;
+//! use pyo3::prelude::*;
+//!
+//! dfdx::python_model!(
+//!     /// An mlp that classifies samples with 4 features into 3 classes.
+//!     pub struct Classifier((Linear<4, 32>, ReLU, Linear<32, 3>)), sample = Tensor1D<4>, batch = 64
+//! );
+//!
+//! #[pymodule]
+//! fn my_models(m: &Bound<'_, PyModule>) -> PyResult<()> {
+//!     m.add_class::<Classifier>()
+//! }
+//! ```
+//!
+//! ```python
+//! import numpy as np
+//! from my_models import Classifier
+//!
+//! model = Classifier.load("classifier.npz")
+//! logits = model(np.random.randn(1000, 4).astype(np.float32))
+//! assert logits.shape == (1000, 3)
+//! ```
+//!
+//! The samples are run through the model `batch` at a time with [forward_dynamic()], so the number
+//! of samples doesn't have to be known at compile time. The extension module should depend on the
+//! same version of `pyo3` as dfdx (re-exported as [pyo3]), with its `extension-module` feature enabled.
+//! See [examples/python](https://github.com/coreylowman/dfdx/tree/main/examples/python) for a complete
+//! example.
+//!
+//! The functions that [python_model!] uses are public too, for classes that need more methods.
+
+pub use ::numpy;
+pub use pyo3;
+
+use crate::devices::{flat, flat_mut};
+use crate::numpy::NumpyShape;
+use crate::prelude::*;
+use ::numpy::ndarray::{ArrayD, IxDyn};
+use ::numpy::{IntoPyArray, PyArrayDyn, PyReadonlyArrayDyn, PyUntypedArrayMethods};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::{Bound, PyErr, PyResult};
+use std::path::Path;
+
+/// Declares a Python class named `$name` for the model type `$Model`, which can be loaded from
+/// `.npz` & `.safetensors` files and called on numpy arrays. See [crate::python].
+///
+/// - `sample` is the type of a single sample, e.g. `Tensor1D<4>` for samples with 4 features.
+///   Arrays passed to the model have the shape `(n, 4)`.
+/// - `batch` is the number of samples passed to the model at a time.
+///
+/// The class has these methods in Python:
+/// - `$name.load(path)` creates the model and loads its parameters from `path`.
+/// - `model(x)` runs the model on the samples in `x`, and returns a new array with the outputs.
+/// - `model.sample_shape` & `model.output_shape` are the shapes of a single sample & output.
+///
+/// The class is not `Send`, so a model can only be used from the thread that loaded it.
+///
+/// Example:
+/// ```no_run
+/// # use dfdx::prelude::*;
+/// dfdx::python_model!(
+///     /// Predicts the price of a house from 3 features.
+///     pub struct PriceModel((Linear<3, 8>, Tanh, Linear<8, 1>)), sample = Tensor1D<3>, batch = 16
+/// );
+/// ```
+#[macro_export]
+macro_rules! python_model {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident($Model:ty), sample = $Sample:ty, batch = $B:expr $(,)?
+    ) => {
+        $(#[$attr])*
+        #[$crate::python::pyo3::pyclass(unsendable)]
+        #[pyo3(crate = "dfdx::python::pyo3")]
+        $vis struct $name {
+            pub model: $Model,
+        }
+
+        #[$crate::python::pyo3::pymethods]
+        #[pyo3(crate = "dfdx::python::pyo3")]
+        impl $name {
+            /// Creates the model and loads its parameters from a `.npz` or `.safetensors` file.
+            #[staticmethod]
+            fn load(path: &str) -> $crate::python::pyo3::PyResult<Self> {
+                let mut model: $Model = ::core::default::Default::default();
+                $crate::python::load_model(&mut model, path)?;
+                Ok(Self { model })
+            }
+
+            /// Runs the model on an array of samples, where the first axis is the number of samples.
+            fn __call__<'py>(
+                &self,
+                x: $crate::python::numpy::PyReadonlyArrayDyn<'py, f32>,
+            ) -> $crate::python::pyo3::PyResult<
+                $crate::python::pyo3::Bound<'py, $crate::python::numpy::PyArrayDyn<f32>>,
+            > {
+                $crate::python::predict::<{ $B }, _, $Sample>(&self.model, x)
+            }
+
+            /// The shape of a single sample.
+            #[getter]
+            fn sample_shape(&self) -> Vec<usize> {
+                $crate::python::sample_shape::<$Sample>()
+            }
+
+            /// The shape of the output for a single sample.
+            #[getter]
+            fn output_shape(&self) -> Vec<usize> {
+                $crate::python::output_shape::<{ $B }, $Model, $Sample>()
+            }
+        }
+    };
+}
+
+/// An array passed to a model does not have the shape `(n, *sample_shape)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeError {
+    /// The shape of a single sample.
+    pub sample_shape: Vec<usize>,
+    /// The shape of the array.
+    pub found: Vec<usize>,
+}
+
+impl std::fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut expected = vec!["n".to_string()];
+        expected.extend(self.sample_shape.iter().map(|d| d.to_string()));
+        let found: Vec<String> = self.found.iter().map(|d| d.to_string()).collect();
+        write!(
+            f,
+            "expected an array of shape ({}), found an array of shape ({})",
+            expected.join(", "),
+            found.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+impl From<ShapeError> for PyErr {
+    /// Raised as a `ValueError`.
+    fn from(e: ShapeError) -> Self {
+        PyValueError::new_err(e.to_string())
+    }
+}
+
+/// The shape of a single sample of type `S`.
+pub fn sample_shape<S: HasArrayType>() -> Vec<usize>
+where
+    S::Array: NumpyShape,
+{
+    S::Array::shape()
+}
+
+/// The shape of the output of `M` for a single sample of type `S`.
+pub fn output_shape<const B: usize, M, S>() -> Vec<usize>
+where
+    S: Collate<B>,
+    M: Module<S::Batched>,
+    M::Output: Uncollate<B>,
+    <M::Output as Uncollate<B>>::Item: HasArrayType,
+    <<M::Output as Uncollate<B>>::Item as HasArrayType>::Array: NumpyShape,
+{
+    <<M::Output as Uncollate<B>>::Item as HasArrayType>::Array::shape()
+}
+
+/// Loads the parameters of `model` with [LoadFromSafetensors::load_safetensors()] if `path` ends
+/// with `.safetensors`, and with [LoadFromNpz::load()] otherwise. Errors are raised as `IOError`s.
+pub fn load_model<M: LoadFromNpz + VisitParams>(model: &mut M, path: &str) -> PyResult<()> {
+    let result = match Path::new(path).extension() {
+        Some(ext) if ext == "safetensors" => {
+            model.load_safetensors(path).map_err(|e| e.to_string())
+        }
+        _ => model.load(path).map_err(|e| e.to_string()),
+    };
+    result.map_err(|e| PyIOError::new_err(format!("failed to load {path}: {e}")))
+}
+
+/// Runs `model` on the `n` samples stored row major in `data`, where `shape` is `[n, *sample_shape]`.
+/// The samples are passed to the model `B` at a time with [forward_dynamic()].
+///
+/// Returns the outputs stored the same way, and their shape `[n, *output_shape]`.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let model: Linear<2, 3> = Default::default();
+/// let x = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+/// let (y, shape) = dfdx::python::predict_slice::<4, _, Tensor1D<2>>(&model, &x, &[3, 2]).unwrap();
+/// assert_eq!(shape, [3, 3]);
+/// assert_eq!(y, [0.0; 9]);
+/// ```
+pub fn predict_slice<const B: usize, M, S>(
+    model: &M,
+    data: &[f32],
+    shape: &[usize],
+) -> Result<(Vec<f32>, Vec<usize>), ShapeError>
+where
+    S: Tensor<Dtype = f32> + TensorCreator + Collate<B> + Clone,
+    S::Array: NumpyShape,
+    M: Module<S::Batched>,
+    M::Output: Uncollate<B>,
+    <M::Output as Uncollate<B>>::Item: HasArrayType<Dtype = f32> + HasArrayData,
+    <<M::Output as Uncollate<B>>::Item as HasArrayType>::Array: NumpyShape,
+{
+    let sample_shape = sample_shape::<S>();
+    if shape.is_empty() || shape[1..] != sample_shape[..] {
+        return Err(ShapeError {
+            sample_shape,
+            found: shape.to_vec(),
+        });
+    }
+    assert_eq!(data.len(), shape.iter().product::<usize>());
+
+    let samples: Vec<S> = data
+        .chunks_exact(S::Array::NUM_ELEMENTS)
+        .map(|sample| {
+            let mut x = S::zeros();
+            flat_mut(x.mut_data()).copy_from_slice(sample);
+            x
+        })
+        .collect();
+    let outputs = forward_dynamic::<B, M, S>(model, samples);
+
+    let mut output_shape = vec![shape[0]];
+    output_shape.extend(self::output_shape::<B, M, S>());
+    let mut output = Vec::with_capacity(output_shape.iter().product());
+    for y in outputs.iter() {
+        output.extend_from_slice(flat(y.data()));
+    }
+    Ok((output, output_shape))
+}
+
+/// Runs `model` on the samples in the numpy array `x` with [predict_slice()], and returns the
+/// outputs as a new numpy array. `x` is copied first if it is not contiguous & row major.
+/// Arrays of the wrong shape raise a `ValueError`, see [ShapeError].
+pub fn predict<'py, const B: usize, M, S>(
+    model: &M,
+    x: PyReadonlyArrayDyn<'py, f32>,
+) -> PyResult<Bound<'py, PyArrayDyn<f32>>>
+where
+    S: Tensor<Dtype = f32> + TensorCreator + Collate<B> + Clone,
+    S::Array: NumpyShape,
+    M: Module<S::Batched>,
+    M::Output: Uncollate<B>,
+    <M::Output as Uncollate<B>>::Item: HasArrayType<Dtype = f32> + HasArrayData,
+    <<M::Output as Uncollate<B>>::Item as HasArrayType>::Array: NumpyShape,
+{
+    let array = x.as_array();
+    let (output, shape) = match array.as_slice() {
+        Some(data) => predict_slice::<B, M, S>(model, data, x.shape())?,
+        None => {
+            let data: Vec<f32> = array.iter().copied().collect();
+            predict_slice::<B, M, S>(model, &data, x.shape())?
+        }
+    };
+    let output = ArrayD::from_shape_vec(IxDyn(&shape), output).unwrap();
+    Ok(output.into_pyarray_bound(x.py()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_close;
+    use rand::{prelude::StdRng, SeedableRng};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_predict_slice() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut model: (Linear<3, 4>, ReLU, Linear<4, 2>) = Default::default();
+        model.reset_params(&mut rng);
+
+        let x: Tensor2D<5, 3> = TensorCreator::randn(&mut rng);
+        let data: Vec<f32> = x.data().iter().flatten().copied().collect();
+        let (y, shape) = predict_slice::<2, _, Tensor1D<3>>(&model, &data, &[5, 3]).unwrap();
+        assert_eq!(shape, [5, 2]);
+        let y: [[f32; 2]; 5] = core::array::from_fn(|i| [y[2 * i], y[2 * i + 1]]);
+        assert_close(&y, model.forward(x).data());
+
+        let (y, shape) = predict_slice::<2, _, Tensor1D<3>>(&model, &[], &[0, 3]).unwrap();
+        assert_eq!(shape, [0, 2]);
+        assert!(y.is_empty());
+    }
+
+    #[test]
+    fn test_predict_slice_2d_samples() {
+        let mut model: Linear<3, 5> = Default::default();
+        model.reset_params(&mut StdRng::seed_from_u64(1));
+        let (y, shape) =
+            predict_slice::<4, _, Tensor2D<2, 3>>(&model, &[1.0; 18], &[3, 2, 3]).unwrap();
+        assert_eq!(shape, [3, 2, 5]);
+        let expected = model.forward(Tensor1D::new([1.0; 3]));
+        for row in y.chunks(5) {
+            assert_close(&<[f32; 5]>::try_from(row).unwrap(), expected.data());
+        }
+    }
+
+    #[test]
+    fn test_predict_slice_wrong_shape() {
+        let model: Linear<3, 2> = Default::default();
+        let err = predict_slice::<2, _, Tensor1D<3>>(&model, &[0.0; 8], &[2, 4]).unwrap_err();
+        assert_eq!(
+            err,
+            ShapeError {
+                sample_shape: vec![3],
+                found: vec![2, 4]
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "expected an array of shape (n, 3), found an array of shape (2, 4)"
+        );
+        assert!(predict_slice::<2, _, Tensor1D<3>>(&model, &[0.0; 3], &[3]).is_err());
+    }
+
+    #[test]
+    fn test_load_model() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut saved: Linear<3, 2> = Default::default();
+        saved.reset_params(&mut rng);
+
+        let npz = NamedTempFile::new().unwrap();
+        let safetensors = tempfile::Builder::new()
+            .suffix(".safetensors")
+            .tempfile()
+            .unwrap();
+        saved.save(npz.path().to_str().unwrap()).unwrap();
+        saved.save_safetensors(safetensors.path()).unwrap();
+
+        for file in [&npz, &safetensors] {
+            let mut loaded: Linear<3, 2> = Default::default();
+            assert!(load_model(&mut loaded, file.path().to_str().unwrap()).is_ok());
+            assert_eq!(loaded.weight.data(), saved.weight.data());
+            assert_eq!(loaded.bias.data(), saved.bias.data());
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! A seedable default random number generator, to make runs reproducible with [set_seed()].
+//!
+//! Everything random in dfdx takes an [rand::Rng] as an argument, and [seeded_rng()] is the
+//! default one: [TensorCreator::randn_seeded()], [TensorCreator::rand_seeded()],
+//! [ResetParams::reset_params_seeded()] and [DataLoader::shuffled_seeded()] use it. [Dropout::p()],
+//! [DropoutOneIn] and [GradNoise::default()] are seeded from it when created.
+//!
+//! Every thread has its own rng. The thread that calls [set_seed()] uses the seed itself, and
+//! threads that start using their rng afterwards get a different seed derived from it.
+//! [DataParallel] seeds each replica from the rng of the thread that creates it, so replicas
+//! don't share the same random values.
+//!
+//! Example:
+//! ```rust
+//! # use dfdx::prelude::*;
+//! fn init() -> (Linear<4, 2>, Tensor1D<4>) {
+//!     let mut model: Linear<4, 2> = Default::default();
+//!     model.reset_params_seeded();
+//!     (model, Tensor1D::randn_seeded())
+//! }
+//!
+//! dfdx::set_seed(42);
+//! let (a, x) = init();
+//! dfdx::set_seed(42);
+//! let (b, y) = init();
+//! assert_eq!(a.weight.data(), b.weight.data());
+//! assert_eq!(x.data(), y.data());
+//! ```
+//!
+//! [TensorCreator::randn_seeded()]: crate::tensor::TensorCreator::randn_seeded()
+//! [TensorCreator::rand_seeded()]: crate::tensor::TensorCreator::rand_seeded()
+//! [ResetParams::reset_params_seeded()]: crate::nn::ResetParams::reset_params_seeded()
+//! [DataLoader::shuffled_seeded()]: crate::data::DataLoader::shuffled_seeded()
+//! [Dropout::p()]: crate::nn::Dropout::p()
+//! [DropoutOneIn]: crate::nn::DropoutOneIn
+//! [GradNoise::default()]: crate::optim::GradNoise
+//! [DataParallel]: crate::nn::DataParallel
+
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The seed of the last [set_seed()].
+static SEED: AtomicU64 = AtomicU64::new(0);
+
+/// How many threads started using their rng since the last [set_seed()].
+static NUM_THREADS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(thread_seed(
+        SEED.load(Ordering::SeqCst),
+        NUM_THREADS.fetch_add(1, Ordering::SeqCst),
+    )));
+}
+
+/// The seed of the `n`th thread that starts using its rng after `set_seed(seed)`. The first
+/// thread uses `seed` itself, so a program that only has one thread starts with seed `0`.
+fn thread_seed(seed: u64, n: u64) -> u64 {
+    seed.wrapping_add(n.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Re-seeds the rng returned by [seeded_rng()] on the current thread with `seed`. Threads that
+/// start using their rng afterwards are seeded with different seeds derived from `seed`.
+///
+/// Until this is called, the first thread that uses its rng starts with seed `0`.
+pub fn set_seed(seed: u64) {
+    reseed_thread(seed);
+    SEED.store(seed, Ordering::SeqCst);
+    NUM_THREADS.store(1, Ordering::SeqCst);
+}
+
+/// Re-seeds the rng of the current thread only.
+pub(crate) fn reseed_thread(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// A handle to the rng of the current thread that is seeded with [set_seed()].
+pub fn seeded_rng() -> SeededRng {
+    SeededRng { _private: () }
+}
+
+/// The rng returned by [seeded_rng()]. All handles on a thread share the same state, so values
+/// only depend on the seed and on the order in which they are drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct SeededRng {
+    _private: (),
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_set_seed_reproduces_values() {
+        set_seed(3);
+        let a: Tensor2D<2, 3> = TensorCreator::randn_seeded();
+        let b: Tensor1D<5> = TensorCreator::rand_seeded();
+        set_seed(3);
+        let c: Tensor2D<2, 3> = TensorCreator::randn(&mut seeded_rng());
+        let d: Tensor1D<5> = TensorCreator::rand(&mut seeded_rng());
+        assert_eq!(a.data(), c.data());
+        assert_eq!(b.data(), d.data());
+        assert_ne!(a.data(), &[[0.0; 3]; 2]);
+
+        set_seed(4);
+        let e: Tensor2D<2, 3> = TensorCreator::randn(&mut seeded_rng());
+        assert_ne!(a.data(), e.data());
+    }
+
+    #[test]
+    fn test_set_seed_reproduces_dropout() {
+        let t: Tensor1D<100> = Tensor1D::ones();
+        set_seed(1);
+        let a = (Dropout::p(0.5), DropoutOneIn::<2>::default()).forward(t.trace());
+        set_seed(1);
+        let b = (Dropout::p(0.5), DropoutOneIn::<2>::default()).forward(t.trace());
+        assert_eq!(a.data(), b.data());
+    }
+
+    #[test]
+    fn test_threads_have_separate_rngs() {
+        set_seed(5);
+        let x = seeded_rng().next_u64();
+        let spawn = || {
+            std::thread::spawn(|| seeded_rng().next_u64())
+                .join()
+                .unwrap()
+        };
+        let (y, z) = (spawn(), spawn());
+        assert_ne!(x, y);
+        assert_ne!(x, z);
+        assert_ne!(y, z);
+    }
+
+    #[test]
+    fn test_thread_seeds_derive_from_seed() {
+        assert_eq!(thread_seed(7, 0), 7);
+        assert_ne!(thread_seed(7, 1), thread_seed(7, 2));
+        assert_ne!(thread_seed(7, 1), thread_seed(8, 1));
+    }
+}
@@ -18,8 +18,14 @@ impl<$(const $Vs: usize, )* H> HasArrayData for $typename<$($Vs, )* H> {
     /// Returns a reference to the underlying array.
     fn data(&self) -> &Self::Array { self.data.as_ref() }
 
-    /// Returns a mutable reference to the underlying array.
-    fn mut_data(&mut self) -> &mut Self::Array { std::rc::Rc::make_mut(&mut self.data) }
+    /// Returns a mutable reference to the underlying array. The array is cloned if it is shared
+    /// with other tensors.
+    fn mut_data(&mut self) -> &mut Self::Array {
+        if alloc::rc::Rc::get_mut(&mut self.data).is_none() {
+            crate::profile::count_allocation();
+        }
+        alloc::rc::Rc::make_mut(&mut self.data)
+    }
 }
     };
 }
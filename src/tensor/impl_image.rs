@@ -0,0 +1,259 @@
+//! Conversions between [image::DynamicImage]s and [Tensor3D]s with shape (C, H, W).
+
+use crate::prelude::*;
+use image::{imageops::FilterType, DynamicImage, GrayImage, RgbImage, RgbaImage};
+use std::error::Error;
+
+/// The order of the color channels in a tensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelOrder {
+    Rgb,
+
+    /// Blue first, as used by OpenCV and caffe models.
+    Bgr,
+}
+
+/// Options for [ImageTensor::from_image()] and [ImageTensor::to_image()].
+///
+/// Pixels are scaled to `[0.0, 1.0]`, and then normalized per channel with `(x - mean) / std`.
+/// The defaults are rgb order, no normalization, and resizing with [FilterType::Triangle].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageConfig<const C: usize> {
+    pub order: ChannelOrder,
+
+    /// The filter to resize images whose size is not (W, H). If this is `None`, those images are
+    /// an error instead.
+    pub resize: Option<FilterType>,
+
+    pub mean: [f32; C],
+    pub std: [f32; C],
+}
+
+impl<const C: usize> Default for ImageConfig<C> {
+    fn default() -> Self {
+        Self {
+            order: ChannelOrder::Rgb,
+            resize: Some(FilterType::Triangle),
+            mean: [0.0; C],
+            std: [1.0; C],
+        }
+    }
+}
+
+impl ImageConfig<3> {
+    /// Normalizes with the per channel mean & std of ImageNet, which most pretrained vision
+    /// models expect.
+    pub fn imagenet() -> Self {
+        Self {
+            mean: [0.485, 0.456, 0.406],
+            std: [0.229, 0.224, 0.225],
+            ..Default::default()
+        }
+    }
+}
+
+/// A [Tensor3D] with shape (C, H, W) that can be converted to and from an image. Images with 1
+/// channel are grayscale, 3 channels are rgb, and 4 channels are rgba.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let img = image::DynamicImage::new_rgb8(64, 48);
+/// let t: Tensor3D<3, 32, 32> = ImageTensor::from_image(&img, &ImageConfig::imagenet()).unwrap();
+/// let img = t.to_image(&ImageConfig::imagenet()).unwrap();
+/// assert_eq!((img.width(), img.height()), (32, 32));
+/// ```
+pub trait ImageTensor<const C: usize>: Sized {
+    /// Converts `img` to a tensor, resizing it if needed.
+    fn from_image(img: &DynamicImage, cfg: &ImageConfig<C>) -> Result<Self, ImageTensorError>;
+
+    /// Converts the tensor back to an image, undoing the normalization of `cfg`. Values are
+    /// clamped to the valid range of pixels.
+    fn to_image(&self, cfg: &ImageConfig<C>) -> Result<DynamicImage, ImageTensorError>;
+}
+
+impl<const C: usize, const H: usize, const W: usize> ImageTensor<C> for Tensor3D<C, H, W> {
+    fn from_image(img: &DynamicImage, cfg: &ImageConfig<C>) -> Result<Self, ImageTensorError> {
+        let size = (W as u32, H as u32);
+        let resized;
+        let img = match cfg.resize {
+            _ if (img.width(), img.height()) == size => img,
+            Some(filter) => {
+                resized = img.resize_exact(size.0, size.1, filter);
+                &resized
+            }
+            None => {
+                return Err(ImageTensorError::SizeMismatch {
+                    expected: size,
+                    found: (img.width(), img.height()),
+                })
+            }
+        };
+
+        let pixels = match C {
+            1 => img.to_luma8().into_raw(),
+            3 => img.to_rgb8().into_raw(),
+            4 => img.to_rgba8().into_raw(),
+            _ => return Err(ImageTensorError::UnsupportedChannels(C)),
+        };
+
+        let mut t = Self::zeros();
+        let data = t.mut_data();
+        for (i, px) in pixels.chunks_exact(C).enumerate() {
+            let (y, x) = (i / W, i % W);
+            for (c, &v) in px.iter().enumerate() {
+                let c = channel(cfg.order, c);
+                data[c][y][x] = (v as f32 / 255.0 - cfg.mean[c]) / cfg.std[c];
+            }
+        }
+        Ok(t)
+    }
+
+    fn to_image(&self, cfg: &ImageConfig<C>) -> Result<DynamicImage, ImageTensorError> {
+        let data = self.data();
+        let mut pixels = Vec::with_capacity(C * H * W);
+        for i in 0..H * W {
+            let (y, x) = (i / W, i % W);
+            for c in 0..C {
+                let c = channel(cfg.order, c);
+                let v = data[c][y][x] * cfg.std[c] + cfg.mean[c];
+                pixels.push((v * 255.0).round().clamp(0.0, 255.0) as u8);
+            }
+        }
+
+        // NOTE: the buffers have exactly the right size, so `from_raw` can't fail
+        let (w, h) = (W as u32, H as u32);
+        match C {
+            1 => Ok(GrayImage::from_raw(w, h, pixels).unwrap().into()),
+            3 => Ok(RgbImage::from_raw(w, h, pixels).unwrap().into()),
+            4 => Ok(RgbaImage::from_raw(w, h, pixels).unwrap().into()),
+            _ => Err(ImageTensorError::UnsupportedChannels(C)),
+        }
+    }
+}
+
+/// The tensor channel of the `c`th image channel. Only the color channels are reordered, so
+/// alpha stays last.
+fn channel(order: ChannelOrder, c: usize) -> usize {
+    match (order, c) {
+        (ChannelOrder::Bgr, 0..=2) => 2 - c,
+        _ => c,
+    }
+}
+
+/// Error that can happen while converting between images and tensors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageTensorError {
+    /// Only 1, 3, and 4 channels are supported.
+    UnsupportedChannels(usize),
+
+    /// The image is not (W, H), and [ImageConfig::resize] is `None`.
+    SizeMismatch {
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+}
+
+impl std::fmt::Display for ImageTensorError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ImageTensorError::UnsupportedChannels(c) => {
+                write!(fmt, "images with {c} channels are not supported")
+            }
+            ImageTensorError::SizeMismatch { expected, found } => write!(
+                fmt,
+                "expected an image of size {expected:?}, found {found:?}"
+            ),
+        }
+    }
+}
+
+impl Error for ImageTensorError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, Rgba};
+
+    fn test_image() -> DynamicImage {
+        RgbImage::from_fn(3, 2, |x, y| Rgb([x as u8 * 100, y as u8 * 255, 51])).into()
+    }
+
+    #[test]
+    fn test_from_image() {
+        let t: Tensor3D<3, 2, 3> =
+            ImageTensor::from_image(&test_image(), &Default::default()).expect("");
+        assert_eq!(
+            t.data(),
+            &[
+                [[0.0, 100.0 / 255.0, 200.0 / 255.0]; 2],
+                [[0.0; 3], [1.0; 3]],
+                [[0.2; 3]; 2],
+            ]
+        );
+
+        let cfg = ImageConfig {
+            order: ChannelOrder::Bgr,
+            mean: [0.5, 0.0, 0.0],
+            std: [2.0, 1.0, 1.0],
+            ..Default::default()
+        };
+        let t: Tensor3D<3, 2, 3> = ImageTensor::from_image(&test_image(), &cfg).expect("");
+        assert_eq!(t.data()[0], [[-0.15; 3]; 2]);
+        assert_eq!(t.data()[2][0], [0.0, 100.0 / 255.0, 200.0 / 255.0]);
+
+        let t: Tensor3D<1, 2, 3> =
+            ImageTensor::from_image(&test_image(), &Default::default()).expect("");
+        assert!(t.data()[0][1].iter().all(|&v| v > 0.5));
+    }
+
+    #[test]
+    fn test_image_roundtrip() {
+        for cfg in [ImageConfig::imagenet(), ImageConfig::default()] {
+            let t: Tensor3D<3, 2, 3> = ImageTensor::from_image(&test_image(), &cfg).expect("");
+            assert_eq!(t.to_image(&cfg).expect(""), test_image());
+        }
+
+        let img: DynamicImage = RgbaImage::from_pixel(2, 2, Rgba([1, 2, 3, 4])).into();
+        let cfg = ImageConfig {
+            order: ChannelOrder::Bgr,
+            ..Default::default()
+        };
+        let t: Tensor3D<4, 2, 2> = ImageTensor::from_image(&img, &cfg).expect("");
+        assert_eq!(t.data()[0][0][0], 3.0 / 255.0);
+        assert_eq!(t.data()[3][0][0], 4.0 / 255.0);
+        assert_eq!(t.to_image(&cfg).expect(""), img);
+    }
+
+    #[test]
+    fn test_image_resize() {
+        let t: Tensor3D<3, 4, 6> =
+            ImageTensor::from_image(&test_image(), &Default::default()).expect("");
+        let img = t.to_image(&Default::default()).expect("");
+        assert_eq!(img.dimensions(), (6, 4));
+
+        let cfg = ImageConfig {
+            resize: None,
+            ..Default::default()
+        };
+        let r: Result<Tensor3D<3, 4, 6>, _> = ImageTensor::from_image(&test_image(), &cfg);
+        assert_eq!(
+            r.unwrap_err(),
+            ImageTensorError::SizeMismatch {
+                expected: (6, 4),
+                found: (3, 2)
+            }
+        );
+    }
+
+    #[test]
+    fn test_to_image_clamps() {
+        let t = Tensor3D::new([[[-1.0, 0.5, 2.0]]]);
+        let img = t.to_image(&Default::default()).expect("");
+        assert_eq!(img.to_luma8().into_raw(), [0, 128, 255]);
+
+        let r: Result<Tensor3D<2, 1, 1>, _> =
+            ImageTensor::from_image(&test_image(), &Default::default());
+        assert_eq!(r.unwrap_err(), ImageTensorError::UnsupportedChannels(2));
+    }
+}
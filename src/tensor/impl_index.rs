@@ -0,0 +1,73 @@
+use super::*;
+use core::ops::{Index, IndexMut};
+
+macro_rules! impl_index {
+    ($typename:ident, [$($Vs:tt),*], [$I:ident], $Out:ty) => {
+        impl_index!(@impl $typename, [$($Vs),*], usize, $I, [$I], $Out);
+    };
+    ($typename:ident, [$($Vs:tt),*], [$($Is:ident),+], $Out:ty) => {
+        impl_index!(@impl $typename, [$($Vs),*], ($(impl_index!(@usize $Is)),+), ($($Is),+), [$($Is),+], $Out);
+    };
+    (@usize $I:ident) => { usize };
+    (@impl $typename:ident, [$($Vs:tt),*], $Idx:ty, $pat:pat, [$($Is:ident),+], $Out:ty) => {
+impl<$(const $Vs: usize, )* H> Index<$Idx> for $typename<$($Vs, )* H> {
+    type Output = $Out;
+    /// Returns a reference to the element (or sub array) at the index. Panics if out of bounds.
+    fn index(&self, $pat: $Idx) -> &Self::Output {
+        &self.data()$([$Is])+
+    }
+}
+
+impl<$(const $Vs: usize, )* H> IndexMut<$Idx> for $typename<$($Vs, )* H> {
+    /// Returns a mutable reference to the element (or sub array) at the index, see
+    /// [HasArrayData::mut_data()]. Panics if out of bounds.
+    fn index_mut(&mut self, $pat: $Idx) -> &mut Self::Output {
+        &mut self.mut_data()$([$Is])+
+    }
+}
+    };
+}
+
+impl_index!(Tensor1D, [M], [i], f32);
+impl_index!(Tensor2D, [M, N], [i], [f32; N]);
+impl_index!(Tensor2D, [M, N], [i, j], f32);
+impl_index!(Tensor3D, [M, N, O], [i], [[f32; O]; N]);
+impl_index!(Tensor3D, [M, N, O], [i, j], [f32; O]);
+impl_index!(Tensor3D, [M, N, O], [i, j, k], f32);
+impl_index!(Tensor4D, [M, N, O, P], [i], [[[f32; P]; O]; N]);
+impl_index!(Tensor4D, [M, N, O, P], [i, j], [[f32; P]; O]);
+impl_index!(Tensor4D, [M, N, O, P], [i, j, k], [f32; P]);
+impl_index!(Tensor4D, [M, N, O, P], [i, j, k, l], f32);
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_index_views_data() {
+        let t = Tensor3D::new([[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]]);
+        assert_eq!(t[1], [[5.0, 6.0], [7.0, 8.0]]);
+        assert_eq!(t[(0, 1)], [3.0, 4.0]);
+        assert_eq!(t[(1, 0, 1)], 6.0);
+
+        let v: Tensor1D<3> = Tensor1D::new([1.0, 2.0, 3.0]);
+        assert_eq!(v[2], 3.0);
+    }
+
+    #[test]
+    fn test_index_mut_does_not_change_clones() {
+        let mut a: Tensor2D<2, 3> = Tensor2D::zeros();
+        let b = a.clone();
+        a[(1, 2)] = 1.0;
+        a[0] = [2.0; 3];
+        assert_eq!(a.data(), &[[2.0; 3], [0.0, 0.0, 1.0]]);
+        assert_eq!(b.data(), &[[0.0; 3]; 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_bounds() {
+        let t: Tensor2D<2, 3> = Tensor2D::zeros();
+        let _ = t[(0, 3)];
+    }
+}
@@ -0,0 +1,180 @@
+//! Conversions between tensors and [ndarray] arrays.
+//!
+//! Tensors can be viewed as an [ArrayView]/[ArrayViewMut] without copying with [AsNdarray].
+//! Going the other way always copies, since tensors own their data. Any [ArrayBase] with the right
+//! shape can be converted with [TryFrom], regardless of its memory layout.
+
+use crate::prelude::*;
+use ndarray::{
+    Array, ArrayBase, ArrayView, ArrayViewMut, Data, Dimension, ErrorKind, Ix0, Ix1, Ix2, Ix3, Ix4,
+    ShapeError,
+};
+
+/// A tensor that can be viewed as an [ndarray] array with the same shape.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let mut t = Tensor2D::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+/// assert_eq!(t.as_array_view(), ndarray::arr2(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]));
+///
+/// t.as_array_view_mut().column_mut(1).fill(0.0);
+/// assert_eq!(t.data(), &[[1.0, 0.0, 3.0], [4.0, 0.0, 6.0]]);
+///
+/// let a = ndarray::Array::linspace(0.0, 1.0, 3);
+/// let t: Tensor1D<3> = a.try_into().unwrap();
+/// assert_eq!(t.data(), &[0.0, 0.5, 1.0]);
+/// ```
+pub trait AsNdarray: HasArrayData {
+    /// The [Dimension] of the array, e.g. [ndarray::Ix2] for a [Tensor2D].
+    type Dim: Dimension;
+
+    /// Returns a view of the tensor's data. This does not copy.
+    fn as_array_view(&self) -> ArrayView<'_, f32, Self::Dim>;
+
+    /// Returns a mutable view of the tensor's data. Like [HasArrayData::mut_data()], this only
+    /// copies if the data is shared with another tensor.
+    fn as_array_view_mut(&mut self) -> ArrayViewMut<'_, f32, Self::Dim>;
+
+    /// Copies the tensor's data into a new [Array].
+    fn to_ndarray(&self) -> Array<f32, Self::Dim> {
+        self.as_array_view().to_owned()
+    }
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*], $dim:ty, |$d:ident| $flat:expr, $flat_mut:expr) => {
+impl<$(const $Vs: usize, )* H> AsNdarray for $typename<$($Vs, )* H> {
+    type Dim = $dim;
+
+    fn as_array_view(&self) -> ArrayView<'_, f32, Self::Dim> {
+        // NOTE: the data is contiguous & row major, so the shape always matches
+        ArrayView::from_shape([$($Vs, )*], { let $d = self.data(); $flat }).unwrap()
+    }
+
+    fn as_array_view_mut(&mut self) -> ArrayViewMut<'_, f32, Self::Dim> {
+        ArrayViewMut::from_shape([$($Vs, )*], { let $d = self.mut_data(); $flat_mut }).unwrap()
+    }
+}
+
+impl<$(const $Vs: usize, )* S: Data<Elem = f32>> TryFrom<ArrayBase<S, $dim>>
+    for $typename<$($Vs, )* NoneTape>
+{
+    type Error = ShapeError;
+
+    /// Copies the array into a new tensor. Returns [ErrorKind::IncompatibleShape] if the
+    /// shape of the array is different from the shape of the tensor.
+    fn try_from(a: ArrayBase<S, $dim>) -> Result<Self, Self::Error> {
+        if a.shape() != [$($Vs, )*] as [usize; _] {
+            return Err(ShapeError::from_kind(ErrorKind::IncompatibleShape));
+        }
+        let mut data: Box<<Self as HasArrayType>::Array> = Cpu::zeros();
+        let flat: &mut [f32] = {
+            let $d = data.as_mut();
+            $flat_mut
+        };
+        match a.as_slice() {
+            Some(src) => flat.copy_from_slice(src),
+            None => flat.iter_mut().zip(a.iter()).for_each(|(x, y)| *x = *y),
+        }
+        Ok(Self::new_boxed(data))
+    }
+}
+    };
+}
+
+tensor_impl!(
+    Tensor0D,
+    [],
+    Ix0,
+    |d| std::slice::from_ref(d),
+    std::slice::from_mut(d)
+);
+tensor_impl!(Tensor1D, [M], Ix1, |d| d.as_slice(), d.as_mut_slice());
+tensor_impl!(
+    Tensor2D,
+    [M, N],
+    Ix2,
+    |d| d.as_flattened(),
+    d.as_flattened_mut()
+);
+tensor_impl!(
+    Tensor3D,
+    [M, N, O],
+    Ix3,
+    |d| d.as_flattened().as_flattened(),
+    d.as_flattened_mut().as_flattened_mut()
+);
+tensor_impl!(
+    Tensor4D,
+    [M, N, O, P],
+    Ix4,
+    |d| d.as_flattened().as_flattened().as_flattened(),
+    d.as_flattened_mut().as_flattened_mut().as_flattened_mut()
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{arr0, arr1, arr3, s, Array4, Axis};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_as_array_view() {
+        let t = Tensor0D::new(2.0);
+        assert_eq!(t.as_array_view(), arr0(2.0));
+
+        let t = Tensor1D::new([1.0, 2.0, 3.0]);
+        assert_eq!(t.to_ndarray(), arr1(&[1.0, 2.0, 3.0]));
+
+        let t: Tensor3D<2, 3, 4> = TensorCreator::randn(&mut thread_rng());
+        let a = t.as_array_view();
+        assert_eq!(a.shape(), [2, 3, 4]);
+        assert_eq!(a[[1, 2, 3]], t.data()[1][2][3]);
+        assert_eq!(a.as_ptr(), &t.data()[0][0][0] as *const f32);
+    }
+
+    #[test]
+    fn test_as_array_view_mut() {
+        let mut t: Tensor4D<2, 1, 2, 3> = TensorCreator::zeros();
+        t.as_array_view_mut().index_axis_mut(Axis(0), 1).fill(1.0);
+        assert_eq!(t.data()[0], [[[0.0; 3]; 2]]);
+        assert_eq!(t.data()[1], [[[1.0; 3]; 2]]);
+    }
+
+    #[test]
+    fn test_try_from_array() {
+        let a = arr3(&[[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]]);
+        let t: Tensor3D<2, 2, 2> = a.view().try_into().expect("");
+        assert_eq!(
+            t.data(),
+            &[[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]]
+        );
+
+        // non contiguous arrays are copied in logical order
+        let t: Tensor3D<2, 2, 2> = a.view().permuted_axes([2, 1, 0]).try_into().expect("");
+        assert_eq!(
+            t.data(),
+            &[[[1.0, 5.0], [3.0, 7.0]], [[2.0, 6.0], [4.0, 8.0]]]
+        );
+        let t: Tensor2D<2, 2> = a.slice(s![.., 1, ..]).try_into().expect("");
+        assert_eq!(t.data(), &[[3.0, 4.0], [7.0, 8.0]]);
+
+        let t: Tensor0D = arr0(3.0).try_into().expect("");
+        assert_eq!(t.data(), &3.0);
+    }
+
+    #[test]
+    fn test_try_from_wrong_shape() {
+        let a: Array4<f32> = Array4::zeros((1, 2, 3, 4));
+        let r: Result<Tensor4D<1, 2, 4, 3>, _> = a.try_into();
+        assert_eq!(r.unwrap_err().kind(), ErrorKind::IncompatibleShape);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let t: Tensor2D<3, 5> = TensorCreator::randn(&mut thread_rng());
+        let u: Tensor2D<3, 5> = t.to_ndarray().try_into().expect("");
+        assert_eq!(t.data(), u.data());
+    }
+}
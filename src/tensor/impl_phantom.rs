@@ -1,5 +1,5 @@
 use crate::prelude::*;
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 /// A fake tensor that holds a [UniqueId] and a type `T` that is [HasArrayType].
 /// This is created and stored in [GradientTape] operations to access gradient data
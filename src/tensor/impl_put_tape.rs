@@ -15,7 +15,7 @@ where
 {
     type Output = $typename<$($Vs, )* HOut>;
     fn put_tape(self, tape: HOut) -> Self::Output {
-        Self::Output { id: self.id, data: self.data, tape }
+        Self::Output { id: self.id, data: self.data, tape, requires_grad: self.requires_grad }
     }
 }
     };
@@ -0,0 +1,137 @@
+//! [serde] support for tensors, which are (de)serialized as nested sequences of their data, e.g.
+//! a `Tensor2D<2, 3>` is `[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]` in JSON. The tape and id are not
+//! serialized, so deserialized tensors have a new id.
+
+use crate::prelude::*;
+use serde::de::{self, DeserializeSeed, IgnoredAny, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// (De)serializes arrays of any length. serde itself only supports arrays up to length 32.
+trait SerdeArray {
+    fn ser<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error>;
+    fn de<'de, D: Deserializer<'de>>(&mut self, d: D) -> Result<(), D::Error>;
+}
+
+impl SerdeArray for f32 {
+    fn ser<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_f32(*self)
+    }
+
+    fn de<'de, D: Deserializer<'de>>(&mut self, d: D) -> Result<(), D::Error> {
+        *self = f32::deserialize(d)?;
+        Ok(())
+    }
+}
+
+impl<T: SerdeArray, const M: usize> SerdeArray for [T; M] {
+    fn ser<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut tup = s.serialize_tuple(M)?;
+        for x in self.iter() {
+            tup.serialize_element(&Ser(x))?;
+        }
+        tup.end()
+    }
+
+    fn de<'de, D: Deserializer<'de>>(&mut self, d: D) -> Result<(), D::Error> {
+        d.deserialize_tuple(M, Fill(self))
+    }
+}
+
+/// Serializes `T` with [SerdeArray].
+struct Ser<'a, T>(&'a T);
+
+impl<'a, T: SerdeArray> Serialize for Ser<'a, T> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.0.ser(s)
+    }
+}
+
+/// Deserializes into `T` in place with [SerdeArray].
+struct Fill<'a, T>(&'a mut T);
+
+impl<'de, 'a, T: SerdeArray> DeserializeSeed<'de> for Fill<'a, T> {
+    type Value = ();
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<(), D::Error> {
+        self.0.de(d)
+    }
+}
+
+impl<'de, 'a, T: SerdeArray, const M: usize> Visitor<'de> for Fill<'a, [T; M]> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "an array of length {M}")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        for (i, x) in self.0.iter_mut().enumerate() {
+            if seq.next_element_seed(Fill(x))?.is_none() {
+                return Err(de::Error::invalid_length(i, &format!("{M}").as_str()));
+            }
+        }
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(M + 1, &format!("{M}").as_str()));
+        }
+        Ok(())
+    }
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H> Serialize for $typename<$($Vs, )* H> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.data().ser(s)
+    }
+}
+
+impl<'de, $(const $Vs: usize, )*> Deserialize<'de> for $typename<$($Vs, )* NoneTape> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let mut data: Box<<Self as HasArrayType>::Array> = Cpu::zeros();
+        data.de(d)?;
+        Ok(Self::new_boxed(data))
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor0D, []);
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tensor_json() {
+        let t = Tensor2D::new([[1.0, 2.0, 3.0], [4.0, 5.5, -6.0]]);
+        let json = serde_json::to_string(&t).expect("");
+        assert_eq!(json, "[[1.0,2.0,3.0],[4.0,5.5,-6.0]]");
+        let loaded: Tensor2D<2, 3> = serde_json::from_str(&json).expect("");
+        assert_eq!(loaded.data(), t.data());
+        assert_ne!(loaded.id(), t.id());
+
+        let t = Tensor0D::new(2.5);
+        assert_eq!(serde_json::to_string(&t).expect(""), "2.5");
+        let loaded: Tensor0D = serde_json::from_str("2.5").expect("");
+        assert_eq!(loaded.data(), &2.5);
+    }
+
+    #[test]
+    fn test_large_tensor_json() {
+        let t: Tensor3D<2, 40, 33> = TensorCreator::randn(&mut rand::thread_rng());
+        let json = serde_json::to_string(&t.trace()).expect("");
+        let loaded: Tensor3D<2, 40, 33> = serde_json::from_str(&json).expect("");
+        assert_eq!(loaded.data(), t.data());
+    }
+
+    #[test]
+    fn test_tensor_json_wrong_length() {
+        assert!(serde_json::from_str::<Tensor1D<3>>("[1.0, 2.0]").is_err());
+        assert!(serde_json::from_str::<Tensor1D<3>>("[1.0, 2.0, 3.0, 4.0]").is_err());
+        assert!(serde_json::from_str::<Tensor2D<1, 2>>("[1.0, 2.0]").is_err());
+    }
+}
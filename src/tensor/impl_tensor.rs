@@ -30,6 +30,13 @@ pub trait Tensor:
 
     /// Clones the data & [UniqueId] of this tensor and returns something with [NoneTape].
     fn duplicate(&self) -> Self::NoTape;
+
+    /// Whether optimizers should update this tensor. Defaults to `true`.
+    /// Change this with [Tensor::set_requires_grad()], or for all parameters of a module with [Freeze].
+    fn requires_grad(&self) -> bool;
+
+    /// Sets whether optimizers should update this tensor.
+    fn set_requires_grad(&mut self, requires_grad: bool);
 }
 
 macro_rules! tensor_impl {
@@ -41,7 +48,7 @@ impl<$(const $Vs: usize, )* H: Tape> Tensor for $struct<$($Vs, )* H> {
 
     fn split_tape(self) -> (Self::NoTape, Self::Tape) {
         (
-            Self::NoTape { id: self.id, data: self.data, tape: Default::default() },
+            Self::NoTape { id: self.id, data: self.data, tape: Default::default(), requires_grad: self.requires_grad },
             self.tape,
         )
     }
@@ -51,8 +58,17 @@ impl<$(const $Vs: usize, )* H: Tape> Tensor for $struct<$($Vs, )* H> {
             id: self.id,
             data: self.data.clone(),
             tape: Default::default(),
+            requires_grad: self.requires_grad,
         }
     }
+
+    fn requires_grad(&self) -> bool {
+        self.requires_grad
+    }
+
+    fn set_requires_grad(&mut self, requires_grad: bool) {
+        self.requires_grad = requires_grad;
+    }
 }
 
 impl<$(const $Vs: usize, )* H: Clone> Clone for $struct<$($Vs, )* H> {
@@ -62,6 +78,7 @@ impl<$(const $Vs: usize, )* H: Clone> Clone for $struct<$($Vs, )* H> {
             id: unique_id(),
             data: self.data.clone(),
             tape: self.tape.clone(),
+            requires_grad: self.requires_grad,
         }
     }
 }
@@ -101,4 +118,17 @@ mod tests {
         let t3 = t2.put_tape(tape);
         assert_eq!(t3.id, t1_id);
     }
+
+    #[test]
+    fn test_requires_grad_is_kept() {
+        let mut t1: Tensor1D<32> = TensorCreator::zeros();
+        assert!(t1.requires_grad());
+        t1.set_requires_grad(false);
+        assert!(!t1.clone().requires_grad());
+        assert!(!t1.duplicate().requires_grad());
+        assert!(!t1.trace().requires_grad());
+        let (t2, tape) = t1.traced().split_tape();
+        assert!(!t2.requires_grad());
+        assert!(!t2.put_tape(tape).requires_grad());
+    }
 }
@@ -1,5 +1,6 @@
 use super::*;
 use crate::prelude::*;
+use alloc::boxed::Box;
 use num_traits::One;
 use rand::prelude::Distribution;
 use rand_distr::{Standard, StandardNormal};
@@ -44,6 +45,26 @@ pub trait TensorCreator: Sized + HasDevice {
             *v = StandardNormal.sample(rng)
         }))
     }
+
+    /// Like [TensorCreator::rand()] with [crate::rng::seeded_rng()], so the values are
+    /// reproducible with [crate::set_seed()].
+    #[cfg(feature = "std")]
+    fn rand_seeded() -> Self
+    where
+        Standard: Distribution<Self::Dtype>,
+    {
+        Self::rand(&mut crate::rng::seeded_rng())
+    }
+
+    /// Like [TensorCreator::randn()] with [crate::rng::seeded_rng()], so the values are
+    /// reproducible with [crate::set_seed()].
+    #[cfg(feature = "std")]
+    fn randn_seeded() -> Self
+    where
+        StandardNormal: Distribution<Self::Dtype>,
+    {
+        Self::randn(&mut crate::rng::seeded_rng())
+    }
 }
 
 macro_rules! tensor_impl {
@@ -53,8 +74,9 @@ impl<$(const $Vs: usize, )*> TensorCreator for $typename<$($Vs, )* NoneTape> {
     fn new_boxed(data: Box<Self::Array>) -> Self {
         Self {
             id: unique_id(),
-            data: data.into(),
+            data: crate::devices::arena::into_rc(data),
             tape: Default::default(),
+            requires_grad: true,
         }
     }
 }
@@ -1,10 +1,20 @@
 use crate::prelude::*;
 
 impl<T: Tensor<Dtype = f32>> CanUpdateWithGradients for T {
-    /// Subtracts the gradient for the tensor from [HasArrayData::mut_data].
+    /// Subtracts the gradient for the tensor from [HasArrayData::mut_data]. Tensors without a
+    /// gradient are added to `unused`.
+    ///
+    /// If [Tensor::requires_grad()] is `false` the tensor is frozen, and `grads` isn't asked
+    /// for a gradient at all.
     fn update<G: GradientProvider>(&mut self, grads: &mut G, unused: &mut UnusedTensors) {
+        if !self.requires_grad() {
+            return;
+        }
         match grads.gradient(self) {
-            Some(gradient) => <Self as HasDevice>::Device::sub(self.mut_data(), gradient.as_ref()),
+            Some(gradient) => {
+                <Self as HasDevice>::Device::sub(self.mut_data(), gradient.as_ref());
+                crate::devices::arena::recycle(gradient);
+            }
             None => unused.add(self),
         }
     }
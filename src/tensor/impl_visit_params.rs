@@ -0,0 +1,13 @@
+use crate::prelude::*;
+
+impl<T: Tensor<Dtype = f32>> VisitParams for T {
+    /// Visits the tensor itself, named `pre`.
+    fn visit_params<V: ParamVisitor>(&self, pre: &str, visitor: &mut V) {
+        visitor.visit(pre, self);
+    }
+
+    /// Visits the tensor itself, named `pre`.
+    fn visit_params_mut<V: ParamVisitorMut>(&mut self, pre: &str, visitor: &mut V) {
+        visitor.visit_mut(pre, self);
+    }
+}
@@ -41,6 +41,21 @@
 //! assert_eq!(t.data(), &[0.0, 2.0, 0.0]);
 //! ```
 //!
+//! Tensors can also be indexed directly with a `usize` or a tuple of `usize`s, which returns the
+//! element or sub array at that index. To extract sub tensors with ranges, see `Slice`.
+//!
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let mut t = Tensor2D::new([[1.0, 2.0], [3.0, 4.0]]);
+//! assert_eq!(t[1], [3.0, 4.0]);
+//! t[(0, 1)] = 5.0;
+//! assert_eq!(t.data(), &[[1.0, 5.0], [3.0, 4.0]]);
+//! ```
+//!
+//! With the `ndarray` feature, tensors can also be viewed as `ndarray` arrays with `AsNdarray`,
+//! and created from them with [TryFrom]. With the `image` feature, images can be converted to and
+//! from [Tensor3D]s with `ImageTensor`.
+//!
 //! # Tracking gradients
 //!
 //! Use the [trace()] or [traced()] methods to add [crate::gradients::OwnedTape] to the [Tensor].
@@ -66,24 +81,32 @@ mod impl_default;
 mod impl_has_array;
 mod impl_has_device;
 mod impl_has_unique_id;
+#[cfg(feature = "image")]
+mod impl_image;
+mod impl_index;
+#[cfg(feature = "ndarray")]
+mod impl_ndarray;
 mod impl_phantom;
 mod impl_put_tape;
 mod impl_randomize;
+#[cfg(feature = "serde")]
+mod impl_serde;
 mod impl_tensor;
 mod impl_tensor_creator;
 mod impl_trace;
 mod impl_update_with_grads;
+mod impl_visit_params;
 mod structs;
 
-pub use impl_default::*;
 pub use impl_has_array::*;
-pub use impl_has_device::*;
-pub use impl_has_unique_id::*;
+#[cfg(feature = "image")]
+pub use impl_image::*;
+#[cfg(feature = "ndarray")]
+pub use impl_ndarray::*;
 pub use impl_phantom::*;
 pub use impl_put_tape::*;
 pub use impl_randomize::*;
 pub use impl_tensor::*;
 pub use impl_tensor_creator::*;
 pub use impl_trace::*;
-pub use impl_update_with_grads::*;
 pub use structs::*;
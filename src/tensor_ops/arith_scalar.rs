@@ -1,6 +1,6 @@
 use super::utils::move_tape_and_add_backward_op;
 use crate::prelude::*;
-use std::ops::{Add, Div, Mul, Sub};
+use core::ops::{Add, Div, Mul, Sub};
 
 /// `t + val`. `val` is used for all elements of `t`.
 ///
@@ -12,6 +12,7 @@ use std::ops::{Add, Div, Mul, Sub};
 /// assert_eq!(r.data(), &[1.5, 2.5, -2.5]);
 /// ```
 pub fn add_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
+    let _op = crate::profile::op("add_scalar");
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| x + val));
     move_tape_and_add_backward_op(t, result, move |t, result, grads| {
         let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
@@ -31,6 +32,7 @@ pub fn add_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
 /// assert_eq!(r.data(), &[0.5, 1.5, -3.5]);
 /// ```
 pub fn sub_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
+    let _op = crate::profile::op("sub_scalar");
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| x - val));
     move_tape_and_add_backward_op(t, result, move |t, result, grads| {
         let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
@@ -50,6 +52,7 @@ pub fn sub_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
 /// assert_eq!(r.data(), &[0.5, 1.0, -1.5]);
 /// ```
 pub fn mul_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
+    let _op = crate::profile::op("mul_scalar");
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| x * val));
     move_tape_and_add_backward_op(t, result, move |t, result, grads| {
         let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
@@ -69,6 +72,7 @@ pub fn mul_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
 /// assert_eq!(r.data(), &[0.5, 1.0, -1.5]);
 /// ```
 pub fn div_scalar<T: Tensor<Dtype = f32>>(t: T, val: T::Dtype) -> T {
+    let _op = crate::profile::op("div_scalar");
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| x / val));
     move_tape_and_add_backward_op(t, result, move |t, result, grads| {
         let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
@@ -1,6 +1,9 @@
 use super::utils::move_tape_and_add_backward_binop;
 use crate::prelude::*;
-use std::ops::{Add, Div, Mul, Sub};
+use alloc::boxed::Box;
+use core::ops::{Add, Div, Mul, Sub};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 /// Element wise addition.
 ///
@@ -13,7 +16,16 @@ use std::ops::{Add, Div, Mul, Sub};
 /// assert_eq!(r.data(), &[[2.0, 3.0, 4.0], [0.0, -1.0, -2.0]]);
 /// ```
 pub fn add<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
-    binary_map(lhs, rhs, |x, y| x + y, |_, _| 1.0, |_, _| 1.0)
+    let _op = crate::profile::op("add");
+    let mut result = T::NoTape::zeros();
+    result.mut_data().clone_from(lhs.data());
+    T::Device::add_assign(result.mut_data(), rhs.data());
+    move_tape_and_add_backward_binop(lhs, rhs, result, move |lhs, rhs, result, grads| {
+        let (lhs_grad, result_grad) = grads.mut_and_ref(&lhs, &result);
+        T::Device::add_assign(lhs_grad, result_grad);
+        let (rhs_grad, result_grad) = grads.mut_and_ref(&rhs, &result);
+        T::Device::add_assign(rhs_grad, result_grad);
+    })
 }
 
 /// Element wise subtraction.
@@ -26,7 +38,16 @@ pub fn add<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
 /// let r = sub(a, &b); // or `a - &b`
 /// assert_eq!(r.data(), &[[0.0, 1.0, 2.0], [-2.0, -3.0, -4.0]]);
 pub fn sub<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
-    binary_map(lhs, rhs, |x, y| x - y, |_, _| 1.0, |_, _| -1.0)
+    let _op = crate::profile::op("sub");
+    let mut result = T::NoTape::zeros();
+    result.mut_data().clone_from(lhs.data());
+    T::Device::sub_assign(result.mut_data(), rhs.data());
+    move_tape_and_add_backward_binop(lhs, rhs, result, move |lhs, rhs, result, grads| {
+        let (lhs_grad, result_grad) = grads.mut_and_ref(&lhs, &result);
+        T::Device::add_assign(lhs_grad, result_grad);
+        let (rhs_grad, result_grad) = grads.mut_and_ref(&rhs, &result);
+        T::Device::sub_assign(rhs_grad, result_grad);
+    })
 }
 
 /// Element wise multiplication.
@@ -39,7 +60,17 @@ pub fn sub<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
 /// let r = mul(a, &b); // or `a * &b`
 /// assert_eq!(r.data(), &[[1.0, 2.0, 3.0], [-1.0, -2.0, -3.0]]);
 pub fn mul<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
-    binary_map(lhs, rhs, |x, y| x * y, |_, y| *y, |x, _| *x)
+    let _op = crate::profile::op("mul");
+    let mut result = T::NoTape::zeros();
+    result.mut_data().clone_from(lhs.data());
+    T::Device::mul_assign(result.mut_data(), rhs.data());
+    let rhs_data = rhs.clone();
+    move_tape_and_add_backward_binop(lhs, rhs, result, move |lhs, rhs, result, grads| {
+        let (lhs_grad, result_grad) = grads.mut_and_ref(&lhs, &result);
+        T::Device::addmul_assign(lhs_grad, rhs_data.data(), result_grad);
+        let (rhs_grad, result_grad) = grads.mut_and_ref(&rhs, &result);
+        T::Device::addmul_assign(rhs_grad, lhs.data(), result_grad);
+    })
 }
 
 /// Element wise division.
@@ -52,6 +83,7 @@ pub fn mul<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
 /// let r = div(a, &b); // or `a / &b`
 /// assert_eq!(r.data(), &[[1.0, 4.0, 3.0], [-2.0, -2.0, -1.0]]);
 pub fn div<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
+    let _op = crate::profile::op("div");
     fn dfdy(x: &f32, y: &f32) -> f32 {
         (-x) * y.powi(2).recip()
     }
@@ -70,6 +102,7 @@ pub fn div<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
 /// let r = minimum(a, &b);
 /// assert_eq!(r.data(), &[[1.0, 0.5, 1.0], [-2.0, -2.0, -3.5]]);
 pub fn minimum<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
+    let _op = crate::profile::op("minimum");
     fn f(x: &f32, y: &f32) -> f32 {
         x.min(*y)
     }
@@ -107,6 +140,7 @@ pub fn minimum<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
 /// let r = maximum(a, &b);
 /// assert_eq!(r.data(), &[[1.0, 2.0, 3.0], [-1.0, 2.0, -3.0]]);
 pub fn maximum<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
+    let _op = crate::profile::op("maximum");
     fn f(x: &f32, y: &f32) -> f32 {
         x.max(*y)
     }
@@ -135,7 +169,8 @@ pub fn maximum<T: Tensor<Dtype = f32>>(lhs: T, rhs: &T::NoTape) -> T {
 /// Applies a binary function `f`, it's partial wrt. x `dfdx`, and its partial wrt. y `dfdy`
 /// to a pair of [Tensor]s `lhs` and `rhs.
 ///
-/// This is primarily used to implement [add()], [sub()], [mul()], and [div()].
+/// This is primarily used to implement [div()], [minimum()], and [maximum()]. [add()], [sub()],
+/// and [mul()] use the [SimdElementwise] kernels instead.
 pub(crate) fn binary_map<
     T: Tensor<Dtype = f32>,
     F: FnMut(&f32, &f32) -> f32,
@@ -34,6 +34,7 @@ macro_rules! impl_broadcast {
     ) => {
 impl<$(const $Dims: usize, )* H: Tape> $TensorTrait<$DstTy, $($Axes, )*> for $SrcTy {
     fn $fn_name(self) -> $DstTy {
+        let _op = crate::profile::op("broadcast");
         let mut result = <$DstTy as Tensor>::NoTape::zeros();
         <Cpu as $DeviceTrait<_, _, $($Axes),*>>::broadcast_copy(result.mut_data(), self.data());
         move_tape_and_add_backward_op(self, result, move |t, result, grads| {
@@ -1,3 +1,6 @@
+use crate::devices::parallel::batches;
+#[cfg(feature = "rayon")]
+use crate::devices::parallel::is_parallel;
 use crate::prelude::*;
 
 /// **Requires Nightly** Perform a 2d convolution.
@@ -22,6 +25,7 @@ pub fn conv2d<
     { (IN_WIDTH + 2 * PADDING - KERNEL) / STRIDE + 1 },
     TAPE,
 > {
+    let _op = crate::profile::op("conv2d");
     let mut result = Tensor3D::zeros();
     conv_forward::<
         IN_CHAN,
@@ -83,25 +87,20 @@ pub fn conv2d_batched<
     { (IN_WIDTH + 2 * PADDING - KERNEL) / STRIDE + 1 },
     TAPE,
 > {
+    let _op = crate::profile::op("conv2d_batched");
     let mut result = Tensor4D::zeros();
-    for i in 0..BATCH_SIZE {
-        conv_forward::<
-            IN_CHAN,
-            OUT_CHAN,
-            KERNEL,
-            STRIDE,
-            PADDING,
-            IN_HEIGHT,
-            IN_WIDTH,
-            { (IN_HEIGHT + 2 * PADDING - KERNEL) / STRIDE + 1 },
-            { (IN_WIDTH + 2 * PADDING - KERNEL) / STRIDE + 1 },
-        >(
-            &x.data()[i],
-            filters.data(),
-            bias.data(),
-            &mut result.mut_data()[i],
-        );
-    }
+    conv_forward_batched::<
+        BATCH_SIZE,
+        IN_CHAN,
+        OUT_CHAN,
+        KERNEL,
+        STRIDE,
+        PADDING,
+        IN_HEIGHT,
+        IN_WIDTH,
+        { (IN_HEIGHT + 2 * PADDING - KERNEL) / STRIDE + 1 },
+        { (IN_WIDTH + 2 * PADDING - KERNEL) / STRIDE + 1 },
+    >(x.data(), filters.data(), bias.data(), result.mut_data());
 
     let f = filters.clone();
 
@@ -113,26 +112,18 @@ pub fn conv2d_batched<
         let (f_grad, b_grad, i_grad, r_grad) =
             grads.muts_and_ref(&phantom_filters, &phantom_bias, &x, &phantom_result);
 
-        for i in 0..BATCH_SIZE {
-            conv_backward::<
-                IN_CHAN,
-                OUT_CHAN,
-                KERNEL,
-                STRIDE,
-                PADDING,
-                IN_HEIGHT,
-                IN_WIDTH,
-                { (IN_HEIGHT + 2 * PADDING - KERNEL) / STRIDE + 1 },
-                { (IN_WIDTH + 2 * PADDING - KERNEL) / STRIDE + 1 },
-            >(
-                &x.data()[i],
-                f.data(),
-                &r_grad[i],
-                &mut i_grad[i],
-                f_grad,
-                b_grad,
-            );
-        }
+        conv_backward_batched::<
+            BATCH_SIZE,
+            IN_CHAN,
+            OUT_CHAN,
+            KERNEL,
+            STRIDE,
+            PADDING,
+            IN_HEIGHT,
+            IN_WIDTH,
+            { (IN_HEIGHT + 2 * PADDING - KERNEL) / STRIDE + 1 },
+            { (IN_WIDTH + 2 * PADDING - KERNEL) / STRIDE + 1 },
+        >(x.data(), f.data(), r_grad, i_grad, f_grad, b_grad);
     });
     result.put_tape(tape)
 }
@@ -231,10 +222,90 @@ fn conv_backward<
     }
 }
 
+/// [conv_forward()] for every image of a batch, split across threads with the `rayon` feature.
+fn conv_forward_batched<
+    const B: usize,
+    const C: usize,
+    const OC: usize,
+    const K: usize,
+    const S: usize,
+    const P: usize,
+    const H: usize,
+    const W: usize,
+    const OH: usize,
+    const OW: usize,
+>(
+    img: &[[[[f32; W]; H]; C]; B],
+    weight: &[[[[f32; K]; K]; C]; OC],
+    bias: &[f32; OC],
+    out: &mut [[[[f32; OW]; OH]; OC]; B],
+) {
+    batches(out, img, |_, out, img| {
+        conv_forward::<C, OC, K, S, P, H, W, OH, OW>(img, weight, bias, out)
+    });
+}
+
+/// [conv_backward()] for every image of a batch. With the `rayon` feature, large batches are
+/// split across threads, which each sum up their own weight and bias gradients.
+fn conv_backward_batched<
+    const B: usize,
+    const C: usize,
+    const OC: usize,
+    const K: usize,
+    const S: usize,
+    const P: usize,
+    const H: usize,
+    const W: usize,
+    const OH: usize,
+    const OW: usize,
+>(
+    img: &[[[[f32; W]; H]; C]; B],
+    weight: &[[[[f32; K]; K]; C]; OC],
+    out_g: &[[[[f32; OW]; OH]; OC]; B],
+    img_g: &mut [[[[f32; W]; H]; C]; B],
+    weight_g: &mut [[[[f32; K]; K]; C]; OC],
+    bias_g: &mut [f32; OC],
+) {
+    #[cfg(feature = "rayon")]
+    if B > 1 && is_parallel(B * C * H * W) {
+        use rayon::prelude::*;
+        type Grads<const C: usize, const OC: usize, const K: usize> =
+            (Box<[[[[f32; K]; K]; C]; OC]>, Box<[f32; OC]>);
+        let zeros = || -> Grads<C, OC, K> { (Cpu::zeros(), Cpu::zeros()) };
+        let (w_g, b_g) = img_g
+            .par_iter_mut()
+            .zip(img.par_iter().zip(out_g.par_iter()))
+            .fold(zeros, |(mut w_g, mut b_g), (img_g, (img, out_g))| {
+                conv_backward::<C, OC, K, S, P, H, W, OH, OW>(
+                    img,
+                    weight,
+                    out_g,
+                    img_g,
+                    w_g.as_mut(),
+                    b_g.as_mut(),
+                );
+                (w_g, b_g)
+            })
+            .reduce(zeros, |(mut w_a, mut b_a), (w_b, b_b)| {
+                Cpu::add(w_a.as_mut(), w_b.as_ref());
+                Cpu::add(b_a.as_mut(), b_b.as_ref());
+                (w_a, b_a)
+            });
+        Cpu::add(weight_g, w_g.as_ref());
+        Cpu::add(bias_g, b_g.as_ref());
+        return;
+    }
+
+    for ((img, out_g), img_g) in img.iter().zip(out_g.iter()).zip(img_g.iter_mut()) {
+        conv_backward::<C, OC, K, S, P, H, W, OH, OW>(img, weight, out_g, img_g, weight_g, bias_g);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::tests::assert_close;
+    use rand::prelude::{SeedableRng, StdRng};
 
     #[test]
     /// Produced by
@@ -521,4 +592,44 @@ mod tests {
             &[0.55381978, 0.55677116, 0.30686682],
         );
     }
+
+    #[test]
+    fn test_batched_conv2d_matches_unbatched() {
+        // large enough to be split across threads with the `rayon` feature
+        let mut rng = StdRng::seed_from_u64(0);
+        let weight: Tensor4D<2, 4, 3, 3> = TensorCreator::randn(&mut rng);
+        let bias: Tensor1D<2> = TensorCreator::randn(&mut rng);
+        let x: Tensor4D<8, 4, 32, 32> = TensorCreator::randn(&mut rng);
+
+        let r = conv2d_batched::<_, 8, 4, 2, 3, 1, 1, 32, 32>(x.trace(), &weight, &bias);
+        let r_data = r.data().clone();
+        let gradients = r.sum().backward();
+        let (mut w_grad, mut b_grad) = (Box::new([[[[0.0; 3]; 3]; 4]; 2]), [0.0; 2]);
+        for i in 0..8 {
+            let x_i = Tensor3D::new(x.data()[i]);
+            let r_i = conv2d::<_, 4, 2, 3, 1, 1, 32, 32>(x_i.trace(), &weight, &bias);
+            assert_close(r_i.data(), &r_data[i]);
+            let g = r_i.sum().backward();
+            assert_close(g.ref_gradient(&x_i), &gradients.ref_gradient(&x)[i]);
+            Cpu::add(w_grad.as_mut(), g.ref_gradient(&weight));
+            Cpu::add(&mut b_grad, g.ref_gradient(&bias));
+        }
+        // the gradients are summed in a different order, so only compare them up to rounding
+        let w = gradients
+            .ref_gradient(&weight)
+            .iter()
+            .flatten()
+            .flatten()
+            .flatten();
+        let b = gradients.ref_gradient(&bias).iter();
+        let expected = w_grad
+            .iter()
+            .flatten()
+            .flatten()
+            .flatten()
+            .chain(b_grad.iter());
+        for (a, b) in w.chain(b).zip(expected) {
+            assert!((a - b).abs() <= 1e-5 * b.abs().max(1.0), "{a} != {b}");
+        }
+    }
 }
@@ -10,6 +10,7 @@ use crate::prelude::*;
 /// assert_eq!(r.data(), &[-0.5, -0.5, 0.0, 0.5, 0.5]);
 /// ```
 pub fn clamp<T: Tensor<Dtype = f32>>(t: T, min: T::Dtype, max: T::Dtype) -> T {
+    let _op = crate::profile::op("clamp");
     map(
         t,
         move |x| x.clamp(min, max),
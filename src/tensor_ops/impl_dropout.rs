@@ -24,6 +24,7 @@ use rand_distr::{Distribution, Standard};
 /// assert_eq!(a.data(), &[2.0, 4.0, 0.0, 8.0]);
 /// ```
 pub fn dropout<T: Tensor<Dtype = f32>, R: Rng>(t: T, p: f32, rng: &mut R) -> T {
+    let _op = crate::profile::op("dropout");
     if !T::Tape::OWNS_TAPE {
         // This is the branch where `t` doesn't own the tape, so we don't have to drop out anything.
         t
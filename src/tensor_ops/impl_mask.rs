@@ -14,6 +14,7 @@ use crate::prelude::*;
 /// assert_eq!(r.data(), &[-1e10, 2.0, -1e10]);
 /// ```
 pub fn value_mask<T: Tensor<Dtype = f32>>(mut t: T, mask: &T::NoTape, value: T::Dtype) -> T {
+    let _op = crate::profile::op("value_mask");
     let mut result = T::NoTape::zeros();
     T::Device::foreach_mrr(result.mut_data(), t.data(), mask.data(), &mut |r, t, o| {
         *r = if o == &value { value } else { *t }
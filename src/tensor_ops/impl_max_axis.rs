@@ -16,6 +16,7 @@ use crate::prelude::*;
 /// assert_eq!(r.data(), &[3.0, -1.0]);
 /// ```
 pub fn max_axis<T: Reduce1<I>, const I: isize>(mut t: T) -> T::Reduced {
+    let _op = crate::profile::op("max_axis");
     let mut result = <T::Reduced as Tensor>::NoTape::zeros();
     T::DeviceR::reduce_into(t.data(), result.mut_data(), f32::max);
 
@@ -10,6 +10,7 @@ use crate::prelude::*;
 /// assert_eq!(r.data(), &0.0);
 /// ```
 pub fn mean<T: Tensor<Dtype = f32>>(t: T) -> Tensor0D<T::Tape> {
+    let _op = crate::profile::op("mean");
     div_scalar(sum(t), T::Array::NUM_ELEMENTS as f32)
 }
 
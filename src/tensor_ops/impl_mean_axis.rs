@@ -15,6 +15,7 @@ pub fn mean_axis<T: Reduce1<I>, const I: isize>(t: T) -> T::Reduced
 where
     T::Array: HasAxis<I>,
 {
+    let _op = crate::profile::op("mean_axis");
     div_scalar(sum_axis::<T, I>(t), <T::Array as HasAxis<I>>::SIZE as f32)
 }
 
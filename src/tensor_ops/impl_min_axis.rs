@@ -16,6 +16,7 @@ use crate::prelude::*;
 /// assert_eq!(r.data(), &[1.0, -3.0]);
 /// ```
 pub fn min_axis<T: Reduce1<I>, const I: isize>(mut t: T) -> T::Reduced {
+    let _op = crate::profile::op("min_axis");
     let mut result = <T::Reduced as Tensor>::NoTape::zeros();
     T::DeviceR::reduce_into(t.data(), result.mut_data(), f32::min);
 
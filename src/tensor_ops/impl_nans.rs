@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-/// Replaces any [std::f32::NAN] with `value`.
+/// Replaces any [core::f32::NAN] with `value`.
 ///
 /// **Pytorch equivalent**: `t.nan_to_num(value)`
 ///
@@ -12,6 +12,7 @@ use crate::prelude::*;
 /// assert_eq!(r.data(), &[1.0, 0.0, 0.0, 4.0]);
 /// ```
 pub fn nans_to<T: Tensor<Dtype = f32>>(t: T, value: T::Dtype) -> T {
+    let _op = crate::profile::op("nans_to");
     map(
         t,
         move |x| if x.is_nan() { value } else { *x },
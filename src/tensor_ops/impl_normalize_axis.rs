@@ -18,6 +18,7 @@ where
     T: Reduce1<I>,
     T::Array: HasAxis<I>,
 {
+    let _op = crate::profile::op("normalize_axis");
     let (t, tape) = t.split_tape();
     let (std, tape) = std_axis::<T, I>(t.duplicate().put_tape(tape), epsilon)
         .broadcast1()
@@ -43,6 +43,7 @@ where
     T: Tensor<Dtype = f32>,
     R: Tensor<Dtype = f32, Tape = T::Tape>,
 {
+    let _op = crate::profile::op("reshape");
     let mut result = R::NoTape::zeros();
     copy_unsafe(t.data(), result.mut_data());
     move_tape_and_add_backward_op(t, result, move |mut t, result, grads| {
@@ -59,7 +60,7 @@ unsafe fn copy_unsafe<Lhs: CountElements, Rhs: CountElements<Dtype = Lhs::Dtype>
 ) {
     let l = lhs.ref_first_elem() as *const Lhs::Dtype;
     let r = rhs.mut_first_elem() as *mut Lhs::Dtype;
-    std::ptr::copy_nonoverlapping(l, r, Lhs::NUM_ELEMENTS.min(Rhs::NUM_ELEMENTS));
+    core::ptr::copy_nonoverlapping(l, r, Lhs::NUM_ELEMENTS.min(Rhs::NUM_ELEMENTS));
 }
 
 #[cfg(test)]
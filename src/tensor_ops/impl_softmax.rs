@@ -21,6 +21,7 @@ use crate::prelude::*;
 /// assert_eq!(r.data(), &2.4519143);
 /// ```
 pub fn logsumexp<T: Reduce1<-1>>(mut t: T) -> T::Reduced {
+    let _op = crate::profile::op("logsumexp");
     let max = T::DeviceR::reduce(t.data(), f32::max);
     T::DeviceR::foreach_br(t.mut_data(), max.as_ref(), &mut |a, b| *a -= b);
     let mut result = ln(sum_axis::<T, -1>(exp(t)));
@@ -34,6 +35,7 @@ pub fn logsumexp<T: Reduce1<-1>>(mut t: T) -> T::Reduced {
 ///
 /// **Related functions**: [logsumexp()], [softmax()]
 pub fn log_softmax<T: Reduce1<-1>>(t: T) -> T {
+    let _op = crate::profile::op("log_softmax");
     let (t, tape) = t.split_tape();
     let (lse, tape) = logsumexp(t.duplicate().put_tape(tape))
         .broadcast1()
@@ -48,6 +50,7 @@ pub fn log_softmax<T: Reduce1<-1>>(t: T) -> T {
 ///
 /// **Related functions**: [logsumexp()], [log_softmax()]
 pub fn softmax<T: Reduce1<-1>>(t: T) -> T {
+    let _op = crate::profile::op("softmax");
     exp(log_softmax(t))
 }
 
@@ -19,6 +19,7 @@ where
     T: Reduce1<I>,
     T::Array: HasAxis<I>,
 {
+    let _op = crate::profile::op("std_axis");
     sqrt(add_scalar(var_axis::<T, I>(t), epsilon))
 }
 
@@ -41,6 +42,7 @@ where
     T: Reduce1<I>,
     T::Array: HasAxis<I>,
 {
+    let _op = crate::profile::op("var_axis");
     let num_elements: f32 = <T::Array as HasAxis<I>>::SIZE as f32;
     let (t, tape) = t.split_tape();
     let mean = mean_axis::<T, I>(t.duplicate().put_tape(tape)).broadcast1();
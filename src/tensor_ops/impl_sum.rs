@@ -11,6 +11,7 @@ use crate::prelude::*;
 /// assert_eq!(r.data(), &21.0);
 /// ```
 pub fn sum<T: Tensor<Dtype = f32>>(t: T) -> Tensor0D<T::Tape> {
+    let _op = crate::profile::op("sum");
     let result = Tensor0D::<NoneTape>::new(T::Device::reduce_all(t.data(), &mut |a, b| a + b));
     move_tape_and_add_backward_op(t, result, move |t, result, grads| {
         let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
@@ -13,6 +13,7 @@ use crate::prelude::*;
 /// let b: Tensor1D<2> = t.sum_axis::<-1>();
 /// ```
 pub fn sum_axis<T: Reduce1<I>, const I: isize>(t: T) -> T::Reduced {
+    let _op = crate::profile::op("sum_axis");
     let mut result = <T::Reduced as Tensor>::NoTape::zeros();
     T::DeviceR::reduce_into(t.data(), result.mut_data(), |a, b| a + b);
     move_tape_and_add_backward_op(t, result, move |t, result, grads| {
@@ -0,0 +1,80 @@
+use super::utils::move_tape_and_add_backward_op;
+use crate::numpy::{f16_to_f32, f32_to_f16};
+use crate::prelude::*;
+
+/// Rounds every element to the nearest half precision float (`f16`), which emulates computing
+/// in half precision while tensors are stored as `f32`. Values that are too large for `f16`
+/// become `inf`, and values that are too small become `0.0`.
+///
+/// The gradient is rounded to `f16` the same way in the backward pass, so small gradients
+/// underflow to `0.0` like they would in half precision. See [crate::optim::LossScaler], which
+/// prevents that.
+///
+/// Example:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = Tensor1D::new([1.0 + 1e-4, 1e5, 1e-8]);
+/// let r = t.to_f16();
+/// assert_eq!(r.data(), &[1.0, f32::INFINITY, 0.0]);
+/// ```
+pub fn to_f16<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("to_f16");
+    let result = T::NoTape::new_boxed(T::Device::map(t.data(), |x| round_to_f16(*x)));
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        T::Device::foreach_mr(t_grad, result_grad, &mut |g, r| *g += round_to_f16(*r));
+    })
+}
+
+/// The `f32` value of the `f16` that is nearest to `x`.
+pub(crate) fn round_to_f16(x: f32) -> f32 {
+    f16_to_f32(f32_to_f16(x))
+}
+
+macro_rules! tensor_impl {
+    ($typename:ident, [$($Vs:tt),*]) => {
+impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
+    /// Calls [to_f16()] on self
+    pub fn to_f16(self) -> Self {
+        to_f16(self)
+    }
+}
+    };
+}
+
+tensor_impl!(Tensor0D, []);
+tensor_impl!(Tensor1D, [M]);
+tensor_impl!(Tensor2D, [M, N]);
+tensor_impl!(Tensor3D, [M, N, O]);
+tensor_impl!(Tensor4D, [M, N, O, P]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_f16_rounds() {
+        let t = Tensor1D::new([0.5, -2.0, 1.0 + 1e-3, 65504.0, 65536.0, -1e-8, 1e-7]);
+        let r = t.to_f16();
+        assert_eq!(
+            r.data(),
+            &[
+                0.5,
+                -2.0,
+                1.0009766,
+                65504.0,
+                f32::INFINITY,
+                -0.0,
+                1.1920929e-7
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_f16_rounds_gradients() {
+        let t = Tensor1D::new([1.0, 2.0]);
+        let r = t.trace().to_f16();
+        let gradients = (r * &Tensor1D::new([1e-3, 1e-9])).sum().backward();
+        assert_eq!(gradients.ref_gradient(&t), &[0.0010004044, 0.0]);
+    }
+}
@@ -1,6 +1,8 @@
 use super::utils::move_tape_and_add_backward_op;
 use crate::prelude::*;
-use std::ops::Neg;
+use core::ops::Neg;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 /// Negates all elements.
 ///
@@ -12,6 +14,7 @@ use std::ops::Neg;
 /// assert_eq!(r.data(), &[2.0, 0.0, -5.0]);
 /// ```
 pub fn negate<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("negate");
     map_df_uses_fx(t, |x| -x, |_| -1.0)
 }
 
@@ -31,7 +34,9 @@ pub fn negate<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.relu();
 /// ```
 pub fn relu<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map_df_uses_fx(t, |x| x.max(0.0), |fx| if fx > &0.0 { 1.0 } else { 0.0 })
+    let _op = crate::profile::op("relu");
+    let df: fn(&mut _, &_, &_) = T::Device::relu_backward;
+    map_kernels_df_uses_fx(t, T::Device::relu_assign, df)
 }
 
 /// `t^2`
@@ -50,6 +55,7 @@ pub fn relu<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.square();
 /// ```
 pub fn square<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("square");
     map(t, |x| x.powi(2), |x| 2.0 * x)
 }
 
@@ -69,6 +75,7 @@ pub fn square<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.sqrt();
 /// ```
 pub fn sqrt<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("sqrt");
     map_df_uses_fx(t, |x| x.sqrt(), |fx| 0.5 * fx.recip())
 }
 
@@ -88,6 +95,7 @@ pub fn sqrt<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.tanh();
 /// ```
 pub fn tanh<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("tanh");
     map_df_uses_fx(t, |x| x.tanh(), |fx| 1.0 - fx.powi(2))
 }
 
@@ -109,6 +117,7 @@ pub fn tanh<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.sigmoid();
 /// ```
 pub fn sigmoid<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("sigmoid");
     fn f(x: &f32) -> f32 {
         (1.0 + x.neg().exp()).recip()
     }
@@ -132,6 +141,7 @@ pub fn sigmoid<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.sin();
 /// ```
 pub fn sin<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("sin");
     map(t, |x| x.sin(), |x| x.cos())
 }
 
@@ -151,6 +161,7 @@ pub fn sin<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.cos();
 /// ```
 pub fn cos<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("cos");
     map(t, |x| x.cos(), |x| x.sin().neg())
 }
 
@@ -170,6 +181,7 @@ pub fn cos<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.ln();
 /// ```
 pub fn ln<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("ln");
     map(t, |x| x.ln(), |x| x.recip())
 }
 
@@ -189,7 +201,9 @@ pub fn ln<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.exp();
 /// ```
 pub fn exp<T: Tensor<Dtype = f32>>(t: T) -> T {
-    map_df_uses_fx(t, |x| x.exp(), |fx| *fx)
+    let _op = crate::profile::op("exp");
+    let df: fn(&mut _, &_, &_) = T::Device::addmul_assign;
+    map_kernels_df_uses_fx(t, T::Device::exp_assign, df)
 }
 
 /// [Absolute value (abs)](https://en.wikipedia.org/wiki/Absolute_value). `|t|`
@@ -208,6 +222,7 @@ pub fn exp<T: Tensor<Dtype = f32>>(t: T) -> T {
 /// let r2 = t.abs();
 /// ```
 pub fn abs<T: Tensor<Dtype = f32>>(t: T) -> T {
+    let _op = crate::profile::op("abs");
     map(t, |x| x.abs(), |x| if x == &0.0 { 0.0 } else { x.signum() })
 }
 
@@ -229,6 +244,7 @@ where
     F: 'static + FnMut(&f32) -> f32,
     Df: 'static + FnMut(&f32) -> f32,
 {
+    let _op = crate::profile::op("map");
     let result = T::NoTape::new_boxed(T::Device::map(t.data(), f));
     move_tape_and_add_backward_op(t, result, move |t, result, grads| {
         let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
@@ -239,20 +255,34 @@ where
 }
 
 /// Same as [map()], but calls `df` with the result of `f(x)`. This can potentially remove an allocation.
-pub fn map_df_uses_fx<T: Tensor<Dtype = f32>, F, Df>(mut t: T, mut f: F, mut df: Df) -> T
+pub fn map_df_uses_fx<T: Tensor<Dtype = f32>, F, Df>(t: T, mut f: F, mut df: Df) -> T
 where
     F: FnMut(&f32) -> f32,
     Df: 'static + FnMut(&f32) -> f32,
 {
-    T::Device::foreach_m(t.mut_data(), &mut |x| *x = f(x)); // clones if there is more than 1 reference to t
+    let _op = crate::profile::op("map_df_uses_fx");
+    map_kernels_df_uses_fx(
+        t,
+        |t| T::Device::foreach_m(t, &mut |x| *x = f(x)),
+        move |g, fx, r| T::Device::foreach_mrr(g, fx, r, &mut |g, fx, r| *g += df(fx) * r),
+    )
+}
+
+/// Same as [map_df_uses_fx()], but with functions over the whole array (like the
+/// [SimdElementwise] kernels) instead of single elements: `f` applies the function in place,
+/// and `df` computes `grad += f'(x) * result_grad` given `f(x)`.
+fn map_kernels_df_uses_fx<T: Tensor<Dtype = f32>, F, Df>(mut t: T, f: F, mut df: Df) -> T
+where
+    F: FnOnce(&mut T::Array),
+    Df: 'static + FnMut(&mut T::Array, &T::Array, &T::Array),
+{
+    f(t.mut_data()); // clones if there is more than 1 reference to t
     let (t, mut tape) = t.split_tape();
     let result = t.clone(); // will always a new reference to t, not start a new one
     let phantom_result = result.phantom();
     tape.add_backward_op(move |grads| {
         let (t_grad, result_grad) = grads.mut_and_ref(&t, &phantom_result);
-        T::Device::foreach_mrr(t_grad, t.data(), result_grad, &mut |g, fx, r| {
-            *g += df(fx) * r;
-        });
+        df(t_grad, t.data(), result_grad);
     });
     result.put_tape(tape)
 }
@@ -282,7 +312,7 @@ impl<$(const $Vs: usize, )* H: Tape> $typename<$($Vs, )* H> {
     activation_impl!(abs, #[doc="Calls [abs()] on `self`."]);
 }
 
-impl<$(const $Vs: usize, )* H: Tape> std::ops::Neg for $typename<$($Vs, )* H>
+impl<$(const $Vs: usize, )* H: Tape> core::ops::Neg for $typename<$($Vs, )* H>
 {
     type Output = Self;
     /// Calls [negate()] on `self`.
@@ -354,7 +384,7 @@ mod tests {
         let r = x.trace().ln();
         assert!(r.data()[0].is_nan());
         assert!(r.data()[1].is_nan());
-        assert!(r.data()[2..] == [f32::NEG_INFINITY, 0.0, std::f32::consts::LN_2]);
+        assert!(r.data()[2..] == [f32::NEG_INFINITY, 0.0, core::f32::consts::LN_2]);
         let gradients = r.mean().backward();
         assert_eq!(
             gradients.ref_gradient(&x),
@@ -368,7 +398,7 @@ mod tests {
         let r = x.trace().exp();
         assert_eq!(
             r.data(),
-            &[0.13533528, 0.36787945, 1.0, std::f32::consts::E, 7.389056]
+            &[0.13533528, 0.36787945, 1.0, core::f32::consts::E, 7.389056]
         );
         let gradients = r.mean().backward();
         assert_eq!(
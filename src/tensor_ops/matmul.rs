@@ -38,6 +38,7 @@ where
     C::Array: Transpose,
     A::Device: MatMulOp<A::Array, B::Array, C::Array>,
 {
+    let _op = crate::profile::op("matmul");
     let mut c = C::NoTape::zeros();
     A::Device::mm(a.data(), b.data(), c.mut_data());
 
@@ -123,6 +124,7 @@ where
     C::Array: Transpose,
     A::Device: MatMulOp<A::Array, <B::Array as Transpose>::T, C::Array>,
 {
+    let _op = crate::profile::op("matmul_transpose");
     let mut c = C::NoTape::zeros();
     A::Device::mm_bt(a.data(), b.data(), c.mut_data());
 
@@ -193,6 +195,7 @@ pub fn vecmat_mul<const K: usize, const N: usize, TAPE: Tape>(
     lhs: Tensor1D<K, TAPE>,
     rhs: &Tensor2D<K, N, NoneTape>,
 ) -> Tensor1D<N, TAPE> {
+    let _op = crate::profile::op("vecmat_mul");
     let mut result = Tensor1D::zeros();
     Cpu::vm(lhs.data(), rhs.data(), result.mut_data());
 
@@ -231,6 +234,7 @@ pub fn vecmat_mul_transpose<const K: usize, const N: usize, TAPE: Tape>(
     lhs: Tensor1D<K, TAPE>,
     rhs_t: &Tensor2D<N, K, NoneTape>,
 ) -> Tensor1D<N, TAPE> {
+    let _op = crate::profile::op("vecmat_mul_transpose");
     let mut result = Tensor1D::zeros();
     Cpu::vm_bt(lhs.data(), rhs_t.data(), result.mut_data());
 
@@ -91,6 +91,21 @@
 //! let b: Tensor2D<2, 2> = t.select(&[[0, 2], [1, 1]]); // select multiple from the last axis
 //! assert_eq!(b.data(), &[[1.0, 3.0], [5.0, 5.0]]);
 //! ```
+//!
+//! # Slicing
+//!
+//! Sub tensors can be copied out with ranges via [Slice::i()], which takes one index or range
+//! per axis. The sizes of the ranges must match the type of the result:
+//! ```rust
+//! # use dfdx::prelude::*;
+//! let t = Tensor2D::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+//!
+//! let a: Tensor1D<2> = t.clone().i((1, 1..)); // the last 2 elements of the second row
+//! assert_eq!(a.data(), &[5.0, 6.0]);
+//!
+//! let b: Tensor2D<2, 1> = t.i((.., ..1)); // the first column
+//! assert_eq!(b.data(), &[[1.0], [4.0]]);
+//! ```
 
 mod arith_scalar;
 pub mod binary_map;
@@ -109,10 +124,13 @@ mod impl_softmax;
 mod impl_std_axis;
 mod impl_sum;
 mod impl_sum_axis;
+#[cfg(feature = "std")]
+mod impl_to_f16;
 mod map;
 mod matmul;
 mod reduce;
 mod select;
+mod slice;
 mod utils;
 
 pub use arith_scalar::*;
@@ -132,10 +150,13 @@ pub use impl_softmax::*;
 pub use impl_std_axis::*;
 pub use impl_sum::*;
 pub use impl_sum_axis::*;
+#[cfg(feature = "std")]
+pub use impl_to_f16::*;
 pub use map::*;
 pub use matmul::*;
 pub use reduce::*;
 pub use select::*;
+pub use slice::*;
 
 #[cfg(feature = "nightly")]
 mod impl_reshape;
@@ -17,6 +17,7 @@ macro_rules! impl_select {
 impl<$(const $Dims: usize, )* H: Tape> Select1<$DstTy, $Axis> for $SrcTy {
     type Indices = $IndTy;
     fn select(self, indices: &Self::Indices) -> $DstTy {
+        let _op = crate::profile::op("select");
         let mut result: <$DstTy as Tensor>::NoTape = TensorCreator::zeros();
         Cpu::select_axis(self.data(), indices, result.mut_data());
 
@@ -0,0 +1,334 @@
+use super::utils::move_tape_and_add_backward_op;
+use crate::devices::{flat, flat_mut};
+use crate::prelude::*;
+use alloc::vec::Vec;
+use core::ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive};
+
+/// Extracts a sub tensor `T` with an index `Idx` that has one component per axis, like
+/// `t.i((.., 3, 1..4))`. Equivalent to indexing tensors in pytorch or numpy.
+///
+/// Each component of the index is either:
+/// 1. A `usize`, which selects one element of the axis and removes the axis.
+/// 2. A range like `1..4`, `..2` or `..`, which keeps the elements of the axis in the range.
+///
+/// A single component can be used without a tuple, and only indexes the first axis.
+///
+/// The size of each kept axis in `T` must be equal to the length of its range, which is checked
+/// at runtime. The result is a copy that keeps the tape, so gradients flow back into the selected
+/// elements. Use [core::ops::Index] for views into the data without a tape.
+///
+/// Examples:
+/// ```rust
+/// # use dfdx::prelude::*;
+/// let t = Tensor3D::new([
+///     [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]],
+///     [[-1.0, -2.0, -3.0], [-4.0, -5.0, -6.0]],
+/// ]);
+/// let a: Tensor2D<2, 2> = t.clone().i((.., 1, 1..));
+/// assert_eq!(a.data(), &[[5.0, 6.0], [-5.0, -6.0]]);
+///
+/// let b: Tensor1D<3> = t.clone().i((1, 0, ..));
+/// assert_eq!(b.data(), &[-1.0, -2.0, -3.0]);
+///
+/// let c: Tensor3D<1, 2, 3> = t.i(..1);
+/// assert_eq!(c.data(), &[[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]]);
+/// ```
+pub trait Slice<T, Idx> {
+    /// Copies the elements at `idx` into a new tensor.
+    fn i(self, idx: Idx) -> T;
+}
+
+/// A range of one axis for [Slice].
+pub trait AxisRange {
+    /// The range of the axis, which has `size` elements. Panics if it's out of bounds.
+    fn range(&self, size: usize) -> Range<usize>;
+}
+
+impl AxisRange for Range<usize> {
+    fn range(&self, size: usize) -> Range<usize> {
+        assert!(
+            self.start <= self.end && self.end <= size,
+            "range {:?} out of bounds for axis of size {size}",
+            self
+        );
+        self.clone()
+    }
+}
+
+impl AxisRange for RangeInclusive<usize> {
+    fn range(&self, size: usize) -> Range<usize> {
+        (*self.start()..*self.end() + 1).range(size)
+    }
+}
+
+impl AxisRange for RangeFrom<usize> {
+    fn range(&self, size: usize) -> Range<usize> {
+        (self.start..size).range(size)
+    }
+}
+
+impl AxisRange for RangeTo<usize> {
+    fn range(&self, size: usize) -> Range<usize> {
+        (0..self.end).range(size)
+    }
+}
+
+impl AxisRange for RangeToInclusive<usize> {
+    fn range(&self, size: usize) -> Range<usize> {
+        (0..self.end + 1).range(size)
+    }
+}
+
+impl AxisRange for RangeFull {
+    fn range(&self, size: usize) -> Range<usize> {
+        0..size
+    }
+}
+
+/// The elements of one axis selected by a component of the index, and whether the axis is kept.
+struct AxisSlice {
+    range: Range<usize>,
+    keep: bool,
+}
+
+trait Component {
+    fn axis_slice(&self, size: usize) -> AxisSlice;
+}
+
+impl Component for usize {
+    fn axis_slice(&self, size: usize) -> AxisSlice {
+        assert!(
+            *self < size,
+            "index {self} out of bounds for axis of size {size}"
+        );
+        AxisSlice {
+            range: *self..*self + 1,
+            keep: false,
+        }
+    }
+}
+
+impl<R: AxisRange> Component for R {
+    fn axis_slice(&self, size: usize) -> AxisSlice {
+        AxisSlice {
+            range: self.range(size),
+            keep: true,
+        }
+    }
+}
+
+/// An index with one [Component] for each of the first axes of a tensor.
+trait Components {
+    fn axis_slices(&self, dims: &[usize]) -> Vec<AxisSlice>;
+}
+
+macro_rules! impl_components {
+    ($($Cs:ident $Is:tt),+) => {
+impl<$($Cs: Component),+> Components for ($($Cs,)+) {
+    fn axis_slices(&self, dims: &[usize]) -> Vec<AxisSlice> {
+        let mut slices = Vec::with_capacity(dims.len());
+        $(slices.push(self.$Is.axis_slice(dims[$Is]));)+
+        slices
+    }
+}
+    };
+}
+
+impl_components!(A 0);
+impl_components!(A 0, B 1);
+impl_components!(A 0, B 1, C 2);
+impl_components!(A 0, B 1, C 2, D 3);
+
+/// Copies the elements of `t` at `slices` into `R`, whose shape is `dst_dims`. Axes without a
+/// slice are kept as a whole.
+fn slice<T, R>(t: T, src_dims: &[usize], mut slices: Vec<AxisSlice>, dst_dims: &[usize]) -> R
+where
+    T: Tensor<Dtype = f32>,
+    R: Tensor<Dtype = f32, Tape = T::Tape>,
+{
+    let _op = crate::profile::op("slice");
+    slices.extend(src_dims[slices.len()..].iter().map(|&size| AxisSlice {
+        range: 0..size,
+        keep: true,
+    }));
+
+    let kept: Vec<usize> = slices
+        .iter()
+        .filter(|s| s.keep)
+        .map(|s| s.range.len())
+        .collect();
+    assert_eq!(
+        kept, dst_dims,
+        "the sliced shape doesn't match the shape of the result"
+    );
+
+    // the offsets into `t` of each element of the result
+    let mut offsets = alloc::vec![0];
+    let mut stride = T::Array::NUM_ELEMENTS;
+    for (s, size) in slices.iter().zip(src_dims.iter()) {
+        stride /= size;
+        offsets = offsets
+            .iter()
+            .flat_map(|o| s.range.clone().map(move |i| o + i * stride))
+            .collect();
+    }
+
+    let mut result = R::NoTape::zeros();
+    let src = flat(t.data());
+    for (r, o) in flat_mut(result.mut_data()).iter_mut().zip(offsets.iter()) {
+        *r = src[*o];
+    }
+
+    move_tape_and_add_backward_op(t, result, move |t, result, grads| {
+        let (t_grad, result_grad) = grads.mut_and_ref(&t, &result);
+        let t_grad = flat_mut(t_grad);
+        for (r, o) in flat(result_grad).iter().zip(offsets.iter()) {
+            t_grad[*o] += r;
+        }
+    })
+}
+
+macro_rules! impl_slice {
+    ($SrcTy:ty, ($($Idx:ty),+), $DstTy:ty, {$($SrcVs:tt),*}, {$($DstVs:tt),*}, {$($Rs:ident),*}) => {
+impl<$(const $SrcVs: usize, )* $(const $DstVs: usize, )* $($Rs: AxisRange, )* H: Tape> Slice<$DstTy, ($($Idx,)+)> for $SrcTy {
+    fn i(self, idx: ($($Idx,)+)) -> $DstTy {
+        slice(self, &[$($SrcVs),*], idx.axis_slices(&[$($SrcVs),*]), &[$($DstVs),*])
+    }
+}
+    };
+}
+
+/// Implements [Slice] with a single component, which indexes the first axis.
+macro_rules! impl_slice_first {
+    ($SrcTy:ident, [$Axis0:tt $(, $Kept:tt)*], $DstTy:ident) => {
+impl<const $Axis0: usize, $(const $Kept: usize, )* H: Tape>
+    Slice<$DstTy<$($Kept, )* H>, usize> for $SrcTy<$Axis0, $($Kept, )* H>
+{
+    fn i(self, idx: usize) -> $DstTy<$($Kept, )* H> {
+        slice(self, &[$Axis0, $($Kept),*], (idx,).axis_slices(&[$Axis0]), &[$($Kept),*])
+    }
+}
+
+impl<const $Axis0: usize, const Z: usize, $(const $Kept: usize, )* R: AxisRange, H: Tape>
+    Slice<$SrcTy<Z, $($Kept, )* H>, R> for $SrcTy<$Axis0, $($Kept, )* H>
+{
+    fn i(self, idx: R) -> $SrcTy<Z, $($Kept, )* H> {
+        slice(self, &[$Axis0, $($Kept),*], (idx,).axis_slices(&[$Axis0]), &[Z, $($Kept),*])
+    }
+}
+    };
+}
+
+impl_slice_first!(Tensor1D, [M], Tensor0D);
+impl_slice_first!(Tensor2D, [M, N], Tensor1D);
+impl_slice_first!(Tensor3D, [M, N, O], Tensor2D);
+impl_slice_first!(Tensor4D, [M, N, O, P], Tensor3D);
+
+// 1d
+impl_slice!(Tensor1D<M, H>, (usize), Tensor0D<H>, {M}, {}, {});
+impl_slice!(Tensor1D<M, H>, (A), Tensor1D<Z, H>, {M}, {Z}, {A});
+
+// 2d
+impl_slice!(Tensor2D<M, N, H>, (usize, usize), Tensor0D<H>, {M, N}, {}, {});
+impl_slice!(Tensor2D<M, N, H>, (A, usize), Tensor1D<Y, H>, {M, N}, {Y}, {A});
+impl_slice!(Tensor2D<M, N, H>, (usize, B), Tensor1D<Z, H>, {M, N}, {Z}, {B});
+impl_slice!(Tensor2D<M, N, H>, (A, B), Tensor2D<Y, Z, H>, {M, N}, {Y, Z}, {A, B});
+
+// 3d
+impl_slice!(Tensor3D<M, N, O, H>, (usize, usize, usize), Tensor0D<H>, {M, N, O}, {}, {});
+impl_slice!(Tensor3D<M, N, O, H>, (A, usize, usize), Tensor1D<X, H>, {M, N, O}, {X}, {A});
+impl_slice!(Tensor3D<M, N, O, H>, (usize, B, usize), Tensor1D<Y, H>, {M, N, O}, {Y}, {B});
+impl_slice!(Tensor3D<M, N, O, H>, (usize, usize, C), Tensor1D<Z, H>, {M, N, O}, {Z}, {C});
+impl_slice!(Tensor3D<M, N, O, H>, (A, B, usize), Tensor2D<X, Y, H>, {M, N, O}, {X, Y}, {A, B});
+impl_slice!(Tensor3D<M, N, O, H>, (A, usize, C), Tensor2D<X, Z, H>, {M, N, O}, {X, Z}, {A, C});
+impl_slice!(Tensor3D<M, N, O, H>, (usize, B, C), Tensor2D<Y, Z, H>, {M, N, O}, {Y, Z}, {B, C});
+impl_slice!(Tensor3D<M, N, O, H>, (A, B, C), Tensor3D<X, Y, Z, H>, {M, N, O}, {X, Y, Z}, {A, B, C});
+
+// 4d
+impl_slice!(Tensor4D<M, N, O, P, H>, (usize, usize, usize, usize), Tensor0D<H>, {M, N, O, P}, {}, {});
+impl_slice!(Tensor4D<M, N, O, P, H>, (A, usize, usize, usize), Tensor1D<W, H>, {M, N, O, P}, {W}, {A});
+impl_slice!(Tensor4D<M, N, O, P, H>, (usize, B, usize, usize), Tensor1D<X, H>, {M, N, O, P}, {X}, {B});
+impl_slice!(Tensor4D<M, N, O, P, H>, (usize, usize, C, usize), Tensor1D<Y, H>, {M, N, O, P}, {Y}, {C});
+impl_slice!(Tensor4D<M, N, O, P, H>, (usize, usize, usize, D), Tensor1D<Z, H>, {M, N, O, P}, {Z}, {D});
+impl_slice!(Tensor4D<M, N, O, P, H>, (A, B, usize, usize), Tensor2D<W, X, H>, {M, N, O, P}, {W, X}, {A, B});
+impl_slice!(Tensor4D<M, N, O, P, H>, (A, usize, C, usize), Tensor2D<W, Y, H>, {M, N, O, P}, {W, Y}, {A, C});
+impl_slice!(Tensor4D<M, N, O, P, H>, (A, usize, usize, D), Tensor2D<W, Z, H>, {M, N, O, P}, {W, Z}, {A, D});
+impl_slice!(Tensor4D<M, N, O, P, H>, (usize, B, C, usize), Tensor2D<X, Y, H>, {M, N, O, P}, {X, Y}, {B, C});
+impl_slice!(Tensor4D<M, N, O, P, H>, (usize, B, usize, D), Tensor2D<X, Z, H>, {M, N, O, P}, {X, Z}, {B, D});
+impl_slice!(Tensor4D<M, N, O, P, H>, (usize, usize, C, D), Tensor2D<Y, Z, H>, {M, N, O, P}, {Y, Z}, {C, D});
+impl_slice!(Tensor4D<M, N, O, P, H>, (A, B, C, usize), Tensor3D<W, X, Y, H>, {M, N, O, P}, {W, X, Y}, {A, B, C});
+impl_slice!(Tensor4D<M, N, O, P, H>, (A, B, usize, D), Tensor3D<W, X, Z, H>, {M, N, O, P}, {W, X, Z}, {A, B, D});
+impl_slice!(Tensor4D<M, N, O, P, H>, (A, usize, C, D), Tensor3D<W, Y, Z, H>, {M, N, O, P}, {W, Y, Z}, {A, C, D});
+impl_slice!(Tensor4D<M, N, O, P, H>, (usize, B, C, D), Tensor3D<X, Y, Z, H>, {M, N, O, P}, {X, Y, Z}, {B, C, D});
+impl_slice!(Tensor4D<M, N, O, P, H>, (A, B, C, D), Tensor4D<W, X, Y, Z, H>, {M, N, O, P}, {W, X, Y, Z}, {A, B, C, D});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_1d() {
+        let t = Tensor1D::new([1.0, 2.0, 3.0, 4.0]);
+        let a: Tensor0D = t.clone().i(2);
+        assert_eq!(a.data(), &3.0);
+        let b: Tensor1D<2> = t.clone().i(1..3);
+        assert_eq!(b.data(), &[2.0, 3.0]);
+        let c: Tensor1D<3> = t.clone().i((..=2,));
+        assert_eq!(c.data(), &[1.0, 2.0, 3.0]);
+        let d: Tensor1D<4> = t.i(..);
+        assert_eq!(d.data(), &[1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_slice_2d() {
+        let t = Tensor2D::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        let a: Tensor1D<3> = t.clone().i(1);
+        assert_eq!(a.data(), &[4.0, 5.0, 6.0]);
+        let b: Tensor1D<2> = t.clone().i((1.., 2));
+        assert_eq!(b.data(), &[6.0, 9.0]);
+        let c: Tensor2D<2, 2> = t.clone().i((..2, 1..=2));
+        assert_eq!(c.data(), &[[2.0, 3.0], [5.0, 6.0]]);
+        let d: Tensor0D = t.i((2, 0));
+        assert_eq!(d.data(), &7.0);
+    }
+
+    #[test]
+    fn test_slice_4d() {
+        let mut t: Tensor4D<2, 3, 4, 5> = TensorCreator::zeros();
+        t[(1, 2, 3, 4)] = 1.0;
+        t[(1, 1, 3, 2)] = 2.0;
+        let a: Tensor2D<2, 3> = t.clone().i((1, 1.., 3, 2..));
+        assert_eq!(a.data(), &[[2.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+        let b: Tensor3D<2, 3, 5> = t.i((.., .., 3, ..));
+        assert_eq!(b.data()[1][2][4], 1.0);
+    }
+
+    #[test]
+    fn test_slice_backward() {
+        let t = Tensor2D::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let r: Tensor2D<2, 2, OwnedTape> = t.trace().i((.., 1..));
+        assert_eq!(r.data(), &[[2.0, 3.0], [5.0, 6.0]]);
+        let g = r.exp().sum().backward();
+        assert_eq!(
+            g.ref_gradient(&t),
+            &[
+                [0.0, 2.0f32.exp(), 3.0f32.exp()],
+                [0.0, 5.0f32.exp(), 6.0f32.exp()]
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic = "the sliced shape doesn't match the shape of the result"]
+    fn test_slice_wrong_size() {
+        let t: Tensor2D<3, 3> = TensorCreator::zeros();
+        let _: Tensor2D<2, 2> = t.i((1.., ..));
+    }
+
+    #[test]
+    #[should_panic = "out of bounds"]
+    fn test_slice_out_of_bounds() {
+        let t: Tensor1D<3> = TensorCreator::zeros();
+        let _: Tensor1D<2> = t.i(2..4);
+    }
+}
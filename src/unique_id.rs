@@ -6,11 +6,12 @@ pub struct UniqueId(usize);
 
 /// Generate a [UniqueId].
 pub(crate) fn unique_id() -> UniqueId {
-    static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
-    UniqueId(COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed))
+    static COUNTER: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+    UniqueId(COUNTER.fetch_add(1, core::sync::atomic::Ordering::Relaxed))
 }
 
 impl UniqueId {
+    #[cfg(not(feature = "std"))]
     pub(crate) fn as_u64(&self) -> u64 {
         self.0 as u64
     }